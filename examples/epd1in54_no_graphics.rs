@@ -12,7 +12,7 @@ use linux_embedded_hal::{
 // needs to be run with sudo because of some sysfs_gpio permission problems and follow-up timing problems
 // see https://github.com/rust-embedded/rust-sysfs-gpio/issues/5 and follow-up issues
 
-fn main() -> Result<(), std::io::Error> {
+fn main() -> Result<(), epd_waveshare::Error<std::io::Error>> {
     // Configure SPI
     // SPI settings are from eink-waveshare-rs documenation
     let mut spi = Spidev::open("/dev/spidev0.0")?;
@@ -64,13 +64,17 @@ fn main() -> Result<(), std::io::Error> {
     epd.clear_frame(&mut spi, &mut delay)?;
     epd.display_frame(&mut spi, &mut delay)?;
 
-    // Speeddemo
+    // Speeddemo, both RAM banks have to hold the base frame for partial refreshes
     epd.set_lut(&mut spi, Some(RefreshLut::Quick))?;
+    epd.clear_frame(&mut spi, &mut delay)?;
+    epd.display_frame(&mut spi, &mut delay)?;
+    epd.clear_frame(&mut spi, &mut delay)?;
+    epd.display_frame(&mut spi, &mut delay)?;
     let small_buffer = [Color::Black.get_byte_value(); 32]; //16x16
     let number_of_runs = 1;
     for i in 0..number_of_runs {
-        let offset = i * 8 % 150;
-        epd.update_partial_frame(&mut spi, &small_buffer, 25 + offset, 25 + offset, 16, 16)?;
+        let offset = i * 8 % 144;
+        epd.update_partial_frame(&mut spi, &small_buffer, 24 + offset, 24 + offset, 16, 16)?;
         epd.display_frame(&mut spi, &mut delay)?;
     }
 
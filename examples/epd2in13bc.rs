@@ -34,7 +34,7 @@ use linux_embedded_hal::{
 //
 // after finishing, put the display to sleep
 
-fn main() -> Result<(), std::io::Error> {
+fn main() -> Result<(), epd_waveshare::Error<std::io::Error>> {
     let busy = Pin::new(24); // GPIO 24, board J-18
     busy.export().expect("busy export");
     while !busy.is_exported() {}
@@ -94,7 +94,7 @@ fn main() -> Result<(), std::io::Error> {
     // the bw-buffer of this tri-color screen
 
     epd2in13
-        .update_and_display_frame(&mut spi, &display.bw_buffer(), &mut delay)
+        .update_and_display_frame(&mut spi, display.bw_buffer(), &mut delay)
         .expect("display frame new graphics");
 
     println!("First frame done. Waiting 5s");
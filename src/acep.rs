@@ -0,0 +1,288 @@
+//! Power sequencing of the ACeP color displays checked at compile time
+//!
+//! The 7 color displays ([Epd5in65f](crate::epd5in65f), [Epd7in3f](crate::epd7in3f))
+//! have to power their rails on before a refresh and off again afterwards.
+//! `display_frame` runs this whole sequence on every call, which is safe but slow when
+//! showing several frames in a row. Driving the steps by hand out of order, e.g.
+//! refreshing without powering on or sleeping with the rails still on, locks the
+//! controller up until the next reset.
+//!
+//! [`IdleEpd`] and [`PoweredEpd`] track the state of the rails in the type instead: the
+//! frame can only be refreshed by a [`PoweredEpd`], and a [`PoweredEpd`] has to be
+//! powered off to get the driver back.
+//!
+//!```rust, no_run
+//!# use embedded_hal_mock::*;
+//!# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
+//!use epd_waveshare::{acep::IdleEpd, epd5in65f::*, prelude::*};
+//!#
+//!# let expectations = [];
+//!# let mut spi = spi::Mock::new(&expectations);
+//!# let expectations = [];
+//!# let cs_pin = pin::Mock::new(&expectations);
+//!# let busy_in = pin::Mock::new(&expectations);
+//!# let dc = pin::Mock::new(&expectations);
+//!# let rst = pin::Mock::new(&expectations);
+//!# let mut delay = delay::MockNoop::new();
+//!# let first = [0x11; WIDTH as usize * HEIGHT as usize / 2];
+//!# let second = first;
+//!
+//!let epd = Epd5in65f::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
+//!let mut idle = IdleEpd::new(epd);
+//!idle.update_frame(&mut spi, &first, &mut delay)?;
+//!
+//!// the rails stay on for both refreshes
+//!let mut powered = idle.power_on(&mut spi, &mut delay).map_err(|(_, e)| e)?;
+//!powered.display_frame(&mut spi, &mut delay)?;
+//!powered.update_frame(&mut spi, &second, &mut delay)?;
+//!powered.display_frame(&mut spi, &mut delay)?;
+//!
+//!let mut epd = powered.power_off(&mut spi, &mut delay).map_err(|(_, e)| e)?.into_inner();
+//!epd.sleep(&mut spi, &mut delay)?;
+//!# Ok(())
+//!# }
+//!```
+//!
+//! If a transition fails on the bus, the state of the rails is unknown. The driver is
+//! handed back together with the error, so it can be reset with `wake_up`.
+
+use embedded_hal::blocking::spi::Write;
+
+pub(crate) mod sealed {
+    use embedded_hal::blocking::spi::Write;
+
+    use crate::Error;
+
+    /// Steps of the refresh sequence, only reachable through the typestates
+    pub trait AcepRails<SPI: Write<u8>, DELAY> {
+        /// Sends a frame, allowed with the rails on or off
+        fn write_frame(
+            &mut self,
+            spi: &mut SPI,
+            buffer: &[u8],
+            delay: &mut DELAY,
+        ) -> Result<(), Error<SPI::Error>>;
+
+        /// Powers the rails on and waits until they are up
+        fn power_on(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>>;
+
+        /// Refreshes the display with the rails on and waits for the end of the refresh
+        fn refresh(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>>;
+
+        /// Powers the rails off and waits until they are down
+        fn power_off(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>>;
+    }
+}
+
+use self::sealed::AcepRails;
+use crate::Error;
+
+/// Driver with the rails powered off
+///
+/// This is the state after `new`, `wake_up` and `display_frame` of the driver.
+#[derive(Debug)]
+pub struct IdleEpd<E> {
+    epd: E,
+}
+
+/// Driver with the rails powered on, ready to refresh
+#[derive(Debug)]
+pub struct PoweredEpd<E> {
+    epd: E,
+}
+
+impl<E> IdleEpd<E> {
+    /// Takes over a driver with the rails powered off
+    pub fn new(epd: E) -> Self {
+        IdleEpd { epd }
+    }
+
+    /// Gives the driver back
+    pub fn into_inner(self) -> E {
+        self.epd
+    }
+
+    /// Sends a frame without showing it yet
+    pub fn update_frame<SPI, DELAY>(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>>
+    where
+        SPI: Write<u8>,
+        E: AcepRails<SPI, DELAY>,
+    {
+        self.epd.write_frame(spi, buffer, delay)
+    }
+
+    /// Powers the rails on
+    ///
+    /// On an error the driver is handed back in an unknown state.
+    pub fn power_on<SPI, DELAY>(
+        mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<PoweredEpd<E>, (E, Error<SPI::Error>)>
+    where
+        SPI: Write<u8>,
+        E: AcepRails<SPI, DELAY>,
+    {
+        match self.epd.power_on(spi, delay) {
+            Ok(()) => Ok(PoweredEpd { epd: self.epd }),
+            Err(e) => Err((self.epd, e)),
+        }
+    }
+}
+
+impl<E> PoweredEpd<E> {
+    /// Sends a frame without showing it yet
+    pub fn update_frame<SPI, DELAY>(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>>
+    where
+        SPI: Write<u8>,
+        E: AcepRails<SPI, DELAY>,
+    {
+        self.epd.write_frame(spi, buffer, delay)
+    }
+
+    /// Shows the frame sent last, the rails stay on
+    pub fn display_frame<SPI, DELAY>(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>>
+    where
+        SPI: Write<u8>,
+        E: AcepRails<SPI, DELAY>,
+    {
+        self.epd.refresh(spi, delay)
+    }
+
+    /// Powers the rails off
+    ///
+    /// On an error the driver is handed back in an unknown state.
+    pub fn power_off<SPI, DELAY>(
+        mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<IdleEpd<E>, (E, Error<SPI::Error>)>
+    where
+        SPI: Write<u8>,
+        E: AcepRails<SPI, DELAY>,
+    {
+        match self.epd.power_off(spi, delay) {
+            Ok(()) => Ok(IdleEpd { epd: self.epd }),
+            Err(e) => Err((self.epd, e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_hal_mock::{delay::MockNoop, spi::Mock, MockError};
+
+    #[derive(Debug, Default)]
+    struct Rails {
+        log: [&'static str; 6],
+        len: usize,
+        fail_power_off: bool,
+    }
+
+    impl Rails {
+        fn push(&mut self, step: &'static str) {
+            self.log[self.len] = step;
+            self.len += 1;
+        }
+
+        fn steps(&self) -> &[&'static str] {
+            &self.log[..self.len]
+        }
+    }
+
+    impl AcepRails<Mock, MockNoop> for Rails {
+        fn write_frame(
+            &mut self,
+            _spi: &mut Mock,
+            _buffer: &[u8],
+            _delay: &mut MockNoop,
+        ) -> Result<(), Error<MockError>> {
+            self.push("write");
+            Ok(())
+        }
+
+        fn power_on(
+            &mut self,
+            _spi: &mut Mock,
+            _delay: &mut MockNoop,
+        ) -> Result<(), Error<MockError>> {
+            self.push("on");
+            Ok(())
+        }
+
+        fn refresh(
+            &mut self,
+            _spi: &mut Mock,
+            _delay: &mut MockNoop,
+        ) -> Result<(), Error<MockError>> {
+            self.push("refresh");
+            Ok(())
+        }
+
+        fn power_off(
+            &mut self,
+            _spi: &mut Mock,
+            _delay: &mut MockNoop,
+        ) -> Result<(), Error<MockError>> {
+            if self.fail_power_off {
+                extern crate std;
+                return Err(MockError::Io(std::io::ErrorKind::Other).into());
+            }
+            self.push("off");
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn refreshes_while_powered() {
+        let mut spi = Mock::new(&[]);
+        let mut delay = MockNoop::new();
+
+        let mut idle = IdleEpd::new(Rails::default());
+        idle.update_frame(&mut spi, &[0x11], &mut delay).unwrap();
+        let mut powered = idle.power_on(&mut spi, &mut delay).unwrap();
+        powered.display_frame(&mut spi, &mut delay).unwrap();
+        powered.update_frame(&mut spi, &[0x22], &mut delay).unwrap();
+        powered.display_frame(&mut spi, &mut delay).unwrap();
+        let rails = powered
+            .power_off(&mut spi, &mut delay)
+            .unwrap()
+            .into_inner();
+
+        assert_eq!(
+            rails.steps(),
+            ["write", "on", "refresh", "write", "refresh", "off"]
+        );
+        spi.done();
+    }
+
+    #[test]
+    fn failed_transition_returns_driver() {
+        let mut spi = Mock::new(&[]);
+        let mut delay = MockNoop::new();
+        let rails = Rails {
+            fail_power_off: true,
+            ..Rails::default()
+        };
+
+        let powered = IdleEpd::new(rails).power_on(&mut spi, &mut delay).unwrap();
+        let (rails, _) = powered.power_off(&mut spi, &mut delay).unwrap_err();
+        assert_eq!(rails.steps(), ["on"]);
+        spi.done();
+    }
+}
@@ -0,0 +1,191 @@
+//! Uploading frames in small steps
+//!
+//! Transferring a full frame can block for a long time on slow SPI buses. Firmware
+//! which also has to service radios or sensors can upload the frame in bands of
+//! rows instead, with a limit on the bytes (or the time) spent per call, and
+//! continue later where it stopped. The position is kept in a [`RenderProgress`].
+//!
+//! The bands are sent with `update_partial_frame`, so this is only usable with
+//! drivers supporting partial updates and buffers with 1 bit per pixel.
+//!
+//!```rust, no_run
+//!# use embedded_hal_mock::*;
+//!# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
+//!use epd_waveshare::{budget::*, epd4in2::*, graphics::Display, prelude::*};
+//!#
+//!# let expectations = [];
+//!# let mut spi = spi::Mock::new(&expectations);
+//!# let expectations = [];
+//!# let cs_pin = pin::Mock::new(&expectations);
+//!# let busy_in = pin::Mock::new(&expectations);
+//!# let dc = pin::Mock::new(&expectations);
+//!# let rst = pin::Mock::new(&expectations);
+//!# let mut delay = delay::MockNoop::new();
+//!
+//!let mut epd = Epd4in2::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
+//!let display = Display4in2::default();
+//!
+//!let mut progress = RenderProgress::new();
+//!// send at most 1kB per call and do something else in between
+//!while !update_frame_budgeted(&mut epd, &mut spi, display.buffer(), &mut progress, 1024)? {
+//!    // service the radio here
+//!}
+//!epd.display_frame(&mut spi, &mut delay)?;
+//!# Ok(())
+//!# }
+//!```
+
+use crate::traits::WaveshareDisplay;
+use crate::Error;
+use embedded_hal::{
+    blocking::{delay::*, spi::Write},
+    digital::v2::*,
+};
+
+/// Smallest number of rows sent at once
+///
+/// Some controllers don't accept windows with a height of a single row.
+pub const MIN_BAND_ROWS: u32 = 8;
+
+/// Position of an interrupted frame upload
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RenderProgress {
+    row: u32,
+}
+
+impl RenderProgress {
+    /// Starts at the first row of a frame
+    pub fn new() -> Self {
+        RenderProgress { row: 0 }
+    }
+
+    /// The next row which will be sent
+    pub fn row(&self) -> u32 {
+        self.row
+    }
+
+    /// Returns true if all rows of a display with `height` rows were sent
+    pub fn is_done(&self, height: u32) -> bool {
+        self.row >= height
+    }
+
+    /// Starts over with the first row
+    pub fn reset(&mut self) {
+        self.row = 0;
+    }
+}
+
+/// Sends the next band of rows of `buffer` which fits into `max_bytes`
+///
+/// At least [`MIN_BAND_ROWS`] rows are sent per call, even if they need more than
+/// `max_bytes`. Returns true once the whole frame was sent.
+pub fn update_frame_budgeted<EPD, SPI, CS, BUSY, DC, RST, DELAY>(
+    epd: &mut EPD,
+    spi: &mut SPI,
+    buffer: &[u8],
+    progress: &mut RenderProgress,
+    max_bytes: usize,
+) -> Result<bool, Error<SPI::Error>>
+where
+    EPD: WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>,
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    let (width, height) = (epd.width(), epd.height());
+    if progress.is_done(height) {
+        return Ok(true);
+    }
+
+    let row_bytes = width.div_ceil(8) as usize;
+    let rows = band_rows(progress.row, height, row_bytes, max_bytes);
+
+    let start = progress.row as usize * row_bytes;
+    let end = start + rows as usize * row_bytes;
+    epd.update_partial_frame(spi, &buffer[start..end], 0, progress.row, width, rows)?;
+
+    progress.row += rows;
+    Ok(progress.is_done(height))
+}
+
+/// Sends bands of `max_bytes` until the frame is complete or `expired` returns true
+///
+/// `expired` is checked after every band, so a time budget can be implemented
+/// with any clock. Returns true once the whole frame was sent.
+pub fn update_frame_until<EPD, SPI, CS, BUSY, DC, RST, DELAY, F>(
+    epd: &mut EPD,
+    spi: &mut SPI,
+    buffer: &[u8],
+    progress: &mut RenderProgress,
+    max_bytes: usize,
+    mut expired: F,
+) -> Result<bool, Error<SPI::Error>>
+where
+    EPD: WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>,
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+    F: FnMut() -> bool,
+{
+    loop {
+        let done = update_frame_budgeted(epd, spi, buffer, progress, max_bytes)?;
+        if done || expired() {
+            return Ok(done);
+        }
+    }
+}
+
+/// Number of rows of the next band starting at `row`
+///
+/// A remainder smaller than `MIN_BAND_ROWS` is merged into the band.
+fn band_rows(row: u32, height: u32, row_bytes: usize, max_bytes: usize) -> u32 {
+    let remaining = height - row;
+    let rows = ((max_bytes / row_bytes.max(1)) as u32).max(MIN_BAND_ROWS);
+    if rows + MIN_BAND_ROWS > remaining {
+        remaining
+    } else {
+        rows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn band_fits_budget() {
+        // 400px wide: 50 bytes per row
+        assert_eq!(band_rows(0, 300, 50, 1000), 20);
+        assert_eq!(band_rows(0, 300, 50, 1024), 20);
+    }
+
+    #[test]
+    fn band_has_min_rows() {
+        assert_eq!(band_rows(0, 300, 50, 0), MIN_BAND_ROWS);
+        assert_eq!(band_rows(0, 300, 50, 100), MIN_BAND_ROWS);
+    }
+
+    #[test]
+    fn band_merges_small_remainder() {
+        assert_eq!(band_rows(280, 300, 50, 1000), 20);
+        assert_eq!(band_rows(270, 300, 50, 1000), 20);
+        assert_eq!(band_rows(275, 300, 50, 1000), 25);
+        assert_eq!(band_rows(296, 300, 50, 1000), 4);
+    }
+
+    #[test]
+    fn progress() {
+        let mut progress = RenderProgress::new();
+        assert!(!progress.is_done(300));
+        progress.row = 300;
+        assert!(progress.is_done(300));
+        progress.reset();
+        assert_eq!(progress.row(), 0);
+    }
+}
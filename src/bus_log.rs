@@ -0,0 +1,117 @@
+//! Recording bus for the tests of the driver sequences
+//!
+//! [`Spi`] logs every byte as a command or as data, depending on the level of the DC
+//! [`Pin`] sharing the same [`Log`]. Reading a [`Pin`] fails, which the drivers take
+//! as never busy.
+
+extern crate std;
+
+use core::cell::{Cell, RefCell};
+use embedded_hal::{
+    blocking::spi::Write,
+    digital::v2::{InputPin, OutputPin},
+};
+use std::vec::Vec;
+
+/// A byte sent over the bus
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Event {
+    Command(u8),
+    Data(u8),
+}
+
+/// Log shared by the SPI and the DC pin
+#[derive(Default)]
+pub(crate) struct Log {
+    events: RefCell<Vec<Event>>,
+    data: Cell<bool>,
+}
+
+impl Log {
+    /// Takes the events logged so far
+    pub(crate) fn take(&self) -> Vec<Event> {
+        self.events.take()
+    }
+}
+
+/// Data sent after every `command` in `events`
+pub(crate) fn data_of(events: &[Event], command: u8) -> Vec<Vec<u8>> {
+    let mut sent = Vec::new();
+    for (i, _) in events
+        .iter()
+        .enumerate()
+        .filter(|(_, &event)| event == Event::Command(command))
+    {
+        let data = events[i + 1..]
+            .iter()
+            .map_while(|event| match *event {
+                Event::Data(byte) => Some(byte),
+                Event::Command(_) => None,
+            })
+            .collect();
+        sent.push(data);
+    }
+    sent
+}
+
+/// Commands in `events`, in order
+pub(crate) fn commands(events: &[Event]) -> Vec<u8> {
+    events
+        .iter()
+        .filter_map(|event| match *event {
+            Event::Command(command) => Some(command),
+            Event::Data(_) => None,
+        })
+        .collect()
+}
+
+pub(crate) struct Spi<'a>(pub &'a Log);
+
+impl Write<u8> for Spi<'_> {
+    type Error = ();
+
+    fn write(&mut self, words: &[u8]) -> Result<(), ()> {
+        let data = self.0.data.get();
+        self.0.events.borrow_mut().extend(words.iter().map(|&b| {
+            if data {
+                Event::Data(b)
+            } else {
+                Event::Command(b)
+            }
+        }));
+        Ok(())
+    }
+}
+
+/// DC pin if it has a log, other pins only accept every level
+pub(crate) struct Pin<'a>(pub Option<&'a Log>);
+
+impl OutputPin for Pin<'_> {
+    type Error = ();
+
+    fn set_low(&mut self) -> Result<(), ()> {
+        if let Some(log) = self.0 {
+            log.data.set(false);
+        }
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), ()> {
+        if let Some(log) = self.0 {
+            log.data.set(true);
+        }
+        Ok(())
+    }
+}
+
+impl InputPin for Pin<'_> {
+    type Error = ();
+
+    fn is_high(&self) -> Result<bool, ()> {
+        Err(())
+    }
+
+    fn is_low(&self) -> Result<bool, ()> {
+        Err(())
+    }
+}
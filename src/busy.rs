@@ -0,0 +1,744 @@
+//! What happens to updates sent while the display is still refreshing
+//!
+//! The drivers differ in how they treat a new frame while the last refresh is still
+//! running: some wait for the BUSY line first, others start transmitting right away and
+//! the controller may or may not accept the data. [`BusyGuard`] wraps any driver and
+//! applies one [`BusyPolicy`] to all updates instead:
+//!
+//! - [`BusyPolicy::Block`] waits until the display is idle and sends the update then
+//! - [`BusyPolicy::Fail`] returns [`BusyError::Busy`] and drops the update
+//! - [`BusyPolicy::Queue`] copies the frame into a buffer you provide and sends it
+//!   with the next call to [`BusyGuard::poll`] after the refresh finished
+//!
+//!```rust, no_run
+//!# use embedded_hal_mock::*;
+//!# fn main() -> Result<(), epd_waveshare::busy::BusyError<epd_waveshare::Error<MockError>>> {
+//!use epd_waveshare::{busy::*, epd4in2::*, graphics::Display, prelude::*};
+//!#
+//!# let expectations = [];
+//!# let mut spi = spi::Mock::new(&expectations);
+//!# let expectations = [];
+//!# let cs_pin = pin::Mock::new(&expectations);
+//!# let busy_in = pin::Mock::new(&expectations);
+//!# let dc = pin::Mock::new(&expectations);
+//!# let rst = pin::Mock::new(&expectations);
+//!# let mut delay = delay::MockNoop::new();
+//!
+//!let epd = Epd4in2::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
+//!let display = Display4in2::default();
+//!
+//!// Frames sent during a refresh are kept here until the display is idle again
+//!let mut queue = [0u8; 15000];
+//!let mut epd = BusyGuard::queued(epd, &mut queue[..]);
+//!
+//!epd.update_and_display_frame(&mut spi, display.buffer(), &mut delay)?;
+//!// doesn't wait for the first refresh to finish
+//!epd.update_and_display_frame(&mut spi, display.buffer(), &mut delay)?;
+//!
+//!while !epd.poll(&mut spi, &mut delay)? {
+//!    // do something else in the meantime
+//!}
+//!# Ok(())
+//!# }
+//!```
+//!
+//! Systems which put the MCU into a stop mode during a refresh don't want to spin on
+//! the busy line at all. [`wait_until_idle`] and [`BusyGuard::wait_until_idle`] check
+//! the line once, then hand over to a closure which sleeps until the next wakeup, e.g.
+//! by the RTC, and decides if the deadline has passed:
+//!
+//!```rust, no_run
+//!# use embedded_hal_mock::*;
+//!# fn main() -> Result<(), epd_waveshare::busy::BusyError<epd_waveshare::Error<MockError>>> {
+//!use epd_waveshare::{busy, epd5in83_v2::*, prelude::*};
+//!#
+//!# let expectations = [];
+//!# let mut spi = spi::Mock::new(&expectations);
+//!# let expectations = [];
+//!# let cs_pin = pin::Mock::new(&expectations);
+//!# let busy_in = pin::Mock::new(&expectations);
+//!# let dc = pin::Mock::new(&expectations);
+//!# let rst = pin::Mock::new(&expectations);
+//!# let mut delay = delay::MockNoop::new();
+//!# let frame = [0xFF; WIDTH as usize / 8 * HEIGHT as usize];
+//!# fn stop_until_rtc_alarm() {}
+//!
+//!let mut epd = Epd5in83::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
+//!// returns once the refresh is started
+//!epd.update_and_display_frame(&mut spi, &frame, &mut delay)?;
+//!
+//!let mut wakeups = 0;
+//!busy::wait_until_idle(&epd, || {
+//!    // sleep until the RTC wakes the MCU up again, give up after 30 wakeups
+//!    stop_until_rtc_alarm();
+//!    wakeups += 1;
+//!    wakeups < 30
+//!})?;
+//!epd.sleep(&mut spi, &mut delay)?;
+//!# Ok(())
+//!# }
+//!```
+//!
+//! Only drivers which return right after starting the refresh leave the waiting to
+//! the caller, the others already wait for the busy line inside `display_frame`.
+
+use embedded_hal::{
+    blocking::{delay::*, spi::Write},
+    digital::v2::*,
+};
+
+use crate::traits::WaveshareDisplay;
+use crate::Error;
+
+/// How updates sent while the display is busy are handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BusyPolicy {
+    /// Wait until the display is idle, then send the update
+    Block,
+    /// Return [`BusyError::Busy`] without sending anything
+    Fail,
+    /// Keep the update and send it with [`BusyGuard::poll`] once the display is idle
+    ///
+    /// Only one update is kept, a newer one replaces it.
+    Queue,
+}
+
+/// Errors returned by a [`BusyGuard`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BusyError<E> {
+    /// The display is busy and the policy is [`BusyPolicy::Fail`]
+    Busy,
+    /// The queue buffer can't hold the frame
+    QueueTooSmall,
+    /// The deadline passed before the display became idle
+    TimedOut,
+    /// The SPI peripheral returned an error
+    Spi(E),
+}
+
+impl<E> From<E> for BusyError<E> {
+    fn from(error: E) -> Self {
+        BusyError::Spi(error)
+    }
+}
+
+/// Waits until `epd` is idle, without polling the busy line in a loop
+///
+/// The line is checked once, and again after every call to `keep_waiting` while the
+/// display is busy. `keep_waiting` should let the MCU sleep until the next wakeup and
+/// return `false` once the deadline has passed, which ends the wait with
+/// [`BusyError::TimedOut`].
+pub fn wait_until_idle<SPI, CS, BUSY, DC, RST, DELAY, EPD, F>(
+    epd: &EPD,
+    mut keep_waiting: F,
+) -> Result<(), BusyError<Error<SPI::Error>>>
+where
+    EPD: WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>,
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+    F: FnMut() -> bool,
+{
+    while epd.is_busy() {
+        if !keep_waiting() {
+            return Err(BusyError::TimedOut);
+        }
+    }
+    Ok(())
+}
+
+/// Update waiting in the queue of a [`BusyGuard`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Pending {
+    /// Number of bytes of the frame in the queue, 0 if there is no frame
+    len: usize,
+    /// Window of a partial frame, `None` for a full one
+    area: Option<(u32, u32, u32, u32)>,
+    /// Display the frame after it was sent
+    display: bool,
+}
+
+/// A driver which handles updates during a refresh according to a [`BusyPolicy`]
+pub struct BusyGuard<EPD, Q = [u8; 0]> {
+    epd: EPD,
+    policy: BusyPolicy,
+    queue: Q,
+    pending: Option<Pending>,
+}
+
+impl<EPD> BusyGuard<EPD, [u8; 0]> {
+    /// Wraps `epd` with the given policy
+    ///
+    /// Without a queue buffer [`BusyPolicy::Queue`] can only keep a pending
+    /// `display_frame`, frames are rejected with [`BusyError::QueueTooSmall`].
+    /// Use [`BusyGuard::queued`] to queue frames.
+    pub fn new(epd: EPD, policy: BusyPolicy) -> Self {
+        BusyGuard {
+            epd,
+            policy,
+            queue: [],
+            pending: None,
+        }
+    }
+}
+
+impl<EPD, Q> BusyGuard<EPD, Q>
+where
+    Q: AsMut<[u8]>,
+{
+    /// Wraps `epd` with [`BusyPolicy::Queue`], frames are kept in `queue`
+    pub fn queued(epd: EPD, queue: Q) -> Self {
+        BusyGuard {
+            epd,
+            policy: BusyPolicy::Queue,
+            queue,
+            pending: None,
+        }
+    }
+
+    /// The current policy
+    pub fn policy(&self) -> BusyPolicy {
+        self.policy
+    }
+
+    /// Changes the policy, an update already in the queue is still sent by [`BusyGuard::poll`]
+    pub fn set_policy(&mut self, policy: BusyPolicy) {
+        self.policy = policy;
+    }
+
+    /// Returns true if an update is waiting to be sent
+    pub fn has_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Drops the update waiting in the queue
+    pub fn discard_pending(&mut self) {
+        self.pending = None;
+    }
+
+    /// Returns the wrapped driver
+    pub fn epd(&self) -> &EPD {
+        &self.epd
+    }
+
+    /// Returns the wrapped driver mutably
+    ///
+    /// Calls made directly on the driver bypass the policy.
+    pub fn epd_mut(&mut self) -> &mut EPD {
+        &mut self.epd
+    }
+
+    /// Releases the driver and the queue buffer, a pending update is dropped
+    pub fn release(self) -> (EPD, Q) {
+        (self.epd, self.queue)
+    }
+
+    /// Sends the pending update if the display is idle
+    ///
+    /// Returns true once nothing is left in the queue.
+    pub fn poll<SPI, CS, BUSY, DC, RST, DELAY>(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<bool, BusyError<Error<SPI::Error>>>
+    where
+        EPD: WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>,
+        SPI: Write<u8>,
+        CS: OutputPin,
+        BUSY: InputPin,
+        DC: OutputPin,
+        RST: OutputPin,
+        DELAY: DelayMs<u8>,
+    {
+        let pending = match self.pending {
+            Some(pending) if !self.epd.is_busy() => pending,
+            Some(_) => return Ok(false),
+            None => return Ok(true),
+        };
+        self.pending = None;
+
+        let buffer = &self.queue.as_mut()[..pending.len];
+        match pending.area {
+            Some((x, y, width, height)) => {
+                self.epd
+                    .update_partial_frame(spi, buffer, x, y, width, height)?;
+            }
+            None if pending.len > 0 => self.epd.update_frame(spi, buffer, delay)?,
+            None => {}
+        }
+        if pending.display {
+            self.epd.display_frame(spi, delay)?;
+        }
+        Ok(true)
+    }
+
+    /// Waits until the queue is sent and the display is idle, like [`wait_until_idle`]
+    ///
+    /// The pending update is sent as soon as a check finds the display idle.
+    pub fn wait_until_idle<SPI, CS, BUSY, DC, RST, DELAY, F>(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        mut keep_waiting: F,
+    ) -> Result<(), BusyError<Error<SPI::Error>>>
+    where
+        EPD: WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>,
+        SPI: Write<u8>,
+        CS: OutputPin,
+        BUSY: InputPin,
+        DC: OutputPin,
+        RST: OutputPin,
+        DELAY: DelayMs<u8>,
+        F: FnMut() -> bool,
+    {
+        while !self.poll(spi, delay)? || self.epd.is_busy() {
+            if !keep_waiting() {
+                return Err(BusyError::TimedOut);
+            }
+        }
+        Ok(())
+    }
+
+    /// Transmits a full frame, see [`WaveshareDisplay::update_frame`]
+    pub fn update_frame<SPI, CS, BUSY, DC, RST, DELAY>(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), BusyError<Error<SPI::Error>>>
+    where
+        EPD: WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>,
+        SPI: Write<u8>,
+        CS: OutputPin,
+        BUSY: InputPin,
+        DC: OutputPin,
+        RST: OutputPin,
+        DELAY: DelayMs<u8>,
+    {
+        if self.ready(spi, delay)? {
+            self.epd.update_frame(spi, buffer, delay)?;
+            Ok(())
+        } else {
+            self.enqueue(buffer, None, false)
+        }
+    }
+
+    /// Transmits a part of a frame, see [`WaveshareDisplay::update_partial_frame`]
+    #[allow(clippy::too_many_arguments)]
+    pub fn update_partial_frame<SPI, CS, BUSY, DC, RST, DELAY>(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        delay: &mut DELAY,
+    ) -> Result<(), BusyError<Error<SPI::Error>>>
+    where
+        EPD: WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>,
+        SPI: Write<u8>,
+        CS: OutputPin,
+        BUSY: InputPin,
+        DC: OutputPin,
+        RST: OutputPin,
+        DELAY: DelayMs<u8>,
+    {
+        if self.ready(spi, delay)? {
+            self.epd
+                .update_partial_frame(spi, buffer, x, y, width, height)?;
+            Ok(())
+        } else {
+            self.enqueue(buffer, Some((x, y, width, height)), false)
+        }
+    }
+
+    /// Displays the frame in the SRAM, see [`WaveshareDisplay::display_frame`]
+    pub fn display_frame<SPI, CS, BUSY, DC, RST, DELAY>(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), BusyError<Error<SPI::Error>>>
+    where
+        EPD: WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>,
+        SPI: Write<u8>,
+        CS: OutputPin,
+        BUSY: InputPin,
+        DC: OutputPin,
+        RST: OutputPin,
+        DELAY: DelayMs<u8>,
+    {
+        if self.ready(spi, delay)? {
+            self.epd.display_frame(spi, delay)?;
+            Ok(())
+        } else {
+            let pending = self.pending.get_or_insert(Pending {
+                len: 0,
+                area: None,
+                display: false,
+            });
+            pending.display = true;
+            Ok(())
+        }
+    }
+
+    /// Transmits and displays a full frame, see [`WaveshareDisplay::update_and_display_frame`]
+    pub fn update_and_display_frame<SPI, CS, BUSY, DC, RST, DELAY>(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), BusyError<Error<SPI::Error>>>
+    where
+        EPD: WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>,
+        SPI: Write<u8>,
+        CS: OutputPin,
+        BUSY: InputPin,
+        DC: OutputPin,
+        RST: OutputPin,
+        DELAY: DelayMs<u8>,
+    {
+        if self.ready(spi, delay)? {
+            self.epd.update_and_display_frame(spi, buffer, delay)?;
+            Ok(())
+        } else {
+            self.enqueue(buffer, None, true)
+        }
+    }
+
+    /// Applies the policy, returns true if the update can be sent right away
+    ///
+    /// A pending update is sent first, so updates always reach the display in order.
+    fn ready<SPI, CS, BUSY, DC, RST, DELAY>(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<bool, BusyError<Error<SPI::Error>>>
+    where
+        EPD: WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>,
+        SPI: Write<u8>,
+        CS: OutputPin,
+        BUSY: InputPin,
+        DC: OutputPin,
+        RST: OutputPin,
+        DELAY: DelayMs<u8>,
+    {
+        if self.policy == BusyPolicy::Block {
+            while self.epd.is_busy() {}
+            self.poll(spi, delay)?;
+            while self.epd.is_busy() {}
+            return Ok(true);
+        }
+        if self.poll(spi, delay)? && !self.epd.is_busy() {
+            return Ok(true);
+        }
+        match self.policy {
+            BusyPolicy::Fail => Err(BusyError::Busy),
+            _ => Ok(false),
+        }
+    }
+
+    fn enqueue<E>(
+        &mut self,
+        buffer: &[u8],
+        area: Option<(u32, u32, u32, u32)>,
+        display: bool,
+    ) -> Result<(), BusyError<E>> {
+        let queue = self.queue.as_mut();
+        if buffer.len() > queue.len() {
+            return Err(BusyError::QueueTooSmall);
+        }
+        queue[..buffer.len()].copy_from_slice(buffer);
+        // a newer frame replaces the queued one, a queued refresh then shows the newer frame
+        let display = display || self.pending.is_some_and(|p| p.display);
+        self.pending = Some(Pending {
+            len: buffer.len(),
+            area,
+            display,
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::Cell;
+    use embedded_hal_mock::{
+        delay::MockNoop, pin::Mock as PinMock, spi::Mock as SpiMock, MockError,
+    };
+
+    /// Driver which only records what was sent
+    #[derive(Default)]
+    struct FakeEpd<'a> {
+        busy: Option<&'a Cell<bool>>,
+        frames: u32,
+        partial_frames: u32,
+        displayed: u32,
+        last: u8,
+    }
+
+    impl<'a> WaveshareDisplay<SpiMock, PinMock, PinMock, PinMock, PinMock, MockNoop> for FakeEpd<'a> {
+        type DisplayColor = ();
+
+        fn new(
+            _spi: &mut SpiMock,
+            _cs: PinMock,
+            _busy: PinMock,
+            _dc: PinMock,
+            _rst: PinMock,
+            _delay: &mut MockNoop,
+        ) -> Result<Self, Error<MockError>> {
+            Ok(FakeEpd::default())
+        }
+        fn sleep(
+            &mut self,
+            _spi: &mut SpiMock,
+            _delay: &mut MockNoop,
+        ) -> Result<(), Error<MockError>> {
+            Ok(())
+        }
+        fn wake_up(
+            &mut self,
+            _spi: &mut SpiMock,
+            _delay: &mut MockNoop,
+        ) -> Result<(), Error<MockError>> {
+            Ok(())
+        }
+        fn set_background_color(&mut self, _color: ()) {}
+        fn background_color(&self) -> &() {
+            &()
+        }
+        fn width(&self) -> u32 {
+            8
+        }
+        fn height(&self) -> u32 {
+            2
+        }
+        fn update_frame(
+            &mut self,
+            _spi: &mut SpiMock,
+            buffer: &[u8],
+            _delay: &mut MockNoop,
+        ) -> Result<(), Error<MockError>> {
+            self.frames += 1;
+            self.last = buffer[0];
+            Ok(())
+        }
+        fn update_partial_frame(
+            &mut self,
+            _spi: &mut SpiMock,
+            buffer: &[u8],
+            _x: u32,
+            _y: u32,
+            _width: u32,
+            _height: u32,
+        ) -> Result<(), Error<MockError>> {
+            self.partial_frames += 1;
+            self.last = buffer[0];
+            Ok(())
+        }
+        fn display_frame(
+            &mut self,
+            _spi: &mut SpiMock,
+            _delay: &mut MockNoop,
+        ) -> Result<(), Error<MockError>> {
+            self.displayed += 1;
+            Ok(())
+        }
+        fn update_and_display_frame(
+            &mut self,
+            spi: &mut SpiMock,
+            buffer: &[u8],
+            delay: &mut MockNoop,
+        ) -> Result<(), Error<MockError>> {
+            self.update_frame(spi, buffer, delay)?;
+            self.display_frame(spi, delay)
+        }
+        fn clear_frame(
+            &mut self,
+            _spi: &mut SpiMock,
+            _delay: &mut MockNoop,
+        ) -> Result<(), Error<MockError>> {
+            Ok(())
+        }
+        fn set_lut(
+            &mut self,
+            _spi: &mut SpiMock,
+            _refresh_rate: Option<crate::traits::RefreshLut>,
+        ) -> Result<(), Error<MockError>> {
+            Ok(())
+        }
+        fn is_busy(&self) -> bool {
+            self.busy.is_some_and(Cell::get)
+        }
+    }
+
+    #[test]
+    fn fail_while_busy() {
+        let mut spi = SpiMock::new(&[]);
+        let mut delay = MockNoop::new();
+        let busy = Cell::new(true);
+        let epd = FakeEpd {
+            busy: Some(&busy),
+            ..FakeEpd::default()
+        };
+        let mut epd = BusyGuard::new(epd, BusyPolicy::Fail);
+
+        assert_eq!(
+            epd.update_frame(&mut spi, &[1, 1], &mut delay),
+            Err(BusyError::Busy)
+        );
+        assert_eq!(epd.epd().frames, 0);
+
+        busy.set(false);
+        epd.update_frame(&mut spi, &[1, 1], &mut delay).unwrap();
+        assert_eq!(epd.epd().frames, 1);
+    }
+
+    #[test]
+    fn queue_while_busy() {
+        let mut spi = SpiMock::new(&[]);
+        let mut delay = MockNoop::new();
+        let busy = Cell::new(true);
+        let epd = FakeEpd {
+            busy: Some(&busy),
+            ..FakeEpd::default()
+        };
+        let mut queue = [0u8; 2];
+        let mut epd = BusyGuard::queued(epd, &mut queue[..]);
+
+        epd.update_and_display_frame(&mut spi, &[1, 1], &mut delay)
+            .unwrap();
+        // the newer frame replaces the queued one
+        epd.update_frame(&mut spi, &[2, 2], &mut delay).unwrap();
+        assert_eq!(
+            epd.update_frame(&mut spi, &[3, 3, 3], &mut delay),
+            Err(BusyError::QueueTooSmall)
+        );
+        assert!(!epd.poll(&mut spi, &mut delay).unwrap());
+        assert_eq!(epd.epd().frames, 0);
+
+        busy.set(false);
+        assert!(epd.poll(&mut spi, &mut delay).unwrap());
+        assert!(!epd.has_pending());
+        assert_eq!(epd.epd().frames, 1);
+        assert_eq!(epd.epd().last, 2);
+        assert_eq!(epd.epd().displayed, 1);
+    }
+
+    #[test]
+    fn queue_is_sent_first() {
+        let mut spi = SpiMock::new(&[]);
+        let mut delay = MockNoop::new();
+        let busy = Cell::new(true);
+        let epd = FakeEpd {
+            busy: Some(&busy),
+            ..FakeEpd::default()
+        };
+        let mut queue = [0u8; 2];
+        let mut epd = BusyGuard::queued(epd, &mut queue[..]);
+
+        epd.update_partial_frame(&mut spi, &[1], 0, 0, 8, 1, &mut delay)
+            .unwrap();
+        busy.set(false);
+        epd.display_frame(&mut spi, &mut delay).unwrap();
+
+        assert_eq!(epd.epd().partial_frames, 1);
+        assert_eq!(epd.epd().displayed, 1);
+        assert!(!epd.has_pending());
+    }
+
+    #[test]
+    fn queue_display_without_buffer() {
+        let mut spi = SpiMock::new(&[]);
+        let mut delay = MockNoop::new();
+        let busy = Cell::new(true);
+        let epd = FakeEpd {
+            busy: Some(&busy),
+            ..FakeEpd::default()
+        };
+        let mut epd = BusyGuard::new(epd, BusyPolicy::Queue);
+
+        epd.display_frame(&mut spi, &mut delay).unwrap();
+        assert_eq!(
+            epd.update_frame(&mut spi, &[1], &mut delay),
+            Err(BusyError::QueueTooSmall)
+        );
+
+        busy.set(false);
+        assert!(epd.poll(&mut spi, &mut delay).unwrap());
+        assert_eq!(epd.epd().displayed, 1);
+        assert_eq!(epd.epd().frames, 0);
+    }
+
+    #[test]
+    fn wait_with_deadline_check() {
+        let busy = Cell::new(true);
+        let epd = FakeEpd {
+            busy: Some(&busy),
+            ..FakeEpd::default()
+        };
+
+        let mut wakeups = 0;
+        let result: Result<(), BusyError<Error<MockError>>> = wait_until_idle(&epd, || {
+            wakeups += 1;
+            busy.set(wakeups < 3);
+            true
+        });
+        assert_eq!(result, Ok(()));
+        assert_eq!(wakeups, 3);
+
+        // idle displays don't call the check at all
+        let result: Result<(), BusyError<Error<MockError>>> =
+            wait_until_idle(&epd, || unreachable!());
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn wait_times_out() {
+        let busy = Cell::new(true);
+        let epd = FakeEpd {
+            busy: Some(&busy),
+            ..FakeEpd::default()
+        };
+
+        let mut wakeups = 0;
+        let result: Result<(), BusyError<Error<MockError>>> = wait_until_idle(&epd, || {
+            wakeups += 1;
+            wakeups < 5
+        });
+        assert_eq!(result, Err(BusyError::TimedOut));
+        assert_eq!(wakeups, 5);
+    }
+
+    #[test]
+    fn guard_sends_queue_while_waiting() {
+        let mut spi = SpiMock::new(&[]);
+        let mut delay = MockNoop::new();
+        let busy = Cell::new(true);
+        let epd = FakeEpd {
+            busy: Some(&busy),
+            ..FakeEpd::default()
+        };
+        let mut queue = [0u8; 2];
+        let mut epd = BusyGuard::queued(epd, &mut queue[..]);
+
+        epd.update_and_display_frame(&mut spi, &[1, 1], &mut delay)
+            .unwrap();
+        let mut wakeups = 0;
+        epd.wait_until_idle(&mut spi, &mut delay, || {
+            wakeups += 1;
+            busy.set(wakeups < 2);
+            true
+        })
+        .unwrap();
+
+        assert_eq!(wakeups, 2);
+        assert!(!epd.has_pending());
+        assert_eq!(epd.epd().displayed, 1);
+    }
+}
@@ -0,0 +1,453 @@
+//! Measuring how long the refreshes of the attached panel take
+//!
+//! The refresh time of a panel varies from batch to batch and with the temperature,
+//! so the values of the datasheet are only a rough guess. [`calibrate_refresh_timing`]
+//! runs a full, a fast and a partial refresh and measures each of them with a clock
+//! you provide, until the busy line is released. The resulting [`RefreshTiming`] gives
+//! the timeouts to wait for the busy line, e.g. with
+//! [`SelfTest::with_refresh_timing`](crate::testing::SelfTest::with_refresh_timing) or
+//! [`busy::wait_until_idle`](crate::busy::wait_until_idle), and the charge of a refresh
+//! to plan the power budget. Store it with [`RefreshTiming::to_bytes`] to skip the
+//! calibration after the next boot.
+//!
+//!```rust, no_run
+//!# use embedded_hal_mock::*;
+//!# fn main() -> Result<(), epd_waveshare::calibration::CalibrationError<epd_waveshare::Error<MockError>>> {
+//!use epd_waveshare::{calibration::*, epd4in2::*, graphics::Display, prelude::*, refresh::RefreshMode};
+//!#
+//!# let expectations = [];
+//!# let mut spi = spi::Mock::new(&expectations);
+//!# let expectations = [];
+//!# let cs_pin = pin::Mock::new(&expectations);
+//!# let busy_in = pin::Mock::new(&expectations);
+//!# let dc = pin::Mock::new(&expectations);
+//!# let rst = pin::Mock::new(&expectations);
+//!# let mut delay = delay::MockNoop::new();
+//!# let now_ms = || 0u32;
+//!
+//!let mut epd = Epd4in2::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
+//!let display = Display4in2::default();
+//!
+//!// `now_ms` returns the current time of a free running timer
+//!let timing = calibrate_refresh_timing(&mut epd, &mut spi, &mut delay, display.buffer(), now_ms)?;
+//!
+//!let _timeout_ms = timing.timeout_ms(RefreshMode::Full);
+//!// the panel draws about 4mA while refreshing
+//!let _charge_uc = timing.charge_uc(RefreshMode::Fast, 4000);
+//!# Ok(())
+//!# }
+//!```
+
+use embedded_hal::{
+    blocking::{delay::*, spi::Write},
+    digital::v2::{InputPin, OutputPin},
+};
+
+use crate::refresh::RefreshMode;
+use crate::traits::WaveshareDisplay;
+use crate::{buffer_len, Error};
+
+/// Time in ms a calibration waits for the busy line at most
+pub const CALIBRATION_TIMEOUT_MS: u32 = 60_000;
+/// Margin in percent [`RefreshTiming::timeout_ms`] adds on top of the measured time
+pub const TIMEOUT_MARGIN_PERCENT: u32 = 50;
+/// Margin in ms [`RefreshTiming::timeout_ms`] adds at least
+pub const MIN_TIMEOUT_MARGIN_MS: u32 = 500;
+
+/// Interval in ms in which the busy line is polled
+const BUSY_POLL_MS: u8 = 1;
+/// Rows of the frame sent for the partial refresh
+const PARTIAL_ROWS: u32 = 8;
+
+/// Reason for a failed calibration
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalibrationError<E> {
+    /// The SPI transfer failed
+    Spi(E),
+    /// The busy line wasn't released within [`CALIBRATION_TIMEOUT_MS`]
+    BusyTimeout,
+    /// The frame is smaller than the display
+    BufferTooSmall,
+}
+
+impl<E> From<E> for CalibrationError<E> {
+    fn from(error: E) -> Self {
+        CalibrationError::Spi(error)
+    }
+}
+
+/// Measured durations of the refreshes in ms
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RefreshTiming {
+    /// Full refresh with the [`RefreshLut::Full`](crate::traits::RefreshLut::Full) waveform
+    pub full_ms: u32,
+    /// Refresh of the whole frame with the
+    /// [`RefreshLut::Quick`](crate::traits::RefreshLut::Quick) waveform
+    pub fast_ms: u32,
+    /// Refresh after a partial update
+    pub partial_ms: u32,
+}
+
+impl RefreshTiming {
+    /// Creates the timing from known durations, e.g. from the datasheet
+    pub const fn new(full_ms: u32, fast_ms: u32, partial_ms: u32) -> Self {
+        RefreshTiming {
+            full_ms,
+            fast_ms,
+            partial_ms,
+        }
+    }
+
+    /// Measured duration of a refresh in `mode`
+    pub fn duration_ms(&self, mode: RefreshMode) -> u32 {
+        match mode {
+            RefreshMode::Full => self.full_ms,
+            RefreshMode::Fast => self.fast_ms,
+            RefreshMode::Partial => self.partial_ms,
+        }
+    }
+
+    /// Time to wait for the busy line after a refresh in `mode`, before giving up
+    ///
+    /// Adds [`TIMEOUT_MARGIN_PERCENT`], but at least [`MIN_TIMEOUT_MARGIN_MS`], to the
+    /// measured time, since a colder panel refreshes slower.
+    pub fn timeout_ms(&self, mode: RefreshMode) -> u32 {
+        let duration = self.duration_ms(mode);
+        let margin = (duration / 100).saturating_mul(TIMEOUT_MARGIN_PERCENT);
+        duration.saturating_add(margin.max(MIN_TIMEOUT_MARGIN_MS))
+    }
+
+    /// Time to wait for the busy line after a refresh of a three color frame
+    ///
+    /// The [`timeout_ms`](Self::timeout_ms) grows with the share of the `chromatic_pixels`
+    /// in the `total_pixels`, by `extra_percent` for a frame of only chromatic pixels.
+    /// The calibration measures frames without chromatic pixels, so red-heavy frames would
+    /// trip the plain timeout. Take the count from
+    /// [`TriDisplay::chromatic_pixels`](crate::graphics::TriDisplay::chromatic_pixels) and
+    /// the percentage from
+    /// [`WaveshareThreeColorDisplay::chromatic_extra_percent`](crate::traits::WaveshareThreeColorDisplay::chromatic_extra_percent).
+    pub fn chromatic_timeout_ms(
+        &self,
+        mode: RefreshMode,
+        chromatic_pixels: u32,
+        total_pixels: u32,
+        extra_percent: u32,
+    ) -> u32 {
+        let timeout = u64::from(self.timeout_ms(mode));
+        if total_pixels == 0 {
+            return timeout as u32;
+        }
+        let chromatic = u64::from(chromatic_pixels.min(total_pixels));
+        let extra = timeout * u64::from(extra_percent) / 100 * chromatic / u64::from(total_pixels);
+        (timeout + extra).min(u64::from(u32::MAX)) as u32
+    }
+
+    /// Charge in µC a refresh in `mode` takes from the supply
+    ///
+    /// `current_ua` is the average current while refreshing, see the datasheet.
+    pub fn charge_uc(&self, mode: RefreshMode, current_ua: u32) -> u32 {
+        let charge = u64::from(self.duration_ms(mode)) * u64::from(current_ua) / 1000;
+        charge.min(u64::from(u32::MAX)) as u32
+    }
+
+    /// Encodes the timing as 3 little endian `u32`, in the order full, fast, partial
+    pub fn to_bytes(&self) -> [u8; 12] {
+        let mut bytes = [0; 12];
+        for (chunk, ms) in
+            bytes
+                .chunks_exact_mut(4)
+                .zip([self.full_ms, self.fast_ms, self.partial_ms])
+        {
+            chunk.copy_from_slice(&ms.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Decodes the timing stored with [`RefreshTiming::to_bytes`]
+    pub fn from_bytes(bytes: [u8; 12]) -> Self {
+        let ms =
+            |i: usize| u32::from_le_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]]);
+        RefreshTiming::new(ms(0), ms(4), ms(8))
+    }
+}
+
+/// Measures the full, fast and partial refresh of the attached panel
+///
+/// `frame` is displayed with every refresh and has to hold a full frame with 1 bit per
+/// pixel, the partial refresh sends its first rows again. `now_ms` returns the current
+/// time of a free running timer, it may wrap around.
+///
+/// Drivers without a quick waveform measure the full one for the fast and partial
+/// refresh. The driver has to support `update_partial_frame`. The full waveform is
+/// selected again afterwards.
+pub fn calibrate_refresh_timing<EPD, SPI, CS, BUSY, DC, RST, DELAY, F>(
+    epd: &mut EPD,
+    spi: &mut SPI,
+    delay: &mut DELAY,
+    frame: &[u8],
+    mut now_ms: F,
+) -> Result<RefreshTiming, CalibrationError<Error<SPI::Error>>>
+where
+    EPD: WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>,
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+    F: FnMut() -> u32,
+{
+    let (width, height) = (epd.width(), epd.height());
+    let len = buffer_len(width as usize, height as usize);
+    if frame.len() < len {
+        return Err(CalibrationError::BufferTooSmall);
+    }
+    let frame = &frame[..len];
+
+    let mut timing = RefreshTiming::new(0, 0, 0);
+    for mode in [RefreshMode::Full, RefreshMode::Fast, RefreshMode::Partial] {
+        wait_idle(epd, delay, CALIBRATION_TIMEOUT_MS)?;
+        epd.set_lut(spi, Some(mode.lut()))?;
+        if mode == RefreshMode::Partial {
+            let rows = PARTIAL_ROWS.min(height);
+            let band = &frame[..buffer_len(width as usize, rows as usize)];
+            epd.update_partial_frame(spi, band, 0, 0, width, rows)?;
+        } else {
+            epd.update_frame(spi, frame, delay)?;
+        }
+        wait_idle(epd, delay, CALIBRATION_TIMEOUT_MS)?;
+
+        let start = now_ms();
+        epd.display_frame(spi, delay)?;
+        wait_idle(epd, delay, CALIBRATION_TIMEOUT_MS)?;
+        let elapsed = now_ms().wrapping_sub(start);
+
+        match mode {
+            RefreshMode::Full => timing.full_ms = elapsed,
+            RefreshMode::Fast => timing.fast_ms = elapsed,
+            RefreshMode::Partial => timing.partial_ms = elapsed,
+        }
+    }
+    epd.set_lut(spi, Some(RefreshMode::Full.lut()))?;
+    Ok(timing)
+}
+
+/// Polls the busy line until it is released or `timeout_ms` passed
+fn wait_idle<EPD, SPI, CS, BUSY, DC, RST, DELAY>(
+    epd: &EPD,
+    delay: &mut DELAY,
+    timeout_ms: u32,
+) -> Result<(), CalibrationError<Error<SPI::Error>>>
+where
+    EPD: WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>,
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    let mut waited = 0;
+    while epd.is_busy() {
+        if waited >= timeout_ms {
+            return Err(CalibrationError::BusyTimeout);
+        }
+        delay.delay_ms(BUSY_POLL_MS);
+        waited += u32::from(BUSY_POLL_MS);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::RefreshLut;
+    use core::cell::Cell;
+    use embedded_hal_mock::{
+        delay::MockNoop, pin::Mock as PinMock, spi::Mock as SpiMock, MockError,
+    };
+
+    /// Driver whose refreshes advance a clock instead of the busy line
+    struct FakeEpd<'a> {
+        clock: &'a Cell<u32>,
+        lut: RefreshLut,
+        partial: bool,
+    }
+
+    impl<'a> WaveshareDisplay<SpiMock, PinMock, PinMock, PinMock, PinMock, MockNoop> for FakeEpd<'a> {
+        type DisplayColor = ();
+
+        fn new(
+            _spi: &mut SpiMock,
+            _cs: PinMock,
+            _busy: PinMock,
+            _dc: PinMock,
+            _rst: PinMock,
+            _delay: &mut MockNoop,
+        ) -> Result<Self, Error<MockError>> {
+            unimplemented!()
+        }
+        fn sleep(
+            &mut self,
+            _spi: &mut SpiMock,
+            _delay: &mut MockNoop,
+        ) -> Result<(), Error<MockError>> {
+            Ok(())
+        }
+        fn wake_up(
+            &mut self,
+            _spi: &mut SpiMock,
+            _delay: &mut MockNoop,
+        ) -> Result<(), Error<MockError>> {
+            Ok(())
+        }
+        fn set_background_color(&mut self, _color: ()) {}
+        fn background_color(&self) -> &() {
+            &()
+        }
+        fn width(&self) -> u32 {
+            16
+        }
+        fn height(&self) -> u32 {
+            20
+        }
+        fn update_frame(
+            &mut self,
+            _spi: &mut SpiMock,
+            _buffer: &[u8],
+            _delay: &mut MockNoop,
+        ) -> Result<(), Error<MockError>> {
+            self.partial = false;
+            Ok(())
+        }
+        fn update_partial_frame(
+            &mut self,
+            _spi: &mut SpiMock,
+            buffer: &[u8],
+            _x: u32,
+            _y: u32,
+            width: u32,
+            height: u32,
+        ) -> Result<(), Error<MockError>> {
+            assert_eq!((width, height, buffer.len()), (16, 8, 16));
+            self.partial = true;
+            Ok(())
+        }
+        fn display_frame(
+            &mut self,
+            _spi: &mut SpiMock,
+            _delay: &mut MockNoop,
+        ) -> Result<(), Error<MockError>> {
+            let duration = match (self.lut, self.partial) {
+                (RefreshLut::Full | RefreshLut::Custom(_), _) => 3000,
+                (RefreshLut::Quick, false) => 800,
+                (RefreshLut::Quick, true) => 300,
+            };
+            self.clock.set(self.clock.get().wrapping_add(duration));
+            Ok(())
+        }
+        fn update_and_display_frame(
+            &mut self,
+            spi: &mut SpiMock,
+            buffer: &[u8],
+            delay: &mut MockNoop,
+        ) -> Result<(), Error<MockError>> {
+            self.update_frame(spi, buffer, delay)?;
+            self.display_frame(spi, delay)
+        }
+        fn clear_frame(
+            &mut self,
+            _spi: &mut SpiMock,
+            _delay: &mut MockNoop,
+        ) -> Result<(), Error<MockError>> {
+            Ok(())
+        }
+        fn set_lut(
+            &mut self,
+            _spi: &mut SpiMock,
+            refresh_rate: Option<RefreshLut>,
+        ) -> Result<(), Error<MockError>> {
+            if let Some(lut) = refresh_rate {
+                self.lut = lut;
+            }
+            Ok(())
+        }
+        fn is_busy(&self) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn measures_all_modes() {
+        let mut spi = SpiMock::new(&[]);
+        let mut delay = MockNoop::new();
+        // close to wrapping around
+        let clock = Cell::new(u32::MAX - 1000);
+        let mut epd = FakeEpd {
+            clock: &clock,
+            lut: RefreshLut::Quick,
+            partial: false,
+        };
+
+        let timing =
+            calibrate_refresh_timing(&mut epd, &mut spi, &mut delay, &[0; 40], || clock.get())
+                .unwrap();
+        assert_eq!(timing, RefreshTiming::new(3000, 800, 300));
+        assert_eq!(epd.lut, RefreshLut::Full);
+
+        assert_eq!(
+            calibrate_refresh_timing(&mut epd, &mut spi, &mut delay, &[0; 39], || 0),
+            Err(CalibrationError::BufferTooSmall)
+        );
+    }
+
+    #[test]
+    fn timeouts_and_charge() {
+        let timing = RefreshTiming::new(4000, 600, 300);
+        assert_eq!(timing.timeout_ms(RefreshMode::Full), 6000);
+        // at least the minimum margin
+        assert_eq!(timing.timeout_ms(RefreshMode::Partial), 800);
+        assert_eq!(timing.charge_uc(RefreshMode::Fast, 5000), 3000);
+        assert_eq!(
+            RefreshTiming::new(u32::MAX, 0, 0).timeout_ms(RefreshMode::Full),
+            u32::MAX
+        );
+    }
+
+    #[test]
+    fn chromatic_timeouts() {
+        let timing = RefreshTiming::new(10_000, 0, 0);
+        assert_eq!(
+            timing.chromatic_timeout_ms(RefreshMode::Full, 0, 1000, 100),
+            15_000
+        );
+        assert_eq!(
+            timing.chromatic_timeout_ms(RefreshMode::Full, 250, 1000, 100),
+            18_750
+        );
+        assert_eq!(
+            timing.chromatic_timeout_ms(RefreshMode::Full, 1000, 1000, 100),
+            30_000
+        );
+        // counts above the total, e.g. from padding bits, don't scale any further
+        assert_eq!(
+            timing.chromatic_timeout_ms(RefreshMode::Full, 2000, 1000, 50),
+            22_500
+        );
+        assert_eq!(
+            timing.chromatic_timeout_ms(RefreshMode::Full, 5, 0, 100),
+            15_000
+        );
+        assert_eq!(
+            RefreshTiming::new(u32::MAX, 0, 0).chromatic_timeout_ms(RefreshMode::Full, 1, 1, 100),
+            u32::MAX
+        );
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let timing = RefreshTiming::new(4000, 600, 70_000);
+        assert_eq!(timing.to_bytes()[..4], [0xA0, 0x0F, 0, 0]);
+        assert_eq!(RefreshTiming::from_bytes(timing.to_bytes()), timing);
+    }
+}
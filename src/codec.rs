@@ -0,0 +1,204 @@
+//! Compression of frame buffers for storage and transfer
+//!
+//! A full frame of the bigger displays is tens of kilobytes, although most of it
+//! is usually just background. Frames which are kept in flash or received over
+//! the air can be stored compressed with a [`FrameCodec`] and unpacked again right
+//! before they are handed to the display.
+//!
+//! [`Rle`] is provided as a simple default, other codecs can be used by
+//! implementing [`FrameCodec`]. Codecs are taken by
+//! [`Display::snapshot_compressed`](crate::graphics::Display::snapshot_compressed) and
+//! [`Display::restore_compressed`](crate::graphics::Display::restore_compressed) for
+//! snapshots, and by [`apply_compressed_frame`](crate::remote::apply_compressed_frame)
+//! for frames received over the air.
+//!
+//!```rust
+//!# fn main() -> Result<(), epd_waveshare::codec::CodecError<core::convert::Infallible>> {
+//!use epd_waveshare::codec::{FrameCodec, Rle};
+//!
+//!let frame = [0xFFu8; 64];
+//!
+//!let mut stored = [0u8; 8];
+//!let len = Rle.encode_to_slice(&frame, &mut stored)?;
+//!assert_eq!(&stored[..len], &[64, 0xFF]);
+//!
+//!let mut buffer = [0u8; 64];
+//!let len = Rle.decode_to_slice(&stored[..len], &mut buffer)?;
+//!assert_eq!(&buffer[..len], &frame[..]);
+//!# Ok(())
+//!# }
+//!```
+
+use core::convert::Infallible;
+
+/// Errors returned by a [`FrameCodec`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodecError<E> {
+    /// The encoded data is malformed
+    Corrupt,
+    /// The output slice can't hold the result
+    BufferTooSmall,
+    /// The sink returned an error
+    Sink(E),
+}
+
+/// A frame compression scheme
+///
+/// Both directions are streaming: the output is handed to `sink` piece by piece,
+/// so a frame can be decoded straight into a SPI transfer or a flash write
+/// without holding the whole result in memory.
+pub trait FrameCodec {
+    /// Compresses `input` and passes the encoded bytes to `sink`
+    fn encode<E, F>(&self, input: &[u8], sink: F) -> Result<(), CodecError<E>>
+    where
+        F: FnMut(&[u8]) -> Result<(), E>;
+
+    /// Decompresses `input` and passes the frame bytes to `sink`
+    fn decode<E, F>(&self, input: &[u8], sink: F) -> Result<(), CodecError<E>>
+    where
+        F: FnMut(&[u8]) -> Result<(), E>;
+
+    /// Compresses `input` into `output` and returns the number of bytes written
+    fn encode_to_slice(
+        &self,
+        input: &[u8],
+        output: &mut [u8],
+    ) -> Result<usize, CodecError<Infallible>> {
+        let mut len = 0;
+        self.encode(input, |chunk| copy_into(output, &mut len, chunk))
+            .map_err(flatten)?;
+        Ok(len)
+    }
+
+    /// Decompresses `input` into `output` and returns the number of bytes written
+    fn decode_to_slice(
+        &self,
+        input: &[u8],
+        output: &mut [u8],
+    ) -> Result<usize, CodecError<Infallible>> {
+        let mut len = 0;
+        self.decode(input, |chunk| copy_into(output, &mut len, chunk))
+            .map_err(flatten)?;
+        Ok(len)
+    }
+}
+
+fn copy_into(output: &mut [u8], len: &mut usize, chunk: &[u8]) -> Result<(), ()> {
+    let end = *len + chunk.len();
+    if end > output.len() {
+        return Err(());
+    }
+    output[*len..end].copy_from_slice(chunk);
+    *len = end;
+    Ok(())
+}
+
+fn flatten(err: CodecError<()>) -> CodecError<Infallible> {
+    match err {
+        CodecError::Corrupt => CodecError::Corrupt,
+        CodecError::BufferTooSmall | CodecError::Sink(()) => CodecError::BufferTooSmall,
+    }
+}
+
+/// Run-length encoding
+///
+/// The data is stored as pairs of `(count, byte)` with a count between 1 and 255.
+/// Works well for frames with large areas of the same color, but doubles the size
+/// of noisy data like dithered images.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Rle;
+
+impl FrameCodec for Rle {
+    fn encode<E, F>(&self, input: &[u8], mut sink: F) -> Result<(), CodecError<E>>
+    where
+        F: FnMut(&[u8]) -> Result<(), E>,
+    {
+        let mut rest = input;
+        while let Some(&value) = rest.first() {
+            let count = rest
+                .iter()
+                .take(u8::MAX as usize)
+                .take_while(|&&b| b == value)
+                .count();
+            sink(&[count as u8, value]).map_err(CodecError::Sink)?;
+            rest = &rest[count..];
+        }
+        Ok(())
+    }
+
+    fn decode<E, F>(&self, input: &[u8], mut sink: F) -> Result<(), CodecError<E>>
+    where
+        F: FnMut(&[u8]) -> Result<(), E>,
+    {
+        if !input.len().is_multiple_of(2) {
+            return Err(CodecError::Corrupt);
+        }
+        let mut run = [0u8; 32];
+        for pair in input.chunks(2) {
+            let (mut count, value) = (pair[0] as usize, pair[1]);
+            if count == 0 {
+                return Err(CodecError::Corrupt);
+            }
+            run.iter_mut().for_each(|b| *b = value);
+            while count > 0 {
+                let n = count.min(run.len());
+                sink(&run[..n]).map_err(CodecError::Sink)?;
+                count -= n;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip(frame: &[u8]) {
+        let mut encoded = [0u8; 1024];
+        let len = Rle.encode_to_slice(frame, &mut encoded).unwrap();
+        let mut decoded = [0u8; 1024];
+        let dlen = Rle.decode_to_slice(&encoded[..len], &mut decoded).unwrap();
+        assert_eq!(&decoded[..dlen], frame);
+    }
+
+    #[test]
+    fn rle_roundtrip() {
+        roundtrip(&[]);
+        roundtrip(&[0x00; 600]);
+        roundtrip(&[0x00, 0x00, 0xFF, 0x0F, 0x0F, 0x0F, 0x00]);
+    }
+
+    #[test]
+    fn rle_long_runs_are_split() {
+        let mut encoded = [0u8; 8];
+        let len = Rle.encode_to_slice(&[0xAA; 300], &mut encoded).unwrap();
+        assert_eq!(&encoded[..len], &[255, 0xAA, 45, 0xAA]);
+    }
+
+    #[test]
+    fn rle_rejects_corrupt_input() {
+        let mut out = [0u8; 16];
+        assert_eq!(
+            Rle.decode_to_slice(&[3], &mut out),
+            Err(CodecError::Corrupt)
+        );
+        assert_eq!(
+            Rle.decode_to_slice(&[0, 0xFF], &mut out),
+            Err(CodecError::Corrupt)
+        );
+    }
+
+    #[test]
+    fn rle_output_too_small() {
+        let mut out = [0u8; 4];
+        assert_eq!(
+            Rle.decode_to_slice(&[5, 0xFF], &mut out),
+            Err(CodecError::BufferTooSmall)
+        );
+        assert_eq!(
+            Rle.encode_to_slice(&[1, 2, 3], &mut out),
+            Err(CodecError::BufferTooSmall)
+        );
+    }
+}
@@ -45,6 +45,27 @@ pub enum TriColor {
     Chromatic,
 }
 
+/// Color of the border around the active area of the panel
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BorderColor {
+    /// Driven to black with every refresh
+    Black,
+    /// Driven to white with every refresh
+    #[default]
+    White,
+    /// Not driven, it keeps the color it has
+    Floating,
+}
+
+/// Number of gray levels a display is driven with
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GrayMode {
+    /// Black and white, 1 bit per pixel
+    Gray1,
+    /// Black, dark gray, light gray and white, 2 bits per pixel
+    Gray4,
+}
+
 /// For the 5in65 7 Color Display
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub enum OctColor {
@@ -121,6 +142,80 @@ impl OctColor {
         }
     }
 }
+/// For the 4 color G-series displays, e.g. the 1in64g
+///
+/// Every pixel is stored with 2 bits.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum QuadColor {
+    /// Black Color
+    Black = 0b00,
+    /// White Color
+    White = 0b01,
+    /// Yellow Color
+    Yellow = 0b10,
+    /// Red Color
+    Red = 0b11,
+}
+
+impl From<()> for QuadColor {
+    fn from(_: ()) -> QuadColor {
+        QuadColor::White
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl PixelColor for QuadColor {
+    type Raw = ();
+}
+
+impl QuadColor {
+    /// Gets the 2 bit representation of the Color as needed by the display
+    pub fn get_bits(self) -> u8 {
+        self as u8
+    }
+
+    /// Gets a full byte of 4 pixels with this color
+    pub fn get_byte_value(self) -> u8 {
+        let bits = self.get_bits();
+        bits << 6 | bits << 4 | bits << 2 | bits
+    }
+
+    /// Converts four colors into a single byte for the Display, `a` being the leftmost pixel
+    pub fn colors_byte(a: QuadColor, b: QuadColor, c: QuadColor, d: QuadColor) -> u8 {
+        a.get_bits() << 6 | b.get_bits() << 4 | c.get_bits() << 2 | d.get_bits()
+    }
+
+    /// Take the lower 2 bits and convert them to a QuadColor
+    pub fn from_bits(bits: u8) -> QuadColor {
+        match bits & 0b11 {
+            0b00 => QuadColor::Black,
+            0b01 => QuadColor::White,
+            0b10 => QuadColor::Yellow,
+            _ => QuadColor::Red,
+        }
+    }
+
+    /// Split a single byte into its four colors, leftmost pixel first
+    pub fn split_byte(byte: u8) -> [QuadColor; 4] {
+        [
+            QuadColor::from_bits(byte >> 6),
+            QuadColor::from_bits(byte >> 4),
+            QuadColor::from_bits(byte >> 2),
+            QuadColor::from_bits(byte),
+        ]
+    }
+
+    /// Converts to limited range of RGB values.
+    pub fn rgb(self) -> (u8, u8, u8) {
+        match self {
+            QuadColor::White => (0xff, 0xff, 0xff),
+            QuadColor::Black => (0x00, 0x00, 0x00),
+            QuadColor::Yellow => (0xff, 0xff, 0x00),
+            QuadColor::Red => (0xff, 0x00, 0x00),
+        }
+    }
+}
+
 //TODO: Rename get_bit_value to bit() and get_byte_value to byte() ?
 
 impl Color {
@@ -145,10 +240,14 @@ impl Color {
         match val {
             0 => Color::Black,
             1 => Color::White,
+            #[cfg(not(feature = "panic_free"))]
             e => panic!(
                 "DisplayColor only parses 0 and 1 (Black and White) and not `{}`",
                 e
             ),
+            // like the other bit values only the lowest bit counts
+            #[cfg(feature = "panic_free")]
+            e => Color::from_u8(e & 1),
         }
     }
 
@@ -185,6 +284,18 @@ impl TriColor {
             TriColor::Black | TriColor::Chromatic => 0x00,
         }
     }
+
+    /// Gets the bytes of the b/w and the chromatic part of a
+    /// [`TriDisplay`](crate::graphics::TriDisplay) buffer filled with this color
+    ///
+    /// A set bit is white in both parts, so the chromatic color sets the bits of the b/w part.
+    pub fn get_buffer_bytes(self) -> (u8, u8) {
+        match self {
+            TriColor::White => (0xff, 0xff),
+            TriColor::Black => (0x00, 0xff),
+            TriColor::Chromatic => (0xff, 0x00),
+        }
+    }
 }
 
 #[cfg(feature = "graphics")]
@@ -203,9 +314,10 @@ mod tests {
     }
 
     // test all values aside from 0 and 1 which all should panic
+    #[cfg(not(feature = "panic_free"))]
     #[test]
     fn from_u8_panic() {
-        for val in 2..=u8::max_value() {
+        for val in 2..=u8::MAX {
             extern crate std;
             let result = std::panic::catch_unwind(|| Color::from(val));
             assert!(result.is_err());
@@ -233,4 +345,18 @@ mod tests {
             Ok((left, right))
         );
     }
+
+    #[test]
+    fn test_quad() {
+        let colors = [
+            QuadColor::Black,
+            QuadColor::White,
+            QuadColor::Yellow,
+            QuadColor::Red,
+        ];
+        let byte = QuadColor::colors_byte(colors[0], colors[1], colors[2], colors[3]);
+        assert_eq!(byte, 0b00_01_10_11);
+        assert_eq!(QuadColor::split_byte(byte), colors);
+        assert_eq!(QuadColor::White.get_byte_value(), 0x55);
+    }
 }
@@ -0,0 +1,8 @@
+//! Shared plumbing of the display controllers used by several panels
+//!
+//! The drivers of panels sharing a controller only add their size and init values.
+
+pub(crate) mod ssd1680;
+pub(crate) mod ssd1681;
+pub(crate) mod uc8151;
+pub(crate) mod uc8179;
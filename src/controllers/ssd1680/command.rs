@@ -0,0 +1,103 @@
+//! SPI Commands of the SSD1680 controller
+
+use crate::traits;
+
+/// SSD1680 commands
+///
+/// Should rarely (never?) be needed directly.
+///
+/// For more infos about the addresses and what they are doing look into the pdfs
+#[allow(dead_code)]
+#[derive(Copy, Clone)]
+pub(crate) enum Command {
+    /// Set the number of gate lines, and the scanning sequence and direction
+    DriverOutputControl = 0x01,
+    /// Set the gate driving voltage
+    GateDrivingVoltageControl = 0x03,
+    /// Set the source driving voltages VSH1, VSH2 and VSL
+    SourceDrivingVoltageControl = 0x04,
+    /// Set the driving strength of the booster soft start
+    BoosterSoftStartControl = 0x0C,
+    /// Set the scanning start position of the gate driver
+    GateScanStartPosition = 0x0F,
+    /// Enter deep sleep mode
+    ///
+    /// 0x00 for Normal mode, 0x01 for Deep Sleep Mode 1 (RAM is kept) and
+    /// 0x03 for Deep Sleep Mode 2 (RAM is lost)
+    DeepSleepMode = 0x10,
+    /// Define the data entry sequence (x/y increment/decrement, update direction)
+    DataEntryModeSetting = 0x11,
+    /// Reset all commands and parameters to their default values except for the
+    /// deep sleep mode
+    SwReset = 0x12,
+    /// Select the internal or an external temperature sensor
+    TemperatureSensorSelection = 0x18,
+    /// Write to the temperature register
+    TemperatureSensorControlWrite = 0x1A,
+    /// Read the temperature register
+    TemperatureSensorControlRead = 0x1B,
+    /// Activate the display update sequence set with `DisplayUpdateControl2`
+    ///
+    /// The BUSY pin is high until the update sequence has finished.
+    MasterActivation = 0x20,
+    /// RAM content options for the display update
+    DisplayUpdateControl1 = 0x21,
+    /// Display update sequence option
+    DisplayUpdateControl2 = 0x22,
+    /// Write data into the black/white RAM
+    WriteRam = 0x24,
+    /// Write data into the red RAM, which holds the previous frame for
+    /// partial/gray updates
+    WriteRam2 = 0x26,
+    /// Write the VCOM register
+    WriteVcomRegister = 0x2C,
+    /// Read the display options of the OTP: VCOM, display mode and waveform version
+    /// (11 bytes)
+    ReadOtpDisplayOption = 0x2D,
+    /// Read the status bits: HV ready, VCI detection, busy and the chip ID
+    StatusBitRead = 0x2F,
+    /// Write the waveform LUT register (153 bytes)
+    WriteLutRegister = 0x32,
+    /// Write the OTP selection option, bit 6 of byte 6 selects the mode 2 ping-pong
+    WriteOtpSelection = 0x37,
+    /// Set the waveform end option
+    EndOption = 0x3F,
+    /// Select the border waveform
+    BorderWaveformControl = 0x3C,
+    /// Specify the start/end positions of the window address in the X direction
+    SetRamXAddressStartEndPosition = 0x44,
+    /// Specify the start/end positions of the window address in the Y direction
+    SetRamYAddressStartEndPosition = 0x45,
+    /// Make the initial settings for the RAM X address in the address counter
+    SetRamXAddressCounter = 0x4E,
+    /// Make the initial settings for the RAM Y address in the address counter
+    SetRamYAddressCounter = 0x4F,
+    /// Undocumented analog block control
+    SetAnalogBlockControl = 0x74,
+    /// Undocumented digital block control
+    SetDigitalBlockControl = 0x7E,
+    /// Does nothing, can be used to terminate a frame memory write or read
+    Nop = 0x7F,
+}
+
+impl traits::Command for Command {
+    /// Returns the address of the command
+    fn address(self) -> u8 {
+        self as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Command;
+    use crate::traits::Command as CommandTrait;
+
+    #[test]
+    fn command_addr() {
+        assert_eq!(Command::DriverOutputControl.address(), 0x01);
+        assert_eq!(Command::BoosterSoftStartControl.address(), 0x0C);
+        assert_eq!(Command::SetRamXAddressCounter.address(), 0x4E);
+        assert_eq!(Command::StatusBitRead.address(), 0x2F);
+        assert_eq!(Command::Nop.address(), 0x7F);
+    }
+}
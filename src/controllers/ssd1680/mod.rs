@@ -0,0 +1,290 @@
+//! Shared plumbing of the SSD1680 controller
+//!
+//! The SSD1680 drives the current revisions of the small black/white panels, like the
+//! 2.7" V2 and 2.9" V2 ones. It has a black/white RAM and a second one, which holds the
+//! red pixels or the old frame for the partial and gray refreshes. Both are written
+//! through a window, which the address counter walks in the order of the data entry
+//! mode. The panels only differ in their size, waveforms and update sequences.
+//!
+//! The controllers of the 2.66" B, 2.9" B V4, 4.2" V2 and 4.2" B/C V2 panels share these
+//! commands and use the same plumbing. The SSD1677 of the 4.26" panel doesn't: it addresses
+//! x in pixels with two bytes and counts y down, so it keeps its own window handling.
+//!
+//! BUSY is high while the controller works.
+
+use embedded_hal::{
+    blocking::{
+        delay::*,
+        spi::{Transfer, Write},
+    },
+    digital::v2::{InputPin, OutputPin},
+};
+
+use crate::interface::DisplayInterface;
+use crate::traits::{DeepSleepMode, Revision};
+use crate::Error;
+
+pub(crate) mod command;
+pub(crate) use self::command::Command;
+
+pub(crate) const IS_BUSY_LOW: bool = false;
+
+/// Order in which the address counter walks through the RAM window
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DataEntry {
+    /// x decrement, y decrement
+    DecrementBoth = 0x00,
+    /// x increment, y decrement
+    IncrementX = 0x01,
+    /// x decrement, y increment
+    IncrementY = 0x02,
+    /// x increment, y increment
+    IncrementBoth = 0x03,
+}
+
+/// Data of the [`Command::SetRamXAddressStartEndPosition`] and
+/// [`Command::SetRamYAddressStartEndPosition`]
+///
+/// x is positioned in bytes, so the last 3 bits which show the position inside a byte
+/// in the ram aren't relevant.
+fn window(start_x: u32, start_y: u32, end_x: u32, end_y: u32) -> ([u8; 2], [u8; 4]) {
+    (
+        [(start_x >> 3) as u8, (end_x >> 3) as u8],
+        // 2 Databytes: A[7:0] & 0..A[8] for each - start and end
+        [
+            start_y as u8,
+            (start_y >> 8) as u8,
+            end_y as u8,
+            (end_y >> 8) as u8,
+        ],
+    )
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> DisplayInterface<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    /// Resets all commands and parameters besides the deep sleep mode
+    pub(crate) fn sw_reset_ssd1680(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle(IS_BUSY_LOW);
+        self.cmd(spi, Command::SwReset)?;
+        self.wait_until_idle(IS_BUSY_LOW);
+        Ok(())
+    }
+
+    /// Sets the order in which the data fills the RAM window
+    pub(crate) fn data_entry_ssd1680(
+        &mut self,
+        spi: &mut SPI,
+        mode: DataEntry,
+    ) -> Result<(), Error<SPI::Error>> {
+        // address counter is updated in x direction
+        self.cmd_with_data(spi, Command::DataEntryModeSetting, &[mode as u8])
+    }
+
+    /// Sets the RAM window from `start` to `end`, both included, so a window can be a
+    /// single row or byte column
+    pub(crate) fn window_ssd1680(
+        &mut self,
+        spi: &mut SPI,
+        start_x: u32,
+        start_y: u32,
+        end_x: u32,
+        end_y: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(start_x <= end_x);
+        ensure!(start_y <= end_y);
+        let (x, y) = window(start_x, start_y, end_x, end_y);
+        self.cmd_with_data(spi, Command::SetRamXAddressStartEndPosition, &x)?;
+        self.cmd_with_data(spi, Command::SetRamYAddressStartEndPosition, &y)
+    }
+
+    /// Moves the address counter to `x`, `y`
+    pub(crate) fn cursor_ssd1680(
+        &mut self,
+        spi: &mut SPI,
+        x: u32,
+        y: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle(IS_BUSY_LOW);
+        self.cmd_with_data(spi, Command::SetRamXAddressCounter, &[(x >> 3) as u8])?;
+        // 2 Databytes: A[7:0] & 0..A[8]
+        self.cmd_with_data(
+            spi,
+            Command::SetRamYAddressCounter,
+            &[y as u8, (y >> 8) as u8],
+        )
+    }
+
+    /// Selects the border waveform
+    pub(crate) fn border_ssd1680(
+        &mut self,
+        spi: &mut SPI,
+        border: u8,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.cmd_with_data(spi, Command::BorderWaveformControl, &[border])
+    }
+
+    /// Lets both RAM banks swap their roles after every refresh with display mode 2
+    ///
+    /// Without ping-pong the new frame is always written to `WriteRam` and the old one to
+    /// `WriteRam2`. The setting stays until it is written again or the controller is reset.
+    pub(crate) fn ping_pong_ssd1680(
+        &mut self,
+        spi: &mut SPI,
+        enabled: bool,
+    ) -> Result<(), Error<SPI::Error>> {
+        let ping_pong = if enabled { 0x40 } else { 0x00 };
+        self.cmd_with_data(
+            spi,
+            Command::WriteOtpSelection,
+            &[
+                0x00, 0x00, 0x00, 0x00, 0x00, ping_pong, 0x00, 0x00, 0x00, 0x00,
+            ],
+        )
+    }
+
+    /// Runs the update `sequence` of the [`Command::DisplayUpdateControl2`] and waits
+    /// until it is done
+    pub(crate) fn activate_ssd1680(
+        &mut self,
+        spi: &mut SPI,
+        sequence: u8,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.start_ssd1680(spi, sequence)?;
+        self.wait_until_idle(IS_BUSY_LOW);
+        Ok(())
+    }
+
+    /// Starts the update `sequence` of the [`Command::DisplayUpdateControl2`] without
+    /// waiting for it
+    pub(crate) fn start_ssd1680(
+        &mut self,
+        spi: &mut SPI,
+        sequence: u8,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.cmd_with_data(spi, Command::DisplayUpdateControl2, &[sequence])?;
+        self.cmd(spi, Command::MasterActivation)
+    }
+
+    /// Enters deep sleep once the controller is idle
+    pub(crate) fn sleep_ssd1680(
+        &mut self,
+        spi: &mut SPI,
+        mode: DeepSleepMode,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle(IS_BUSY_LOW);
+        self.cmd_with_data(spi, Command::DeepSleepMode, &[mode as u8])
+    }
+}
+
+/// Whole degrees Celsius of the [`Command::TemperatureSensorControlRead`] response
+///
+/// The register holds a 12 bit two's complement value in 1/16 degrees, so its first byte
+/// is already the temperature rounded down.
+fn temperature(register: [u8; 2]) -> i8 {
+    register[0] as i8
+}
+
+/// [`Revision`] of the [`Command::StatusBitRead`] and [`Command::ReadOtpDisplayOption`]
+/// responses
+///
+/// The chip ID is in the lowest 2 status bits, the waveform version in the last 4 bytes
+/// of the display options.
+fn revision(status: u8, options: [u8; 11]) -> Revision {
+    Revision {
+        chip_id: status & 0x03,
+        lut_version: [options[7], options[8], options[9], options[10]],
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> DisplayInterface<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8> + Transfer<u8, Error = <SPI as Write<u8>>::Error>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    /// Measures the temperature with the internal sensor and reads it back
+    ///
+    /// Only the temperature register is loaded, the waveform in use stays as it is.
+    pub(crate) fn temperature_ssd1680(
+        &mut self,
+        spi: &mut SPI,
+    ) -> Result<i8, Error<<SPI as Write<u8>>::Error>> {
+        self.wait_until_idle(IS_BUSY_LOW);
+        self.cmd_with_data(spi, Command::TemperatureSensorSelection, &[0x80])?;
+        // enable clock, load temperature, disable clock
+        self.activate_ssd1680(spi, 0xA1)?;
+        let mut register = [0; 2];
+        self.cmd_read(spi, Command::TemperatureSensorControlRead, &mut register)?;
+        Ok(temperature(register))
+    }
+
+    /// Reads the chip ID and the version of the waveform in the OTP
+    pub(crate) fn revision_ssd1680(
+        &mut self,
+        spi: &mut SPI,
+    ) -> Result<Revision, Error<<SPI as Write<u8>>::Error>> {
+        self.wait_until_idle(IS_BUSY_LOW);
+        let mut status = [0];
+        self.cmd_read(spi, Command::StatusBitRead, &mut status)?;
+        let mut options = [0; 11];
+        self.cmd_read(spi, Command::ReadOtpDisplayOption, &mut options)?;
+        Ok(revision(status[0], options))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus_log::{data_of, Log, Pin, Spi};
+    use embedded_hal_mock::delay::MockNoop;
+
+    #[test]
+    fn window_bytes() {
+        assert_eq!(window(0, 0, 175, 263), ([0, 21], [0, 0, 7, 1]));
+        // the position inside a byte isn't sent
+        assert_eq!(window(12, 300, 39, 310), ([1, 4], [44, 1, 54, 1]));
+    }
+
+    #[test]
+    fn single_row_and_column_windows() {
+        let log = Log::default();
+        let mut interface: DisplayInterface<_, _, _, _, _, MockNoop> =
+            DisplayInterface::new(Pin(None), Pin(None), Pin(Some(&log)), Pin(None));
+        let mut spi = Spi(&log);
+
+        interface.window_ssd1680(&mut spi, 8, 5, 15, 5).unwrap();
+        interface.window_ssd1680(&mut spi, 8, 5, 8, 20).unwrap();
+        let events = log.take();
+        assert_eq!(data_of(&events, 0x44), [[1, 1], [1, 1]]);
+        assert_eq!(data_of(&events, 0x45), [[5, 0, 5, 0], [5, 0, 20, 0]]);
+    }
+
+    #[test]
+    fn revision_registers() {
+        let options = [0x00, 0x36, 0, 0, 0, 0, 0, 0x12, 0x34, 0x56, 0x78];
+        let revision = revision(0x31, options);
+        // the HV ready and VCI detection flags aren't part of the ID
+        assert_eq!(revision.chip_id, 0x01);
+        assert_eq!(revision.lut_version, [0x12, 0x34, 0x56, 0x78]);
+    }
+
+    #[test]
+    fn temperature_register() {
+        assert_eq!(temperature([0x19, 0x80]), 25);
+        assert_eq!(temperature([0x00, 0x00]), 0);
+        // -0.5 degrees rounds down
+        assert_eq!(temperature([0xFF, 0x80]), -1);
+        assert_eq!(temperature([0xF6, 0x00]), -10);
+    }
+}
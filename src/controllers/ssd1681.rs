@@ -0,0 +1,78 @@
+//! Shared plumbing of the SSD1681 controller
+//!
+//! The SSD1681 drives the 1.54" class panels with up to 200 x 200 pixels, like the
+//! 1.54" V2. It is command compatible with the SSD1680, so the RAM window, data entry,
+//! border, update and deep sleep handling of [`ssd1680`](super::ssd1680) apply as they
+//! are. This adds what the SSD1681 panels set up on top of it: the gate lines and the
+//! waveform with its voltages.
+
+use embedded_hal::{
+    blocking::{delay::*, spi::Write},
+    digital::v2::{InputPin, OutputPin},
+};
+
+use crate::interface::DisplayInterface;
+use crate::Error;
+
+pub(crate) use super::ssd1680::{Command, DataEntry, IS_BUSY_LOW};
+
+/// Length of a waveform with the voltages stored behind it
+pub(crate) const WAVEFORM_LEN: usize = 159;
+
+/// Data of the [`Command::DriverOutputControl`] for `gates` lines, scanned from the last
+/// to the first one (TB) if `reverse`
+fn driver_output(gates: u32, reverse: bool) -> [u8; 3] {
+    let last = gates - 1;
+    [last as u8, (last >> 8) as u8, reverse as u8]
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> DisplayInterface<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    /// Sets the number of gate lines, scanned from the first to the last unless `reverse`
+    pub(crate) fn gates_ssd1681(
+        &mut self,
+        spi: &mut SPI,
+        gates: u32,
+        reverse: bool,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(gates > 0);
+        self.cmd_with_data(
+            spi,
+            Command::DriverOutputControl,
+            &driver_output(gates, reverse),
+        )
+    }
+
+    /// Writes the 153 byte waveform followed by the voltages stored behind it
+    pub(crate) fn waveform_ssd1681(
+        &mut self,
+        spi: &mut SPI,
+        lut: &[u8; WAVEFORM_LEN],
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle(IS_BUSY_LOW);
+        self.cmd_with_data(spi, Command::WriteLutRegister, &lut[..153])?;
+        self.cmd_with_data(spi, Command::EndOption, &lut[153..154])?;
+        self.cmd_with_data(spi, Command::GateDrivingVoltageControl, &lut[154..155])?;
+        self.cmd_with_data(spi, Command::SourceDrivingVoltageControl, &lut[155..158])?;
+        self.cmd_with_data(spi, Command::WriteVcomRegister, &lut[158..159])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn driver_output_bytes() {
+        assert_eq!(driver_output(200, false), [199, 0, 0]);
+        assert_eq!(driver_output(296, false), [39, 1, 0]);
+        assert_eq!(driver_output(296, true), [39, 1, 1]);
+    }
+}
@@ -1,4 +1,4 @@
-//! SPI Commands for the Waveshare 1.54" C yellow E-Ink Display
+//! SPI Commands of the UC8151 controller and its IL0373 clone
 use crate::traits;
 
 #[allow(dead_code)]
@@ -14,6 +14,8 @@ pub(crate) enum Command {
     DataStartTransmission1 = 0x10,
     DisplayRefresh = 0x12,
     DataStartTransmission2 = 0x13,
+    /// Runs power on, refresh and power off in one go on the UC8253
+    AutoSequence = 0x17,
 
     LutForVcom = 0x20,
     LutWhiteToWhite = 0x21,
@@ -25,8 +27,13 @@ pub(crate) enum Command {
     TemperatureSensor = 0x40,
     TemperatureSensorSelection = 0x41,
     VcomAndDataIntervalSetting = 0x50,
+    TconSetting = 0x60,
     ResolutionSetting = 0x61,
     VcmDcSetting = 0x82,
+
+    PartialWindow = 0x90,
+    PartialIn = 0x91,
+    PartialOut = 0x92,
     PowerSaving = 0xE3,
 }
 
@@ -0,0 +1,239 @@
+//! Shared plumbing of the UC8151 controller
+//!
+//! The UC8151 (and the IL0373 clone) drives many of the small panels, like the 1.54" C,
+//! 2.13" B/C and 2.9" B/C ones. It keeps a black/white and a chromatic frame, which are
+//! written with [`Command::DataStartTransmission1`] and
+//! [`Command::DataStartTransmission2`]. On the black/white only panels the first one
+//! holds the old frame for the partial refresh instead. The panels only differ in their
+//! size, panel setting and border. Without the lookup tables written with
+//! [`Command::LutForVcom`] and the following ones, the controller refreshes with the
+//! waveform from its OTP memory.
+//!
+//! BUSY is low while the controller works.
+
+use embedded_hal::{
+    blocking::{delay::*, spi::Write},
+    digital::v2::{InputPin, OutputPin},
+};
+
+use crate::interface::DisplayInterface;
+use crate::Error;
+
+pub(crate) mod command;
+pub(crate) use self::command::Command;
+
+pub(crate) const IS_BUSY_LOW: bool = true;
+
+/// Border values of the [`Command::VcomAndDataIntervalSetting`], to be ORed with
+/// the data interval
+pub(crate) const WHITE_BORDER: u8 = 0x70;
+pub(crate) const BLACK_BORDER: u8 = 0x30;
+pub(crate) const CHROMATIC_BORDER: u8 = 0xB0;
+pub(crate) const FLOATING_BORDER: u8 = 0xF0;
+
+/// Frame memories of the controller
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Frame {
+    /// Black/white pixels, or the old frame on black/white only panels
+    Black,
+    /// Chromatic pixels, or the new frame on black/white only panels
+    Chromatic,
+}
+
+impl Frame {
+    fn command(self) -> Command {
+        match self {
+            Frame::Black => Command::DataStartTransmission1,
+            Frame::Chromatic => Command::DataStartTransmission2,
+        }
+    }
+}
+
+/// Lowest and highest VCOM of the [`Command::VcmDcSetting`] in mV, without the sign
+pub(crate) const VCOM_RANGE_MV: core::ops::RangeInclusive<u16> = 100..=3000;
+
+/// Data of the [`Command::VcmDcSetting`] for a VCOM of minus `millivolts`
+///
+/// The register counts in steps of 50mV from -0.1V on, the UC8176 and UC8179 use the same
+/// encoding. Values in between are rounded to the nearest step.
+pub(crate) fn vcm_dc(millivolts: u16) -> u8 {
+    let millivolts = millivolts.clamp(*VCOM_RANGE_MV.start(), *VCOM_RANGE_MV.end());
+    ((millivolts - VCOM_RANGE_MV.start() + 25) / 50) as u8
+}
+
+/// Data of the [`Command::ResolutionSetting`]
+///
+/// The lowest 3 bits of the width are ignored by the controller.
+fn resolution(width: u32, height: u32) -> [u8; 3] {
+    [
+        (width as u8) & 0b1111_1000,
+        (height >> 8) as u8,
+        height as u8,
+    ]
+}
+
+/// Data of the [`Command::PartialWindow`]
+///
+/// The lowest 3 bits of the horizontal start and end are ignored, the end is included.
+/// The last byte scans the gates inside of the window only.
+fn partial_window(x: u32, y: u32, width: u32, height: u32) -> [u8; 7] {
+    let (x_end, y_end) = (x + width - 1, y + height - 1);
+    [
+        (x as u8) & 0b1111_1000,
+        (x_end as u8) | 0b0000_0111,
+        (y >> 8) as u8,
+        y as u8,
+        (y_end >> 8) as u8,
+        y_end as u8,
+        0x01,
+    ]
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> DisplayInterface<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    /// Starts the booster with `soft_start` and powers the panel on
+    pub(crate) fn power_on_uc8151(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        soft_start: [u8; 3],
+    ) -> Result<(), Error<SPI::Error>> {
+        self.cmd_with_data(spi, Command::BoosterSoftStart, &soft_start)?;
+        self.cmd(spi, Command::PowerOn)?;
+        delay.delay_ms(5);
+        self.wait_until_idle(IS_BUSY_LOW);
+        Ok(())
+    }
+
+    /// Sets the resolution of the panel
+    pub(crate) fn resolution_uc8151(
+        &mut self,
+        spi: &mut SPI,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        // Specification shows C/D is zero while sending the last byte,
+        // but upstream code does not implement it like that. So for now
+        // we follow upstream code.
+        self.cmd_with_data(spi, Command::ResolutionSetting, &resolution(width, height))
+    }
+
+    /// Sets the border and the interval between the data and the VCOM
+    pub(crate) fn border_uc8151(
+        &mut self,
+        spi: &mut SPI,
+        border: u8,
+        interval: u8,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.cmd_with_data(
+            spi,
+            Command::VcomAndDataIntervalSetting,
+            &[border | interval],
+        )
+    }
+
+    /// Writes `buffer` to `frame`
+    pub(crate) fn frame_uc8151(
+        &mut self,
+        spi: &mut SPI,
+        frame: Frame,
+        buffer: &[u8],
+    ) -> Result<(), Error<SPI::Error>> {
+        self.cmd_with_data(spi, frame.command(), buffer)
+    }
+
+    /// Writes `buffer` to the `width` x `height` window at `x`, `y` of `frame`
+    ///
+    /// The rest of the frame keeps its content. `x` and `width` have to be multiples of 8.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn partial_frame_uc8151(
+        &mut self,
+        spi: &mut SPI,
+        frame: Frame,
+        buffer: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(x.is_multiple_of(8) && width.is_multiple_of(8));
+        ensure!(width > 0 && height > 0);
+        ensure!(buffer.len() == (width / 8 * height) as usize);
+        self.cmd(spi, Command::PartialIn)?;
+        self.cmd_with_data(
+            spi,
+            Command::PartialWindow,
+            &partial_window(x, y, width, height),
+        )?;
+        self.cmd_with_data(spi, frame.command(), buffer)?;
+        self.cmd(spi, Command::PartialOut)
+    }
+
+    /// Fills `frame` with `num_bytes` times `byte`
+    pub(crate) fn fill_uc8151(
+        &mut self,
+        spi: &mut SPI,
+        frame: Frame,
+        byte: u8,
+        num_bytes: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.cmd(spi, frame.command())?;
+        self.data_x_times(spi, byte, num_bytes)
+    }
+
+    /// Refreshes the panel with the frames and waits until it is done
+    pub(crate) fn refresh_uc8151(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        self.start_refresh_uc8151(spi)?;
+        self.wait_until_idle(IS_BUSY_LOW);
+        Ok(())
+    }
+
+    /// Starts refreshing the panel with the frames without waiting for it
+    pub(crate) fn start_refresh_uc8151(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        self.cmd(spi, Command::DisplayRefresh)
+    }
+
+    /// Powers the panel off and enters deep sleep
+    pub(crate) fn sleep_uc8151(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        self.cmd(spi, Command::PowerOff)?;
+        self.wait_until_idle(IS_BUSY_LOW);
+        self.cmd_with_data(spi, Command::DeepSleep, &[0xA5])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolution_bytes() {
+        assert_eq!(resolution(104, 212), [104, 0, 212]);
+        assert_eq!(resolution(128, 296), [128, 1, 40]);
+        // the controller ignores the lowest bits
+        assert_eq!(resolution(155, 152), [152, 0, 152]);
+    }
+
+    #[test]
+    fn partial_window_bytes() {
+        assert_eq!(partial_window(0, 0, 104, 212), [0, 103, 0, 0, 0, 211, 1]);
+        assert_eq!(partial_window(16, 250, 32, 20), [16, 47, 0, 250, 1, 13, 1]);
+    }
+
+    #[test]
+    fn vcm_dc_steps() {
+        assert_eq!(vcm_dc(100), 0x00);
+        assert_eq!(vcm_dc(600), 0x0A);
+        assert_eq!(vcm_dc(1000), 0x12);
+        // rounded to the nearest step
+        assert_eq!(vcm_dc(1530), 0x1D);
+        assert_eq!(vcm_dc(3000), 0x3A);
+        assert_eq!(vcm_dc(5000), 0x3A);
+    }
+}
@@ -0,0 +1,237 @@
+//! Shared plumbing of the UC8179 controller
+//!
+//! The UC8179 drives the 800x480 panels of the 7.5" family, like the 7.5" V2 and the
+//! 7.5" (B) V2 ones, and the quadrants of the 12.48". It keeps a black/white and a
+//! chromatic frame, which are written with [`Command::DataStartTransmission1`] and
+//! [`Command::DataStartTransmission2`]. On the black/white only panels the first one
+//! holds the old frame instead. The panels only differ in their resolution, power
+//! setting and panel setting, and whether they use the chromatic frame.
+//!
+//! BUSY is low while the controller works.
+
+use embedded_hal::{
+    blocking::{delay::*, spi::Write},
+    digital::v2::{InputPin, OutputPin},
+};
+
+use crate::interface::DisplayInterface;
+use crate::Error;
+
+pub(crate) mod command;
+pub(crate) use self::command::Command;
+
+pub(crate) const IS_BUSY_LOW: bool = true;
+
+/// Modes of the [`Command::DualSpi`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DualSpi {
+    /// A single data line, the MM pin is unused
+    Disabled = 0x00,
+    /// The data is sent on two lines at once
+    Enabled = 0x10,
+    /// The MM pin synchronizes the refresh of cascaded controllers
+    MmInput = 0x20,
+}
+
+/// Frame memories of the controller
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Frame {
+    /// Black/white pixels, or the old frame on black/white only panels
+    Black,
+    /// Chromatic pixels, or the new frame on black/white only panels
+    Chromatic,
+}
+
+impl Frame {
+    fn command(self) -> Command {
+        match self {
+            Frame::Black => Command::DataStartTransmission1,
+            Frame::Chromatic => Command::DataStartTransmission2,
+        }
+    }
+}
+
+/// Data of the [`Command::TconResolution`]
+///
+/// Unlike the smaller controllers, the width and the height both take two bytes.
+pub(crate) fn resolution(width: u32, height: u32) -> [u8; 4] {
+    [
+        (width >> 8) as u8,
+        width as u8,
+        (height >> 8) as u8,
+        height as u8,
+    ]
+}
+
+/// Data of the [`Command::PartialWindow`]
+///
+/// The horizontal bounds are rounded to bytes, and the gates scan the whole panel.
+pub(crate) fn partial_window(x: u32, y: u32, width: u32, height: u32) -> [u8; 9] {
+    let x_end = (x + width - 1) | 0x07;
+    let y_end = y + height - 1;
+    [
+        (x >> 8) as u8,
+        (x & 0xF8) as u8,
+        (x_end >> 8) as u8,
+        x_end as u8,
+        (y >> 8) as u8,
+        y as u8,
+        (y_end >> 8) as u8,
+        y_end as u8,
+        0x01,
+    ]
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> DisplayInterface<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    /// Sets the supply voltages and the booster soft start and powers the panel on
+    ///
+    /// The panel is ready once the controller is idle again.
+    pub(crate) fn power_on_uc8179(
+        &mut self,
+        spi: &mut SPI,
+        power: [u8; 4],
+        soft_start: [u8; 4],
+    ) -> Result<(), Error<SPI::Error>> {
+        self.cmd_with_data(spi, Command::BoosterSoftStart, &soft_start)?;
+        self.cmd_with_data(spi, Command::PowerSetting, &power)?;
+        self.cmd(spi, Command::PowerOn)
+    }
+
+    /// Waits until the controller is idle, polling its status every 20ms
+    pub(crate) fn wait_uc8179(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        while self.is_busy(IS_BUSY_LOW) {
+            self.cmd(spi, Command::GetStatus)?;
+            delay.delay_ms(20);
+        }
+        Ok(())
+    }
+
+    /// Sets the resolution of the panel
+    pub(crate) fn resolution_uc8179(
+        &mut self,
+        spi: &mut SPI,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.cmd_with_data(spi, Command::TconResolution, &resolution(width, height))
+    }
+
+    /// Selects how the data is sent to the controller
+    pub(crate) fn dual_spi_uc8179(
+        &mut self,
+        spi: &mut SPI,
+        mode: DualSpi,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.cmd_with_data(spi, Command::DualSpi, &[mode as u8])
+    }
+
+    /// Enters partial mode, limiting the writes and the refresh to a window
+    pub(crate) fn partial_in_uc8179(
+        &mut self,
+        spi: &mut SPI,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.cmd(spi, Command::PartialIn)?;
+        self.cmd_with_data(
+            spi,
+            Command::PartialWindow,
+            &partial_window(x, y, width, height),
+        )
+    }
+
+    /// Writes `buffer` to `frame`
+    pub(crate) fn frame_uc8179(
+        &mut self,
+        spi: &mut SPI,
+        frame: Frame,
+        buffer: &[u8],
+    ) -> Result<(), Error<SPI::Error>> {
+        self.cmd_with_data(spi, frame.command(), buffer)
+    }
+
+    /// Writes `buffer` to `frame`, the command over `spi` and the data over `dual`
+    ///
+    /// In [`DualSpi::Enabled`] only the frame data is sent on two data lines.
+    pub(crate) fn frame_dual_uc8179<D>(
+        &mut self,
+        spi: &mut SPI,
+        dual: &mut D,
+        frame: Frame,
+        buffer: &[u8],
+    ) -> Result<(), Error<SPI::Error>>
+    where
+        D: Write<u8, Error = SPI::Error>,
+    {
+        self.cmd(spi, frame.command())?;
+        self.data_over(dual, buffer)
+    }
+
+    /// Writes `buffer` to `frame`, with every bit flipped
+    pub(crate) fn frame_inverted_uc8179(
+        &mut self,
+        spi: &mut SPI,
+        frame: Frame,
+        buffer: &[u8],
+    ) -> Result<(), Error<SPI::Error>> {
+        self.cmd(spi, frame.command())?;
+        self.data_inverted(spi, buffer)
+    }
+
+    /// Fills `frame` with `num_bytes` times `byte`
+    pub(crate) fn fill_uc8179(
+        &mut self,
+        spi: &mut SPI,
+        frame: Frame,
+        byte: u8,
+        num_bytes: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.cmd(spi, frame.command())?;
+        self.data_x_times(spi, byte, num_bytes)
+    }
+
+    /// Powers the panel off and enters deep sleep
+    pub(crate) fn sleep_uc8179(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        self.cmd(spi, Command::PowerOff)?;
+        self.wait_until_idle(IS_BUSY_LOW);
+        self.cmd_with_data(spi, Command::DeepSleep, &[0xA5])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolution_bytes() {
+        assert_eq!(resolution(800, 480), [0x03, 0x20, 0x01, 0xE0]);
+        assert_eq!(resolution(648, 492), [0x02, 0x88, 0x01, 0xEC]);
+    }
+
+    #[test]
+    fn partial_window_bytes() {
+        assert_eq!(
+            partial_window(0, 0, 800, 480),
+            [0x00, 0x00, 0x03, 0x1F, 0x00, 0x00, 0x01, 0xDF, 0x01]
+        );
+        // unaligned bounds are rounded out to whole bytes
+        assert_eq!(
+            partial_window(260, 300, 10, 20),
+            [0x01, 0x00, 0x01, 0x0F, 0x01, 0x2C, 0x01, 0x3F, 0x01]
+        );
+    }
+}
@@ -0,0 +1,125 @@
+//! Glitch filtering for the BUSY line
+//!
+//! Long or unshielded cables can pick up enough noise for the BUSY pin to toggle
+//! for a short moment while the display is still refreshing. The drivers would then
+//! stop waiting too early and send the next commands into a half-refreshed frame.
+//!
+//! Wrap the BUSY pin in a [`DebouncedPin`] to only accept a level after it was read
+//! a given number of times in a row:
+//!
+//!```rust, no_run
+//!# use embedded_hal_mock::*;
+//!# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
+//!use epd_waveshare::{debounce::DebouncedPin, epd4in2::*, prelude::*};
+//!#
+//!# let expectations = [];
+//!# let mut spi = spi::Mock::new(&expectations);
+//!# let expectations = [];
+//!# let cs_pin = pin::Mock::new(&expectations);
+//!# let busy_in = pin::Mock::new(&expectations);
+//!# let dc = pin::Mock::new(&expectations);
+//!# let rst = pin::Mock::new(&expectations);
+//!# let mut delay = delay::MockNoop::new();
+//!
+//!// Require 5 identical reads before the busy state is trusted
+//!let busy_in = DebouncedPin::new(busy_in, 5);
+//!
+//!let mut epd = Epd4in2::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
+//!# Ok(())
+//!# }
+//!```
+
+use embedded_hal::digital::v2::InputPin;
+
+/// An [`InputPin`] which only reports a level after it was read
+/// `reads` times in a row.
+///
+/// Every call to `is_high`/`is_low` keeps sampling the pin until enough
+/// consecutive identical reads were made, so a single spike on the line can't
+/// end a `wait_until_idle` early.
+pub struct DebouncedPin<P> {
+    pin: P,
+    reads: u8,
+}
+
+impl<P> DebouncedPin<P>
+where
+    P: InputPin,
+{
+    /// Wraps `pin` and requires `reads` consecutive identical reads.
+    ///
+    /// A value of 0 or 1 disables the filtering.
+    pub fn new(pin: P, reads: u8) -> Self {
+        DebouncedPin { pin, reads }
+    }
+
+    /// Number of consecutive identical reads required
+    pub fn reads(&self) -> u8 {
+        self.reads
+    }
+
+    /// Releases the wrapped pin
+    pub fn release(self) -> P {
+        self.pin
+    }
+
+    fn read_stable(&self) -> Result<bool, P::Error> {
+        let mut level = self.pin.is_high()?;
+        let mut count = 1;
+        while count < self.reads {
+            let next = self.pin.is_high()?;
+            if next == level {
+                count += 1;
+            } else {
+                level = next;
+                count = 1;
+            }
+        }
+        Ok(level)
+    }
+}
+
+impl<P> InputPin for DebouncedPin<P>
+where
+    P: InputPin,
+{
+    type Error = P::Error;
+
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        self.read_stable()
+    }
+
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        self.read_stable().map(|high| !high)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_hal_mock::pin::{Mock, State, Transaction};
+
+    #[test]
+    fn single_glitch_is_ignored() {
+        let expectations = [
+            Transaction::get(State::High),
+            Transaction::get(State::Low),
+            Transaction::get(State::High),
+            Transaction::get(State::High),
+            Transaction::get(State::High),
+        ];
+        let pin = DebouncedPin::new(Mock::new(&expectations), 3);
+
+        assert!(pin.is_high().unwrap());
+        pin.release().done();
+    }
+
+    #[test]
+    fn no_filtering() {
+        let expectations = [Transaction::get(State::Low)];
+        let pin = DebouncedPin::new(Mock::new(&expectations), 1);
+
+        assert!(pin.is_low().unwrap());
+        pin.release().done();
+    }
+}
@@ -0,0 +1,459 @@
+//! Drawing static content straight to the RAM of the display, without any buffer
+//!
+//! Boxes, lines and pre-packed glyphs are all the content of many small devices, and
+//! they don't need a frame buffer: a [`DirectDisplay`] turns filled rectangles,
+//! horizontal and vertical lines and packed images into windowed RAM writes, e.g. a
+//! closure calling `update_partial_frame`. Only a small chunk of bytes on the stack is
+//! used, and the bytes of packed images are handed to the sink as they are.
+//!
+//! The RAM is written in whole bytes of 8 pixels, so a window always starts at a multiple
+//! of 8. The pixels of the edge bytes which aren't covered by a rectangle or a line get
+//! the `background` color, like everything else which is never drawn. Packed images have
+//! to start at a multiple of 8, and return [`DirectError::Unaligned`] otherwise. The
+//! content is drawn without rotation, and drawing something again replaces the old
+//! content of its bytes instead of drawing on top of it.
+//!
+//!```rust, no_run
+//!# use embedded_hal_mock::*;
+//!# fn main() -> Result<(), epd_waveshare::direct::DirectError<epd_waveshare::Error<MockError>>> {
+//!use embedded_graphics::{
+//!    pixelcolor::BinaryColor::On as Black,
+//!    prelude::*,
+//!    primitives::{Line, PrimitiveStyle, Rectangle},
+//!};
+//!use epd_waveshare::{color::Color, direct::DirectDisplay, epd4in2::*, prelude::*};
+//!#
+//!# let expectations = [];
+//!# let mut spi = spi::Mock::new(&expectations);
+//!# let expectations = [];
+//!# let cs_pin = pin::Mock::new(&expectations);
+//!# let busy_in = pin::Mock::new(&expectations);
+//!# let dc = pin::Mock::new(&expectations);
+//!# let rst = pin::Mock::new(&expectations);
+//!# let mut delay = delay::MockNoop::new();
+//!
+//!let mut epd = Epd4in2::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
+//!epd.clear_frame(&mut spi, &mut delay)?;
+//!
+//!let mut display = DirectDisplay::new(WIDTH, HEIGHT, Color::White, |x, y, width, height, bytes| {
+//!    epd.update_partial_frame(&mut spi, bytes, x, y, width, height)
+//!});
+//!
+//!display.draw_rect(
+//!    &Rectangle::new(Point::new(8, 8), Size::new(384, 284))
+//!        .into_styled(PrimitiveStyle::with_stroke(Black, 2)),
+//!)?;
+//!display.draw_line(
+//!    &Line::new(Point::new(8, 150), Point::new(391, 150))
+//!        .into_styled(PrimitiveStyle::with_stroke(Black, 1)),
+//!)?;
+//!// a 16x2 glyph, packed like the display buffers
+//!display.draw_packed(Point::new(16, 16), 16, &[0x00, 0x00, 0x0F, 0xF0])?;
+//!
+//!epd.display_frame(&mut spi, &mut delay)?;
+//!# Ok(())
+//!# }
+//!```
+
+use crate::color::Color;
+use embedded_graphics::{
+    pixelcolor::BinaryColor,
+    primitives::{Line, PrimitiveStyle, Rectangle, Styled},
+};
+use embedded_graphics_core::prelude::*;
+
+/// Bytes on the stack which are filled and handed to the sink at once
+const CHUNK_BYTES: usize = 64;
+
+/// Errors returned while drawing to a [`DirectDisplay`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DirectError<E> {
+    /// The primitive isn't a filled rectangle or a horizontal or vertical line
+    Unsupported,
+    /// The packed image doesn't start at a multiple of 8
+    Unaligned,
+    /// The packed image doesn't fit completely on the display
+    OutOfBounds,
+    /// The sink returned an error
+    Sink(E),
+}
+
+impl<E> From<E> for DirectError<E> {
+    fn from(error: E) -> Self {
+        DirectError::Sink(error)
+    }
+}
+
+/// Draws primitives as windowed RAM writes, without a frame buffer
+///
+/// `sink` is called with the x and y position, the width and the height of every window
+/// and its bytes, which are always made of full rows.
+pub struct DirectDisplay<F> {
+    width: u32,
+    height: u32,
+    background: Color,
+    sink: F,
+}
+
+impl<F, E> DirectDisplay<F>
+where
+    F: FnMut(u32, u32, u32, u32, &[u8]) -> Result<(), E>,
+{
+    /// Creates a display of `width` x `height` pixels writing to `sink`
+    ///
+    /// The pixels of the written bytes which aren't covered by the primitives get the
+    /// `background` color.
+    pub fn new(width: u32, height: u32, background: Color, sink: F) -> Self {
+        DirectDisplay {
+            width,
+            height,
+            background,
+            sink,
+        }
+    }
+
+    /// Fills `area` with `color`
+    pub fn fill_rect(&mut self, area: Rectangle, color: BinaryColor) -> Result<(), DirectError<E>> {
+        let Rectangle { top_left, size } = area;
+        self.fill_rows(
+            top_left.y,
+            top_left.y + size.height as i32,
+            &[(top_left.x, top_left.x + size.width as i32, color)],
+        )
+    }
+
+    /// Draws a horizontal or vertical line with its stroke width
+    ///
+    /// Other lines return [`DirectError::Unsupported`].
+    pub fn draw_line(
+        &mut self,
+        line: &Styled<Line, PrimitiveStyle<BinaryColor>>,
+    ) -> Result<(), DirectError<E>> {
+        let Line { start, end } = line.primitive;
+        if start.x != end.x && start.y != end.y {
+            return Err(DirectError::Unsupported);
+        }
+        match line.style.stroke_color {
+            Some(color) if line.style.stroke_width > 0 => {
+                self.fill_rect(line.bounding_box(), color)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Draws a rectangle with its fill and its stroke
+    ///
+    /// Every row is written once, so the stroke doesn't wipe out the fill in the bytes
+    /// they share. Rounded corners aren't supported, since a [`Rectangle`] has none.
+    pub fn draw_rect(
+        &mut self,
+        rect: &Styled<Rectangle, PrimitiveStyle<BinaryColor>>,
+    ) -> Result<(), DirectError<E>> {
+        let Rectangle { top_left, size } = rect.bounding_box();
+        let (x0, y0) = (top_left.x, top_left.y);
+        let (x1, y1) = (x0 + size.width as i32, y0 + size.height as i32);
+        let fill = rect.style.fill_color;
+        let (stroke, width) = match rect.style.stroke_color {
+            Some(color) if rect.style.stroke_width > 0 => (color, rect.style.stroke_width as i32),
+            _ => {
+                return match fill {
+                    Some(color) => self.fill_rows(y0, y1, &[(x0, x1, color)]),
+                    None => Ok(()),
+                }
+            }
+        };
+
+        // the stroke rows at the top and the bottom, which overlap on short rectangles
+        let top_end = (y0 + width).min(y1);
+        let bottom_start = (y1 - width).max(top_end);
+        self.fill_rows(y0, top_end, &[(x0, x1, stroke)])?;
+        self.fill_rows(bottom_start, y1, &[(x0, x1, stroke)])?;
+
+        let left_end = (x0 + width).min(x1);
+        let right_start = (x1 - width).max(left_end);
+        // without a fill the inside keeps the background
+        let inside = fill.map_or((0, 0, stroke), |color| (left_end, right_start, color));
+        self.fill_rows(
+            top_end,
+            bottom_start,
+            &[(x0, left_end, stroke), inside, (right_start, x1, stroke)],
+        )
+    }
+
+    /// Writes a packed image of `width` pixels at `top_left`
+    ///
+    /// `bytes` is packed like the display buffers: rows of `(width + 7) / 8` bytes with a
+    /// set bit being white. It's handed to the sink as it is, a trailing partial row is
+    /// left out.
+    pub fn draw_packed(
+        &mut self,
+        top_left: Point,
+        width: u32,
+        bytes: &[u8],
+    ) -> Result<(), DirectError<E>> {
+        let row_bytes = width.div_ceil(8) as usize;
+        if row_bytes == 0 {
+            return Ok(());
+        }
+        let height = (bytes.len() / row_bytes) as u32;
+        if top_left.x < 0 || top_left.y < 0 {
+            return Err(DirectError::OutOfBounds);
+        }
+        let (x, y) = (top_left.x as u32, top_left.y as u32);
+        if x % 8 != 0 {
+            return Err(DirectError::Unaligned);
+        }
+        if x + width > self.width || y + height > self.height {
+            return Err(DirectError::OutOfBounds);
+        }
+        (self.sink)(x, y, width, height, &bytes[..height as usize * row_bytes])?;
+        Ok(())
+    }
+
+    /// Writes the rows from `y0` to `y1`, each made of the same `spans` of pixels
+    ///
+    /// A span goes from its first x to its second one, the pixels between the spans keep
+    /// the background color.
+    fn fill_rows(
+        &mut self,
+        y0: i32,
+        y1: i32,
+        spans: &[(i32, i32, BinaryColor)],
+    ) -> Result<(), DirectError<E>> {
+        let (y0, y1) = (y0.max(0) as u32, y1.clamp(0, self.height as i32) as u32);
+        let visible = |&&(start, end, _): &&(i32, i32, BinaryColor)| {
+            start.max(0) < end.min(self.width as i32)
+        };
+        let x0 = spans.iter().filter(visible).map(|span| span.0.max(0)).min();
+        let x1 = spans
+            .iter()
+            .filter(visible)
+            .map(|span| span.1.min(self.width as i32))
+            .max();
+        let (x0, x1) = match (x0, x1) {
+            (Some(x0), Some(x1)) if y0 < y1 => (x0 as u32, x1 as u32),
+            _ => return Ok(()),
+        };
+
+        let end_col = (x1 - 1) / 8 + 1;
+        let mut col = x0 / 8;
+        let mut chunk = [0u8; CHUNK_BYTES];
+        while col < end_col {
+            let cols = (end_col - col).min(CHUNK_BYTES as u32) as usize;
+            for (i, byte) in chunk[..cols].iter_mut().enumerate() {
+                *byte = self.byte(col + i as u32, spans);
+            }
+            // every row of the window is the same, so the chunk holds as many as fit
+            let chunk_rows = CHUNK_BYTES / cols;
+            for row in 1..chunk_rows {
+                chunk.copy_within(..cols, row * cols);
+            }
+
+            let x = col * 8;
+            let width = (cols as u32 * 8).min(self.width - x);
+            let mut y = y0;
+            while y < y1 {
+                let rows = (chunk_rows as u32).min(y1 - y);
+                (self.sink)(x, y, width, rows, &chunk[..rows as usize * cols])?;
+                y += rows;
+            }
+            col += cols as u32;
+        }
+        Ok(())
+    }
+
+    /// Byte `col` of a row made of `spans`
+    fn byte(&self, col: u32, spans: &[(i32, i32, BinaryColor)]) -> u8 {
+        let mut byte = self.background.get_byte_value();
+        for bit in 0..8 {
+            let x = (col * 8 + bit) as i32;
+            let mask = 0x80 >> bit;
+            match spans.iter().find(|&&(start, end, _)| x >= start && x < end) {
+                // Black
+                Some((_, _, BinaryColor::On)) => byte &= !mask,
+                // White
+                Some((_, _, BinaryColor::Off)) => byte |= mask,
+                None => {}
+            }
+        }
+        byte
+    }
+}
+
+impl<F> OriginDimensions for DirectDisplay<F> {
+    fn size(&self) -> Size {
+        Size::new(self.width, self.height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Black;
+    use embedded_graphics::prelude::Primitive;
+    use embedded_graphics::primitives::{PrimitiveStyleBuilder, StrokeAlignment};
+    extern crate std;
+    use std::vec::Vec;
+
+    type Window = (u32, u32, u32, u32, Vec<u8>);
+
+    fn record(
+        sent: &mut Vec<Window>,
+    ) -> impl FnMut(u32, u32, u32, u32, &[u8]) -> Result<(), ()> + '_ {
+        move |x, y, width, height, bytes| {
+            sent.push((x, y, width, height, bytes.to_vec()));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn fill_rect_rounds_to_bytes() {
+        let mut sent = Vec::new();
+        let mut display = DirectDisplay::new(32, 8, Color::White, record(&mut sent));
+        display
+            .fill_rect(Rectangle::new(Point::new(4, 1), Size::new(8, 2)), Black)
+            .unwrap();
+
+        drop(display);
+
+        assert_eq!(sent, [(0, 1, 16, 2, std::vec![0xF0, 0x0F, 0xF0, 0x0F])]);
+    }
+
+    #[test]
+    fn fill_rect_in_chunks() {
+        let mut sent = Vec::new();
+        let mut display = DirectDisplay::new(16, 100, Color::Black, record(&mut sent));
+        display
+            .fill_rect(
+                Rectangle::new(Point::new(0, 0), Size::new(16, 40)),
+                BinaryColor::Off,
+            )
+            .unwrap();
+
+        drop(display);
+        // 32 rows of 2 bytes fit into a chunk
+        assert_eq!(sent[0], (0, 0, 16, 32, std::vec![0xFF; 64]));
+        assert_eq!(sent[1], (0, 32, 16, 8, std::vec![0xFF; 16]));
+    }
+
+    #[test]
+    fn fill_rect_is_clipped() {
+        let mut sent = Vec::new();
+        let mut display = DirectDisplay::new(16, 4, Color::White, record(&mut sent));
+        display
+            .fill_rect(Rectangle::new(Point::new(-4, 2), Size::new(40, 40)), Black)
+            .unwrap();
+        display
+            .fill_rect(Rectangle::new(Point::new(20, 0), Size::new(4, 4)), Black)
+            .unwrap();
+
+        drop(display);
+
+        assert_eq!(sent, [(0, 2, 16, 2, std::vec![0x00; 4])]);
+    }
+
+    #[test]
+    fn lines() {
+        let mut sent = Vec::new();
+        let mut display = DirectDisplay::new(16, 16, Color::White, record(&mut sent));
+        let style = PrimitiveStyle::with_stroke(Black, 1);
+        display
+            .draw_line(&Line::new(Point::new(9, 2), Point::new(9, 4)).into_styled(style))
+            .unwrap();
+        display
+            .draw_line(&Line::new(Point::new(0, 5), Point::new(15, 5)).into_styled(style))
+            .unwrap();
+        assert_eq!(
+            display.draw_line(&Line::new(Point::new(0, 0), Point::new(3, 3)).into_styled(style)),
+            Err(DirectError::Unsupported)
+        );
+
+        drop(display);
+
+        assert_eq!(
+            sent,
+            [
+                (8, 2, 8, 3, std::vec![0xBF; 3]),
+                (0, 5, 16, 1, std::vec![0x00, 0x00]),
+            ]
+        );
+    }
+
+    #[test]
+    fn rect_stroke_and_fill() {
+        let mut sent = Vec::new();
+        let mut display = DirectDisplay::new(8, 8, Color::White, record(&mut sent));
+        let style = PrimitiveStyleBuilder::new()
+            .stroke_color(Black)
+            .stroke_width(1)
+            .stroke_alignment(StrokeAlignment::Inside)
+            .fill_color(BinaryColor::Off)
+            .build();
+        display
+            .draw_rect(&Rectangle::new(Point::new(0, 0), Size::new(4, 3)).into_styled(style))
+            .unwrap();
+
+        drop(display);
+
+        assert_eq!(
+            sent,
+            [
+                // top and bottom stroke
+                (0, 0, 8, 1, std::vec![0x0F]),
+                (0, 2, 8, 1, std::vec![0x0F]),
+                // the stroke and the fill in between
+                (0, 1, 8, 1, std::vec![0x6F]),
+            ]
+        );
+    }
+
+    #[test]
+    fn rect_stroke_keeps_fill() {
+        let mut sent = Vec::new();
+        let mut display = DirectDisplay::new(16, 8, Color::White, record(&mut sent));
+        let style = PrimitiveStyleBuilder::new()
+            .stroke_color(BinaryColor::Off)
+            .stroke_width(2)
+            .stroke_alignment(StrokeAlignment::Inside)
+            .fill_color(Black)
+            .build();
+        display
+            .draw_rect(&Rectangle::new(Point::new(2, 0), Size::new(8, 5)).into_styled(style))
+            .unwrap();
+
+        // white stroke around 4x1 black pixels, the rest keeps the background
+        drop(display);
+        assert_eq!(sent[2], (0, 2, 16, 1, std::vec![0xF0, 0xFF]));
+    }
+
+    #[test]
+    fn packed_images() {
+        let mut sent = Vec::new();
+        let mut display = DirectDisplay::new(32, 8, Color::White, record(&mut sent));
+        display
+            .draw_packed(Point::new(8, 1), 12, &[0x01, 0x20, 0x03, 0x40, 0xFF])
+            .unwrap();
+        assert_eq!(
+            display.draw_packed(Point::new(4, 0), 8, &[0x00]),
+            Err(DirectError::Unaligned)
+        );
+        assert_eq!(
+            display.draw_packed(Point::new(24, 0), 16, &[0x00, 0x00]),
+            Err(DirectError::OutOfBounds)
+        );
+
+        drop(display);
+
+        assert_eq!(sent, [(8, 1, 12, 2, std::vec![0x01, 0x20, 0x03, 0x40])]);
+    }
+
+    #[test]
+    fn propagates_sink_errors() {
+        let mut display =
+            DirectDisplay::new(8, 8, Color::White, |_, _, _, _, _: &[u8]| Err("bus error"));
+        assert_eq!(
+            display.fill_rect(Rectangle::new(Point::zero(), Size::new(8, 8)), Black),
+            Err(DirectError::Sink("bus error"))
+        );
+    }
+}
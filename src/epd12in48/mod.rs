@@ -0,0 +1,527 @@
+//! A simple Driver for the Waveshare 12.48" E-Ink Display via SPI
+//!
+//! Black and white with a resolution of 1304x984. The panel is driven by four controllers,
+//! each covering one quadrant:
+//!
+//! | Controller | Quadrant     | Size    |
+//! |------------|--------------|---------|
+//! | S2         | top left     | 648x492 |
+//! | M2         | top right    | 656x492 |
+//! | M1         | bottom left  | 648x492 |
+//! | S1         | bottom right | 656x492 |
+//!
+//! Every controller has its own chip select and busy line, the data/command and reset
+//! lines are shared by M1/S1 and M2/S2. The SPI bus is shared by all of them, so the driver
+//! can't be set up with [`WaveshareDisplay::new`](crate::prelude::WaveshareDisplay::new)
+//! and takes all pins in [`Pins12in48`] instead. The rest of the API has the same methods
+//! as the other drivers.
+//!
+//! The driver splits the frame across the controllers. The masters M1 and M2 clock their
+//! slaves and each other, so all four quadrants are refreshed together.
+//!
+//! # References
+//!
+//! - [Datasheet](https://www.waveshare.com/wiki/12.48inch_e-Paper_Module)
+//! - Waveshare C driver `EPD_12in48.c` of the module
+//!
+//! # Example for the 12.48" E-Ink Display
+//!
+//!```rust, no_run
+//!# use embedded_hal_mock::*;
+//!# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
+//!use embedded_graphics::{
+//!    pixelcolor::BinaryColor::On as Black, prelude::*, primitives::{Line, PrimitiveStyle},
+//!};
+//!use epd_waveshare::{epd12in48::*, prelude::*};
+//!#
+//!# let expectations = [];
+//!# let mut spi = spi::Mock::new(&expectations);
+//!# let expectations = [];
+//!# let pin = || pin::Mock::new(&expectations);
+//!# let mut delay = delay::MockNoop::new();
+//!
+//!// Setup EPD, the pins are given in the order M1, S1, M2, S2
+//!let pins = Pins12in48 {
+//!    cs: [pin(), pin(), pin(), pin()],
+//!    busy: [pin(), pin(), pin(), pin()],
+//!    dc: [pin(), pin()],
+//!    rst: [pin(), pin()],
+//!};
+//!let mut epd = Epd12in48::new(&mut spi, pins, &mut delay)?;
+//!
+//!// Use display graphics from embedded-graphics
+//!let mut display = Display12in48::default();
+//!
+//!let _ = Line::new(Point::new(0, 120), Point::new(0, 983))
+//!    .into_styled(PrimitiveStyle::with_stroke(Black, 1))
+//!    .draw(&mut display);
+//!
+//!// Display updated frame
+//!epd.update_and_display_frame(&mut spi, display.buffer(), &mut delay)?;
+//!
+//!// Set the EPD to sleep
+//!epd.sleep(&mut spi, &mut delay)?;
+//!# Ok(())
+//!# }
+//!```
+
+use core::marker::PhantomData;
+use core::ops::Range;
+
+use embedded_hal::{
+    blocking::{delay::*, spi::Write},
+    digital::v2::{InputPin, OutputPin},
+};
+
+use crate::color::Color;
+use crate::controllers::uc8179::{self, Command, DualSpi, IS_BUSY_LOW};
+use crate::traits;
+use crate::Error;
+
+#[cfg(feature = "graphics")]
+mod graphics;
+#[cfg(feature = "graphics")]
+pub use self::graphics::Display12in48;
+
+/// Width of the display
+pub const WIDTH: u32 = 1304;
+/// Height of the display
+pub const HEIGHT: u32 = 984;
+/// Default Background Color
+pub const DEFAULT_BACKGROUND_COLOR: Color = Color::White;
+
+/// Number of bytes of a frame
+const NUM_DISPLAY_BYTES: u32 = WIDTH * HEIGHT / 8;
+
+/// Width of the left quadrants, driven by M1 and S2
+const LEFT_WIDTH: u32 = 648;
+/// Height of all quadrants
+const HALF_HEIGHT: u32 = HEIGHT / 2;
+
+/// Pins of the four controllers
+///
+/// Pins with the same role need to have the same type, most HALs can erase the pin
+/// numbers from the type for this.
+pub struct Pins12in48<CS, BUSY, DC, RST> {
+    /// Chip selects of M1, S1, M2 and S2
+    pub cs: [CS; 4],
+    /// Busy lines of M1, S1, M2 and S2, low while busy
+    pub busy: [BUSY; 4],
+    /// Data/command lines of M1/S1 and M2/S2 (high for data, low for commands)
+    pub dc: [DC; 2],
+    /// Reset lines of M1/S1 and M2/S2
+    pub rst: [RST; 2],
+}
+
+/// One of the four controllers, the value is the index into the pins
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Controller {
+    M1 = 0,
+    S1 = 1,
+    M2 = 2,
+    S2 = 3,
+}
+
+use self::Controller::*;
+
+const ALL: [Controller; 4] = [M1, S1, M2, S2];
+const MASTERS: [Controller; 2] = [M1, M2];
+
+impl Controller {
+    /// Index of the shared data/command and reset pins
+    fn pair(self) -> usize {
+        self as usize / 2
+    }
+
+    /// Bytes of a row of the frame which belong to the quadrant
+    fn columns(self) -> Range<usize> {
+        let split = LEFT_WIDTH as usize / 8;
+        match self {
+            M1 | S2 => 0..split,
+            S1 | M2 => split..WIDTH as usize / 8,
+        }
+    }
+
+    /// Rows of the frame which belong to the quadrant
+    fn rows(self) -> Range<usize> {
+        match self {
+            S2 | M2 => 0..HALF_HEIGHT as usize,
+            M1 | S1 => HALF_HEIGHT as usize..HEIGHT as usize,
+        }
+    }
+
+    /// Width of the quadrant in pixels
+    fn width(self) -> u32 {
+        self.columns().len() as u32 * 8
+    }
+}
+
+/// Epd12in48 driver
+///
+pub struct Epd12in48<SPI, CS, BUSY, DC, RST, DELAY> {
+    /// Pins of all controllers
+    pins: Pins12in48<CS, BUSY, DC, RST>,
+    /// Background Color
+    color: Color,
+    _spi: PhantomData<SPI>,
+    _delay: PhantomData<DELAY>,
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> Epd12in48<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    /// Creates a new driver from the pins of all four controllers
+    ///
+    /// This already initialises the device.
+    pub fn new(
+        spi: &mut SPI,
+        pins: Pins12in48<CS, BUSY, DC, RST>,
+        delay: &mut DELAY,
+    ) -> Result<Self, Error<SPI::Error>> {
+        let mut epd = Epd12in48 {
+            pins,
+            color: DEFAULT_BACKGROUND_COLOR,
+            _spi: PhantomData,
+            _delay: PhantomData,
+        };
+
+        epd.init(spi, delay)?;
+
+        Ok(epd)
+    }
+
+    /// Wakes the device up from sleep
+    pub fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.init(spi, delay)
+    }
+
+    /// Lets all controllers go to sleep, [wake_up](Epd12in48::wake_up) resets them again
+    pub fn sleep(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle(spi, delay)?;
+        self.command(spi, &ALL, Command::PowerOff)?;
+        self.wait_until_idle(spi, delay)?;
+        self.cmd_with_data(spi, &ALL, Command::DeepSleep, &[0xA5])
+    }
+
+    /// Transmits a full frame, split across the quadrants, without showing it yet
+    pub fn update_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(buffer.len() == NUM_DISPLAY_BYTES as usize);
+        self.wait_until_idle(spi, delay)?;
+
+        let row_len = WIDTH as usize / 8;
+        for controller in ALL {
+            self.command(spi, &[controller], Command::DataStartTransmission2)?;
+            let columns = controller.columns();
+            for row in controller.rows() {
+                let start = row * row_len;
+                // the controller expects a set bit for a black pixel
+                self.data_inverted(
+                    spi,
+                    controller,
+                    &buffer[start + columns.start..start + columns.end],
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Shows the frame sent last on all quadrants at once and waits for the end of the
+    /// refresh
+    pub fn display_frame(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        // the masters power the slaves as well
+        self.command(spi, &MASTERS, Command::PowerOn)?;
+        delay.delay_ms(100);
+        self.wait_until_idle(spi, delay)?;
+
+        self.command(spi, &ALL, Command::DisplayRefresh)?;
+        delay.delay_ms(100);
+        self.wait_until_idle(spi, delay)
+    }
+
+    /// Transmits and shows a full frame
+    pub fn update_and_display_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.update_frame(spi, buffer, delay)?;
+        self.display_frame(spi, delay)
+    }
+
+    /// Clears the whole display with the background color
+    pub fn clear_frame(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle(spi, delay)?;
+
+        let color = !self.color.get_byte_value();
+        for controller in ALL {
+            let len = controller.columns().len() * controller.rows().len();
+            for cmd in [
+                Command::DataStartTransmission1,
+                Command::DataStartTransmission2,
+            ] {
+                self.command(spi, &[controller], cmd)?;
+                self.data_x_times(spi, controller, color, len)?;
+            }
+        }
+
+        self.display_frame(spi, delay)
+    }
+
+    /// Sets the background color for [clear_frame](Epd12in48::clear_frame)
+    pub fn set_background_color(&mut self, color: Color) {
+        self.color = color;
+    }
+
+    /// Returns the background color
+    pub fn background_color(&self) -> &Color {
+        &self.color
+    }
+
+    /// Width of the whole display
+    pub fn width(&self) -> u32 {
+        WIDTH
+    }
+
+    /// Height of the whole display
+    pub fn height(&self) -> u32 {
+        HEIGHT
+    }
+
+    /// Checks if any of the controllers is still busy
+    pub fn is_busy(&self) -> bool {
+        ALL.iter()
+            .any(|&controller| self.is_controller_busy(controller))
+    }
+
+    /// Gives the pins back
+    pub fn release(self) -> Pins12in48<CS, BUSY, DC, RST> {
+        self.pins
+    }
+
+    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.reset(delay);
+
+        // KW mode, waveform from OTP. The glass of the top quadrants is turned around,
+        // so M2 and S2 scan in the opposite direction.
+        self.cmd_with_data(spi, &[M1, S1], Command::PanelSetting, &[0x1F])?;
+        self.cmd_with_data(spi, &[M2, S2], Command::PanelSetting, &[0x13])?;
+        self.cmd_with_data(
+            spi,
+            &MASTERS,
+            Command::BoosterSoftStart,
+            &[0x17, 0x17, 0x39, 0x17],
+        )?;
+
+        for controller in ALL {
+            let (w, h) = (controller.width(), HALF_HEIGHT);
+            self.cmd_with_data(
+                spi,
+                &[controller],
+                Command::TconResolution,
+                &uc8179::resolution(w, h),
+            )?;
+        }
+
+        // synchronize the masters for a common refresh
+        self.cmd_with_data(spi, &ALL, Command::DualSpi, &[DualSpi::MmInput as u8])?;
+        self.cmd_with_data(spi, &ALL, Command::PllControl, &[0x08])?;
+        self.cmd_with_data(
+            spi,
+            &ALL,
+            Command::VcomAndDataIntervalSetting,
+            &[0x31, 0x07],
+        )?;
+        self.cmd_with_data(spi, &ALL, Command::TconSetting, &[0x22])?;
+        // the masters clock their slaves
+        self.cmd_with_data(spi, &MASTERS, Command::CascadeSetting, &[0x01])?;
+        self.cmd_with_data(spi, &ALL, Command::PowerSaving, &[0x00])?;
+        self.cmd_with_data(spi, &MASTERS, Command::VcmDcSetting, &[0x1C])?;
+        Ok(())
+    }
+
+    /// Resets all controllers at once
+    fn reset(&mut self, delay: &mut DELAY) {
+        for rst in self.pins.rst.iter_mut() {
+            let _ = rst.set_high();
+        }
+        delay.delay_ms(200);
+
+        for rst in self.pins.rst.iter_mut() {
+            let _ = rst.set_low();
+        }
+        delay.delay_ms(10);
+
+        for rst in self.pins.rst.iter_mut() {
+            let _ = rst.set_high();
+        }
+        delay.delay_ms(200);
+    }
+
+    fn command(
+        &mut self,
+        spi: &mut SPI,
+        controllers: &[Controller],
+        command: Command,
+    ) -> Result<(), Error<SPI::Error>> {
+        // low for commands
+        self.set_dc(controllers, false);
+        self.write(spi, controllers, &[traits::Command::address(command)])
+    }
+
+    fn data(
+        &mut self,
+        spi: &mut SPI,
+        controllers: &[Controller],
+        data: &[u8],
+    ) -> Result<(), Error<SPI::Error>> {
+        // high for data
+        self.set_dc(controllers, true);
+        self.write(spi, controllers, data)
+    }
+
+    /// Sends the same command and data to all `controllers` at once
+    fn cmd_with_data(
+        &mut self,
+        spi: &mut SPI,
+        controllers: &[Controller],
+        command: Command,
+        data: &[u8],
+    ) -> Result<(), Error<SPI::Error>> {
+        self.command(spi, controllers, command)?;
+        self.data(spi, controllers, data)
+    }
+
+    fn data_inverted(
+        &mut self,
+        spi: &mut SPI,
+        controller: Controller,
+        data: &[u8],
+    ) -> Result<(), Error<SPI::Error>> {
+        let mut chunk = [0u8; 64];
+        for bytes in data.chunks(chunk.len()) {
+            for (inverted, byte) in chunk.iter_mut().zip(bytes) {
+                *inverted = !byte;
+            }
+            self.data(spi, &[controller], &chunk[..bytes.len()])?;
+        }
+        Ok(())
+    }
+
+    fn data_x_times(
+        &mut self,
+        spi: &mut SPI,
+        controller: Controller,
+        val: u8,
+        repetitions: usize,
+    ) -> Result<(), Error<SPI::Error>> {
+        let chunk = [val; 64];
+        let mut left = repetitions;
+        while left > 0 {
+            let len = left.min(chunk.len());
+            self.data(spi, &[controller], &chunk[..len])?;
+            left -= len;
+        }
+        Ok(())
+    }
+
+    fn set_dc(&mut self, controllers: &[Controller], high: bool) {
+        for controller in controllers {
+            let dc = &mut self.pins.dc[controller.pair()];
+            let _ = if high { dc.set_high() } else { dc.set_low() };
+        }
+    }
+
+    /// Writes `data` to all `controllers` with their chip selects held low together
+    fn write(
+        &mut self,
+        spi: &mut SPI,
+        controllers: &[Controller],
+        data: &[u8],
+    ) -> Result<(), Error<SPI::Error>> {
+        for &controller in controllers {
+            let _ = self.pins.cs[controller as usize].set_low();
+        }
+
+        let result = Write::write(spi, data);
+
+        for &controller in controllers {
+            let _ = self.pins.cs[controller as usize].set_high();
+        }
+        result.map_err(Error::Spi)
+    }
+
+    fn is_controller_busy(&self, controller: Controller) -> bool {
+        let busy = &self.pins.busy[controller as usize];
+        (IS_BUSY_LOW && busy.is_low().unwrap_or(false))
+            || (!IS_BUSY_LOW && busy.is_high().unwrap_or(false))
+    }
+
+    /// Waits until all controllers are idle, asking each for its status
+    fn wait_until_idle(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        for controller in ALL {
+            while self.is_controller_busy(controller) {
+                self.command(spi, &[controller], Command::GetStatus)?;
+                delay.delay_ms(20);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn epd_size() {
+        assert_eq!(WIDTH, 1304);
+        assert_eq!(HEIGHT, 984);
+        assert_eq!(NUM_DISPLAY_BYTES, 160392);
+        assert_eq!(DEFAULT_BACKGROUND_COLOR, Color::White);
+    }
+
+    #[test]
+    fn quadrants_cover_frame() {
+        let mut covered = [[0u8; 163]; 2];
+        for controller in ALL {
+            let half = controller.rows().start / HALF_HEIGHT as usize;
+            for column in controller.columns() {
+                covered[half][column] += 1;
+            }
+            assert_eq!(controller.rows().len(), 492);
+        }
+        assert!(covered.iter().flatten().all(|&count| count == 1));
+
+        assert_eq!(M1.width(), 648);
+        assert_eq!(S1.width(), 656);
+        assert_eq!(M2.width(), 656);
+        assert_eq!(S2.width(), 648);
+        assert_eq!(M1.pair(), S1.pair());
+        assert_eq!(M2.pair(), S2.pair());
+        assert_ne!(M1.pair(), M2.pair());
+    }
+}
@@ -0,0 +1,27 @@
+//! Waveform look-up-table of the 13.3" (K) display, taken from the vendor driver.
+//!
+//! The table has 10 groups of 10 bytes plus 5 bytes of frame rate settings, the
+//! driving voltages belonging to it are kept separately.
+
+/// Waveform for the 4 gray levels
+#[rustfmt::skip]
+pub(crate) const LUT_4GRAY: [u8; 105] = [
+    0x80, 0x48, 0x4A, 0x22, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x0A, 0x48, 0x68, 0x22, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x88, 0x48, 0x60, 0x11, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0xA8, 0x48, 0x45, 0x11, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x07, 0x1E, 0x1C, 0x02, 0x00, 0x05, 0x01, 0x05, 0x01, 0x02,
+    0x08, 0x01, 0x01, 0x04, 0x04, 0x00, 0x02, 0x00, 0x02, 0x01,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01,
+    0x22, 0x22, 0x22, 0x22, 0x22,
+];
+
+/// Gate voltage of the gray waveform
+pub(crate) const GATE_VOLTAGE_4GRAY: u8 = 0x17;
+/// Source voltages (VSH1, VSH2, VSL) of the gray waveform
+pub(crate) const SOURCE_VOLTAGE_4GRAY: [u8; 3] = [0x41, 0xA8, 0x32];
+/// VCOM of the gray waveform
+pub(crate) const VCOM_4GRAY: u8 = 0x30;
@@ -0,0 +1,155 @@
+use crate::epd13in3k::{DEFAULT_BACKGROUND_COLOR, HEIGHT, NUM_GRAY_BYTES, WIDTH};
+use crate::graphics::{Display, DisplayRotation, GrayDisplay};
+use embedded_graphics::pixelcolor::{BinaryColor, Gray2};
+use embedded_graphics_core::prelude::*;
+
+/// Display with Fullsize buffer for use with the 13in3k EPD
+///
+/// Can also be manually constructed:
+/// `buffer: [DEFAULT_BACKGROUND_COLOR.get_byte_value(); WIDTH / 8 * HEIGHT]`
+pub struct Display13in3k {
+    buffer: [u8; WIDTH as usize * HEIGHT as usize / 8],
+    rotation: DisplayRotation,
+}
+
+impl Default for Display13in3k {
+    fn default() -> Self {
+        Display13in3k {
+            buffer: [DEFAULT_BACKGROUND_COLOR.get_byte_value();
+                WIDTH as usize * HEIGHT as usize / 8],
+            rotation: DisplayRotation::default(),
+        }
+    }
+}
+
+impl DrawTarget for Display13in3k {
+    type Color = BinaryColor;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for pixel in pixels {
+            self.draw_helper(WIDTH, HEIGHT, pixel)?;
+        }
+        Ok(())
+    }
+}
+
+impl OriginDimensions for Display13in3k {
+    fn size(&self) -> Size {
+        Size::new(WIDTH, HEIGHT)
+    }
+}
+
+impl Display for Display13in3k {
+    fn buffer(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    fn get_mut_buffer(&mut self) -> &mut [u8] {
+        &mut self.buffer
+    }
+
+    fn set_rotation(&mut self, rotation: DisplayRotation) {
+        self.rotation = rotation;
+    }
+
+    fn rotation(&self) -> DisplayRotation {
+        self.rotation
+    }
+}
+
+/// Full size buffer with 4 gray levels for use with the 13in3k EPD
+///
+/// Every byte holds 4 pixels, white (`0xFF`) is the default.
+pub struct Display13in3kGray {
+    buffer: [u8; NUM_GRAY_BYTES],
+    rotation: DisplayRotation,
+}
+
+impl Default for Display13in3kGray {
+    fn default() -> Self {
+        Display13in3kGray {
+            buffer: [0xFF; NUM_GRAY_BYTES],
+            rotation: DisplayRotation::default(),
+        }
+    }
+}
+
+impl DrawTarget for Display13in3kGray {
+    type Color = Gray2;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for pixel in pixels {
+            self.draw_helper(WIDTH, HEIGHT, pixel)?;
+        }
+        Ok(())
+    }
+}
+
+impl OriginDimensions for Display13in3kGray {
+    fn size(&self) -> Size {
+        Size::new(WIDTH, HEIGHT)
+    }
+}
+
+impl GrayDisplay for Display13in3kGray {
+    fn buffer(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    fn get_mut_buffer(&mut self) -> &mut [u8] {
+        &mut self.buffer
+    }
+
+    fn set_rotation(&mut self, rotation: DisplayRotation) {
+        self.rotation = rotation;
+    }
+
+    fn rotation(&self) -> DisplayRotation {
+        self.rotation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // test buffer length
+    #[test]
+    fn graphics_size() {
+        let display = Display13in3k::default();
+        assert_eq!(display.buffer().len(), 81600);
+    }
+
+    // test default background color on all bytes
+    #[test]
+    fn graphics_default() {
+        let display = Display13in3k::default();
+        for &byte in display.buffer() {
+            assert_eq!(byte, DEFAULT_BACKGROUND_COLOR.get_byte_value());
+        }
+    }
+
+    #[test]
+    fn graphics_gray_size() {
+        let display = Display13in3kGray::default();
+        assert_eq!(display.buffer().len(), 163200);
+    }
+
+    #[test]
+    fn graphics_gray_levels() {
+        let mut display = Display13in3kGray::default();
+        for (x, luma) in (0..4).enumerate() {
+            let _ = Pixel(Point::new(x as i32, 0), Gray2::new(luma)).draw(&mut display);
+        }
+        assert_eq!(display.buffer()[0], 0b00_01_10_11);
+        assert_eq!(display.buffer()[1], 0xFF);
+    }
+}
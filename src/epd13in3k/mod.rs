@@ -0,0 +1,395 @@
+//! A simple Driver for the Waveshare 13.3" (K) E-Ink Display via SPI
+//!
+//! The 960x680 panel is driven by an SSD1677, like the [7.5" HD](crate::epd7in5_hd).
+//! It can be used in two modes:
+//!
+//! - black/white with the usual 1 bit per pixel buffers of [`WaveshareDisplay`] and the
+//!   waveform from the OTP memory
+//! - 4 gray levels with 2 bits per pixel, see [`Epd13in3k::update_gray_frame`] and
+//!   the `Gray2` buffer [`Display13in3kGray`], with the waveform of the vendor driver
+//!
+//! The driver switches between both modes depending on the last uploaded frame.
+//!
+//! # References
+//!
+//! - [Waveshare product page](https://www.waveshare.com/wiki/13.3inch_e-Paper_HAT_(K)_Manual)
+//! - [Waveshare C driver](https://github.com/waveshare/e-Paper/blob/master/RaspberryPi_JetsonNano/c/lib/e-Paper/EPD_13in3k.c)
+//!
+//! # Example for the 13.3" (K) E-Ink Display
+//!
+//!```rust, no_run
+//!# use embedded_hal_mock::*;
+//!# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
+//!use embedded_graphics::{
+//!    pixelcolor::{BinaryColor::On as Black, Gray2}, prelude::*,
+//!    primitives::{Line, PrimitiveStyle},
+//!};
+//!use epd_waveshare::{epd13in3k::*, prelude::*};
+//!#
+//!# let expectations = [];
+//!# let mut spi = spi::Mock::new(&expectations);
+//!# let expectations = [];
+//!# let cs_pin = pin::Mock::new(&expectations);
+//!# let busy_in = pin::Mock::new(&expectations);
+//!# let dc = pin::Mock::new(&expectations);
+//!# let rst = pin::Mock::new(&expectations);
+//!# let mut delay = delay::MockNoop::new();
+//!
+//!// Setup EPD
+//!let mut epd = Epd13in3k::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
+//!
+//!// Black/white
+//!let mut display = Display13in3k::default();
+//!let _ = Line::new(Point::new(0, 120), Point::new(0, 679))
+//!    .into_styled(PrimitiveStyle::with_stroke(Black, 1))
+//!    .draw(&mut display);
+//!epd.update_and_display_frame(&mut spi, display.buffer(), &mut delay)?;
+//!
+//!// 4 gray levels
+//!let mut display = Display13in3kGray::default();
+//!let _ = Line::new(Point::new(10, 120), Point::new(10, 679))
+//!    .into_styled(PrimitiveStyle::with_stroke(Gray2::new(1), 1))
+//!    .draw(&mut display);
+//!epd.update_gray_frame(&mut spi, display.buffer())?;
+//!epd.display_frame(&mut spi, &mut delay)?;
+//!
+//!// Set the EPD to sleep
+//!epd.sleep(&mut spi, &mut delay)?;
+//!# Ok(())
+//!# }
+//!```
+
+use embedded_hal::{
+    blocking::{delay::*, spi::Write},
+    digital::v2::*,
+};
+
+use crate::color::Color;
+pub use crate::color::GrayMode;
+use crate::epd7in5_hd::command::Command;
+use crate::interface::DisplayInterface;
+use crate::traits::{InternalWiAdditions, RefreshLut, WaveshareDisplay};
+use crate::Error;
+
+mod constants;
+use self::constants::*;
+
+#[cfg(feature = "graphics")]
+mod graphics;
+#[cfg(feature = "graphics")]
+pub use self::graphics::{Display13in3k, Display13in3kGray};
+
+/// Width of the display
+pub const WIDTH: u32 = 960;
+/// Height of the display
+pub const HEIGHT: u32 = 680;
+/// Default Background Color
+pub const DEFAULT_BACKGROUND_COLOR: Color = Color::White;
+const IS_BUSY_LOW: bool = false;
+
+/// Number of bytes of a black/white frame
+const NUM_DISPLAY_BYTES: u32 = WIDTH * HEIGHT / 8;
+/// Number of bytes of a frame with 2 bits per pixel
+const NUM_GRAY_BYTES: usize = WIDTH as usize * HEIGHT as usize / 4;
+
+/// Epd13in3k driver
+///
+pub struct Epd13in3k<SPI, CS, BUSY, DC, RST, DELAY> {
+    /// Connection Interface
+    interface: DisplayInterface<SPI, CS, BUSY, DC, RST, DELAY>,
+    /// Background Color
+    color: Color,
+    /// Mode of the last uploaded frame
+    mode: GrayMode,
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> InternalWiAdditions<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd13in3k<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        // Reset the device
+        self.interface.reset(delay, 2);
+
+        self.wait_until_idle();
+        self.interface.cmd(spi, Command::SwReset)?;
+        self.wait_until_idle();
+
+        self.interface
+            .cmd_with_data(spi, Command::SoftStart, &[0xAE, 0xC7, 0xC3, 0xC0, 0x80])?;
+
+        // 680 gates
+        self.interface.cmd_with_data(
+            spi,
+            Command::DriverOutputControl,
+            &[(HEIGHT - 1) as u8, ((HEIGHT - 1) >> 8) as u8, 0x02],
+        )?;
+
+        // x and y increment, the frame is written row by row from the top left
+        self.interface
+            .cmd_with_data(spi, Command::DataEntry, &[0x03])?;
+        self.interface.cmd_with_data(
+            spi,
+            Command::SetRamXStartEnd,
+            &[0x00, 0x00, (WIDTH - 1) as u8, ((WIDTH - 1) >> 8) as u8],
+        )?;
+        self.interface.cmd_with_data(
+            spi,
+            Command::SetRamYStartEnd,
+            &[0x00, 0x00, (HEIGHT - 1) as u8, ((HEIGHT - 1) >> 8) as u8],
+        )?;
+
+        self.interface
+            .cmd_with_data(spi, Command::VbdControl, &[0x01])?;
+        // use the internal temperature sensor
+        self.interface
+            .cmd_with_data(spi, Command::TemperatureSensorControl, &[0x80])?;
+        self.set_ram_counter(spi)?;
+        self.wait_until_idle();
+
+        if self.mode == GrayMode::Gray4 {
+            self.load_gray_lut(spi)?;
+        }
+        Ok(())
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd13in3k<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    type DisplayColor = Color;
+    fn new(
+        spi: &mut SPI,
+        cs: CS,
+        busy: BUSY,
+        dc: DC,
+        rst: RST,
+        delay: &mut DELAY,
+    ) -> Result<Self, Error<SPI::Error>> {
+        let interface = DisplayInterface::new(cs, busy, dc, rst);
+
+        let mut epd = Epd13in3k {
+            interface,
+            color: DEFAULT_BACKGROUND_COLOR,
+            mode: GrayMode::Gray1,
+        };
+
+        epd.init(spi, delay)?;
+
+        Ok(epd)
+    }
+
+    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.init(spi, delay)
+    }
+
+    fn sleep(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        self.interface
+            .cmd_with_data(spi, Command::DeepSleep, &[0x01])
+    }
+
+    /// Uploads a black/white frame and switches the display into `GrayMode::Gray1`
+    fn update_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(buffer.len() == NUM_DISPLAY_BYTES as usize);
+        self.wait_until_idle();
+        self.mode = GrayMode::Gray1;
+
+        self.set_ram_counter(spi)?;
+        self.interface
+            .cmd_with_data(spi, Command::WriteRamBw, buffer)
+    }
+
+    fn update_partial_frame(
+        &mut self,
+        _spi: &mut SPI,
+        _buffer: &[u8],
+        _x: u32,
+        _y: u32,
+        _width: u32,
+        _height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        unsupported!();
+    }
+
+    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.start_display_frame(spi, delay)?;
+        self.wait_until_idle();
+        Ok(())
+    }
+
+    fn start_display_frame(
+        &mut self,
+        spi: &mut SPI,
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        let sequence = match self.mode {
+            // load the waveform from OTP
+            GrayMode::Gray1 => 0xF7,
+            // keep the uploaded gray waveform
+            GrayMode::Gray4 => 0xC7,
+        };
+        self.interface
+            .cmd_with_data(spi, Command::DisplayUpdateControl2, &[sequence])?;
+        self.interface.cmd(spi, Command::MasterActivation)?;
+        Ok(())
+    }
+
+    fn update_and_display_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.update_frame(spi, buffer, delay)?;
+        self.display_frame(spi, delay)?;
+        Ok(())
+    }
+
+    /// Clears both RAMs, which works for both modes as white is `0b11` and black `0b00`
+    fn clear_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        let color = self.color.get_byte_value();
+
+        for command in [Command::WriteRamBw, Command::WriteRamRed] {
+            self.set_ram_counter(spi)?;
+            self.interface.cmd(spi, command)?;
+            self.interface.data_x_times(spi, color, NUM_DISPLAY_BYTES)?;
+        }
+        self.display_frame(spi, delay)
+    }
+
+    fn set_background_color(&mut self, color: Color) {
+        self.color = color;
+    }
+
+    fn background_color(&self) -> &Color {
+        &self.color
+    }
+
+    fn width(&self) -> u32 {
+        WIDTH
+    }
+
+    fn height(&self) -> u32 {
+        HEIGHT
+    }
+
+    fn set_lut(
+        &mut self,
+        _spi: &mut SPI,
+        refresh_rate: Option<RefreshLut>,
+    ) -> Result<(), Error<SPI::Error>> {
+        if let Some(RefreshLut::Custom(_)) = refresh_rate {
+            return Err(Error::Unsupported);
+        }
+        unsupported!();
+    }
+
+    fn is_busy(&self) -> bool {
+        self.interface.is_busy(IS_BUSY_LOW)
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> Epd13in3k<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    /// Uploads a frame with 4 gray levels and switches the display into `GrayMode::Gray4`
+    ///
+    /// The buffer holds 2 bits per pixel, see [`Display13in3kGray`].
+    pub fn update_gray_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(buffer.len() == NUM_GRAY_BYTES);
+        self.wait_until_idle();
+        if self.mode == GrayMode::Gray1 {
+            self.mode = GrayMode::Gray4;
+            self.load_gray_lut(spi)?;
+        }
+
+        // the high bit of every pixel goes into the first RAM, the low bit into the second
+        self.set_ram_counter(spi)?;
+        self.interface.cmd(spi, Command::WriteRamBw)?;
+        self.interface.data_gray_plane(spi, buffer, 0b10, false)?;
+        self.set_ram_counter(spi)?;
+        self.interface.cmd(spi, Command::WriteRamRed)?;
+        self.interface.data_gray_plane(spi, buffer, 0b01, false)
+    }
+
+    /// Mode of the last uploaded frame
+    pub fn gray_mode(&self) -> GrayMode {
+        self.mode
+    }
+
+    fn wait_until_idle(&mut self) {
+        self.interface.wait_until_idle(IS_BUSY_LOW);
+    }
+
+    /// Uploads the gray waveform with its voltages, a black/white refresh loads the
+    /// waveform from OTP again
+    fn load_gray_lut(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        self.interface
+            .cmd_with_data(spi, Command::WriteLutRegister, &LUT_4GRAY)?;
+        self.interface.cmd_with_data(
+            spi,
+            Command::GateDrivingVoltageControl,
+            &[GATE_VOLTAGE_4GRAY],
+        )?;
+        self.interface.cmd_with_data(
+            spi,
+            Command::SourceDrivingVoltageControl,
+            &SOURCE_VOLTAGE_4GRAY,
+        )?;
+        self.interface
+            .cmd_with_data(spi, Command::VcomWrite, &[VCOM_4GRAY])
+    }
+
+    /// Moves the RAM address counters back to the top left corner
+    fn set_ram_counter(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        self.interface
+            .cmd_with_data(spi, Command::SetRamXAc, &[0x00, 0x00])?;
+        self.interface
+            .cmd_with_data(spi, Command::SetRamYAc, &[0x00, 0x00])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn epd_size() {
+        assert_eq!(WIDTH, 960);
+        assert_eq!(HEIGHT, 680);
+        assert_eq!(NUM_DISPLAY_BYTES, 81600);
+        assert_eq!(NUM_GRAY_BYTES, 163200);
+        assert_eq!(DEFAULT_BACKGROUND_COLOR, Color::White);
+    }
+}
@@ -0,0 +1,84 @@
+//! SPI Commands for the Waveshare 1.02" E-Ink Display
+use crate::traits;
+/// EPD1IN02 commands
+///
+/// Should rarely (never?) be needed directly.
+///
+/// For more infos about the addresses and what they are doing look into the pdfs
+///
+/// The description of the single commands is mostly taken from UC8175.pdf
+#[allow(dead_code)]
+#[derive(Copy, Clone)]
+pub(crate) enum Command {
+    /// Set LUT selection, gate scan direction, source shift direction, booster switch, soft reset
+    ///
+    /// 0x6F: LUT set by registers
+    PanelSetting = 0x00,
+    /// Selecting internal and external power
+    PowerSetting = 0x01,
+    /// Turns off charge pump, T-con, source driver, gate driver, VCOM, and temperature sensor.
+    /// Register data is kept until VDD becomes OFF.
+    PowerOff = 0x02,
+    /// Turning On the Power
+    PowerOn = 0x04,
+    /// Starting data transmission
+    BoosterSoftStart = 0x06,
+    /// After this command is transmitted, the chip would enter the deep-sleep mode to save power.
+    ///
+    /// The deep sleep mode would return to standby by hardware reset.
+    ///
+    /// The only one parameter is a check code, the command would be excuted if check code = 0xA5.
+    DeepSleep = 0x07,
+    /// Writes the “OLD” frame into SRAM, which is compared against for partial refreshes
+    DataStartTransmission1 = 0x10,
+    /// While user sent this command, driver will refresh display (data/VCOM) according to SRAM data and LUT.
+    DisplayRefresh = 0x12,
+    /// Writes the “NEW” frame into SRAM
+    DataStartTransmission2 = 0x13,
+    /// Look-Up Table for the pixels which end up white, 7 groups of 6 bytes
+    LutWhite = 0x23,
+    /// Look-Up Table for the pixels which end up black, 7 groups of 6 bytes
+    LutBlack = 0x24,
+    /// Undocumented, set to 0x00 0x00 by the vendor driver
+    LutOption = 0x2A,
+    /// The command controls the PLL clock frequency.
+    PllControl = 0x30,
+    /// This command indicates the interval of Vcom and data output and the border color
+    VcomAndDataIntervalSetting = 0x50,
+    /// This command defines non-overlap period of Gate and Source.
+    TconSetting = 0x60,
+    /// This command defines alternative resolution and this setting is of higher priority than the RES\[1:0\] in R00H (PSR).
+    ResolutionSetting = 0x61,
+    /// Set VCM_DC
+    VcmDcSetting = 0x82,
+    /// This command sets partial window
+    PartialWindow = 0x90,
+    /// This command makes the display enter partial mode
+    PartialIn = 0x91,
+    /// This command makes the display exit partial mode and enter normal mode
+    PartialOut = 0x92,
+    /// Undocumented, set to 0x3F by the vendor driver
+    ChargePumpSetting = 0xD2,
+    /// This command is set for saving power during fresh period.
+    PowerSaving = 0xE3,
+}
+
+impl traits::Command for Command {
+    /// Returns the address of the command
+    fn address(self) -> u8 {
+        self as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::Command as CommandTrait;
+
+    #[test]
+    fn command_addr() {
+        assert_eq!(Command::PanelSetting.address(), 0x00);
+        assert_eq!(Command::LutBlack.address(), 0x24);
+        assert_eq!(Command::ChargePumpSetting.address(), 0xD2);
+    }
+}
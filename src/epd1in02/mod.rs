@@ -0,0 +1,493 @@
+//! A simple Driver for the Waveshare 1.02" E-Ink Display via SPI
+//!
+//! The small panel uses an UC8175 controller with its own command set. Both the
+//! full and the partial refresh use waveforms written into the LUT registers.
+//!
+//! # References
+//!
+//! - [Waveshare product page](https://www.waveshare.com/wiki/1.02inch_e-Paper_Module)
+//! - [Waveshare C driver](https://github.com/waveshare/e-Paper/blob/master/RaspberryPi_JetsonNano/c/lib/e-Paper/EPD_1in02d.c)
+//!
+//! # Example for the 1.02" E-Ink Display
+//!
+//!```rust, no_run
+//!# use embedded_hal_mock::*;
+//!# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
+//!use embedded_graphics::{
+//!    pixelcolor::BinaryColor::On as Black, prelude::*, primitives::{Line, PrimitiveStyle},
+//!};
+//!use epd_waveshare::{epd1in02::*, prelude::*};
+//!#
+//!# let expectations = [];
+//!# let mut spi = spi::Mock::new(&expectations);
+//!# let expectations = [];
+//!# let cs_pin = pin::Mock::new(&expectations);
+//!# let busy_in = pin::Mock::new(&expectations);
+//!# let dc = pin::Mock::new(&expectations);
+//!# let rst = pin::Mock::new(&expectations);
+//!# let mut delay = delay::MockNoop::new();
+//!
+//!// Setup EPD
+//!let mut epd = Epd1in02::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
+//!
+//!// Use display graphics from embedded-graphics
+//!let mut display = Display1in02::default();
+//!
+//!// Use embedded graphics for drawing a line
+//!let _ = Line::new(Point::new(0, 20), Point::new(0, 127))
+//!    .into_styled(PrimitiveStyle::with_stroke(Black, 1))
+//!    .draw(&mut display);
+//!
+//!// Display updated frame, this also sets it as base for quick refreshes
+//!epd.update_frame(&mut spi, display.buffer(), &mut delay)?;
+//!epd.display_frame(&mut spi, &mut delay)?;
+//!
+//!// Draw something new here
+//!
+//!// quick refresh of updated pixels
+//!epd.update_and_display_new_frame(&mut spi, display.buffer(), &mut delay)?;
+//!
+//!// Set the EPD to sleep
+//!epd.sleep(&mut spi, &mut delay)?;
+//!# Ok(())
+//!# }
+//!```
+
+use embedded_hal::{
+    blocking::{delay::*, spi::Write},
+    digital::v2::*,
+};
+
+use crate::color::Color;
+use crate::interface::DisplayInterface;
+use crate::traits::{InternalWiAdditions, QuickRefresh, RefreshLut, WaveshareDisplay};
+use crate::{buffer_len, span_fits, Error};
+
+pub(crate) mod command;
+use self::command::Command;
+
+//The Lookup Tables for the Display
+mod constants;
+use self::constants::*;
+
+#[cfg(feature = "graphics")]
+mod graphics;
+#[cfg(feature = "graphics")]
+pub use self::graphics::Display1in02;
+
+/// Width of epd1in02 in pixels
+pub const WIDTH: u32 = 80;
+/// Height of epd1in02 in pixels
+pub const HEIGHT: u32 = 128;
+/// Default Background Color (white)
+pub const DEFAULT_BACKGROUND_COLOR: Color = Color::White;
+const IS_BUSY_LOW: bool = true;
+
+/// Epd1in02 driver
+pub struct Epd1in02<SPI, CS, BUSY, DC, RST, DELAY> {
+    /// Connection Interface
+    interface: DisplayInterface<SPI, CS, BUSY, DC, RST, DELAY>,
+    /// Background Color
+    color: Color,
+    /// Refresh LUT
+    refresh: RefreshLut,
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> InternalWiAdditions<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd1in02<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.interface.reset(delay, 20);
+
+        self.interface
+            .cmd_with_data(spi, Command::ChargePumpSetting, &[0x3F])?;
+
+        // LUT set by registers
+        self.interface
+            .cmd_with_data(spi, Command::PanelSetting, &[0x6F])?;
+
+        self.interface
+            .cmd_with_data(spi, Command::PowerSetting, &[0x03, 0x00, 0x2B, 0x2B])?;
+
+        self.interface
+            .cmd_with_data(spi, Command::BoosterSoftStart, &[0x3F])?;
+
+        self.interface
+            .cmd_with_data(spi, Command::LutOption, &[0x00, 0x00])?;
+
+        self.interface
+            .cmd_with_data(spi, Command::TconSetting, &[0x22])?;
+
+        self.interface.cmd_with_data(
+            spi,
+            Command::ResolutionSetting,
+            &[WIDTH as u8, HEIGHT as u8],
+        )?;
+
+        self.interface
+            .cmd_with_data(spi, Command::PowerSaving, &[0x33])?;
+
+        self.set_lut(spi, None)?;
+
+        self.interface.cmd(spi, Command::PowerOn)?;
+        self.wait_until_idle();
+        Ok(())
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd1in02<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    type DisplayColor = Color;
+    fn new(
+        spi: &mut SPI,
+        cs: CS,
+        busy: BUSY,
+        dc: DC,
+        rst: RST,
+        delay: &mut DELAY,
+    ) -> Result<Self, Error<SPI::Error>> {
+        let interface = DisplayInterface::new(cs, busy, dc, rst);
+
+        let mut epd = Epd1in02 {
+            interface,
+            color: DEFAULT_BACKGROUND_COLOR,
+            refresh: RefreshLut::Full,
+        };
+
+        epd.init(spi, delay)?;
+
+        Ok(epd)
+    }
+
+    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.init(spi, delay)
+    }
+
+    fn sleep(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        // border floating
+        self.interface
+            .cmd_with_data(spi, Command::VcomAndDataIntervalSetting, &[0xF7])?;
+        self.interface.cmd(spi, Command::PowerOff)?;
+        self.wait_until_idle();
+        self.interface
+            .cmd_with_data(spi, Command::DeepSleep, &[0xA5])
+    }
+
+    /// Writes the frame as old and new frame, so it's also the base for following quick refreshes
+    fn update_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(buffer.len() == buffer_len(WIDTH as usize, HEIGHT as usize));
+        self.wait_until_idle();
+
+        self.interface
+            .cmd_with_data(spi, Command::DataStartTransmission1, buffer)?;
+        self.interface
+            .cmd_with_data(spi, Command::DataStartTransmission2, buffer)
+    }
+
+    fn update_partial_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+
+        self.set_partial_window(spi, x, y, width, height)?;
+        self.interface
+            .cmd_with_data(spi, Command::DataStartTransmission2, buffer)?;
+        self.interface.cmd(spi, Command::PartialOut)
+    }
+
+    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.start_display_frame(spi, delay)?;
+        self.wait_until_idle();
+        Ok(())
+    }
+
+    fn start_display_frame(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        self.load_lut(spi, self.refresh)?;
+        self.interface.cmd(spi, Command::DisplayRefresh)?;
+        // BUSY goes low a moment after the command
+        delay.delay_ms(10);
+        Ok(())
+    }
+
+    fn update_and_display_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.update_frame(spi, buffer, delay)?;
+        self.display_frame(spi, delay)?;
+        Ok(())
+    }
+
+    fn clear_frame(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        let color = self.color.get_byte_value();
+        let len = buffer_len(WIDTH as usize, HEIGHT as usize) as u32;
+
+        self.interface.cmd(spi, Command::DataStartTransmission1)?;
+        self.interface.data_x_times(spi, color, len)?;
+
+        self.interface.cmd(spi, Command::DataStartTransmission2)?;
+        self.interface.data_x_times(spi, color, len)
+    }
+
+    fn set_background_color(&mut self, color: Color) {
+        self.color = color;
+    }
+
+    fn background_color(&self) -> &Color {
+        &self.color
+    }
+
+    fn width(&self) -> u32 {
+        WIDTH
+    }
+
+    fn height(&self) -> u32 {
+        HEIGHT
+    }
+
+    fn set_lut(
+        &mut self,
+        spi: &mut SPI,
+        refresh_rate: Option<RefreshLut>,
+    ) -> Result<(), Error<SPI::Error>> {
+        if let Some(RefreshLut::Custom(_)) = refresh_rate {
+            return Err(Error::Unsupported);
+        }
+        if let Some(refresh_lut) = refresh_rate {
+            self.refresh = refresh_lut;
+        }
+        self.load_lut(spi, self.refresh)
+    }
+
+    fn is_busy(&self) -> bool {
+        self.interface.is_busy(IS_BUSY_LOW)
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> QuickRefresh<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd1in02<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    /// Sets the base frame the next quick refresh is compared against.
+    fn update_old_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        self.interface
+            .cmd_with_data(spi, Command::DataStartTransmission1, buffer)
+    }
+
+    /// Transmits the frame which is shown with the next `display_new_frame`.
+    fn update_new_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        self.interface
+            .cmd_with_data(spi, Command::DataStartTransmission2, buffer)
+    }
+
+    /// Refreshes with the partial update waveform, regardless of the LUT chosen with `set_lut`.
+    fn display_new_frame(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        self.load_lut(spi, RefreshLut::Quick)?;
+        self.refresh_display(spi, delay)
+    }
+
+    fn update_and_display_new_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.update_new_frame(spi, buffer, delay)?;
+        self.display_new_frame(spi, delay)?;
+        self.update_old_frame(spi, buffer, delay)
+    }
+
+    fn update_partial_old_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+
+        self.set_partial_window(spi, x, y, width, height)?;
+        self.interface
+            .cmd_with_data(spi, Command::DataStartTransmission1, buffer)?;
+        self.interface.cmd(spi, Command::PartialOut)
+    }
+
+    fn update_partial_new_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.update_partial_frame(spi, buffer, x, y, width, height)
+    }
+
+    fn clear_partial_frame(
+        &mut self,
+        spi: &mut SPI,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        let color = self.color.get_byte_value();
+        let len = buffer_len(width as usize, height as usize) as u32;
+
+        self.set_partial_window(spi, x, y, width, height)?;
+        for command in [
+            Command::DataStartTransmission1,
+            Command::DataStartTransmission2,
+        ] {
+            self.interface.cmd(spi, command)?;
+            self.interface.data_x_times(spi, color, len)?;
+        }
+        self.interface.cmd(spi, Command::PartialOut)
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> Epd1in02<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn wait_until_idle(&mut self) {
+        self.interface.wait_until_idle(IS_BUSY_LOW);
+    }
+
+    fn refresh_display(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.interface.cmd(spi, Command::DisplayRefresh)?;
+        delay.delay_ms(10);
+        self.wait_until_idle();
+        Ok(())
+    }
+
+    /// Writes the waveform and the matching timing registers
+    fn load_lut(&mut self, spi: &mut SPI, refresh: RefreshLut) -> Result<(), Error<SPI::Error>> {
+        // PLL, border/data interval and VCOM differ between both waveforms
+        let (pll, interval, vcom, lut_w, lut_b) = match refresh {
+            RefreshLut::Full | RefreshLut::Custom(_) => {
+                (0x13, 0x57, 0x12, &LUT_W_FULL, &LUT_B_FULL)
+            }
+            RefreshLut::Quick => (0x05, 0xF2, 0x00, &LUT_W_QUICK, &LUT_B_QUICK),
+        };
+        self.interface
+            .cmd_with_data(spi, Command::PllControl, &[pll])?;
+        self.interface
+            .cmd_with_data(spi, Command::VcomAndDataIntervalSetting, &[interval])?;
+        self.interface
+            .cmd_with_data(spi, Command::VcmDcSetting, &[vcom])?;
+        self.interface
+            .cmd_with_data(spi, Command::LutWhite, lut_w)?;
+        self.interface.cmd_with_data(spi, Command::LutBlack, lut_b)
+    }
+
+    /// Enters the partial mode with the window to send the next frame to
+    fn set_partial_window(
+        &mut self,
+        spi: &mut SPI,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(span_fits(x, width, WIDTH) && span_fits(y, height, HEIGHT));
+        // x should be a multiple of 8, the last 3 bits are always ignored
+        let x = x & 0xF8;
+        self.interface.cmd(spi, Command::PartialIn)?;
+        self.interface.cmd_with_data(
+            spi,
+            Command::PartialWindow,
+            &[
+                x as u8,
+                (x + width - 1) as u8 | 0x07,
+                y as u8,
+                (y + height - 1) as u8,
+                // Gates scan both inside and outside of the partial window
+                0x01,
+            ],
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn epd_size() {
+        assert_eq!(WIDTH, 80);
+        assert_eq!(HEIGHT, 128);
+        assert_eq!(DEFAULT_BACKGROUND_COLOR, Color::White);
+    }
+}
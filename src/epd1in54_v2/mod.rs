@@ -0,0 +1,809 @@
+//! A simple Driver for the Waveshare 1.54" V2 E-Ink Display via SPI
+//!
+//! The V2 revision uses an SSD1681 controller. The init sequence of the original
+//! 1.54" panel doesn't work with it, use this module for all panels with a "V2" sticker.
+//!
+//! Partial refreshes work out of the box: select [`RefreshLut::Quick`] with `set_lut`,
+//! afterwards `display_frame` only refreshes the pixels which changed since the last
+//! full refresh, without flashing the whole panel. Together with `update_partial_frame`
+//! only a window has to be transmitted, e.g. the digits of a clock. The window has to
+//! start at a multiple of 8 and be a multiple of 8 wide.
+//!
+//! # References
+//!
+//! - [Waveshare product page](https://www.waveshare.com/wiki/1.54inch_e-Paper_Module)
+//! - [Waveshare C driver](https://github.com/waveshare/e-Paper/blob/master/RaspberryPi_JetsonNano/c/lib/e-Paper/EPD_1in54_V2.c)
+//! - [Waveshare Python driver](https://github.com/waveshare/e-Paper/blob/master/RaspberryPi_JetsonNano/python/lib/waveshare_epd/epd1in54_V2.py)
+//!
+//! # Example for the 1.54" V2 E-Ink Display
+//!
+//!```rust, no_run
+//!# use embedded_hal_mock::*;
+//!# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
+//!use embedded_graphics::{
+//!    pixelcolor::BinaryColor::On as Black, prelude::*, primitives::{Line, PrimitiveStyle},
+//!};
+//!use epd_waveshare::{epd1in54_v2::*, prelude::*};
+//!#
+//!# let expectations = [];
+//!# let mut spi = spi::Mock::new(&expectations);
+//!# let expectations = [];
+//!# let cs_pin = pin::Mock::new(&expectations);
+//!# let busy_in = pin::Mock::new(&expectations);
+//!# let dc = pin::Mock::new(&expectations);
+//!# let rst = pin::Mock::new(&expectations);
+//!# let mut delay = delay::MockNoop::new();
+//!
+//!// Setup EPD
+//!let mut epd = Epd1in54::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
+//!
+//!// Use display graphics from embedded-graphics
+//!let mut display = Display1in54::default();
+//!
+//!// Use embedded graphics for drawing a line
+//!let _ = Line::new(Point::new(0, 120), Point::new(0, 199))
+//!    .into_styled(PrimitiveStyle::with_stroke(Black, 1))
+//!    .draw(&mut display);
+//!
+//!// Display updated frame, this also sets it as base for partial refreshes
+//!epd.update_frame(&mut spi, display.buffer(), &mut delay)?;
+//!epd.display_frame(&mut spi, &mut delay)?;
+//!
+//!// Switch to partial refreshes and draw something new here
+//!epd.set_lut(&mut spi, Some(RefreshLut::Quick))?;
+//!
+//!epd.update_frame(&mut spi, display.buffer(), &mut delay)?;
+//!epd.display_frame(&mut spi, &mut delay)?;
+//!
+//!// Set the EPD to sleep
+//!epd.sleep(&mut spi, &mut delay)?;
+//!# Ok(())
+//!# }
+//!```
+
+use embedded_hal::{
+    blocking::{
+        delay::*,
+        spi::{Transfer, Write},
+    },
+    digital::v2::*,
+};
+
+use crate::color::{BorderColor, Color};
+use crate::controllers::ssd1681::{Command, DataEntry, IS_BUSY_LOW};
+use crate::interface::DisplayInterface;
+use crate::luts::ssd1680;
+use crate::refresh::QuickRefreshCounter;
+use crate::traits::{
+    BorderControl, CustomLut, DeepSleepMode, FullRefreshInterval, GlassOffset, HardwareMirror,
+    InternalWiAdditions, Mirror, PanelOffset, QuickRefresh, RamBanks, ReadRevision,
+    ReadTemperature, RefreshLut, Revision, SleepMode, WaveshareDisplay,
+};
+use crate::{buffer_len, span_fits, Error};
+
+pub(crate) mod constants;
+use self::constants::LUT_PARTIAL_UPDATE;
+
+#[cfg(feature = "graphics")]
+mod graphics;
+#[cfg(feature = "graphics")]
+pub use self::graphics::Display1in54;
+
+/// Width of epd1in54 V2 in pixels
+pub const WIDTH: u32 = 200;
+/// Height of epd1in54 V2 in pixels
+pub const HEIGHT: u32 = 200;
+/// Default Background Color (white)
+pub const DEFAULT_BACKGROUND_COLOR: Color = Color::White;
+
+/// Epd1in54 (V2) driver
+pub struct Epd1in54<SPI, CS, BUSY, DC, RST, DELAY> {
+    /// Connection Interface
+    interface: DisplayInterface<SPI, CS, BUSY, DC, RST, DELAY>,
+    /// Background Color
+    background_color: Color,
+    /// Refresh LUT
+    refresh: RefreshLut,
+    /// Position of the visible area in the controller RAM
+    offset: PanelOffset,
+    /// Border waveform of the full refreshes
+    border: u8,
+    /// Whether the registers hold a waveform written by `load_lut`
+    custom_lut: bool,
+    /// Deep sleep mode of `sleep`
+    sleep_mode: DeepSleepMode,
+    /// Quick refreshes since the last full one
+    quick_refreshes: QuickRefreshCounter,
+    /// Gate scan direction
+    mirror: Mirror,
+    /// Whether the RAM banks swap their roles after a refresh with display mode 2
+    ping_pong: bool,
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> InternalWiAdditions<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd1in54<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.interface.reset(delay, 10);
+        self.ping_pong = false;
+
+        self.interface.sw_reset_ssd1680(spi)?;
+        self.interface
+            .gates_ssd1681(spi, HEIGHT, self.mirror.vertical())?;
+        self.interface
+            .data_entry_ssd1680(spi, DataEntry::IncrementBoth)?;
+
+        self.use_full_frame(spi)?;
+
+        self.set_lut(spi, None)?;
+
+        self.wait_until_idle();
+        Ok(())
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd1in54<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    type DisplayColor = Color;
+    fn new(
+        spi: &mut SPI,
+        cs: CS,
+        busy: BUSY,
+        dc: DC,
+        rst: RST,
+        delay: &mut DELAY,
+    ) -> Result<Self, Error<SPI::Error>> {
+        let interface = DisplayInterface::new(cs, busy, dc, rst);
+
+        let mut epd = Epd1in54 {
+            interface,
+            background_color: DEFAULT_BACKGROUND_COLOR,
+            refresh: RefreshLut::Full,
+            offset: PanelOffset::default(),
+            border: 0x01,
+            custom_lut: false,
+            sleep_mode: DeepSleepMode::RetainRam,
+            quick_refreshes: QuickRefreshCounter::default(),
+            mirror: Mirror::None,
+            ping_pong: false,
+        };
+
+        epd.init(spi, delay)?;
+
+        Ok(epd)
+    }
+
+    fn sleep(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.interface.sleep_ssd1680(spi, self.sleep_mode)
+    }
+
+    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.init(spi, delay)
+    }
+
+    fn set_background_color(&mut self, background_color: Color) {
+        self.background_color = background_color;
+    }
+
+    fn background_color(&self) -> &Color {
+        &self.background_color
+    }
+
+    fn width(&self) -> u32 {
+        WIDTH
+    }
+
+    fn height(&self) -> u32 {
+        HEIGHT
+    }
+
+    /// With `RefreshLut::Full` the frame is also written as base for the
+    /// following partial refreshes.
+    fn update_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(buffer.len() == buffer_len(WIDTH as usize, HEIGHT as usize));
+        self.wait_until_idle();
+
+        self.use_full_frame(spi)?;
+        self.interface
+            .cmd_with_data(spi, Command::WriteRam, buffer)?;
+
+        if self.refresh == RefreshLut::Full {
+            self.use_full_frame(spi)?;
+            self.interface
+                .cmd_with_data(spi, Command::WriteRam2, buffer)?;
+        }
+        Ok(())
+    }
+
+    fn update_partial_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(span_fits(x, width, WIDTH) && span_fits(y, height, HEIGHT));
+        ensure!(x.is_multiple_of(8) && width.is_multiple_of(8) && width > 0 && height > 0);
+        ensure!(buffer.len() == buffer_len(width as usize, height as usize));
+        self.wait_until_idle();
+        self.set_ram_area(spi, x, y, x + width - 1, y + height - 1)?;
+        self.set_ram_counter(spi, x, y)?;
+
+        self.interface.cmd_with_data(spi, Command::WriteRam, buffer)
+    }
+
+    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.start_display_frame(spi, delay)?;
+        self.wait_until_idle();
+        Ok(())
+    }
+
+    fn start_display_frame(
+        &mut self,
+        spi: &mut SPI,
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        if self.refresh == RefreshLut::Quick {
+            // display_new_frame might have disabled it
+            self.use_ping_pong(spi, true)?;
+        } else {
+            self.quick_refreshes.full();
+        }
+        let mode = match self.refresh {
+            // Enable clock and analog, display with the LUT of load_lut, disable analog and clock
+            RefreshLut::Full | RefreshLut::Custom(_) if self.custom_lut => 0xC7,
+            // Enable clock and analog, load temperature and LUT, display, disable analog and clock
+            RefreshLut::Full | RefreshLut::Custom(_) => 0xF7,
+            // Enable clock and analog, display with mode 2 and the LUT loaded by set_lut,
+            // disable analog and clock
+            RefreshLut::Quick => 0xCF,
+        };
+        self.interface.start_ssd1680(spi, mode)
+    }
+
+    fn update_and_display_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.update_frame(spi, buffer, delay)?;
+        self.display_frame(spi, delay)?;
+        Ok(())
+    }
+
+    fn clear_frame(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        let color = self.background_color.get_byte_value();
+
+        self.use_full_frame(spi)?;
+        self.interface.cmd(spi, Command::WriteRam)?;
+        self.interface.data_x_times(
+            spi,
+            color,
+            buffer_len(WIDTH as usize, HEIGHT as usize) as u32,
+        )?;
+
+        if self.refresh == RefreshLut::Full {
+            self.use_full_frame(spi)?;
+            self.interface.cmd(spi, Command::WriteRam2)?;
+            self.interface.data_x_times(
+                spi,
+                color,
+                buffer_len(WIDTH as usize, HEIGHT as usize) as u32,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// `RefreshLut::Full` loads the waveform for the measured temperature from OTP,
+    /// `RefreshLut::Quick` loads the partial refresh waveform of the vendor driver.
+    fn set_lut(
+        &mut self,
+        spi: &mut SPI,
+        refresh_rate: Option<RefreshLut>,
+    ) -> Result<(), Error<SPI::Error>> {
+        if let Some(RefreshLut::Custom(_)) = refresh_rate {
+            return Err(Error::Unsupported);
+        }
+        if let Some(refresh_lut) = refresh_rate {
+            self.refresh = refresh_lut;
+        }
+
+        self.wait_until_idle();
+        self.custom_lut = false;
+        match self.refresh {
+            RefreshLut::Full | RefreshLut::Custom(_) => {
+                self.interface.border_ssd1680(spi, self.border)?;
+                // use the internal temperature sensor
+                self.interface
+                    .cmd_with_data(spi, Command::TemperatureSensorSelection, &[0x80])?;
+                // load the temperature and the waveform from OTP
+                self.interface.activate_ssd1680(spi, 0xB1)
+            }
+            RefreshLut::Quick => {
+                self.interface.waveform_ssd1681(spi, &LUT_PARTIAL_UPDATE)?;
+                self.use_ping_pong(spi, true)?;
+                self.interface.border_ssd1680(spi, 0x80)?;
+                // enable clock and analog
+                self.interface.activate_ssd1680(spi, 0xC0)
+            }
+        }
+    }
+
+    fn is_busy(&self) -> bool {
+        self.interface.is_busy(IS_BUSY_LOW)
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> QuickRefresh<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd1in54<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    /// Sets the base frame the next quick refresh is compared against.
+    ///
+    /// The RAM keeps its content during deep sleep, but call this after `wake_up`
+    /// anyway if the frame shown isn't known to be in the RAM.
+    fn update_old_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(buffer.len() == buffer_len(WIDTH as usize, HEIGHT as usize));
+        self.wait_until_idle();
+        self.use_full_frame(spi)?;
+        self.interface
+            .cmd_with_data(spi, Command::WriteRam2, buffer)
+    }
+
+    /// Transmits the frame which is shown with the next `display_new_frame`.
+    fn update_new_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(buffer.len() == buffer_len(WIDTH as usize, HEIGHT as usize));
+        self.wait_until_idle();
+        self.use_full_frame(spi)?;
+        self.interface.cmd_with_data(spi, Command::WriteRam, buffer)
+    }
+
+    /// Refreshes the pixels which differ between the old and the new frame.
+    ///
+    /// Uses the partial waveform if `RefreshLut::Quick` is selected, otherwise the
+    /// display mode 2 waveform from OTP.
+    fn display_new_frame(
+        &mut self,
+        spi: &mut SPI,
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        // keep the banks where they are, the old frame is written explicitly
+        self.use_ping_pong(spi, false)?;
+        let mode = match self.refresh {
+            RefreshLut::Full | RefreshLut::Custom(_) => 0xFF,
+            RefreshLut::Quick => 0xCF,
+        };
+        self.interface.activate_ssd1680(spi, mode)
+    }
+
+    /// Updates and displays the new frame, which becomes the old frame afterwards.
+    ///
+    /// Refreshes with the full waveform from OTP if a full refresh is due, see
+    /// [`FullRefreshInterval`].
+    fn update_and_display_new_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.update_new_frame(spi, buffer, delay)?;
+        if self.quick_refreshes.next_is_full() {
+            self.wait_until_idle();
+            // Enable clock and analog, load temperature and LUT, display, disable analog and clock
+            self.interface.activate_ssd1680(spi, 0xF7)?;
+            // the OTP waveform replaced the one of set_lut
+            self.set_lut(spi, None)?;
+        } else {
+            self.display_new_frame(spi, delay)?;
+        }
+        self.update_old_frame(spi, buffer, delay)
+    }
+
+    fn update_partial_old_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(span_fits(x, width, WIDTH) && span_fits(y, height, HEIGHT));
+        self.wait_until_idle();
+        self.set_ram_area(spi, x, y, x + width - 1, y + height - 1)?;
+        self.set_ram_counter(spi, x, y)?;
+
+        self.interface
+            .cmd_with_data(spi, Command::WriteRam2, buffer)
+    }
+
+    fn update_partial_new_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(span_fits(x, width, WIDTH) && span_fits(y, height, HEIGHT));
+        self.wait_until_idle();
+        self.set_ram_area(spi, x, y, x + width - 1, y + height - 1)?;
+        self.set_ram_counter(spi, x, y)?;
+
+        self.interface.cmd_with_data(spi, Command::WriteRam, buffer)
+    }
+
+    fn clear_partial_frame(
+        &mut self,
+        spi: &mut SPI,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(span_fits(x, width, WIDTH) && span_fits(y, height, HEIGHT));
+        self.wait_until_idle();
+        let color = self.background_color.get_byte_value();
+        let len = buffer_len(width as usize, height as usize) as u32;
+
+        for command in [Command::WriteRam, Command::WriteRam2] {
+            self.set_ram_area(spi, x, y, x + width - 1, y + height - 1)?;
+            self.set_ram_counter(spi, x, y)?;
+            self.interface.cmd(spi, command)?;
+            self.interface.data_x_times(spi, color, len)?;
+        }
+        Ok(())
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> RamBanks<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd1in54<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn update_back_bank(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(buffer.len() == buffer_len(WIDTH as usize, HEIGHT as usize));
+        self.wait_until_idle();
+        self.use_full_frame(spi)?;
+        // with ping-pong enabled, this always addresses the bank which isn't shown
+        self.interface.cmd_with_data(spi, Command::WriteRam, buffer)
+    }
+
+    fn update_partial_back_bank(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.update_partial_frame(spi, buffer, x, y, width, height)
+    }
+
+    fn swap_banks(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        // stays enabled until a refresh which compares against WriteRam2 turns it off
+        self.use_ping_pong(spi, true)?;
+        let mode = match self.refresh {
+            // load the waveform from OTP and display with mode 2
+            RefreshLut::Full | RefreshLut::Custom(_) => 0xFF,
+            // display with mode 2 and the partial waveform loaded by set_lut
+            RefreshLut::Quick => 0xCF,
+        };
+        self.interface.activate_ssd1680(spi, mode)
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> Epd1in54<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn wait_until_idle(&mut self) {
+        self.interface.wait_until_idle(IS_BUSY_LOW);
+    }
+
+    /// Enables or disables the ping-pong of the RAM banks, if it isn't already
+    fn use_ping_pong(&mut self, spi: &mut SPI, enabled: bool) -> Result<(), Error<SPI::Error>> {
+        if self.ping_pong != enabled {
+            self.interface.ping_pong_ssd1680(spi, enabled)?;
+            self.ping_pong = enabled;
+        }
+        Ok(())
+    }
+
+    fn use_full_frame(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        // choose full frame/ram
+        self.set_ram_area(spi, 0, 0, WIDTH - 1, HEIGHT - 1)?;
+
+        // start from the beginning
+        self.set_ram_counter(spi, 0, 0)
+    }
+
+    fn set_ram_area(
+        &mut self,
+        spi: &mut SPI,
+        start_x: u32,
+        start_y: u32,
+        end_x: u32,
+        end_y: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        let (start_x, end_x) = (start_x + self.offset.source, end_x + self.offset.source);
+        let (start_y, end_y) = (start_y + self.offset.gate, end_y + self.offset.gate);
+        self.interface
+            .window_ssd1680(spi, start_x, start_y, end_x, end_y)
+    }
+
+    fn set_ram_counter(&mut self, spi: &mut SPI, x: u32, y: u32) -> Result<(), Error<SPI::Error>> {
+        let (x, y) = (x + self.offset.source, y + self.offset.gate);
+        self.interface.cursor_ssd1680(spi, x, y)
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> ReadRevision<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd1in54<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8> + Transfer<u8, Error = <SPI as Write<u8>>::Error>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn read_revision(
+        &mut self,
+        spi: &mut SPI,
+    ) -> Result<Revision, Error<<SPI as Write<u8>>::Error>> {
+        self.interface.revision_ssd1680(spi)
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> ReadTemperature<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd1in54<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8> + Transfer<u8, Error = <SPI as Write<u8>>::Error>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn read_temperature(&mut self, spi: &mut SPI) -> Result<i8, Error<<SPI as Write<u8>>::Error>> {
+        self.interface.temperature_ssd1680(spi)
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> FullRefreshInterval<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd1in54<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn set_full_refresh_every(&mut self, updates: u16) {
+        self.quick_refreshes.set_full_every(updates);
+    }
+
+    fn quick_refreshes(&self) -> u16 {
+        self.quick_refreshes.since_full()
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> CustomLut<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd1in54<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    type Lut = ssd1680::Lut;
+
+    /// Replaces the waveform of the current [`RefreshLut`], the quick refreshes run it
+    /// with the display mode 2.
+    fn load_lut(&mut self, spi: &mut SPI, lut: &ssd1680::Lut) -> Result<(), Error<SPI::Error>> {
+        self.interface.waveform_ssd1681(spi, &lut.to_bytes())?;
+        self.custom_lut = true;
+        Ok(())
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> BorderControl<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd1in54<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    /// The partial waveform keeps the border at VCOM, so it doesn't flash. Setting the
+    /// border after selecting `RefreshLut::Quick` drives it anyway, until the partial
+    /// waveform is loaded again by `set_lut` or `wake_up`.
+    fn set_border(&mut self, spi: &mut SPI, border: BorderColor) -> Result<(), Error<SPI::Error>> {
+        self.border = match border {
+            // follow the waveform of black or white pixels
+            BorderColor::Black => 0x00,
+            BorderColor::White => 0x01,
+            // high impedance
+            BorderColor::Floating => 0xC0,
+        };
+        self.wait_until_idle();
+        self.interface.border_ssd1680(spi, self.border)
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> GlassOffset<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd1in54<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn set_panel_offset(&mut self, offset: PanelOffset) {
+        self.offset = offset;
+    }
+
+    fn panel_offset(&self) -> PanelOffset {
+        self.offset
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> HardwareMirror<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd1in54<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn set_mirror(&mut self, spi: &mut SPI, mirror: Mirror) -> Result<(), Error<SPI::Error>> {
+        // the sources can't be shifted the other way
+        ensure!(!mirror.horizontal());
+        self.mirror = mirror;
+        self.wait_until_idle();
+        self.interface.gates_ssd1681(spi, HEIGHT, mirror.vertical())
+    }
+
+    fn mirror(&self) -> Mirror {
+        self.mirror
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> SleepMode<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd1in54<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn set_sleep_mode(&mut self, mode: DeepSleepMode) {
+        self.sleep_mode = mode;
+    }
+
+    fn sleep_mode(&self) -> DeepSleepMode {
+        self.sleep_mode
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus_log::{commands, data_of, Log, Pin, Spi};
+    use embedded_hal_mock::delay::MockNoop;
+
+    #[test]
+    fn epd_size() {
+        assert_eq!(WIDTH, 200);
+        assert_eq!(HEIGHT, 200);
+        assert_eq!(DEFAULT_BACKGROUND_COLOR, Color::White);
+    }
+
+    #[test]
+    fn partial_refresh_after_swap_disables_ping_pong() {
+        let log = Log::default();
+        let mut spi = Spi(&log);
+        let mut delay = MockNoop::new();
+        let mut epd = Epd1in54::new(
+            &mut spi,
+            Pin(None),
+            Pin(None),
+            Pin(Some(&log)),
+            Pin(None),
+            &mut delay,
+        )
+        .unwrap();
+        let frame = [0xFF; WIDTH as usize / 8 * HEIGHT as usize];
+
+        log.take();
+        epd.update_back_bank(&mut spi, &frame, &mut delay).unwrap();
+        epd.swap_banks(&mut spi, &mut delay).unwrap();
+        let events = log.take();
+        assert_eq!(data_of(&events, 0x37), [[0, 0, 0, 0, 0, 0x40, 0, 0, 0, 0]]);
+
+        // a second swap keeps it enabled
+        epd.swap_banks(&mut spi, &mut delay).unwrap();
+        assert!(data_of(&log.take(), 0x37).is_empty());
+
+        epd.update_new_frame(&mut spi, &frame, &mut delay).unwrap();
+        epd.display_new_frame(&mut spi, &mut delay).unwrap();
+        let events = log.take();
+        assert_eq!(data_of(&events, 0x37), [[0; 10]]);
+        let commands = commands(&events);
+        let ping_pong = commands.iter().position(|&c| c == 0x37).unwrap();
+        let activation = commands.iter().position(|&c| c == 0x20).unwrap();
+        assert!(ping_pong < activation);
+    }
+}
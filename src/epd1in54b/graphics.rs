@@ -1,28 +1,30 @@
-use crate::epd1in54b::{DEFAULT_BACKGROUND_COLOR, HEIGHT, WIDTH};
-use crate::graphics::{Display, DisplayRotation};
-use embedded_graphics::pixelcolor::BinaryColor;
+use crate::color::TriColor;
+use crate::epd1in54b::{DEFAULT_BACKGROUND_COLOR, HEIGHT, NUM_DISPLAY_BITS, WIDTH};
+use crate::graphics::{DisplayRotation, TriDisplay};
 use embedded_graphics_core::prelude::*;
 
-/// Full size buffer for use with the 1in54 EPD
+/// Full size buffer for use with the 1in54b EPD
 ///
 /// Can also be manually constructed and be used together with VarDisplay
 pub struct Display1in54b {
-    buffer: [u8; WIDTH as usize * HEIGHT as usize / 8],
+    // one buffer for both b/w and for chromatic:
+    // * &buffer[0..NUM_DISPLAY_BITS] for b/w buffer and
+    // * &buffer[NUM_DISPLAY_BITS..2*NUM_DISPLAY_BITS] for chromatic buffer
+    buffer: [u8; 2 * NUM_DISPLAY_BITS as usize],
     rotation: DisplayRotation,
 }
 
 impl Default for Display1in54b {
     fn default() -> Self {
         Display1in54b {
-            buffer: [DEFAULT_BACKGROUND_COLOR.get_byte_value();
-                WIDTH as usize * HEIGHT as usize / 8],
+            buffer: [DEFAULT_BACKGROUND_COLOR.get_byte_value(); 2 * NUM_DISPLAY_BITS as usize],
             rotation: DisplayRotation::default(),
         }
     }
 }
 
 impl DrawTarget for Display1in54b {
-    type Color = BinaryColor;
+    type Color = TriColor;
     type Error = core::convert::Infallible;
 
     fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
@@ -30,7 +32,7 @@ impl DrawTarget for Display1in54b {
         I: IntoIterator<Item = Pixel<Self::Color>>,
     {
         for pixel in pixels {
-            self.draw_helper(WIDTH, HEIGHT, pixel)?;
+            self.draw_helper_tri(WIDTH, HEIGHT, pixel)?;
         }
         Ok(())
     }
@@ -42,7 +44,7 @@ impl OriginDimensions for Display1in54b {
     }
 }
 
-impl Display for Display1in54b {
+impl TriDisplay for Display1in54b {
     fn buffer(&self) -> &[u8] {
         &self.buffer
     }
@@ -58,4 +60,61 @@ impl Display for Display1in54b {
     fn rotation(&self) -> DisplayRotation {
         self.rotation
     }
+
+    fn chromatic_offset(&self) -> usize {
+        NUM_DISPLAY_BITS as usize
+    }
+
+    fn bw_buffer(&self) -> &[u8] {
+        &self.buffer[0..self.chromatic_offset()]
+    }
+
+    fn chromatic_buffer(&self) -> &[u8] {
+        &self.buffer[self.chromatic_offset()..]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_graphics::{
+        prelude::*,
+        primitives::{Line, PrimitiveStyle},
+    };
+
+    // test buffer length
+    #[test]
+    fn graphics_size() {
+        let display = Display1in54b::default();
+        assert_eq!(display.buffer().len(), 2 * 5000);
+        assert_eq!(display.bw_buffer().len(), 5000);
+        assert_eq!(display.chromatic_buffer().len(), 5000);
+    }
+
+    // test default background color on all bytes
+    #[test]
+    fn graphics_default() {
+        let display = Display1in54b::default();
+        for &byte in display.buffer() {
+            assert_eq!(byte, DEFAULT_BACKGROUND_COLOR.get_byte_value());
+        }
+    }
+
+    #[test]
+    fn graphics_black_and_chromatic() {
+        let mut display = Display1in54b::default();
+        let _ = Line::new(Point::new(0, 0), Point::new(7, 0))
+            .into_styled(PrimitiveStyle::with_stroke(TriColor::Black, 1))
+            .draw(&mut display);
+        let _ = Line::new(Point::new(8, 0), Point::new(15, 0))
+            .into_styled(PrimitiveStyle::with_stroke(TriColor::Chromatic, 1))
+            .draw(&mut display);
+
+        // chromatic pixels are white on the b/w plane and vice versa
+        assert_eq!(&display.bw_buffer()[..2], &[0x00, 0xFF]);
+        assert_eq!(&display.chromatic_buffer()[..2], &[0xFF, 0x00]);
+        for &byte in display.bw_buffer().iter().skip(2) {
+            assert_eq!(byte, DEFAULT_BACKGROUND_COLOR.get_byte_value());
+        }
+    }
 }
@@ -1,4 +1,57 @@
 //! A simple Driver for the Waveshare 1.54" (B) E-Ink Display via SPI
+//!
+//! The black/white plane is transmitted with two bits per pixel, followed by the
+//! red plane with one bit per pixel.
+//!
+//! [Documentation](https://www.waveshare.com/wiki/1.54inch_e-Paper_Module_(B))
+//!
+//! # Example for the 1.54" (B) E-Ink Display
+//!
+//!```rust, no_run
+//!# use embedded_hal_mock::*;
+//!# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
+//!use embedded_graphics::{prelude::*, primitives::{Line, PrimitiveStyle}};
+//!use epd_waveshare::{epd1in54b::*, prelude::*};
+//!#
+//!# let expectations = [];
+//!# let mut spi = spi::Mock::new(&expectations);
+//!# let expectations = [];
+//!# let cs_pin = pin::Mock::new(&expectations);
+//!# let busy_in = pin::Mock::new(&expectations);
+//!# let dc = pin::Mock::new(&expectations);
+//!# let rst = pin::Mock::new(&expectations);
+//!# let mut delay = delay::MockNoop::new();
+//!
+//!// Setup EPD
+//!let mut epd = Epd1in54b::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
+//!
+//!// Use display graphics from embedded-graphics
+//!// This display is for the black/white/chromatic pixels
+//!let mut tricolor_display = Display1in54b::default();
+//!
+//!// Use embedded graphics for drawing a black line
+//!let _ = Line::new(Point::new(0, 120), Point::new(0, 199))
+//!    .into_styled(PrimitiveStyle::with_stroke(TriColor::Black, 1))
+//!    .draw(&mut tricolor_display);
+//!
+//!// We use `chromatic` but it will be shown as red
+//!let _ = Line::new(Point::new(15, 120), Point::new(15, 199))
+//!    .into_styled(PrimitiveStyle::with_stroke(TriColor::Chromatic, 1))
+//!    .draw(&mut tricolor_display);
+//!
+//!// Display updated frame
+//!epd.update_color_frame(
+//!    &mut spi,
+//!    tricolor_display.bw_buffer(),
+//!    tricolor_display.chromatic_buffer()
+//!)?;
+//!epd.display_frame(&mut spi, &mut delay)?;
+//!
+//!// Set the EPD to sleep
+//!epd.sleep(&mut spi, &mut delay)?;
+//!# Ok(())
+//!# }
+//!```
 
 use embedded_hal::{
     blocking::{delay::*, spi::Write},
@@ -7,8 +60,10 @@ use embedded_hal::{
 
 use crate::interface::DisplayInterface;
 use crate::traits::{
-    InternalWiAdditions, RefreshLut, WaveshareDisplay, WaveshareThreeColorDisplay,
+    FrameRate, FrameRateControl, InternalWiAdditions, RefreshLut, WaveshareDisplay,
+    WaveshareThreeColorDisplay,
 };
+use crate::Error;
 
 //The Lookup Tables for the Display
 mod constants;
@@ -19,10 +74,15 @@ pub const WIDTH: u32 = 200;
 /// Height of epd1in54 in pixels
 pub const HEIGHT: u32 = 200;
 /// Default Background Color (white)
-pub const DEFAULT_BACKGROUND_COLOR: Color = Color::White;
+pub const DEFAULT_BACKGROUND_COLOR: TriColor = TriColor::White;
 const IS_BUSY_LOW: bool = true;
+/// Frame rate of the vendor driver
+const DEFAULT_FRAME_RATE: FrameRate = FrameRate::Hz200;
 
-use crate::color::Color;
+/// Number of bytes for b/w buffer and same for chromatic buffer
+const NUM_DISPLAY_BITS: u32 = WIDTH * HEIGHT / 8;
+
+use crate::color::TriColor;
 
 pub(crate) mod command;
 use self::command::Command;
@@ -35,7 +95,8 @@ pub use self::graphics::Display1in54b;
 /// Epd1in54b driver
 pub struct Epd1in54b<SPI, CS, BUSY, DC, RST, DELAY> {
     interface: DisplayInterface<SPI, CS, BUSY, DC, RST, DELAY>,
-    color: Color,
+    color: TriColor,
+    frame_rate: FrameRate,
 }
 
 impl<SPI, CS, BUSY, DC, RST, DELAY> InternalWiAdditions<SPI, CS, BUSY, DC, RST, DELAY>
@@ -48,7 +109,7 @@ where
     RST: OutputPin,
     DELAY: DelayMs<u8>,
 {
-    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         self.interface.reset(delay, 10);
 
         // set the power settings
@@ -70,7 +131,7 @@ where
         self.cmd_with_data(spi, Command::VcomAndDataIntervalSetting, &[0x37])?;
 
         // PLL
-        self.cmd_with_data(spi, Command::PllControl, &[0x39])?;
+        self.cmd_with_data(spi, Command::PllControl, &[self.frame_rate as u8])?;
 
         // set resolution
         self.send_resolution(spi)?;
@@ -100,12 +161,16 @@ where
         spi: &mut SPI,
         black: &[u8],
         chromatic: &[u8],
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.update_achromatic_frame(spi, black)?;
         self.update_chromatic_frame(spi, chromatic)
     }
 
-    fn update_achromatic_frame(&mut self, spi: &mut SPI, black: &[u8]) -> Result<(), SPI::Error> {
+    fn update_achromatic_frame(
+        &mut self,
+        spi: &mut SPI,
+        black: &[u8],
+    ) -> Result<(), Error<SPI::Error>> {
         self.wait_until_idle();
         self.send_resolution(spi)?;
 
@@ -122,7 +187,7 @@ where
         &mut self,
         spi: &mut SPI,
         chromatic: &[u8],
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.interface.cmd(spi, Command::DataStartTransmission2)?;
         self.interface.data(spi, chromatic)?;
         Ok(())
@@ -139,7 +204,7 @@ where
     RST: OutputPin,
     DELAY: DelayMs<u8>,
 {
-    type DisplayColor = Color;
+    type DisplayColor = TriColor;
     fn new(
         spi: &mut SPI,
         cs: CS,
@@ -147,18 +212,22 @@ where
         dc: DC,
         rst: RST,
         delay: &mut DELAY,
-    ) -> Result<Self, SPI::Error> {
+    ) -> Result<Self, Error<SPI::Error>> {
         let interface = DisplayInterface::new(cs, busy, dc, rst);
         let color = DEFAULT_BACKGROUND_COLOR;
 
-        let mut epd = Epd1in54b { interface, color };
+        let mut epd = Epd1in54b {
+            interface,
+            color,
+            frame_rate: DEFAULT_FRAME_RATE,
+        };
 
         epd.init(spi, delay)?;
 
         Ok(epd)
     }
 
-    fn sleep(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn sleep(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         self.wait_until_idle();
         self.interface
             .cmd_with_data(spi, Command::VcomAndDataIntervalSetting, &[0x17])?; //border floating
@@ -178,15 +247,15 @@ where
         Ok(())
     }
 
-    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         self.init(spi, delay)
     }
 
-    fn set_background_color(&mut self, color: Color) {
+    fn set_background_color(&mut self, color: TriColor) {
         self.color = color;
     }
 
-    fn background_color(&self) -> &Color {
+    fn background_color(&self) -> &TriColor {
         &self.color
     }
 
@@ -203,7 +272,7 @@ where
         spi: &mut SPI,
         buffer: &[u8],
         _delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.wait_until_idle();
         self.send_resolution(spi)?;
 
@@ -217,12 +286,9 @@ where
 
         //NOTE: Example code has a delay here
 
-        // Clear the read layer
-        let color = self.color.get_byte_value();
-        let nbits = WIDTH * (HEIGHT / 8);
-
+        // Clear the red layer, a cleared bit would be red
         self.interface.cmd(spi, Command::DataStartTransmission2)?;
-        self.interface.data_x_times(spi, color, nbits)?;
+        self.interface.data_x_times(spi, 0xFF, NUM_DISPLAY_BITS)?;
 
         //NOTE: Example code has a delay here
         Ok(())
@@ -237,11 +303,20 @@ where
         y: u32,
         width: u32,
         height: u32,
-    ) -> Result<(), SPI::Error> {
-        unimplemented!()
+    ) -> Result<(), Error<SPI::Error>> {
+        unsupported!()
+    }
+
+    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        // doesn't wait for the refresh, the next command does
+        self.start_display_frame(spi, delay)
     }
 
-    fn display_frame(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn start_display_frame(
+        &mut self,
+        spi: &mut SPI,
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
         self.wait_until_idle();
         self.command(spi, Command::DisplayRefresh)?;
         Ok(())
@@ -252,37 +327,43 @@ where
         spi: &mut SPI,
         buffer: &[u8],
         delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.update_frame(spi, buffer, delay)?;
         self.display_frame(spi, delay)?;
         Ok(())
     }
 
-    fn clear_frame(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn clear_frame(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         self.wait_until_idle();
         self.send_resolution(spi)?;
 
-        let color = DEFAULT_BACKGROUND_COLOR.get_byte_value();
+        // A cleared bit means black on the first and red on the second layer
+        let (black, chromatic) = match self.color {
+            TriColor::White => (0xFF, 0xFF),
+            TriColor::Black => (0x00, 0xFF),
+            TriColor::Chromatic => (0xFF, 0x00),
+        };
 
-        // Clear the black
+        // Clear the black, uses 2 bits per pixel
         self.interface.cmd(spi, Command::DataStartTransmission1)?;
-
-        // Uses 2 bits per pixel
         self.interface
-            .data_x_times(spi, color, 2 * (WIDTH * HEIGHT / 8))?;
+            .data_x_times(spi, black, 2 * NUM_DISPLAY_BITS)?;
 
         // Clear the red
         self.interface.cmd(spi, Command::DataStartTransmission2)?;
         self.interface
-            .data_x_times(spi, color, WIDTH * HEIGHT / 8)?;
+            .data_x_times(spi, chromatic, NUM_DISPLAY_BITS)?;
         Ok(())
     }
 
     fn set_lut(
         &mut self,
         spi: &mut SPI,
-        _refresh_rate: Option<RefreshLut>,
-    ) -> Result<(), SPI::Error> {
+        refresh_rate: Option<RefreshLut>,
+    ) -> Result<(), Error<SPI::Error>> {
+        if let Some(RefreshLut::Custom(_)) = refresh_rate {
+            return Err(Error::Unsupported);
+        }
         self.interface
             .cmd_with_data(spi, Command::LutForVcom, LUT_VCOM0)?;
         self.interface
@@ -306,6 +387,27 @@ where
     }
 }
 
+impl<SPI, CS, BUSY, DC, RST, DELAY> FrameRateControl<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd1in54b<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn set_frame_rate(&mut self, spi: &mut SPI, rate: FrameRate) -> Result<(), Error<SPI::Error>> {
+        self.frame_rate = rate;
+        self.wait_until_idle();
+        self.cmd_with_data(spi, Command::PllControl, &[rate as u8])
+    }
+
+    fn frame_rate(&self) -> FrameRate {
+        self.frame_rate
+    }
+}
+
 impl<SPI, CS, BUSY, DC, RST, DELAY> Epd1in54b<SPI, CS, BUSY, DC, RST, DELAY>
 where
     SPI: Write<u8>,
@@ -315,11 +417,11 @@ where
     RST: OutputPin,
     DELAY: DelayMs<u8>,
 {
-    fn command(&mut self, spi: &mut SPI, command: Command) -> Result<(), SPI::Error> {
+    fn command(&mut self, spi: &mut SPI, command: Command) -> Result<(), Error<SPI::Error>> {
         self.interface.cmd(spi, command)
     }
 
-    fn send_data(&mut self, spi: &mut SPI, data: &[u8]) -> Result<(), SPI::Error> {
+    fn send_data(&mut self, spi: &mut SPI, data: &[u8]) -> Result<(), Error<SPI::Error>> {
         self.interface.data(spi, data)
     }
 
@@ -328,15 +430,15 @@ where
         spi: &mut SPI,
         command: Command,
         data: &[u8],
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.interface.cmd_with_data(spi, command, data)
     }
 
     fn wait_until_idle(&mut self) {
-        let _ = self.interface.wait_until_idle(IS_BUSY_LOW);
+        self.interface.wait_until_idle(IS_BUSY_LOW);
     }
 
-    fn send_resolution(&mut self, spi: &mut SPI) -> Result<(), SPI::Error> {
+    fn send_resolution(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
         let w = self.width();
         let h = self.height();
 
@@ -367,6 +469,6 @@ mod tests {
     fn epd_size() {
         assert_eq!(WIDTH, 200);
         assert_eq!(HEIGHT, 200);
-        assert_eq!(DEFAULT_BACKGROUND_COLOR, Color::White);
+        assert_eq!(DEFAULT_BACKGROUND_COLOR, TriColor::White);
     }
 }
@@ -1,14 +1,66 @@
 //! A simple Driver for the Waveshare 1.54" (C) E-Ink Display via SPI
+//!
+//! The (C) variant is a black/white/yellow panel with a resolution of 152x152 and a
+//! different controller than the 1.54" (B), so it has its own module. Both layers are
+//! transmitted with one bit per pixel, a cleared bit is black or yellow.
+//!
+//! [Documentation](https://www.waveshare.com/wiki/1.54inch_e-Paper_Module_(C))
+//!
+//! # Example for the 1.54" (C) E-Ink Display
+//!
+//!```rust, no_run
+//!# use embedded_hal_mock::*;
+//!# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
+//!use embedded_graphics::{
+//!    pixelcolor::BinaryColor::On as Black, prelude::*, primitives::{Line, PrimitiveStyle},
+//!};
+//!use epd_waveshare::{epd1in54c::*, prelude::*};
+//!#
+//!# let expectations = [];
+//!# let mut spi = spi::Mock::new(&expectations);
+//!# let expectations = [];
+//!# let cs_pin = pin::Mock::new(&expectations);
+//!# let busy_in = pin::Mock::new(&expectations);
+//!# let dc = pin::Mock::new(&expectations);
+//!# let rst = pin::Mock::new(&expectations);
+//!# let mut delay = delay::MockNoop::new();
+//!
+//!// Setup EPD
+//!let mut epd = Epd1in54c::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
+//!
+//!// One buffer for the black and one for the yellow pixels
+//!let mut mono_display = Display1in54c::default();
+//!let mut chromatic_display = Display1in54c::default();
+//!
+//!let _ = Line::new(Point::new(0, 120), Point::new(0, 151))
+//!    .into_styled(PrimitiveStyle::with_stroke(Black, 1))
+//!    .draw(&mut mono_display);
+//!
+//!// "Black" in the chromatic buffer is shown as yellow
+//!let _ = Line::new(Point::new(15, 120), Point::new(15, 151))
+//!    .into_styled(PrimitiveStyle::with_stroke(Black, 1))
+//!    .draw(&mut chromatic_display);
+//!
+//!epd.update_color_frame(&mut spi, mono_display.buffer(), chromatic_display.buffer())?;
+//!epd.display_frame(&mut spi, &mut delay)?;
+//!
+//!// Set the EPD to sleep
+//!epd.sleep(&mut spi, &mut delay)?;
+//!# Ok(())
+//!# }
+//!```
 
 use embedded_hal::{
     blocking::{delay::*, spi::Write},
     digital::v2::*,
 };
 
+use crate::controllers::uc8151::{Command, Frame, IS_BUSY_LOW};
 use crate::interface::DisplayInterface;
 use crate::traits::{
     InternalWiAdditions, RefreshLut, WaveshareDisplay, WaveshareThreeColorDisplay,
 };
+use crate::Error;
 
 /// Width of epd1in54 in pixels
 pub const WIDTH: u32 = 152;
@@ -16,14 +68,10 @@ pub const WIDTH: u32 = 152;
 pub const HEIGHT: u32 = 152;
 /// Default Background Color (white)
 pub const DEFAULT_BACKGROUND_COLOR: Color = Color::White;
-const IS_BUSY_LOW: bool = true;
 const NUM_DISPLAY_BITS: u32 = WIDTH * HEIGHT / 8;
 
 use crate::color::Color;
 
-pub(crate) mod command;
-use self::command::Command;
-
 #[cfg(feature = "graphics")]
 mod graphics;
 
@@ -46,28 +94,24 @@ where
     RST: OutputPin,
     DELAY: DelayMs<u8>,
 {
-    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         // Based on Reference Program Code from:
         // https://www.waveshare.com/w/upload/a/ac/1.54inch_e-Paper_Module_C_Specification.pdf
         // and:
         // https://github.com/waveshare/e-Paper/blob/master/STM32/STM32-F103ZET6/User/e-Paper/EPD_1in54c.c
         self.interface.reset(delay, 2);
 
-        // start the booster
-        self.cmd_with_data(spi, Command::BoosterSoftStart, &[0x17, 0x17, 0x17])?;
-
-        // power on
-        self.command(spi, Command::PowerOn)?;
-        delay.delay_ms(5);
-        self.wait_until_idle();
+        self.interface
+            .power_on_uc8151(spi, delay, [0x17, 0x17, 0x17])?;
 
         // set the panel settings
-        self.cmd_with_data(spi, Command::PanelSetting, &[0x0f, 0x0d])?;
+        self.interface
+            .cmd_with_data(spi, Command::PanelSetting, &[0x0f, 0x0d])?;
 
-        // set resolution
-        self.send_resolution(spi)?;
+        self.interface.resolution_uc8151(spi, WIDTH, HEIGHT)?;
 
-        self.cmd_with_data(spi, Command::VcomAndDataIntervalSetting, &[0x77])?;
+        self.interface
+            .cmd_with_data(spi, Command::VcomAndDataIntervalSetting, &[0x77])?;
 
         Ok(())
     }
@@ -88,27 +132,28 @@ where
         spi: &mut SPI,
         black: &[u8],
         chromatic: &[u8],
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.update_achromatic_frame(spi, black)?;
         self.update_chromatic_frame(spi, chromatic)
     }
 
-    fn update_achromatic_frame(&mut self, spi: &mut SPI, black: &[u8]) -> Result<(), SPI::Error> {
+    fn update_achromatic_frame(
+        &mut self,
+        spi: &mut SPI,
+        black: &[u8],
+    ) -> Result<(), Error<SPI::Error>> {
         self.wait_until_idle();
-        self.cmd_with_data(spi, Command::DataStartTransmission1, black)?;
-
-        Ok(())
+        self.interface.frame_uc8151(spi, Frame::Black, black)
     }
 
     fn update_chromatic_frame(
         &mut self,
         spi: &mut SPI,
         chromatic: &[u8],
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.wait_until_idle();
-        self.cmd_with_data(spi, Command::DataStartTransmission2, chromatic)?;
-
-        Ok(())
+        self.interface
+            .frame_uc8151(spi, Frame::Chromatic, chromatic)
     }
 }
 
@@ -130,7 +175,7 @@ where
         dc: DC,
         rst: RST,
         delay: &mut DELAY,
-    ) -> Result<Self, SPI::Error> {
+    ) -> Result<Self, Error<SPI::Error>> {
         let interface = DisplayInterface::new(cs, busy, dc, rst);
         let color = DEFAULT_BACKGROUND_COLOR;
 
@@ -141,17 +186,12 @@ where
         Ok(epd)
     }
 
-    fn sleep(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), SPI::Error> {
-        self.wait_until_idle();
-
-        self.command(spi, Command::PowerOff)?;
+    fn sleep(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         self.wait_until_idle();
-        self.cmd_with_data(spi, Command::DeepSleep, &[0xa5])?;
-
-        Ok(())
+        self.interface.sleep_uc8151(spi)
     }
 
-    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         self.init(spi, delay)
     }
 
@@ -176,14 +216,12 @@ where
         spi: &mut SPI,
         buffer: &[u8],
         _delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.update_achromatic_frame(spi, buffer)?;
 
-        // Clear the chromatic layer
-        let color = self.color.get_byte_value();
-
-        self.command(spi, Command::DataStartTransmission2)?;
-        self.interface.data_x_times(spi, color, NUM_DISPLAY_BITS)?;
+        // Clear the chromatic layer, a cleared bit would be yellow
+        self.interface
+            .fill_uc8151(spi, Frame::Chromatic, 0xFF, NUM_DISPLAY_BITS)?;
 
         Ok(())
     }
@@ -197,15 +235,24 @@ where
         y: u32,
         width: u32,
         height: u32,
-    ) -> Result<(), SPI::Error> {
-        unimplemented!()
+    ) -> Result<(), Error<SPI::Error>> {
+        unsupported!()
     }
 
-    fn display_frame(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), SPI::Error> {
-        self.command(spi, Command::DisplayRefresh)?;
-        self.wait_until_idle();
+    fn display_frame(
+        &mut self,
+        spi: &mut SPI,
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.interface.refresh_uc8151(spi)
+    }
 
-        Ok(())
+    fn start_display_frame(
+        &mut self,
+        spi: &mut SPI,
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.interface.start_refresh_uc8151(spi)
     }
 
     fn update_and_display_frame(
@@ -213,24 +260,24 @@ where
         spi: &mut SPI,
         buffer: &[u8],
         delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.update_frame(spi, buffer, delay)?;
         self.display_frame(spi, delay)?;
 
         Ok(())
     }
 
-    fn clear_frame(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn clear_frame(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         self.wait_until_idle();
         let color = DEFAULT_BACKGROUND_COLOR.get_byte_value();
 
         // Clear the black
-        self.command(spi, Command::DataStartTransmission1)?;
-        self.interface.data_x_times(spi, color, NUM_DISPLAY_BITS)?;
+        self.interface
+            .fill_uc8151(spi, Frame::Black, color, NUM_DISPLAY_BITS)?;
 
         // Clear the chromatic
-        self.command(spi, Command::DataStartTransmission2)?;
-        self.interface.data_x_times(spi, color, NUM_DISPLAY_BITS)?;
+        self.interface
+            .fill_uc8151(spi, Frame::Chromatic, color, NUM_DISPLAY_BITS)?;
 
         Ok(())
     }
@@ -238,8 +285,11 @@ where
     fn set_lut(
         &mut self,
         _spi: &mut SPI,
-        _refresh_rate: Option<RefreshLut>,
-    ) -> Result<(), SPI::Error> {
+        refresh_rate: Option<RefreshLut>,
+    ) -> Result<(), Error<SPI::Error>> {
+        if let Some(RefreshLut::Custom(_)) = refresh_rate {
+            return Err(Error::Unsupported);
+        }
         Ok(())
     }
 
@@ -257,44 +307,40 @@ where
     RST: OutputPin,
     DELAY: DelayMs<u8>,
 {
-    fn command(&mut self, spi: &mut SPI, command: Command) -> Result<(), SPI::Error> {
-        self.interface.cmd(spi, command)
-    }
-
-    fn send_data(&mut self, spi: &mut SPI, data: &[u8]) -> Result<(), SPI::Error> {
-        self.interface.data(spi, data)
+    fn wait_until_idle(&mut self) {
+        self.interface.wait_until_idle(IS_BUSY_LOW);
     }
 
-    fn cmd_with_data(
+    /// Updates only the chromatic pixels in the `width` x `height` window at `x`, `y`
+    ///
+    /// The black/white frame and the chromatic pixels outside of the window stay in the
+    /// memory of the controller, so e.g. a yellow badge can change without sending the
+    /// black content again. The next `display_frame` shows it. `x` and `width` have to
+    /// be multiples of 8.
+    pub fn update_chromatic_partial_frame(
         &mut self,
         spi: &mut SPI,
-        command: Command,
-        data: &[u8],
-    ) -> Result<(), SPI::Error> {
-        self.interface.cmd_with_data(spi, command, data)
+        chromatic: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        self.interface
+            .partial_frame_uc8151(spi, Frame::Chromatic, chromatic, x, y, width, height)
     }
+}
 
-    fn wait_until_idle(&mut self) {
-        let _ = self.interface.wait_until_idle(IS_BUSY_LOW);
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    fn send_resolution(&mut self, spi: &mut SPI) -> Result<(), SPI::Error> {
-        let w = self.width();
-        let h = self.height();
-
-        self.command(spi, Command::ResolutionSetting)?;
-
-        // | D7 | D6 | D5 | D4 | D3 | D2 | D1 | D0 |
-        // |       HRES[7:3]        |  0 |  0 |  0 |
-        self.send_data(spi, &[(w as u8) & 0b1111_1000])?;
-        // | D7 | D6 | D5 | D4 | D3 | D2 | D1 |      D0 |
-        // |  - |  - |  - |  - |  - |  - |  - | VRES[8] |
-        self.send_data(spi, &[(w >> 8) as u8])?;
-        // | D7 | D6 | D5 | D4 | D3 | D2 | D1 |      D0 |
-        // |                  VRES[7:0]                 |
-        // Specification shows C/D is zero while sending the last byte,
-        // but upstream code does not implement it like that. So for now
-        // we follow upstream code.
-        self.send_data(spi, &[h as u8])
+    #[test]
+    fn epd_size() {
+        assert_eq!(WIDTH, 152);
+        assert_eq!(HEIGHT, 152);
+        assert_eq!(NUM_DISPLAY_BITS, 2888);
+        assert_eq!(DEFAULT_BACKGROUND_COLOR, Color::White);
     }
 }
@@ -89,7 +89,6 @@ impl DriverOutput {
 ///  | | `------------- load temp
 ///  | `--------------- enable clock
 ///  `----------------- enable analog
-
 pub(crate) struct DisplayUpdateControl2(pub u8);
 #[allow(dead_code)]
 impl DisplayUpdateControl2 {
@@ -133,8 +132,7 @@ impl DisplayUpdateControl2 {
     }
 }
 
-#[allow(dead_code)]
-
+#[allow(dead_code, clippy::enum_variant_names)]
 pub(crate) enum DataEntryModeIncr {
     XDecrYDecr = 0x0,
     XIncrYDecr = 0x1,
@@ -143,7 +141,6 @@ pub(crate) enum DataEntryModeIncr {
 }
 
 #[allow(dead_code)]
-
 pub(crate) enum DataEntryModeDir {
     XDir = 0x0,
     YDir = 0x4,
@@ -189,19 +186,6 @@ impl BorderWaveForm {
     }
 }
 
-#[allow(dead_code)]
-#[derive(Copy, Clone)]
-pub enum DeepSleepMode {
-    // Sleeps and keeps access to RAM and controller
-    Normal = 0x00,
-
-    // Sleeps without access to RAM/controller but keeps RAM content
-    Mode1 = 0x01,
-
-    // Same as MODE_1 but RAM content is not kept
-    Mode2 = 0x11,
-}
-
 pub(crate) struct GateDrivingVoltage(pub u8);
 pub(crate) struct SourceDrivingVoltage(pub u8);
 pub(crate) struct Vcom(pub u8);
@@ -216,6 +200,7 @@ impl I32Ext for i32 {
     // This is really not very nice. Until I find something better, this will be
     // a placeholder.
     fn vcom(self) -> Vcom {
+        #[cfg(not(feature = "panic_free"))]
         assert!((-30..=-2).contains(&self));
         let u = match -self {
             2 => 0x08,
@@ -253,11 +238,13 @@ impl I32Ext for i32 {
     }
 
     fn gate_driving_decivolt(self) -> GateDrivingVoltage {
+        #[cfg(not(feature = "panic_free"))]
         assert!((100..=210).contains(&self) && self % 5 == 0);
         GateDrivingVoltage(((self - 100) / 5 + 0x03) as u8)
     }
 
     fn source_driving_decivolt(self) -> SourceDrivingVoltage {
+        #[cfg(not(feature = "panic_free"))]
         assert!((24..=88).contains(&self) || (self % 5 == 0 && (90..=180).contains(&self.abs())));
 
         if (24..=88).contains(&self) {
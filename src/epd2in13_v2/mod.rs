@@ -13,16 +13,20 @@ use embedded_hal::{
     digital::v2::{InputPin, OutputPin},
 };
 
-use crate::buffer_len;
 use crate::color::Color;
 use crate::interface::DisplayInterface;
-use crate::traits::{InternalWiAdditions, RefreshLut, WaveshareDisplay};
+use crate::luts::{LutPreset, Panel};
+use crate::traits::{
+    DeepSleepMode, GlassOffset, InternalWiAdditions, PanelOffset, QuickRefresh, RefreshLut,
+    SleepMode, WaveshareDisplay,
+};
+use crate::{buffer_len, span_fits, Error};
 
 pub(crate) mod command;
 use self::command::{
     BorderWaveForm, BorderWaveFormFixLevel, BorderWaveFormGs, BorderWaveFormVbd, Command,
-    DataEntryModeDir, DataEntryModeIncr, DeepSleepMode, DisplayUpdateControl2, DriverOutput,
-    GateDrivingVoltage, I32Ext, SourceDrivingVoltage, Vcom,
+    DataEntryModeDir, DataEntryModeIncr, DisplayUpdateControl2, DriverOutput, GateDrivingVoltage,
+    I32Ext, SourceDrivingVoltage, Vcom,
 };
 
 pub(crate) mod constants;
@@ -54,6 +58,8 @@ pub struct Epd2in13<SPI, CS, BUSY, DC, RST, DELAY> {
     /// Background Color
     background_color: Color,
     refresh: RefreshLut,
+    /// Position of the visible area in the controller RAM
+    offset: PanelOffset,
 }
 
 impl<SPI, CS, BUSY, DC, RST, DELAY> InternalWiAdditions<SPI, CS, BUSY, DC, RST, DELAY>
@@ -66,7 +72,7 @@ where
     RST: OutputPin,
     DELAY: DelayMs<u8>,
 {
-    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         // HW reset
         self.interface.reset(delay, 10);
 
@@ -168,23 +174,24 @@ where
         dc: DC,
         rst: RST,
         delay: &mut DELAY,
-    ) -> Result<Self, SPI::Error> {
+    ) -> Result<Self, Error<SPI::Error>> {
         let mut epd = Epd2in13 {
             interface: DisplayInterface::new(cs, busy, dc, rst),
-            sleep_mode: DeepSleepMode::Mode1,
+            sleep_mode: DeepSleepMode::RetainRam,
             background_color: DEFAULT_BACKGROUND_COLOR,
             refresh: RefreshLut::Full,
+            offset: PanelOffset::default(),
         };
 
         epd.init(spi, delay)?;
         Ok(epd)
     }
 
-    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         self.init(spi, delay)
     }
 
-    fn sleep(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn sleep(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         self.wait_until_idle();
 
         // All sample code enables and disables analog/clocks...
@@ -198,7 +205,7 @@ where
         )?;
         self.command(spi, Command::MasterActivation)?;
 
-        self.set_sleep_mode(spi, self.sleep_mode)?;
+        self.enter_deep_sleep(spi, self.sleep_mode)?;
         Ok(())
     }
 
@@ -207,14 +214,14 @@ where
         spi: &mut SPI,
         buffer: &[u8],
         _delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
-        assert!(buffer.len() == buffer_len(WIDTH as usize, HEIGHT as usize));
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(buffer.len() == buffer_len(WIDTH as usize, HEIGHT as usize));
         self.set_ram_area(spi, 0, 0, WIDTH - 1, HEIGHT - 1)?;
         self.set_ram_address_counters(spi, 0, 0)?;
 
         self.cmd_with_data(spi, Command::WriteRam, buffer)?;
 
-        if self.refresh == RefreshLut::Full {
+        if self.refresh != RefreshLut::Quick {
             // Always keep the base buffer equal to current if not doing partial refresh.
             self.set_ram_area(spi, 0, 0, WIDTH - 1, HEIGHT - 1)?;
             self.set_ram_address_counters(spi, 0, 0)?;
@@ -235,8 +242,9 @@ where
         y: u32,
         width: u32,
         height: u32,
-    ) -> Result<(), SPI::Error> {
-        assert!((width * height / 8) as usize == buffer.len());
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(span_fits(x, width, WIDTH) && span_fits(y, height, HEIGHT));
+        ensure!((width * height / 8) as usize == buffer.len());
 
         // This should not be used when doing partial refresh. The RAM_RED must
         // be updated with the last buffer having been displayed. Doing partial
@@ -244,14 +252,14 @@ where
         // RAM content). Using this function will most probably make the actual
         // display incorrect as the controler will compare with something
         // incorrect.
-        assert!(self.refresh == RefreshLut::Full);
+        ensure!(self.refresh != RefreshLut::Quick);
 
         self.set_ram_area(spi, x, y, x + width, y + height)?;
         self.set_ram_address_counters(spi, x, y)?;
 
         self.cmd_with_data(spi, Command::WriteRam, buffer)?;
 
-        if self.refresh == RefreshLut::Full {
+        if self.refresh != RefreshLut::Quick {
             // Always keep the base buffer equals to current if not doing partial refresh.
             self.set_ram_area(spi, x, y, x + width, y + height)?;
             self.set_ram_address_counters(spi, x, y)?;
@@ -264,8 +272,18 @@ where
 
     /// Never use directly this function when using partial refresh, or also
     /// keep the base buffer in syncd using `set_partial_base_buffer` function.
-    fn display_frame(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), SPI::Error> {
-        if self.refresh == RefreshLut::Full {
+    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.start_display_frame(spi, delay)?;
+        self.wait_until_idle();
+        Ok(())
+    }
+
+    fn start_display_frame(
+        &mut self,
+        spi: &mut SPI,
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        if self.refresh != RefreshLut::Quick {
             self.set_display_update_control_2(
                 spi,
                 DisplayUpdateControl2::new()
@@ -279,8 +297,6 @@ where
             self.set_display_update_control_2(spi, DisplayUpdateControl2::new().display())?;
         }
         self.command(spi, Command::MasterActivation)?;
-        self.wait_until_idle();
-
         Ok(())
     }
 
@@ -289,7 +305,7 @@ where
         spi: &mut SPI,
         buffer: &[u8],
         delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.update_frame(spi, buffer, delay)?;
         self.display_frame(spi, delay)?;
 
@@ -299,7 +315,7 @@ where
         Ok(())
     }
 
-    fn clear_frame(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn clear_frame(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         let color = self.background_color.get_byte_value();
 
         self.set_ram_area(spi, 0, 0, WIDTH - 1, HEIGHT - 1)?;
@@ -313,7 +329,7 @@ where
         )?;
 
         // Always keep the base buffer equals to current if not doing partial refresh.
-        if self.refresh == RefreshLut::Full {
+        if self.refresh != RefreshLut::Quick {
             self.set_ram_area(spi, 0, 0, WIDTH - 1, HEIGHT - 1)?;
             self.set_ram_address_counters(spi, 0, 0)?;
 
@@ -347,10 +363,11 @@ where
         &mut self,
         spi: &mut SPI,
         refresh_rate: Option<RefreshLut>,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         let buffer = match refresh_rate {
             Some(RefreshLut::Full) | None => &LUT_FULL_UPDATE,
             Some(RefreshLut::Quick) => &LUT_PARTIAL_UPDATE,
+            Some(RefreshLut::Custom(preset)) => return self.set_lut_preset(spi, preset),
         };
 
         self.cmd_with_data(spi, Command::WriteLutRegister, buffer)
@@ -361,6 +378,128 @@ where
     }
 }
 
+impl<SPI, CS, BUSY, DC, RST, DELAY> QuickRefresh<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd2in13<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    /// Sets the base frame the next quick refresh is compared against, same as
+    /// `set_partial_base_buffer`.
+    fn update_old_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        self.set_partial_base_buffer(spi, buffer)
+    }
+
+    /// Transmits the frame which is shown with the next `display_new_frame`.
+    fn update_new_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(buffer.len() == buffer_len(WIDTH as usize, HEIGHT as usize));
+        self.wait_until_idle();
+        self.set_ram_area(spi, 0, 0, WIDTH - 1, HEIGHT - 1)?;
+        self.set_ram_address_counters(spi, 0, 0)?;
+
+        self.cmd_with_data(spi, Command::WriteRam, buffer)
+    }
+
+    /// Refreshes the pixels which differ between the old and the new frame.
+    ///
+    /// The partial waveform has to be selected with `set_refresh(.., RefreshLut::Quick)`.
+    fn display_new_frame(
+        &mut self,
+        spi: &mut SPI,
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(self.refresh == RefreshLut::Quick);
+        self.wait_until_idle();
+        self.set_display_update_control_2(spi, DisplayUpdateControl2::new().display())?;
+        self.command(spi, Command::MasterActivation)?;
+        self.wait_until_idle();
+        Ok(())
+    }
+
+    /// Updates and displays the new frame, which becomes the old frame afterwards.
+    fn update_and_display_new_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.update_new_frame(spi, buffer, delay)?;
+        self.display_new_frame(spi, delay)?;
+        self.update_old_frame(spi, buffer, delay)
+    }
+
+    fn update_partial_old_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(span_fits(x, width, WIDTH) && span_fits(y, height, HEIGHT));
+        self.wait_until_idle();
+        self.set_ram_area(spi, x, y, x + width - 1, y + height - 1)?;
+        self.set_ram_address_counters(spi, x, y)?;
+
+        self.cmd_with_data(spi, Command::WriteRamRed, buffer)
+    }
+
+    fn update_partial_new_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(span_fits(x, width, WIDTH) && span_fits(y, height, HEIGHT));
+        self.wait_until_idle();
+        self.set_ram_area(spi, x, y, x + width - 1, y + height - 1)?;
+        self.set_ram_address_counters(spi, x, y)?;
+
+        self.cmd_with_data(spi, Command::WriteRam, buffer)
+    }
+
+    fn clear_partial_frame(
+        &mut self,
+        spi: &mut SPI,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(span_fits(x, width, WIDTH) && span_fits(y, height, HEIGHT));
+        self.wait_until_idle();
+        let color = self.background_color.get_byte_value();
+        let len = buffer_len(width as usize, height as usize) as u32;
+
+        for command in [Command::WriteRam, Command::WriteRamRed] {
+            self.set_ram_area(spi, x, y, x + width - 1, y + height - 1)?;
+            self.set_ram_address_counters(spi, x, y)?;
+            self.command(spi, command)?;
+            self.interface.data_x_times(spi, color, len)?;
+        }
+        Ok(())
+    }
+}
+
 impl<SPI, CS, BUSY, DC, RST, DELAY> Epd2in13<SPI, CS, BUSY, DC, RST, DELAY>
 where
     SPI: Write<u8>,
@@ -370,14 +509,29 @@ where
     RST: OutputPin,
     DELAY: DelayMs<u8>,
 {
+    /// Writes the waveform of a [`LutPreset`] made for this panel
+    ///
+    /// The driver loads its own waveform again in `set_lut` and `init`.
+    ///
+    /// Panics if the preset is made for another panel.
+    pub fn set_lut_preset(
+        &mut self,
+        spi: &mut SPI,
+        preset: &LutPreset,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        self.interface
+            .write_lut_preset(spi, Panel::Epd2in13V2, preset)
+    }
+
     /// When using partial refresh, the controller uses the provided buffer for
     /// comparison with new buffer.
     pub fn set_partial_base_buffer(
         &mut self,
         spi: &mut SPI,
         buffer: &[u8],
-    ) -> Result<(), SPI::Error> {
-        assert!(buffer_len(WIDTH as usize, HEIGHT as usize) == buffer.len());
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(buffer_len(WIDTH as usize, HEIGHT as usize) == buffer.len());
         self.set_ram_area(spi, 0, 0, WIDTH - 1, HEIGHT - 1)?;
         self.set_ram_address_counters(spi, 0, 0)?;
 
@@ -385,11 +539,6 @@ where
         Ok(())
     }
 
-    /// Selects which sleep mode will be used when triggering the deep sleep.
-    pub fn set_deep_sleep_mode(&mut self, mode: DeepSleepMode) {
-        self.sleep_mode = mode;
-    }
-
     /// Sets the refresh mode. When changing mode, the screen will be
     /// re-initialized accordingly.
     pub fn set_refresh(
@@ -397,7 +546,7 @@ where
         spi: &mut SPI,
         delay: &mut DELAY,
         refresh: RefreshLut,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         if self.refresh != refresh {
             self.refresh = refresh;
             self.init(spi, delay)?;
@@ -409,8 +558,8 @@ where
         &mut self,
         spi: &mut SPI,
         start: u16,
-    ) -> Result<(), SPI::Error> {
-        assert!(start <= 295);
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(start <= 295);
         self.cmd_with_data(
             spi,
             Command::GateScanStartPosition,
@@ -422,7 +571,7 @@ where
         &mut self,
         spi: &mut SPI,
         borderwaveform: BorderWaveForm,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.cmd_with_data(
             spi,
             Command::BorderWaveformControl,
@@ -430,7 +579,7 @@ where
         )
     }
 
-    fn set_vcom_register(&mut self, spi: &mut SPI, vcom: Vcom) -> Result<(), SPI::Error> {
+    fn set_vcom_register(&mut self, spi: &mut SPI, vcom: Vcom) -> Result<(), Error<SPI::Error>> {
         self.cmd_with_data(spi, Command::WriteVcomRegister, &[vcom.0])
     }
 
@@ -438,7 +587,7 @@ where
         &mut self,
         spi: &mut SPI,
         voltage: GateDrivingVoltage,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.cmd_with_data(spi, Command::GateDrivingVoltageCtrl, &[voltage.0])
     }
 
@@ -446,12 +595,12 @@ where
         &mut self,
         spi: &mut SPI,
         number_of_lines: u8,
-    ) -> Result<(), SPI::Error> {
-        assert!(number_of_lines <= 127);
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(number_of_lines <= 127);
         self.cmd_with_data(spi, Command::SetDummyLinePeriod, &[number_of_lines])
     }
 
-    fn set_gate_line_width(&mut self, spi: &mut SPI, width: u8) -> Result<(), SPI::Error> {
+    fn set_gate_line_width(&mut self, spi: &mut SPI, width: u8) -> Result<(), Error<SPI::Error>> {
         self.cmd_with_data(spi, Command::SetGateLineWidth, &[width & 0x0F])
     }
 
@@ -462,7 +611,7 @@ where
         vsh1: SourceDrivingVoltage,
         vsh2: SourceDrivingVoltage,
         vsl: SourceDrivingVoltage,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.cmd_with_data(
             spi,
             Command::SourceDrivingVoltageCtrl,
@@ -476,16 +625,24 @@ where
         &mut self,
         spi: &mut SPI,
         value: DisplayUpdateControl2,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.cmd_with_data(spi, Command::DisplayUpdateControl2, &[value.0])
     }
 
     /// Triggers the deep sleep mode
-    fn set_sleep_mode(&mut self, spi: &mut SPI, mode: DeepSleepMode) -> Result<(), SPI::Error> {
+    fn enter_deep_sleep(
+        &mut self,
+        spi: &mut SPI,
+        mode: DeepSleepMode,
+    ) -> Result<(), Error<SPI::Error>> {
         self.cmd_with_data(spi, Command::DeepSleepMode, &[mode as u8])
     }
 
-    fn set_driver_output(&mut self, spi: &mut SPI, output: DriverOutput) -> Result<(), SPI::Error> {
+    fn set_driver_output(
+        &mut self,
+        spi: &mut SPI,
+        output: DriverOutput,
+    ) -> Result<(), Error<SPI::Error>> {
         self.cmd_with_data(spi, Command::DriverOutputControl, &output.to_bytes())
     }
 
@@ -496,7 +653,7 @@ where
         spi: &mut SPI,
         counter_incr_mode: DataEntryModeIncr,
         counter_direction: DataEntryModeDir,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         let mode = counter_incr_mode as u8 | counter_direction as u8;
         self.cmd_with_data(spi, Command::DataEntryModeSetting, &[mode])
     }
@@ -509,7 +666,9 @@ where
         start_y: u32,
         end_x: u32,
         end_y: u32,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
+        let (start_x, end_x) = (start_x + self.offset.source, end_x + self.offset.source);
+        let (start_y, end_y) = (start_y + self.offset.gate, end_y + self.offset.gate);
         self.cmd_with_data(
             spi,
             Command::SetRamXAddressStartEndPosition,
@@ -534,7 +693,8 @@ where
         spi: &mut SPI,
         x: u32,
         y: u32,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
+        let (x, y) = (x + self.offset.source, y + self.offset.gate);
         self.wait_until_idle();
         self.cmd_with_data(spi, Command::SetRamXAddressCounter, &[(x >> 3) as u8])?;
 
@@ -546,7 +706,7 @@ where
         Ok(())
     }
 
-    fn command(&mut self, spi: &mut SPI, command: Command) -> Result<(), SPI::Error> {
+    fn command(&mut self, spi: &mut SPI, command: Command) -> Result<(), Error<SPI::Error>> {
         self.interface.cmd(spi, command)
     }
 
@@ -555,12 +715,50 @@ where
         spi: &mut SPI,
         command: Command,
         data: &[u8],
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.interface.cmd_with_data(spi, command, data)
     }
 
     fn wait_until_idle(&mut self) {
-        let _ = self.interface.wait_until_idle(IS_BUSY_LOW);
+        self.interface.wait_until_idle(IS_BUSY_LOW);
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> GlassOffset<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd2in13<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn set_panel_offset(&mut self, offset: PanelOffset) {
+        self.offset = offset;
+    }
+
+    fn panel_offset(&self) -> PanelOffset {
+        self.offset
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> SleepMode<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd2in13<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn set_sleep_mode(&mut self, mode: DeepSleepMode) {
+        self.sleep_mode = mode;
+    }
+
+    fn sleep_mode(&self) -> DeepSleepMode {
+        self.sleep_mode
     }
 }
 
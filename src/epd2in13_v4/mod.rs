@@ -0,0 +1,608 @@
+//! A simple Driver for the Waveshare 2.13" V4 E-Ink Display via SPI
+//!
+//! The V4 revision uses an SSD1680 controller which isn't compatible with the
+//! command set of the [V2](crate::epd2in13_v2).
+//!
+//! # References
+//!
+//! - [Waveshare product page](https://www.waveshare.com/wiki/2.13inch_e-Paper_HAT_Manual)
+//! - [Waveshare C driver](https://github.com/waveshare/e-Paper/blob/master/RaspberryPi_JetsonNano/c/lib/e-Paper/EPD_2in13_V4.c)
+//! - [Waveshare Python driver](https://github.com/waveshare/e-Paper/blob/master/RaspberryPi_JetsonNano/python/lib/waveshare_epd/epd2in13_V4.py)
+//!
+//! # Partial refresh
+//!
+//! The OTP holds a waveform which only drives the pixels that differ between the old
+//! frame in the second RAM and the new one, in about 0.3 s. [`QuickRefresh`] writes the
+//! new frame or a window of it and refreshes with that waveform, e.g. for the clock or
+//! the counters of a badge. A full [`update_frame`](WaveshareDisplay::update_frame) sets
+//! both frames. Partial refreshes leave ghosting behind, so do a full refresh from time
+//! to time.
+//!
+//! # Example for the 2.13" V4 E-Ink Display
+//!
+//!```rust, no_run
+//!# use embedded_hal_mock::*;
+//!# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
+//!use embedded_graphics::{
+//!    pixelcolor::BinaryColor::On as Black, prelude::*, primitives::{Line, PrimitiveStyle},
+//!};
+//!use epd_waveshare::{epd2in13_v4::*, prelude::*};
+//!#
+//!# let expectations = [];
+//!# let mut spi = spi::Mock::new(&expectations);
+//!# let expectations = [];
+//!# let cs_pin = pin::Mock::new(&expectations);
+//!# let busy_in = pin::Mock::new(&expectations);
+//!# let dc = pin::Mock::new(&expectations);
+//!# let rst = pin::Mock::new(&expectations);
+//!# let mut delay = delay::MockNoop::new();
+//!
+//!// Setup EPD
+//!let mut epd = Epd2in13::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
+//!
+//!// Use display graphics from embedded-graphics
+//!let mut display = Display2in13::default();
+//!
+//!// Use embedded graphics for drawing a line
+//!let _ = Line::new(Point::new(0, 120), Point::new(0, 249))
+//!    .into_styled(PrimitiveStyle::with_stroke(Black, 1))
+//!    .draw(&mut display);
+//!
+//!// Display updated frame, this also sets it as base for partial refreshes
+//!epd.update_frame(&mut spi, display.buffer(), &mut delay)?;
+//!epd.display_frame(&mut spi, &mut delay)?;
+//!
+//!// Draw something new here
+//!
+//!// partial refresh of updated pixels
+//!epd.update_and_display_new_frame(&mut spi, display.buffer(), &mut delay)?;
+//!
+//!// Set the EPD to sleep
+//!epd.sleep(&mut spi, &mut delay)?;
+//!# Ok(())
+//!# }
+//!```
+
+use embedded_hal::{
+    blocking::{
+        delay::*,
+        spi::{Transfer, Write},
+    },
+    digital::v2::*,
+};
+
+use crate::color::Color;
+use crate::controllers::ssd1680::{Command, DataEntry, IS_BUSY_LOW};
+use crate::interface::DisplayInterface;
+use crate::traits::{
+    DeepSleepMode, InternalWiAdditions, QuickRefresh, ReadRevision, ReadTemperature, RefreshLut,
+    Revision, SleepMode, WaveshareDisplay,
+};
+use crate::{buffer_len, span_fits, Error};
+
+#[cfg(feature = "graphics")]
+mod graphics;
+#[cfg(feature = "graphics")]
+pub use self::graphics::Display2in13;
+
+/// Width of epd2in13 V4 in pixels
+pub const WIDTH: u32 = 122;
+/// Height of epd2in13 V4 in pixels
+pub const HEIGHT: u32 = 250;
+/// Default Background Color (white)
+pub const DEFAULT_BACKGROUND_COLOR: Color = Color::White;
+
+/// Border waveform of the full refreshes, following LUT1
+const BORDER_FULL: u8 = 0x05;
+/// Border waveform of the partial refreshes, held at VSS
+const BORDER_PARTIAL: u8 = 0x80;
+
+/// Epd2in13 (V4) driver
+pub struct Epd2in13<SPI, CS, BUSY, DC, RST, DELAY> {
+    /// Connection Interface
+    interface: DisplayInterface<SPI, CS, BUSY, DC, RST, DELAY>,
+    /// Background Color
+    background_color: Color,
+    /// Refresh LUT
+    refresh: RefreshLut,
+    /// Deep sleep mode of `sleep`
+    sleep_mode: DeepSleepMode,
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> InternalWiAdditions<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd2in13<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.interface.reset(delay, 2);
+
+        self.interface.sw_reset_ssd1680(spi)?;
+
+        // 250 gates, scanned from G0
+        self.interface.cmd_with_data(
+            spi,
+            Command::DriverOutputControl,
+            &[(HEIGHT - 1) as u8, ((HEIGHT - 1) >> 8) as u8, 0x00],
+        )?;
+
+        self.interface
+            .data_entry_ssd1680(spi, DataEntry::IncrementBoth)?;
+
+        self.use_full_frame(spi)?;
+
+        self.interface.border_ssd1680(spi, BORDER_FULL)?;
+
+        // normal RAM content, the panel starts at source S8
+        self.interface
+            .cmd_with_data(spi, Command::DisplayUpdateControl1, &[0x00, 0x80])?;
+
+        self.set_lut(spi, None)?;
+
+        self.wait_until_idle();
+        Ok(())
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd2in13<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    type DisplayColor = Color;
+    fn new(
+        spi: &mut SPI,
+        cs: CS,
+        busy: BUSY,
+        dc: DC,
+        rst: RST,
+        delay: &mut DELAY,
+    ) -> Result<Self, Error<SPI::Error>> {
+        let interface = DisplayInterface::new(cs, busy, dc, rst);
+
+        let mut epd = Epd2in13 {
+            interface,
+            background_color: DEFAULT_BACKGROUND_COLOR,
+            refresh: RefreshLut::Full,
+            sleep_mode: DeepSleepMode::RetainRam,
+        };
+
+        epd.init(spi, delay)?;
+
+        Ok(epd)
+    }
+
+    fn sleep(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.interface.sleep_ssd1680(spi, self.sleep_mode)
+    }
+
+    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.init(spi, delay)
+    }
+
+    fn set_background_color(&mut self, background_color: Color) {
+        self.background_color = background_color;
+    }
+
+    fn background_color(&self) -> &Color {
+        &self.background_color
+    }
+
+    fn width(&self) -> u32 {
+        WIDTH
+    }
+
+    fn height(&self) -> u32 {
+        HEIGHT
+    }
+
+    fn update_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(buffer.len() == buffer_len(WIDTH as usize, HEIGHT as usize));
+        self.wait_until_idle();
+
+        self.use_full_frame(spi)?;
+        self.interface
+            .cmd_with_data(spi, Command::WriteRam, buffer)?;
+
+        // Always keep the base buffer equal to the current frame, so following
+        // partial refreshes compare against the right data.
+        self.use_full_frame(spi)?;
+        self.interface
+            .cmd_with_data(spi, Command::WriteRam2, buffer)
+    }
+
+    fn update_partial_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(window_fits(x, y, width, height));
+        ensure!(buffer.len() == buffer_len(width as usize, height as usize));
+        self.wait_until_idle();
+        self.set_ram_area(spi, x, y, x + width - 1, y + height - 1)?;
+        self.interface.cursor_ssd1680(spi, x, y)?;
+
+        self.interface.cmd_with_data(spi, Command::WriteRam, buffer)
+    }
+
+    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.start_display_frame(spi, delay)?;
+        self.wait_until_idle();
+        Ok(())
+    }
+
+    fn start_display_frame(
+        &mut self,
+        spi: &mut SPI,
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        self.interface.border_ssd1680(spi, BORDER_FULL)?;
+        let mode = match self.refresh {
+            // Enable clock and analog, load temperature and LUT, display, disable analog and clock
+            RefreshLut::Full | RefreshLut::Custom(_) => 0xF7,
+            // Same as above, but keep the LUT loaded for the faked temperature
+            RefreshLut::Quick => 0xC7,
+        };
+        self.interface.start_ssd1680(spi, mode)
+    }
+
+    fn update_and_display_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.update_frame(spi, buffer, delay)?;
+        self.display_frame(spi, delay)?;
+        Ok(())
+    }
+
+    fn clear_frame(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        let color = self.background_color.get_byte_value();
+
+        for command in [Command::WriteRam, Command::WriteRam2] {
+            self.use_full_frame(spi)?;
+            self.interface.cmd(spi, command)?;
+            self.interface.data_x_times(
+                spi,
+                color,
+                buffer_len(WIDTH as usize, HEIGHT as usize) as u32,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// `RefreshLut::Quick` selects the fast refresh mode of the vendor driver: the
+    /// controller gets a faked temperature and keeps the matching (shorter) waveform
+    /// loaded for the following full refreshes.
+    fn set_lut(
+        &mut self,
+        spi: &mut SPI,
+        refresh_rate: Option<RefreshLut>,
+    ) -> Result<(), Error<SPI::Error>> {
+        if let Some(RefreshLut::Custom(_)) = refresh_rate {
+            return Err(Error::Unsupported);
+        }
+        if let Some(refresh_lut) = refresh_rate {
+            self.refresh = refresh_lut;
+        }
+
+        self.wait_until_idle();
+        // use the internal temperature sensor
+        self.interface
+            .cmd_with_data(spi, Command::TemperatureSensorSelection, &[0x80])?;
+
+        if self.refresh == RefreshLut::Quick {
+            // load the temperature and the waveform from OTP ...
+            self.interface.activate_ssd1680(spi, 0xB1)?;
+            // ... overwrite the measured temperature with 100°C ...
+            self.interface.cmd_with_data(
+                spi,
+                Command::TemperatureSensorControlWrite,
+                &[0x64, 0x00],
+            )?;
+            // ... and only load the waveform for it
+            self.interface.activate_ssd1680(spi, 0x91)?;
+        }
+        Ok(())
+    }
+
+    fn is_busy(&self) -> bool {
+        self.interface.is_busy(IS_BUSY_LOW)
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> QuickRefresh<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd2in13<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    /// Sets the base frame the next partial refresh is compared against.
+    fn update_old_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(buffer.len() == buffer_len(WIDTH as usize, HEIGHT as usize));
+        self.wait_until_idle();
+        self.use_full_frame(spi)?;
+        self.interface
+            .cmd_with_data(spi, Command::WriteRam2, buffer)
+    }
+
+    /// Transmits the frame which is shown with the next `display_new_frame`.
+    fn update_new_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(buffer.len() == buffer_len(WIDTH as usize, HEIGHT as usize));
+        self.wait_until_idle();
+        self.use_full_frame(spi)?;
+        self.interface.cmd_with_data(spi, Command::WriteRam, buffer)
+    }
+
+    /// Partial refresh of the pixels which differ between the old and the new frame.
+    fn display_new_frame(
+        &mut self,
+        spi: &mut SPI,
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        self.interface.border_ssd1680(spi, BORDER_PARTIAL)?;
+        // like a full refresh, but with display mode 2
+        self.interface.activate_ssd1680(spi, 0xFF)
+    }
+
+    /// Afterwards the new frame is copied into the base frame, so that several
+    /// partial refreshes can follow each other.
+    fn update_and_display_new_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.update_new_frame(spi, buffer, delay)?;
+        self.display_new_frame(spi, delay)?;
+        self.update_old_frame(spi, buffer, delay)
+    }
+
+    fn update_partial_old_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(window_fits(x, y, width, height));
+        ensure!(buffer.len() == buffer_len(width as usize, height as usize));
+        self.wait_until_idle();
+        self.set_ram_area(spi, x, y, x + width - 1, y + height - 1)?;
+        self.interface.cursor_ssd1680(spi, x, y)?;
+
+        self.interface
+            .cmd_with_data(spi, Command::WriteRam2, buffer)
+    }
+
+    fn update_partial_new_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.update_partial_frame(spi, buffer, x, y, width, height)
+    }
+
+    fn clear_partial_frame(
+        &mut self,
+        spi: &mut SPI,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(window_fits(x, y, width, height));
+        self.wait_until_idle();
+        let color = self.background_color.get_byte_value();
+        let len = buffer_len(width as usize, height as usize) as u32;
+
+        for command in [Command::WriteRam, Command::WriteRam2] {
+            self.set_ram_area(spi, x, y, x + width - 1, y + height - 1)?;
+            self.interface.cursor_ssd1680(spi, x, y)?;
+            self.interface.cmd(spi, command)?;
+            self.interface.data_x_times(spi, color, len)?;
+        }
+        Ok(())
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> Epd2in13<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn wait_until_idle(&mut self) {
+        self.interface.wait_until_idle(IS_BUSY_LOW);
+    }
+
+    fn use_full_frame(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        // choose full frame/ram
+        self.set_ram_area(spi, 0, 0, WIDTH - 1, HEIGHT - 1)?;
+
+        // start from the beginning
+        self.interface.cursor_ssd1680(spi, 0, 0)
+    }
+
+    fn set_ram_area(
+        &mut self,
+        spi: &mut SPI,
+        start_x: u32,
+        start_y: u32,
+        end_x: u32,
+        end_y: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.interface
+            .window_ssd1680(spi, start_x, start_y, end_x, end_y)
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> ReadRevision<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd2in13<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8> + Transfer<u8, Error = <SPI as Write<u8>>::Error>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn read_revision(
+        &mut self,
+        spi: &mut SPI,
+    ) -> Result<Revision, Error<<SPI as Write<u8>>::Error>> {
+        self.interface.revision_ssd1680(spi)
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> ReadTemperature<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd2in13<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8> + Transfer<u8, Error = <SPI as Write<u8>>::Error>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn read_temperature(&mut self, spi: &mut SPI) -> Result<i8, Error<<SPI as Write<u8>>::Error>> {
+        self.interface.temperature_ssd1680(spi)
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> SleepMode<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd2in13<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn set_sleep_mode(&mut self, mode: DeepSleepMode) {
+        self.sleep_mode = mode;
+    }
+
+    fn sleep_mode(&self) -> DeepSleepMode {
+        self.sleep_mode
+    }
+}
+
+/// Whether a partial window is aligned to bytes and lies on the panel
+///
+/// The width may end in the last, partly used byte column.
+fn window_fits(x: u32, y: u32, width: u32, height: u32) -> bool {
+    x.is_multiple_of(8)
+        && (width.is_multiple_of(8) || x.checked_add(width) == Some(WIDTH))
+        && span_fits(x, width, WIDTH)
+        && span_fits(y, height, HEIGHT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus_log::{commands, data_of, Log, Pin, Spi};
+    use embedded_hal_mock::delay::MockNoop;
+
+    #[test]
+    fn epd_size() {
+        assert_eq!(WIDTH, 122);
+        assert_eq!(HEIGHT, 250);
+        assert_eq!(buffer_len(WIDTH as usize, HEIGHT as usize), 4000);
+        assert_eq!(DEFAULT_BACKGROUND_COLOR, Color::White);
+    }
+
+    #[test]
+    fn partial_windows() {
+        assert!(window_fits(0, 0, WIDTH, HEIGHT));
+        assert!(window_fits(16, 10, 64, 20));
+        assert!(window_fits(112, 10, 10, 20));
+        assert!(!window_fits(4, 10, 64, 20));
+        assert!(!window_fits(16, 10, 60, 20));
+        assert!(!window_fits(16, 10, 0, 20));
+        assert!(!window_fits(112, 0, 16, 20));
+        assert!(!window_fits(0, 240, 8, 20));
+        assert!(!window_fits(u32::MAX - 7, 0, 8, 20));
+        assert!(!window_fits(0, u32::MAX, 8, 2));
+    }
+
+    #[test]
+    fn partial_refresh_sequence() {
+        let log = Log::default();
+        let mut spi = Spi(&log);
+        let mut delay = MockNoop::new();
+        let mut epd = Epd2in13::new(
+            &mut spi,
+            Pin(None),
+            Pin(None),
+            Pin(Some(&log)),
+            Pin(None),
+            &mut delay,
+        )
+        .unwrap();
+
+        log.take();
+        epd.update_partial_new_frame(&mut spi, &[0; 2 * 8], 8, 16, 16, 8)
+            .unwrap();
+        epd.display_new_frame(&mut spi, &mut delay).unwrap();
+        let events = log.take();
+        assert_eq!(
+            commands(&events),
+            [0x44, 0x45, 0x4E, 0x4F, 0x24, 0x3C, 0x22, 0x20]
+        );
+        assert_eq!(data_of(&events, 0x44), [[1, 2]]);
+        assert_eq!(data_of(&events, 0x45), [[16, 0, 23, 0]]);
+        assert_eq!(data_of(&events, 0x4E), [[1]]);
+        assert_eq!(data_of(&events, 0x4F), [[16, 0]]);
+        assert_eq!(data_of(&events, 0x24), [[0; 16]]);
+        assert_eq!(data_of(&events, 0x3C), [[BORDER_PARTIAL]]);
+        assert_eq!(data_of(&events, 0x22), [[0xFF]]);
+    }
+}
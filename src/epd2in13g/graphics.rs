@@ -0,0 +1,110 @@
+use crate::color::QuadColor;
+use crate::epd2in13g::{DEFAULT_BACKGROUND_COLOR, HEIGHT, NUM_DISPLAY_BYTES, WIDTH};
+use crate::graphics::{DisplayRotation, QuadDisplay};
+use embedded_graphics_core::prelude::*;
+
+/// Full size buffer for use with the 2in13g EPD
+///
+/// Can also be manually constructed, every row is rounded up to a full byte:
+/// `buffer: [DEFAULT_BACKGROUND_COLOR.get_byte_value(); WIDTH.div_ceil(4) * HEIGHT]`
+pub struct Display2in13g {
+    buffer: [u8; NUM_DISPLAY_BYTES as usize],
+    rotation: DisplayRotation,
+}
+
+impl Default for Display2in13g {
+    fn default() -> Self {
+        Display2in13g {
+            buffer: [DEFAULT_BACKGROUND_COLOR.get_byte_value(); NUM_DISPLAY_BYTES as usize],
+            rotation: DisplayRotation::default(),
+        }
+    }
+}
+
+impl DrawTarget for Display2in13g {
+    type Color = QuadColor;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for pixel in pixels {
+            self.draw_helper(WIDTH, HEIGHT, pixel)?;
+        }
+        Ok(())
+    }
+}
+
+impl OriginDimensions for Display2in13g {
+    fn size(&self) -> Size {
+        Size::new(WIDTH, HEIGHT)
+    }
+}
+
+impl QuadDisplay for Display2in13g {
+    fn buffer(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    fn get_mut_buffer(&mut self) -> &mut [u8] {
+        &mut self.buffer
+    }
+
+    fn set_rotation(&mut self, rotation: DisplayRotation) {
+        self.rotation = rotation;
+    }
+
+    fn rotation(&self) -> DisplayRotation {
+        self.rotation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::epd2in13g;
+
+    // test buffer length
+    #[test]
+    fn graphics_size() {
+        let display = Display2in13g::default();
+        assert_eq!(display.buffer().len(), 7750);
+    }
+
+    // test default background color on all bytes
+    #[test]
+    fn graphics_default() {
+        let display = Display2in13g::default();
+        for &byte in display.buffer() {
+            assert_eq!(byte, epd2in13g::DEFAULT_BACKGROUND_COLOR.get_byte_value());
+        }
+    }
+
+    #[test]
+    fn graphics_colors() {
+        let mut display = Display2in13g::default();
+        let colors = [
+            QuadColor::Red,
+            QuadColor::Yellow,
+            QuadColor::White,
+            QuadColor::Black,
+        ];
+        for (x, &color) in colors.iter().enumerate() {
+            let _ = Pixel(Point::new(x as i32, 1), color).draw(&mut display);
+        }
+        let row = 31;
+        assert_eq!(display.buffer()[row], 0b11_10_01_00);
+        assert_eq!(display.buffer()[row + 1], QuadColor::White.get_byte_value());
+    }
+
+    #[test]
+    fn graphics_last_pixel_in_row() {
+        let mut display = Display2in13g::default();
+        display.clear_buffer(QuadColor::Black);
+        let _ = Pixel(Point::new(WIDTH as i32 - 1, 0), QuadColor::Yellow).draw(&mut display);
+        // 122 = 30 * 4 + 2, so the last pixel is the second one of the 31st byte
+        assert_eq!(display.buffer()[30], 0b00_10_00_00);
+        assert_eq!(display.buffer()[31], QuadColor::Black.get_byte_value());
+    }
+}
@@ -0,0 +1,314 @@
+//! A simple Driver for the Waveshare 2.13" (G) E-Ink Display via SPI
+//!
+//! Black, white, yellow and red with a resolution of 122x250. It uses the same 2 bit per
+//! pixel buffer as the [2.36" (G)](crate::epd2in36g), so the same [`QuadColor`] and
+//! [`QuadDisplay`](crate::graphics::QuadDisplay) apply. As 122 isn't a multiple of 4, every
+//! row of the buffer is rounded up to 31 bytes.
+//!
+//! The controller is set to 128 source lines, so every row is padded with a white byte
+//! while it is transmitted.
+//!
+//! # References
+//!
+//! - [Documentation](https://www.waveshare.com/wiki/2.13inch_e-Paper_HAT_(G))
+//! - [Waveshare C driver](https://github.com/waveshare/e-Paper/blob/master/RaspberryPi_JetsonNano/c/lib/e-Paper/EPD_2in13g.c)
+//! - [Waveshare Python driver](https://github.com/waveshare/e-Paper/blob/master/RaspberryPi_JetsonNano/python/lib/waveshare_epd/epd2in13g.py)
+//!
+//! # Example for the 2.13" (G) E-Ink Display
+//!
+//!```rust, no_run
+//!# use embedded_hal_mock::*;
+//!# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
+//!use embedded_graphics::{prelude::*, primitives::{Line, PrimitiveStyle}};
+//!use epd_waveshare::{epd2in13g::*, prelude::*};
+//!#
+//!# let expectations = [];
+//!# let mut spi = spi::Mock::new(&expectations);
+//!# let expectations = [];
+//!# let cs_pin = pin::Mock::new(&expectations);
+//!# let busy_in = pin::Mock::new(&expectations);
+//!# let dc = pin::Mock::new(&expectations);
+//!# let rst = pin::Mock::new(&expectations);
+//!# let mut delay = delay::MockNoop::new();
+//!
+//!// Setup EPD
+//!let mut epd = Epd2in13g::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
+//!
+//!// Use display graphics from embedded-graphics
+//!let mut display = Display2in13g::default();
+//!
+//!let _ = Line::new(Point::new(0, 200), Point::new(0, 249))
+//!    .into_styled(PrimitiveStyle::with_stroke(QuadColor::Red, 1))
+//!    .draw(&mut display);
+//!let _ = Line::new(Point::new(15, 200), Point::new(15, 249))
+//!    .into_styled(PrimitiveStyle::with_stroke(QuadColor::Yellow, 1))
+//!    .draw(&mut display);
+//!
+//!epd.update_and_display_frame(&mut spi, display.buffer(), &mut delay)?;
+//!
+//!// Set the EPD to sleep
+//!epd.sleep(&mut spi, &mut delay)?;
+//!# Ok(())
+//!# }
+//!```
+
+use embedded_hal::{
+    blocking::{delay::*, spi::Write},
+    digital::v2::{InputPin, OutputPin},
+};
+
+use crate::color::QuadColor;
+use crate::interface::DisplayInterface;
+use crate::traits::{InternalWiAdditions, RefreshLut, WaveshareDisplay};
+use crate::type_g::{Command, IS_BUSY_LOW};
+use crate::Error;
+
+#[cfg(feature = "graphics")]
+mod graphics;
+#[cfg(feature = "graphics")]
+pub use self::graphics::Display2in13g;
+
+/// Width of the display
+pub const WIDTH: u32 = 122;
+/// Height of the display
+pub const HEIGHT: u32 = 250;
+/// Default Background Color
+pub const DEFAULT_BACKGROUND_COLOR: QuadColor = QuadColor::White;
+const ROW_BYTES: u32 = WIDTH.div_ceil(4);
+pub(crate) const NUM_DISPLAY_BYTES: u32 = ROW_BYTES * HEIGHT;
+/// Source lines the controller is set to
+const SOURCE_LINES: u32 = 128;
+/// Bytes of a row as they are transmitted, including the padding
+const SOURCE_BYTES: u32 = SOURCE_LINES / 4;
+
+/// Epd2in13g driver
+///
+pub struct Epd2in13g<SPI, CS, BUSY, DC, RST, DELAY> {
+    /// Connection Interface
+    interface: DisplayInterface<SPI, CS, BUSY, DC, RST, DELAY>,
+    /// Background Color
+    color: QuadColor,
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> InternalWiAdditions<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd2in13g<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.interface.reset(delay, 2);
+        self.wait_until_idle();
+
+        self.interface
+            .cmd_with_data(spi, Command::Unknown4D, &[0x78])?;
+        self.interface
+            .cmd_with_data(spi, Command::PanelSetting, &[0x0F, 0x29])?;
+        self.interface
+            .cmd_with_data(spi, Command::PowerSetting, &[0x07, 0x00])?;
+        self.interface
+            .cmd_with_data(spi, Command::PowerOffSequenceSetting, &[0x10, 0x54, 0x44])?;
+        self.interface.cmd_with_data(
+            spi,
+            Command::BoosterSoftStart,
+            &[0x05, 0x00, 0x3F, 0x0A, 0x25, 0x12, 0x1A],
+        )?;
+        self.interface
+            .cmd_with_data(spi, Command::VcomAndDataIntervalSetting, &[0x37])?;
+        self.interface
+            .cmd_with_data(spi, Command::TconSetting, &[0x02, 0x02])?;
+        self.interface.cmd_with_data(
+            spi,
+            Command::TconResolution,
+            &[
+                (SOURCE_LINES >> 8) as u8,
+                SOURCE_LINES as u8,
+                (HEIGHT >> 8) as u8,
+                HEIGHT as u8,
+            ],
+        )?;
+        self.interface
+            .cmd_with_data(spi, Command::UnknownE7, &[0x1C])?;
+        self.interface
+            .cmd_with_data(spi, Command::PowerSaving, &[0x22])?;
+        self.interface
+            .cmd_with_data(spi, Command::UnknownB4, &[0xD0])?;
+        self.interface
+            .cmd_with_data(spi, Command::UnknownB5, &[0x03])?;
+        self.interface
+            .cmd_with_data(spi, Command::UnknownE9, &[0x01])?;
+        self.interface
+            .cmd_with_data(spi, Command::PllControl, &[0x08])?;
+
+        self.interface.cmd(spi, Command::PowerOn)?;
+        self.wait_until_idle();
+        Ok(())
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd2in13g<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    type DisplayColor = QuadColor;
+    fn new(
+        spi: &mut SPI,
+        cs: CS,
+        busy: BUSY,
+        dc: DC,
+        rst: RST,
+        delay: &mut DELAY,
+    ) -> Result<Self, Error<SPI::Error>> {
+        let interface = DisplayInterface::new(cs, busy, dc, rst);
+        let color = DEFAULT_BACKGROUND_COLOR;
+
+        let mut epd = Epd2in13g { interface, color };
+
+        epd.init(spi, delay)?;
+
+        Ok(epd)
+    }
+
+    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.init(spi, delay)
+    }
+
+    fn sleep(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.interface.sleep_quad(spi)
+    }
+
+    fn update_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(buffer.len() == NUM_DISPLAY_BYTES as usize);
+        let padding =
+            [DEFAULT_BACKGROUND_COLOR.get_byte_value(); (SOURCE_BYTES - ROW_BYTES) as usize];
+
+        self.interface.cmd(spi, Command::DataStartTransmission)?;
+        for row in buffer.chunks(ROW_BYTES as usize) {
+            self.interface.data(spi, row)?;
+            self.interface.data(spi, &padding)?;
+        }
+        Ok(())
+    }
+
+    fn update_partial_frame(
+        &mut self,
+        _spi: &mut SPI,
+        _buffer: &[u8],
+        _x: u32,
+        _y: u32,
+        _width: u32,
+        _height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        unsupported!();
+    }
+
+    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.start_display_frame(spi, delay)?;
+        self.wait_until_idle();
+        Ok(())
+    }
+
+    fn start_display_frame(
+        &mut self,
+        spi: &mut SPI,
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.interface
+            .cmd_with_data(spi, Command::DisplayRefresh, &[0x00])?;
+        Ok(())
+    }
+
+    fn update_and_display_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.update_frame(spi, buffer, delay)?;
+        self.display_frame(spi, delay)?;
+        Ok(())
+    }
+
+    fn clear_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.interface.cmd(spi, Command::DataStartTransmission)?;
+        self.interface
+            .data_x_times(spi, self.color.get_byte_value(), SOURCE_BYTES * HEIGHT)?;
+        self.display_frame(spi, delay)?;
+        Ok(())
+    }
+
+    fn set_background_color(&mut self, color: QuadColor) {
+        self.color = color;
+    }
+
+    fn background_color(&self) -> &QuadColor {
+        &self.color
+    }
+
+    fn width(&self) -> u32 {
+        WIDTH
+    }
+
+    fn height(&self) -> u32 {
+        HEIGHT
+    }
+
+    fn set_lut(
+        &mut self,
+        _spi: &mut SPI,
+        refresh_rate: Option<RefreshLut>,
+    ) -> Result<(), Error<SPI::Error>> {
+        if let Some(RefreshLut::Custom(_)) = refresh_rate {
+            return Err(Error::Unsupported);
+        }
+        unsupported!();
+    }
+
+    fn is_busy(&self) -> bool {
+        self.interface.is_busy(IS_BUSY_LOW)
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> Epd2in13g<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn wait_until_idle(&mut self) {
+        self.interface.wait_until_idle(IS_BUSY_LOW);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn epd_size() {
+        assert_eq!(WIDTH, 122);
+        assert_eq!(HEIGHT, 250);
+        assert_eq!(ROW_BYTES, 31);
+        assert_eq!(NUM_DISPLAY_BYTES, 7750);
+        assert_eq!(SOURCE_BYTES, 32);
+        assert_eq!(DEFAULT_BACKGROUND_COLOR, QuadColor::White);
+    }
+}
@@ -0,0 +1,151 @@
+use crate::buffer_len;
+use crate::epd2in7_v2::{DEFAULT_BACKGROUND_COLOR, HEIGHT, NUM_GRAY_BYTES, WIDTH};
+use crate::graphics::{Display, DisplayRotation, GrayDisplay};
+use embedded_graphics::pixelcolor::{BinaryColor, Gray2};
+use embedded_graphics_core::prelude::*;
+
+/// Full size buffer for use with the 2in7 V2 EPD
+///
+/// Can also be manually constructed:
+/// `buffer: [DEFAULT_BACKGROUND_COLOR.get_byte_value(); WIDTH / 8 * HEIGHT]`
+pub struct Display2in7 {
+    buffer: [u8; buffer_len(WIDTH as usize, HEIGHT as usize)],
+    rotation: DisplayRotation,
+}
+
+impl Default for Display2in7 {
+    fn default() -> Self {
+        Display2in7 {
+            buffer: [DEFAULT_BACKGROUND_COLOR.get_byte_value();
+                buffer_len(WIDTH as usize, HEIGHT as usize)],
+            rotation: DisplayRotation::default(),
+        }
+    }
+}
+
+impl DrawTarget for Display2in7 {
+    type Color = BinaryColor;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for pixel in pixels {
+            self.draw_helper(WIDTH, HEIGHT, pixel)?;
+        }
+        Ok(())
+    }
+}
+
+impl OriginDimensions for Display2in7 {
+    fn size(&self) -> Size {
+        Size::new(WIDTH, HEIGHT)
+    }
+}
+
+impl Display for Display2in7 {
+    fn buffer(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    fn get_mut_buffer(&mut self) -> &mut [u8] {
+        &mut self.buffer
+    }
+
+    fn set_rotation(&mut self, rotation: DisplayRotation) {
+        self.rotation = rotation;
+    }
+
+    fn rotation(&self) -> DisplayRotation {
+        self.rotation
+    }
+}
+
+/// Full size buffer with 4 gray levels for use with the 2in7 V2 EPD
+///
+/// Every byte holds 4 pixels, white (`0xFF`) is the default.
+pub struct Display2in7Gray {
+    buffer: [u8; NUM_GRAY_BYTES],
+    rotation: DisplayRotation,
+}
+
+impl Default for Display2in7Gray {
+    fn default() -> Self {
+        Display2in7Gray {
+            buffer: [0xFF; NUM_GRAY_BYTES],
+            rotation: DisplayRotation::default(),
+        }
+    }
+}
+
+impl DrawTarget for Display2in7Gray {
+    type Color = Gray2;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for pixel in pixels {
+            self.draw_helper(WIDTH, HEIGHT, pixel)?;
+        }
+        Ok(())
+    }
+}
+
+impl OriginDimensions for Display2in7Gray {
+    fn size(&self) -> Size {
+        Size::new(WIDTH, HEIGHT)
+    }
+}
+
+impl GrayDisplay for Display2in7Gray {
+    fn buffer(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    fn get_mut_buffer(&mut self) -> &mut [u8] {
+        &mut self.buffer
+    }
+
+    fn set_rotation(&mut self, rotation: DisplayRotation) {
+        self.rotation = rotation;
+    }
+
+    fn rotation(&self) -> DisplayRotation {
+        self.rotation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // test buffer length
+    #[test]
+    fn graphics_size() {
+        let display = Display2in7::default();
+        assert_eq!(display.buffer().len(), 5808);
+    }
+
+    // test default background color on all bytes
+    #[test]
+    fn graphics_default() {
+        let display = Display2in7::default();
+        for &byte in display.buffer() {
+            assert_eq!(byte, DEFAULT_BACKGROUND_COLOR.get_byte_value());
+        }
+    }
+
+    #[test]
+    fn graphics_gray_levels() {
+        let mut display = Display2in7Gray::default();
+        assert_eq!(GrayDisplay::buffer(&display).len(), 11616);
+        for (x, luma) in (0..4).enumerate() {
+            let _ = Pixel(Point::new(x as i32, 0), Gray2::new(luma)).draw(&mut display);
+        }
+        assert_eq!(GrayDisplay::buffer(&display)[0], 0b00_01_10_11);
+        assert_eq!(GrayDisplay::buffer(&display)[1], 0xFF);
+    }
+}
@@ -0,0 +1,774 @@
+//! A simple Driver for the Waveshare 2.7" V2 E-Ink Display via SPI
+//!
+//! The V2 revision uses an SSD1680 controller which isn't compatible with the
+//! command set of the original 2.7" panel.
+//!
+//! # References
+//!
+//! - [Waveshare product page](https://www.waveshare.com/wiki/2.7inch_e-Paper_HAT_Manual)
+//! - [Waveshare C driver](https://github.com/waveshare/e-Paper/blob/master/RaspberryPi_JetsonNano/c/lib/e-Paper/EPD_2in7_V2.c)
+//! - [Waveshare Python driver](https://github.com/waveshare/e-Paper/blob/master/RaspberryPi_JetsonNano/python/lib/waveshare_epd/epd2in7_V2.py)
+//!
+//! # 4 gray levels
+//!
+//! The display can also show black, dark gray, light gray and white with the
+//! waveform of the vendor driver, e.g. for the anti-aliased text of an e-reader. Draw
+//! into the `Gray2` buffer [`Display2in7Gray`] and upload it with
+//! [`Epd2in7::update_gray_frame`]. Gray frames are always shown with a full refresh,
+//! uploading a black/white frame switches back.
+//!
+//! # Example for the 2.7" V2 E-Ink Display
+//!
+//!```rust, no_run
+//!# use embedded_hal_mock::*;
+//!# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
+//!use embedded_graphics::{
+//!    pixelcolor::BinaryColor::On as Black, prelude::*, primitives::{Line, PrimitiveStyle},
+//!};
+//!use epd_waveshare::{epd2in7_v2::*, prelude::*};
+//!#
+//!# let expectations = [];
+//!# let mut spi = spi::Mock::new(&expectations);
+//!# let expectations = [];
+//!# let cs_pin = pin::Mock::new(&expectations);
+//!# let busy_in = pin::Mock::new(&expectations);
+//!# let dc = pin::Mock::new(&expectations);
+//!# let rst = pin::Mock::new(&expectations);
+//!# let mut delay = delay::MockNoop::new();
+//!
+//!// Setup EPD
+//!let mut epd = Epd2in7::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
+//!
+//!// Use display graphics from embedded-graphics
+//!let mut display = Display2in7::default();
+//!
+//!// Use embedded graphics for drawing a line
+//!let _ = Line::new(Point::new(0, 120), Point::new(0, 263))
+//!    .into_styled(PrimitiveStyle::with_stroke(Black, 1))
+//!    .draw(&mut display);
+//!
+//!// Display updated frame, this also sets it as base for quick refreshes
+//!epd.update_frame(&mut spi, display.buffer(), &mut delay)?;
+//!epd.display_frame(&mut spi, &mut delay)?;
+//!
+//!// Draw something new here
+//!
+//!// quick refresh of updated pixels
+//!epd.update_new_frame(&mut spi, display.buffer(), &mut delay)?;
+//!epd.display_new_frame(&mut spi, &mut delay)?;
+//!
+//!// Set the EPD to sleep
+//!epd.sleep(&mut spi, &mut delay)?;
+//!# Ok(())
+//!# }
+//!```
+
+use embedded_hal::{
+    blocking::{
+        delay::*,
+        spi::{Transfer, Write},
+    },
+    digital::v2::*,
+};
+
+use crate::color::Color;
+pub use crate::color::GrayMode;
+use crate::controllers::ssd1680::{Command, DataEntry, IS_BUSY_LOW};
+use crate::interface::DisplayInterface;
+use crate::traits::{
+    DeepSleepMode, GlassOffset, InternalWiAdditions, PanelOffset, QuickRefresh, RamBanks,
+    ReadRevision, ReadTemperature, RefreshLut, Revision, SleepMode, WaveshareDisplay,
+};
+use crate::{buffer_len, span_fits, Error};
+
+#[cfg(feature = "graphics")]
+mod graphics;
+#[cfg(feature = "graphics")]
+pub use self::graphics::{Display2in7, Display2in7Gray};
+
+/// Width of epd2in7 V2 in pixels
+pub const WIDTH: u32 = 176;
+/// Height of epd2in7 V2 in pixels
+pub const HEIGHT: u32 = 264;
+/// Default Background Color (white)
+pub const DEFAULT_BACKGROUND_COLOR: Color = Color::White;
+/// Bytes of a frame with 4 gray levels, 2 bits per pixel
+pub(crate) const NUM_GRAY_BYTES: usize = WIDTH as usize * HEIGHT as usize / 4;
+
+/// Waveform for 4 gray levels from the vendor driver
+///
+/// 153 bytes of LUT, followed by the end option, the gate voltage, the 3 source
+/// voltages and VCOM.
+#[rustfmt::skip]
+pub(crate) const LUT_GRAY4_2IN7: [u8; 159] = [
+    0x40, 0x48, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x08, 0x48, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x02, 0x48, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x20, 0x48, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x0A, 0x19, 0x00, 0x03, 0x08, 0x00, 0x00,
+    0x14, 0x01, 0x00, 0x14, 0x01, 0x00, 0x03,
+    0x0A, 0x03, 0x00, 0x08, 0x19, 0x00, 0x00,
+    0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x00, 0x00, 0x00,
+    0x22, 0x17, 0x41, 0x00, 0x32, 0x1C,
+];
+
+/// Epd2in7 (V2) driver
+pub struct Epd2in7<SPI, CS, BUSY, DC, RST, DELAY> {
+    /// Connection Interface
+    interface: DisplayInterface<SPI, CS, BUSY, DC, RST, DELAY>,
+    /// Background Color
+    background_color: Color,
+    /// Refresh LUT
+    refresh: RefreshLut,
+    /// Position of the visible area in the controller RAM
+    offset: PanelOffset,
+    /// Gray levels of the last uploaded frame
+    mode: GrayMode,
+    /// Deep sleep mode of `sleep`
+    sleep_mode: DeepSleepMode,
+    /// Whether the RAM banks swap their roles after a refresh with display mode 2
+    ping_pong: bool,
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> InternalWiAdditions<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd2in7<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.interface.reset(delay, 2);
+        self.mode = GrayMode::Gray1;
+        self.ping_pong = false;
+
+        self.interface.sw_reset_ssd1680(spi)?;
+
+        self.interface
+            .data_entry_ssd1680(spi, DataEntry::IncrementBoth)?;
+
+        self.use_full_frame(spi)?;
+
+        self.set_lut(spi, None)?;
+
+        self.wait_until_idle();
+        Ok(())
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd2in7<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    type DisplayColor = Color;
+    fn new(
+        spi: &mut SPI,
+        cs: CS,
+        busy: BUSY,
+        dc: DC,
+        rst: RST,
+        delay: &mut DELAY,
+    ) -> Result<Self, Error<SPI::Error>> {
+        let interface = DisplayInterface::new(cs, busy, dc, rst);
+
+        let mut epd = Epd2in7 {
+            interface,
+            background_color: DEFAULT_BACKGROUND_COLOR,
+            refresh: RefreshLut::Full,
+            offset: PanelOffset::default(),
+            mode: GrayMode::Gray1,
+            sleep_mode: DeepSleepMode::RetainRam,
+            ping_pong: false,
+        };
+
+        epd.init(spi, delay)?;
+
+        Ok(epd)
+    }
+
+    fn sleep(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.interface.sleep_ssd1680(spi, self.sleep_mode)
+    }
+
+    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.init(spi, delay)
+    }
+
+    fn set_background_color(&mut self, background_color: Color) {
+        self.background_color = background_color;
+    }
+
+    fn background_color(&self) -> &Color {
+        &self.background_color
+    }
+
+    fn width(&self) -> u32 {
+        WIDTH
+    }
+
+    fn height(&self) -> u32 {
+        HEIGHT
+    }
+
+    fn update_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(buffer.len() == buffer_len(WIDTH as usize, HEIGHT as usize));
+        self.wait_until_idle();
+        self.use_bw_mode(spi)?;
+
+        self.use_full_frame(spi)?;
+        self.interface
+            .cmd_with_data(spi, Command::WriteRam, buffer)?;
+
+        // Always keep the base buffer equal to the current frame, so following
+        // quick refreshes compare against the right data.
+        self.use_full_frame(spi)?;
+        self.interface
+            .cmd_with_data(spi, Command::WriteRam2, buffer)
+    }
+
+    fn update_partial_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(span_fits(x, width, WIDTH) && span_fits(y, height, HEIGHT));
+        self.wait_until_idle();
+        self.use_bw_mode(spi)?;
+        self.set_ram_area(spi, x, y, x + width - 1, y + height - 1)?;
+        self.set_ram_counter(spi, x, y)?;
+
+        self.interface.cmd_with_data(spi, Command::WriteRam, buffer)
+    }
+
+    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.start_display_frame(spi, delay)?;
+        self.wait_until_idle();
+        Ok(())
+    }
+
+    fn start_display_frame(
+        &mut self,
+        spi: &mut SPI,
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        let mode = match (self.mode, self.refresh) {
+            // Enable clock and analog, load temperature and LUT, display, disable analog and clock
+            (GrayMode::Gray1, RefreshLut::Full | RefreshLut::Custom(_)) => 0xF7,
+            // Same as above, but keep the LUT loaded for the faked temperature,
+            // or the gray LUT written by update_gray_frame
+            (GrayMode::Gray1, RefreshLut::Quick) | (GrayMode::Gray4, _) => 0xC7,
+        };
+        self.interface.start_ssd1680(spi, mode)
+    }
+
+    fn update_and_display_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.update_frame(spi, buffer, delay)?;
+        self.display_frame(spi, delay)?;
+        Ok(())
+    }
+
+    fn clear_frame(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        self.use_bw_mode(spi)?;
+        let color = self.background_color.get_byte_value();
+
+        self.use_full_frame(spi)?;
+        self.interface.cmd(spi, Command::WriteRam)?;
+        self.interface.data_x_times(
+            spi,
+            color,
+            buffer_len(WIDTH as usize, HEIGHT as usize) as u32,
+        )?;
+
+        self.use_full_frame(spi)?;
+        self.interface.cmd(spi, Command::WriteRam2)?;
+        self.interface.data_x_times(
+            spi,
+            color,
+            buffer_len(WIDTH as usize, HEIGHT as usize) as u32,
+        )
+    }
+
+    /// `RefreshLut::Quick` selects the fast refresh mode of the vendor driver: the
+    /// controller gets a faked temperature and keeps the matching (shorter) waveform
+    /// loaded for the following full refreshes.
+    fn set_lut(
+        &mut self,
+        spi: &mut SPI,
+        refresh_rate: Option<RefreshLut>,
+    ) -> Result<(), Error<SPI::Error>> {
+        if let Some(RefreshLut::Custom(_)) = refresh_rate {
+            return Err(Error::Unsupported);
+        }
+        if let Some(refresh_lut) = refresh_rate {
+            self.refresh = refresh_lut;
+        }
+        if self.mode == GrayMode::Gray4 {
+            // loaded when the next black/white frame is uploaded
+            return Ok(());
+        }
+
+        self.wait_until_idle();
+        // use the internal temperature sensor
+        self.interface
+            .cmd_with_data(spi, Command::TemperatureSensorSelection, &[0x80])?;
+        // load the temperature and the waveform from OTP
+        self.interface.activate_ssd1680(spi, 0xB1)?;
+
+        if self.refresh == RefreshLut::Quick {
+            // overwrite the measured temperature with 100°C ...
+            self.interface.cmd_with_data(
+                spi,
+                Command::TemperatureSensorControlWrite,
+                &[0x64, 0x00],
+            )?;
+            // ... and only load the waveform for it
+            self.interface.activate_ssd1680(spi, 0x91)?;
+        }
+        Ok(())
+    }
+
+    fn is_busy(&self) -> bool {
+        self.interface.is_busy(IS_BUSY_LOW)
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> QuickRefresh<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd2in7<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    /// Sets the base frame the next quick refresh is compared against.
+    fn update_old_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        self.use_bw_mode(spi)?;
+        self.use_full_frame(spi)?;
+        self.interface
+            .cmd_with_data(spi, Command::WriteRam2, buffer)
+    }
+
+    /// Transmits the frame which is shown with the next `display_new_frame`.
+    fn update_new_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        self.use_bw_mode(spi)?;
+        self.use_full_frame(spi)?;
+        self.interface.cmd_with_data(spi, Command::WriteRam, buffer)
+    }
+
+    /// Quick refresh of the pixels which differ between the old and the new frame.
+    ///
+    /// Afterwards the new frame is copied into the base frame, so that several
+    /// quick refreshes can follow each other.
+    fn display_new_frame(
+        &mut self,
+        spi: &mut SPI,
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        // swap_banks might have enabled it, the old frame is written explicitly
+        self.use_ping_pong(spi, false)?;
+        self.interface.border_ssd1680(spi, 0x80)?;
+        // like a full refresh, but with display mode 2
+        self.interface.activate_ssd1680(spi, 0xFF)
+    }
+
+    fn update_and_display_new_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.update_new_frame(spi, buffer, delay)?;
+        self.display_new_frame(spi, delay)?;
+        self.update_old_frame(spi, buffer, delay)
+    }
+
+    fn update_partial_old_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(span_fits(x, width, WIDTH) && span_fits(y, height, HEIGHT));
+        self.wait_until_idle();
+        self.use_bw_mode(spi)?;
+        self.set_ram_area(spi, x, y, x + width - 1, y + height - 1)?;
+        self.set_ram_counter(spi, x, y)?;
+
+        self.interface
+            .cmd_with_data(spi, Command::WriteRam2, buffer)
+    }
+
+    fn update_partial_new_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(span_fits(x, width, WIDTH) && span_fits(y, height, HEIGHT));
+        self.wait_until_idle();
+        self.use_bw_mode(spi)?;
+        self.set_ram_area(spi, x, y, x + width - 1, y + height - 1)?;
+        self.set_ram_counter(spi, x, y)?;
+
+        self.interface.cmd_with_data(spi, Command::WriteRam, buffer)
+    }
+
+    fn clear_partial_frame(
+        &mut self,
+        spi: &mut SPI,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(span_fits(x, width, WIDTH) && span_fits(y, height, HEIGHT));
+        self.wait_until_idle();
+        self.use_bw_mode(spi)?;
+        let color = self.background_color.get_byte_value();
+        let len = buffer_len(width as usize, height as usize) as u32;
+
+        for command in [Command::WriteRam, Command::WriteRam2] {
+            self.set_ram_area(spi, x, y, x + width - 1, y + height - 1)?;
+            self.set_ram_counter(spi, x, y)?;
+            self.interface.cmd(spi, command)?;
+            self.interface.data_x_times(spi, color, len)?;
+        }
+        Ok(())
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> RamBanks<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd2in7<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn update_back_bank(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(buffer.len() == buffer_len(WIDTH as usize, HEIGHT as usize));
+        self.wait_until_idle();
+        self.use_bw_mode(spi)?;
+        self.use_full_frame(spi)?;
+        // with ping-pong enabled, this always addresses the bank which isn't shown
+        self.interface.cmd_with_data(spi, Command::WriteRam, buffer)
+    }
+
+    fn update_partial_back_bank(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.update_partial_frame(spi, buffer, x, y, width, height)
+    }
+
+    fn swap_banks(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        self.use_bw_mode(spi)?;
+        // let both banks swap their roles after every refresh with display mode 2, until
+        // display_new_frame turns it off again
+        self.use_ping_pong(spi, true)?;
+        // like a full refresh, but with display mode 2
+        self.interface.activate_ssd1680(spi, 0xFF)
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> Epd2in7<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    /// Uploads a frame with 4 gray levels and switches the display into `GrayMode::Gray4`
+    ///
+    /// The buffer holds 2 bits per pixel, see [`Display2in7Gray`].
+    pub fn update_gray_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(buffer.len() == NUM_GRAY_BYTES);
+        self.wait_until_idle();
+
+        if self.mode == GrayMode::Gray1 {
+            self.mode = GrayMode::Gray4;
+            self.interface
+                .cmd_with_data(spi, Command::SetAnalogBlockControl, &[0x54])?;
+            self.interface
+                .cmd_with_data(spi, Command::SetDigitalBlockControl, &[0x3B])?;
+            self.interface.border_ssd1680(spi, 0x00)?;
+            self.set_gray_lut(spi)?;
+        }
+
+        // the high bit of every pixel goes into the first RAM, the low bit into the second
+        self.use_full_frame(spi)?;
+        self.interface.cmd(spi, Command::WriteRam)?;
+        self.interface.data_gray_plane(spi, buffer, 0b10, false)?;
+        self.use_full_frame(spi)?;
+        self.interface.cmd(spi, Command::WriteRam2)?;
+        self.interface.data_gray_plane(spi, buffer, 0b01, false)
+    }
+
+    /// Mode of the last uploaded frame
+    pub fn gray_mode(&self) -> GrayMode {
+        self.mode
+    }
+
+    /// Switches back to black/white frames with the waveform from OTP
+    fn use_bw_mode(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        if self.mode == GrayMode::Gray4 {
+            self.mode = GrayMode::Gray1;
+            // value after reset
+            self.interface.border_ssd1680(spi, 0xC0)?;
+            self.set_lut(spi, None)?;
+        }
+        Ok(())
+    }
+
+    fn set_gray_lut(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        let lut = &LUT_GRAY4_2IN7;
+        self.interface
+            .cmd_with_data(spi, Command::WriteLutRegister, &lut[..153])?;
+        self.wait_until_idle();
+        self.interface
+            .cmd_with_data(spi, Command::EndOption, &lut[153..154])?;
+        self.interface
+            .cmd_with_data(spi, Command::GateDrivingVoltageControl, &lut[154..155])?;
+        self.interface
+            .cmd_with_data(spi, Command::SourceDrivingVoltageControl, &lut[155..158])?;
+        self.interface
+            .cmd_with_data(spi, Command::WriteVcomRegister, &lut[158..159])
+    }
+
+    fn wait_until_idle(&mut self) {
+        self.interface.wait_until_idle(IS_BUSY_LOW);
+    }
+
+    /// Enables or disables the ping-pong of the RAM banks, if it isn't already
+    fn use_ping_pong(&mut self, spi: &mut SPI, enabled: bool) -> Result<(), Error<SPI::Error>> {
+        if self.ping_pong != enabled {
+            self.interface.ping_pong_ssd1680(spi, enabled)?;
+            self.ping_pong = enabled;
+        }
+        Ok(())
+    }
+
+    fn use_full_frame(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        // choose full frame/ram
+        self.set_ram_area(spi, 0, 0, WIDTH - 1, HEIGHT - 1)?;
+
+        // start from the beginning
+        self.set_ram_counter(spi, 0, 0)
+    }
+
+    fn set_ram_area(
+        &mut self,
+        spi: &mut SPI,
+        start_x: u32,
+        start_y: u32,
+        end_x: u32,
+        end_y: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        let (start_x, end_x) = (start_x + self.offset.source, end_x + self.offset.source);
+        let (start_y, end_y) = (start_y + self.offset.gate, end_y + self.offset.gate);
+        self.interface
+            .window_ssd1680(spi, start_x, start_y, end_x, end_y)
+    }
+
+    fn set_ram_counter(&mut self, spi: &mut SPI, x: u32, y: u32) -> Result<(), Error<SPI::Error>> {
+        let (x, y) = (x + self.offset.source, y + self.offset.gate);
+        self.interface.cursor_ssd1680(spi, x, y)
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> ReadRevision<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd2in7<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8> + Transfer<u8, Error = <SPI as Write<u8>>::Error>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn read_revision(
+        &mut self,
+        spi: &mut SPI,
+    ) -> Result<Revision, Error<<SPI as Write<u8>>::Error>> {
+        self.interface.revision_ssd1680(spi)
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> ReadTemperature<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd2in7<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8> + Transfer<u8, Error = <SPI as Write<u8>>::Error>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn read_temperature(&mut self, spi: &mut SPI) -> Result<i8, Error<<SPI as Write<u8>>::Error>> {
+        self.interface.temperature_ssd1680(spi)
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> GlassOffset<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd2in7<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn set_panel_offset(&mut self, offset: PanelOffset) {
+        self.offset = offset;
+    }
+
+    fn panel_offset(&self) -> PanelOffset {
+        self.offset
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> SleepMode<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd2in7<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn set_sleep_mode(&mut self, mode: DeepSleepMode) {
+        self.sleep_mode = mode;
+    }
+
+    fn sleep_mode(&self) -> DeepSleepMode {
+        self.sleep_mode
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus_log::{commands, data_of, Log, Pin, Spi};
+    use embedded_hal_mock::delay::MockNoop;
+
+    #[test]
+    fn epd_size() {
+        assert_eq!(WIDTH, 176);
+        assert_eq!(HEIGHT, 264);
+        assert_eq!(NUM_GRAY_BYTES, 11616);
+        assert_eq!(DEFAULT_BACKGROUND_COLOR, Color::White);
+    }
+
+    #[test]
+    fn partial_refresh_after_swap_disables_ping_pong() {
+        let log = Log::default();
+        let mut spi = Spi(&log);
+        let mut delay = MockNoop::new();
+        let mut epd = Epd2in7::new(
+            &mut spi,
+            Pin(None),
+            Pin(None),
+            Pin(Some(&log)),
+            Pin(None),
+            &mut delay,
+        )
+        .unwrap();
+        let frame = [0xFF; WIDTH as usize / 8 * HEIGHT as usize];
+
+        log.take();
+        epd.update_back_bank(&mut spi, &frame, &mut delay).unwrap();
+        epd.swap_banks(&mut spi, &mut delay).unwrap();
+        let events = log.take();
+        assert_eq!(data_of(&events, 0x37), [[0, 0, 0, 0, 0, 0x40, 0, 0, 0, 0]]);
+
+        // a second swap keeps it enabled
+        epd.swap_banks(&mut spi, &mut delay).unwrap();
+        assert!(data_of(&log.take(), 0x37).is_empty());
+
+        epd.update_new_frame(&mut spi, &frame, &mut delay).unwrap();
+        epd.display_new_frame(&mut spi, &mut delay).unwrap();
+        let events = log.take();
+        assert_eq!(data_of(&events, 0x37), [[0; 10]]);
+        let commands = commands(&events);
+        let ping_pong = commands.iter().position(|&c| c == 0x37).unwrap();
+        let activation = commands.iter().position(|&c| c == 0x20).unwrap();
+        assert!(ping_pong < activation);
+    }
+}
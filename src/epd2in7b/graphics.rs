@@ -1,29 +1,30 @@
-use crate::epd2in7b::{DEFAULT_BACKGROUND_COLOR, HEIGHT, WIDTH};
-use crate::graphics::{Display, DisplayRotation};
-use embedded_graphics::pixelcolor::BinaryColor;
+use crate::color::TriColor;
+use crate::epd2in7b::{DEFAULT_BACKGROUND_COLOR, HEIGHT, NUM_DISPLAY_BITS, WIDTH};
+use crate::graphics::{DisplayRotation, TriDisplay};
 use embedded_graphics_core::prelude::*;
 
 /// Full size buffer for use with the 2in7B EPD
 ///
-/// Can also be manuall constructed:
-/// `buffer: [DEFAULT_BACKGROUND_COLOR.get_byte_value(); WIDTH * HEIGHT / 8]`
+/// Can also be manually constructed and be used together with VarDisplay
 pub struct Display2in7b {
-    buffer: [u8; WIDTH as usize * HEIGHT as usize / 8],
+    // one buffer for both b/w and for chromatic:
+    // * &buffer[0..NUM_DISPLAY_BITS] for b/w buffer and
+    // * &buffer[NUM_DISPLAY_BITS..2*NUM_DISPLAY_BITS] for chromatic buffer
+    buffer: [u8; 2 * NUM_DISPLAY_BITS as usize],
     rotation: DisplayRotation,
 }
 
 impl Default for Display2in7b {
     fn default() -> Self {
         Display2in7b {
-            buffer: [DEFAULT_BACKGROUND_COLOR.get_byte_value();
-                WIDTH as usize * HEIGHT as usize / 8],
+            buffer: [DEFAULT_BACKGROUND_COLOR.get_byte_value(); 2 * NUM_DISPLAY_BITS as usize],
             rotation: DisplayRotation::default(),
         }
     }
 }
 
 impl DrawTarget for Display2in7b {
-    type Color = BinaryColor;
+    type Color = TriColor;
     type Error = core::convert::Infallible;
 
     fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
@@ -31,7 +32,7 @@ impl DrawTarget for Display2in7b {
         I: IntoIterator<Item = Pixel<Self::Color>>,
     {
         for pixel in pixels {
-            self.draw_helper(WIDTH, HEIGHT, pixel)?;
+            self.draw_helper_tri(WIDTH, HEIGHT, pixel)?;
         }
         Ok(())
     }
@@ -43,7 +44,7 @@ impl OriginDimensions for Display2in7b {
     }
 }
 
-impl Display for Display2in7b {
+impl TriDisplay for Display2in7b {
     fn buffer(&self) -> &[u8] {
         &self.buffer
     }
@@ -59,16 +60,26 @@ impl Display for Display2in7b {
     fn rotation(&self) -> DisplayRotation {
         self.rotation
     }
+
+    fn chromatic_offset(&self) -> usize {
+        NUM_DISPLAY_BITS as usize
+    }
+
+    fn bw_buffer(&self) -> &[u8] {
+        &self.buffer[0..self.chromatic_offset()]
+    }
+
+    fn chromatic_buffer(&self) -> &[u8] {
+        &self.buffer[self.chromatic_offset()..]
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::color::Black;
-    use crate::color::Color;
     use crate::epd2in7b;
     use crate::epd2in7b::{HEIGHT, WIDTH};
-    use crate::graphics::{Display, DisplayRotation};
+    use crate::graphics::{DisplayRotation, TriDisplay};
     use embedded_graphics::{
         prelude::*,
         primitives::{Line, PrimitiveStyle},
@@ -78,7 +89,9 @@ mod tests {
     #[test]
     fn graphics_size() {
         let display = Display2in7b::default();
-        assert_eq!(display.buffer().len(), 5808);
+        assert_eq!(display.buffer().len(), 2 * 5808);
+        assert_eq!(display.bw_buffer().len(), 5808);
+        assert_eq!(display.chromatic_buffer().len(), 5808);
     }
 
     // test default background color on all bytes
@@ -94,12 +107,12 @@ mod tests {
     fn graphics_rotation_0() {
         let mut display = Display2in7b::default();
         let _ = Line::new(Point::new(0, 0), Point::new(7, 0))
-            .into_styled(PrimitiveStyle::with_stroke(Black, 1))
+            .into_styled(PrimitiveStyle::with_stroke(TriColor::Black, 1))
             .draw(&mut display);
 
-        let buffer = display.buffer();
+        let buffer = display.bw_buffer();
 
-        assert_eq!(buffer[0], Color::Black.get_byte_value());
+        assert_eq!(buffer[0], TriColor::Black.get_byte_value());
 
         for &byte in buffer.iter().skip(1) {
             assert_eq!(byte, epd2in7b::DEFAULT_BACKGROUND_COLOR.get_byte_value());
@@ -114,12 +127,12 @@ mod tests {
             Point::new(0, WIDTH as i32 - 8),
             Point::new(0, WIDTH as i32 - 1),
         )
-        .into_styled(PrimitiveStyle::with_stroke(Black, 1))
+        .into_styled(PrimitiveStyle::with_stroke(TriColor::Black, 1))
         .draw(&mut display);
 
-        let buffer = display.buffer();
+        let buffer = display.bw_buffer();
 
-        assert_eq!(buffer[0], Color::Black.get_byte_value());
+        assert_eq!(buffer[0], TriColor::Black.get_byte_value());
 
         for &byte in buffer.iter().skip(1) {
             assert_eq!(byte, epd2in7b::DEFAULT_BACKGROUND_COLOR.get_byte_value());
@@ -135,15 +148,12 @@ mod tests {
             Point::new(WIDTH as i32 - 8, HEIGHT as i32 - 1),
             Point::new(WIDTH as i32 - 1, HEIGHT as i32 - 1),
         )
-        .into_styled(PrimitiveStyle::with_stroke(Black, 1))
+        .into_styled(PrimitiveStyle::with_stroke(TriColor::Black, 1))
         .draw(&mut display);
 
-        let buffer = display.buffer();
-
-        extern crate std;
-        std::println!("{:?}", buffer);
+        let buffer = display.bw_buffer();
 
-        assert_eq!(buffer[0], Color::Black.get_byte_value());
+        assert_eq!(buffer[0], TriColor::Black.get_byte_value());
 
         for &byte in buffer.iter().skip(1) {
             assert_eq!(byte, epd2in7b::DEFAULT_BACKGROUND_COLOR.get_byte_value());
@@ -158,18 +168,34 @@ mod tests {
             Point::new(HEIGHT as i32 - 1, 0),
             Point::new(HEIGHT as i32 - 1, 7),
         )
-        .into_styled(PrimitiveStyle::with_stroke(Black, 1))
+        .into_styled(PrimitiveStyle::with_stroke(TriColor::Black, 1))
         .draw(&mut display);
 
-        let buffer = display.buffer();
-
-        extern crate std;
-        std::println!("{:?}", buffer);
+        let buffer = display.bw_buffer();
 
-        assert_eq!(buffer[0], Color::Black.get_byte_value());
+        assert_eq!(buffer[0], TriColor::Black.get_byte_value());
 
         for &byte in buffer.iter().skip(1) {
             assert_eq!(byte, epd2in7b::DEFAULT_BACKGROUND_COLOR.get_byte_value());
         }
     }
+
+    #[test]
+    fn graphics_chromatic() {
+        let mut display = Display2in7b::default();
+        let _ = Line::new(Point::new(0, 0), Point::new(7, 0))
+            .into_styled(PrimitiveStyle::with_stroke(TriColor::Chromatic, 1))
+            .draw(&mut display);
+
+        // chromatic pixels are white on the b/w plane
+        for &byte in display.bw_buffer() {
+            assert_eq!(byte, epd2in7b::DEFAULT_BACKGROUND_COLOR.get_byte_value());
+        }
+
+        let chromatic = display.chromatic_buffer();
+        assert_eq!(chromatic[0], 0x00);
+        for &byte in chromatic.iter().skip(1) {
+            assert_eq!(byte, epd2in7b::DEFAULT_BACKGROUND_COLOR.get_byte_value());
+        }
+    }
 }
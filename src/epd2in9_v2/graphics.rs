@@ -1,6 +1,7 @@
 use crate::epd2in9::{DEFAULT_BACKGROUND_COLOR, HEIGHT, WIDTH};
-use crate::graphics::{Display, DisplayRotation};
-use embedded_graphics::pixelcolor::BinaryColor;
+use crate::epd2in9_v2::NUM_GRAY_BYTES;
+use crate::graphics::{Display, DisplayRotation, GrayDisplay};
+use embedded_graphics::pixelcolor::{BinaryColor, Gray2};
 use embedded_graphics_core::prelude::*;
 
 /// Display with Fullsize buffer for use with the 2in9 EPD V2
@@ -61,6 +62,62 @@ impl Display for Display2in9 {
     }
 }
 
+/// Full size buffer with 4 gray levels for use with the 2in9 EPD V2
+///
+/// Every byte holds 4 pixels, white (`0xFF`) is the default.
+pub struct Display2in9Gray {
+    buffer: [u8; NUM_GRAY_BYTES],
+    rotation: DisplayRotation,
+}
+
+impl Default for Display2in9Gray {
+    fn default() -> Self {
+        Display2in9Gray {
+            buffer: [0xFF; NUM_GRAY_BYTES],
+            rotation: DisplayRotation::default(),
+        }
+    }
+}
+
+impl DrawTarget for Display2in9Gray {
+    type Color = Gray2;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for pixel in pixels {
+            self.draw_helper(WIDTH, HEIGHT, pixel)?;
+        }
+        Ok(())
+    }
+}
+
+impl OriginDimensions for Display2in9Gray {
+    fn size(&self) -> Size {
+        Size::new(WIDTH, HEIGHT)
+    }
+}
+
+impl GrayDisplay for Display2in9Gray {
+    fn buffer(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    fn get_mut_buffer(&mut self) -> &mut [u8] {
+        &mut self.buffer
+    }
+
+    fn set_rotation(&mut self, rotation: DisplayRotation) {
+        self.rotation = rotation;
+    }
+
+    fn rotation(&self) -> DisplayRotation {
+        self.rotation
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -80,4 +137,20 @@ mod tests {
             assert_eq!(byte, DEFAULT_BACKGROUND_COLOR.get_byte_value());
         }
     }
+
+    #[test]
+    fn graphics_gray_size() {
+        let display = Display2in9Gray::default();
+        assert_eq!(display.buffer().len(), 9472);
+    }
+
+    #[test]
+    fn graphics_gray_levels() {
+        let mut display = Display2in9Gray::default();
+        for (x, luma) in (0..4).enumerate() {
+            let _ = Pixel(Point::new(x as i32, 0), Gray2::new(luma)).draw(&mut display);
+        }
+        assert_eq!(display.buffer()[0], 0b00_01_10_11);
+        assert_eq!(display.buffer()[1], 0xFF);
+    }
 }
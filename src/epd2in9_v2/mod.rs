@@ -6,7 +6,7 @@
 //!
 //!```rust, no_run
 //!# use embedded_hal_mock::*;
-//!# fn main() -> Result<(), MockError> {
+//!# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
 //!use embedded_graphics::{
 //!    pixelcolor::BinaryColor::On as Black, prelude::*, primitives::{Line, PrimitiveStyle},
 //!};
@@ -53,6 +53,46 @@
 //!# Ok(())
 //!# }
 //!```
+//!
+//! # 4 gray levels
+//!
+//! The display can also show black, dark gray, light gray and white with the
+//! waveform of the vendor driver. Draw into the `Gray2` buffer [`Display2in9Gray`]
+//! and upload it with [`Epd2in9::update_gray_frame`]. Gray frames are always shown
+//! with a full refresh, uploading a black/white frame switches back.
+//!
+//! The two grays are enough to smooth the edges of text, draw the glyphs with their
+//! coverage mapped to `Gray2` values to get antialiased fonts on an e-reader.
+//!
+//!```rust, no_run
+//!# use embedded_hal_mock::*;
+//!# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
+//!use embedded_graphics::{
+//!    pixelcolor::Gray2, prelude::*, primitives::{Line, PrimitiveStyle},
+//!};
+//!use epd_waveshare::{epd2in9_v2::*, prelude::*};
+//!#
+//!# let expectations = [];
+//!# let mut spi = spi::Mock::new(&expectations);
+//!# let expectations = [];
+//!# let cs_pin = pin::Mock::new(&expectations);
+//!# let busy_in = pin::Mock::new(&expectations);
+//!# let dc = pin::Mock::new(&expectations);
+//!# let rst = pin::Mock::new(&expectations);
+//!# let mut delay = delay::MockNoop::new();
+//!
+//!let mut epd = Epd2in9::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
+//!let mut display = Display2in9Gray::default();
+//!
+//!let _ = Line::new(Point::new(10, 120), Point::new(10, 295))
+//!    .into_styled(PrimitiveStyle::with_stroke(Gray2::new(1), 1))
+//!    .draw(&mut display);
+//!
+//!epd.update_gray_frame(&mut spi, display.buffer())?;
+//!epd.display_frame(&mut spi, &mut delay)?;
+//!# Ok(())
+//!# }
+//!```
 
 /// Width of epd2in9 in pixels
 pub const WIDTH: u32 = 128;
@@ -60,9 +100,11 @@ pub const WIDTH: u32 = 128;
 pub const HEIGHT: u32 = 296;
 /// Default Background Color (white)
 pub const DEFAULT_BACKGROUND_COLOR: Color = Color::White;
-const IS_BUSY_LOW: bool = false;
 
-const LUT_PARTIAL_2IN9: [u8; 153] = [
+/// Number of bytes of a frame with 2 bits per pixel
+const NUM_GRAY_BYTES: usize = WIDTH as usize * HEIGHT as usize / 4;
+
+pub(crate) const LUT_PARTIAL_2IN9: [u8; 153] = [
     0x0, 0x40, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x80, 0x80, 0x0, 0x0, 0x0, 0x0,
     0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x40, 0x40, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0,
     0x0, 0x80, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0,
@@ -74,24 +116,56 @@ const LUT_PARTIAL_2IN9: [u8; 153] = [
     0x22, 0x0, 0x0, 0x0,
 ];
 
+/// 4 gray waveform of the vendor driver, followed by the end option, gate voltage,
+/// the three source voltages and VCOM
+#[rustfmt::skip]
+pub(crate) const LUT_GRAY4_2IN9: [u8; 159] = [
+    0x00, 0x60, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x20, 0x60, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x28, 0x60, 0x14, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x2A, 0x60, 0x15, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x90, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x02, 0x00, 0x05, 0x14, 0x00, 0x00,
+    0x1E, 0x1E, 0x00, 0x00, 0x00, 0x00, 0x01,
+    0x00, 0x02, 0x00, 0x05, 0x14, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x24, 0x22, 0x22, 0x22, 0x23, 0x32, 0x00, 0x00, 0x00,
+    0x22, 0x17, 0x41, 0xAE, 0x32, 0x28,
+];
+
 use embedded_hal::{
-    blocking::{delay::*, spi::Write},
+    blocking::{
+        delay::*,
+        spi::{Transfer, Write},
+    },
     digital::v2::*,
 };
 
-use crate::type_a::command::Command;
+use crate::controllers::ssd1680::{Command, DataEntry, IS_BUSY_LOW};
 
 use crate::color::Color;
+pub use crate::color::GrayMode;
+use crate::{buffer_len, span_fits, Error};
 
 use crate::traits::*;
 
 use crate::interface::DisplayInterface;
+use crate::luts::{ssd1680, LutPreset, LutSource, Panel};
+use crate::refresh::QuickRefreshCounter;
 use crate::traits::QuickRefresh;
 
 #[cfg(feature = "graphics")]
 mod graphics;
 #[cfg(feature = "graphics")]
-pub use crate::epd2in9_v2::graphics::Display2in9;
+pub use crate::epd2in9_v2::graphics::{Display2in9, Display2in9Gray};
 
 /// Epd2in9 driver
 ///
@@ -102,6 +176,18 @@ pub struct Epd2in9<SPI, CS, BUSY, DC, RST, DELAY> {
     background_color: Color,
     /// Refresh LUT
     refresh: RefreshLut,
+    /// Mode of the last uploaded frame
+    mode: GrayMode,
+    /// Waveform of the full refreshes, if it doesn't come from the OTP
+    register_lut: Option<&'static LutPreset>,
+    /// Whether the registers hold a waveform written by `load_lut`
+    custom_lut: bool,
+    /// Deep sleep mode of `sleep`
+    sleep_mode: DeepSleepMode,
+    /// Quick refreshes since the last full one
+    quick_refreshes: QuickRefreshCounter,
+    /// Gate scan direction
+    mirror: Mirror,
 }
 
 impl<SPI, CS, BUSY, DC, RST, DELAY> Epd2in9<SPI, CS, BUSY, DC, RST, DELAY>
@@ -113,25 +199,25 @@ where
     RST: OutputPin,
     DELAY: DelayMs<u8>,
 {
-    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         self.interface.reset(delay, 2);
 
-        self.wait_until_idle();
-        self.interface.cmd(spi, Command::SwReset)?;
-        self.wait_until_idle();
+        self.interface.sw_reset_ssd1680(spi)?;
 
         // 3 Databytes:
         // A[7:0]
         // 0.. A[8]
         // 0.. B[2:0]
         // Default Values: A = Height of Screen (0x127), B = 0x00 (GD, SM and TB=0?)
-        self.interface
-            .cmd_with_data(spi, Command::DriverOutputControl, &[0x27, 0x01, 0x00])?;
+        // TB reverses the gate scan for a vertical mirror
+        self.interface.cmd_with_data(
+            spi,
+            Command::DriverOutputControl,
+            &[0x27, 0x01, self.mirror.vertical() as u8],
+        )?;
 
-        // One Databyte with default value 0x03
-        //  -> address: x increment, y increment, address counter is updated in x direction
         self.interface
-            .cmd_with_data(spi, Command::DataEntryModeSetting, &[0x03])?;
+            .data_entry_ssd1680(spi, DataEntry::IncrementBoth)?;
 
         self.set_ram_area(spi, 0, 0, WIDTH - 1, HEIGHT - 1)?;
 
@@ -141,8 +227,75 @@ where
         self.set_ram_counter(spi, 0, 0)?;
 
         self.wait_until_idle();
+        self.mode = GrayMode::Gray1;
+        self.custom_lut = false;
         Ok(())
     }
+
+    /// Uploads a frame with 4 gray levels and switches the display into `GrayMode::Gray4`
+    ///
+    /// The buffer holds 2 bits per pixel, see [`Display2in9Gray`]. The gray waveform
+    /// is loaded on every call, since a quick refresh replaces it.
+    pub fn update_gray_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(buffer.len() == NUM_GRAY_BYTES);
+        self.wait_until_idle();
+        self.mode = GrayMode::Gray4;
+
+        self.interface.border_ssd1680(spi, 0x04)?;
+        self.set_gray_lut(spi)?;
+
+        // the high bit of every pixel goes into the first RAM, the low bit into the second
+        self.use_full_frame(spi)?;
+        self.interface.cmd(spi, Command::WriteRam)?;
+        self.interface.data_gray_plane(spi, buffer, 0b10, false)?;
+        self.use_full_frame(spi)?;
+        self.interface.cmd(spi, Command::WriteRam2)?;
+        self.interface.data_gray_plane(spi, buffer, 0b01, false)
+    }
+
+    /// Mode of the last uploaded frame
+    pub fn gray_mode(&self) -> GrayMode {
+        self.mode
+    }
+
+    /// Switches back to black/white frames with the waveform from OTP
+    fn use_bw_mode(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        if self.mode == GrayMode::Gray4 {
+            self.mode = GrayMode::Gray1;
+            self.interface.border_ssd1680(spi, 0x05)?;
+            self.use_full_frame(spi)?;
+        }
+        Ok(())
+    }
+
+    /// Loads the partial refresh waveform and enables the RAM ping-pong
+    fn use_partial_lut(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        self.set_lut_helper(spi, &LUT_PARTIAL_2IN9)?;
+        self.interface.cmd_with_data(
+            spi,
+            Command::WriteOtpSelection,
+            &[0x00, 0x00, 0x00, 0x00, 0x00, 0x40, 0x00, 0x00, 0x00, 0x00],
+        )?;
+        self.interface.border_ssd1680(spi, 0x80)?;
+        self.interface.activate_ssd1680(spi, 0xC0)
+    }
+
+    fn set_gray_lut(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        let lut = &LUT_GRAY4_2IN9;
+        self.set_lut_helper(spi, &lut[..153])?;
+        self.interface
+            .cmd_with_data(spi, Command::EndOption, &lut[153..154])?;
+        self.interface
+            .cmd_with_data(spi, Command::GateDrivingVoltageControl, &lut[154..155])?;
+        self.interface
+            .cmd_with_data(spi, Command::SourceDrivingVoltageControl, &lut[155..158])?;
+        self.interface
+            .cmd_with_data(spi, Command::WriteVcomRegister, &lut[158..159])
+    }
 }
 
 impl<SPI, CS, BUSY, DC, RST, DELAY> WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>
@@ -171,13 +324,19 @@ where
         dc: DC,
         rst: RST,
         delay: &mut DELAY,
-    ) -> Result<Self, SPI::Error> {
+    ) -> Result<Self, Error<SPI::Error>> {
         let interface = DisplayInterface::new(cs, busy, dc, rst);
 
         let mut epd = Epd2in9 {
             interface,
             background_color: DEFAULT_BACKGROUND_COLOR,
             refresh: RefreshLut::Full,
+            mode: GrayMode::Gray1,
+            register_lut: None,
+            custom_lut: false,
+            sleep_mode: DeepSleepMode::RetainRam,
+            quick_refreshes: QuickRefreshCounter::default(),
+            mirror: Mirror::None,
         };
 
         epd.init(spi, delay)?;
@@ -185,15 +344,11 @@ where
         Ok(epd)
     }
 
-    fn sleep(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), SPI::Error> {
-        self.wait_until_idle();
-        // 0x00 for Normal mode (Power on Reset), 0x01 for Deep Sleep Mode
-        self.interface
-            .cmd_with_data(spi, Command::DeepSleepMode, &[0x01])?;
-        Ok(())
+    fn sleep(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.interface.sleep_ssd1680(spi, self.sleep_mode)
     }
 
-    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         self.init(spi, delay)?;
         Ok(())
     }
@@ -203,8 +358,9 @@ where
         spi: &mut SPI,
         buffer: &[u8],
         _delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.wait_until_idle();
+        self.use_bw_mode(spi)?;
         self.interface.cmd_with_data(spi, Command::WriteRam, buffer)
     }
 
@@ -216,9 +372,11 @@ where
         y: u32,
         width: u32,
         height: u32,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         //TODO This is copied from epd2in9 but it seems not working. Partial refresh supported by version 2?
+        ensure!(span_fits(x, width, WIDTH) && span_fits(y, height, HEIGHT));
         self.wait_until_idle();
+        self.use_bw_mode(spi)?;
         self.set_ram_area(spi, x, y, x + width, y + height)?;
         self.set_ram_counter(spi, x, y)?;
 
@@ -227,30 +385,52 @@ where
         Ok(())
     }
 
-    fn display_frame(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), SPI::Error> {
-        self.wait_until_idle();
-        // Enable clock signal, Enable Analog, Load temperature value, DISPLAY with DISPLAY Mode 1, Disable Analog, Disable OSC
-        self.interface
-            .cmd_with_data(spi, Command::DisplayUpdateControl2, &[0xF7])?;
-        self.interface.cmd(spi, Command::MasterActivation)?;
+    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.start_display_frame(spi, delay)?;
         self.wait_until_idle();
         Ok(())
     }
 
+    fn start_display_frame(
+        &mut self,
+        spi: &mut SPI,
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        self.quick_refreshes.full();
+        let mode = match (self.mode, self.register_lut) {
+            // Enable clock signal, Enable Analog, Load temperature value, DISPLAY with DISPLAY Mode 1, Disable Analog, Disable OSC
+            (GrayMode::Gray1, None) if !self.custom_lut => 0xF7,
+            // Same as above, but with the LUT in the registers, written again as the
+            // partial refreshes replace it
+            (GrayMode::Gray1, Some(preset)) => {
+                self.interface
+                    .write_lut_preset(spi, Panel::Epd2in9V2, preset)?;
+                0xC7
+            }
+            // Same as above, but with the gray LUT written by update_gray_frame or the one
+            // of load_lut
+            (GrayMode::Gray4, _) | (GrayMode::Gray1, None) => 0xC7,
+        };
+        self.interface.start_ssd1680(spi, mode)
+    }
+
     fn update_and_display_frame(
         &mut self,
         spi: &mut SPI,
         buffer: &[u8],
         delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.update_frame(spi, buffer, delay)?;
         self.display_frame(spi, delay)?;
         Ok(())
     }
 
-    fn clear_frame(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn clear_frame(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         self.wait_until_idle();
 
+        self.use_bw_mode(spi)?;
+
         // clear the ram with the background color
         let color = self.background_color.get_byte_value();
 
@@ -270,9 +450,18 @@ where
         &mut self,
         _spi: &mut SPI,
         refresh_rate: Option<RefreshLut>,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         if let Some(refresh_lut) = refresh_rate {
             self.refresh = refresh_lut;
+            // written before every full refresh, like the one of `use_register_lut`
+            self.register_lut = match refresh_lut {
+                RefreshLut::Custom(preset) => {
+                    ensure!(preset.panel == Panel::Epd2in9V2);
+                    Some(preset)
+                }
+                RefreshLut::Full | RefreshLut::Quick => None,
+            };
+            self.custom_lut = false;
         }
         Ok(())
     }
@@ -291,11 +480,27 @@ where
     RST: OutputPin,
     DELAY: DelayMs<u8>,
 {
+    /// Writes the waveform of a [`LutPreset`] made for this panel
+    ///
+    /// The driver loads its own waveform again for partial and gray refreshes, so the preset
+    /// has to be written after those are set up.
+    ///
+    /// Panics if the preset is made for another panel.
+    pub fn set_lut_preset(
+        &mut self,
+        spi: &mut SPI,
+        preset: &LutPreset,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        self.interface
+            .write_lut_preset(spi, Panel::Epd2in9V2, preset)
+    }
+
     fn wait_until_idle(&mut self) {
         self.interface.wait_until_idle(IS_BUSY_LOW);
     }
 
-    fn use_full_frame(&mut self, spi: &mut SPI) -> Result<(), SPI::Error> {
+    fn use_full_frame(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
         // choose full frame/ram
         self.set_ram_area(spi, 0, 0, WIDTH - 1, HEIGHT - 1)?;
 
@@ -310,50 +515,19 @@ where
         start_y: u32,
         end_x: u32,
         end_y: u32,
-    ) -> Result<(), SPI::Error> {
-        assert!(start_x < end_x);
-        assert!(start_y < end_y);
-
-        // x is positioned in bytes, so the last 3 bits which show the position inside a byte in the ram
-        // aren't relevant
-        self.interface.cmd_with_data(
-            spi,
-            Command::SetRamXAddressStartEndPosition,
-            &[(start_x >> 3) as u8, (end_x >> 3) as u8],
-        )?;
-
-        // 2 Databytes: A[7:0] & 0..A[8] for each - start and end
-        self.interface.cmd_with_data(
-            spi,
-            Command::SetRamYAddressStartEndPosition,
-            &[
-                start_y as u8,
-                (start_y >> 8) as u8,
-                end_y as u8,
-                (end_y >> 8) as u8,
-            ],
-        )
-    }
-
-    fn set_ram_counter(&mut self, spi: &mut SPI, x: u32, y: u32) -> Result<(), SPI::Error> {
-        self.wait_until_idle();
-        // x is positioned in bytes, so the last 3 bits which show the position inside a byte in the ram
-        // aren't relevant
+    ) -> Result<(), Error<SPI::Error>> {
         self.interface
-            .cmd_with_data(spi, Command::SetRamXAddressCounter, &[x as u8])?;
+            .window_ssd1680(spi, start_x, start_y, end_x, end_y)
+    }
 
-        // 2 Databytes: A[7:0] & 0..A[8]
-        self.interface.cmd_with_data(
-            spi,
-            Command::SetRamYAddressCounter,
-            &[y as u8, (y >> 8) as u8],
-        )?;
-        Ok(())
+    fn set_ram_counter(&mut self, spi: &mut SPI, x: u32, y: u32) -> Result<(), Error<SPI::Error>> {
+        self.interface.cursor_ssd1680(spi, x, y)
     }
 
     /// Set your own LUT, this function is also used internally for set_lut
-    fn set_lut_helper(&mut self, spi: &mut SPI, buffer: &[u8]) -> Result<(), SPI::Error> {
+    fn set_lut_helper(&mut self, spi: &mut SPI, buffer: &[u8]) -> Result<(), Error<SPI::Error>> {
         self.wait_until_idle();
+        self.custom_lut = false;
         self.interface
             .cmd_with_data(spi, Command::WriteLutRegister, buffer)?;
         self.wait_until_idle();
@@ -377,8 +551,9 @@ where
         spi: &mut SPI,
         buffer: &[u8],
         _delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.wait_until_idle();
+        self.use_bw_mode(spi)?;
         self.interface
             .cmd_with_data(spi, Command::WriteRam, buffer)?;
         self.interface
@@ -391,24 +566,12 @@ where
         spi: &mut SPI,
         buffer: &[u8],
         delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.wait_until_idle();
+        self.use_bw_mode(spi)?;
         self.interface.reset(delay, 2);
 
-        self.set_lut_helper(spi, &LUT_PARTIAL_2IN9)?;
-        self.interface.cmd_with_data(
-            spi,
-            Command::WriteOtpSelection,
-            &[0x00, 0x00, 0x00, 0x00, 0x00, 0x40, 0x00, 0x00, 0x00, 0x00],
-        )?;
-        self.interface
-            .cmd_with_data(spi, Command::BorderWaveformControl, &[0x80])?;
-        self.interface
-            .cmd_with_data(spi, Command::DisplayUpdateControl2, &[0xC0])?;
-        self.interface.cmd(spi, Command::MasterActivation)?;
-
-        self.wait_until_idle();
-
+        self.use_partial_lut(spi)?;
         self.use_full_frame(spi)?;
 
         self.interface
@@ -417,29 +580,39 @@ where
     }
 
     /// For a quick refresh of the new updated frame. To be used immediately after `update_new_frame`
-    fn display_new_frame(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), SPI::Error> {
-        self.wait_until_idle();
-        self.interface
-            .cmd_with_data(spi, Command::DisplayUpdateControl2, &[0x0F])?;
-        self.interface.cmd(spi, Command::MasterActivation)?;
+    fn display_new_frame(
+        &mut self,
+        spi: &mut SPI,
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
         self.wait_until_idle();
-        Ok(())
+        self.interface.activate_ssd1680(spi, 0x0F)
     }
 
-    /// Updates and displays the new frame.
+    /// Updates and displays the new frame, with a full refresh if one is due, see
+    /// [`FullRefreshInterval`].
     fn update_and_display_new_frame(
         &mut self,
         spi: &mut SPI,
         buffer: &[u8],
         delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
+        if self.quick_refreshes.next_is_full() {
+            // both RAMs get the frame, so the next quick refresh starts from it
+            self.wait_until_idle();
+            self.use_bw_mode(spi)?;
+            for command in [Command::WriteRam, Command::WriteRam2] {
+                self.use_full_frame(spi)?;
+                self.interface.cmd_with_data(spi, command, buffer)?;
+            }
+            return self.display_frame(spi, delay);
+        }
         self.update_new_frame(spi, buffer, delay)?;
         self.display_new_frame(spi, delay)?;
         Ok(())
     }
 
-    /// Partial quick refresh not supported yet
-    #[allow(unused)]
+    /// To be followed immediately by `update_partial_new_frame`.
     fn update_partial_old_frame(
         &mut self,
         spi: &mut SPI,
@@ -448,13 +621,22 @@ where
         y: u32,
         width: u32,
         height: u32,
-    ) -> Result<(), SPI::Error> {
-        //TODO supported by display?
-        unimplemented!()
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(span_fits(x, width, WIDTH) && span_fits(y, height, HEIGHT));
+        self.wait_until_idle();
+        self.use_bw_mode(spi)?;
+        for command in [Command::WriteRam, Command::WriteRam2] {
+            self.set_ram_area(spi, x, y, x + width - 1, y + height - 1)?;
+            self.set_ram_counter(spi, x, y)?;
+            self.interface.cmd_with_data(spi, command, buffer)?;
+        }
+        Ok(())
     }
 
-    /// Partial quick refresh not supported yet
-    #[allow(unused)]
+    /// To be used immediately after `update_partial_old_frame`.
+    ///
+    /// Unlike `update_new_frame` this doesn't reset the controller, so the rest of
+    /// the RAM keeps its content.
     fn update_partial_new_frame(
         &mut self,
         spi: &mut SPI,
@@ -463,13 +645,17 @@ where
         y: u32,
         width: u32,
         height: u32,
-    ) -> Result<(), SPI::Error> {
-        //TODO supported by display?
-        unimplemented!()
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(span_fits(x, width, WIDTH) && span_fits(y, height, HEIGHT));
+        self.wait_until_idle();
+        self.use_bw_mode(spi)?;
+        self.use_partial_lut(spi)?;
+
+        self.set_ram_area(spi, x, y, x + width - 1, y + height - 1)?;
+        self.set_ram_counter(spi, x, y)?;
+        self.interface.cmd_with_data(spi, Command::WriteRam, buffer)
     }
 
-    /// Partial quick refresh not supported yet
-    #[allow(unused)]
     fn clear_partial_frame(
         &mut self,
         spi: &mut SPI,
@@ -477,9 +663,178 @@ where
         y: u32,
         width: u32,
         height: u32,
-    ) -> Result<(), SPI::Error> {
-        //TODO supported by display?
-        unimplemented!()
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(span_fits(x, width, WIDTH) && span_fits(y, height, HEIGHT));
+        self.wait_until_idle();
+        self.use_bw_mode(spi)?;
+        let color = self.background_color.get_byte_value();
+        let len = buffer_len(width as usize, height as usize) as u32;
+
+        for command in [Command::WriteRam, Command::WriteRam2] {
+            self.set_ram_area(spi, x, y, x + width - 1, y + height - 1)?;
+            self.set_ram_counter(spi, x, y)?;
+            self.interface.cmd(spi, command)?;
+            self.interface.data_x_times(spi, color, len)?;
+        }
+        Ok(())
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> ReadRevision<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd2in9<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8> + Transfer<u8, Error = <SPI as Write<u8>>::Error>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn read_revision(
+        &mut self,
+        spi: &mut SPI,
+    ) -> Result<Revision, Error<<SPI as Write<u8>>::Error>> {
+        self.interface.revision_ssd1680(spi)
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> ReadTemperature<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd2in9<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8> + Transfer<u8, Error = <SPI as Write<u8>>::Error>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn read_temperature(&mut self, spi: &mut SPI) -> Result<i8, Error<<SPI as Write<u8>>::Error>> {
+        self.interface.temperature_ssd1680(spi)
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> FullRefreshInterval<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd2in9<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn set_full_refresh_every(&mut self, updates: u16) {
+        self.quick_refreshes.set_full_every(updates);
+    }
+
+    fn quick_refreshes(&self) -> u16 {
+        self.quick_refreshes.since_full()
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> LutSelection<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd2in9<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn lut_source(&self) -> LutSource {
+        match self.register_lut {
+            Some(_) => LutSource::Register,
+            None if self.custom_lut => LutSource::Register,
+            None => LutSource::Otp,
+        }
+    }
+
+    fn use_otp_lut(&mut self, _spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        self.register_lut = None;
+        self.custom_lut = false;
+        Ok(())
+    }
+
+    /// The preset is written before every full refresh, so the partial and gray
+    /// refreshes can't replace it.
+    fn use_register_lut(
+        &mut self,
+        _spi: &mut SPI,
+        preset: &'static LutPreset,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(preset.panel == Panel::Epd2in9V2);
+        self.register_lut = Some(preset);
+        self.custom_lut = false;
+        Ok(())
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> CustomLut<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd2in9<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    type Lut = ssd1680::Lut;
+
+    /// The partial and gray refreshes load their own waveform, after which the full
+    /// refreshes use the one from OTP again.
+    fn load_lut(&mut self, spi: &mut SPI, lut: &ssd1680::Lut) -> Result<(), Error<SPI::Error>> {
+        self.interface.waveform_ssd1681(spi, &lut.to_bytes())?;
+        self.register_lut = None;
+        self.custom_lut = true;
+        Ok(())
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> HardwareMirror<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd2in9<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn set_mirror(&mut self, spi: &mut SPI, mirror: Mirror) -> Result<(), Error<SPI::Error>> {
+        // the sources can't be shifted the other way
+        ensure!(!mirror.horizontal());
+        self.mirror = mirror;
+        self.wait_until_idle();
+        self.interface.cmd_with_data(
+            spi,
+            Command::DriverOutputControl,
+            &[0x27, 0x01, mirror.vertical() as u8],
+        )
+    }
+
+    fn mirror(&self) -> Mirror {
+        self.mirror
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> SleepMode<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd2in9<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn set_sleep_mode(&mut self, mode: DeepSleepMode) {
+        self.sleep_mode = mode;
+    }
+
+    fn sleep_mode(&self) -> DeepSleepMode {
+        self.sleep_mode
     }
 }
 
@@ -4,7 +4,7 @@
 //!
 //!```rust, no_run
 //!# use embedded_hal_mock::*;
-//!# fn main() -> Result<(), MockError> {
+//!# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
 //!use embedded_graphics::{
 //!    pixelcolor::BinaryColor::On as Black, prelude::*, primitives::{Line, PrimitiveStyle},
 //!};
@@ -58,10 +58,16 @@ use embedded_hal::{
     digital::v2::*,
 };
 
+use crate::controllers::uc8151::{
+    vcm_dc, Command, Frame, BLACK_BORDER, CHROMATIC_BORDER, FLOATING_BORDER, IS_BUSY_LOW,
+    VCOM_RANGE_MV, WHITE_BORDER,
+};
 use crate::interface::DisplayInterface;
 use crate::traits::{
-    InternalWiAdditions, RefreshLut, WaveshareDisplay, WaveshareThreeColorDisplay,
+    BorderControl, InternalWiAdditions, RefreshLut, VcomSetting, WaveshareDisplay,
+    WaveshareThreeColorDisplay,
 };
+use crate::Error;
 
 /// Width of epd2in9bc in pixels
 pub const WIDTH: u32 = 128;
@@ -69,20 +75,15 @@ pub const WIDTH: u32 = 128;
 pub const HEIGHT: u32 = 296;
 /// Default background color (white) of epd2in9bc display
 pub const DEFAULT_BACKGROUND_COLOR: Color = Color::White;
+/// VCOM of the vendor driver in mV, without the sign
+const DEFAULT_VCOM_MV: u16 = 600;
 
+/// Number of bits for b/w buffer and same for chromatic buffer
 const NUM_DISPLAY_BITS: u32 = WIDTH * HEIGHT / 8;
 
-const IS_BUSY_LOW: bool = true;
 const VCOM_DATA_INTERVAL: u8 = 0x07;
-const WHITE_BORDER: u8 = 0x70;
-const BLACK_BORDER: u8 = 0x30;
-const CHROMATIC_BORDER: u8 = 0xb0;
-const FLOATING_BORDER: u8 = 0xF0;
-
-use crate::color::{Color, TriColor};
 
-pub(crate) mod command;
-use self::command::Command;
+use crate::color::{BorderColor, Color, TriColor};
 
 #[cfg(feature = "graphics")]
 mod graphics;
@@ -93,6 +94,10 @@ pub use self::graphics::Display2in9bc;
 pub struct Epd2in9bc<SPI, CS, BUSY, DC, RST, DELAY> {
     interface: DisplayInterface<SPI, CS, BUSY, DC, RST, DELAY>,
     color: Color,
+    /// VCOM in mV, without the sign
+    vcom_mv: u16,
+    /// Border bits of the VCOM and data interval setting
+    border: u8,
 }
 
 impl<SPI, CS, BUSY, DC, RST, DELAY> InternalWiAdditions<SPI, CS, BUSY, DC, RST, DELAY>
@@ -105,33 +110,25 @@ where
     RST: OutputPin,
     DELAY: DelayMs<u8>,
 {
-    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         // Values taken from datasheet and sample code
 
         self.interface.reset(delay, 10);
 
-        // start the booster
         self.interface
-            .cmd_with_data(spi, Command::BoosterSoftStart, &[0x17, 0x17, 0x17])?;
-
-        // power on
-        self.command(spi, Command::PowerOn)?;
-        delay.delay_ms(5);
-        self.wait_until_idle();
+            .power_on_uc8151(spi, delay, [0x17, 0x17, 0x17])?;
 
         // set the panel settings
-        self.cmd_with_data(spi, Command::PanelSetting, &[0x8F])?;
+        self.interface
+            .cmd_with_data(spi, Command::PanelSetting, &[0x8F])?;
 
-        self.cmd_with_data(
-            spi,
-            Command::VcomAndDataIntervalSetting,
-            &[WHITE_BORDER | VCOM_DATA_INTERVAL],
-        )?;
+        self.interface
+            .border_uc8151(spi, self.border, VCOM_DATA_INTERVAL)?;
 
-        // set resolution
-        self.send_resolution(spi)?;
+        self.interface.resolution_uc8151(spi, WIDTH, HEIGHT)?;
 
-        self.cmd_with_data(spi, Command::VcmDcSetting, &[0x0A])?;
+        self.interface
+            .cmd_with_data(spi, Command::VcmDcSetting, &[vcm_dc(self.vcom_mv)])?;
 
         self.wait_until_idle();
 
@@ -154,7 +151,7 @@ where
         spi: &mut SPI,
         black: &[u8],
         chromatic: &[u8],
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.update_achromatic_frame(spi, black)?;
         self.update_chromatic_frame(spi, chromatic)
     }
@@ -162,10 +159,12 @@ where
     /// Update only the black/white data of the display.
     ///
     /// Finish by calling `update_chromatic_frame`.
-    fn update_achromatic_frame(&mut self, spi: &mut SPI, black: &[u8]) -> Result<(), SPI::Error> {
-        self.interface.cmd(spi, Command::DataStartTransmission1)?;
-        self.interface.data(spi, black)?;
-        Ok(())
+    fn update_achromatic_frame(
+        &mut self,
+        spi: &mut SPI,
+        black: &[u8],
+    ) -> Result<(), Error<SPI::Error>> {
+        self.interface.frame_uc8151(spi, Frame::Black, black)
     }
 
     /// Update only chromatic data of the display.
@@ -175,9 +174,9 @@ where
         &mut self,
         spi: &mut SPI,
         chromatic: &[u8],
-    ) -> Result<(), SPI::Error> {
-        self.interface.cmd(spi, Command::DataStartTransmission2)?;
-        self.interface.data(spi, chromatic)?;
+    ) -> Result<(), Error<SPI::Error>> {
+        self.interface
+            .frame_uc8151(spi, Frame::Chromatic, chromatic)?;
 
         self.wait_until_idle();
         Ok(())
@@ -202,35 +201,32 @@ where
         dc: DC,
         rst: RST,
         delay: &mut DELAY,
-    ) -> Result<Self, SPI::Error> {
+    ) -> Result<Self, Error<SPI::Error>> {
         let interface = DisplayInterface::new(cs, busy, dc, rst);
         let color = DEFAULT_BACKGROUND_COLOR;
 
-        let mut epd = Epd2in9bc { interface, color };
+        let mut epd = Epd2in9bc {
+            interface,
+            color,
+            vcom_mv: DEFAULT_VCOM_MV,
+            border: WHITE_BORDER,
+        };
 
         epd.init(spi, delay)?;
 
         Ok(epd)
     }
 
-    fn sleep(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn sleep(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         // Section 8.2 from datasheet
-        self.interface.cmd_with_data(
-            spi,
-            Command::VcomAndDataIntervalSetting,
-            &[FLOATING_BORDER | VCOM_DATA_INTERVAL],
-        )?;
+        self.interface
+            .border_uc8151(spi, FLOATING_BORDER, VCOM_DATA_INTERVAL)?;
 
-        self.command(spi, Command::PowerOff)?;
         // The example STM code from Github has a wait after PowerOff
-        self.wait_until_idle();
-
-        self.cmd_with_data(spi, Command::DeepSleep, &[0xA5])?;
-
-        Ok(())
+        self.interface.sleep_uc8151(spi)
     }
 
-    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         self.init(spi, delay)
     }
 
@@ -255,16 +251,14 @@ where
         spi: &mut SPI,
         buffer: &[u8],
         _delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
-        self.interface.cmd(spi, Command::DataStartTransmission1)?;
-
-        self.interface.data(spi, &buffer)?;
+    ) -> Result<(), Error<SPI::Error>> {
+        self.interface.frame_uc8151(spi, Frame::Black, buffer)?;
 
         // Clear the chromatic layer
         let color = self.color.get_byte_value();
 
-        self.interface.cmd(spi, Command::DataStartTransmission2)?;
-        self.interface.data_x_times(spi, color, NUM_DISPLAY_BITS)?;
+        self.interface
+            .fill_uc8151(spi, Frame::Chromatic, color, NUM_DISPLAY_BITS)?;
 
         self.wait_until_idle();
         Ok(())
@@ -279,15 +273,24 @@ where
         y: u32,
         width: u32,
         height: u32,
-    ) -> Result<(), SPI::Error> {
-        Ok(())
+    ) -> Result<(), Error<SPI::Error>> {
+        unsupported!()
     }
 
-    fn display_frame(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), SPI::Error> {
-        self.command(spi, Command::DisplayRefresh)?;
+    fn display_frame(
+        &mut self,
+        spi: &mut SPI,
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.interface.refresh_uc8151(spi)
+    }
 
-        self.wait_until_idle();
-        Ok(())
+    fn start_display_frame(
+        &mut self,
+        spi: &mut SPI,
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.interface.start_refresh_uc8151(spi)
     }
 
     fn update_and_display_frame(
@@ -295,25 +298,24 @@ where
         spi: &mut SPI,
         buffer: &[u8],
         delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.update_frame(spi, buffer, delay)?;
         self.display_frame(spi, delay)?;
         Ok(())
     }
 
-    fn clear_frame(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), SPI::Error> {
-        self.send_resolution(spi)?;
+    fn clear_frame(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.interface.resolution_uc8151(spi, WIDTH, HEIGHT)?;
 
         let color = DEFAULT_BACKGROUND_COLOR.get_byte_value();
 
         // Clear the black
-        self.interface.cmd(spi, Command::DataStartTransmission1)?;
-
-        self.interface.data_x_times(spi, color, NUM_DISPLAY_BITS)?;
+        self.interface
+            .fill_uc8151(spi, Frame::Black, color, NUM_DISPLAY_BITS)?;
 
         // Clear the chromatic
-        self.interface.cmd(spi, Command::DataStartTransmission2)?;
-        self.interface.data_x_times(spi, color, NUM_DISPLAY_BITS)?;
+        self.interface
+            .fill_uc8151(spi, Frame::Chromatic, color, NUM_DISPLAY_BITS)?;
 
         self.wait_until_idle();
         Ok(())
@@ -322,8 +324,11 @@ where
     fn set_lut(
         &mut self,
         _spi: &mut SPI,
-        _refresh_rate: Option<RefreshLut>,
-    ) -> Result<(), SPI::Error> {
+        refresh_rate: Option<RefreshLut>,
+    ) -> Result<(), Error<SPI::Error>> {
+        if let Some(RefreshLut::Custom(_)) = refresh_rate {
+            return Err(Error::Unsupported);
+        }
         Ok(())
     }
 
@@ -341,49 +346,88 @@ where
     RST: OutputPin,
     DELAY: DelayMs<u8>,
 {
-    fn command(&mut self, spi: &mut SPI, command: Command) -> Result<(), SPI::Error> {
-        self.interface.cmd(spi, command)
+    fn wait_until_idle(&mut self) {
+        self.interface.wait_until_idle(IS_BUSY_LOW);
     }
 
-    fn send_data(&mut self, spi: &mut SPI, data: &[u8]) -> Result<(), SPI::Error> {
-        self.interface.data(spi, data)
+    /// Updates only the chromatic pixels in the `width` x `height` window at `x`, `y`
+    ///
+    /// The black/white frame and the chromatic pixels outside of the window stay in the
+    /// memory of the controller, so e.g. a yellow badge can change without sending the
+    /// black content again. The next `display_frame` shows it. `x` and `width` have to
+    /// be multiples of 8.
+    pub fn update_chromatic_partial_frame(
+        &mut self,
+        spi: &mut SPI,
+        chromatic: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        self.interface
+            .partial_frame_uc8151(spi, Frame::Chromatic, chromatic, x, y, width, height)
     }
 
-    fn cmd_with_data(
+    /// Set the outer border of the display to the chosen color.
+    ///
+    /// Like [`set_border`](BorderControl::set_border), but the border can be chromatic too.
+    pub fn set_border_color(
         &mut self,
         spi: &mut SPI,
-        command: Command,
-        data: &[u8],
-    ) -> Result<(), SPI::Error> {
-        self.interface.cmd_with_data(spi, command, data)
+        color: TriColor,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.border = match color {
+            TriColor::Black => BLACK_BORDER,
+            TriColor::White => WHITE_BORDER,
+            TriColor::Chromatic => CHROMATIC_BORDER,
+        };
+        self.interface
+            .border_uc8151(spi, self.border, VCOM_DATA_INTERVAL)
     }
+}
 
-    fn wait_until_idle(&mut self) {
-        let _ = self.interface.wait_until_idle(IS_BUSY_LOW);
+impl<SPI, CS, BUSY, DC, RST, DELAY> BorderControl<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd2in9bc<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn set_border(&mut self, spi: &mut SPI, border: BorderColor) -> Result<(), Error<SPI::Error>> {
+        self.border = match border {
+            BorderColor::Black => BLACK_BORDER,
+            BorderColor::White => WHITE_BORDER,
+            BorderColor::Floating => FLOATING_BORDER,
+        };
+        self.interface
+            .border_uc8151(spi, self.border, VCOM_DATA_INTERVAL)
     }
+}
 
-    fn send_resolution(&mut self, spi: &mut SPI) -> Result<(), SPI::Error> {
-        let w = self.width();
-        let h = self.height();
-
-        self.command(spi, Command::ResolutionSetting)?;
-
-        self.send_data(spi, &[w as u8])?;
-        self.send_data(spi, &[(h >> 8) as u8])?;
-        self.send_data(spi, &[h as u8])
+impl<SPI, CS, BUSY, DC, RST, DELAY> VcomSetting<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd2in9bc<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn set_vcom(&mut self, spi: &mut SPI, millivolts: u16) -> Result<(), Error<SPI::Error>> {
+        ensure!(VCOM_RANGE_MV.contains(&millivolts));
+        self.vcom_mv = millivolts;
+        self.wait_until_idle();
+        self.interface
+            .cmd_with_data(spi, Command::VcmDcSetting, &[vcm_dc(millivolts)])
     }
 
-    /// Set the outer border of the display to the chosen color.
-    pub fn set_border_color(&mut self, spi: &mut SPI, color: TriColor) -> Result<(), SPI::Error> {
-        let border = match color {
-            TriColor::Black => BLACK_BORDER,
-            TriColor::White => WHITE_BORDER,
-            TriColor::Chromatic => CHROMATIC_BORDER,
-        };
-        self.cmd_with_data(
-            spi,
-            Command::VcomAndDataIntervalSetting,
-            &[border | VCOM_DATA_INTERVAL],
-        )
+    fn vcom(&self) -> u16 {
+        self.vcom_mv
     }
 }
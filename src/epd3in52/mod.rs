@@ -0,0 +1,353 @@
+//! A simple Driver for the Waveshare 3.52" E-Ink Display via SPI
+//!
+//! The 240x360 panel has a UC8253 controller, which understands the commands of the
+//! UC8151. The waveforms are always loaded from the registers: [`RefreshLut::Full`]
+//! uses the GC waveform of the vendor driver, which clears the whole panel, and
+//! [`RefreshLut::Quick`] the DU waveform, which only drives the pixels changing color.
+//! Like the vendor driver, the black to white and the white to black LUTs are swapped
+//! on every refresh, so the panel isn't driven with a DC bias.
+//!
+//! # References
+//!
+//! - [Waveshare product page](https://www.waveshare.com/wiki/3.52inch_e-Paper_HAT)
+//! - [Waveshare C driver](https://github.com/waveshare/e-Paper/blob/master/RaspberryPi_JetsonNano/c/lib/e-Paper/EPD_3in52.c)
+//! - [Waveshare Python driver](https://github.com/waveshare/e-Paper/blob/master/RaspberryPi_JetsonNano/python/lib/waveshare_epd/epd3in52.py)
+//!
+//! # Example for the 3.52" E-Ink Display
+//!
+//!```rust, no_run
+//!# use embedded_hal_mock::*;
+//!# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
+//!use embedded_graphics::{
+//!    pixelcolor::BinaryColor::On as Black, prelude::*, primitives::{Line, PrimitiveStyle},
+//!};
+//!use epd_waveshare::{epd3in52::*, prelude::*};
+//!#
+//!# let expectations = [];
+//!# let mut spi = spi::Mock::new(&expectations);
+//!# let expectations = [];
+//!# let cs_pin = pin::Mock::new(&expectations);
+//!# let busy_in = pin::Mock::new(&expectations);
+//!# let dc = pin::Mock::new(&expectations);
+//!# let rst = pin::Mock::new(&expectations);
+//!# let mut delay = delay::MockNoop::new();
+//!
+//!// Setup EPD
+//!let mut epd = Epd3in52::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
+//!
+//!let mut display = Display3in52::default();
+//!let _ = Line::new(Point::new(0, 120), Point::new(0, 295))
+//!    .into_styled(PrimitiveStyle::with_stroke(Black, 1))
+//!    .draw(&mut display);
+//!epd.update_and_display_frame(&mut spi, display.buffer(), &mut delay)?;
+//!
+//!// Small changes can be shown with the quick waveform
+//!epd.set_lut(&mut spi, Some(RefreshLut::Quick))?;
+//!let _ = Line::new(Point::new(15, 120), Point::new(15, 295))
+//!    .into_styled(PrimitiveStyle::with_stroke(Black, 1))
+//!    .draw(&mut display);
+//!epd.update_and_display_frame(&mut spi, display.buffer(), &mut delay)?;
+//!
+//!// Set the EPD to sleep
+//!epd.sleep(&mut spi, &mut delay)?;
+//!# Ok(())
+//!# }
+//!```
+
+use embedded_hal::{
+    blocking::{delay::*, spi::Write},
+    digital::v2::*,
+};
+
+use crate::color::Color;
+use crate::controllers::uc8151::{vcm_dc, Command, Frame, IS_BUSY_LOW, VCOM_RANGE_MV};
+use crate::interface::DisplayInterface;
+use crate::traits::{InternalWiAdditions, RefreshLut, VcomSetting, WaveshareDisplay};
+use crate::Error;
+
+mod constants;
+use self::constants::*;
+
+#[cfg(feature = "graphics")]
+mod graphics;
+#[cfg(feature = "graphics")]
+pub use self::graphics::Display3in52;
+
+/// Width of epd3in52 in pixels
+pub const WIDTH: u32 = 240;
+/// Height of epd3in52 in pixels
+pub const HEIGHT: u32 = 360;
+/// Default Background Color (white)
+pub const DEFAULT_BACKGROUND_COLOR: Color = Color::White;
+/// VCOM of the vendor driver in mV, without the sign
+const DEFAULT_VCOM_MV: u16 = 450;
+const NUM_DISPLAY_BITS: u32 = WIDTH * HEIGHT / 8;
+
+/// Epd3in52 driver
+pub struct Epd3in52<SPI, CS, BUSY, DC, RST, DELAY> {
+    interface: DisplayInterface<SPI, CS, BUSY, DC, RST, DELAY>,
+    color: Color,
+    refresh: RefreshLut,
+    /// Whether the black to white and white to black LUTs are swapped for the next refresh
+    swapped: bool,
+    /// VCOM in mV, without the sign
+    vcom_mv: u16,
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> InternalWiAdditions<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd3in52<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.interface.reset(delay, 2);
+
+        // 240x360, LUT from register, black/white, scan up and right
+        self.interface
+            .cmd_with_data(spi, Command::PanelSetting, &[0xFF, 0x01])?;
+        // VGH=20V, VGL=-20V, VDH=15V, VDL=-15V, VDHR=6.4V
+        self.interface.cmd_with_data(
+            spi,
+            Command::PowerSetting,
+            &[0x03, 0x10, 0x3F, 0x3F, 0x03],
+        )?;
+        self.interface
+            .cmd_with_data(spi, Command::BoosterSoftStart, &[0x37, 0x3D, 0x3D])?;
+        self.interface
+            .cmd_with_data(spi, Command::TconSetting, &[0x22])?;
+        self.interface
+            .cmd_with_data(spi, Command::VcmDcSetting, &[vcm_dc(self.vcom_mv)])?;
+        self.interface
+            .cmd_with_data(spi, Command::PllControl, &[0x09])?;
+        self.interface
+            .cmd_with_data(spi, Command::PowerSaving, &[0x88])?;
+        self.interface.resolution_uc8151(spi, WIDTH, HEIGHT)?;
+        self.interface
+            .cmd_with_data(spi, Command::VcomAndDataIntervalSetting, &[0xB7])?;
+
+        Ok(())
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd3in52<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    type DisplayColor = Color;
+    fn new(
+        spi: &mut SPI,
+        cs: CS,
+        busy: BUSY,
+        dc: DC,
+        rst: RST,
+        delay: &mut DELAY,
+    ) -> Result<Self, Error<SPI::Error>> {
+        let interface = DisplayInterface::new(cs, busy, dc, rst);
+        let color = DEFAULT_BACKGROUND_COLOR;
+
+        let mut epd = Epd3in52 {
+            interface,
+            color,
+            refresh: RefreshLut::Full,
+            swapped: false,
+            vcom_mv: DEFAULT_VCOM_MV,
+        };
+
+        epd.init(spi, delay)?;
+
+        Ok(epd)
+    }
+
+    fn sleep(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        // the auto sequence already powered the panel off after the refresh
+        self.wait_until_idle();
+        self.interface
+            .cmd_with_data(spi, Command::DeepSleep, &[0xA5])
+    }
+
+    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.init(spi, delay)
+    }
+
+    fn set_background_color(&mut self, color: Color) {
+        self.color = color;
+    }
+
+    fn background_color(&self) -> &Color {
+        &self.color
+    }
+
+    fn width(&self) -> u32 {
+        WIDTH
+    }
+
+    fn height(&self) -> u32 {
+        HEIGHT
+    }
+
+    fn update_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(buffer.len() == NUM_DISPLAY_BITS as usize);
+        self.wait_until_idle();
+        self.interface.frame_uc8151(spi, Frame::Chromatic, buffer)
+    }
+
+    #[allow(unused)]
+    fn update_partial_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        unsupported!()
+    }
+
+    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.start_display_frame(spi, delay)?;
+        self.wait_until_idle();
+        Ok(())
+    }
+
+    fn start_display_frame(
+        &mut self,
+        spi: &mut SPI,
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        self.write_lut(spi)?;
+        self.interface
+            .cmd_with_data(spi, Command::AutoSequence, &[0xA5])?;
+        Ok(())
+    }
+
+    fn update_and_display_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.update_frame(spi, buffer, delay)?;
+        self.display_frame(spi, delay)
+    }
+
+    fn clear_frame(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        self.interface.fill_uc8151(
+            spi,
+            Frame::Chromatic,
+            self.color.get_byte_value(),
+            NUM_DISPLAY_BITS,
+        )
+    }
+
+    fn set_lut(
+        &mut self,
+        _spi: &mut SPI,
+        refresh_rate: Option<RefreshLut>,
+    ) -> Result<(), Error<SPI::Error>> {
+        if let Some(RefreshLut::Custom(_)) = refresh_rate {
+            return Err(Error::Unsupported);
+        }
+        // the LUTs are written before every refresh, since they are swapped each time
+        if let Some(refresh_lut) = refresh_rate {
+            self.refresh = refresh_lut;
+        }
+        Ok(())
+    }
+
+    fn is_busy(&self) -> bool {
+        self.interface.is_busy(IS_BUSY_LOW)
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> Epd3in52<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn wait_until_idle(&mut self) {
+        self.interface.wait_until_idle(IS_BUSY_LOW);
+    }
+
+    /// Writes the LUTs of the selected waveform, swapping the black to white and white
+    /// to black ones every time
+    fn write_lut(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        let (vcom, ww, bw, wb, bb) = match self.refresh {
+            RefreshLut::Full | RefreshLut::Custom(_) => {
+                (&LUT_VCOM_GC, &LUT_WW_GC, &LUT_BW_GC, &LUT_WB_GC, &LUT_BB_GC)
+            }
+            RefreshLut::Quick => (&LUT_VCOM_DU, &LUT_WW_DU, &LUT_BW_DU, &LUT_WB_DU, &LUT_BB_DU),
+        };
+        let (bw, wb) = if self.swapped { (wb, bw) } else { (bw, wb) };
+        self.swapped = !self.swapped;
+
+        self.interface
+            .cmd_with_data(spi, Command::LutForVcom, vcom)?;
+        self.interface
+            .cmd_with_data(spi, Command::LutWhiteToWhite, ww)?;
+        self.interface
+            .cmd_with_data(spi, Command::LutBlackToBlack, bb)?;
+        self.interface
+            .cmd_with_data(spi, Command::LutBlackToWhite, bw)?;
+        self.interface
+            .cmd_with_data(spi, Command::LutWhiteToBlack, wb)
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> VcomSetting<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd3in52<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn set_vcom(&mut self, spi: &mut SPI, millivolts: u16) -> Result<(), Error<SPI::Error>> {
+        ensure!(VCOM_RANGE_MV.contains(&millivolts));
+        self.vcom_mv = millivolts;
+        self.wait_until_idle();
+        self.interface
+            .cmd_with_data(spi, Command::VcmDcSetting, &[vcm_dc(millivolts)])
+    }
+
+    fn vcom(&self) -> u16 {
+        self.vcom_mv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn epd_size() {
+        assert_eq!(WIDTH, 240);
+        assert_eq!(HEIGHT, 360);
+        assert_eq!(NUM_DISPLAY_BITS, 10800);
+        assert_eq!(DEFAULT_BACKGROUND_COLOR, Color::White);
+    }
+}
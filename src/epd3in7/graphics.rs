@@ -0,0 +1,122 @@
+use crate::epd3in7::{HEIGHT, NUM_GRAY_BYTES, WIDTH};
+use crate::graphics::{DisplayRotation, GrayDisplay};
+use embedded_graphics::pixelcolor::Gray2;
+use embedded_graphics_core::prelude::*;
+
+/// Full size buffer with 4 gray levels for use with the 3in7 EPD
+///
+/// Every byte holds 4 pixels, white (`0xFF`) is the default.
+/// For the black/white mode a `VarDisplay` can be used instead.
+pub struct Display3in7 {
+    buffer: [u8; NUM_GRAY_BYTES],
+    rotation: DisplayRotation,
+}
+
+impl Default for Display3in7 {
+    fn default() -> Self {
+        Display3in7 {
+            buffer: [0xFF; NUM_GRAY_BYTES],
+            rotation: DisplayRotation::default(),
+        }
+    }
+}
+
+impl DrawTarget for Display3in7 {
+    type Color = Gray2;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for pixel in pixels {
+            self.draw_helper(WIDTH, HEIGHT, pixel)?;
+        }
+        Ok(())
+    }
+}
+
+impl OriginDimensions for Display3in7 {
+    fn size(&self) -> Size {
+        Size::new(WIDTH, HEIGHT)
+    }
+}
+
+impl GrayDisplay for Display3in7 {
+    fn buffer(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    fn get_mut_buffer(&mut self) -> &mut [u8] {
+        &mut self.buffer
+    }
+
+    fn set_rotation(&mut self, rotation: DisplayRotation) {
+        self.rotation = rotation;
+    }
+
+    fn rotation(&self) -> DisplayRotation {
+        self.rotation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_graphics::{
+        prelude::*,
+        primitives::{Line, PrimitiveStyle},
+    };
+
+    // test buffer length
+    #[test]
+    fn graphics_size() {
+        let display = Display3in7::default();
+        assert_eq!(display.buffer().len(), 33600);
+    }
+
+    // test default background color on all bytes
+    #[test]
+    fn graphics_default() {
+        let display = Display3in7::default();
+        for &byte in display.buffer() {
+            assert_eq!(byte, 0xFF);
+        }
+    }
+
+    #[test]
+    fn graphics_gray_levels() {
+        let mut display = Display3in7::default();
+        for (x, luma) in (0..4).enumerate() {
+            let _ = Pixel(Point::new(x as i32, 0), Gray2::new(luma)).draw(&mut display);
+        }
+        assert_eq!(display.buffer()[0], 0b00_01_10_11);
+        assert_eq!(display.buffer()[1], 0xFF);
+    }
+
+    #[test]
+    fn graphics_rotation_90() {
+        let mut display = Display3in7::default();
+        display.set_rotation(DisplayRotation::Rotate90);
+        let _ = Line::new(
+            Point::new(0, WIDTH as i32 - 4),
+            Point::new(0, WIDTH as i32 - 1),
+        )
+        .into_styled(PrimitiveStyle::with_stroke(Gray2::BLACK, 1))
+        .draw(&mut display);
+
+        assert_eq!(display.buffer()[0], 0x00);
+        for &byte in display.buffer().iter().skip(1) {
+            assert_eq!(byte, 0xFF);
+        }
+    }
+
+    #[test]
+    fn graphics_clear() {
+        let mut display = Display3in7::default();
+        display.clear_buffer(Gray2::new(1));
+        for &byte in display.buffer() {
+            assert_eq!(byte, 0b01_01_01_01);
+        }
+    }
+}
@@ -0,0 +1,539 @@
+//! A simple Driver for the Waveshare 3.7" E-Ink Display via SPI
+//!
+//! The display can be used in two modes:
+//!
+//! - black/white with the usual 1 bit per pixel buffers of [`WaveshareDisplay`]
+//! - 4 gray levels with 2 bits per pixel, see [`Epd3in7::update_gray_frame`] and
+//!   the `Gray2` buffer [`Display3in7`]
+//!
+//! The driver switches between both modes depending on the last uploaded frame.
+//! Black/white frames can also refresh with the A2 waveform in well under a second,
+//! see [`Epd3in7::set_fast_mono`].
+//!
+//! # References
+//!
+//! - [Waveshare product page](https://www.waveshare.com/wiki/3.7inch_e-Paper_HAT)
+//! - [Waveshare C driver](https://github.com/waveshare/e-Paper/blob/master/RaspberryPi_JetsonNano/c/lib/e-Paper/EPD_3in7.c)
+//!
+//! # Example for the 3.7" E-Ink Display
+//!
+//!```rust, no_run
+//!# use embedded_hal_mock::*;
+//!# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
+//!use embedded_graphics::{
+//!    pixelcolor::Gray2, prelude::*, primitives::{Line, PrimitiveStyle},
+//!};
+//!use epd_waveshare::{epd3in7::*, prelude::*};
+//!#
+//!# let expectations = [];
+//!# let mut spi = spi::Mock::new(&expectations);
+//!# let expectations = [];
+//!# let cs_pin = pin::Mock::new(&expectations);
+//!# let busy_in = pin::Mock::new(&expectations);
+//!# let dc = pin::Mock::new(&expectations);
+//!# let rst = pin::Mock::new(&expectations);
+//!# let mut delay = delay::MockNoop::new();
+//!
+//!// Setup EPD
+//!let mut epd = Epd3in7::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
+//!
+//!// Use display graphics from embedded-graphics
+//!let mut display = Display3in7::default();
+//!
+//!// Use embedded graphics for drawing a black and a light gray line
+//!let _ = Line::new(Point::new(0, 120), Point::new(0, 479))
+//!    .into_styled(PrimitiveStyle::with_stroke(Gray2::BLACK, 1))
+//!    .draw(&mut display);
+//!let _ = Line::new(Point::new(10, 120), Point::new(10, 479))
+//!    .into_styled(PrimitiveStyle::with_stroke(Gray2::new(2), 1))
+//!    .draw(&mut display);
+//!
+//!// Display updated frame with 4 gray levels
+//!epd.update_gray_frame(&mut spi, display.buffer())?;
+//!epd.display_frame(&mut spi, &mut delay)?;
+//!
+//!// Set the EPD to sleep
+//!epd.sleep(&mut spi, &mut delay)?;
+//!# Ok(())
+//!# }
+//!```
+
+use embedded_hal::{
+    blocking::{delay::*, spi::Write},
+    digital::v2::*,
+};
+
+use crate::color::Color;
+pub use crate::color::GrayMode;
+use crate::interface::DisplayInterface;
+use crate::traits::{DeepSleepMode, InternalWiAdditions, RefreshLut, SleepMode, WaveshareDisplay};
+use crate::{buffer_len, span_fits, Error};
+
+pub(crate) mod command;
+use self::command::Command;
+
+mod constants;
+use self::constants::*;
+
+#[cfg(feature = "graphics")]
+mod graphics;
+#[cfg(feature = "graphics")]
+pub use self::graphics::Display3in7;
+
+/// Width of epd3in7 in pixels
+pub const WIDTH: u32 = 280;
+/// Height of epd3in7 in pixels
+pub const HEIGHT: u32 = 480;
+/// Default Background Color (white)
+pub const DEFAULT_BACKGROUND_COLOR: Color = Color::White;
+const IS_BUSY_LOW: bool = false;
+
+/// Number of bytes of a frame with 2 bits per pixel
+const NUM_GRAY_BYTES: usize = WIDTH as usize * HEIGHT as usize / 4;
+
+/// Epd3in7 driver
+pub struct Epd3in7<SPI, CS, BUSY, DC, RST, DELAY> {
+    /// Connection Interface
+    interface: DisplayInterface<SPI, CS, BUSY, DC, RST, DELAY>,
+    /// Background Color
+    background_color: Color,
+    /// Refresh LUT
+    refresh: RefreshLut,
+    /// Mode of the last uploaded frame
+    mode: GrayMode,
+    /// Deep sleep mode of `sleep`
+    sleep_mode: DeepSleepMode,
+    /// Whether black/white frames refresh with the A2 waveform
+    fast_mono: bool,
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> InternalWiAdditions<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd3in7<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.interface.reset(delay, 3);
+
+        self.wait_until_idle();
+        self.interface.cmd(spi, Command::SwReset)?;
+        delay.delay_ms(200);
+        self.wait_until_idle();
+
+        // fill both RAMs with white
+        self.interface
+            .cmd_with_data(spi, Command::AutoWriteRedRam, &[0xF7])?;
+        self.wait_until_idle();
+        self.interface
+            .cmd_with_data(spi, Command::AutoWriteBwRam, &[0xF7])?;
+        self.wait_until_idle();
+
+        // 480 gate lines
+        self.interface.cmd_with_data(
+            spi,
+            Command::DriverOutputControl,
+            &[(HEIGHT - 1) as u8, ((HEIGHT - 1) >> 8) as u8, 0x00],
+        )?;
+        self.interface
+            .cmd_with_data(spi, Command::GateDrivingVoltageControl, &[0x00])?;
+        self.interface.cmd_with_data(
+            spi,
+            Command::SourceDrivingVoltageControl,
+            &[0x41, 0xA8, 0x32],
+        )?;
+
+        // x increment, y increment, address counter is updated in x direction
+        self.interface
+            .cmd_with_data(spi, Command::DataEntryModeSetting, &[0x03])?;
+
+        self.interface.cmd_with_data(
+            spi,
+            Command::BoosterSoftStartControl,
+            &[0xAE, 0xC7, 0xC3, 0xC0, 0xC0],
+        )?;
+
+        // use the internal temperature sensor
+        self.interface
+            .cmd_with_data(spi, Command::TemperatureSensorSelection, &[0x80])?;
+        self.interface
+            .cmd_with_data(spi, Command::WriteVcomRegister, &[0x44])?;
+
+        self.send_mode_settings(spi)?;
+
+        self.use_full_frame(spi)?;
+
+        // Enable clock and analog, display with the LUT from the register, disable analog
+        self.interface
+            .cmd_with_data(spi, Command::DisplayUpdateControl2, &[0xCF])?;
+
+        self.wait_until_idle();
+        Ok(())
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd3in7<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    type DisplayColor = Color;
+    fn new(
+        spi: &mut SPI,
+        cs: CS,
+        busy: BUSY,
+        dc: DC,
+        rst: RST,
+        delay: &mut DELAY,
+    ) -> Result<Self, Error<SPI::Error>> {
+        let interface = DisplayInterface::new(cs, busy, dc, rst);
+
+        let mut epd = Epd3in7 {
+            interface,
+            background_color: DEFAULT_BACKGROUND_COLOR,
+            refresh: RefreshLut::Full,
+            mode: GrayMode::Gray1,
+            sleep_mode: DeepSleepMode::DiscardRam,
+            fast_mono: false,
+        };
+
+        epd.init(spi, delay)?;
+
+        Ok(epd)
+    }
+
+    fn sleep(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        self.interface
+            .cmd_with_data(spi, Command::DeepSleepMode, &[self.sleep_mode as u8])
+    }
+
+    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.init(spi, delay)
+    }
+
+    fn set_background_color(&mut self, background_color: Color) {
+        self.background_color = background_color;
+    }
+
+    fn background_color(&self) -> &Color {
+        &self.background_color
+    }
+
+    fn width(&self) -> u32 {
+        WIDTH
+    }
+
+    fn height(&self) -> u32 {
+        HEIGHT
+    }
+
+    /// Uploads a black/white frame and switches the display into `GrayMode::Gray1`
+    fn update_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(buffer.len() == buffer_len(WIDTH as usize, HEIGHT as usize));
+        self.wait_until_idle();
+        self.set_mode(spi, GrayMode::Gray1)?;
+
+        self.use_full_frame(spi)?;
+        self.interface.cmd_with_data(spi, Command::WriteRam, buffer)
+    }
+
+    fn update_partial_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(span_fits(x, width, WIDTH) && span_fits(y, height, HEIGHT));
+        self.wait_until_idle();
+        self.set_mode(spi, GrayMode::Gray1)?;
+
+        self.set_ram_area(spi, x, y, x + width - 1, y + height - 1)?;
+        self.set_ram_counter(spi, x, y)?;
+        self.interface.cmd_with_data(spi, Command::WriteRam, buffer)
+    }
+
+    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.start_display_frame(spi, delay)?;
+        self.wait_until_idle();
+        Ok(())
+    }
+
+    fn start_display_frame(
+        &mut self,
+        spi: &mut SPI,
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        self.load_lut(spi)?;
+        self.interface.cmd(spi, Command::MasterActivation)?;
+        Ok(())
+    }
+
+    fn update_and_display_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.update_frame(spi, buffer, delay)?;
+        self.display_frame(spi, delay)?;
+        Ok(())
+    }
+
+    /// Clears both RAMs, which works for both modes as white is `0b11` and black `0b00`
+    fn clear_frame(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        let color = self.background_color.get_byte_value();
+
+        for command in [Command::WriteRam, Command::WriteRam2] {
+            self.use_full_frame(spi)?;
+            self.interface.cmd(spi, command)?;
+            self.interface.data_x_times(
+                spi,
+                color,
+                buffer_len(WIDTH as usize, HEIGHT as usize) as u32,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// `RefreshLut::Quick` uses the direct update waveform of the black/white mode,
+    /// the gray mode always does a full refresh.
+    fn set_lut(
+        &mut self,
+        spi: &mut SPI,
+        refresh_rate: Option<RefreshLut>,
+    ) -> Result<(), Error<SPI::Error>> {
+        if let Some(RefreshLut::Custom(_)) = refresh_rate {
+            return Err(Error::Unsupported);
+        }
+        if let Some(refresh_lut) = refresh_rate {
+            self.refresh = refresh_lut;
+        }
+        self.wait_until_idle();
+        self.load_lut(spi)
+    }
+
+    fn is_busy(&self) -> bool {
+        self.interface.is_busy(IS_BUSY_LOW)
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> Epd3in7<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    /// Uploads a frame with 4 gray levels and switches the display into `GrayMode::Gray4`
+    ///
+    /// The buffer holds 2 bits per pixel, see [`Display3in7`].
+    pub fn update_gray_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(buffer.len() == NUM_GRAY_BYTES);
+        self.wait_until_idle();
+        self.set_mode(spi, GrayMode::Gray4)?;
+
+        // the high bit of every pixel goes into the first RAM, the low bit into the second
+        self.use_full_frame(spi)?;
+        self.interface.cmd(spi, Command::WriteRam)?;
+        self.interface.data_gray_plane(spi, buffer, 0b10, false)?;
+        self.use_full_frame(spi)?;
+        self.interface.cmd(spi, Command::WriteRam2)?;
+        self.interface.data_gray_plane(spi, buffer, 0b01, false)
+    }
+
+    /// Mode of the last uploaded frame
+    pub fn gray_mode(&self) -> GrayMode {
+        self.mode
+    }
+
+    /// Refreshes black/white frames with the A2 waveform, or with the one chosen by
+    /// [`set_lut`](WaveshareDisplay::set_lut) again
+    ///
+    /// A2 drives every pixel straight to black or white in a few frames, fast enough for
+    /// menus and other UI updates. It leaves more ghosting behind than
+    /// `RefreshLut::Quick`, so switch it off for a full refresh now and then. Gray
+    /// frames always refresh with the gray waveform.
+    pub fn set_fast_mono(&mut self, spi: &mut SPI, enabled: bool) -> Result<(), Error<SPI::Error>> {
+        self.fast_mono = enabled;
+        self.wait_until_idle();
+        self.load_lut(spi)
+    }
+
+    /// Whether black/white frames refresh with the A2 waveform
+    pub fn is_fast_mono(&self) -> bool {
+        self.fast_mono
+    }
+
+    fn wait_until_idle(&mut self) {
+        self.interface.wait_until_idle(IS_BUSY_LOW);
+    }
+
+    fn set_mode(&mut self, spi: &mut SPI, mode: GrayMode) -> Result<(), Error<SPI::Error>> {
+        if self.mode != mode {
+            self.mode = mode;
+            self.send_mode_settings(spi)?;
+        }
+        Ok(())
+    }
+
+    fn send_mode_settings(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        match self.mode {
+            GrayMode::Gray1 => {
+                self.interface
+                    .cmd_with_data(spi, Command::BorderWaveformControl, &[0x03])?;
+                self.interface.cmd_with_data(
+                    spi,
+                    Command::WriteDisplayOption,
+                    &[0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0x4F, 0xFF, 0xFF, 0xFF, 0xFF],
+                )
+            }
+            GrayMode::Gray4 => {
+                self.interface
+                    .cmd_with_data(spi, Command::BorderWaveformControl, &[0x00])?;
+                self.interface
+                    .cmd_with_data(spi, Command::WriteDisplayOption, &[0x00; 10])
+            }
+        }
+    }
+
+    fn load_lut(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        let lut = waveform(self.mode, self.refresh, self.fast_mono);
+        self.interface
+            .cmd_with_data(spi, Command::WriteLutRegister, lut)
+    }
+
+    fn use_full_frame(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        // choose full frame/ram
+        self.set_ram_area(spi, 0, 0, WIDTH - 1, HEIGHT - 1)?;
+
+        // start from the beginning
+        self.set_ram_counter(spi, 0, 0)
+    }
+
+    fn set_ram_area(
+        &mut self,
+        spi: &mut SPI,
+        start_x: u32,
+        start_y: u32,
+        end_x: u32,
+        end_y: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(start_x <= end_x);
+        ensure!(start_y <= end_y);
+
+        // the SSD1677 addresses x in pixels, 2 Databytes each for start and end
+        self.interface.cmd_with_data(
+            spi,
+            Command::SetRamXAddressStartEndPosition,
+            &[
+                start_x as u8,
+                (start_x >> 8) as u8,
+                end_x as u8,
+                (end_x >> 8) as u8,
+            ],
+        )?;
+
+        self.interface.cmd_with_data(
+            spi,
+            Command::SetRamYAddressStartEndPosition,
+            &[
+                start_y as u8,
+                (start_y >> 8) as u8,
+                end_y as u8,
+                (end_y >> 8) as u8,
+            ],
+        )
+    }
+
+    fn set_ram_counter(&mut self, spi: &mut SPI, x: u32, y: u32) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        self.interface.cmd_with_data(
+            spi,
+            Command::SetRamXAddressCounter,
+            &[x as u8, (x >> 8) as u8],
+        )?;
+
+        self.interface.cmd_with_data(
+            spi,
+            Command::SetRamYAddressCounter,
+            &[y as u8, (y >> 8) as u8],
+        )
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> SleepMode<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd3in7<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn set_sleep_mode(&mut self, mode: DeepSleepMode) {
+        self.sleep_mode = mode;
+    }
+
+    fn sleep_mode(&self) -> DeepSleepMode {
+        self.sleep_mode
+    }
+}
+
+/// Waveform of the next refresh
+fn waveform(mode: GrayMode, refresh: RefreshLut, fast_mono: bool) -> &'static [u8; 105] {
+    match (mode, refresh) {
+        (GrayMode::Gray4, _) => &LUT_4GRAY_GC,
+        (GrayMode::Gray1, _) if fast_mono => &LUT_1GRAY_A2,
+        (GrayMode::Gray1, RefreshLut::Full | RefreshLut::Custom(_)) => &LUT_1GRAY_GC,
+        (GrayMode::Gray1, RefreshLut::Quick) => &LUT_1GRAY_DU,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn epd_size() {
+        assert_eq!(WIDTH, 280);
+        assert_eq!(HEIGHT, 480);
+        assert_eq!(DEFAULT_BACKGROUND_COLOR, Color::White);
+    }
+
+    #[test]
+    fn fast_mono_waveform() {
+        use GrayMode::*;
+        assert_eq!(waveform(Gray1, RefreshLut::Full, false), &LUT_1GRAY_GC);
+        assert_eq!(waveform(Gray1, RefreshLut::Quick, false), &LUT_1GRAY_DU);
+        assert_eq!(waveform(Gray1, RefreshLut::Full, true), &LUT_1GRAY_A2);
+        assert_eq!(waveform(Gray1, RefreshLut::Quick, true), &LUT_1GRAY_A2);
+        assert_eq!(waveform(Gray4, RefreshLut::Full, true), &LUT_4GRAY_GC);
+    }
+}
@@ -0,0 +1,123 @@
+use crate::color::OctColor;
+use crate::epd4in01f::{DEFAULT_BACKGROUND_COLOR, HEIGHT, WIDTH};
+use crate::graphics::{DisplayRotation, OctDisplay};
+use embedded_graphics_core::prelude::*;
+
+/// Full size buffer for use with the 4in01f EPD
+///
+/// Can also be manually constructed:
+/// `buffer: [DEFAULT_BACKGROUND_COLOR.get_byte_value(); WIDTH / 2 * HEIGHT]`
+pub struct Display4in01f {
+    buffer: [u8; WIDTH as usize * HEIGHT as usize / 2],
+    rotation: DisplayRotation,
+}
+
+impl Default for Display4in01f {
+    fn default() -> Self {
+        Display4in01f {
+            buffer: [OctColor::colors_byte(DEFAULT_BACKGROUND_COLOR, DEFAULT_BACKGROUND_COLOR);
+                WIDTH as usize * HEIGHT as usize / 2],
+            rotation: DisplayRotation::default(),
+        }
+    }
+}
+
+impl DrawTarget for Display4in01f {
+    type Color = OctColor;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for pixel in pixels {
+            self.draw_helper(WIDTH, HEIGHT, pixel)?;
+        }
+        Ok(())
+    }
+}
+
+impl OriginDimensions for Display4in01f {
+    fn size(&self) -> Size {
+        Size::new(WIDTH, HEIGHT)
+    }
+}
+
+impl OctDisplay for Display4in01f {
+    fn buffer(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    fn get_mut_buffer(&mut self) -> &mut [u8] {
+        &mut self.buffer
+    }
+
+    fn set_rotation(&mut self, rotation: DisplayRotation) {
+        self.rotation = rotation;
+    }
+
+    fn rotation(&self) -> DisplayRotation {
+        self.rotation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::epd4in01f;
+    use crate::graphics::OctDisplay;
+    use embedded_graphics::{
+        prelude::*,
+        primitives::{Line, PrimitiveStyle},
+    };
+
+    // test buffer length
+    #[test]
+    fn graphics_size() {
+        let display = Display4in01f::default();
+        assert_eq!(display.buffer().len(), 400 * 640 / 2);
+    }
+
+    // test default background color on all bytes
+    #[test]
+    fn graphics_default() {
+        let display = Display4in01f::default();
+        for &byte in display.buffer() {
+            assert_eq!(
+                byte,
+                OctColor::colors_byte(
+                    epd4in01f::DEFAULT_BACKGROUND_COLOR,
+                    epd4in01f::DEFAULT_BACKGROUND_COLOR,
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn graphics_rotation_0() {
+        let mut display = Display4in01f::default();
+
+        let _ = Line::new(Point::new(0, 0), Point::new(1, 0))
+            .into_styled(PrimitiveStyle::with_stroke(OctColor::Black, 1))
+            .draw(&mut display);
+
+        let buffer = display.buffer();
+
+        for &byte in buffer.iter().take(1) {
+            assert_eq!(
+                OctColor::split_byte(byte),
+                Ok((OctColor::Black, OctColor::Black))
+            );
+        }
+
+        for &byte in buffer.iter().skip(1) {
+            assert_eq!(
+                OctColor::split_byte(byte),
+                Ok((
+                    epd4in01f::DEFAULT_BACKGROUND_COLOR,
+                    epd4in01f::DEFAULT_BACKGROUND_COLOR
+                ))
+            );
+        }
+    }
+}
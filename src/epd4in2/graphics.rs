@@ -1,6 +1,6 @@
-use crate::epd4in2::{DEFAULT_BACKGROUND_COLOR, HEIGHT, WIDTH};
-use crate::graphics::{Display, DisplayRotation};
-use embedded_graphics::pixelcolor::BinaryColor;
+use crate::epd4in2::{DEFAULT_BACKGROUND_COLOR, HEIGHT, NUM_GRAY_BYTES, WIDTH};
+use crate::graphics::{Display, DisplayRotation, GrayDisplay};
+use embedded_graphics::pixelcolor::{BinaryColor, Gray2};
 use embedded_graphics_core::prelude::*;
 
 /// Full size buffer for use with the 4in2 EPD
@@ -60,6 +60,62 @@ impl Display for Display4in2 {
     }
 }
 
+/// Full size buffer with 4 gray levels for use with the 4in2 EPD
+///
+/// Every byte holds 4 pixels, white (`0xFF`) is the default.
+pub struct Display4in2Gray {
+    buffer: [u8; NUM_GRAY_BYTES],
+    rotation: DisplayRotation,
+}
+
+impl Default for Display4in2Gray {
+    fn default() -> Self {
+        Display4in2Gray {
+            buffer: [0xFF; NUM_GRAY_BYTES],
+            rotation: DisplayRotation::default(),
+        }
+    }
+}
+
+impl DrawTarget for Display4in2Gray {
+    type Color = Gray2;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for pixel in pixels {
+            self.draw_helper(WIDTH, HEIGHT, pixel)?;
+        }
+        Ok(())
+    }
+}
+
+impl OriginDimensions for Display4in2Gray {
+    fn size(&self) -> Size {
+        Size::new(WIDTH, HEIGHT)
+    }
+}
+
+impl GrayDisplay for Display4in2Gray {
+    fn buffer(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    fn get_mut_buffer(&mut self) -> &mut [u8] {
+        &mut self.buffer
+    }
+
+    fn set_rotation(&mut self, rotation: DisplayRotation) {
+        self.rotation = rotation;
+    }
+
+    fn rotation(&self) -> DisplayRotation {
+        self.rotation
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -161,4 +217,15 @@ mod tests {
             assert_eq!(byte, epd4in2::DEFAULT_BACKGROUND_COLOR.get_byte_value());
         }
     }
+
+    #[test]
+    fn graphics_gray_levels() {
+        let mut display = Display4in2Gray::default();
+        assert_eq!(GrayDisplay::buffer(&display).len(), 30000);
+        for (x, luma) in (0..4).enumerate() {
+            let _ = Pixel(Point::new(x as i32, 0), Gray2::new(luma)).draw(&mut display);
+        }
+        assert_eq!(GrayDisplay::buffer(&display)[0], 0b00_01_10_11);
+        assert_eq!(GrayDisplay::buffer(&display)[1], 0xFF);
+    }
 }
@@ -9,7 +9,7 @@
 //!
 //!```rust, no_run
 //!# use embedded_hal_mock::*;
-//!# fn main() -> Result<(), MockError> {
+//!# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
 //!use embedded_graphics::{
 //!    pixelcolor::BinaryColor::On as Black, prelude::*, primitives::{Line, PrimitiveStyle},
 //!};
@@ -45,7 +45,51 @@
 //!# }
 //!```
 //!
+//! # Partial refresh
 //!
+//! [`update_partial_frame`](WaveshareDisplay::update_partial_frame) puts the controller
+//! into partial mode, and the next [`display_frame`](WaveshareDisplay::display_frame)
+//! only refreshes that window, e.g. the entry of a menu which changed after a keypress.
+//! It waits for the refresh and leaves partial mode again. Combined with
+//! `set_lut(spi, Some(RefreshLut::Quick))` the window is redrawn without flashing, as the
+//! quick waveform drives every pixel to its new color whatever the old one was. The window
+//! has to start at a multiple of 8 and be a multiple of 8 wide.
+//!
+//! # 4 gray levels
+//!
+//! Draw into the `Gray2` buffer [`Display4in2Gray`] and upload it with
+//! [`Epd4in2::update_gray_frame`], which loads the gray waveform of the vendor driver.
+//! Uploading a black/white frame switches back.
+//!
+//!```rust, no_run
+//!# use embedded_hal_mock::*;
+//!# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
+//!use embedded_graphics::{
+//!    pixelcolor::Gray2, prelude::*, primitives::{Line, PrimitiveStyle},
+//!};
+//!use epd_waveshare::{epd4in2::*, prelude::*};
+//!#
+//!# let expectations = [];
+//!# let mut spi = spi::Mock::new(&expectations);
+//!# let expectations = [];
+//!# let cs_pin = pin::Mock::new(&expectations);
+//!# let busy_in = pin::Mock::new(&expectations);
+//!# let dc = pin::Mock::new(&expectations);
+//!# let rst = pin::Mock::new(&expectations);
+//!# let mut delay = delay::MockNoop::new();
+//!
+//!let mut epd = Epd4in2::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
+//!let mut display = Display4in2Gray::default();
+//!
+//!let _ = Line::new(Point::new(10, 20), Point::new(10, 280))
+//!    .into_styled(PrimitiveStyle::with_stroke(Gray2::new(1), 1))
+//!    .draw(&mut display);
+//!
+//!epd.update_gray_frame(&mut spi, display.buffer())?;
+//!epd.display_frame(&mut spi, &mut delay)?;
+//!# Ok(())
+//!# }
+//!```
 //!
 //! BE CAREFUL! The screen can get ghosting/burn-ins through the Partial Fast Update Drawing.
 
@@ -54,11 +98,18 @@ use embedded_hal::{
     digital::v2::*,
 };
 
+use crate::controllers::uc8151::{vcm_dc, VCOM_RANGE_MV};
 use crate::interface::DisplayInterface;
-use crate::traits::{InternalWiAdditions, QuickRefresh, RefreshLut, WaveshareDisplay};
+use crate::luts::{uc81xx, LutPreset, LutSource, Panel};
+use crate::refresh::QuickRefreshCounter;
+use crate::traits::{
+    BorderControl, CustomLut, FrameRate, FrameRateControl, FullRefreshInterval, HardwareMirror,
+    InternalWiAdditions, LutSelection, Mirror, PowerDown, QuickRefresh, RefreshLut, VcomSetting,
+    WaveshareDisplay,
+};
 
 //The Lookup Tables for the Display
-mod constants;
+pub(crate) mod constants;
 use crate::epd4in2::constants::*;
 
 /// Width of the display
@@ -67,9 +118,20 @@ pub const WIDTH: u32 = 400;
 pub const HEIGHT: u32 = 300;
 /// Default Background Color
 pub const DEFAULT_BACKGROUND_COLOR: Color = Color::White;
+/// VCOM of the vendor driver in mV, without the sign
+const DEFAULT_VCOM_MV: u16 = 1000;
 const IS_BUSY_LOW: bool = true;
+/// Bytes of a frame with 4 gray levels, 2 bits per pixel
+pub(crate) const NUM_GRAY_BYTES: usize = WIDTH as usize * HEIGHT as usize / 4;
+/// Power setting of black/white frames, the gray waveform needs a lower VDHR
+const POWER_SETTING: [u8; 5] = [0x03, 0x00, 0x2b, 0x2b, 0xff];
+/// Frame rate of black/white frames in the vendor driver
+const DEFAULT_FRAME_RATE: FrameRate = FrameRate::Hz100;
+
+pub use crate::color::GrayMode;
 
-use crate::color::Color;
+use crate::color::{BorderColor, Color};
+use crate::{span_fits, Error};
 
 pub(crate) mod command;
 use self::command::Command;
@@ -77,7 +139,7 @@ use self::command::Command;
 #[cfg(feature = "graphics")]
 mod graphics;
 #[cfg(feature = "graphics")]
-pub use self::graphics::Display4in2;
+pub use self::graphics::{Display4in2, Display4in2Gray};
 
 /// Epd4in2 driver
 ///
@@ -88,6 +150,26 @@ pub struct Epd4in2<SPI, CS, BUSY, DC, RST, DELAY> {
     color: Color,
     /// Refresh LUT
     refresh: RefreshLut,
+    /// Whether the controller is in partial mode, waiting for the window to be refreshed
+    partial: bool,
+    /// Gray levels of the last uploaded frame
+    mode: GrayMode,
+    /// VCOM in mV, without the sign
+    vcom_mv: u16,
+    /// VCOM and data interval setting with the border bits
+    border: u8,
+    /// Where the waveform of the black/white refreshes comes from
+    lut_source: LutSource,
+    /// Waveform written instead of the one selected with `set_lut`
+    preset: Option<&'static LutPreset>,
+    /// Quick refreshes since the last full one
+    quick_refreshes: QuickRefreshCounter,
+    /// Scan directions of the panel setting
+    mirror: Mirror,
+    /// PLL setting of black/white frames
+    frame_rate: FrameRate,
+    /// Whether the drivers were switched off after the last refresh
+    powered_down: bool,
 }
 
 impl<SPI, CS, BUSY, DC, RST, DELAY> InternalWiAdditions<SPI, CS, BUSY, DC, RST, DELAY>
@@ -100,16 +182,16 @@ where
     RST: OutputPin,
     DELAY: DelayMs<u8>,
 {
-    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         // reset the device
         self.interface.reset(delay, 10);
+        self.partial = false;
+        self.powered_down = false;
+        self.mode = GrayMode::Gray1;
 
         // set the power settings
-        self.interface.cmd_with_data(
-            spi,
-            Command::PowerSetting,
-            &[0x03, 0x00, 0x2b, 0x2b, 0xff],
-        )?;
+        self.interface
+            .cmd_with_data(spi, Command::PowerSetting, &POWER_SETTING)?;
 
         // start the booster
         self.interface
@@ -121,22 +203,23 @@ where
         self.wait_until_idle();
 
         // set the panel settings
-        self.cmd_with_data(spi, Command::PanelSetting, &[0x3F])?;
+        let panel_setting = self.panel_setting(self.lut_source);
+        self.cmd_with_data(spi, Command::PanelSetting, &[panel_setting])?;
 
         // Set Frequency, 200 Hz didn't work on my board
         // 150Hz and 171Hz wasn't tested yet
         // TODO: Test these other frequencies
         // 3A 100HZ   29 150Hz 39 200HZ  31 171HZ DEFAULT: 3c 50Hz
-        self.cmd_with_data(spi, Command::PllControl, &[0x3A])?;
+        self.cmd_with_data(spi, Command::PllControl, &[self.frame_rate as u8])?;
 
         self.send_resolution(spi)?;
 
         self.interface
-            .cmd_with_data(spi, Command::VcmDcSetting, &[0x12])?;
+            .cmd_with_data(spi, Command::VcmDcSetting, &[vcm_dc(self.vcom_mv)])?;
 
         //VBDF 17|D7 VBDW 97  VBDB 57  VBDF F7  VBDW 77  VBDB 37  VBDR B7
         self.interface
-            .cmd_with_data(spi, Command::VcomAndDataIntervalSetting, &[0x97])?;
+            .cmd_with_data(spi, Command::VcomAndDataIntervalSetting, &[self.border])?;
 
         self.set_lut(spi, None)?;
 
@@ -163,7 +246,7 @@ where
         dc: DC,
         rst: RST,
         delay: &mut DELAY,
-    ) -> Result<Self, SPI::Error> {
+    ) -> Result<Self, Error<SPI::Error>> {
         let interface = DisplayInterface::new(cs, busy, dc, rst);
         let color = DEFAULT_BACKGROUND_COLOR;
 
@@ -171,6 +254,16 @@ where
             interface,
             color,
             refresh: RefreshLut::Full,
+            partial: false,
+            mode: GrayMode::Gray1,
+            vcom_mv: DEFAULT_VCOM_MV,
+            border: 0x97,
+            lut_source: LutSource::Register,
+            preset: None,
+            quick_refreshes: QuickRefreshCounter::default(),
+            mirror: Mirror::None,
+            frame_rate: DEFAULT_FRAME_RATE,
+            powered_down: false,
         };
 
         epd.init(spi, delay)?;
@@ -178,12 +271,13 @@ where
         Ok(epd)
     }
 
-    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         self.init(spi, delay)
     }
 
-    fn sleep(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn sleep(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         self.wait_until_idle();
+        self.leave_partial(spi)?;
         self.interface
             .cmd_with_data(spi, Command::VcomAndDataIntervalSetting, &[0x17])?; //border floating
         self.command(spi, Command::VcmDcSetting)?; // VCOM to 0V
@@ -206,8 +300,10 @@ where
         spi: &mut SPI,
         buffer: &[u8],
         _delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.wait_until_idle();
+        self.leave_partial(spi)?;
+        self.use_bw_mode(spi)?;
         let color_value = self.color.get_byte_value();
 
         self.interface.cmd(spi, Command::DataStartTransmission1)?;
@@ -227,47 +323,54 @@ where
         y: u32,
         width: u32,
         height: u32,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(x.is_multiple_of(8) && width.is_multiple_of(8) && width > 0 && height > 0);
+        ensure!(span_fits(x, width, WIDTH) && span_fits(y, height, HEIGHT));
+        ensure!(buffer.len() as u32 == width / 8 * height);
         self.wait_until_idle();
-        if buffer.len() as u32 != width / 8 * height {
-            //TODO: panic!! or sth like that
-            //return Err("Wrong buffersize");
-        }
+        self.use_bw_mode(spi)?;
 
+        // stays in partial mode until the window is refreshed by display_frame
         self.command(spi, Command::PartialIn)?;
         self.command(spi, Command::PartialWindow)?;
-        self.send_data(spi, &[(x >> 8) as u8])?;
-        let tmp = x & 0xf8;
-        self.send_data(spi, &[tmp as u8])?; // x should be the multiple of 8, the last 3 bit will always be ignored
-        let tmp = tmp + width - 1;
-        self.send_data(spi, &[(tmp >> 8) as u8])?;
-        self.send_data(spi, &[(tmp | 0x07) as u8])?;
-
-        self.send_data(spi, &[(y >> 8) as u8])?;
-        self.send_data(spi, &[y as u8])?;
-
-        self.send_data(spi, &[((y + height - 1) >> 8) as u8])?;
-        self.send_data(spi, &[(y + height - 1) as u8])?;
+        self.shift_display(spi, x, y, width, height)?;
 
-        self.send_data(spi, &[0x01])?; // Gates scan both inside and outside of the partial window. (default)
+        // only the new data is written, the quick waveform doesn't depend on the old one
+        self.cmd_with_data(spi, Command::DataStartTransmission2, buffer)?;
+        self.partial = true;
+        Ok(())
+    }
 
-        //TODO: handle dtm somehow
-        let is_dtm1 = false;
-        if is_dtm1 {
-            self.command(spi, Command::DataStartTransmission1)? //TODO: check if data_start transmission 1 also needs "old"/background data here
-        } else {
-            self.command(spi, Command::DataStartTransmission2)?
+    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.start_display_frame(spi, delay)?;
+        if self.partial {
+            // the window has to be refreshed before partial mode is left
+            self.finish_refresh(spi, delay)?;
         }
-
-        self.send_data(spi, buffer)?;
-
-        self.command(spi, Command::PartialOut)?;
         Ok(())
     }
 
-    fn display_frame(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn start_display_frame(
+        &mut self,
+        spi: &mut SPI,
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
         self.wait_until_idle();
-        self.command(spi, Command::DisplayRefresh)?;
+        if self.refresh == RefreshLut::Full || self.lut_source == LutSource::Otp {
+            self.quick_refreshes.full();
+        }
+        self.start_refresh(spi)
+    }
+
+    fn finish_refresh(
+        &mut self,
+        spi: &mut SPI,
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        if self.partial {
+            self.leave_partial(spi)?;
+        }
         Ok(())
     }
 
@@ -276,14 +379,18 @@ where
         spi: &mut SPI,
         buffer: &[u8],
         delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.update_frame(spi, buffer, delay)?;
-        self.command(spi, Command::DisplayRefresh)?;
-        Ok(())
+        if self.refresh == RefreshLut::Full || self.lut_source == LutSource::Otp {
+            self.quick_refreshes.full();
+        }
+        self.start_refresh(spi)
     }
 
-    fn clear_frame(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn clear_frame(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         self.wait_until_idle();
+        self.leave_partial(spi)?;
+        self.use_bw_mode(spi)?;
         self.send_resolution(spi)?;
 
         let color_value = self.color.get_byte_value();
@@ -318,9 +425,24 @@ where
         &mut self,
         spi: &mut SPI,
         refresh_rate: Option<RefreshLut>,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         if let Some(refresh_lut) = refresh_rate {
             self.refresh = refresh_lut;
+            self.preset = None;
+            self.lut_source = LutSource::Register;
+            if let RefreshLut::Custom(preset) = refresh_lut {
+                ensure!(preset.panel == Panel::Epd4in2);
+            }
+        }
+        if self.mode == GrayMode::Gray4 {
+            // loaded when the next black/white frame is uploaded
+            return Ok(());
+        }
+        let panel_setting = self.panel_setting(self.lut_source);
+        self.cmd_with_data(spi, Command::PanelSetting, &[panel_setting])?;
+        if let Some(preset) = self.preset {
+            self.wait_until_idle();
+            return self.interface.write_lut_preset(spi, Panel::Epd4in2, preset);
         }
         match self.refresh {
             RefreshLut::Full => {
@@ -334,6 +456,10 @@ where
                 &LUT_WB_QUICK,
                 &LUT_BB_QUICK,
             ),
+            RefreshLut::Custom(preset) => {
+                self.wait_until_idle();
+                self.interface.write_lut_preset(spi, Panel::Epd4in2, preset)
+            }
         }
     }
 
@@ -351,11 +477,96 @@ where
     RST: OutputPin,
     DELAY: DelayMs<u8>,
 {
-    fn command(&mut self, spi: &mut SPI, command: Command) -> Result<(), SPI::Error> {
+    /// Writes the waveform of a [`LutPreset`] made for this panel
+    ///
+    /// The driver loads its own waveform again in `set_lut`.
+    ///
+    /// Panics if the preset is made for another panel.
+    pub fn set_lut_preset(
+        &mut self,
+        spi: &mut SPI,
+        preset: &LutPreset,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        self.interface.write_lut_preset(spi, Panel::Epd4in2, preset)
+    }
+
+    /// Uploads a frame with 4 gray levels and switches the display into `GrayMode::Gray4`
+    ///
+    /// The buffer holds 2 bits per pixel, see [`Display4in2Gray`].
+    pub fn update_gray_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(buffer.len() == NUM_GRAY_BYTES);
+        self.wait_until_idle();
+        self.leave_partial(spi)?;
+
+        if self.mode == GrayMode::Gray1 {
+            // the gray waveform is always in the registers
+            let panel_setting = self.panel_setting(LutSource::Register);
+            self.cmd_with_data(spi, Command::PanelSetting, &[panel_setting])?;
+            let mut power = POWER_SETTING;
+            power[4] = 0x13;
+            self.cmd_with_data(spi, Command::PowerSetting, &power)?;
+            self.cmd_with_data(spi, Command::PllControl, &[FrameRate::Hz50 as u8])?;
+            self.set_lut_helper(
+                spi,
+                &LUT_VCOM_GRAY4,
+                &LUT_WW_GRAY4,
+                &LUT_BW_GRAY4,
+                &LUT_WB_GRAY4,
+                &LUT_BB_GRAY4,
+            )?;
+            self.mode = GrayMode::Gray4;
+        }
+
+        // the high bit of every pixel goes into the old frame, the low bit into the new one,
+        // the waveform tells the 4 combinations apart
+        self.command(spi, Command::DataStartTransmission1)?;
+        self.interface.data_gray_plane(spi, buffer, 0b10, false)?;
+        self.command(spi, Command::DataStartTransmission2)?;
+        self.interface.data_gray_plane(spi, buffer, 0b01, false)
+    }
+
+    /// Mode of the last uploaded frame
+    pub fn gray_mode(&self) -> GrayMode {
+        self.mode
+    }
+
+    /// Switches back to black/white frames, the gray mode changed several registers
+    fn use_bw_mode(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        if self.mode == GrayMode::Gray4 {
+            self.mode = GrayMode::Gray1;
+            self.cmd_with_data(spi, Command::PowerSetting, &POWER_SETTING)?;
+            self.cmd_with_data(spi, Command::PllControl, &[self.frame_rate as u8])?;
+            self.set_lut(spi, None)?;
+        }
+        Ok(())
+    }
+
+    /// Panel setting with the LUTs from the OTP or the registers, scanning up (UD) and
+    /// shifting right (SHL) unless mirrored
+    fn panel_setting(&self, source: LutSource) -> u8 {
+        let mut setting = match source {
+            LutSource::Otp => 0x1F,
+            LutSource::Register => 0x3F,
+        };
+        if self.mirror.vertical() {
+            setting &= !0x08;
+        }
+        if self.mirror.horizontal() {
+            setting &= !0x04;
+        }
+        setting
+    }
+
+    fn command(&mut self, spi: &mut SPI, command: Command) -> Result<(), Error<SPI::Error>> {
         self.interface.cmd(spi, command)
     }
 
-    fn send_data(&mut self, spi: &mut SPI, data: &[u8]) -> Result<(), SPI::Error> {
+    fn send_data(&mut self, spi: &mut SPI, data: &[u8]) -> Result<(), Error<SPI::Error>> {
         self.interface.data(spi, data)
     }
 
@@ -364,15 +575,39 @@ where
         spi: &mut SPI,
         command: Command,
         data: &[u8],
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.interface.cmd_with_data(spi, command, data)
     }
 
     fn wait_until_idle(&mut self) {
-        let _ = self.interface.wait_until_idle(IS_BUSY_LOW);
+        self.interface.wait_until_idle(IS_BUSY_LOW);
     }
 
-    fn send_resolution(&mut self, spi: &mut SPI) -> Result<(), SPI::Error> {
+    /// Starts a refresh, powering the drivers on again after `power_down`
+    fn start_refresh(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        if self.powered_down {
+            self.command(spi, Command::PowerOn)?;
+            self.wait_until_idle();
+            self.interface.cmd_with_data(
+                spi,
+                Command::VcomAndDataIntervalSetting,
+                &[self.border],
+            )?;
+            self.powered_down = false;
+        }
+        self.command(spi, Command::DisplayRefresh)
+    }
+
+    /// Goes back to full frames, if a partial frame wasn't displayed
+    fn leave_partial(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        if self.partial {
+            self.command(spi, Command::PartialOut)?;
+            self.partial = false;
+        }
+        Ok(())
+    }
+
+    fn send_resolution(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
         let w = self.width();
         let h = self.height();
 
@@ -391,7 +626,7 @@ where
         lut_bw: &[u8],
         lut_wb: &[u8],
         lut_bb: &[u8],
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.wait_until_idle();
         // LUT VCOM
         self.cmd_with_data(spi, Command::LutForVcom, lut_vcom)?;
@@ -419,7 +654,8 @@ where
         y: u32,
         width: u32,
         height: u32,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(span_fits(x, width, WIDTH) && span_fits(y, height, HEIGHT));
         self.send_data(spi, &[(x >> 8) as u8])?;
         let tmp = x & 0xf8;
         self.send_data(spi, &[tmp as u8])?; // x should be the multiple of 8, the last 3 bit will always be ignored
@@ -455,8 +691,10 @@ where
         spi: &mut SPI,
         buffer: &[u8],
         _delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.wait_until_idle();
+        self.leave_partial(spi)?;
+        self.use_bw_mode(spi)?;
 
         self.interface.cmd(spi, Command::DataStartTransmission1)?;
 
@@ -471,9 +709,10 @@ where
         spi: &mut SPI,
         buffer: &[u8],
         _delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.wait_until_idle();
-        // self.send_resolution(spi)?;
+        self.leave_partial(spi)?;
+        self.use_bw_mode(spi)?;
 
         self.interface.cmd(spi, Command::DataStartTransmission2)?;
 
@@ -482,21 +721,39 @@ where
         Ok(())
     }
 
-    /// This function is not needed for this display
-    #[allow(unused)]
-    fn display_new_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
-        unimplemented!()
+    /// Refreshes the display, only the window if the new frame was a partial one
+    fn display_new_frame(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.display_frame(spi, delay)
     }
 
-    /// This function is not needed for this display
-    #[allow(unused)]
+    /// Updates and displays the new frame, which becomes the old frame afterwards.
+    ///
+    /// Refreshes with the full waveform if a full refresh is due, see
+    /// [`FullRefreshInterval`].
     fn update_and_display_new_frame(
         &mut self,
         spi: &mut SPI,
         buffer: &[u8],
         delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
-        unimplemented!()
+    ) -> Result<(), Error<SPI::Error>> {
+        self.update_new_frame(spi, buffer, delay)?;
+        if self.quick_refreshes.next_is_full() {
+            // the full waveform for this refresh only
+            self.wait_until_idle();
+            let panel_setting = self.panel_setting(LutSource::Register);
+            self.cmd_with_data(spi, Command::PanelSetting, &[panel_setting])?;
+            self.set_lut_helper(spi, &LUT_VCOM0, &LUT_WW, &LUT_BW, &LUT_WB, &LUT_BB)?;
+            self.start_refresh(spi)?;
+            self.wait_until_idle();
+            self.set_lut(spi, None)?;
+        } else {
+            self.display_new_frame(spi, delay)?;
+        }
+        self.update_old_frame(spi, buffer, delay)
     }
 
     fn update_partial_old_frame(
@@ -507,13 +764,12 @@ where
         y: u32,
         width: u32,
         height: u32,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(x.is_multiple_of(8) && width.is_multiple_of(8) && width > 0 && height > 0);
+        ensure!(span_fits(x, width, WIDTH) && span_fits(y, height, HEIGHT));
+        ensure!(buffer.len() as u32 == width / 8 * height);
         self.wait_until_idle();
-
-        if buffer.len() as u32 != width / 8 * height {
-            //TODO: panic!! or sth like that
-            //return Err("Wrong buffersize");
-        }
+        self.use_bw_mode(spi)?;
 
         self.interface.cmd(spi, Command::PartialIn)?;
         self.interface.cmd(spi, Command::PartialWindow)?;
@@ -523,12 +779,14 @@ where
         self.interface.cmd(spi, Command::DataStartTransmission1)?;
 
         self.interface.data(spi, buffer)?;
+        self.partial = true;
 
         Ok(())
     }
 
     /// Always call `update_partial_old_frame` before this, with buffer-updating code
-    /// between the calls.
+    /// between the calls. Stays in partial mode, so `display_new_frame` only refreshes
+    /// the window.
     fn update_partial_new_frame(
         &mut self,
         spi: &mut SPI,
@@ -537,20 +795,21 @@ where
         y: u32,
         width: u32,
         height: u32,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(x.is_multiple_of(8) && width.is_multiple_of(8) && width > 0 && height > 0);
+        ensure!(span_fits(x, width, WIDTH) && span_fits(y, height, HEIGHT));
+        ensure!(buffer.len() as u32 == width / 8 * height);
         self.wait_until_idle();
-        if buffer.len() as u32 != width / 8 * height {
-            //TODO: panic!! or sth like that
-            //return Err("Wrong buffersize");
-        }
+        self.use_bw_mode(spi)?;
 
+        self.interface.cmd(spi, Command::PartialIn)?;
+        self.interface.cmd(spi, Command::PartialWindow)?;
         self.shift_display(spi, x, y, width, height)?;
 
         self.interface.cmd(spi, Command::DataStartTransmission2)?;
 
         self.interface.data(spi, buffer)?;
-
-        self.interface.cmd(spi, Command::PartialOut)?;
+        self.partial = true;
         Ok(())
     }
 
@@ -561,8 +820,10 @@ where
         y: u32,
         width: u32,
         height: u32,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.wait_until_idle();
+        self.leave_partial(spi)?;
+        self.use_bw_mode(spi)?;
         self.send_resolution(spi)?;
 
         let color_value = self.color.get_byte_value();
@@ -585,14 +846,259 @@ where
     }
 }
 
+impl<SPI, CS, BUSY, DC, RST, DELAY> FullRefreshInterval<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd4in2<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn set_full_refresh_every(&mut self, updates: u16) {
+        self.quick_refreshes.set_full_every(updates);
+    }
+
+    fn quick_refreshes(&self) -> u16 {
+        self.quick_refreshes.since_full()
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> HardwareMirror<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd4in2<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn set_mirror(&mut self, spi: &mut SPI, mirror: Mirror) -> Result<(), Error<SPI::Error>> {
+        self.mirror = mirror;
+        self.wait_until_idle();
+        let panel_setting = self.panel_setting(self.lut_source);
+        self.cmd_with_data(spi, Command::PanelSetting, &[panel_setting])
+    }
+
+    fn mirror(&self) -> Mirror {
+        self.mirror
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> FrameRateControl<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd4in2<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    /// The gray waveform runs at 50 Hz, a frame rate set while gray frames are shown
+    /// applies once the driver goes back to black/white frames.
+    fn set_frame_rate(&mut self, spi: &mut SPI, rate: FrameRate) -> Result<(), Error<SPI::Error>> {
+        self.frame_rate = rate;
+        if self.mode == GrayMode::Gray4 {
+            return Ok(());
+        }
+        self.wait_until_idle();
+        self.cmd_with_data(spi, Command::PllControl, &[rate as u8])
+    }
+
+    fn frame_rate(&self) -> FrameRate {
+        self.frame_rate
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> LutSelection<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd4in2<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn lut_source(&self) -> LutSource {
+        self.lut_source
+    }
+
+    /// `set_lut` with a new refresh rate goes back to the register LUTs of the driver.
+    fn use_otp_lut(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        self.lut_source = LutSource::Otp;
+        self.preset = None;
+        self.wait_until_idle();
+        self.set_lut(spi, None)
+    }
+
+    /// `set_lut` with a new refresh rate goes back to the register LUTs of the driver.
+    fn use_register_lut(
+        &mut self,
+        spi: &mut SPI,
+        preset: &'static LutPreset,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(preset.panel == Panel::Epd4in2);
+        self.lut_source = LutSource::Register;
+        self.preset = Some(preset);
+        self.wait_until_idle();
+        self.set_lut(spi, None)
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> CustomLut<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd4in2<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    type Lut = uc81xx::Lut;
+
+    /// Also switches to the register LUTs, see [`LutSelection`].
+    fn load_lut(&mut self, spi: &mut SPI, lut: &uc81xx::Lut) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        self.lut_source = LutSource::Register;
+        let panel_setting = self.panel_setting(self.lut_source);
+        self.cmd_with_data(spi, Command::PanelSetting, &[panel_setting])?;
+        // the VCOM table of this controller ends with 2 more bytes
+        let mut vcom = [0; uc81xx::TABLE_LEN + 2];
+        vcom[..uc81xx::TABLE_LEN].copy_from_slice(&lut.vcom.to_bytes());
+        self.set_lut_helper(
+            spi,
+            &vcom,
+            &lut.ww.to_bytes(),
+            &lut.bw.to_bytes(),
+            &lut.wb.to_bytes(),
+            &lut.bb.to_bytes(),
+        )
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> BorderControl<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd4in2<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn set_border(&mut self, spi: &mut SPI, border: BorderColor) -> Result<(), Error<SPI::Error>> {
+        self.border = match border {
+            BorderColor::Black => 0x57,
+            BorderColor::White => 0x97,
+            BorderColor::Floating => 0x17,
+        };
+        self.wait_until_idle();
+        self.interface
+            .cmd_with_data(spi, Command::VcomAndDataIntervalSetting, &[self.border])
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> PowerDown<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd4in2<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn power_down(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        self.leave_partial(spi)?;
+        // border floating, the border set with `set_border` is restored by the next refresh
+        self.interface
+            .cmd_with_data(spi, Command::VcomAndDataIntervalSetting, &[0x17])?;
+        self.command(spi, Command::PowerOff)?;
+        self.wait_until_idle();
+        self.powered_down = true;
+        Ok(())
+    }
+
+    fn is_powered_down(&self) -> bool {
+        self.powered_down
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> VcomSetting<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd4in2<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn set_vcom(&mut self, spi: &mut SPI, millivolts: u16) -> Result<(), Error<SPI::Error>> {
+        ensure!(VCOM_RANGE_MV.contains(&millivolts));
+        self.vcom_mv = millivolts;
+        self.wait_until_idle();
+        self.interface
+            .cmd_with_data(spi, Command::VcmDcSetting, &[vcm_dc(millivolts)])
+    }
+
+    fn vcom(&self) -> u16 {
+        self.vcom_mv
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::bus_log::{commands, data_of, Log, Pin, Spi};
+    use embedded_hal_mock::delay::MockNoop;
 
     #[test]
     fn epd_size() {
         assert_eq!(WIDTH, 400);
         assert_eq!(HEIGHT, 300);
         assert_eq!(DEFAULT_BACKGROUND_COLOR, Color::White);
+        assert_eq!(NUM_GRAY_BYTES, 30_000);
+        assert_eq!(DEFAULT_FRAME_RATE as u8, 0x3A);
+    }
+
+    #[test]
+    fn refresh_after_power_down() {
+        let log = Log::default();
+        let mut spi = Spi(&log);
+        let mut delay = MockNoop::new();
+        let mut epd = Epd4in2::new(
+            &mut spi,
+            Pin(None),
+            Pin(None),
+            Pin(Some(&log)),
+            Pin(None),
+            &mut delay,
+        )
+        .unwrap();
+        epd.set_border(&mut spi, BorderColor::Black).unwrap();
+
+        log.take();
+        epd.power_down(&mut spi).unwrap();
+        assert!(epd.is_powered_down());
+        let events = log.take();
+        assert_eq!(commands(&events), [0x50, 0x02]);
+        assert_eq!(data_of(&events, 0x50), [[0x17]]);
+
+        // power on and the border set before again
+        epd.display_frame(&mut spi, &mut delay).unwrap();
+        assert!(!epd.is_powered_down());
+        let events = log.take();
+        assert_eq!(commands(&events), [0x04, 0x50, 0x12]);
+        assert_eq!(data_of(&events, 0x50), [[0x57]]);
+
+        epd.display_frame(&mut spi, &mut delay).unwrap();
+        assert_eq!(commands(&log.take()), [0x12]);
     }
 }
@@ -0,0 +1,41 @@
+//! Look-up-tables of the 4.2" V2 display, taken from the vendor driver.
+
+/// 4 gray waveform, followed by the end option, gate voltage, the three source
+/// voltages and VCOM
+#[rustfmt::skip]
+pub(crate) const LUT_GRAY4: [u8; 233] = [
+    0x01, 0x0A, 0x1B, 0x0F, 0x03, 0x01, 0x01,
+    0x05, 0x0A, 0x01, 0x0A, 0x01, 0x01, 0x01,
+    0x05, 0x08, 0x03, 0x02, 0x04, 0x01, 0x01,
+    0x01, 0x04, 0x04, 0x02, 0x00, 0x01, 0x01,
+    0x01, 0x00, 0x00, 0x00, 0x00, 0x01, 0x01,
+    0x01, 0x00, 0x00, 0x00, 0x00, 0x01, 0x01,
+    0x01, 0x0A, 0x1B, 0x0F, 0x03, 0x01, 0x01,
+    0x05, 0x4A, 0x01, 0x8A, 0x01, 0x01, 0x01,
+    0x05, 0x48, 0x03, 0x82, 0x84, 0x01, 0x01,
+    0x01, 0x84, 0x84, 0x82, 0x00, 0x01, 0x01,
+    0x01, 0x00, 0x00, 0x00, 0x00, 0x01, 0x01,
+    0x01, 0x00, 0x00, 0x00, 0x00, 0x01, 0x01,
+    0x01, 0x0A, 0x1B, 0x8F, 0x03, 0x01, 0x01,
+    0x05, 0x4A, 0x01, 0x8A, 0x01, 0x01, 0x01,
+    0x05, 0x48, 0x83, 0x82, 0x04, 0x01, 0x01,
+    0x01, 0x04, 0x04, 0x02, 0x00, 0x01, 0x01,
+    0x01, 0x00, 0x00, 0x00, 0x00, 0x01, 0x01,
+    0x01, 0x00, 0x00, 0x00, 0x00, 0x01, 0x01,
+    0x01, 0x8A, 0x1B, 0x8F, 0x03, 0x01, 0x01,
+    0x05, 0x4A, 0x01, 0x8A, 0x01, 0x01, 0x01,
+    0x05, 0x48, 0x83, 0x02, 0x04, 0x01, 0x01,
+    0x01, 0x04, 0x04, 0x02, 0x00, 0x01, 0x01,
+    0x01, 0x00, 0x00, 0x00, 0x00, 0x01, 0x01,
+    0x01, 0x00, 0x00, 0x00, 0x00, 0x01, 0x01,
+    0x01, 0x8A, 0x9B, 0x8F, 0x03, 0x01, 0x01,
+    0x05, 0x4A, 0x01, 0x8A, 0x01, 0x01, 0x01,
+    0x05, 0x48, 0x03, 0x42, 0x04, 0x01, 0x01,
+    0x01, 0x04, 0x04, 0x42, 0x00, 0x01, 0x01,
+    0x01, 0x00, 0x00, 0x00, 0x00, 0x01, 0x01,
+    0x01, 0x00, 0x00, 0x00, 0x00, 0x01, 0x01,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x02, 0x00, 0x00, 0x07, 0x17, 0x41, 0xA8,
+    0x32, 0x30,
+];
@@ -0,0 +1,595 @@
+//! A simple Driver for the Waveshare 4.2" E-Ink Display V2 via SPI
+//!
+//! The V2 uses a controller of the SSD168x family and has nothing in common with the
+//! [V1](crate::epd4in2) apart from the resolution. Besides the normal refresh it has a fast
+//! waveform, selected with `set_lut(spi, Some(RefreshLut::Quick))`, and can show 4 gray levels.
+//!
+//! # References
+//!
+//! - [Waveshare product page](https://www.waveshare.com/wiki/4.2inch_e-Paper_Module_Manual)
+//! - [Waveshare C driver](https://github.com/waveshare/e-Paper/blob/master/RaspberryPi_JetsonNano/c/lib/e-Paper/EPD_4in2_V2.c)
+//! - [Waveshare Python driver](https://github.com/waveshare/e-Paper/blob/master/RaspberryPi_JetsonNano/python/lib/waveshare_epd/epd4in2_V2.py)
+//!
+//! # Example for the 4.2" E-Ink Display V2
+//!
+//!```rust, no_run
+//!# use embedded_hal_mock::*;
+//!# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
+//!use embedded_graphics::{
+//!    pixelcolor::BinaryColor::On as Black, prelude::*, primitives::{Line, PrimitiveStyle},
+//!};
+//!use epd_waveshare::{epd4in2_v2::*, prelude::*};
+//!#
+//!# let expectations = [];
+//!# let mut spi = spi::Mock::new(&expectations);
+//!# let expectations = [];
+//!# let cs_pin = pin::Mock::new(&expectations);
+//!# let busy_in = pin::Mock::new(&expectations);
+//!# let dc = pin::Mock::new(&expectations);
+//!# let rst = pin::Mock::new(&expectations);
+//!# let mut delay = delay::MockNoop::new();
+//!
+//!// Setup EPD
+//!let mut epd = Epd4in2::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
+//!
+//!// Use display graphics from embedded-graphics
+//!let mut display = Display4in2::default();
+//!
+//!let _ = Line::new(Point::new(0, 120), Point::new(0, 295))
+//!    .into_styled(PrimitiveStyle::with_stroke(Black, 1))
+//!    .draw(&mut display);
+//!
+//!// Use the fast waveform
+//!epd.set_lut(&mut spi, Some(RefreshLut::Quick))?;
+//!epd.update_and_display_frame(&mut spi, display.buffer(), &mut delay)?;
+//!
+//!// Set the EPD to sleep
+//!epd.sleep(&mut spi, &mut delay)?;
+//!# Ok(())
+//!# }
+//!```
+//!
+//! # 4 gray levels
+//!
+//! Draw into the `Gray2` buffer [`Display4in2Gray`] and upload it with
+//! [`Epd4in2::update_gray_frame`]. Uploading a black/white frame switches back.
+//!
+//!```rust, no_run
+//!# use embedded_hal_mock::*;
+//!# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
+//!use embedded_graphics::{
+//!    pixelcolor::Gray2, prelude::*, primitives::{Line, PrimitiveStyle},
+//!};
+//!use epd_waveshare::{epd4in2_v2::*, prelude::*};
+//!#
+//!# let expectations = [];
+//!# let mut spi = spi::Mock::new(&expectations);
+//!# let expectations = [];
+//!# let cs_pin = pin::Mock::new(&expectations);
+//!# let busy_in = pin::Mock::new(&expectations);
+//!# let dc = pin::Mock::new(&expectations);
+//!# let rst = pin::Mock::new(&expectations);
+//!# let mut delay = delay::MockNoop::new();
+//!
+//!let mut epd = Epd4in2::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
+//!let mut display = Display4in2Gray::default();
+//!
+//!let _ = Line::new(Point::new(10, 120), Point::new(10, 295))
+//!    .into_styled(PrimitiveStyle::with_stroke(Gray2::new(1), 1))
+//!    .draw(&mut display);
+//!
+//!epd.update_gray_frame(&mut spi, display.buffer())?;
+//!epd.display_frame(&mut spi, &mut delay)?;
+//!# Ok(())
+//!# }
+//!```
+
+use embedded_hal::{
+    blocking::{delay::*, spi::Write},
+    digital::v2::*,
+};
+
+use crate::color::Color;
+pub use crate::color::GrayMode;
+use crate::controllers::ssd1680::{Command, DataEntry, IS_BUSY_LOW};
+use crate::interface::DisplayInterface;
+use crate::traits::{
+    DeepSleepMode, InternalWiAdditions, QuickRefresh, RefreshLut, SleepMode, WaveshareDisplay,
+};
+use crate::{buffer_len, span_fits, Error};
+
+mod constants;
+use self::constants::LUT_GRAY4;
+
+#[cfg(feature = "graphics")]
+mod graphics;
+#[cfg(feature = "graphics")]
+pub use self::graphics::{Display4in2, Display4in2Gray};
+
+/// Width of the display
+pub const WIDTH: u32 = 400;
+/// Height of the display
+pub const HEIGHT: u32 = 300;
+/// Default Background Color
+pub const DEFAULT_BACKGROUND_COLOR: Color = Color::White;
+
+/// Number of bytes of a black/white frame
+const NUM_DISPLAY_BYTES: u32 = WIDTH * HEIGHT / 8;
+/// Number of bytes of a frame with 2 bits per pixel
+const NUM_GRAY_BYTES: usize = WIDTH as usize * HEIGHT as usize / 4;
+
+/// Epd4in2 (V2) driver
+///
+pub struct Epd4in2<SPI, CS, BUSY, DC, RST, DELAY> {
+    /// Connection Interface
+    interface: DisplayInterface<SPI, CS, BUSY, DC, RST, DELAY>,
+    /// Background Color
+    color: Color,
+    /// Normal or fast waveform
+    refresh: RefreshLut,
+    /// Mode of the last uploaded frame
+    mode: GrayMode,
+    /// Deep sleep mode of `sleep`
+    sleep_mode: DeepSleepMode,
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> InternalWiAdditions<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd4in2<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.interface.reset(delay, 2);
+        self.init_registers(spi)
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd4in2<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    type DisplayColor = Color;
+    fn new(
+        spi: &mut SPI,
+        cs: CS,
+        busy: BUSY,
+        dc: DC,
+        rst: RST,
+        delay: &mut DELAY,
+    ) -> Result<Self, Error<SPI::Error>> {
+        let interface = DisplayInterface::new(cs, busy, dc, rst);
+
+        let mut epd = Epd4in2 {
+            interface,
+            color: DEFAULT_BACKGROUND_COLOR,
+            refresh: RefreshLut::Full,
+            mode: GrayMode::Gray1,
+            sleep_mode: DeepSleepMode::RetainRam,
+        };
+
+        epd.init(spi, delay)?;
+
+        Ok(epd)
+    }
+
+    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.init(spi, delay)
+    }
+
+    fn sleep(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.interface.sleep_ssd1680(spi, self.sleep_mode)
+    }
+
+    fn update_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        self.use_bw_mode(spi)?;
+
+        // the vendor driver writes the frame into both RAMs
+        self.use_full_frame(spi)?;
+        self.interface
+            .cmd_with_data(spi, Command::WriteRam, buffer)?;
+        self.use_full_frame(spi)?;
+        self.interface
+            .cmd_with_data(spi, Command::WriteRam2, buffer)
+    }
+
+    fn update_partial_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(span_fits(x, width, WIDTH) && span_fits(y, height, HEIGHT));
+        self.wait_until_idle();
+        self.use_bw_mode(spi)?;
+        self.set_ram_area(spi, x, y, x + width - 1, y + height - 1)?;
+        self.interface.cursor_ssd1680(spi, x, y)?;
+
+        self.interface.cmd_with_data(spi, Command::WriteRam, buffer)
+    }
+
+    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.start_display_frame(spi, delay)?;
+        self.wait_until_idle();
+        Ok(())
+    }
+
+    fn start_display_frame(
+        &mut self,
+        spi: &mut SPI,
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        if self.mode == GrayMode::Gray1 {
+            // display_new_frame might have enabled RAM2
+            self.interface
+                .cmd_with_data(spi, Command::DisplayUpdateControl1, &[0x40, 0x00])?;
+        }
+        let mode = match (self.mode, self.refresh) {
+            (GrayMode::Gray4, _) => 0xCF,
+            (GrayMode::Gray1, RefreshLut::Full | RefreshLut::Custom(_)) => 0xF7,
+            // the fast waveform was loaded by init, don't load it from OTP again
+            (GrayMode::Gray1, RefreshLut::Quick) => 0xC7,
+        };
+        self.interface.start_ssd1680(spi, mode)
+    }
+
+    fn update_and_display_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.update_frame(spi, buffer, delay)?;
+        self.display_frame(spi, delay)?;
+        Ok(())
+    }
+
+    fn clear_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        self.use_bw_mode(spi)?;
+
+        let color = self.color.get_byte_value();
+        self.use_full_frame(spi)?;
+        self.interface.cmd(spi, Command::WriteRam)?;
+        self.interface.data_x_times(spi, color, NUM_DISPLAY_BYTES)?;
+        self.use_full_frame(spi)?;
+        self.interface.cmd(spi, Command::WriteRam2)?;
+        self.interface.data_x_times(spi, color, NUM_DISPLAY_BYTES)?;
+
+        self.display_frame(spi, delay)
+    }
+
+    fn set_background_color(&mut self, color: Color) {
+        self.color = color;
+    }
+
+    fn background_color(&self) -> &Color {
+        &self.color
+    }
+
+    fn width(&self) -> u32 {
+        WIDTH
+    }
+
+    fn height(&self) -> u32 {
+        HEIGHT
+    }
+
+    /// Selects the normal (`Full`) or the fast (`Quick`) waveform
+    ///
+    /// Switching resets the controller, so the frame has to be transmitted again.
+    fn set_lut(
+        &mut self,
+        spi: &mut SPI,
+        refresh_rate: Option<RefreshLut>,
+    ) -> Result<(), Error<SPI::Error>> {
+        if let Some(RefreshLut::Custom(_)) = refresh_rate {
+            return Err(Error::Unsupported);
+        }
+        let refresh = refresh_rate.unwrap_or(self.refresh);
+        if refresh == self.refresh {
+            return Ok(());
+        }
+        self.refresh = refresh;
+        self.init_registers(spi)
+    }
+
+    fn is_busy(&self) -> bool {
+        self.interface.is_busy(IS_BUSY_LOW)
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> QuickRefresh<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd4in2<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    /// Sets the base frame the next quick refresh is compared against.
+    fn update_old_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        self.use_bw_mode(spi)?;
+        self.use_full_frame(spi)?;
+        self.interface
+            .cmd_with_data(spi, Command::WriteRam2, buffer)
+    }
+
+    /// Transmits the frame which is shown with the next `display_new_frame`.
+    fn update_new_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        self.use_bw_mode(spi)?;
+        self.use_full_frame(spi)?;
+        self.interface.cmd_with_data(spi, Command::WriteRam, buffer)
+    }
+
+    /// Refreshes the pixels which differ between the old and the new frame.
+    fn display_new_frame(
+        &mut self,
+        spi: &mut SPI,
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        // use the old frame in RAM2 instead of bypassing it
+        self.interface
+            .cmd_with_data(spi, Command::DisplayUpdateControl1, &[0x00, 0x00])?;
+        self.interface.border_ssd1680(spi, 0x80)?;
+        // display mode 2 with the waveform from OTP
+        self.interface.activate_ssd1680(spi, 0xFF)
+    }
+
+    /// Updates and displays the new frame, which becomes the old frame afterwards.
+    fn update_and_display_new_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.update_new_frame(spi, buffer, delay)?;
+        self.display_new_frame(spi, delay)?;
+        self.update_old_frame(spi, buffer, delay)
+    }
+
+    fn update_partial_old_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(span_fits(x, width, WIDTH) && span_fits(y, height, HEIGHT));
+        self.wait_until_idle();
+        self.use_bw_mode(spi)?;
+        self.set_ram_area(spi, x, y, x + width - 1, y + height - 1)?;
+        self.interface.cursor_ssd1680(spi, x, y)?;
+
+        self.interface
+            .cmd_with_data(spi, Command::WriteRam2, buffer)
+    }
+
+    fn update_partial_new_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(span_fits(x, width, WIDTH) && span_fits(y, height, HEIGHT));
+        self.wait_until_idle();
+        self.use_bw_mode(spi)?;
+        self.set_ram_area(spi, x, y, x + width - 1, y + height - 1)?;
+        self.interface.cursor_ssd1680(spi, x, y)?;
+
+        self.interface.cmd_with_data(spi, Command::WriteRam, buffer)
+    }
+
+    fn clear_partial_frame(
+        &mut self,
+        spi: &mut SPI,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(span_fits(x, width, WIDTH) && span_fits(y, height, HEIGHT));
+        self.wait_until_idle();
+        self.use_bw_mode(spi)?;
+        let color = self.color.get_byte_value();
+        let len = buffer_len(width as usize, height as usize) as u32;
+
+        for command in [Command::WriteRam, Command::WriteRam2] {
+            self.set_ram_area(spi, x, y, x + width - 1, y + height - 1)?;
+            self.interface.cursor_ssd1680(spi, x, y)?;
+            self.interface.cmd(spi, command)?;
+            self.interface.data_x_times(spi, color, len)?;
+        }
+        Ok(())
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> Epd4in2<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    /// Uploads a frame with 4 gray levels and switches the display into `GrayMode::Gray4`
+    ///
+    /// The buffer holds 2 bits per pixel, see [`Display4in2Gray`].
+    pub fn update_gray_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(buffer.len() == NUM_GRAY_BYTES);
+        self.wait_until_idle();
+        self.mode = GrayMode::Gray4;
+
+        self.interface
+            .cmd_with_data(spi, Command::DisplayUpdateControl1, &[0x00, 0x00])?;
+        self.interface.border_ssd1680(spi, 0x03)?;
+        self.interface.cmd_with_data(
+            spi,
+            Command::BoosterSoftStartControl,
+            &[0x8B, 0x9C, 0xA4, 0x0F],
+        )?;
+        self.set_gray_lut(spi)?;
+
+        // a set bit is a dark pixel: the low bit of every pixel goes into the first RAM,
+        // the high bit into the second
+        self.use_full_frame(spi)?;
+        self.interface.cmd(spi, Command::WriteRam)?;
+        self.interface.data_gray_plane(spi, buffer, 0b01, true)?;
+        self.use_full_frame(spi)?;
+        self.interface.cmd(spi, Command::WriteRam2)?;
+        self.interface.data_gray_plane(spi, buffer, 0b10, true)
+    }
+
+    /// Mode of the last uploaded frame
+    pub fn gray_mode(&self) -> GrayMode {
+        self.mode
+    }
+
+    /// Sets up the controller after a reset, including the waveform of `self.refresh`
+    fn init_registers(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        self.interface.sw_reset_ssd1680(spi)?;
+
+        self.interface
+            .cmd_with_data(spi, Command::DisplayUpdateControl1, &[0x40, 0x00])?;
+        self.interface.border_ssd1680(spi, 0x05)?;
+
+        if self.refresh == RefreshLut::Quick {
+            // Load the waveform for the temperature the fast refresh was made for
+            self.interface
+                .cmd_with_data(spi, Command::TemperatureSensorControlWrite, &[0x5A])?;
+            self.interface.activate_ssd1680(spi, 0x91)?;
+        }
+
+        self.interface
+            .data_entry_ssd1680(spi, DataEntry::IncrementBoth)?;
+        self.use_full_frame(spi)?;
+
+        self.wait_until_idle();
+        self.mode = GrayMode::Gray1;
+        Ok(())
+    }
+
+    /// Switches back to black/white frames, the gray mode changed several registers
+    fn use_bw_mode(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        if self.mode == GrayMode::Gray4 {
+            self.init_registers(spi)?;
+        }
+        Ok(())
+    }
+
+    fn set_gray_lut(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        let lut = &LUT_GRAY4;
+        self.interface
+            .cmd_with_data(spi, Command::WriteLutRegister, &lut[..227])?;
+        self.interface
+            .cmd_with_data(spi, Command::EndOption, &lut[227..228])?;
+        self.interface
+            .cmd_with_data(spi, Command::GateDrivingVoltageControl, &lut[228..229])?;
+        self.interface
+            .cmd_with_data(spi, Command::SourceDrivingVoltageControl, &lut[229..232])?;
+        self.interface
+            .cmd_with_data(spi, Command::WriteVcomRegister, &lut[232..233])
+    }
+
+    fn wait_until_idle(&mut self) {
+        self.interface.wait_until_idle(IS_BUSY_LOW);
+    }
+
+    fn use_full_frame(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        // choose full frame/ram
+        self.set_ram_area(spi, 0, 0, WIDTH - 1, HEIGHT - 1)?;
+
+        // start from the beginning
+        self.interface.cursor_ssd1680(spi, 0, 0)
+    }
+
+    fn set_ram_area(
+        &mut self,
+        spi: &mut SPI,
+        start_x: u32,
+        start_y: u32,
+        end_x: u32,
+        end_y: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.interface
+            .window_ssd1680(spi, start_x, start_y, end_x, end_y)
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> SleepMode<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd4in2<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn set_sleep_mode(&mut self, mode: DeepSleepMode) {
+        self.sleep_mode = mode;
+    }
+
+    fn sleep_mode(&self) -> DeepSleepMode {
+        self.sleep_mode
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn epd_size() {
+        assert_eq!(WIDTH, 400);
+        assert_eq!(HEIGHT, 300);
+        assert_eq!(NUM_DISPLAY_BYTES, 15000);
+        assert_eq!(NUM_GRAY_BYTES, 30000);
+        assert_eq!(DEFAULT_BACKGROUND_COLOR, Color::White);
+    }
+}
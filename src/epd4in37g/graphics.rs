@@ -0,0 +1,101 @@
+use crate::color::QuadColor;
+use crate::epd4in37g::{DEFAULT_BACKGROUND_COLOR, HEIGHT, WIDTH};
+use crate::graphics::{DisplayRotation, QuadDisplay};
+use embedded_graphics_core::prelude::*;
+
+/// Full size buffer for use with the 4in37g EPD
+///
+/// Can also be manually constructed:
+/// `buffer: [DEFAULT_BACKGROUND_COLOR.get_byte_value(); WIDTH / 4 * HEIGHT]`
+pub struct Display4in37g {
+    buffer: [u8; WIDTH as usize * HEIGHT as usize / 4],
+    rotation: DisplayRotation,
+}
+
+impl Default for Display4in37g {
+    fn default() -> Self {
+        Display4in37g {
+            buffer: [DEFAULT_BACKGROUND_COLOR.get_byte_value();
+                WIDTH as usize * HEIGHT as usize / 4],
+            rotation: DisplayRotation::default(),
+        }
+    }
+}
+
+impl DrawTarget for Display4in37g {
+    type Color = QuadColor;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for pixel in pixels {
+            self.draw_helper(WIDTH, HEIGHT, pixel)?;
+        }
+        Ok(())
+    }
+}
+
+impl OriginDimensions for Display4in37g {
+    fn size(&self) -> Size {
+        Size::new(WIDTH, HEIGHT)
+    }
+}
+
+impl QuadDisplay for Display4in37g {
+    fn buffer(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    fn get_mut_buffer(&mut self) -> &mut [u8] {
+        &mut self.buffer
+    }
+
+    fn set_rotation(&mut self, rotation: DisplayRotation) {
+        self.rotation = rotation;
+    }
+
+    fn rotation(&self) -> DisplayRotation {
+        self.rotation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::epd4in37g;
+
+    // test buffer length
+    #[test]
+    fn graphics_size() {
+        let display = Display4in37g::default();
+        assert_eq!(display.buffer().len(), 47104);
+    }
+
+    // test default background color on all bytes
+    #[test]
+    fn graphics_default() {
+        let display = Display4in37g::default();
+        for &byte in display.buffer() {
+            assert_eq!(byte, epd4in37g::DEFAULT_BACKGROUND_COLOR.get_byte_value());
+        }
+    }
+
+    #[test]
+    fn graphics_colors() {
+        let mut display = Display4in37g::default();
+        let colors = [
+            QuadColor::Red,
+            QuadColor::Yellow,
+            QuadColor::White,
+            QuadColor::Black,
+        ];
+        for (x, &color) in colors.iter().enumerate() {
+            let _ = Pixel(Point::new(x as i32, 1), color).draw(&mut display);
+        }
+        let row = WIDTH as usize / 4;
+        assert_eq!(display.buffer()[row], 0b11_10_01_00);
+        assert_eq!(display.buffer()[row + 1], QuadColor::White.get_byte_value());
+    }
+}
@@ -0,0 +1,93 @@
+//! SPI Commands for the Waveshare 5.79" E-Ink Display (SSD1683 pair)
+
+use crate::traits;
+
+/// Epd5in79 commands
+///
+/// The panel is driven by two cascaded controllers. Commands with the highest bit set
+/// only reach the second (slave) controller, which drives the right half of the screen.
+///
+/// Should rarely (never?) be needed directly.
+///
+/// For more infos about the addresses and what they are doing look into the pdfs
+#[allow(dead_code)]
+#[derive(Copy, Clone)]
+pub(crate) enum Command {
+    /// Set the number of gate lines, and the scanning sequence and direction
+    DriverOutputControl = 0x01,
+    /// Enter deep sleep mode
+    ///
+    /// 0x00 for Normal mode, 0x01 for Deep Sleep Mode 1 (RAM is kept) and
+    /// 0x03 for Deep Sleep Mode 2 (RAM is lost)
+    DeepSleepMode = 0x10,
+    /// Define the data entry sequence of the master (x/y increment/decrement, update direction)
+    DataEntryModeSetting = 0x11,
+    /// Reset all commands and parameters of both controllers to their default values
+    SwReset = 0x12,
+    /// Select the internal or an external temperature sensor
+    TemperatureSensorSelection = 0x18,
+    /// Write to the temperature register
+    TemperatureSensorControl = 0x1A,
+    /// Activate the display update sequence set with `DisplayUpdateControl2`
+    ///
+    /// The BUSY pin is high until the update sequence has finished.
+    MasterActivation = 0x20,
+    /// RAM content options for the display update
+    DisplayUpdateControl1 = 0x21,
+    /// Display update sequence options
+    DisplayUpdateControl2 = 0x22,
+    /// Write to the black/white RAM of the master, 1 is white
+    WriteRam = 0x24,
+    /// Write to the red RAM of the master, used as old frame for partial refreshes
+    WriteRam2 = 0x26,
+    /// Select the border waveform
+    BorderWaveformControl = 0x3C,
+    /// Specify the start/end positions of the window address in the X direction (bytes)
+    SetRamXAddressStartEndPosition = 0x44,
+    /// Specify the start/end positions of the window address in the Y direction
+    SetRamYAddressStartEndPosition = 0x45,
+    /// Make the initial settings for the RAM X address in the address counter
+    SetRamXAddressCounter = 0x4E,
+    /// Make the initial settings for the RAM Y address in the address counter
+    SetRamYAddressCounter = 0x4F,
+    /// `DataEntryModeSetting` of the slave
+    SlaveDataEntryModeSetting = 0x91,
+    /// `WriteRam` of the slave
+    SlaveWriteRam = 0xA4,
+    /// `WriteRam2` of the slave
+    SlaveWriteRam2 = 0xA6,
+    /// `SetRamXAddressStartEndPosition` of the slave
+    SlaveSetRamXAddressStartEndPosition = 0xC4,
+    /// `SetRamYAddressStartEndPosition` of the slave
+    SlaveSetRamYAddressStartEndPosition = 0xC5,
+    /// `SetRamXAddressCounter` of the slave
+    SlaveSetRamXAddressCounter = 0xCE,
+    /// `SetRamYAddressCounter` of the slave
+    SlaveSetRamYAddressCounter = 0xCF,
+    /// Does nothing, can be used to terminate a frame memory write or read
+    Nop = 0x7F,
+}
+
+impl traits::Command for Command {
+    /// Returns the address of the command
+    fn address(self) -> u8 {
+        self as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Command;
+    use crate::traits::Command as CommandTrait;
+
+    #[test]
+    fn command_addr() {
+        assert_eq!(Command::DriverOutputControl.address(), 0x01);
+        assert_eq!(Command::SetRamXAddressCounter.address(), 0x4E);
+        assert_eq!(
+            Command::SlaveSetRamXAddressCounter.address(),
+            Command::SetRamXAddressCounter.address() | 0x80
+        );
+        assert_eq!(Command::Nop.address(), 0x7F);
+    }
+}
@@ -0,0 +1,505 @@
+//! A simple Driver for the Waveshare 5.79" E-Ink Display via SPI
+//!
+//! The panel is driven by two cascaded SSD1683 controllers sharing the SPI bus. Each of
+//! them covers one half of the screen with its own RAM, the right half (slave) is mounted
+//! mirrored and addressed with its own set of commands. The driver splits every frame
+//! between both controllers, so the display is used like any other one with a single
+//! 792x272 framebuffer, see [`Display5in79`].
+//!
+//! # References
+//!
+//! - [Waveshare product page](https://www.waveshare.com/wiki/5.79inch_e-Paper_Module_Manual)
+//! - [Waveshare C driver](https://github.com/waveshare/e-Paper/blob/master/RaspberryPi_JetsonNano/c/lib/e-Paper/EPD_5in79.c)
+//! - [Waveshare Python driver](https://github.com/waveshare/e-Paper/blob/master/RaspberryPi_JetsonNano/python/lib/waveshare_epd/epd5in79.py)
+//!
+//! # Example for the 5.79" E-Ink Display
+//!
+//!```rust, no_run
+//!# use embedded_hal_mock::*;
+//!# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
+//!use embedded_graphics::{
+//!    pixelcolor::BinaryColor::On as Black, prelude::*, primitives::{Line, PrimitiveStyle},
+//!};
+//!use epd_waveshare::{epd5in79::*, prelude::*};
+//!#
+//!# let expectations = [];
+//!# let mut spi = spi::Mock::new(&expectations);
+//!# let expectations = [];
+//!# let cs_pin = pin::Mock::new(&expectations);
+//!# let busy_in = pin::Mock::new(&expectations);
+//!# let dc = pin::Mock::new(&expectations);
+//!# let rst = pin::Mock::new(&expectations);
+//!# let mut delay = delay::MockNoop::new();
+//!
+//!// Setup EPD
+//!let mut epd = Epd5in79::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
+//!
+//!// Use display graphics from embedded-graphics
+//!let mut display = Display5in79::default();
+//!
+//!// a line across both halves of the screen
+//!let _ = Line::new(Point::new(0, 136), Point::new(791, 136))
+//!    .into_styled(PrimitiveStyle::with_stroke(Black, 1))
+//!    .draw(&mut display);
+//!
+//!// Display updated frame
+//!epd.update_and_display_frame(&mut spi, display.buffer(), &mut delay)?;
+//!
+//!// Set the EPD to sleep
+//!epd.sleep(&mut spi, &mut delay)?;
+//!# Ok(())
+//!# }
+//!```
+
+use embedded_hal::{
+    blocking::{delay::*, spi::Write},
+    digital::v2::*,
+};
+
+use crate::color::Color;
+use crate::interface::DisplayInterface;
+use crate::traits::{DeepSleepMode, InternalWiAdditions, RefreshLut, SleepMode, WaveshareDisplay};
+use crate::{span_fits, Error};
+
+pub(crate) mod command;
+use self::command::Command;
+
+#[cfg(feature = "graphics")]
+mod graphics;
+#[cfg(feature = "graphics")]
+pub use self::graphics::Display5in79;
+
+/// Width of the display
+pub const WIDTH: u32 = 792;
+/// Height of the display
+pub const HEIGHT: u32 = 272;
+/// Default Background Color
+pub const DEFAULT_BACKGROUND_COLOR: Color = Color::White;
+const IS_BUSY_LOW: bool = false;
+
+/// Number of bytes of a frame
+const NUM_DISPLAY_BYTES: u32 = WIDTH * HEIGHT / 8;
+/// Bytes in the RAM row of one controller
+const HALF_BYTES: u32 = 50;
+/// First byte of a framebuffer row shown by the slave
+///
+/// The halves are 396 pixels wide, the byte around the split is sent to both controllers.
+const SLAVE_FIRST_BYTE: u32 = WIDTH / 8 - HALF_BYTES;
+
+/// One of the two controllers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Half {
+    /// Left half
+    Master,
+    /// Right half, mounted mirrored
+    Slave,
+}
+
+impl Half {
+    /// Bytes of a framebuffer row shown by this half
+    fn bytes(self) -> (u32, u32) {
+        match self {
+            Half::Master => (0, HALF_BYTES - 1),
+            Half::Slave => (SLAVE_FIRST_BYTE, WIDTH / 8 - 1),
+        }
+    }
+
+    /// RAM x address of byte `byte` of a framebuffer row
+    fn ram_x(self, byte: u32) -> u32 {
+        match self {
+            Half::Master => byte,
+            // x counts down on the slave
+            Half::Slave => HALF_BYTES - 1 - (byte - SLAVE_FIRST_BYTE),
+        }
+    }
+
+    /// Bytes `first..=last` of a framebuffer row clipped to this half
+    fn clip(self, first: u32, last: u32) -> Option<(u32, u32)> {
+        let (start, end) = self.bytes();
+        let (first, last) = (first.max(start), last.min(end));
+        if first <= last {
+            Some((first, last))
+        } else {
+            None
+        }
+    }
+
+    fn write_ram(self) -> Command {
+        match self {
+            Half::Master => Command::WriteRam,
+            Half::Slave => Command::SlaveWriteRam,
+        }
+    }
+
+    fn write_ram2(self) -> Command {
+        match self {
+            Half::Master => Command::WriteRam2,
+            Half::Slave => Command::SlaveWriteRam2,
+        }
+    }
+}
+
+/// Epd5in79 driver
+///
+pub struct Epd5in79<SPI, CS, BUSY, DC, RST, DELAY> {
+    /// Connection Interface
+    interface: DisplayInterface<SPI, CS, BUSY, DC, RST, DELAY>,
+    /// Background Color
+    color: Color,
+    /// Normal or fast waveform
+    refresh: RefreshLut,
+    /// Deep sleep mode of `sleep`
+    sleep_mode: DeepSleepMode,
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> InternalWiAdditions<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd5in79<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.interface.reset(delay, 2);
+        self.init_registers(spi)
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd5in79<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    type DisplayColor = Color;
+    fn new(
+        spi: &mut SPI,
+        cs: CS,
+        busy: BUSY,
+        dc: DC,
+        rst: RST,
+        delay: &mut DELAY,
+    ) -> Result<Self, Error<SPI::Error>> {
+        let interface = DisplayInterface::new(cs, busy, dc, rst);
+
+        let mut epd = Epd5in79 {
+            interface,
+            color: DEFAULT_BACKGROUND_COLOR,
+            refresh: RefreshLut::Full,
+            sleep_mode: DeepSleepMode::RetainRam,
+        };
+
+        epd.init(spi, delay)?;
+
+        Ok(epd)
+    }
+
+    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.init(spi, delay)
+    }
+
+    fn sleep(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        self.interface
+            .cmd_with_data(spi, Command::DeepSleepMode, &[self.sleep_mode as u8])
+    }
+
+    fn update_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(buffer.len() == NUM_DISPLAY_BYTES as usize);
+        self.wait_until_idle();
+        self.write_window(spi, buffer, 0, 0, WIDTH, HEIGHT)
+    }
+
+    /// Transmits a window of the frame
+    ///
+    /// `x` and `width` have to be multiples of 8. Windows crossing the middle of the
+    /// screen are split between both controllers.
+    fn update_partial_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(x.is_multiple_of(8) && width.is_multiple_of(8));
+        ensure!(buffer.len() == (width / 8 * height) as usize);
+        self.wait_until_idle();
+        self.write_window(spi, buffer, x, y, width, height)
+    }
+
+    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.start_display_frame(spi, delay)?;
+        self.wait_until_idle();
+        Ok(())
+    }
+
+    fn start_display_frame(
+        &mut self,
+        spi: &mut SPI,
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        let mode = match self.refresh {
+            RefreshLut::Full | RefreshLut::Custom(_) => 0xF7,
+            // the fast waveform was loaded by init, don't load it from OTP again
+            RefreshLut::Quick => 0xC7,
+        };
+        self.interface
+            .cmd_with_data(spi, Command::DisplayUpdateControl2, &[mode])?;
+        self.interface.cmd(spi, Command::MasterActivation)?;
+        Ok(())
+    }
+
+    fn update_and_display_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.update_frame(spi, buffer, delay)?;
+        self.display_frame(spi, delay)?;
+        Ok(())
+    }
+
+    fn clear_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+
+        let color = self.color.get_byte_value();
+        for half in [Half::Master, Half::Slave] {
+            let (first, last) = half.bytes();
+            for command in [half.write_ram(), half.write_ram2()] {
+                self.set_ram_area(spi, half, first, last, 0, HEIGHT - 1)?;
+                self.interface.cmd(spi, command)?;
+                self.interface
+                    .data_x_times(spi, color, HALF_BYTES * HEIGHT)?;
+            }
+        }
+
+        self.display_frame(spi, delay)
+    }
+
+    fn set_background_color(&mut self, color: Color) {
+        self.color = color;
+    }
+
+    fn background_color(&self) -> &Color {
+        &self.color
+    }
+
+    fn width(&self) -> u32 {
+        WIDTH
+    }
+
+    fn height(&self) -> u32 {
+        HEIGHT
+    }
+
+    /// Selects the normal (`Full`) or the fast (`Quick`) waveform
+    ///
+    /// Switching resets the controllers, so the frame has to be transmitted again.
+    fn set_lut(
+        &mut self,
+        spi: &mut SPI,
+        refresh_rate: Option<RefreshLut>,
+    ) -> Result<(), Error<SPI::Error>> {
+        if let Some(RefreshLut::Custom(_)) = refresh_rate {
+            return Err(Error::Unsupported);
+        }
+        let refresh = refresh_rate.unwrap_or(self.refresh);
+        if refresh == self.refresh {
+            return Ok(());
+        }
+        self.refresh = refresh;
+        self.init_registers(spi)
+    }
+
+    fn is_busy(&self) -> bool {
+        self.interface.is_busy(IS_BUSY_LOW)
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> Epd5in79<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    /// Sets up both controllers after a reset
+    fn init_registers(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle();
+        self.interface.cmd(spi, Command::SwReset)?;
+        self.wait_until_idle();
+
+        self.interface
+            .cmd_with_data(spi, Command::BorderWaveformControl, &[0x01])?;
+        // use the internal temperature sensor
+        self.interface
+            .cmd_with_data(spi, Command::TemperatureSensorSelection, &[0x80])?;
+
+        // x increment on the master, x decrement on the mirrored slave, y decrement on both
+        self.interface
+            .cmd_with_data(spi, Command::DataEntryModeSetting, &[0x01])?;
+        self.interface
+            .cmd_with_data(spi, Command::SlaveDataEntryModeSetting, &[0x00])?;
+        self.wait_until_idle();
+
+        if self.refresh == RefreshLut::Quick {
+            // load the waveform for a fixed temperature instead of the measured one
+            self.interface
+                .cmd_with_data(spi, Command::TemperatureSensorControl, &[0x5A])?;
+            self.interface
+                .cmd_with_data(spi, Command::DisplayUpdateControl2, &[0x91])?;
+            self.interface.cmd(spi, Command::MasterActivation)?;
+            self.wait_until_idle();
+        }
+        Ok(())
+    }
+
+    fn wait_until_idle(&mut self) {
+        self.interface.wait_until_idle(IS_BUSY_LOW);
+    }
+
+    /// Sends the rows of a window of the framebuffer to the halves it covers
+    fn write_window(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(span_fits(x, width, WIDTH) && span_fits(y, height, HEIGHT));
+        let row_len = (width / 8) as usize;
+        let (first, last) = (x / 8, (x + width) / 8 - 1);
+
+        for half in [Half::Master, Half::Slave] {
+            let (start, end) = match half.clip(first, last) {
+                Some(bytes) => bytes,
+                None => continue,
+            };
+            self.set_ram_area(spi, half, start, end, y, y + height - 1)?;
+            self.interface.cmd(spi, half.write_ram())?;
+
+            let (start, end) = ((start - first) as usize, (end - first) as usize);
+            for row in buffer.chunks(row_len) {
+                self.interface.data(spi, &row[start..=end])?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets the RAM window of one half to the bytes `first..=last` of the framebuffer
+    /// rows `start_y..=end_y` and moves the address counter to its start
+    ///
+    /// The rows are stored bottom up on both halves.
+    fn set_ram_area(
+        &mut self,
+        spi: &mut SPI,
+        half: Half,
+        first: u32,
+        last: u32,
+        start_y: u32,
+        end_y: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        let (area_x, area_y, counter_x, counter_y) = match half {
+            Half::Master => (
+                Command::SetRamXAddressStartEndPosition,
+                Command::SetRamYAddressStartEndPosition,
+                Command::SetRamXAddressCounter,
+                Command::SetRamYAddressCounter,
+            ),
+            Half::Slave => (
+                Command::SlaveSetRamXAddressStartEndPosition,
+                Command::SlaveSetRamYAddressStartEndPosition,
+                Command::SlaveSetRamXAddressCounter,
+                Command::SlaveSetRamYAddressCounter,
+            ),
+        };
+        let (start_x, end_x) = (half.ram_x(first), half.ram_x(last));
+        let (start_y, end_y) = (HEIGHT - 1 - start_y, HEIGHT - 1 - end_y);
+
+        self.wait_until_idle();
+        self.interface
+            .cmd_with_data(spi, area_x, &[start_x as u8, end_x as u8])?;
+        self.interface.cmd_with_data(
+            spi,
+            area_y,
+            &[
+                start_y as u8,
+                (start_y >> 8) as u8,
+                end_y as u8,
+                (end_y >> 8) as u8,
+            ],
+        )?;
+        self.interface
+            .cmd_with_data(spi, counter_x, &[start_x as u8])?;
+        self.interface
+            .cmd_with_data(spi, counter_y, &[start_y as u8, (start_y >> 8) as u8])
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> SleepMode<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd5in79<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn set_sleep_mode(&mut self, mode: DeepSleepMode) {
+        self.sleep_mode = mode;
+    }
+
+    fn sleep_mode(&self) -> DeepSleepMode {
+        self.sleep_mode
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn epd_size() {
+        assert_eq!(WIDTH, 792);
+        assert_eq!(HEIGHT, 272);
+        assert_eq!(NUM_DISPLAY_BYTES, 26928);
+        assert_eq!(DEFAULT_BACKGROUND_COLOR, Color::White);
+    }
+
+    #[test]
+    fn halves() {
+        // both halves overlap in the byte around the split
+        assert_eq!(Half::Master.bytes(), (0, 49));
+        assert_eq!(Half::Slave.bytes(), (49, 98));
+
+        // the slave is mirrored
+        assert_eq!(Half::Master.ram_x(0), 0);
+        assert_eq!(Half::Slave.ram_x(49), 49);
+        assert_eq!(Half::Slave.ram_x(98), 0);
+
+        assert_eq!(Half::Master.clip(40, 60), Some((40, 49)));
+        assert_eq!(Half::Slave.clip(40, 60), Some((49, 60)));
+        assert_eq!(Half::Slave.clip(0, 10), None);
+    }
+}
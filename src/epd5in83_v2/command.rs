@@ -1,8 +1,8 @@
-//! SPI Commands for the Waveshare 7.5" E-Ink Display
+//! SPI Commands for the Waveshare 5.83" V2 E-Ink Display
 
 use crate::traits;
 
-/// Epd7in5 commands
+/// Epd5in83 (V2) commands
 ///
 /// Should rarely (never?) be needed directly.
 ///
@@ -128,9 +128,17 @@ pub(crate) enum Command {
     ReadVcomValue = 0x81,
     /// This command sets `VCOM_DC` value.
     VcmDcSetting = 0x82,
-    // /// This is in all the Waveshare controllers for Epd7in5, but it's not documented
-    // /// anywhere in the datasheet `¯\_(ツ)_/¯`
-    // FlashMode = 0xE5,
+    /// Sets the window used by partial refreshes
+    PartialWindow = 0x90,
+    /// Enters partial mode, only the window is written and refreshed
+    PartialIn = 0x91,
+    /// Leaves partial mode
+    PartialOut = 0x92,
+    /// This command sets the clock output and synchronization of cascaded controllers.
+    CascadeSetting = 0xE0,
+    /// This command sets the temperature the waveform is chosen for, once the cascade
+    /// setting fixes it instead of using the sensor.
+    ForceTemperature = 0xE5,
 }
 
 impl traits::Command for Command {
@@ -149,5 +157,6 @@ mod tests {
     fn command_addr() {
         assert_eq!(Command::PanelSetting.address(), 0x00);
         assert_eq!(Command::DisplayRefresh.address(), 0x12);
+        assert_eq!(Command::ForceTemperature.address(), 0xE5);
     }
 }
@@ -0,0 +1,493 @@
+//! A simple Driver for the Waveshare 5.83" E-Ink Display (V2) via SPI
+//!
+//! # References
+//!
+//! - [Datasheet](https://www.waveshare.com/wiki/5.83inch_e-Paper_HAT_Manual)
+//! - [Waveshare C driver](https://github.com/waveshare/e-Paper/blob/master/RaspberryPi_JetsonNano/c/lib/e-Paper/EPD_5in83_V2.c)
+//! - [Waveshare Python driver](https://github.com/waveshare/e-Paper/blob/master/RaspberryPi_JetsonNano/python/lib/waveshare_epd/epd5in83_V2.py)
+//!
+//! Important note for V2:
+//! The resolution of V2 is 648×480 instead of the 600×448 of V1 and it uses a different
+//! controller. V2 boards hang in the power on sequence of V1, so make sure to pick the
+//! driver matching the sticker on the back of the display.
+//!
+//! # Partial refresh
+//!
+//! [`update_partial_frame`](WaveshareDisplay::update_partial_frame) puts the controller
+//! into partial mode, and the next [`display_frame`](WaveshareDisplay::display_frame)
+//! refreshes only that window with the fast waveform of the vendor driver, in well under
+//! a second instead of the several seconds of a full refresh. It waits for the refresh,
+//! and the next full frame leaves partial mode again. The window has to start at a
+//! multiple of 8 and be a multiple of 8 wide. The fast waveform leaves ghosting behind,
+//! so do a full refresh from time to time.
+//!
+//! # Example for the 5.83" V2 E-Ink Display
+//!
+//!```rust, no_run
+//!# use embedded_hal_mock::*;
+//!# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
+//!use embedded_graphics::{
+//!    pixelcolor::BinaryColor::On as Black, prelude::*, primitives::{Line, PrimitiveStyle},
+//!};
+//!use epd_waveshare::{epd5in83_v2::*, prelude::*};
+//!#
+//!# let expectations = [];
+//!# let mut spi = spi::Mock::new(&expectations);
+//!# let expectations = [];
+//!# let cs_pin = pin::Mock::new(&expectations);
+//!# let busy_in = pin::Mock::new(&expectations);
+//!# let dc = pin::Mock::new(&expectations);
+//!# let rst = pin::Mock::new(&expectations);
+//!# let mut delay = delay::MockNoop::new();
+//!
+//!// Setup EPD
+//!let mut epd = Epd5in83::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
+//!
+//!// Use display graphics from embedded-graphics
+//!let mut display = Display5in83::default();
+//!
+//!let _ = Line::new(Point::new(0, 120), Point::new(0, 295))
+//!    .into_styled(PrimitiveStyle::with_stroke(Black, 1))
+//!    .draw(&mut display);
+//!
+//!// Display updated frame
+//!epd.update_and_display_frame(&mut spi, display.buffer(), &mut delay)?;
+//!
+//!// Set the EPD to sleep
+//!epd.sleep(&mut spi, &mut delay)?;
+//!# Ok(())
+//!# }
+//!```
+
+use embedded_hal::{
+    blocking::{delay::*, spi::Write},
+    digital::v2::{InputPin, OutputPin},
+};
+
+use crate::color::Color;
+use crate::interface::DisplayInterface;
+use crate::traits::{InternalWiAdditions, PowerDown, RefreshLut, WaveshareDisplay};
+use crate::{span_fits, Error};
+
+pub(crate) mod command;
+use self::command::Command;
+
+#[cfg(feature = "graphics")]
+mod graphics;
+#[cfg(feature = "graphics")]
+pub use self::graphics::Display5in83;
+
+/// Width of the display
+pub const WIDTH: u32 = 648;
+/// Height of the display
+pub const HEIGHT: u32 = 480;
+/// Default Background Color
+pub const DEFAULT_BACKGROUND_COLOR: Color = Color::White;
+const IS_BUSY_LOW: bool = true;
+
+/// Number of bytes of a frame
+const NUM_DISPLAY_BYTES: u32 = WIDTH * HEIGHT / 8;
+
+/// Epd5in83 (V2) driver
+///
+pub struct Epd5in83<SPI, CS, BUSY, DC, RST, DELAY> {
+    /// Connection Interface
+    interface: DisplayInterface<SPI, CS, BUSY, DC, RST, DELAY>,
+    /// Background Color
+    color: Color,
+    /// Whether the controller is in partial mode, waiting for the window to be refreshed
+    partial: bool,
+    /// Whether the drivers were switched off after the last refresh
+    powered_down: bool,
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> InternalWiAdditions<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd5in83<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        // Reset the device
+        self.interface.reset(delay, 2);
+        self.partial = false;
+        self.powered_down = false;
+
+        // VGH=20V, VGL=-20V, VDH=15V, VDL=-15V
+        self.cmd_with_data(spi, Command::PowerSetting, &[0x07, 0x07, 0x3F, 0x3F])?;
+        self.command(spi, Command::PowerOn)?;
+        delay.delay_ms(100);
+        self.wait_until_idle(spi, delay)?;
+
+        // KW mode, waveform from OTP
+        self.cmd_with_data(spi, Command::PanelSetting, &[0x1F])?;
+        self.send_resolution(spi)?;
+        self.cmd_with_data(spi, Command::DualSpi, &[0x00])?;
+        self.cmd_with_data(spi, Command::TconSetting, &[0x22])?;
+        self.cmd_with_data(spi, Command::VcomAndDataIntervalSetting, &[0x10, 0x07])?;
+        self.wait_until_idle(spi, delay)?;
+        Ok(())
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd5in83<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    type DisplayColor = Color;
+    fn new(
+        spi: &mut SPI,
+        cs: CS,
+        busy: BUSY,
+        dc: DC,
+        rst: RST,
+        delay: &mut DELAY,
+    ) -> Result<Self, Error<SPI::Error>> {
+        let interface = DisplayInterface::new(cs, busy, dc, rst);
+        let color = DEFAULT_BACKGROUND_COLOR;
+
+        let mut epd = Epd5in83 {
+            interface,
+            color,
+            partial: false,
+            powered_down: false,
+        };
+
+        epd.init(spi, delay)?;
+
+        Ok(epd)
+    }
+
+    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.init(spi, delay)
+    }
+
+    fn sleep(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.leave_partial(spi, delay)?;
+        self.command(spi, Command::PowerOff)?;
+        self.wait_until_idle(spi, delay)?;
+        self.cmd_with_data(spi, Command::DeepSleep, &[0xA5])?;
+        Ok(())
+    }
+
+    fn update_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.leave_partial(spi, delay)?;
+        // the controller expects a set bit for a black pixel
+        self.command(spi, Command::DataStartTransmission2)?;
+        self.interface.data_inverted(spi, buffer)
+    }
+
+    /// Transmits a window of the frame and enters partial mode, see the
+    /// [module documentation](self#partial-refresh)
+    fn update_partial_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(window_fits(x, y, width, height));
+        ensure!(buffer.len() as u32 == width / 8 * height);
+        self.enter_partial(spi, x, y, width, height)?;
+        self.command(spi, Command::DataStartTransmission2)?;
+        self.interface.data_inverted(spi, buffer)
+    }
+
+    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.start_display_frame(spi, delay)?;
+        if self.partial {
+            // the window has to be refreshed before partial mode is left
+            self.leave_partial(spi, delay)?;
+        }
+        Ok(())
+    }
+
+    fn start_display_frame(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle(spi, delay)?;
+        self.start_refresh(spi, delay)
+    }
+
+    fn finish_refresh(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.leave_partial(spi, delay)
+    }
+
+    fn update_and_display_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.update_frame(spi, buffer, delay)?;
+        self.start_refresh(spi, delay)?;
+        Ok(())
+    }
+
+    fn clear_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.leave_partial(spi, delay)?;
+        self.send_resolution(spi)?;
+
+        let color = !self.color.get_byte_value();
+        self.command(spi, Command::DataStartTransmission1)?;
+        self.interface.data_x_times(spi, color, NUM_DISPLAY_BYTES)?;
+
+        self.command(spi, Command::DataStartTransmission2)?;
+        self.interface.data_x_times(spi, color, NUM_DISPLAY_BYTES)?;
+
+        self.start_refresh(spi, delay)?;
+        Ok(())
+    }
+
+    fn set_background_color(&mut self, color: Color) {
+        self.color = color;
+    }
+
+    fn background_color(&self) -> &Color {
+        &self.color
+    }
+
+    fn width(&self) -> u32 {
+        WIDTH
+    }
+
+    fn height(&self) -> u32 {
+        HEIGHT
+    }
+
+    fn set_lut(
+        &mut self,
+        _spi: &mut SPI,
+        refresh_rate: Option<RefreshLut>,
+    ) -> Result<(), Error<SPI::Error>> {
+        if let Some(RefreshLut::Custom(_)) = refresh_rate {
+            return Err(Error::Unsupported);
+        }
+        unsupported!();
+    }
+
+    fn is_busy(&self) -> bool {
+        self.interface.is_busy(IS_BUSY_LOW)
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> Epd5in83<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn command(&mut self, spi: &mut SPI, command: Command) -> Result<(), Error<SPI::Error>> {
+        self.interface.cmd(spi, command)
+    }
+
+    fn send_data(&mut self, spi: &mut SPI, data: &[u8]) -> Result<(), Error<SPI::Error>> {
+        self.interface.data(spi, data)
+    }
+
+    fn cmd_with_data(
+        &mut self,
+        spi: &mut SPI,
+        command: Command,
+        data: &[u8],
+    ) -> Result<(), Error<SPI::Error>> {
+        self.interface.cmd_with_data(spi, command, data)
+    }
+
+    fn wait_until_idle(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        while self.interface.is_busy(IS_BUSY_LOW) {
+            self.interface.cmd(spi, Command::GetStatus)?;
+            delay.delay_ms(20);
+        }
+        Ok(())
+    }
+
+    fn send_resolution(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        let w = self.width();
+        let h = self.height();
+
+        self.command(spi, Command::TconResolution)?;
+        self.send_data(spi, &[(w >> 8) as u8])?;
+        self.send_data(spi, &[w as u8])?;
+        self.send_data(spi, &[(h >> 8) as u8])?;
+        self.send_data(spi, &[h as u8])
+    }
+
+    /// Switches to the fast waveform and limits the writes and the refresh to a window
+    fn enter_partial(
+        &mut self,
+        spi: &mut SPI,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        // no delay at hand, poll the busy line without asking for the status
+        self.interface.wait_until_idle(IS_BUSY_LOW);
+        if !self.partial {
+            // the vendor driver fixes the temperature to get the fast waveform
+            self.cmd_with_data(spi, Command::CascadeSetting, &[0x02])?;
+            self.cmd_with_data(spi, Command::ForceTemperature, &[0x6E])?;
+            // floating border, and the new frame is copied to the old one after the refresh
+            self.cmd_with_data(spi, Command::VcomAndDataIntervalSetting, &[0xA9, 0x07])?;
+        }
+        self.interface.partial_in_uc8179(spi, x, y, width, height)?;
+        self.partial = true;
+        Ok(())
+    }
+
+    /// Waits for the refresh of the window and goes back to full frames, if in partial mode
+    fn leave_partial(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle(spi, delay)?;
+        self.partial_out(spi)
+    }
+
+    /// Goes back to full frames, if in partial mode
+    fn partial_out(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        if self.partial {
+            self.command(spi, Command::PartialOut)?;
+            self.cmd_with_data(spi, Command::CascadeSetting, &[0x00])?;
+            self.cmd_with_data(spi, Command::VcomAndDataIntervalSetting, &[0x10, 0x07])?;
+            self.partial = false;
+        }
+        Ok(())
+    }
+
+    /// Starts a refresh, powering the drivers on again after `power_down`
+    fn start_refresh(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        if self.powered_down {
+            self.command(spi, Command::PowerOn)?;
+            self.wait_until_idle(spi, delay)?;
+            // the border of a partial refresh stays floating
+            if !self.partial {
+                self.cmd_with_data(spi, Command::VcomAndDataIntervalSetting, &[0x10, 0x07])?;
+            }
+            self.powered_down = false;
+        }
+        self.command(spi, Command::DisplayRefresh)
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> PowerDown<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd5in83<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn power_down(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        // no delay at hand, poll the busy line without asking for the status
+        self.interface.wait_until_idle(IS_BUSY_LOW);
+        self.partial_out(spi)?;
+        // border floating, restored by the next refresh
+        self.cmd_with_data(spi, Command::VcomAndDataIntervalSetting, &[0x90, 0x07])?;
+        self.command(spi, Command::PowerOff)?;
+        self.interface.wait_until_idle(IS_BUSY_LOW);
+        self.powered_down = true;
+        Ok(())
+    }
+
+    fn is_powered_down(&self) -> bool {
+        self.powered_down
+    }
+}
+
+/// Whether a partial window is aligned to bytes and lies on the panel
+fn window_fits(x: u32, y: u32, width: u32, height: u32) -> bool {
+    x.is_multiple_of(8)
+        && width.is_multiple_of(8)
+        && span_fits(x, width, WIDTH)
+        && span_fits(y, height, HEIGHT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus_log::{commands, data_of, Log, Pin, Spi};
+    use embedded_hal_mock::delay::MockNoop;
+
+    #[test]
+    fn epd_size() {
+        assert_eq!(WIDTH, 648);
+        assert_eq!(HEIGHT, 480);
+        assert_eq!(NUM_DISPLAY_BYTES, 38880);
+        assert_eq!(DEFAULT_BACKGROUND_COLOR, Color::White);
+    }
+
+    #[test]
+    fn partial_windows() {
+        assert!(window_fits(0, 0, WIDTH, HEIGHT));
+        assert!(window_fits(640, 400, 8, 80));
+        assert!(!window_fits(4, 10, 64, 20));
+        assert!(!window_fits(16, 10, 60, 20));
+        assert!(!window_fits(16, 10, 64, 0));
+        assert!(!window_fits(648, 0, 8, 20));
+        assert!(!window_fits(0, 470, 8, 20));
+    }
+
+    #[test]
+    fn refresh_after_power_down() {
+        let log = Log::default();
+        let mut spi = Spi(&log);
+        let mut delay = MockNoop::new();
+        let mut epd = Epd5in83::new(
+            &mut spi,
+            Pin(None),
+            Pin(None),
+            Pin(Some(&log)),
+            Pin(None),
+            &mut delay,
+        )
+        .unwrap();
+
+        log.take();
+        epd.power_down(&mut spi).unwrap();
+        assert!(epd.is_powered_down());
+        let events = log.take();
+        assert_eq!(commands(&events), [0x50, 0x02]);
+        assert_eq!(data_of(&events, 0x50), [[0x90, 0x07]]);
+
+        // power on and the border again before the refresh
+        epd.display_frame(&mut spi, &mut delay).unwrap();
+        assert!(!epd.is_powered_down());
+        let events = log.take();
+        assert_eq!(commands(&events), [0x04, 0x50, 0x12]);
+        assert_eq!(data_of(&events, 0x50), [[0x10, 0x07]]);
+
+        epd.display_frame(&mut spi, &mut delay).unwrap();
+        assert_eq!(commands(&log.take()), [0x12]);
+    }
+}
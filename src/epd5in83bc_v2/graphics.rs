@@ -0,0 +1,95 @@
+use crate::color::TriColor;
+use crate::epd5in83bc_v2::{DEFAULT_BACKGROUND_COLOR, HEIGHT, NUM_DISPLAY_BITS, WIDTH};
+use crate::graphics::{DisplayRotation, TriDisplay};
+use embedded_graphics_core::prelude::*;
+
+/// Full size buffer for use with the 5.83" b/c V2 EPD
+///
+/// Can also be manually constructed and be used together with VarDisplay
+pub struct Display5in83bcV2 {
+    // one buffer for both b/w and for chromatic:
+    // * &buffer[0..NUM_DISPLAY_BITS] for b/w buffer and
+    // * &buffer[NUM_DISPLAY_BITS..2*NUM_DISPLAY_BITS] for chromatic buffer
+    buffer: [u8; 2 * NUM_DISPLAY_BITS as usize],
+    rotation: DisplayRotation,
+}
+
+impl Default for Display5in83bcV2 {
+    fn default() -> Self {
+        Display5in83bcV2 {
+            buffer: [DEFAULT_BACKGROUND_COLOR.get_byte_value(); 2 * NUM_DISPLAY_BITS as usize],
+            rotation: DisplayRotation::default(),
+        }
+    }
+}
+
+impl DrawTarget for Display5in83bcV2 {
+    type Color = TriColor;
+    type Error = core::convert::Infallible;
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for pixel in pixels {
+            self.draw_helper_tri(WIDTH, HEIGHT, pixel)?;
+        }
+        Ok(())
+    }
+}
+
+impl OriginDimensions for Display5in83bcV2 {
+    fn size(&self) -> Size {
+        Size::new(WIDTH, HEIGHT)
+    }
+}
+
+impl TriDisplay for Display5in83bcV2 {
+    fn buffer(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    fn get_mut_buffer(&mut self) -> &mut [u8] {
+        &mut self.buffer
+    }
+
+    fn set_rotation(&mut self, rotation: DisplayRotation) {
+        self.rotation = rotation;
+    }
+
+    fn rotation(&self) -> DisplayRotation {
+        self.rotation
+    }
+
+    fn chromatic_offset(&self) -> usize {
+        NUM_DISPLAY_BITS as usize
+    }
+
+    fn bw_buffer(&self) -> &[u8] {
+        &self.buffer[0..self.chromatic_offset()]
+    }
+
+    fn chromatic_buffer(&self) -> &[u8] {
+        &self.buffer[self.chromatic_offset()..]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // test buffer length
+    #[test]
+    fn graphics_size() {
+        let display = Display5in83bcV2::default();
+        assert_eq!(display.bw_buffer().len(), 38880);
+        assert_eq!(display.chromatic_buffer().len(), 38880);
+    }
+
+    #[test]
+    fn graphics_chromatic_pixel() {
+        let mut display = Display5in83bcV2::default();
+        let _ = Pixel(Point::new(WIDTH as i32 - 1, 0), TriColor::Chromatic).draw(&mut display);
+        assert_eq!(display.bw_buffer()[80], 0xFF);
+        assert_eq!(display.chromatic_buffer()[80], 0xFE);
+    }
+}
@@ -0,0 +1,124 @@
+//! SPI Commands for the Waveshare 7.3" (F) and (G) E-Ink Displays
+
+use crate::traits;
+
+/// Epd7in3f and Epd7in3g commands
+///
+/// Should rarely (never?) be needed directly.
+///
+/// Most of the registers past the resolution aren't documented in the public
+/// datasheet, their names follow the Waveshare C driver.
+#[allow(dead_code)]
+#[derive(Copy, Clone)]
+pub(crate) enum Command {
+    /// Set Resolution, LUT selection, BWR pixels, gate scan direction, source shift
+    /// direction, booster switch, soft reset.
+    PanelSetting = 0x00,
+
+    /// Selecting internal and external power
+    PowerSetting = 0x01,
+
+    /// After the Power Off command, the driver will power off following the Power Off
+    /// Sequence; BUSY signal will become "0".
+    PowerOff = 0x02,
+
+    /// Setting Power OFF sequence
+    PowerOffSequenceSetting = 0x03,
+
+    /// Turning On the Power
+    ///
+    /// After the Power ON command, the driver will power on following the Power ON
+    /// sequence. Once complete, the BUSY signal will become "1".
+    PowerOn = 0x04,
+
+    /// Booster soft start for phase 1
+    BoosterSoftStart1 = 0x05,
+
+    /// Booster soft start for phase 2
+    BoosterSoftStart2 = 0x06,
+
+    /// This command makes the chip enter the deep-sleep mode to save power.
+    ///
+    /// The deep sleep mode would return to stand-by by hardware reset.
+    ///
+    /// The only one parameter is a check code, the command would be excuted if check code = 0xA5.
+    DeepSleep = 0x07,
+
+    /// Booster soft start for phase 3
+    BoosterSoftStart3 = 0x08,
+
+    /// This command starts transmitting data and write them into SRAM.
+    ///
+    /// Two pixels per byte, see [`OctColor`](crate::color::OctColor).
+    DataStartTransmission1 = 0x10,
+
+    /// After this command is issued, driver will refresh display (data/VCOM) according to
+    /// SRAM data and LUT.
+    ///
+    /// After Display Refresh command, BUSY signal will become "0" until the display
+    /// update is finished.
+    DisplayRefresh = 0x12,
+
+    /// Image Process Command
+    ImageProcess = 0x13,
+
+    /// The command controls the PLL clock frequency.
+    PllControl = 0x30,
+
+    /// This command selects the Internal or External temperature sensor.
+    TemperatureCalibration = 0x41,
+
+    /// This command indicates the interval of Vcom and data output.
+    VcomAndDataIntervalSetting = 0x50,
+
+    /// This command defines non-overlap period of Gate and Source.
+    TconSetting = 0x60,
+    /// This command defines alternative resolution and this setting is of higher priority
+    /// than the RES\[1:0\] in R00H (PSR).
+    TconResolution = 0x61,
+
+    /// This command reads the IC status.
+    GetStatus = 0x71,
+
+    /// This command sets `VCOM_DC` value.
+    VcmDcSetting = 0x82,
+
+    /// Source voltage of the data (`T_VDCS` in the Waveshare driver)
+    TVdcs = 0x84,
+
+    /// Gate timing (`AGID` in the Waveshare driver)
+    Agid = 0x86,
+
+    /// Cascade setting (`CCSET` in the Waveshare driver)
+    CascadeSetting = 0xE0,
+
+    /// Power saving setting
+    PowerSaving = 0xE3,
+
+    /// Forces the temperature used for the waveform (`TSSET` in the Waveshare driver)
+    ForceTemperature = 0xE6,
+
+    /// Unlocks the analog settings (`CMDH` in the Waveshare driver), sent with a fixed
+    /// key right after reset
+    Cmdh = 0xAA,
+}
+
+impl traits::Command for Command {
+    /// Returns the address of the command
+    fn address(self) -> u8 {
+        self as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::Command as CommandTrait;
+
+    #[test]
+    fn command_addr() {
+        assert_eq!(Command::PanelSetting.address(), 0x00);
+        assert_eq!(Command::DisplayRefresh.address(), 0x12);
+        assert_eq!(Command::Cmdh.address(), 0xAA);
+    }
+}
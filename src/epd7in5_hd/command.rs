@@ -1,4 +1,4 @@
-//! SPI Commands for the Waveshare 7.5" E-Ink Display
+//! SPI Commands for the SSD1677 of the Waveshare 7.5" (HD), 10.2" and 13.3" (K) E-Ink Displays
 
 use crate::traits;
 
@@ -94,6 +94,9 @@ pub(crate) enum Command {
     /// Read Register for Display Option
     OtpRead = 0x2D,
 
+    /// Write the waveform of the display update into the LUT register
+    WriteLutRegister = 0x32,
+
     /// CRC calculation command for OTP content validation
     CrcCalculation = 0x34,
 
@@ -0,0 +1,61 @@
+//! Register LUTs of the 7.5" V2 for [`RefreshLut::Quick`](crate::traits::RefreshLut::Quick)
+//!
+//! Every LUT has 7 groups of 6 bytes: the levels of the 4 phases, their frame counts and
+//! the repeats of the group. Only the first 3 groups are used, 124 frames in total, which
+//! is quicker than the waveform of the OTP but leaves more ghosting behind. Every pixel is
+//! driven to its new color whatever the old one was, so the old frame doesn't matter.
+
+#[rustfmt::skip]
+pub(crate) const LUT_VCOM_QUICK: [u8; 42] = [
+    0x00, 0x0F, 0x0F, 0x00, 0x00, 0x01,
+    0x00, 0x0F, 0x01, 0x0F, 0x01, 0x02,
+    0x00, 0x0F, 0x0F, 0x00, 0x00, 0x01,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+
+#[rustfmt::skip]
+pub(crate) const LUT_WW_QUICK: [u8; 42] = [
+    0x10, 0x0F, 0x0F, 0x00, 0x00, 0x01,
+    0x84, 0x0F, 0x01, 0x0F, 0x01, 0x02,
+    0x20, 0x0F, 0x0F, 0x00, 0x00, 0x01,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+
+#[rustfmt::skip]
+pub(crate) const LUT_BW_QUICK: [u8; 42] = [
+    0x10, 0x0F, 0x0F, 0x00, 0x00, 0x01,
+    0x84, 0x0F, 0x01, 0x0F, 0x01, 0x02,
+    0x20, 0x0F, 0x0F, 0x00, 0x00, 0x01,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+
+#[rustfmt::skip]
+pub(crate) const LUT_WB_QUICK: [u8; 42] = [
+    0x80, 0x0F, 0x0F, 0x00, 0x00, 0x01,
+    0x84, 0x0F, 0x01, 0x0F, 0x01, 0x02,
+    0x40, 0x0F, 0x0F, 0x00, 0x00, 0x01,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+
+#[rustfmt::skip]
+pub(crate) const LUT_BB_QUICK: [u8; 42] = [
+    0x80, 0x0F, 0x0F, 0x00, 0x00, 0x01,
+    0x84, 0x0F, 0x01, 0x0F, 0x01, 0x02,
+    0x40, 0x0F, 0x0F, 0x00, 0x00, 0x01,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
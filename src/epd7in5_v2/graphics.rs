@@ -1,6 +1,6 @@
-use crate::epd7in5_v2::{DEFAULT_BACKGROUND_COLOR, HEIGHT, WIDTH};
-use crate::graphics::{Display, DisplayRotation};
-use embedded_graphics::pixelcolor::BinaryColor;
+use crate::epd7in5_v2::{DEFAULT_BACKGROUND_COLOR, HEIGHT, NUM_GRAY_BYTES, WIDTH};
+use crate::graphics::{Display, DisplayRotation, GrayDisplay};
+use embedded_graphics::pixelcolor::{BinaryColor, Gray2};
 use embedded_graphics_core::prelude::*;
 
 /// Full size buffer for use with the 7in5 EPD
@@ -61,6 +61,123 @@ impl Display for Display7in5 {
     }
 }
 
+/// Full size buffer for the 7in5 EPD mounted in portrait orientation
+///
+/// Drawing happens in a 480x800 coordinate system, but the buffer keeps the native
+/// layout of the panel with one row per gate line. The pixels are transposed while
+/// drawing, so the buffer can be sent with `update_frame` without rotating it first.
+///
+/// The rotation is relative to the portrait orientation, `Rotate0` is the panel
+/// turned by 90 degrees clockwise.
+pub struct Display7in5Portrait {
+    display: Display7in5,
+    rotation: DisplayRotation,
+}
+
+impl Default for Display7in5Portrait {
+    fn default() -> Self {
+        let mut display = Display7in5::default();
+        display.set_rotation(DisplayRotation::default().quarter_turn());
+        Display7in5Portrait {
+            display,
+            rotation: DisplayRotation::default(),
+        }
+    }
+}
+
+impl DrawTarget for Display7in5Portrait {
+    type Color = BinaryColor;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        self.display.draw_iter(pixels)
+    }
+}
+
+impl OriginDimensions for Display7in5Portrait {
+    fn size(&self) -> Size {
+        Size::new(HEIGHT, WIDTH)
+    }
+}
+
+impl Display for Display7in5Portrait {
+    fn buffer(&self) -> &[u8] {
+        self.display.buffer()
+    }
+
+    fn get_mut_buffer(&mut self) -> &mut [u8] {
+        self.display.get_mut_buffer()
+    }
+
+    fn set_rotation(&mut self, rotation: DisplayRotation) {
+        self.rotation = rotation;
+        self.display.set_rotation(rotation.quarter_turn());
+    }
+
+    fn rotation(&self) -> DisplayRotation {
+        self.rotation
+    }
+}
+
+/// Full size buffer with 4 gray levels for use with the 7in5 EPD
+///
+/// Every byte holds 4 pixels, white (`0xFF`) is the default.
+pub struct Display7in5Gray {
+    buffer: [u8; NUM_GRAY_BYTES],
+    rotation: DisplayRotation,
+}
+
+impl Default for Display7in5Gray {
+    fn default() -> Self {
+        Display7in5Gray {
+            buffer: [0xFF; NUM_GRAY_BYTES],
+            rotation: DisplayRotation::default(),
+        }
+    }
+}
+
+impl DrawTarget for Display7in5Gray {
+    type Color = Gray2;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for pixel in pixels {
+            self.draw_helper(WIDTH, HEIGHT, pixel)?;
+        }
+        Ok(())
+    }
+}
+
+impl OriginDimensions for Display7in5Gray {
+    fn size(&self) -> Size {
+        Size::new(WIDTH, HEIGHT)
+    }
+}
+
+impl GrayDisplay for Display7in5Gray {
+    fn buffer(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    fn get_mut_buffer(&mut self) -> &mut [u8] {
+        &mut self.buffer
+    }
+
+    fn set_rotation(&mut self, rotation: DisplayRotation) {
+        self.rotation = rotation;
+    }
+
+    fn rotation(&self) -> DisplayRotation {
+        self.rotation
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -158,4 +275,54 @@ mod tests {
             assert_eq!(byte, epd7in5_v2::DEFAULT_BACKGROUND_COLOR.get_byte_value());
         }
     }
+
+    #[test]
+    fn graphics_portrait() {
+        let mut display = Display7in5Portrait::default();
+        assert_eq!(display.size(), Size::new(480, 800));
+        assert_eq!(display.buffer().len(), 48000);
+
+        // the bottom left corner of the portrait is the start of the first gate line
+        let _ = Line::new(Point::new(0, 792), Point::new(0, 799))
+            .into_styled(PrimitiveStyle::with_stroke(Black, 1))
+            .draw(&mut display);
+
+        let buffer = display.buffer();
+
+        assert_eq!(buffer[0], Color::Black.get_byte_value());
+
+        for &byte in buffer.iter().skip(1) {
+            assert_eq!(byte, epd7in5_v2::DEFAULT_BACKGROUND_COLOR.get_byte_value());
+        }
+    }
+
+    #[test]
+    fn graphics_portrait_rotation_180() {
+        let mut display = Display7in5Portrait::default();
+        display.set_rotation(DisplayRotation::Rotate180);
+        assert!(matches!(display.rotation(), DisplayRotation::Rotate180));
+
+        let _ = Line::new(Point::new(479, 0), Point::new(479, 7))
+            .into_styled(PrimitiveStyle::with_stroke(Black, 1))
+            .draw(&mut display);
+
+        let buffer = display.buffer();
+
+        assert_eq!(buffer[0], Color::Black.get_byte_value());
+
+        for &byte in buffer.iter().skip(1) {
+            assert_eq!(byte, epd7in5_v2::DEFAULT_BACKGROUND_COLOR.get_byte_value());
+        }
+    }
+
+    #[test]
+    fn graphics_gray_levels() {
+        let mut display = Display7in5Gray::default();
+        assert_eq!(GrayDisplay::buffer(&display).len(), 96000);
+        for (x, luma) in (0..4).enumerate() {
+            let _ = Pixel(Point::new(x as i32, 0), Gray2::new(luma)).draw(&mut display);
+        }
+        assert_eq!(GrayDisplay::buffer(&display)[0], 0b00_01_10_11);
+        assert_eq!(GrayDisplay::buffer(&display)[1], 0xFF);
+    }
 }
@@ -6,6 +6,85 @@
 //! - [Waveshare C driver](https://github.com/waveshare/e-Paper/blob/702def0/RaspberryPi%26JetsonNano/c/lib/e-Paper/EPD_7in5_V2.c)
 //! - [Waveshare Python driver](https://github.com/waveshare/e-Paper/blob/702def0/RaspberryPi%26JetsonNano/python/lib/waveshare_epd/epd7in5_V2.py)
 //!
+//! # Partial refresh
+//!
+//! [`update_partial_frame`](WaveshareDisplay::update_partial_frame) puts the controller
+//! into partial mode, and the next [`display_frame`](WaveshareDisplay::display_frame)
+//! only refreshes that window, e.g. a clock, with the fast waveform of the vendor driver
+//! instead of flashing the whole panel. It waits for the refresh, and the next full frame
+//! leaves partial mode again. The window has to start at a multiple of 8 and be a multiple
+//! of 8 wide, only the window of the last update is refreshed. Fast refreshes leave ghosting behind, so do a full refresh
+//! from time to time.
+//!
+//! [`QuickRefresh`] writes the old and the new frame, or their partial windows, for code
+//! which is generic over the panels with fast refreshes.
+//!
+//! # Quick refresh
+//!
+//! [`RefreshLut::Quick`] switches from the waveform in the OTP to a shorter one in the
+//! registers, trading more ghosting for speed, e.g. for dashboards. It's kept across
+//! [`wake_up`](WaveshareDisplay::wake_up).
+//!
+//! # Fast init
+//!
+//! [`Epd7in5::set_fast_init`] initializes the display like the fast init of the vendor
+//! driver, which picks a shorter full waveform from the OTP. A full refresh takes about
+//! 1.5 s instead of 4 s, with a little less contrast.
+//!
+//! # Temperature
+//!
+//! The full refreshes use the waveform of the OTP for the temperature of the on-glass
+//! sensor. Where that reads wrong, e.g. next to a warm board, [`TemperatureOverride`]
+//! chooses the waveform for a temperature measured elsewhere. The quick, partial and gray
+//! refreshes are made for a fixed temperature and ignore it.
+//!
+//! # Dual SPI
+//!
+//! Some carrier boards wire a second data line to the controller, which then takes the
+//! frame data two bits per clock. [`Epd7in5::set_dual_spi`] switches the controller over,
+//! and [`Epd7in5::update_frame_dual`] sends the commands over `spi` and the frame data
+//! over the peripheral configured for two data lines, which splits every byte into bit
+//! pairs. While it's enabled, the other uploads can't be used; only
+//! [`clear_frame`](WaveshareDisplay::clear_frame) switches back for the time it fills the
+//! frame.
+//!
+//! # 4 gray levels
+//!
+//! Draw into the `Gray2` buffer [`Display7in5Gray`] and upload it with
+//! [`Epd7in5::update_gray_frame`]. Like the vendor driver it uses the gray waveform of the
+//! OTP, selected by forcing the temperature, with the two bits of every pixel in the two
+//! frame memories. Uploading a black/white frame switches back.
+//!
+//!```rust, no_run
+//!# use embedded_hal_mock::*;
+//!# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
+//!use embedded_graphics::{
+//!    pixelcolor::Gray2, prelude::*, primitives::{Line, PrimitiveStyle},
+//!};
+//!use epd_waveshare::{epd7in5_v2::*, prelude::*};
+//!#
+//!# let expectations = [];
+//!# let mut spi = spi::Mock::new(&expectations);
+//!# let expectations = [];
+//!# let cs_pin = pin::Mock::new(&expectations);
+//!# let busy_in = pin::Mock::new(&expectations);
+//!# let dc = pin::Mock::new(&expectations);
+//!# let rst = pin::Mock::new(&expectations);
+//!# let mut delay = delay::MockNoop::new();
+//!
+//!let mut epd = Epd7in5::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
+//!let mut display = Display7in5Gray::default();
+//!
+//!let _ = Line::new(Point::new(10, 20), Point::new(10, 400))
+//!    .into_styled(PrimitiveStyle::with_stroke(Gray2::new(1), 1))
+//!    .draw(&mut display);
+//!
+//!epd.update_gray_frame(&mut spi, display.buffer(), &mut delay)?;
+//!epd.display_frame(&mut spi, &mut delay)?;
+//!# Ok(())
+//!# }
+//!```
+//!
 //! Important note for V2:
 //! Revision V2 has been released on 2019.11, the resolution is upgraded to 800×480, from 640×384 of V1.
 //! The hardware and interface of V2 are compatible with V1, however, the related software should be updated.
@@ -15,17 +94,24 @@ use embedded_hal::{
     digital::v2::{InputPin, OutputPin},
 };
 
+pub use crate::color::GrayMode;
+
 use crate::color::Color;
+use crate::controllers::uc8179::{Command, DualSpi, Frame, IS_BUSY_LOW};
 use crate::interface::DisplayInterface;
-use crate::traits::{InternalWiAdditions, RefreshLut, WaveshareDisplay};
+use crate::luts::{LutPreset, Panel};
+use crate::traits::{
+    InternalWiAdditions, PowerDown, QuickRefresh, RefreshLut, TemperatureOverride, WaveshareDisplay,
+};
+use crate::{span_fits, Error};
 
-pub(crate) mod command;
-use self::command::Command;
+pub(crate) mod constants;
+use self::constants::*;
 
 #[cfg(feature = "graphics")]
 mod graphics;
 #[cfg(feature = "graphics")]
-pub use self::graphics::Display7in5;
+pub use self::graphics::{Display7in5, Display7in5Gray, Display7in5Portrait};
 
 /// Width of the display
 pub const WIDTH: u32 = 800;
@@ -33,7 +119,14 @@ pub const WIDTH: u32 = 800;
 pub const HEIGHT: u32 = 480;
 /// Default Background Color
 pub const DEFAULT_BACKGROUND_COLOR: Color = Color::White;
-const IS_BUSY_LOW: bool = true;
+/// Bytes of a frame with 4 gray levels, 2 bits per pixel
+pub(crate) const NUM_GRAY_BYTES: usize = WIDTH as usize * HEIGHT as usize / 4;
+/// Soft start of the booster for black/white frames
+const SOFT_START: [u8; 4] = [0x17, 0x17, 0x27, 0x17];
+/// Stronger soft start of the booster for the fast and the gray waveform
+const FAST_SOFT_START: [u8; 4] = [0x27, 0x27, 0x18, 0x17];
+/// Temperature forced by the fast init of the vendor driver, the OTP has a short waveform for it
+const FAST_TEMPERATURE: u8 = 0x5A;
 
 /// Epd7in5 (V2) driver
 ///
@@ -42,6 +135,20 @@ pub struct Epd7in5<SPI, CS, BUSY, DC, RST, DELAY> {
     interface: DisplayInterface<SPI, CS, BUSY, DC, RST, DELAY>,
     /// Background Color
     color: Color,
+    /// Whether the controller is in partial mode, waiting for the window to be refreshed
+    partial: bool,
+    /// Waveform of the refreshes
+    refresh: RefreshLut,
+    /// Gray levels of the last uploaded frame
+    mode: GrayMode,
+    /// Whether the frame data is sent on two data lines
+    dual_spi: bool,
+    /// Temperature the full waveform is chosen for instead of the sensor reading
+    temperature: Option<i8>,
+    /// Whether the full refreshes use the fast waveform of the OTP
+    fast: bool,
+    /// Whether the drivers were switched off after the last refresh
+    powered_down: bool,
 }
 
 impl<SPI, CS, BUSY, DC, RST, DELAY> InternalWiAdditions<SPI, CS, BUSY, DC, RST, DELAY>
@@ -54,25 +161,31 @@ where
     RST: OutputPin,
     DELAY: DelayMs<u8>,
 {
-    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         // Reset the device
         self.interface.reset(delay, 2);
+        self.partial = false;
+        self.powered_down = false;
+        self.mode = GrayMode::Gray1;
 
         // V2 procedure as described here:
         // https://github.com/waveshare/e-Paper/blob/master/RaspberryPi%26JetsonNano/python/lib/waveshare_epd/epd7in5bc_V2.py
         // and as per specs:
         // https://www.waveshare.com/w/upload/6/60/7.5inch_e-Paper_V2_Specification.pdf
 
-        self.cmd_with_data(spi, Command::BoosterSoftStart, &[0x17, 0x17, 0x27, 0x17])?;
-        self.cmd_with_data(spi, Command::PowerSetting, &[0x07, 0x17, 0x3F, 0x3F])?;
-        self.command(spi, Command::PowerOn)?;
+        self.interface
+            .power_on_uc8179(spi, [0x07, 0x17, 0x3F, 0x3F], self.soft_start())?;
         self.wait_until_idle(spi, delay)?;
         self.cmd_with_data(spi, Command::PanelSetting, &[0x1F])?;
         self.cmd_with_data(spi, Command::PllControl, &[0x06])?;
-        self.cmd_with_data(spi, Command::TconResolution, &[0x03, 0x20, 0x01, 0xE0])?;
-        self.cmd_with_data(spi, Command::DualSpi, &[0x00])?;
+        self.interface.resolution_uc8179(spi, WIDTH, HEIGHT)?;
+        self.interface.dual_spi_uc8179(spi, self.dual_spi_mode())?;
         self.cmd_with_data(spi, Command::TconSetting, &[0x22])?;
         self.cmd_with_data(spi, Command::VcomAndDataIntervalSetting, &[0x10, 0x07])?;
+        self.use_temperature(spi)?;
+        if self.refresh == RefreshLut::Quick {
+            self.write_quick_lut(spi)?;
+        }
         self.wait_until_idle(spi, delay)?;
         Ok(())
     }
@@ -96,27 +209,34 @@ where
         dc: DC,
         rst: RST,
         delay: &mut DELAY,
-    ) -> Result<Self, SPI::Error> {
+    ) -> Result<Self, Error<SPI::Error>> {
         let interface = DisplayInterface::new(cs, busy, dc, rst);
         let color = DEFAULT_BACKGROUND_COLOR;
 
-        let mut epd = Epd7in5 { interface, color };
+        let mut epd = Epd7in5 {
+            interface,
+            color,
+            partial: false,
+            refresh: RefreshLut::Full,
+            mode: GrayMode::Gray1,
+            dual_spi: false,
+            temperature: None,
+            fast: false,
+            powered_down: false,
+        };
 
         epd.init(spi, delay)?;
 
         Ok(epd)
     }
 
-    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
+    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
         self.init(spi, delay)
     }
 
-    fn sleep(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
-        self.wait_until_idle(spi, delay)?;
-        self.command(spi, Command::PowerOff)?;
-        self.wait_until_idle(spi, delay)?;
-        self.cmd_with_data(spi, Command::DeepSleep, &[0xA5])?;
-        Ok(())
+    fn sleep(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.leave_partial(spi, delay)?;
+        self.interface.sleep_uc8179(spi)
     }
 
     fn update_frame(
@@ -124,52 +244,83 @@ where
         spi: &mut SPI,
         buffer: &[u8],
         delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
-        self.wait_until_idle(spi, delay)?;
-        self.cmd_with_data(spi, Command::DataStartTransmission2, buffer)?;
-        Ok(())
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(!self.dual_spi);
+        self.leave_partial(spi, delay)?;
+        self.use_bw_mode(spi)?;
+        self.interface.frame_uc8179(spi, Frame::Chromatic, buffer)
     }
 
     fn update_partial_frame(
         &mut self,
-        _spi: &mut SPI,
-        _buffer: &[u8],
-        _x: u32,
-        _y: u32,
-        _width: u32,
-        _height: u32,
-    ) -> Result<(), SPI::Error> {
-        unimplemented!();
+        spi: &mut SPI,
+        buffer: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(!self.dual_spi && window_fits(x, y, width, height));
+        ensure!(buffer.len() as u32 == width / 8 * height);
+        self.enter_partial(spi, x, y, width, height)?;
+        self.interface.frame_uc8179(spi, Frame::Chromatic, buffer)
     }
 
-    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
-        self.wait_until_idle(spi, delay)?;
-        self.command(spi, Command::DisplayRefresh)?;
+    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.start_display_frame(spi, delay)?;
+        if self.partial {
+            // the window has to be refreshed before partial mode is left
+            self.leave_partial(spi, delay)?;
+        }
         Ok(())
     }
 
+    fn start_display_frame(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle(spi, delay)?;
+        self.start_refresh(spi, delay)
+    }
+
+    fn finish_refresh(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.leave_partial(spi, delay)
+    }
+
     fn update_and_display_frame(
         &mut self,
         spi: &mut SPI,
         buffer: &[u8],
         delay: &mut DELAY,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.update_frame(spi, buffer, delay)?;
-        self.command(spi, Command::DisplayRefresh)?;
+        self.start_refresh(spi, delay)?;
         Ok(())
     }
 
-    fn clear_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
-        self.wait_until_idle(spi, delay)?;
-        self.send_resolution(spi)?;
+    fn clear_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.leave_partial(spi, delay)?;
+        self.use_bw_mode(spi)?;
+        self.interface.resolution_uc8179(spi, WIDTH, HEIGHT)?;
+        if self.dual_spi {
+            self.interface.dual_spi_uc8179(spi, DualSpi::Disabled)?;
+        }
 
-        self.command(spi, Command::DataStartTransmission1)?;
-        self.interface.data_x_times(spi, 0x00, WIDTH * HEIGHT / 8)?;
+        self.interface
+            .fill_uc8179(spi, Frame::Black, 0x00, WIDTH * HEIGHT / 8)?;
+        self.interface
+            .fill_uc8179(spi, Frame::Chromatic, 0x00, WIDTH * HEIGHT / 8)?;
 
-        self.command(spi, Command::DataStartTransmission2)?;
-        self.interface.data_x_times(spi, 0x00, WIDTH * HEIGHT / 8)?;
+        if self.dual_spi {
+            self.interface.dual_spi_uc8179(spi, DualSpi::Enabled)?;
+        }
 
-        self.command(spi, Command::DisplayRefresh)?;
+        self.start_refresh(spi, delay)?;
         Ok(())
     }
 
@@ -191,10 +342,27 @@ where
 
     fn set_lut(
         &mut self,
-        _spi: &mut SPI,
-        _refresh_rate: Option<RefreshLut>,
-    ) -> Result<(), SPI::Error> {
-        unimplemented!();
+        spi: &mut SPI,
+        refresh_rate: Option<RefreshLut>,
+    ) -> Result<(), Error<SPI::Error>> {
+        if let Some(RefreshLut::Custom(_)) = refresh_rate {
+            return Err(Error::Unsupported);
+        }
+        if let Some(refresh_lut) = refresh_rate {
+            self.refresh = refresh_lut;
+        }
+        self.interface.wait_until_idle(IS_BUSY_LOW);
+        if self.mode == GrayMode::Gray4 {
+            // applied when the next black/white frame is uploaded
+            return Ok(());
+        }
+        match self.refresh {
+            // LUTs from the OTP
+            RefreshLut::Full | RefreshLut::Custom(_) => {
+                self.cmd_with_data(spi, Command::PanelSetting, &[0x1F])
+            }
+            RefreshLut::Quick => self.write_quick_lut(spi),
+        }
     }
 
     fn is_busy(&self) -> bool {
@@ -211,12 +379,123 @@ where
     RST: OutputPin,
     DELAY: DelayMs<u8>,
 {
-    fn command(&mut self, spi: &mut SPI, command: Command) -> Result<(), SPI::Error> {
-        self.interface.cmd(spi, command)
+    /// Uploads a frame with 4 gray levels and switches the display into `GrayMode::Gray4`
+    ///
+    /// The buffer holds 2 bits per pixel, see [`Display7in5Gray`].
+    pub fn update_gray_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(!self.dual_spi && buffer.len() == NUM_GRAY_BYTES);
+        self.leave_partial(spi, delay)?;
+
+        if self.mode == GrayMode::Gray1 {
+            // LUTs from the OTP, the vendor driver fixes the temperature to get the gray waveform
+            self.cmd_with_data(spi, Command::PanelSetting, &[0x1F])?;
+            self.cmd_with_data(spi, Command::BoosterSoftStart, &FAST_SOFT_START)?;
+            self.cmd_with_data(spi, Command::CascadeSetting, &[0x02])?;
+            self.cmd_with_data(spi, Command::ForceTemperature, &[0x5F])?;
+            self.mode = GrayMode::Gray4;
+        }
+
+        // a set bit is a dark pixel: the low bit of every pixel goes into the old frame,
+        // the high bit into the new one
+        self.command(spi, Command::DataStartTransmission1)?;
+        self.interface.data_gray_plane(spi, buffer, 0b01, true)?;
+        self.command(spi, Command::DataStartTransmission2)?;
+        self.interface.data_gray_plane(spi, buffer, 0b10, true)
+    }
+
+    /// Mode of the last uploaded frame
+    pub fn gray_mode(&self) -> GrayMode {
+        self.mode
+    }
+
+    /// Switches between frame data on one or two data lines, kept across `wake_up`
+    pub fn set_dual_spi(&mut self, spi: &mut SPI, enabled: bool) -> Result<(), Error<SPI::Error>> {
+        self.dual_spi = enabled;
+        self.interface.wait_until_idle(IS_BUSY_LOW);
+        self.interface.dual_spi_uc8179(spi, self.dual_spi_mode())
+    }
+
+    /// Whether the frame data is sent on two data lines
+    pub fn dual_spi(&self) -> bool {
+        self.dual_spi
+    }
+
+    /// Initializes the display again with the fast init of the vendor driver, or the normal
+    /// one, kept across `wake_up`
+    ///
+    /// The fast init forces the temperature the OTP has a short full waveform for, which
+    /// cuts a full refresh from about 4 s to 1.5 s. It still clears the panel, but with less
+    /// contrast and more ghosting, and a [`TemperatureOverride`] waits until it's switched
+    /// off again.
+    pub fn set_fast_init(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        enabled: bool,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.fast = enabled;
+        self.interface.wait_until_idle(IS_BUSY_LOW);
+        self.init(spi, delay)
+    }
+
+    /// Whether the display was initialized with the fast init
+    pub fn fast_init(&self) -> bool {
+        self.fast
+    }
+
+    /// Writes the waveform of a [`LutPreset`] made for this panel and switches the full
+    /// refreshes to the LUT registers
+    ///
+    /// The driver loads its own waveform again in `set_lut`.
+    ///
+    /// Panics if the preset is made for another panel.
+    pub fn set_lut_preset(
+        &mut self,
+        spi: &mut SPI,
+        preset: &LutPreset,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.interface.wait_until_idle(IS_BUSY_LOW);
+        self.cmd_with_data(spi, Command::PanelSetting, &[0x3F])?;
+        self.interface
+            .write_lut_preset(spi, Panel::Epd7in5V2, preset)
+    }
+
+    /// Uploads a frame in dual SPI mode, see [`set_dual_spi`](Epd7in5::set_dual_spi)
+    ///
+    /// The command goes over `spi`, the frame data over `dual`, which has to be the
+    /// peripheral sending two bits per clock. CS and DC are driven like for `spi`.
+    pub fn update_frame_dual<D>(
+        &mut self,
+        spi: &mut SPI,
+        dual: &mut D,
+        buffer: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>>
+    where
+        D: Write<u8, Error = SPI::Error>,
+    {
+        ensure!(self.dual_spi);
+        self.leave_partial(spi, delay)?;
+        self.use_bw_mode(spi)?;
+        self.interface
+            .frame_dual_uc8179(spi, dual, Frame::Chromatic, buffer)
     }
 
-    fn send_data(&mut self, spi: &mut SPI, data: &[u8]) -> Result<(), SPI::Error> {
-        self.interface.data(spi, data)
+    fn dual_spi_mode(&self) -> DualSpi {
+        if self.dual_spi {
+            DualSpi::Enabled
+        } else {
+            DualSpi::Disabled
+        }
+    }
+
+    fn command(&mut self, spi: &mut SPI, command: Command) -> Result<(), Error<SPI::Error>> {
+        self.interface.cmd(spi, command)
     }
 
     fn cmd_with_data(
@@ -224,38 +503,422 @@ where
         spi: &mut SPI,
         command: Command,
         data: &[u8],
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.interface.cmd_with_data(spi, command, data)
     }
 
-    fn wait_until_idle(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error> {
-        while self.interface.is_busy(IS_BUSY_LOW) {
-            self.interface.cmd(spi, Command::GetStatus)?;
-            delay.delay_ms(20);
+    fn wait_until_idle(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.interface.wait_uc8179(spi, delay)
+    }
+
+    /// Switches to the LUTs from the registers and writes the quick ones
+    fn write_quick_lut(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        self.cmd_with_data(spi, Command::PanelSetting, &[0x3F])?;
+        self.cmd_with_data(spi, Command::LutForVcom, &LUT_VCOM_QUICK)?;
+        // in the black/white mode the LUTs are used for the transitions
+        // white to white, black to white, white to black and black to black
+        self.cmd_with_data(spi, Command::LutBlack, &LUT_WW_QUICK)?;
+        self.cmd_with_data(spi, Command::LutWhite, &LUT_BW_QUICK)?;
+        self.cmd_with_data(spi, Command::LutGray1, &LUT_WB_QUICK)?;
+        self.cmd_with_data(spi, Command::LutGray2, &LUT_BB_QUICK)
+    }
+
+    /// Switches back to black/white frames, the gray mode changed several registers
+    fn use_bw_mode(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        if self.mode == GrayMode::Gray4 {
+            let soft_start = self.soft_start();
+            self.cmd_with_data(spi, Command::BoosterSoftStart, &soft_start)?;
+            self.use_temperature(spi)?;
+            if self.refresh == RefreshLut::Quick {
+                self.write_quick_lut(spi)?;
+            }
+            self.mode = GrayMode::Gray1;
         }
         Ok(())
     }
 
-    fn send_resolution(&mut self, spi: &mut SPI) -> Result<(), SPI::Error> {
-        let w = self.width();
-        let h = self.height();
+    /// Booster soft start of black/white frames
+    fn soft_start(&self) -> [u8; 4] {
+        if self.fast {
+            FAST_SOFT_START
+        } else {
+            SOFT_START
+        }
+    }
 
-        self.command(spi, Command::TconResolution)?;
-        self.send_data(spi, &[(w >> 8) as u8])?;
-        self.send_data(spi, &[w as u8])?;
-        self.send_data(spi, &[(h >> 8) as u8])?;
-        self.send_data(spi, &[h as u8])
+    /// Chooses the waveform for the temperature of the sensor or the override, or the fast
+    /// waveform
+    fn use_temperature(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        let forced = match (self.fast, self.temperature) {
+            (true, _) => Some(FAST_TEMPERATURE),
+            (false, celsius) => celsius.map(|celsius| celsius as u8),
+        };
+        match forced {
+            Some(temperature) => {
+                self.cmd_with_data(spi, Command::CascadeSetting, &[0x02])?;
+                self.cmd_with_data(spi, Command::ForceTemperature, &[temperature])
+            }
+            None => self.cmd_with_data(spi, Command::CascadeSetting, &[0x00]),
+        }
+    }
+
+    /// Switches to the fast waveform and limits the writes and the refresh to a window
+    fn enter_partial(
+        &mut self,
+        spi: &mut SPI,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.interface.wait_until_idle(IS_BUSY_LOW);
+        self.use_bw_mode(spi)?;
+
+        if !self.partial {
+            // the vendor driver fixes the temperature to get the fast waveform
+            self.cmd_with_data(spi, Command::CascadeSetting, &[0x02])?;
+            self.cmd_with_data(spi, Command::ForceTemperature, &[0x6E])?;
+            // floating border, and the new frame is copied to the old one after the refresh
+            self.cmd_with_data(spi, Command::VcomAndDataIntervalSetting, &[0xA9, 0x07])?;
+        }
+        self.interface.partial_in_uc8179(spi, x, y, width, height)?;
+        self.partial = true;
+        Ok(())
+    }
+
+    /// Waits for the refresh of the window and goes back to full frames, if in partial mode
+    fn leave_partial(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle(spi, delay)?;
+        self.partial_out(spi)
+    }
+
+    /// Goes back to full frames, if in partial mode
+    fn partial_out(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        if self.partial {
+            self.command(spi, Command::PartialOut)?;
+            self.use_temperature(spi)?;
+            self.cmd_with_data(spi, Command::VcomAndDataIntervalSetting, &[0x10, 0x07])?;
+            self.partial = false;
+        }
+        Ok(())
+    }
+
+    /// Starts a refresh, powering the drivers on again after `power_down`
+    fn start_refresh(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        if self.powered_down {
+            self.command(spi, Command::PowerOn)?;
+            self.wait_until_idle(spi, delay)?;
+            // the border of a partial refresh stays floating
+            if !self.partial {
+                self.cmd_with_data(spi, Command::VcomAndDataIntervalSetting, &[0x10, 0x07])?;
+            }
+            self.powered_down = false;
+        }
+        self.command(spi, Command::DisplayRefresh)
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> PowerDown<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd7in5<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn power_down(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        // no delay at hand, poll the busy line without asking for the status
+        self.interface.wait_until_idle(IS_BUSY_LOW);
+        self.partial_out(spi)?;
+        // border floating, restored by the next refresh
+        self.cmd_with_data(spi, Command::VcomAndDataIntervalSetting, &[0x90, 0x07])?;
+        self.command(spi, Command::PowerOff)?;
+        self.interface.wait_until_idle(IS_BUSY_LOW);
+        self.powered_down = true;
+        Ok(())
+    }
+
+    fn is_powered_down(&self) -> bool {
+        self.powered_down
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> TemperatureOverride<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd7in5<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    /// Forces the temperature with the cascade setting, applied right away unless a gray
+    /// frame, a partial window or the fast init holds its own fixed temperature
+    fn set_temperature(
+        &mut self,
+        spi: &mut SPI,
+        celsius: Option<i8>,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.temperature = celsius;
+        self.interface.wait_until_idle(IS_BUSY_LOW);
+        if self.mode == GrayMode::Gray1 && !self.partial {
+            self.use_temperature(spi)?;
+        }
+        Ok(())
+    }
+
+    fn temperature_override(&self) -> Option<i8> {
+        self.temperature
     }
 }
 
+impl<SPI, CS, BUSY, DC, RST, DELAY> QuickRefresh<SPI, CS, BUSY, DC, RST, DELAY>
+    for Epd7in5<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    /// Writes the frame the next refresh starts from
+    fn update_old_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(!self.dual_spi);
+        self.leave_partial(spi, delay)?;
+        self.use_bw_mode(spi)?;
+        self.interface.frame_uc8179(spi, Frame::Black, buffer)
+    }
+
+    /// Writes the frame which is shown with the next `display_new_frame`, same as `update_frame`
+    fn update_new_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.update_frame(spi, buffer, delay)
+    }
+
+    /// Refreshes the display, only the window if the new frame was a partial one
+    fn display_new_frame(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.display_frame(spi, delay)
+    }
+
+    /// Updates and displays the new frame, which becomes the old frame afterwards.
+    fn update_and_display_new_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.update_new_frame(spi, buffer, delay)?;
+        self.display_new_frame(spi, delay)?;
+        self.update_old_frame(spi, buffer, delay)
+    }
+
+    fn update_partial_old_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(!self.dual_spi && window_fits(x, y, width, height));
+        ensure!(buffer.len() as u32 == width / 8 * height);
+        self.enter_partial(spi, x, y, width, height)?;
+        self.interface.frame_uc8179(spi, Frame::Black, buffer)
+    }
+
+    fn update_partial_new_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.update_partial_frame(spi, buffer, x, y, width, height)
+    }
+
+    fn clear_partial_frame(
+        &mut self,
+        spi: &mut SPI,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(!self.dual_spi && window_fits(x, y, width, height));
+        self.enter_partial(spi, x, y, width, height)?;
+        let color = self.color.get_byte_value();
+        for frame in [Frame::Black, Frame::Chromatic] {
+            self.interface
+                .fill_uc8179(spi, frame, color, width / 8 * height)?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether a partial window is aligned to bytes and lies on the panel
+fn window_fits(x: u32, y: u32, width: u32, height: u32) -> bool {
+    x.is_multiple_of(8)
+        && width.is_multiple_of(8)
+        && span_fits(x, width, WIDTH)
+        && span_fits(y, height, HEIGHT)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::bus_log::{commands, data_of, Log, Pin, Spi};
+    use embedded_hal_mock::delay::MockNoop;
 
     #[test]
     fn epd_size() {
         assert_eq!(WIDTH, 800);
         assert_eq!(HEIGHT, 480);
         assert_eq!(DEFAULT_BACKGROUND_COLOR, Color::White);
+        assert_eq!(NUM_GRAY_BYTES, 96_000);
+    }
+
+    #[test]
+    fn partial_windows() {
+        assert!(window_fits(0, 0, WIDTH, HEIGHT));
+        assert!(window_fits(16, 10, 64, 20));
+        assert!(!window_fits(4, 10, 64, 20));
+        assert!(!window_fits(16, 10, 60, 20));
+        assert!(!window_fits(16, 10, 0, 20));
+        assert!(!window_fits(760, 0, 48, 20));
+        assert!(!window_fits(0, 470, 8, 20));
+        assert!(!window_fits(u32::MAX - 7, 0, 8, 20));
+        assert!(!window_fits(0, u32::MAX, 8, 2));
+    }
+
+    #[test]
+    fn quick_lut_frames() {
+        // the frames of the 4 phases times the repeats of every group
+        let frames: u32 = LUT_VCOM_QUICK
+            .chunks(6)
+            .map(|group| {
+                group[1..5].iter().map(|&f| u32::from(f)).sum::<u32>() * u32::from(group[5])
+            })
+            .sum();
+        assert_eq!(frames, 124);
+        for lut in [&LUT_WW_QUICK, &LUT_BW_QUICK, &LUT_WB_QUICK, &LUT_BB_QUICK] {
+            for (group, vcom) in lut.chunks(6).zip(LUT_VCOM_QUICK.chunks(6)) {
+                assert_eq!(group[1..], vcom[1..]);
+            }
+        }
+    }
+
+    #[test]
+    fn fast_preset() {
+        let log = Log::default();
+        let mut spi = Spi(&log);
+        let mut delay = MockNoop::new();
+        let mut epd = Epd7in5::new(
+            &mut spi,
+            Pin(None),
+            Pin(None),
+            Pin(Some(&log)),
+            Pin(None),
+            &mut delay,
+        )
+        .unwrap();
+        log.take();
+        let preset = crate::luts::by_name("7in5_v2/fast").unwrap();
+        epd.set_lut_preset(&mut spi, preset).unwrap();
+        let events = log.take();
+        assert_eq!(commands(&events), [0x00, 0x20, 0x21, 0x22, 0x23, 0x24]);
+        assert_eq!(data_of(&events, 0x00), [[0x3F]]);
+        assert_eq!(data_of(&events, 0x20), [LUT_VCOM_QUICK]);
+        assert_eq!(data_of(&events, 0x24), [LUT_BB_QUICK]);
+    }
+
+    #[test]
+    fn refresh_after_power_down() {
+        let log = Log::default();
+        let mut spi = Spi(&log);
+        let mut delay = MockNoop::new();
+        let mut epd = Epd7in5::new(
+            &mut spi,
+            Pin(None),
+            Pin(None),
+            Pin(Some(&log)),
+            Pin(None),
+            &mut delay,
+        )
+        .unwrap();
+
+        log.take();
+        epd.power_down(&mut spi).unwrap();
+        assert!(epd.is_powered_down());
+        let events = log.take();
+        assert_eq!(commands(&events), [0x50, 0x02]);
+        assert_eq!(data_of(&events, 0x50), [[0x90, 0x07]]);
+
+        // power on and the border again before the refresh
+        epd.display_frame(&mut spi, &mut delay).unwrap();
+        assert!(!epd.is_powered_down());
+        let events = log.take();
+        assert_eq!(commands(&events), [0x04, 0x50, 0x12]);
+        assert_eq!(data_of(&events, 0x50), [[0x10, 0x07]]);
+
+        epd.display_frame(&mut spi, &mut delay).unwrap();
+        assert_eq!(commands(&log.take()), [0x12]);
+
+        // a partial refresh keeps its floating border
+        epd.power_down(&mut spi).unwrap();
+        epd.update_partial_frame(&mut spi, &[0; 8 * 2], 8, 8, 64, 2)
+            .unwrap();
+        log.take();
+        epd.display_frame(&mut spi, &mut delay).unwrap();
+        let events = log.take();
+        assert_eq!(commands(&events)[..2], [0x04, 0x12]);
+        assert_eq!(data_of(&events, 0x50), [[0x10, 0x07]]);
+    }
+
+    #[test]
+    fn custom_lut_is_rejected() {
+        let log = Log::default();
+        let mut spi = Spi(&log);
+        let mut delay = MockNoop::new();
+        let mut epd = Epd7in5::new(
+            &mut spi,
+            Pin(None),
+            Pin(None),
+            Pin(Some(&log)),
+            Pin(None),
+            &mut delay,
+        )
+        .unwrap();
+        log.take();
+        let preset = &crate::luts::PRESETS[0];
+        assert_eq!(
+            epd.set_lut(&mut spi, Some(RefreshLut::Custom(preset))),
+            Err(Error::Unsupported)
+        );
+        assert!(log.take().is_empty());
+        assert_eq!(epd.refresh, RefreshLut::Full);
     }
 }
@@ -0,0 +1,23 @@
+//! The error returned by the drivers
+
+/// Errors of the drivers
+///
+/// Besides the failures of the SPI peripheral, the drivers reject calls they can't carry
+/// out. Without the `panic_free` feature most of these calls panic instead, which points
+/// at the mistake during development.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error<E> {
+    /// The SPI peripheral returned an error
+    Spi(E),
+    /// An argument breaks a precondition, like a partial window reaching past the panel or
+    /// a buffer of the wrong length
+    InvalidArgument,
+    /// The display doesn't support the call
+    Unsupported,
+}
+
+impl<E> From<E> for Error<E> {
+    fn from(error: E) -> Self {
+        Error::Spi(error)
+    }
+}
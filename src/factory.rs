@@ -0,0 +1,429 @@
+//! Choosing the driver at runtime
+//!
+//! Firmware supporting several panels usually learns which one is attached from a
+//! configuration byte, e.g. in the EEPROM of the board. Every driver has its own type,
+//! so [`Panel`] names the supported ones with a fixed number and, with the `alloc`
+//! feature, [`create`] instantiates the matching driver behind a [`DynEpd`]:
+//!
+//!```rust, no_run
+//!# use embedded_hal_mock::*;
+//!# #[cfg(not(feature = "alloc"))]
+//!# fn main() {}
+//!# #[cfg(feature = "alloc")]
+//!# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
+//!use epd_waveshare::factory::{self, Panel, Pins};
+//!#
+//!# let expectations = [];
+//!# let mut spi = spi::Mock::new(&expectations);
+//!# let expectations = [];
+//!# let cs = pin::Mock::new(&expectations);
+//!# let busy = pin::Mock::new(&expectations);
+//!# let dc = pin::Mock::new(&expectations);
+//!# let rst = pin::Mock::new(&expectations);
+//!# let mut delay = delay::MockNoop::new();
+//!# let config_byte = Panel::Epd7in5V2 as u8;
+//!
+//!let panel = Panel::from_byte(config_byte).expect("unknown panel");
+//!let pins = Pins { cs, busy, dc, rst };
+//!let mut epd = factory::create(panel, &mut spi, pins, &mut delay)?;
+//!
+//!let frame = vec![0xFF; epd_waveshare::buffer_len(epd.width() as usize, epd.height() as usize)];
+//!epd.update_and_display_frame(&mut spi, &frame, &mut delay)?;
+//!epd.sleep(&mut spi, &mut delay)?;
+//!# Ok(())
+//!# }
+//!```
+//!
+//! The 12.48" isn't listed, since it is wired with more pins than the other panels.
+
+#[cfg(feature = "alloc")]
+use alloc::boxed::Box;
+#[cfg(feature = "alloc")]
+use core::marker::PhantomData;
+
+use embedded_hal::blocking::spi::Write;
+#[cfg(feature = "alloc")]
+use embedded_hal::{
+    blocking::delay::DelayMs,
+    digital::v2::{InputPin, OutputPin},
+};
+
+use crate::traits::RefreshLut;
+#[cfg(feature = "alloc")]
+use crate::traits::WaveshareDisplay;
+use crate::Error;
+#[cfg(feature = "alloc")]
+use crate::{
+    epd10in2, epd13in3k, epd1in02, epd1in54, epd1in54_v2, epd1in54b, epd1in54c, epd1in64g,
+    epd2in13_v2, epd2in13_v4, epd2in13bc, epd2in13g, epd2in36g, epd2in66b, epd2in7_v2, epd2in7b,
+    epd2in9, epd2in9_v2, epd2in9b_v4, epd2in9bc, epd3in0g, epd3in52, epd3in7, epd4in01f, epd4in2,
+    epd4in26, epd4in2_v2, epd4in2bc_v2, epd4in37g, epd5in65f, epd5in79, epd5in83_v2, epd5in83bc_v2,
+    epd7in3f, epd7in3g, epd7in5, epd7in5_hd, epd7in5_v2, epd7in5b_v2,
+};
+
+/// The panels which can be chosen at runtime
+///
+/// The numbers don't change between releases, so they can be stored in a configuration.
+/// 0 and 0xFF, the content of erased memories, are never used.
+#[allow(missing_docs)]
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Panel {
+    Epd1in02 = 1,
+    Epd1in54 = 2,
+    Epd1in54V2 = 3,
+    Epd1in54b = 4,
+    Epd1in54c = 5,
+    Epd1in64g = 6,
+    Epd2in13V2 = 7,
+    Epd2in13bc = 8,
+    Epd2in36g = 9,
+    Epd2in66b = 10,
+    Epd2in7V2 = 11,
+    Epd2in7b = 12,
+    Epd2in9 = 13,
+    Epd2in9V2 = 14,
+    Epd2in9bV4 = 15,
+    Epd2in9bc = 16,
+    Epd3in0g = 17,
+    Epd3in52 = 18,
+    Epd3in7 = 19,
+    Epd4in01f = 20,
+    Epd4in2 = 21,
+    Epd4in26 = 22,
+    Epd4in2V2 = 23,
+    Epd4in2bcV2 = 24,
+    Epd4in37g = 25,
+    Epd5in65f = 26,
+    Epd5in79 = 27,
+    Epd5in83V2 = 28,
+    Epd5in83bcV2 = 29,
+    Epd7in3f = 30,
+    Epd7in3g = 31,
+    Epd7in5 = 32,
+    Epd7in5Hd = 33,
+    Epd7in5V2 = 34,
+    Epd7in5bV2 = 35,
+    Epd10in2 = 36,
+    Epd13in3k = 37,
+    Epd2in13g = 38,
+    Epd2in13V4 = 39,
+}
+
+impl Panel {
+    /// All panels, in the order of their numbers
+    pub const ALL: [Panel; 39] = [
+        Panel::Epd1in02,
+        Panel::Epd1in54,
+        Panel::Epd1in54V2,
+        Panel::Epd1in54b,
+        Panel::Epd1in54c,
+        Panel::Epd1in64g,
+        Panel::Epd2in13V2,
+        Panel::Epd2in13bc,
+        Panel::Epd2in36g,
+        Panel::Epd2in66b,
+        Panel::Epd2in7V2,
+        Panel::Epd2in7b,
+        Panel::Epd2in9,
+        Panel::Epd2in9V2,
+        Panel::Epd2in9bV4,
+        Panel::Epd2in9bc,
+        Panel::Epd3in0g,
+        Panel::Epd3in52,
+        Panel::Epd3in7,
+        Panel::Epd4in01f,
+        Panel::Epd4in2,
+        Panel::Epd4in26,
+        Panel::Epd4in2V2,
+        Panel::Epd4in2bcV2,
+        Panel::Epd4in37g,
+        Panel::Epd5in65f,
+        Panel::Epd5in79,
+        Panel::Epd5in83V2,
+        Panel::Epd5in83bcV2,
+        Panel::Epd7in3f,
+        Panel::Epd7in3g,
+        Panel::Epd7in5,
+        Panel::Epd7in5Hd,
+        Panel::Epd7in5V2,
+        Panel::Epd7in5bV2,
+        Panel::Epd10in2,
+        Panel::Epd13in3k,
+        Panel::Epd2in13g,
+        Panel::Epd2in13V4,
+    ];
+
+    /// The panel with the number `byte`, if there is one
+    pub fn from_byte(byte: u8) -> Option<Panel> {
+        Panel::ALL
+            .iter()
+            .copied()
+            .find(|&panel| panel as u8 == byte)
+    }
+}
+
+/// The pins every panel besides the 12.48" is connected with
+pub struct Pins<CS, BUSY, DC, RST> {
+    /// Chip select
+    pub cs: CS,
+    /// Busy line of the controller
+    pub busy: BUSY,
+    /// Data/command selection
+    pub dc: DC,
+    /// Reset
+    pub rst: RST,
+}
+
+/// The calls of [`WaveshareDisplay`] which don't depend on the type of the driver
+///
+/// The colors differ between the panels, so the buffers are passed as bytes and the
+/// background color of the driver isn't accessible.
+pub trait DynEpd<SPI: Write<u8>, DELAY> {
+    /// The panel the driver was created for
+    fn panel(&self) -> Panel;
+
+    /// See [`WaveshareDisplay::width`]
+    fn width(&self) -> u32;
+
+    /// See [`WaveshareDisplay::height`]
+    fn height(&self) -> u32;
+
+    /// See [`WaveshareDisplay::sleep`]
+    fn sleep(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>>;
+
+    /// See [`WaveshareDisplay::wake_up`]
+    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>>;
+
+    /// See [`WaveshareDisplay::update_frame`]
+    fn update_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>>;
+
+    /// See [`WaveshareDisplay::display_frame`]
+    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>>;
+
+    /// See [`WaveshareDisplay::start_display_frame`]
+    fn start_display_frame(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>>;
+
+    /// See [`WaveshareDisplay::is_refresh_complete`]
+    fn is_refresh_complete(&self) -> bool;
+
+    /// See [`WaveshareDisplay::finish_refresh`]
+    fn finish_refresh(&mut self, spi: &mut SPI, delay: &mut DELAY)
+        -> Result<(), Error<SPI::Error>>;
+
+    /// See [`WaveshareDisplay::update_and_display_frame`]
+    fn update_and_display_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>>;
+
+    /// See [`WaveshareDisplay::clear_frame`]
+    fn clear_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>>;
+
+    /// See [`WaveshareDisplay::set_lut`]
+    fn set_lut(
+        &mut self,
+        spi: &mut SPI,
+        refresh_rate: Option<RefreshLut>,
+    ) -> Result<(), Error<SPI::Error>>;
+
+    /// See [`WaveshareDisplay::is_busy`]
+    fn is_busy(&self) -> bool;
+}
+
+/// A driver with the pin types moved out of the trait
+#[cfg(feature = "alloc")]
+struct Erased<EPD, CS, BUSY, DC, RST> {
+    epd: EPD,
+    panel: Panel,
+    pins: PhantomData<Pins<CS, BUSY, DC, RST>>,
+}
+
+#[cfg(feature = "alloc")]
+impl<EPD, SPI, CS, BUSY, DC, RST, DELAY> DynEpd<SPI, DELAY> for Erased<EPD, CS, BUSY, DC, RST>
+where
+    EPD: WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>,
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    fn panel(&self) -> Panel {
+        self.panel
+    }
+
+    fn width(&self) -> u32 {
+        self.epd.width()
+    }
+
+    fn height(&self) -> u32 {
+        self.epd.height()
+    }
+
+    fn sleep(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.epd.sleep(spi, delay)
+    }
+
+    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.epd.wake_up(spi, delay)
+    }
+
+    fn update_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.epd.update_frame(spi, buffer, delay)
+    }
+
+    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.epd.display_frame(spi, delay)
+    }
+
+    fn start_display_frame(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.epd.start_display_frame(spi, delay)
+    }
+
+    fn is_refresh_complete(&self) -> bool {
+        self.epd.is_refresh_complete()
+    }
+
+    fn finish_refresh(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.epd.finish_refresh(spi, delay)
+    }
+
+    fn update_and_display_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.epd.update_and_display_frame(spi, buffer, delay)
+    }
+
+    fn clear_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>> {
+        self.epd.clear_frame(spi, delay)
+    }
+
+    fn set_lut(
+        &mut self,
+        spi: &mut SPI,
+        refresh_rate: Option<RefreshLut>,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.epd.set_lut(spi, refresh_rate)
+    }
+
+    fn is_busy(&self) -> bool {
+        self.epd.is_busy()
+    }
+}
+
+/// Creates and initializes the driver of `panel`
+#[cfg(feature = "alloc")]
+pub fn create<SPI, CS, BUSY, DC, RST, DELAY>(
+    panel: Panel,
+    spi: &mut SPI,
+    pins: Pins<CS, BUSY, DC, RST>,
+    delay: &mut DELAY,
+) -> Result<Box<dyn DynEpd<SPI, DELAY>>, Error<SPI::Error>>
+where
+    SPI: Write<u8> + 'static,
+    CS: OutputPin + 'static,
+    BUSY: InputPin + 'static,
+    DC: OutputPin + 'static,
+    RST: OutputPin + 'static,
+    DELAY: DelayMs<u8> + 'static,
+{
+    let Pins { cs, busy, dc, rst } = pins;
+    macro_rules! erase {
+        ($driver:ty) => {
+            Box::new(Erased {
+                epd: <$driver>::new(spi, cs, busy, dc, rst, delay)?,
+                panel,
+                pins: PhantomData,
+            }) as Box<dyn DynEpd<SPI, DELAY>>
+        };
+    }
+
+    Ok(match panel {
+        Panel::Epd1in02 => erase!(epd1in02::Epd1in02<SPI, CS, BUSY, DC, RST, DELAY>),
+        Panel::Epd1in54 => erase!(epd1in54::Epd1in54<SPI, CS, BUSY, DC, RST, DELAY>),
+        Panel::Epd1in54V2 => erase!(epd1in54_v2::Epd1in54<SPI, CS, BUSY, DC, RST, DELAY>),
+        Panel::Epd1in54b => erase!(epd1in54b::Epd1in54b<SPI, CS, BUSY, DC, RST, DELAY>),
+        Panel::Epd1in54c => erase!(epd1in54c::Epd1in54c<SPI, CS, BUSY, DC, RST, DELAY>),
+        Panel::Epd1in64g => erase!(epd1in64g::Epd1in64g<SPI, CS, BUSY, DC, RST, DELAY>),
+        Panel::Epd2in13V2 => erase!(epd2in13_v2::Epd2in13<SPI, CS, BUSY, DC, RST, DELAY>),
+        Panel::Epd2in13bc => erase!(epd2in13bc::Epd2in13bc<SPI, CS, BUSY, DC, RST, DELAY>),
+        Panel::Epd2in36g => erase!(epd2in36g::Epd2in36g<SPI, CS, BUSY, DC, RST, DELAY>),
+        Panel::Epd2in66b => erase!(epd2in66b::Epd2in66b<SPI, CS, BUSY, DC, RST, DELAY>),
+        Panel::Epd2in7V2 => erase!(epd2in7_v2::Epd2in7<SPI, CS, BUSY, DC, RST, DELAY>),
+        Panel::Epd2in7b => erase!(epd2in7b::Epd2in7b<SPI, CS, BUSY, DC, RST, DELAY>),
+        Panel::Epd2in9 => erase!(epd2in9::Epd2in9<SPI, CS, BUSY, DC, RST, DELAY>),
+        Panel::Epd2in9V2 => erase!(epd2in9_v2::Epd2in9<SPI, CS, BUSY, DC, RST, DELAY>),
+        Panel::Epd2in9bV4 => erase!(epd2in9b_v4::Epd2in9bV4<SPI, CS, BUSY, DC, RST, DELAY>),
+        Panel::Epd2in9bc => erase!(epd2in9bc::Epd2in9bc<SPI, CS, BUSY, DC, RST, DELAY>),
+        Panel::Epd3in0g => erase!(epd3in0g::Epd3in0g<SPI, CS, BUSY, DC, RST, DELAY>),
+        Panel::Epd3in52 => erase!(epd3in52::Epd3in52<SPI, CS, BUSY, DC, RST, DELAY>),
+        Panel::Epd3in7 => erase!(epd3in7::Epd3in7<SPI, CS, BUSY, DC, RST, DELAY>),
+        Panel::Epd4in01f => erase!(epd4in01f::Epd4in01f<SPI, CS, BUSY, DC, RST, DELAY>),
+        Panel::Epd4in2 => erase!(epd4in2::Epd4in2<SPI, CS, BUSY, DC, RST, DELAY>),
+        Panel::Epd4in26 => erase!(epd4in26::Epd4in26<SPI, CS, BUSY, DC, RST, DELAY>),
+        Panel::Epd4in2V2 => erase!(epd4in2_v2::Epd4in2<SPI, CS, BUSY, DC, RST, DELAY>),
+        Panel::Epd4in2bcV2 => erase!(epd4in2bc_v2::Epd4in2bcV2<SPI, CS, BUSY, DC, RST, DELAY>),
+        Panel::Epd4in37g => erase!(epd4in37g::Epd4in37g<SPI, CS, BUSY, DC, RST, DELAY>),
+        Panel::Epd5in65f => erase!(epd5in65f::Epd5in65f<SPI, CS, BUSY, DC, RST, DELAY>),
+        Panel::Epd5in79 => erase!(epd5in79::Epd5in79<SPI, CS, BUSY, DC, RST, DELAY>),
+        Panel::Epd5in83V2 => erase!(epd5in83_v2::Epd5in83<SPI, CS, BUSY, DC, RST, DELAY>),
+        Panel::Epd5in83bcV2 => {
+            erase!(epd5in83bc_v2::Epd5in83bcV2<SPI, CS, BUSY, DC, RST, DELAY>)
+        }
+        Panel::Epd7in3f => erase!(epd7in3f::Epd7in3f<SPI, CS, BUSY, DC, RST, DELAY>),
+        Panel::Epd7in3g => erase!(epd7in3g::Epd7in3g<SPI, CS, BUSY, DC, RST, DELAY>),
+        Panel::Epd7in5 => erase!(epd7in5::Epd7in5<SPI, CS, BUSY, DC, RST, DELAY>),
+        Panel::Epd7in5Hd => erase!(epd7in5_hd::Epd7in5<SPI, CS, BUSY, DC, RST, DELAY>),
+        Panel::Epd7in5V2 => erase!(epd7in5_v2::Epd7in5<SPI, CS, BUSY, DC, RST, DELAY>),
+        Panel::Epd7in5bV2 => erase!(epd7in5b_v2::Epd7in5bV2<SPI, CS, BUSY, DC, RST, DELAY>),
+        Panel::Epd10in2 => erase!(epd10in2::Epd10in2<SPI, CS, BUSY, DC, RST, DELAY>),
+        Panel::Epd13in3k => erase!(epd13in3k::Epd13in3k<SPI, CS, BUSY, DC, RST, DELAY>),
+        Panel::Epd2in13g => erase!(epd2in13g::Epd2in13g<SPI, CS, BUSY, DC, RST, DELAY>),
+        Panel::Epd2in13V4 => erase!(epd2in13_v4::Epd2in13<SPI, CS, BUSY, DC, RST, DELAY>),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn panel_numbers() {
+        for (i, &panel) in Panel::ALL.iter().enumerate() {
+            assert_eq!(panel as u8, i as u8 + 1);
+            assert_eq!(Panel::from_byte(panel as u8), Some(panel));
+        }
+        // erased memories
+        assert_eq!(Panel::from_byte(0x00), None);
+        assert_eq!(Panel::from_byte(0xFF), None);
+    }
+}
@@ -1,14 +1,17 @@
 //! Graphics Support for EPDs
 
-use crate::buffer_len;
-use crate::color::{Color, OctColor, TriColor};
-use embedded_graphics::pixelcolor::BinaryColor;
+use crate::codec::{CodecError, FrameCodec};
+use crate::color::{Color, OctColor, QuadColor, TriColor};
+use core::convert::Infallible;
+use embedded_graphics::pixelcolor::{BinaryColor, Gray2, Gray4, GrayColor, Rgb888, RgbColor};
 use embedded_graphics_core::prelude::*;
+use embedded_graphics_core::primitives::Rectangle;
 
 /// Displayrotation
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Default)]
 pub enum DisplayRotation {
     /// No rotation
+    #[default]
     Rotate0,
     /// Rotate by 90 degrees clockwise
     Rotate90,
@@ -18,9 +21,15 @@ pub enum DisplayRotation {
     Rotate270,
 }
 
-impl Default for DisplayRotation {
-    fn default() -> Self {
-        DisplayRotation::Rotate0
+impl DisplayRotation {
+    /// The rotation turned by another 90 degrees clockwise
+    pub(crate) fn quarter_turn(self) -> DisplayRotation {
+        match self {
+            DisplayRotation::Rotate0 => DisplayRotation::Rotate90,
+            DisplayRotation::Rotate90 => DisplayRotation::Rotate180,
+            DisplayRotation::Rotate180 => DisplayRotation::Rotate270,
+            DisplayRotation::Rotate270 => DisplayRotation::Rotate0,
+        }
     }
 }
 
@@ -31,6 +40,20 @@ impl Default for DisplayRotation {
 /// - Rotations
 /// - Clearing
 pub trait Display: DrawTarget<Color = BinaryColor> {
+    /// Creates the buffer filled with `background_color`
+    ///
+    /// `Default` fills it with the `DEFAULT_BACKGROUND_COLOR` of the driver, use this to
+    /// start from the background color set on the driver instead, e.g.
+    /// `with_background(*epd.background_color())`.
+    fn with_background(background_color: Color) -> Self
+    where
+        Self: Default + Sized,
+    {
+        let mut display = Self::default();
+        display.clear_buffer(background_color);
+        display
+    }
+
     /// Clears the buffer of the display with the chosen background color
     fn clear_buffer(&mut self, background_color: Color) {
         for elem in self.get_mut_buffer().iter_mut() {
@@ -50,6 +73,106 @@ pub trait Display: DrawTarget<Color = BinaryColor> {
     /// Get the current rotation of the display
     fn rotation(&self) -> DisplayRotation;
 
+    /// Copies the buffer, e.g. before drawing a menu or an alert on top of it
+    ///
+    /// `N` is the length of the buffer, `buffer_len(WIDTH, HEIGHT)` of the display.
+    /// Panics if it differs.
+    fn snapshot<const N: usize>(&self) -> FrameSnapshot<N> {
+        let mut snapshot = FrameSnapshot { buffer: [0; N] };
+        snapshot.buffer.copy_from_slice(self.buffer());
+        snapshot
+    }
+
+    /// Puts the content of `snapshot` back into the buffer
+    ///
+    /// Returns the rows which differ from what was drawn since, so only these need
+    /// to be sent with a partial update. `None` if nothing changed.
+    fn restore<const N: usize>(&mut self, snapshot: &FrameSnapshot<N>) -> Option<ChangedRows> {
+        let row_bytes = row_bytes(self);
+        let changed = ChangedRows::between(self.buffer(), &snapshot.buffer, row_bytes);
+        self.get_mut_buffer().copy_from_slice(&snapshot.buffer);
+        changed
+    }
+
+    /// Hands the buffer to `storage`, for snapshots which don't fit into RAM twice
+    fn snapshot_to<S: SnapshotStorage>(&self, storage: &mut S) -> Result<(), S::Error> {
+        storage.store(self.buffer())
+    }
+
+    /// Loads the buffer back from `storage`, see [`restore`](Display::restore)
+    ///
+    /// The stored frame is read a row at a time.
+    fn restore_from<S: SnapshotStorage>(
+        &mut self,
+        storage: &mut S,
+    ) -> Result<Option<ChangedRows>, S::Error> {
+        let row_bytes = row_bytes(self);
+        let mut changed: Option<ChangedRows> = None;
+        let mut row = [0u8; SNAPSHOT_CHUNK];
+        for (y, current) in self.get_mut_buffer().chunks_mut(row_bytes).enumerate() {
+            let mut differs = false;
+            for (i, current) in current.chunks_mut(SNAPSHOT_CHUNK).enumerate() {
+                let stored = &mut row[..current.len()];
+                storage.load(y * row_bytes + i * SNAPSHOT_CHUNK, stored)?;
+                differs |= current != stored;
+                current.copy_from_slice(stored);
+            }
+            if differs {
+                changed = Some(ChangedRows::extend(changed, y as u32, row_bytes));
+            }
+        }
+        Ok(changed)
+    }
+
+    /// Compresses the buffer with `codec` into `output` and returns its length
+    ///
+    /// For snapshots kept in flash, where the compressed frame usually takes a
+    /// fraction of the buffer.
+    fn snapshot_compressed<C: FrameCodec>(
+        &self,
+        codec: &C,
+        output: &mut [u8],
+    ) -> Result<usize, CodecError<Infallible>> {
+        codec.encode_to_slice(self.buffer(), output)
+    }
+
+    /// Decodes a snapshot made with [`snapshot_compressed`](Display::snapshot_compressed)
+    /// straight into the buffer, see [`restore`](Display::restore)
+    ///
+    /// Returns [`CodecError::Corrupt`] if `stored` doesn't decode to exactly one
+    /// buffer, which is then partly overwritten.
+    fn restore_compressed<C: FrameCodec>(
+        &mut self,
+        codec: &C,
+        stored: &[u8],
+    ) -> Result<Option<ChangedRows>, CodecError<Infallible>> {
+        let row_bytes = row_bytes(self);
+        let buffer = self.get_mut_buffer();
+        let mut changed: Option<ChangedRows> = None;
+        let mut offset = 0;
+        codec
+            .decode(stored, |chunk| {
+                let current = buffer.get_mut(offset..offset + chunk.len()).ok_or(())?;
+                for (i, (current, &stored)) in current.iter_mut().zip(chunk).enumerate() {
+                    if *current != stored {
+                        let y = ((offset + i) / row_bytes) as u32;
+                        changed = Some(ChangedRows::extend(changed, y, row_bytes));
+                        *current = stored;
+                    }
+                }
+                offset += chunk.len();
+                Ok(())
+            })
+            .map_err(|err| match err {
+                CodecError::Sink(()) | CodecError::BufferTooSmall => CodecError::Corrupt,
+                CodecError::Corrupt => CodecError::Corrupt,
+            })?;
+        if offset != buffer.len() {
+            return Err(CodecError::Corrupt);
+        }
+        Ok(changed)
+    }
+
     /// Helperfunction for the Embedded Graphics draw trait
     ///
     /// Becomes uneccesary when const_generics become stablised
@@ -71,15 +194,20 @@ pub trait Display: DrawTarget<Color = BinaryColor> {
         let (index, bit) = find_position(point.x as u32, point.y as u32, width, height, rotation);
         let index = index as usize;
 
+        // a buffer shorter than the display (e.g. of a `VarDisplay`) just misses the pixel
+        let Some(byte) = buffer.get_mut(index) else {
+            return Ok(());
+        };
+
         // "Draw" the Pixel on that bit
         match color {
             // Black
             BinaryColor::On => {
-                buffer[index] &= !bit;
+                *byte &= !bit;
             }
             // White
             BinaryColor::Off => {
-                buffer[index] |= bit;
+                *byte |= bit;
             }
         }
         Ok(())
@@ -93,11 +221,26 @@ pub trait Display: DrawTarget<Color = BinaryColor> {
 /// - Rotations
 /// - Clearing
 pub trait TriDisplay: DrawTarget<Color = TriColor> {
+    /// Creates the buffer filled with `background_color`, see [`Display::with_background`]
+    fn with_background(background_color: TriColor) -> Self
+    where
+        Self: Default + Sized,
+    {
+        let mut display = Self::default();
+        display.clear_buffer(background_color);
+        display
+    }
+
     /// Clears the buffer of the display with the chosen background color
+    ///
+    /// Both the b/w and the chromatic part are filled, so every color clears to itself.
     fn clear_buffer(&mut self, background_color: TriColor) {
-        for elem in self.get_mut_buffer().iter_mut() {
-            *elem = background_color.get_byte_value();
-        }
+        let (bw, chromatic) = background_color.get_buffer_bytes();
+        let offset = self.chromatic_offset();
+        let buffer = self.get_mut_buffer();
+        let (bw_part, chromatic_part) = buffer.split_at_mut(offset.min(buffer.len()));
+        bw_part.fill(bw);
+        chromatic_part.fill(chromatic);
     }
 
     /// Returns the buffer
@@ -121,6 +264,19 @@ pub trait TriDisplay: DrawTarget<Color = TriColor> {
     /// return the chromatic part of the buffer
     fn chromatic_buffer(&self) -> &[u8];
 
+    /// Number of chromatic pixels in the buffer
+    ///
+    /// Counts the cleared bits of the chromatic part, so the padding of rows which aren't
+    /// a multiple of 8 wide counts too if it was cleared to the chromatic color. Used to
+    /// scale the busy timeout with
+    /// [`RefreshTiming::chromatic_timeout_ms`](crate::calibration::RefreshTiming::chromatic_timeout_ms).
+    fn chromatic_pixels(&self) -> u32 {
+        self.chromatic_buffer()
+            .iter()
+            .map(|byte| byte.count_zeros())
+            .sum()
+    }
+
     /// Helperfunction for the Embedded Graphics draw trait
     ///
     /// Becomes uneccesary when const_generics become stablised
@@ -143,6 +299,9 @@ pub trait TriDisplay: DrawTarget<Color = TriColor> {
         let offset = self.chromatic_offset();
 
         let buffer = self.get_mut_buffer();
+        if index + offset >= buffer.len() {
+            return Ok(());
+        }
 
         // "Draw" the Pixel on that bit
         match color {
@@ -176,6 +335,16 @@ pub trait TriDisplay: DrawTarget<Color = TriColor> {
 /// - Rotations
 /// - Clearing
 pub trait OctDisplay: DrawTarget<Color = OctColor> {
+    /// Creates the buffer filled with `background_color`, see [`Display::with_background`]
+    fn with_background(background_color: OctColor) -> Self
+    where
+        Self: Default + Sized,
+    {
+        let mut display = Self::default();
+        display.clear_buffer(background_color);
+        display
+    }
+
     /// Clears the buffer of the display with the chosen background color
     fn clear_buffer(&mut self, background_color: OctColor) {
         for elem in self.get_mut_buffer().iter_mut() {
@@ -223,7 +392,208 @@ pub trait OctDisplay: DrawTarget<Color = OctColor> {
         } else {
             (0xf0, color.get_nibble())
         };
-        buffer[index] = (buffer[index] & mask) | color_nibble;
+        if let Some(byte) = buffer.get_mut(index) {
+            *byte = (*byte & mask) | color_nibble;
+        }
+        Ok(())
+    }
+}
+
+/// Necessary traits for all displays with 4 gray levels
+///
+/// The buffer holds 4 pixels per byte, starting with the most significant bits.
+/// Every pixel is stored as its `Gray2` luma value, so `0b11` is white and `0b00` black.
+pub trait GrayDisplay: DrawTarget<Color = Gray2> {
+    /// Creates the buffer filled with `background_color`, see [`Display::with_background`]
+    fn with_background(background_color: Gray2) -> Self
+    where
+        Self: Default + Sized,
+    {
+        let mut display = Self::default();
+        display.clear_buffer(background_color);
+        display
+    }
+
+    /// Clears the buffer of the display with the chosen background color
+    fn clear_buffer(&mut self, background_color: Gray2) {
+        let luma = background_color.luma();
+        let byte = luma << 6 | luma << 4 | luma << 2 | luma;
+        for elem in self.get_mut_buffer().iter_mut() {
+            *elem = byte;
+        }
+    }
+
+    /// Returns the buffer
+    fn buffer(&self) -> &[u8];
+
+    /// Returns a mutable buffer
+    fn get_mut_buffer(&mut self) -> &mut [u8];
+
+    /// Sets the rotation of the display
+    fn set_rotation(&mut self, rotation: DisplayRotation);
+
+    /// Get the current rotation of the display
+    fn rotation(&self) -> DisplayRotation;
+
+    /// Helperfunction for the Embedded Graphics draw trait
+    ///
+    /// Becomes uneccesary when const_generics become stablised
+    fn draw_helper(
+        &mut self,
+        width: u32,
+        height: u32,
+        pixel: Pixel<Gray2>,
+    ) -> Result<(), Self::Error> {
+        let rotation = self.rotation();
+        let buffer = self.get_mut_buffer();
+
+        let Pixel(point, color) = pixel;
+        if outside_display(point, width, height, rotation) {
+            return Ok(());
+        }
+
+        // Give us index inside the buffer and the shift of the pixel inside that u8
+        let (index, shift) =
+            find_gray2_position(point.x as u32, point.y as u32, width, height, rotation);
+        let index = index as usize;
+
+        // "Draw" the Pixel on those two bits
+        if let Some(byte) = buffer.get_mut(index) {
+            *byte = (*byte & !(0b11 << shift)) | (color.luma() << shift);
+        }
+        Ok(())
+    }
+}
+
+/// Necessary traits for all displays with 16 gray levels
+///
+/// The buffer holds 2 pixels per byte, starting with the most significant nibble.
+/// Every pixel is stored as its `Gray4` luma value, so `0xF` is white and `0x0` black.
+pub trait Gray4Display: DrawTarget<Color = Gray4> {
+    /// Creates the buffer filled with `background_color`, see [`Display::with_background`]
+    fn with_background(background_color: Gray4) -> Self
+    where
+        Self: Default + Sized,
+    {
+        let mut display = Self::default();
+        display.clear_buffer(background_color);
+        display
+    }
+
+    /// Clears the buffer of the display with the chosen background color
+    fn clear_buffer(&mut self, background_color: Gray4) {
+        let luma = background_color.luma();
+        for elem in self.get_mut_buffer().iter_mut() {
+            *elem = luma << 4 | luma;
+        }
+    }
+
+    /// Returns the buffer
+    fn buffer(&self) -> &[u8];
+
+    /// Returns a mutable buffer
+    fn get_mut_buffer(&mut self) -> &mut [u8];
+
+    /// Sets the rotation of the display
+    fn set_rotation(&mut self, rotation: DisplayRotation);
+
+    /// Get the current rotation of the display
+    fn rotation(&self) -> DisplayRotation;
+
+    /// Helperfunction for the Embedded Graphics draw trait
+    ///
+    /// Becomes uneccesary when const_generics become stablised
+    fn draw_helper(
+        &mut self,
+        width: u32,
+        height: u32,
+        pixel: Pixel<Gray4>,
+    ) -> Result<(), Self::Error> {
+        let rotation = self.rotation();
+        let buffer = self.get_mut_buffer();
+
+        let Pixel(point, color) = pixel;
+        if outside_display(point, width, height, rotation) {
+            return Ok(());
+        }
+
+        // Same packing as the 7 colors
+        let (index, upper) =
+            find_oct_position(point.x as u32, point.y as u32, width, height, rotation);
+        let index = index as usize;
+
+        // "Draw" the Pixel on that nibble
+        let (mask, nibble) = if upper {
+            (0x0f, color.luma() << 4)
+        } else {
+            (0xf0, color.luma())
+        };
+        if let Some(byte) = buffer.get_mut(index) {
+            *byte = (*byte & mask) | nibble;
+        }
+        Ok(())
+    }
+}
+
+/// Necessary traits for all 4 color displays of the G-series
+///
+/// The buffer holds 4 pixels per byte, starting with the most significant bits.
+pub trait QuadDisplay: DrawTarget<Color = QuadColor> {
+    /// Creates the buffer filled with `background_color`, see [`Display::with_background`]
+    fn with_background(background_color: QuadColor) -> Self
+    where
+        Self: Default + Sized,
+    {
+        let mut display = Self::default();
+        display.clear_buffer(background_color);
+        display
+    }
+
+    /// Clears the buffer of the display with the chosen background color
+    fn clear_buffer(&mut self, background_color: QuadColor) {
+        for elem in self.get_mut_buffer().iter_mut() {
+            *elem = background_color.get_byte_value();
+        }
+    }
+
+    /// Returns the buffer
+    fn buffer(&self) -> &[u8];
+
+    /// Returns a mutable buffer
+    fn get_mut_buffer(&mut self) -> &mut [u8];
+
+    /// Sets the rotation of the display
+    fn set_rotation(&mut self, rotation: DisplayRotation);
+
+    /// Get the current rotation of the display
+    fn rotation(&self) -> DisplayRotation;
+
+    /// Helperfunction for the Embedded Graphics draw trait
+    ///
+    /// Becomes uneccesary when const_generics become stablised
+    fn draw_helper(
+        &mut self,
+        width: u32,
+        height: u32,
+        pixel: Pixel<QuadColor>,
+    ) -> Result<(), Self::Error> {
+        let rotation = self.rotation();
+        let buffer = self.get_mut_buffer();
+
+        let Pixel(point, color) = pixel;
+        if outside_display(point, width, height, rotation) {
+            return Ok(());
+        }
+
+        // Same packing as the 4 gray levels
+        let (index, shift) =
+            find_gray2_position(point.x as u32, point.y as u32, width, height, rotation);
+        let index = index as usize;
+
+        // "Draw" the Pixel on those two bits
+        if let Some(byte) = buffer.get_mut(index) {
+            *byte = (*byte & !(0b11 << shift)) | (color.get_bits() << shift);
+        }
         Ok(())
     }
 }
@@ -266,8 +636,8 @@ impl<'a> VarDisplay<'a> {
     ///
     /// Buffersize must be at least (width + 7) / 8 * height bytes.
     pub fn new(width: u32, height: u32, buffer: &'a mut [u8]) -> VarDisplay<'a> {
-        let len = buffer.len() as u32;
-        assert!(buffer_len(width as usize, height as usize) >= len as usize);
+        #[cfg(not(feature = "panic_free"))]
+        assert!(crate::buffer_len(width as usize, height as usize) >= buffer.len());
         VarDisplay {
             width,
             height,
@@ -300,11 +670,11 @@ impl<'a> OriginDimensions for VarDisplay<'a> {
 
 impl<'a> Display for VarDisplay<'a> {
     fn buffer(&self) -> &[u8] {
-        &self.buffer
+        self.buffer
     }
 
     fn get_mut_buffer(&mut self) -> &mut [u8] {
-        &mut self.buffer
+        self.buffer
     }
 
     fn set_rotation(&mut self, rotation: DisplayRotation) {
@@ -316,6 +686,528 @@ impl<'a> Display for VarDisplay<'a> {
     }
 }
 
+/// Bytes loaded from a [`SnapshotStorage`] at once
+const SNAPSHOT_CHUNK: usize = 64;
+
+/// A copy of the buffer of a [`Display`] with `N` bytes
+///
+/// Made with [`Display::snapshot`] and put back with [`Display::restore`]. The
+/// rotation isn't part of the snapshot.
+#[derive(Clone)]
+pub struct FrameSnapshot<const N: usize> {
+    buffer: [u8; N],
+}
+
+impl<const N: usize> FrameSnapshot<N> {
+    /// The copied buffer
+    pub fn buffer(&self) -> &[u8] {
+        &self.buffer
+    }
+}
+
+/// External memory keeping a snapshot, e.g. flash or PSRAM
+pub trait SnapshotStorage {
+    /// Error of the memory
+    type Error;
+
+    /// Stores the whole `buffer`, replacing the previous snapshot
+    fn store(&mut self, buffer: &[u8]) -> Result<(), Self::Error>;
+
+    /// Fills `buffer` with the stored bytes starting at `offset`
+    fn load(&mut self, offset: usize, buffer: &mut [u8]) -> Result<(), Self::Error>;
+}
+
+/// A band of full rows of the native (unrotated) buffer which changed on a restore
+///
+/// Whole rows are contiguous in the buffer, so the band can be handed to
+/// `update_partial_frame` as it is:
+///
+///```rust, no_run
+///# use embedded_hal_mock::*;
+///# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
+///use embedded_graphics::{
+///    pixelcolor::BinaryColor, prelude::*, primitives::{PrimitiveStyle, Rectangle},
+///};
+///use epd_waveshare::{epd4in2::*, prelude::*};
+///#
+///# let expectations = [];
+///# let mut spi = spi::Mock::new(&expectations);
+///# let expectations = [];
+///# let cs_pin = pin::Mock::new(&expectations);
+///# let busy_in = pin::Mock::new(&expectations);
+///# let dc = pin::Mock::new(&expectations);
+///# let rst = pin::Mock::new(&expectations);
+///# let mut delay = delay::MockNoop::new();
+///
+///let mut epd = Epd4in2::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
+///let mut display = Display4in2::default();
+///
+///let snapshot = display.snapshot::<{ WIDTH as usize / 8 * HEIGHT as usize }>();
+///let _ = Rectangle::new(Point::new(100, 100), Size::new(200, 50))
+///    .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+///    .draw(&mut display);
+///epd.update_and_display_frame(&mut spi, display.buffer(), &mut delay)?;
+///
+///// close the alert again
+///if let Some(rows) = display.restore(&snapshot) {
+///    let band = rows.bytes(display.buffer());
+///    epd.update_partial_frame(&mut spi, band, 0, rows.first, WIDTH, rows.count)?;
+///    epd.display_frame(&mut spi, &mut delay)?;
+///}
+///# Ok(())
+///# }
+///```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChangedRows {
+    /// First changed row
+    pub first: u32,
+    /// Number of rows from the first to the last changed one
+    pub count: u32,
+    row_bytes: usize,
+}
+
+impl ChangedRows {
+    /// The bytes of the band in `buffer`
+    pub fn bytes<'a>(&self, buffer: &'a [u8]) -> &'a [u8] {
+        let start = self.first as usize * self.row_bytes;
+        let end = start + self.count as usize * self.row_bytes;
+        buffer.get(start..end).unwrap_or_default()
+    }
+
+    fn between(old: &[u8], new: &[u8], row_bytes: usize) -> Option<ChangedRows> {
+        old.chunks(row_bytes)
+            .zip(new.chunks(row_bytes))
+            .enumerate()
+            .filter(|(_, (old, new))| old != new)
+            .fold(None, |changed, (y, _)| {
+                Some(ChangedRows::extend(changed, y as u32, row_bytes))
+            })
+    }
+
+    fn extend(changed: Option<ChangedRows>, row: u32, row_bytes: usize) -> ChangedRows {
+        match changed {
+            Some(rows) => ChangedRows {
+                count: row + 1 - rows.first,
+                ..rows
+            },
+            None => ChangedRows {
+                first: row,
+                count: 1,
+                row_bytes,
+            },
+        }
+    }
+}
+
+/// Bytes per row of the native (unrotated) buffer of `display`
+fn row_bytes<D: Display + ?Sized>(display: &D) -> usize {
+    let size = display.bounding_box().size;
+    let width = match display.rotation() {
+        DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => size.width,
+        DisplayRotation::Rotate90 | DisplayRotation::Rotate270 => size.height,
+    };
+    width.div_ceil(8) as usize
+}
+
+/// Handle of a region reserved in a [`RegionMap`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegionId(usize);
+
+/// Errors returned by a [`RegionMap`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegionError {
+    /// The area overlaps the already reserved region
+    Overlap(RegionId),
+    /// All slots of the map are in use
+    Full,
+    /// The region was released or never reserved
+    Unknown,
+}
+
+/// Reserves disjoint areas of one display buffer for independent widgets
+///
+/// Every widget (or task) reserves its area once and draws through the [`Region`]
+/// it gets from [`RegionMap::region`]. A region translates the coordinates to its
+/// own top left corner and drops every pixel outside of it, so widgets can't draw
+/// over each other. Drawing marks a region dirty, the areas which need to be sent
+/// to the display again can be collected with [`RegionMap::next_dirty`].
+///
+/// ```rust
+///# use embedded_graphics_core::primitives::Rectangle;
+/// use embedded_graphics::{
+///     pixelcolor::BinaryColor::On as Black, prelude::*, primitives::{Line, PrimitiveStyle},
+/// };
+/// use epd_waveshare::{epd2in9::*, graphics::RegionMap};
+///
+/// let mut display = Display2in9::default();
+/// let mut regions: RegionMap<4> = RegionMap::new();
+///
+/// let clock = regions.reserve(Rectangle::new(Point::new(0, 0), Size::new(128, 32))).unwrap();
+/// let status = regions.reserve(Rectangle::new(Point::new(0, 32), Size::new(128, 16))).unwrap();
+///
+/// // The line is clipped at the lower edge of the clock region
+/// let _ = Line::new(Point::new(0, 0), Point::new(0, 100))
+///     .into_styled(PrimitiveStyle::with_stroke(Black, 1))
+///     .draw(&mut regions.region(clock, &mut display).unwrap());
+///
+/// assert_eq!(regions.next_dirty(), Some(Rectangle::new(Point::new(0, 0), Size::new(128, 32))));
+/// assert_eq!(regions.next_dirty(), None);
+///# let _ = status;
+/// ```
+pub struct RegionMap<const N: usize> {
+    // reserved area and dirty flag of every slot
+    regions: [Option<(Rectangle, bool)>; N],
+}
+
+impl<const N: usize> Default for RegionMap<N> {
+    fn default() -> Self {
+        RegionMap::new()
+    }
+}
+
+impl<const N: usize> RegionMap<N> {
+    /// Creates a map without any reserved region
+    pub const fn new() -> Self {
+        RegionMap { regions: [None; N] }
+    }
+
+    /// Reserves `area`, fails if it overlaps a region reserved before
+    pub fn reserve(&mut self, area: Rectangle) -> Result<RegionId, RegionError> {
+        for (i, region) in self.regions.iter().enumerate() {
+            if let Some((reserved, _)) = region {
+                if !reserved.intersection(&area).is_zero_sized() {
+                    return Err(RegionError::Overlap(RegionId(i)));
+                }
+            }
+        }
+        let slot = self
+            .regions
+            .iter()
+            .position(Option::is_none)
+            .ok_or(RegionError::Full)?;
+        self.regions[slot] = Some((area, false));
+        Ok(RegionId(slot))
+    }
+
+    /// Releases a region, its area can be reserved again afterwards
+    pub fn release(&mut self, id: RegionId) -> Result<(), RegionError> {
+        self.slot(id)?;
+        self.regions[id.0] = None;
+        Ok(())
+    }
+
+    /// Area of a reserved region in display coordinates
+    pub fn area(&self, id: RegionId) -> Option<Rectangle> {
+        self.regions
+            .get(id.0)
+            .copied()
+            .flatten()
+            .map(|(area, _)| area)
+    }
+
+    /// Returns true if something was drawn into the region since it was last flushed
+    pub fn is_dirty(&self, id: RegionId) -> bool {
+        self.regions
+            .get(id.0)
+            .copied()
+            .flatten()
+            .is_some_and(|(_, dirty)| dirty)
+    }
+
+    /// Marks a region dirty without drawing, e.g. after the display was cleared
+    pub fn mark_dirty(&mut self, id: RegionId) -> Result<(), RegionError> {
+        self.slot(id)?.1 = true;
+        Ok(())
+    }
+
+    /// Returns the area of the next dirty region and marks it clean
+    ///
+    /// Call it until it returns `None` and send every area, e.g. with
+    /// `update_partial_frame`.
+    pub fn next_dirty(&mut self) -> Option<Rectangle> {
+        self.regions
+            .iter_mut()
+            .flatten()
+            .find(|(_, dirty)| *dirty)
+            .map(|region| {
+                region.1 = false;
+                region.0
+            })
+    }
+
+    /// Draw target for a reserved region of `target`
+    pub fn region<'a, D>(
+        &'a mut self,
+        id: RegionId,
+        target: &'a mut D,
+    ) -> Result<Region<'a, D>, RegionError>
+    where
+        D: DrawTarget,
+    {
+        let (area, dirty) = self.slot(id)?;
+        Ok(Region {
+            target,
+            area: *area,
+            dirty,
+        })
+    }
+
+    fn slot(&mut self, id: RegionId) -> Result<&mut (Rectangle, bool), RegionError> {
+        self.regions
+            .get_mut(id.0)
+            .and_then(Option::as_mut)
+            .ok_or(RegionError::Unknown)
+    }
+}
+
+/// A reserved area of a display, see [`RegionMap`]
+///
+/// (0, 0) is the top left corner of the area, pixels outside of it are dropped.
+pub struct Region<'a, D> {
+    target: &'a mut D,
+    area: Rectangle,
+    dirty: &'a mut bool,
+}
+
+impl<'a, D: DrawTarget> DrawTarget for Region<'a, D> {
+    type Color = D::Color;
+    type Error = D::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let area = self.area;
+        let dirty = &mut *self.dirty;
+        self.target.draw_iter(
+            pixels
+                .into_iter()
+                .map(|Pixel(point, color)| Pixel(point + area.top_left, color))
+                .filter(|Pixel(point, _)| area.contains(*point))
+                .inspect(|_| *dirty = true),
+        )
+    }
+}
+
+impl<'a, D> OriginDimensions for Region<'a, D> {
+    fn size(&self) -> Size {
+        self.area.size
+    }
+}
+
+/// Colors a panel can show, used by [`PaletteAdapter`] to map RGB colors to them
+pub trait Palette: PixelColor + 'static {
+    /// All colors of the palette
+    const COLORS: &'static [Self];
+
+    /// How the color looks on the panel, roughly
+    fn to_rgb(self) -> Rgb888;
+}
+
+impl Palette for BinaryColor {
+    const COLORS: &'static [Self] = &[BinaryColor::Off, BinaryColor::On];
+
+    fn to_rgb(self) -> Rgb888 {
+        // Off is white on the displays, see `color::White`
+        match self {
+            BinaryColor::Off => Rgb888::WHITE,
+            BinaryColor::On => Rgb888::BLACK,
+        }
+    }
+}
+
+impl Palette for Gray2 {
+    const COLORS: &'static [Self] = &[Gray2::new(0), Gray2::new(1), Gray2::new(2), Gray2::new(3)];
+
+    fn to_rgb(self) -> Rgb888 {
+        let luma = self.luma() * 0x55;
+        Rgb888::new(luma, luma, luma)
+    }
+}
+
+impl Palette for TriColor {
+    const COLORS: &'static [Self] = &[TriColor::Black, TriColor::White, TriColor::Chromatic];
+
+    /// The chromatic color is assumed to be red
+    fn to_rgb(self) -> Rgb888 {
+        match self {
+            TriColor::Black => Rgb888::BLACK,
+            TriColor::White => Rgb888::WHITE,
+            TriColor::Chromatic => Rgb888::RED,
+        }
+    }
+}
+
+impl Palette for QuadColor {
+    const COLORS: &'static [Self] = &[
+        QuadColor::Black,
+        QuadColor::White,
+        QuadColor::Yellow,
+        QuadColor::Red,
+    ];
+
+    fn to_rgb(self) -> Rgb888 {
+        let (r, g, b) = self.rgb();
+        Rgb888::new(r, g, b)
+    }
+}
+
+impl Palette for OctColor {
+    // HiZ isn't a color to draw with
+    const COLORS: &'static [Self] = &[
+        OctColor::Black,
+        OctColor::White,
+        OctColor::Green,
+        OctColor::Blue,
+        OctColor::Red,
+        OctColor::Yellow,
+        OctColor::Orange,
+    ];
+
+    fn to_rgb(self) -> Rgb888 {
+        let (r, g, b) = self.rgb();
+        Rgb888::new(r, g, b)
+    }
+}
+
+/// How [`PaletteAdapter`] maps RGB colors to the palette of the panel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorMapping {
+    /// Cuts every channel at the half before picking the nearest color, gives hard
+    /// edges and keeps saturated UI colors stable
+    Threshold,
+    /// Picks the nearest color of the palette
+    #[default]
+    Nearest,
+    /// Ordered dithering with a 4x4 Bayer matrix, renders gradients and photos as
+    /// patterns of the available colors
+    Dither,
+}
+
+/// 4x4 Bayer matrix for [`ColorMapping::Dither`]
+const BAYER_4X4: [[i16; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+impl ColorMapping {
+    /// The palette color `color` is drawn with at `point`
+    ///
+    /// Only [`ColorMapping::Dither`] depends on the position.
+    pub fn apply<C: Palette>(self, point: Point, color: Rgb888) -> C {
+        let channels = [color.r(), color.g(), color.b()];
+        let channels = match self {
+            ColorMapping::Nearest => channels,
+            ColorMapping::Threshold => channels.map(|c| if c >= 0x80 { 0xFF } else { 0x00 }),
+            ColorMapping::Dither => {
+                // spreads the color over +-1/2 of the distance between black and white
+                let bias =
+                    BAYER_4X4[point.y.rem_euclid(4) as usize][point.x.rem_euclid(4) as usize];
+                let offset = (bias * 2 - 15) * 8;
+                channels.map(|c| (i16::from(c) + offset).clamp(0, 0xFF) as u8)
+            }
+        };
+        nearest(Rgb888::new(channels[0], channels[1], channels[2]))
+    }
+}
+
+/// Lets code drawing in `Rgb888` draw on a display with a limited palette
+///
+/// Every pixel is mapped to the palette of the display while drawing, as selected
+/// with [`ColorMapping`]. Nothing is buffered, so this works with any
+/// [`DrawTarget`] whose color implements [`Palette`].
+///
+/// ```rust
+/// use embedded_graphics::{
+///     pixelcolor::Rgb888, prelude::*, primitives::{PrimitiveStyle, Rectangle},
+/// };
+/// use epd_waveshare::{epd2in9::*, graphics::{ColorMapping, PaletteAdapter}};
+///
+/// let mut display = Display2in9::default();
+/// let mut rgb = PaletteAdapter::new(&mut display, ColorMapping::Dither);
+///
+/// // a mid gray turns into a pattern of black and white pixels
+/// let _ = Rectangle::new(Point::new(0, 0), Size::new(16, 16))
+///     .into_styled(PrimitiveStyle::with_fill(Rgb888::new(0x80, 0x80, 0x80)))
+///     .draw(&mut rgb);
+/// ```
+pub struct PaletteAdapter<'a, D> {
+    target: &'a mut D,
+    mapping: ColorMapping,
+}
+
+impl<'a, D> PaletteAdapter<'a, D>
+where
+    D: DrawTarget,
+    D::Color: Palette,
+{
+    /// Draws on `target`, mapping the colors with `mapping`
+    pub fn new(target: &'a mut D, mapping: ColorMapping) -> Self {
+        PaletteAdapter { target, mapping }
+    }
+
+    /// The mapping currently used
+    pub fn mapping(&self) -> ColorMapping {
+        self.mapping
+    }
+
+    /// Changes the mapping for the following drawing operations
+    pub fn set_mapping(&mut self, mapping: ColorMapping) {
+        self.mapping = mapping;
+    }
+}
+
+/// The color of the palette closest to `color`
+fn nearest<C: Palette>(color: Rgb888) -> C {
+    let distance = |candidate: &C| {
+        let rgb = candidate.to_rgb();
+        [
+            (color.r(), rgb.r()),
+            (color.g(), rgb.g()),
+            (color.b(), rgb.b()),
+        ]
+        .iter()
+        .map(|&(a, b)| (i32::from(a) - i32::from(b)).pow(2))
+        .sum::<i32>()
+    };
+    const { assert!(!C::COLORS.is_empty(), "palettes must not be empty") };
+    let nearest = C::COLORS.iter().min_by_key(|candidate| distance(candidate));
+    // cannot panic: the palette was checked for colors at compile time
+    *nearest.expect("palettes are not empty")
+}
+
+impl<'a, D> DrawTarget for PaletteAdapter<'a, D>
+where
+    D: DrawTarget,
+    D::Color: Palette,
+{
+    type Color = Rgb888;
+    type Error = D::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let mapping = self.mapping;
+        self.target.draw_iter(
+            pixels
+                .into_iter()
+                .map(|Pixel(point, color)| Pixel(point, mapping.apply(point, color))),
+        )
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        if self.mapping == ColorMapping::Dither {
+            return self.fill_contiguous(area, core::iter::repeat(color));
+        }
+        let color = self.mapping.apply(Point::zero(), color);
+        self.target.fill_solid(area, color)
+    }
+}
+
+impl<'a, D: Dimensions> Dimensions for PaletteAdapter<'a, D> {
+    fn bounding_box(&self) -> Rectangle {
+        self.target.bounding_box()
+    }
+}
+
 // Checks if a pos is outside the defined display
 fn outside_display(p: Point, width: u32, height: u32, rotation: DisplayRotation) -> bool {
     if p.x < 0 || p.y < 0 {
@@ -373,25 +1265,144 @@ fn find_oct_position(x: u32, y: u32, width: u32, height: u32, rotation: DisplayR
     )
 }
 
+#[rustfmt::skip]
+//returns index position in the u8-slice and the shift of the two pixel bits inside that u8
+fn find_gray2_position(x: u32, y: u32, width: u32, height: u32, rotation: DisplayRotation) -> (u32, u8) {
+    let (nx, ny) = find_rotation(x, y, width, height, rotation);
+    (
+        nx / 4 + width.div_ceil(4) * ny,
+        6 - 2 * (nx % 4) as u8,
+    )
+}
+
 #[rustfmt::skip]
 //returns index position in the u8-slice and the bit-position inside that u8
 fn find_position(x: u32, y: u32, width: u32, height: u32, rotation: DisplayRotation) -> (u32, u8) {
     let (nx, ny) = find_rotation(x, y, width, height, rotation);
     (
-        nx / 8 + ((width + 7) / 8) * ny,
+        nx / 8 + width.div_ceil(8) * ny,
         0x80 >> (nx % 8),
     )
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{buffer_len, find_position, outside_display, Display, DisplayRotation, VarDisplay};
+    use super::{
+        find_position, outside_display, ChangedRows, ColorMapping, Display, DisplayRotation,
+        PaletteAdapter, RegionError, RegionMap, SnapshotStorage, TriDisplay, VarDisplay,
+    };
+    use crate::buffer_len;
+    use crate::codec::{CodecError, Rle};
     use crate::color::Black;
     use crate::color::Color;
+    use crate::color::{OctColor, TriColor};
+    use embedded_graphics::pixelcolor::{BinaryColor, Rgb888};
     use embedded_graphics::{
         prelude::*,
         primitives::{Line, PrimitiveStyle},
     };
+    use embedded_graphics_core::primitives::Rectangle;
+
+    #[test]
+    fn short_buffer_misses_pixels() {
+        let mut buffer = [Color::White.get_byte_value(); 16 / 8 * 2];
+        let mut display = VarDisplay::new(16, 10, &mut buffer);
+
+        let _ = Line::new(Point::new(0, 0), Point::new(0, 9))
+            .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
+            .draw(&mut display);
+        assert_eq!(display.buffer(), &[0x7F, 0xFF, 0x7F, 0xFF]);
+    }
+
+    #[test]
+    fn snapshot_restore_changed_rows() {
+        let mut buffer = [Color::White.get_byte_value(); 16 / 8 * 10];
+        let mut display = VarDisplay::new(16, 10, &mut buffer);
+        let snapshot = display.snapshot::<20>();
+
+        assert_eq!(display.restore(&snapshot), None);
+
+        let _ = Line::new(Point::new(0, 3), Point::new(15, 5))
+            .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
+            .draw(&mut display);
+        let rows = display.restore(&snapshot).unwrap();
+        assert_eq!((rows.first, rows.count), (3, 3));
+        assert_eq!(rows.bytes(display.buffer()), &[0xFF; 6]);
+        assert_eq!(display.buffer(), snapshot.buffer());
+    }
+
+    #[test]
+    fn changed_rows_of_rotated_display() {
+        let mut buffer = [Color::White.get_byte_value(); 16 / 8 * 10];
+        let mut display = VarDisplay::new(16, 10, &mut buffer);
+        display.set_rotation(DisplayRotation::Rotate90);
+        let snapshot = display.snapshot::<20>();
+
+        // the last column of the rotated display is the last row of the buffer
+        let _ = Pixel(Point::new(9, 0), BinaryColor::On).draw(&mut display);
+        let rows = display.restore(&snapshot).unwrap();
+        assert_eq!((rows.first, rows.count), (9, 1));
+    }
+
+    struct RamStorage([u8; 20]);
+
+    impl SnapshotStorage for RamStorage {
+        type Error = ();
+
+        fn store(&mut self, buffer: &[u8]) -> Result<(), ()> {
+            self.0.copy_from_slice(buffer);
+            Ok(())
+        }
+
+        fn load(&mut self, offset: usize, buffer: &mut [u8]) -> Result<(), ()> {
+            let len = buffer.len();
+            buffer.copy_from_slice(&self.0[offset..offset + len]);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn snapshot_to_storage() {
+        let mut buffer = [Color::White.get_byte_value(); 16 / 8 * 10];
+        let mut display = VarDisplay::new(16, 10, &mut buffer);
+        let mut storage = RamStorage([0; 20]);
+        display.snapshot_to(&mut storage).unwrap();
+
+        let _ = Pixel(Point::new(0, 1), BinaryColor::On).draw(&mut display);
+        let _ = Pixel(Point::new(0, 7), BinaryColor::On).draw(&mut display);
+        let rows: ChangedRows = display.restore_from(&mut storage).unwrap().unwrap();
+        assert_eq!((rows.first, rows.count), (1, 7));
+        assert_eq!(display.buffer(), &[0xFF; 20]);
+        assert_eq!(display.restore_from(&mut storage), Ok(None));
+    }
+
+    #[test]
+    fn compressed_snapshot() {
+        let mut buffer = [Color::White.get_byte_value(); 16 / 8 * 10];
+        let mut display = VarDisplay::new(16, 10, &mut buffer);
+        let mut stored = [0u8; 8];
+        let len = display.snapshot_compressed(&Rle, &mut stored).unwrap();
+        assert_eq!(&stored[..len], &[20, 0xFF]);
+
+        let _ = Pixel(Point::new(0, 2), BinaryColor::On).draw(&mut display);
+        let _ = Pixel(Point::new(9, 4), BinaryColor::On).draw(&mut display);
+        let rows = display
+            .restore_compressed(&Rle, &stored[..len])
+            .unwrap()
+            .unwrap();
+        assert_eq!((rows.first, rows.count), (2, 3));
+        assert_eq!(display.buffer(), &[0xFF; 20]);
+        assert_eq!(display.restore_compressed(&Rle, &stored[..len]), Ok(None));
+
+        assert_eq!(
+            display.restore_compressed(&Rle, &[19, 0xFF]),
+            Err(CodecError::Corrupt)
+        );
+        assert_eq!(
+            display.restore_compressed(&Rle, &[21, 0xFF]),
+            Err(CodecError::Corrupt)
+        );
+    }
 
     #[test]
     fn buffer_clear() {
@@ -415,8 +1426,8 @@ mod tests {
     #[test]
     fn rotation_overflow() {
         use crate::epd4in2::{HEIGHT, WIDTH};
-        let width = WIDTH as u32;
-        let height = HEIGHT as u32;
+        let width = WIDTH;
+        let height = HEIGHT;
         test_rotation_overflow(width, height, DisplayRotation::Rotate0);
         test_rotation_overflow(width, height, DisplayRotation::Rotate90);
         test_rotation_overflow(width, height, DisplayRotation::Rotate180);
@@ -427,7 +1438,7 @@ mod tests {
         let max_value = width / 8 * height;
         for x in 0..(width + height) {
             //limit x because it runs too long
-            for y in 0..(u32::max_value()) {
+            for y in 0..(u32::MAX) {
                 if outside_display(Point::new(x as i32, y as i32), width, height, rotation2) {
                     break;
                 } else {
@@ -486,4 +1497,179 @@ mod tests {
             assert_eq!(byte, DEFAULT_BACKGROUND_COLOR.get_byte_value());
         }
     }
+
+    #[test]
+    fn regions_are_disjoint() {
+        let mut regions: RegionMap<2> = RegionMap::new();
+        let top = regions
+            .reserve(Rectangle::new(Point::new(0, 0), Size::new(16, 8)))
+            .unwrap();
+        assert_eq!(
+            regions.reserve(Rectangle::new(Point::new(8, 4), Size::new(16, 8))),
+            Err(RegionError::Overlap(top))
+        );
+        let bottom = regions
+            .reserve(Rectangle::new(Point::new(0, 8), Size::new(16, 8)))
+            .unwrap();
+        assert_eq!(
+            regions.reserve(Rectangle::new(Point::new(0, 16), Size::new(16, 8))),
+            Err(RegionError::Full)
+        );
+
+        regions.release(bottom).unwrap();
+        assert_eq!(regions.release(bottom), Err(RegionError::Unknown));
+        assert_eq!(regions.area(bottom), None);
+        assert!(regions
+            .reserve(Rectangle::new(Point::new(0, 16), Size::new(16, 8)))
+            .is_ok());
+    }
+
+    #[test]
+    fn region_clips_and_marks_dirty() {
+        let width = 16;
+        let height = 16;
+        let mut buffer = [Color::White.get_byte_value(); 16 / 8 * 16];
+        let mut display = VarDisplay::new(width, height, &mut buffer);
+
+        let mut regions: RegionMap<2> = RegionMap::new();
+        let area = Rectangle::new(Point::new(8, 8), Size::new(8, 8));
+        let id = regions.reserve(area).unwrap();
+        let other = regions
+            .reserve(Rectangle::new(Point::new(0, 0), Size::new(8, 8)))
+            .unwrap();
+
+        // starts inside the region and leaves it at the right edge
+        let _ = Line::new(Point::new(0, 0), Point::new(15, 0))
+            .into_styled(PrimitiveStyle::with_stroke(Black, 1))
+            .draw(&mut regions.region(id, &mut display).unwrap());
+
+        assert!(regions.is_dirty(id));
+        assert!(!regions.is_dirty(other));
+
+        let buffer = display.buffer();
+        // row 8: the right byte belongs to the region
+        assert_eq!(buffer[16], 0xFF);
+        assert_eq!(buffer[17], 0x00);
+        for (i, &byte) in buffer.iter().enumerate() {
+            if i != 17 {
+                assert_eq!(byte, 0xFF);
+            }
+        }
+
+        assert_eq!(regions.next_dirty(), Some(area));
+        assert_eq!(regions.next_dirty(), None);
+        assert!(!regions.is_dirty(id));
+    }
+
+    #[test]
+    fn palette_mapping() {
+        let orange = Rgb888::new(0xF0, 0x70, 0x10);
+        let dark_red = Rgb888::new(0x70, 0x00, 0x00);
+        let point = Point::zero();
+
+        assert_eq!(
+            ColorMapping::Nearest.apply::<OctColor>(point, orange),
+            OctColor::Orange
+        );
+        assert_eq!(
+            ColorMapping::Nearest.apply::<TriColor>(point, dark_red),
+            TriColor::Black
+        );
+        // only the red channel is cut, and goes to 0
+        assert_eq!(
+            ColorMapping::Threshold.apply::<TriColor>(point, dark_red),
+            TriColor::Black
+        );
+        assert_eq!(
+            ColorMapping::Threshold.apply::<OctColor>(point, orange),
+            OctColor::Red
+        );
+        assert_eq!(
+            ColorMapping::Threshold.apply::<BinaryColor>(point, Rgb888::new(0x90, 0x90, 0x90)),
+            BinaryColor::Off
+        );
+    }
+
+    #[test]
+    fn palette_dither() {
+        let width = 8;
+        let height = 4;
+        let mut buffer = [Color::White.get_byte_value(); 4];
+        let mut display = VarDisplay::new(width, height, &mut buffer);
+        let mut rgb = PaletteAdapter::new(&mut display, ColorMapping::Dither);
+
+        let gray = Rgb888::new(0x80, 0x80, 0x80);
+        let _ = Rectangle::new(Point::zero(), Size::new(8, 4))
+            .into_styled(PrimitiveStyle::with_fill(gray))
+            .draw(&mut rgb);
+
+        // half of the pixels are black
+        let black: u32 = display.buffer().iter().map(|b| b.count_zeros()).sum();
+        assert_eq!(black, 16);
+
+        // solid colors of the palette stay solid
+        let mut rgb = PaletteAdapter::new(&mut display, ColorMapping::Dither);
+        let _ = Rectangle::new(Point::zero(), Size::new(8, 4))
+            .into_styled(PrimitiveStyle::with_fill(Rgb888::new(0, 0, 0)))
+            .draw(&mut rgb);
+        assert_eq!(display.buffer(), &[0x00; 4]);
+    }
+
+    #[test]
+    fn tri_clear_buffer() {
+        use crate::epd2in13bc::Display2in13bc;
+
+        let mut display = Display2in13bc::default();
+        for (color, bw, chromatic) in [
+            (TriColor::Black, 0x00, 0xff),
+            (TriColor::Chromatic, 0xff, 0x00),
+            (TriColor::White, 0xff, 0xff),
+        ] {
+            display.clear_buffer(color);
+            assert!(display.bw_buffer().iter().all(|&byte| byte == bw));
+            assert!(display
+                .chromatic_buffer()
+                .iter()
+                .all(|&byte| byte == chromatic));
+        }
+
+        // drawing a color on its own background changes nothing
+        let mut display = Display2in13bc::with_background(TriColor::Chromatic);
+        let _ = Pixel(Point::new(3, 7), TriColor::Chromatic).draw(&mut display);
+        let background = Display2in13bc::with_background(TriColor::Chromatic);
+        assert_eq!(display.buffer(), background.buffer());
+    }
+
+    #[test]
+    fn tri_chromatic_pixels() {
+        use crate::epd2in13bc::Display2in13bc;
+
+        let mut display = Display2in13bc::default();
+        assert_eq!(display.chromatic_pixels(), 0);
+        let _ = Line::new(Point::new(0, 0), Point::new(9, 0))
+            .into_styled(PrimitiveStyle::with_stroke(TriColor::Chromatic, 1))
+            .draw(&mut display);
+        let _ = Pixel(Point::new(0, 5), TriColor::Black).draw(&mut display);
+        assert_eq!(display.chromatic_pixels(), 10);
+
+        display.clear_buffer(TriColor::Chromatic);
+        assert_eq!(
+            display.chromatic_pixels() as usize,
+            display.chromatic_buffer().len() * 8
+        );
+    }
+
+    #[test]
+    fn with_background() {
+        use crate::epd2in9::Display2in9;
+        use crate::epd5in65f::Display5in65f;
+        use crate::graphics::OctDisplay;
+
+        let display = Display2in9::with_background(Color::Black);
+        assert!(display.buffer().iter().all(|&byte| byte == 0x00));
+
+        let display = Display5in65f::with_background(OctColor::Green);
+        let green = OctColor::colors_byte(OctColor::Green, OctColor::Green);
+        assert!(display.buffer().iter().all(|&byte| byte == green));
+    }
 }
@@ -1,13 +1,22 @@
 use crate::traits::Command;
+use crate::transport::{DefaultStrategy, TransportStrategy};
+use crate::Error;
 use core::marker::PhantomData;
 use embedded_hal::{
-    blocking::{delay::*, spi::Write},
+    blocking::{
+        delay::*,
+        spi::{Transfer, Write},
+    },
     digital::v2::*,
 };
 
 /// The Connection Interface of all (?) Waveshare EPD-Devices
 ///
-pub(crate) struct DisplayInterface<SPI, CS, BUSY, DC, RST, DELAY> {
+///
+/// Data is put on the wire with the [TransportStrategy] `S`. The drivers use the
+/// [DefaultStrategy] of the platform, the SPI can be wrapped in a
+/// [Transport](crate::transport::Transport) to send the transfers differently.
+pub(crate) struct DisplayInterface<SPI, CS, BUSY, DC, RST, DELAY, S = DefaultStrategy> {
     /// SPI
     _spi: PhantomData<SPI>,
     /// DELAY
@@ -20,9 +29,11 @@ pub(crate) struct DisplayInterface<SPI, CS, BUSY, DC, RST, DELAY> {
     dc: DC,
     /// Pin for Resetting
     rst: RST,
+    /// How the data is transferred
+    strategy: S,
 }
 
-impl<SPI, CS, BUSY, DC, RST, DELAY> DisplayInterface<SPI, CS, BUSY, DC, RST, DELAY>
+impl<SPI, CS, BUSY, DC, RST, DELAY, S> DisplayInterface<SPI, CS, BUSY, DC, RST, DELAY, S>
 where
     SPI: Write<u8>,
     CS: OutputPin,
@@ -30,22 +41,39 @@ where
     DC: OutputPin,
     RST: OutputPin,
     DELAY: DelayMs<u8>,
+    S: TransportStrategy<SPI> + Default,
 {
     pub fn new(cs: CS, busy: BUSY, dc: DC, rst: RST) -> Self {
         DisplayInterface {
-            _spi: PhantomData::default(),
-            _delay: PhantomData::default(),
+            _spi: PhantomData,
+            _delay: PhantomData,
             cs,
             busy,
             dc,
             rst,
+            strategy: S::default(),
         }
     }
+}
 
+impl<SPI, CS, BUSY, DC, RST, DELAY, S> DisplayInterface<SPI, CS, BUSY, DC, RST, DELAY, S>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+    S: TransportStrategy<SPI>,
+{
     /// Basic function for sending [Commands](Command).
     ///
     /// Enables direct interaction with the device with the help of [data()](DisplayInterface::data())
-    pub(crate) fn cmd<T: Command>(&mut self, spi: &mut SPI, command: T) -> Result<(), SPI::Error> {
+    pub(crate) fn cmd<T: Command>(
+        &mut self,
+        spi: &mut SPI,
+        command: T,
+    ) -> Result<(), Error<SPI::Error>> {
         // low for commands
         let _ = self.dc.set_low();
 
@@ -56,7 +84,7 @@ where
     /// Basic function for sending an array of u8-values of data over spi
     ///
     /// Enables direct interaction with the device with the help of [command()](Epd4in2::command())
-    pub(crate) fn data(&mut self, spi: &mut SPI, data: &[u8]) -> Result<(), SPI::Error> {
+    pub(crate) fn data(&mut self, spi: &mut SPI, data: &[u8]) -> Result<(), Error<SPI::Error>> {
         // high for data
         let _ = self.dc.set_high();
 
@@ -72,7 +100,7 @@ where
         spi: &mut SPI,
         command: T,
         data: &[u8],
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         self.cmd(spi, command)?;
         self.data(spi, data)
     }
@@ -85,7 +113,7 @@ where
         spi: &mut SPI,
         val: u8,
         repetitions: u32,
-    ) -> Result<(), SPI::Error> {
+    ) -> Result<(), Error<SPI::Error>> {
         // high for data
         let _ = self.dc.set_high();
         // Transfer data (u8) over spi
@@ -95,21 +123,78 @@ where
         Ok(())
     }
 
+    /// Sends an array of u8-values with all bits flipped
+    ///
+    /// For controllers expecting a set bit for a black pixel, the buffers of the
+    /// displays use a set bit for white.
+    pub(crate) fn data_inverted(
+        &mut self,
+        spi: &mut SPI,
+        data: &[u8],
+    ) -> Result<(), Error<SPI::Error>> {
+        let mut chunk = [0u8; 64];
+        for bytes in data.chunks(chunk.len()) {
+            for (inverted, byte) in chunk.iter_mut().zip(bytes) {
+                *inverted = !byte;
+            }
+            self.data(spi, &chunk[..bytes.len()])?;
+        }
+        Ok(())
+    }
+
+    /// Sends one bit plane of a buffer with 2 bits per pixel (4 gray levels)
+    ///
+    /// `bit` selects the bit of every pixel which is sent, `0b10` for the high and
+    /// `0b01` for the low one. 8 pixels are packed into every byte sent, `invert`
+    /// flips all of them for controllers expecting a set bit for a dark pixel.
+    pub(crate) fn data_gray_plane(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        bit: u8,
+        invert: bool,
+    ) -> Result<(), Error<SPI::Error>> {
+        let mut chunk = [0u8; 64];
+        for pixels in buffer.chunks(2 * chunk.len()) {
+            let len = pixels.len() / 2;
+            for (byte, pair) in chunk.iter_mut().zip(pixels.chunks_exact(2)) {
+                *byte = gray_plane_byte(pair[0], pair[1], bit);
+                if invert {
+                    *byte = !*byte;
+                }
+            }
+            self.data(spi, &chunk[..len])?;
+        }
+        Ok(())
+    }
+
+    /// Sends data over a second bus sharing CS and DC with `spi`
+    ///
+    /// Used for controllers which take their frame data on more data lines than the
+    /// commands, e.g. the same SPI peripheral switched to two data lines.
+    pub(crate) fn data_over<BUS>(
+        &mut self,
+        bus: &mut BUS,
+        data: &[u8],
+    ) -> Result<(), Error<SPI::Error>>
+    where
+        BUS: Write<u8, Error = SPI::Error>,
+    {
+        let _ = self.dc.set_high();
+        let _ = self.cs.set_low();
+        bus.write(data)?;
+        let _ = self.cs.set_high();
+        Ok(())
+    }
+
     // spi write helper/abstraction function
-    fn write(&mut self, spi: &mut SPI, data: &[u8]) -> Result<(), SPI::Error> {
+    // setup/hold times for slow CS/DC edges can be added with crate::timing::GuardedPin
+    fn write(&mut self, spi: &mut SPI, data: &[u8]) -> Result<(), Error<SPI::Error>> {
         // activate spi with cs low
         let _ = self.cs.set_low();
 
         // transfer spi data
-        // Be careful!! Linux has a default limit of 4096 bytes per spi transfer
-        // see https://raspberrypi.stackexchange.com/questions/65595/spi-transfer-fails-with-buffer-size-greater-than-4096
-        if cfg!(target_os = "linux") {
-            for data_chunk in data.chunks(4096) {
-                spi.write(data_chunk)?;
-            }
-        } else {
-            spi.write(data)?;
-        }
+        self.strategy.transfer(spi, data)?;
 
         // deactivate spi with cs high
         let _ = self.cs.set_high();
@@ -130,6 +215,8 @@ where
     ///
     /// Most likely there was a mistake with the 2in9 busy connection
     /// //TODO: use the #cfg feature to make this compile the right way for the certain types
+    ///
+    /// Noisy busy lines can be filtered with [DebouncedPin](crate::debounce::DebouncedPin)
     pub(crate) fn wait_until_idle(&mut self, is_busy_low: bool) {
         // //tested: worked without the delay for all tested devices
         // //self.delay_ms(1);
@@ -177,3 +264,114 @@ where
         delay.delay_ms(200);
     }
 }
+
+impl<SPI, CS, BUSY, DC, RST, DELAY, S> DisplayInterface<SPI, CS, BUSY, DC, RST, DELAY, S>
+where
+    SPI: Write<u8> + Transfer<u8, Error = <SPI as Write<u8>>::Error>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    /// Sends a [Command] and reads `buffer.len()` bytes of its response
+    ///
+    /// CS stays low in between, the controllers only answer within the same transaction.
+    pub(crate) fn cmd_read<T: Command>(
+        &mut self,
+        spi: &mut SPI,
+        command: T,
+        buffer: &mut [u8],
+    ) -> Result<(), Error<<SPI as Write<u8>>::Error>> {
+        let _ = self.dc.set_low();
+        let _ = self.cs.set_low();
+        let result = Write::write(spi, &[command.address()]).and_then(|_| {
+            let _ = self.dc.set_high();
+            spi.transfer(buffer).map(|_| ())
+        });
+        let _ = self.cs.set_high();
+        result.map_err(Error::Spi)
+    }
+}
+
+/// Collects `bit` of the 8 pixels in two bytes of a 2 bit per pixel buffer into one byte
+fn gray_plane_byte(first: u8, second: u8, bit: u8) -> u8 {
+    let mut byte = 0;
+    for (i, pixels) in [first, second].iter().enumerate() {
+        for pixel in 0..4 {
+            if (pixels >> (6 - 2 * pixel)) & bit != 0 {
+                byte |= 0x80 >> (4 * i + pixel);
+            }
+        }
+    }
+    byte
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_hal_mock::{
+        delay::MockNoop,
+        pin::{Mock as PinMock, State, Transaction as PinTransaction},
+        spi::{Mock as SpiMock, Transaction as SpiTransaction},
+    };
+
+    /// Splits every block into single bytes and counts them
+    #[derive(Default)]
+    struct Bytewise(usize);
+
+    impl<SPI: Write<u8>> TransportStrategy<SPI> for Bytewise {
+        fn transfer(&mut self, spi: &mut SPI, data: &[u8]) -> Result<(), SPI::Error> {
+            for byte in data {
+                spi.write(&[*byte])?;
+                self.0 += 1;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn writes_with_strategy() {
+        let mut spi = SpiMock::new(&[
+            SpiTransaction::write([0x10].to_vec()),
+            SpiTransaction::write([0xAA].to_vec()),
+            SpiTransaction::write([0xBB].to_vec()),
+        ]);
+        let cs = PinMock::new(&[
+            PinTransaction::set(State::Low),
+            PinTransaction::set(State::High),
+            PinTransaction::set(State::Low),
+            PinTransaction::set(State::High),
+        ]);
+        let dc = PinMock::new(&[
+            PinTransaction::set(State::Low),
+            PinTransaction::set(State::High),
+        ]);
+        let mut interface: DisplayInterface<_, _, _, _, _, MockNoop, Bytewise> =
+            DisplayInterface::new(cs, PinMock::new(&[]), dc, PinMock::new(&[]));
+
+        interface
+            .cmd_with_data(&mut spi, TestCommand, &[0xAA, 0xBB])
+            .unwrap();
+        assert_eq!(interface.strategy.0, 3);
+        spi.done();
+        interface.cs.done();
+        interface.dc.done();
+    }
+
+    struct TestCommand;
+
+    impl Command for TestCommand {
+        fn address(self) -> u8 {
+            0x10
+        }
+    }
+
+    #[test]
+    fn gray_planes() {
+        // white, light gray, dark gray, black | black, black, white, white
+        let (first, second) = (0b11_10_01_00, 0b00_00_11_11);
+        assert_eq!(gray_plane_byte(first, second, 0b10), 0b1100_0011);
+        assert_eq!(gray_plane_byte(first, second, 0b01), 0b1010_0011);
+    }
+}
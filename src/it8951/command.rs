@@ -0,0 +1,77 @@
+//! SPI Commands and Registers of the IT8951 controller
+
+/// IT8951 commands
+///
+/// The controller takes 16 bit commands, so these don't use the [`Command`](crate::traits::Command)
+/// trait of the other drivers. The first block are the I80 commands of the controller,
+/// the rest are user defined commands of the firmware on the Waveshare HATs.
+#[allow(dead_code)]
+#[derive(Copy, Clone)]
+pub(crate) enum Command {
+    /// Leaves sleep or standby
+    SysRun = 0x0001,
+    /// Stops the clocks, the registers are kept
+    Standby = 0x0002,
+    /// Turns off the clocks and the power of the panel
+    Sleep = 0x0003,
+    /// Reads a register, the address is sent as data
+    RegisterRead = 0x0010,
+    /// Writes a register, the address and the value are sent as data
+    RegisterWrite = 0x0011,
+    /// Starts to load an image of the full panel size
+    LoadImage = 0x0020,
+    /// Starts to load an image into an area, given as arguments
+    LoadImageArea = 0x0021,
+    /// Ends the loading of an image
+    LoadImageEnd = 0x0022,
+
+    /// Refreshes an area with a waveform mode, given as arguments
+    DisplayArea = 0x0034,
+    /// Gets or sets the VCOM voltage
+    Vcom = 0x0039,
+    /// Reads the size of the panel, the address of the image buffer and the versions
+    GetDeviceInfo = 0x0302,
+}
+
+impl Command {
+    /// Returns the address of the command
+    pub(crate) fn address(self) -> u16 {
+        self as u16
+    }
+}
+
+/// IT8951 registers used by the driver
+#[allow(dead_code)]
+#[derive(Copy, Clone)]
+pub(crate) enum Register {
+    /// Packed write of the host interface, enabled with 0x0001
+    I80Cpcr = 0x0004,
+    /// Low word of the image buffer address the next image is loaded to
+    LisarLow = 0x0208,
+    /// High word of the image buffer address the next image is loaded to
+    LisarHigh = 0x020A,
+    /// Status of the waveform engines, 0 once all refreshes are done
+    Lutafsr = 0x1224,
+}
+
+impl Register {
+    /// Returns the address of the register
+    pub(crate) fn address(self) -> u16 {
+        self as u16
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn command_addr() {
+        assert_eq!(Command::SysRun.address(), 0x0001);
+        assert_eq!(Command::GetDeviceInfo.address(), 0x0302);
+        assert_eq!(
+            Register::LisarHigh.address(),
+            Register::LisarLow.address() + 2
+        );
+    }
+}
@@ -0,0 +1,114 @@
+use crate::graphics::{DisplayRotation, Gray4Display};
+use embedded_graphics::pixelcolor::Gray4;
+use embedded_graphics_core::prelude::*;
+
+/// Buffer with 16 gray levels for an area of an IT8951 panel
+///
+/// The full frames of the panels are up to 1.3MB, so the buffer is borrowed and can
+/// cover only a part of the panel. It needs `width / 2 * height` bytes, with a `width`
+/// which is a multiple of 4 for loading it with
+/// [update_partial_frame](super::It8951::update_partial_frame). A buffer of `0xFF` is white.
+pub struct It8951Display<'a> {
+    width: u32,
+    height: u32,
+    rotation: DisplayRotation,
+    buffer: &'a mut [u8],
+}
+
+impl<'a> It8951Display<'a> {
+    /// Creates a display of `width` x `height` pixels on `buffer`
+    pub fn new(width: u32, height: u32, buffer: &'a mut [u8]) -> It8951Display<'a> {
+        #[cfg(not(feature = "panic_free"))]
+        assert!(buffer.len() >= (width / 2 * height) as usize);
+        It8951Display {
+            width,
+            height,
+            rotation: DisplayRotation::default(),
+            buffer,
+        }
+    }
+}
+
+impl<'a> DrawTarget for It8951Display<'a> {
+    type Color = Gray4;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for pixel in pixels {
+            self.draw_helper(self.width, self.height, pixel)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> OriginDimensions for It8951Display<'a> {
+    fn size(&self) -> Size {
+        Size::new(self.width, self.height)
+    }
+}
+
+impl<'a> Gray4Display for It8951Display<'a> {
+    fn buffer(&self) -> &[u8] {
+        self.buffer
+    }
+
+    fn get_mut_buffer(&mut self) -> &mut [u8] {
+        self.buffer
+    }
+
+    fn set_rotation(&mut self, rotation: DisplayRotation) {
+        self.rotation = rotation;
+    }
+
+    fn rotation(&self) -> DisplayRotation {
+        self.rotation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_graphics::{
+        pixelcolor::GrayColor,
+        prelude::*,
+        primitives::{Line, PrimitiveStyle},
+    };
+
+    #[test]
+    fn graphics_rotation_0() {
+        let mut buffer = [0xFF; 8 / 2 * 4];
+        let mut display = It8951Display::new(8, 4, &mut buffer);
+
+        let _ = Line::new(Point::new(0, 0), Point::new(2, 0))
+            .into_styled(PrimitiveStyle::with_stroke(Gray4::new(0x5), 1))
+            .draw(&mut display);
+
+        let buffer = display.buffer();
+        assert_eq!(&buffer[..2], &[0x55, 0x5F]);
+        assert!(buffer[2..].iter().all(|&b| b == 0xFF));
+    }
+
+    #[test]
+    fn graphics_rotation_90() {
+        let mut buffer = [0xFF; 8 / 2 * 4];
+        let mut display = It8951Display::new(8, 4, &mut buffer);
+        display.set_rotation(DisplayRotation::Rotate90);
+
+        let _ = Pixel(Point::new(0, 7), Gray4::BLACK).draw(&mut display);
+
+        let buffer = display.buffer();
+        assert_eq!(buffer[0], 0x0F);
+        assert!(buffer[1..].iter().all(|&b| b == 0xFF));
+    }
+
+    #[test]
+    fn clear_buffer() {
+        let mut buffer = [0x00; 4];
+        let mut display = It8951Display::new(4, 2, &mut buffer);
+        display.clear_buffer(Gray4::new(0xA));
+        assert!(display.buffer().iter().all(|&b| b == 0xAA));
+    }
+}
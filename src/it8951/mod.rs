@@ -0,0 +1,605 @@
+//! A Driver for the large Waveshare HATs with the IT8951 controller via SPI
+//!
+//! The 6", 7.8", 9.7" and 10.3" HATs don't talk to the panel controller directly but
+//! to an IT8951, which has its own image memory and runs the waveforms stored on the
+//! panel. It shows 16 gray levels, 4 bits per pixel with `0x0` for black and `0xF` for
+//! white, and can refresh any area of the panel on its own.
+//!
+//! The IT8951 uses a packet based protocol on 16 bit words: every packet starts with a
+//! preamble for a command, data to write or data to read. There is no data/command pin,
+//! but a HRDY line, which is low while the controller can't take the next word. Reading
+//! needs a SPI with [`Transfer`], the device info and the refresh status are read back.
+//!
+//! Frames are loaded in two steps, [update_partial_frame](It8951::update_partial_frame)
+//! copies the pixels into the image memory and [display_area](It8951::display_area)
+//! refreshes the area with a [`WaveformMode`]. The [`presets`] hold what the controller
+//! doesn't report about the panels. The VCOM voltage differs for every panel and is
+//! printed on its ribbon cable, e.g. `-1.53V`, which is passed as `1530`.
+//!
+//! # References
+//!
+//! - [Datasheet](https://www.waveshare.com/wiki/10.3inch_e-Paper_HAT)
+//! - IT8951 programming guide of ITE
+//!
+//! # Example for the 10.3" HAT
+//!
+//!```rust, no_run
+//!# use embedded_hal_mock::*;
+//!# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
+//!use embedded_graphics::{
+//!    pixelcolor::{Gray4, GrayColor}, prelude::*, primitives::{Line, PrimitiveStyle},
+//!};
+//!use epd_waveshare::{it8951::*, prelude::*};
+//!#
+//!# let expectations = [];
+//!# let mut spi = spi::Mock::new(&expectations);
+//!# let expectations = [];
+//!# let cs_pin = pin::Mock::new(&expectations);
+//!# let hrdy = pin::Mock::new(&expectations);
+//!# let rst = pin::Mock::new(&expectations);
+//!# let mut delay = delay::MockNoop::new();
+//!
+//!// Setup EPD, the VCOM is printed on the ribbon cable
+//!let mut epd = It8951::new(&mut spi, cs_pin, hrdy, rst, presets::HAT_10IN3, 1530, &mut delay)?;
+//!
+//!// Draw a 200x200 area in the top left corner
+//!let mut buffer = [0xFF; 200 / 2 * 200];
+//!let mut display = It8951Display::new(200, 200, &mut buffer);
+//!let _ = Line::new(Point::new(0, 0), Point::new(199, 199))
+//!    .into_styled(PrimitiveStyle::with_stroke(Gray4::BLACK, 1))
+//!    .draw(&mut display);
+//!
+//!epd.update_partial_frame(&mut spi, display.buffer(), 0, 0, 200, 200)?;
+//!epd.display_area(&mut spi, 0, 0, 200, 200, WaveformMode::Gc16)?;
+//!
+//!// Set the EPD to sleep once the refresh is done
+//!epd.sleep(&mut spi)?;
+//!# Ok(())
+//!# }
+//!```
+
+use core::marker::PhantomData;
+
+use embedded_hal::{
+    blocking::{
+        delay::*,
+        spi::{Transfer, Write},
+    },
+    digital::v2::{InputPin, OutputPin},
+};
+
+use crate::Error;
+
+pub(crate) mod command;
+use self::command::{Command, Register};
+
+pub mod presets;
+use self::presets::Preset;
+
+#[cfg(feature = "graphics")]
+mod graphics;
+#[cfg(feature = "graphics")]
+pub use self::graphics::It8951Display;
+
+/// Preamble of a command packet
+const PREAMBLE_COMMAND: u16 = 0x6000;
+/// Preamble of a packet with data for the controller
+const PREAMBLE_WRITE: u16 = 0x0000;
+/// Preamble of a packet reading data from the controller
+const PREAMBLE_READ: u16 = 0x1000;
+
+/// Argument of [`Command::LoadImageArea`]: little endian words of 4 bit pixels, not rotated
+const LOAD_4BPP: u16 = 2 << 4;
+
+/// Number of words of the device info
+const DEVICE_INFO_WORDS: usize = 20;
+
+/// White in both pixels of a byte
+const WHITE: u8 = 0xFF;
+
+/// Errors of the SPI
+type SpiError<SPI> = <SPI as Write<u8>>::Error;
+
+/// Waveforms in the LUT of the panels
+///
+/// The modes differ in the number of gray levels, speed and ghosting. The index of
+/// [`WaveformMode::A2`] differs between the panels and is taken from the [`Preset`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WaveformMode {
+    /// Clears the panel to white with a lot of flashing, used after power on
+    Init,
+    /// Direct update, fast change of any gray level to black or white
+    Du,
+    /// Full refresh with 16 gray levels and flashing
+    Gc16,
+    /// Refresh with 16 gray levels without flashing, some ghosting
+    Gl16,
+    /// Like [`WaveformMode::Gl16`] with less ghosting
+    Glr16,
+    /// Like [`WaveformMode::Glr16`] with even less ghosting
+    Gld16,
+    /// Fastest update of black and white only, for animations
+    A2,
+}
+
+impl WaveformMode {
+    /// Index of the mode in the LUT of the panel
+    pub fn index(self, preset: &Preset) -> u16 {
+        match self {
+            WaveformMode::Init => 0,
+            WaveformMode::Du => 1,
+            WaveformMode::Gc16 => 2,
+            WaveformMode::Gl16 => 3,
+            WaveformMode::Glr16 => 4,
+            WaveformMode::Gld16 => 5,
+            WaveformMode::A2 => preset.a2_mode,
+        }
+    }
+}
+
+/// Information the controller reports about itself and the panel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeviceInfo {
+    /// Width of the panel
+    pub width: u16,
+    /// Height of the panel
+    pub height: u16,
+    /// Address of the image buffer in the memory of the controller
+    pub image_buffer_address: u32,
+    firmware: [u8; 16],
+    lut: [u8; 16],
+}
+
+impl DeviceInfo {
+    fn from_words(words: &[u16; DEVICE_INFO_WORDS]) -> Self {
+        // the strings are stored with the first character in the low byte
+        let string = |words: &[u16]| {
+            let mut bytes = [0u8; 16];
+            for (pair, word) in bytes.chunks_exact_mut(2).zip(words) {
+                pair.copy_from_slice(&word.to_le_bytes());
+            }
+            bytes
+        };
+        DeviceInfo {
+            width: words[0],
+            height: words[1],
+            image_buffer_address: words[2] as u32 | (words[3] as u32) << 16,
+            firmware: string(&words[4..12]),
+            lut: string(&words[12..20]),
+        }
+    }
+
+    /// Version of the controller firmware
+    pub fn firmware_version(&self) -> &str {
+        version(&self.firmware)
+    }
+
+    /// Version of the LUT of the panel, e.g. `"M641"` for the 6" panel
+    pub fn lut_version(&self) -> &str {
+        version(&self.lut)
+    }
+}
+
+fn version(bytes: &[u8]) -> &str {
+    let len = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    core::str::from_utf8(&bytes[..len]).unwrap_or("")
+}
+
+/// IT8951 driver
+///
+pub struct It8951<SPI, CS, HRDY, RST, DELAY> {
+    /// CS for SPI
+    cs: CS,
+    /// Low while the controller can't take the next word
+    hrdy: HRDY,
+    /// Pin for Resetting
+    rst: RST,
+    /// Facts about the panel which the controller doesn't report
+    preset: Preset,
+    /// VCOM in mV, without the sign
+    vcom_mv: u16,
+    /// Reported by the controller on init
+    info: DeviceInfo,
+    _spi: PhantomData<SPI>,
+    _delay: PhantomData<DELAY>,
+}
+
+impl<SPI, CS, HRDY, RST, DELAY> It8951<SPI, CS, HRDY, RST, DELAY>
+where
+    SPI: Write<u8> + Transfer<u8, Error = SpiError<SPI>>,
+    CS: OutputPin,
+    HRDY: InputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    /// Creates a new driver for the panel of `preset`
+    ///
+    /// `vcom_mv` is the VCOM voltage printed on the ribbon cable of the panel in mV,
+    /// without the sign. This already initialises the device.
+    pub fn new(
+        spi: &mut SPI,
+        cs: CS,
+        hrdy: HRDY,
+        rst: RST,
+        preset: Preset,
+        vcom_mv: u16,
+        delay: &mut DELAY,
+    ) -> Result<Self, Error<SpiError<SPI>>> {
+        let mut epd = It8951 {
+            cs,
+            hrdy,
+            rst,
+            preset,
+            vcom_mv,
+            info: DeviceInfo::from_words(&[0; DEVICE_INFO_WORDS]),
+            _spi: PhantomData,
+            _delay: PhantomData,
+        };
+
+        epd.init(spi, delay)?;
+
+        Ok(epd)
+    }
+
+    /// Wakes the device up from sleep or standby
+    pub fn wake_up(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SpiError<SPI>>> {
+        self.init(spi, delay)
+    }
+
+    /// Waits for the refreshes to end and lets the controller and the panel sleep
+    pub fn sleep(&mut self, spi: &mut SPI) -> Result<(), Error<SpiError<SPI>>> {
+        self.wait_display_ready(spi)?;
+        self.command(spi, Command::Sleep)
+    }
+
+    /// Waits for the refreshes to end and stops the clocks of the controller
+    ///
+    /// Wakes up faster than [sleep](It8951::sleep), but draws more current.
+    pub fn standby(&mut self, spi: &mut SPI) -> Result<(), Error<SpiError<SPI>>> {
+        self.wait_display_ready(spi)?;
+        self.command(spi, Command::Standby)
+    }
+
+    /// Loads a full frame into the image memory without showing it yet
+    pub fn update_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+    ) -> Result<(), Error<SpiError<SPI>>> {
+        let (width, height) = (self.width(), self.height());
+        self.update_partial_frame(spi, buffer, 0, 0, width, height)
+    }
+
+    /// Loads the pixels of an area into the image memory without showing them yet
+    ///
+    /// `buffer` holds 4 bits per pixel, 2 pixels per byte starting with the most
+    /// significant nibble, like an [`It8951Display`]. `width` has to be a multiple of 4.
+    pub fn update_partial_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SpiError<SPI>>> {
+        ensure!(width.is_multiple_of(4));
+        ensure!(buffer.len() == (width / 2 * height) as usize);
+        self.load_image_area(spi, x, y, width, height)?;
+        self.image_data(spi, buffer)?;
+        self.command(spi, Command::LoadImageEnd)
+    }
+
+    /// Refreshes an area with the pixels in the image memory
+    ///
+    /// Returns once the refresh is started, [wait_display_ready](It8951::wait_display_ready)
+    /// waits for the end.
+    pub fn display_area(
+        &mut self,
+        spi: &mut SPI,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        mode: WaveformMode,
+    ) -> Result<(), Error<SpiError<SPI>>> {
+        self.wait_display_ready(spi)?;
+        let mode = mode.index(&self.preset);
+        self.command_with_args(
+            spi,
+            Command::DisplayArea,
+            &[x as u16, y as u16, width as u16, height as u16, mode],
+        )
+    }
+
+    /// Refreshes the whole panel with the pixels in the image memory
+    pub fn display_frame(
+        &mut self,
+        spi: &mut SPI,
+        mode: WaveformMode,
+    ) -> Result<(), Error<SpiError<SPI>>> {
+        let (width, height) = (self.width(), self.height());
+        self.display_area(spi, 0, 0, width, height, mode)
+    }
+
+    /// Loads and shows a full frame
+    pub fn update_and_display_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        mode: WaveformMode,
+    ) -> Result<(), Error<SpiError<SPI>>> {
+        self.update_frame(spi, buffer)?;
+        self.display_frame(spi, mode)
+    }
+
+    /// Clears the whole panel to white with [`WaveformMode::Init`]
+    pub fn clear_frame(&mut self, spi: &mut SPI) -> Result<(), Error<SpiError<SPI>>> {
+        let (width, height) = (self.width(), self.height());
+        self.load_image_area(spi, 0, 0, width, height)?;
+
+        self.wait_until_ready();
+        let _ = self.cs.set_low();
+        let result = self.fill(spi, WHITE, (width / 2 * height) as usize);
+        let _ = self.cs.set_high();
+        result?;
+
+        self.command(spi, Command::LoadImageEnd)?;
+        self.display_frame(spi, WaveformMode::Init)
+    }
+
+    /// Waits until all refreshes are done, by reading the status of the waveform engines
+    pub fn wait_display_ready(&mut self, spi: &mut SPI) -> Result<(), Error<SpiError<SPI>>> {
+        while self.read_register(spi, Register::Lutafsr)? != 0 {}
+        Ok(())
+    }
+
+    /// What the controller reported about itself on init
+    pub fn device_info(&self) -> &DeviceInfo {
+        &self.info
+    }
+
+    /// The preset the driver was created with
+    pub fn preset(&self) -> &Preset {
+        &self.preset
+    }
+
+    /// Width of the panel, as reported by the controller
+    pub fn width(&self) -> u32 {
+        self.info.width as u32
+    }
+
+    /// Height of the panel, as reported by the controller
+    pub fn height(&self) -> u32 {
+        self.info.height as u32
+    }
+
+    /// Checks if the controller can't take the next word yet
+    pub fn is_busy(&self) -> bool {
+        self.hrdy.is_low().unwrap_or(false)
+    }
+
+    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SpiError<SPI>>> {
+        self.reset(delay);
+        self.command(spi, Command::SysRun)?;
+
+        self.command(spi, Command::GetDeviceInfo)?;
+        let mut words = [0u16; DEVICE_INFO_WORDS];
+        self.read_words(spi, &mut words)?;
+        self.info = DeviceInfo::from_words(&words);
+
+        // packed pixels in the image data
+        self.write_register(spi, Register::I80Cpcr, 0x0001)?;
+        // set the VCOM without writing it to the flash
+        let vcom_mv = self.vcom_mv;
+        self.command_with_args(spi, Command::Vcom, &[0x0001, vcom_mv])
+    }
+
+    fn reset(&mut self, delay: &mut DELAY) {
+        let _ = self.rst.set_high();
+        delay.delay_ms(200);
+        let _ = self.rst.set_low();
+        delay.delay_ms(10);
+        let _ = self.rst.set_high();
+        delay.delay_ms(200);
+    }
+
+    /// Waits until the controller can take the next word
+    fn wait_until_ready(&self) {
+        while self.is_busy() {}
+    }
+
+    /// Sends one packet, the preamble followed by `data`, with CS held low
+    fn packet(
+        &mut self,
+        spi: &mut SPI,
+        preamble: u16,
+        data: &[u8],
+    ) -> Result<(), Error<SpiError<SPI>>> {
+        self.wait_until_ready();
+        let _ = self.cs.set_low();
+        let result = Write::write(spi, &preamble.to_be_bytes()).and_then(|()| {
+            self.wait_until_ready();
+            Write::write(spi, data)
+        });
+        let _ = self.cs.set_high();
+        result.map_err(Error::Spi)
+    }
+
+    fn command(&mut self, spi: &mut SPI, command: Command) -> Result<(), Error<SpiError<SPI>>> {
+        self.packet(spi, PREAMBLE_COMMAND, &command.address().to_be_bytes())
+    }
+
+    fn data_word(&mut self, spi: &mut SPI, word: u16) -> Result<(), Error<SpiError<SPI>>> {
+        self.packet(spi, PREAMBLE_WRITE, &word.to_be_bytes())
+    }
+
+    fn command_with_args(
+        &mut self,
+        spi: &mut SPI,
+        command: Command,
+        args: &[u16],
+    ) -> Result<(), Error<SpiError<SPI>>> {
+        self.command(spi, command)?;
+        for &arg in args {
+            self.data_word(spi, arg)?;
+        }
+        Ok(())
+    }
+
+    fn write_register(
+        &mut self,
+        spi: &mut SPI,
+        register: Register,
+        value: u16,
+    ) -> Result<(), Error<SpiError<SPI>>> {
+        self.command(spi, Command::RegisterWrite)?;
+        self.data_word(spi, register.address())?;
+        self.data_word(spi, value)
+    }
+
+    fn read_register(
+        &mut self,
+        spi: &mut SPI,
+        register: Register,
+    ) -> Result<u16, Error<SpiError<SPI>>> {
+        self.command(spi, Command::RegisterRead)?;
+        self.data_word(spi, register.address())?;
+        let mut value = [0u16];
+        self.read_words(spi, &mut value)?;
+        Ok(value[0])
+    }
+
+    /// Reads `words` in one packet, the controller sends a dummy word first
+    fn read_words(&mut self, spi: &mut SPI, words: &mut [u16]) -> Result<(), Error<SpiError<SPI>>> {
+        self.wait_until_ready();
+        let _ = self.cs.set_low();
+        let result = self.read_packet(spi, words);
+        let _ = self.cs.set_high();
+        result
+    }
+
+    fn read_packet(
+        &mut self,
+        spi: &mut SPI,
+        words: &mut [u16],
+    ) -> Result<(), Error<SpiError<SPI>>> {
+        Write::write(spi, &PREAMBLE_READ.to_be_bytes())?;
+        self.wait_until_ready();
+        spi.transfer(&mut [0, 0])?;
+        self.wait_until_ready();
+        for word in words.iter_mut() {
+            let mut bytes = [0, 0];
+            let bytes = spi.transfer(&mut bytes)?;
+            *word = u16::from_be_bytes([bytes[0], bytes[1]]);
+        }
+        Ok(())
+    }
+
+    /// Points the controller at the image buffer and starts loading an area
+    fn load_image_area(
+        &mut self,
+        spi: &mut SPI,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SpiError<SPI>>> {
+        // the image memory must not change under a running refresh
+        self.wait_display_ready(spi)?;
+        let address = self.info.image_buffer_address;
+        self.write_register(spi, Register::LisarHigh, (address >> 16) as u16)?;
+        self.write_register(spi, Register::LisarLow, address as u16)?;
+        self.command_with_args(
+            spi,
+            Command::LoadImageArea,
+            &[LOAD_4BPP, x as u16, y as u16, width as u16, height as u16],
+        )
+    }
+
+    /// Sends the pixels of `buffer` as little endian words in one packet
+    fn image_data(&mut self, spi: &mut SPI, buffer: &[u8]) -> Result<(), Error<SpiError<SPI>>> {
+        self.wait_until_ready();
+        let _ = self.cs.set_low();
+        let result = Write::write(spi, &PREAMBLE_WRITE.to_be_bytes()).and_then(|()| {
+            self.wait_until_ready();
+            let mut chunk = [0u8; 64];
+            for bytes in buffer.chunks(chunk.len()) {
+                let chunk = &mut chunk[..bytes.len()];
+                swap_words(bytes, chunk);
+                Write::write(spi, chunk)?;
+            }
+            Ok(())
+        });
+        let _ = self.cs.set_high();
+        result.map_err(Error::Spi)
+    }
+
+    /// Sends `len` bytes of `value` as image data in one packet, CS is already low
+    fn fill(&mut self, spi: &mut SPI, value: u8, len: usize) -> Result<(), Error<SpiError<SPI>>> {
+        Write::write(spi, &PREAMBLE_WRITE.to_be_bytes())?;
+        self.wait_until_ready();
+        let chunk = [value; 64];
+        let mut left = len;
+        while left > 0 {
+            let n = left.min(chunk.len());
+            Write::write(spi, &chunk[..n])?;
+            left -= n;
+        }
+        Ok(())
+    }
+}
+
+/// Copies `bytes` into `out` with the bytes of every 16 bit word swapped
+///
+/// The words are sent most significant byte first, but loaded as little endian, so
+/// the first byte of the buffer has to go out second.
+fn swap_words(bytes: &[u8], out: &mut [u8]) {
+    for (pair, swapped) in bytes.chunks(2).zip(out.chunks_mut(2)) {
+        for (i, byte) in pair.iter().rev().enumerate() {
+            if let Some(out) = swapped.get_mut(i) {
+                *out = *byte;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn device_info_from_words() {
+        let mut words = [0u16; DEVICE_INFO_WORDS];
+        words[..4].copy_from_slice(&[1872, 1404, 0x36E0, 0x0012]);
+        // "SWv_0.1." and "M841"
+        words[4..8].copy_from_slice(&[0x5753, 0x5F76, 0x2E30, 0x2E31]);
+        words[12..14].copy_from_slice(&[0x384D, 0x3134]);
+
+        let info = DeviceInfo::from_words(&words);
+        assert_eq!((info.width, info.height), (1872, 1404));
+        assert_eq!(info.image_buffer_address, 0x0012_36E0);
+        assert_eq!(info.firmware_version(), "SWv_0.1.");
+        assert_eq!(info.lut_version(), "M841");
+        assert!(presets::HAT_10IN3.matches(&info));
+        assert!(!presets::HAT_9IN7.matches(&info));
+    }
+
+    #[test]
+    fn a2_mode_from_preset() {
+        assert_eq!(WaveformMode::A2.index(&presets::HAT_6IN), 4);
+        assert_eq!(WaveformMode::A2.index(&presets::HAT_10IN3), 6);
+        assert_eq!(WaveformMode::Gc16.index(&presets::HAT_6IN), 2);
+    }
+
+    #[test]
+    fn words_are_swapped() {
+        let mut out = [0u8; 4];
+        swap_words(&[0x01, 0x23, 0x45, 0x67], &mut out);
+        assert_eq!(out, [0x23, 0x01, 0x67, 0x45]);
+    }
+}
@@ -0,0 +1,90 @@
+//! Presets of the Waveshare HATs with the IT8951 controller
+//!
+//! The controller reports the size of the panel itself, but not which waveforms are in
+//! the LUT of the panel. The presets fill this in, see [`WaveformMode`](super::WaveformMode).
+//! The 7.8" and 10.3" panels have the same resolution, so the preset can't be detected
+//! from the device info and has to be picked by the user.
+
+use super::DeviceInfo;
+
+/// Facts about a panel which the controller doesn't report
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Preset {
+    /// Name of the HAT
+    pub name: &'static str,
+    /// Width of the panel
+    pub width: u16,
+    /// Height of the panel
+    pub height: u16,
+    /// Index of the A2 waveform in the LUT of the panel
+    pub a2_mode: u16,
+}
+
+impl Preset {
+    /// Whether the controller reports the size of this panel
+    pub fn matches(&self, info: &DeviceInfo) -> bool {
+        self.width == info.width && self.height == info.height
+    }
+}
+
+/// 6" HAT, 800x600
+pub const HAT_6IN: Preset = Preset {
+    name: "6inch",
+    width: 800,
+    height: 600,
+    a2_mode: 4,
+};
+
+/// 7.8" HAT, 1872x1404
+pub const HAT_7IN8: Preset = Preset {
+    name: "7.8inch",
+    width: 1872,
+    height: 1404,
+    a2_mode: 6,
+};
+
+/// 9.7" HAT, 1200x825
+pub const HAT_9IN7: Preset = Preset {
+    name: "9.7inch",
+    width: 1200,
+    height: 825,
+    a2_mode: 6,
+};
+
+/// 10.3" HAT, 1872x1404
+pub const HAT_10IN3: Preset = Preset {
+    name: "10.3inch",
+    width: 1872,
+    height: 1404,
+    a2_mode: 6,
+};
+
+/// All presets
+pub static PRESETS: &[Preset] = &[HAT_6IN, HAT_7IN8, HAT_9IN7, HAT_10IN3];
+
+/// Finds the preset called `name`
+pub fn by_name(name: &str) -> Option<&'static Preset> {
+    PRESETS.iter().find(|preset| preset.name == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_are_unique() {
+        for (i, preset) in PRESETS.iter().enumerate() {
+            assert!(PRESETS[i + 1..].iter().all(|p| p.name != preset.name));
+            assert_eq!(by_name(preset.name), Some(preset));
+        }
+        assert_eq!(by_name("13.3inch"), None);
+    }
+
+    #[test]
+    fn sizes_fit_4bpp_rows() {
+        // the image is loaded in 16 bit words of 4 pixels
+        for preset in PRESETS {
+            assert_eq!(preset.width % 4, 0);
+        }
+    }
+}
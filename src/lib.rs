@@ -12,7 +12,7 @@
 //!
 //!```rust, no_run
 //!# use embedded_hal_mock::*;
-//!# fn main() -> Result<(), MockError> {
+//!# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
 //!use embedded_graphics::{
 //!    pixelcolor::BinaryColor::On as Black, prelude::*, primitives::{Line, PrimitiveStyle},
 //!};
@@ -60,9 +60,18 @@
 //!
 //! Maximum speed tested by myself was 8Mhz but more should be possible (Ben Krasnow used 18Mhz with his implemenation)
 //!
-#![no_std]
+#![cfg_attr(not(feature = "std"), no_std)]
 #![deny(missing_docs)]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[macro_use]
+mod macros;
+
+mod error;
+pub use crate::error::Error;
+
 #[cfg(feature = "graphics")]
 pub mod graphics;
 
@@ -70,37 +79,119 @@ mod traits;
 
 pub mod color;
 
+pub mod codec;
+
+pub mod budget;
+
+pub mod debounce;
+
+pub mod power;
+
+pub mod busy;
+
+pub mod refresh;
+
+pub mod calibration;
+
+pub mod service;
+
+pub mod metrics;
+
+pub mod testing;
+
+pub mod timing;
+
+pub mod transport;
+
+pub mod luts;
+
+pub mod acep;
+
+pub mod remote;
+
+pub mod tiled;
+
+pub mod factory;
+#[cfg(feature = "alloc")]
+pub use crate::factory::create;
+pub use crate::factory::{DynEpd, Panel, Pins};
+
+pub mod sequence;
+
+#[cfg(feature = "graphics")]
+pub mod stream;
+
+#[cfg(feature = "graphics")]
+pub mod direct;
+
 /// Interface for the physical connection between display and the controlling device
 mod interface;
 
+#[cfg(test)]
+mod bus_log;
+
+pub mod epd10in2;
+pub mod epd12in48;
+pub mod epd13in3k;
+pub mod epd1in02;
 pub mod epd1in54;
+pub mod epd1in54_v2;
 pub mod epd1in54b;
 pub mod epd1in54c;
+pub mod epd1in64g;
 pub mod epd2in13_v2;
+pub mod epd2in13_v4;
 pub mod epd2in13bc;
+pub mod epd2in13g;
+pub mod epd2in36g;
+pub mod epd2in66b;
+pub mod epd2in7_v2;
 pub mod epd2in7b;
 pub mod epd2in9;
 pub mod epd2in9_v2;
+pub mod epd2in9b_v4;
 pub mod epd2in9bc;
+pub mod epd3in0g;
+pub mod epd3in52;
+pub mod epd3in7;
+pub mod epd4in01f;
 pub mod epd4in2;
+pub mod epd4in26;
+pub mod epd4in2_v2;
+pub mod epd4in2bc_v2;
+pub mod epd4in37g;
 pub mod epd5in65f;
+pub mod epd5in79;
+pub mod epd5in83_v2;
+pub mod epd5in83bc_v2;
+pub mod epd7in3f;
+pub mod epd7in3g;
 pub mod epd7in5;
 pub mod epd7in5_hd;
 pub mod epd7in5_v2;
+pub mod epd7in5b_v2;
+pub mod it8951;
 
+pub(crate) mod controllers;
 pub(crate) mod type_a;
+pub(crate) mod type_g;
 
 /// Includes everything important besides the chosen Display
 pub mod prelude {
-    pub use crate::color::{Color, OctColor, TriColor};
+    pub use crate::color::{BorderColor, Color, OctColor, QuadColor, TriColor};
     pub use crate::traits::{
-        QuickRefresh, RefreshLut, WaveshareDisplay, WaveshareThreeColorDisplay,
+        BorderControl, CustomLut, DeepSleepMode, FrameRate, FrameRateControl, FullRefreshInterval,
+        GlassOffset, HardwareMirror, LutSelection, Mirror, PanelOffset, PowerDown, QuickRefresh,
+        RamBanks, ReadRevision, ReadTemperature, RefreshLut, Revision, SleepMode,
+        TemperatureOverride, VcomSetting, WaveshareDisplay, WaveshareThreeColorDisplay,
     };
 
     pub use crate::SPI_MODE;
 
     #[cfg(feature = "graphics")]
-    pub use crate::graphics::{Display, DisplayRotation, OctDisplay, TriDisplay};
+    pub use crate::graphics::{
+        Display, DisplayRotation, Gray4Display, GrayDisplay, OctDisplay, QuadDisplay, TriDisplay,
+    };
 }
 
 /// Computes the needed buffer length. Takes care of rounding up in case width
@@ -113,7 +204,17 @@ pub mod prelude {
 /// [XXXXX210][76543210]...[76543210] | height
 /// [XXXXX210][76543210]...[76543210] v
 pub const fn buffer_len(width: usize, height: usize) -> usize {
-    (width + 7) / 8 * height
+    width.div_ceil(8) * height
+}
+
+/// Last pixel of `len` pixels from `start` on, `None` if there are none or it overflows
+pub(crate) fn span_end(start: u32, len: u32) -> Option<u32> {
+    len.checked_sub(1).and_then(|last| start.checked_add(last))
+}
+
+/// Whether `len` pixels from `start` on are a non-empty span within `size` pixels
+pub(crate) fn span_fits(start: u32, len: u32, size: u32) -> bool {
+    span_end(start, len).is_some_and(|end| end < size)
 }
 
 use embedded_hal::spi::{Mode, Phase, Polarity};
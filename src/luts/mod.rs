@@ -0,0 +1,471 @@
+//! Waveform presets of the popular panels
+//!
+//! The lookup tables define the voltage sequence of a refresh. Every table listed here
+//! is one the drivers already load themselves, taken from the Waveshare vendor drivers,
+//! so there is no need to copy unverified blobs from forum posts to get a fast or gray
+//! refresh. Each [`LutPreset`] names the panel and the kind of refresh, and lists the
+//! registers to write.
+//!
+//! The vendor tables are tuned for the whole operating range of the panels, so every
+//! preset here is made for [`TemperatureBand::OPERATING`]. Outside of it [`select`] finds
+//! nothing and the display should refresh with the waveform from its OTP memory instead.
+//! Waveforms tuned for narrower bands are picked from a [`LutTable`].
+//!
+//! Tuned waveforms are written as a [`ssd1680::Lut`] or [`uc81xx::Lut`], with named
+//! phases and timings, and loaded with [`CustomLut`](crate::traits::CustomLut).
+//!
+//!```rust, no_run
+//!# use embedded_hal_mock::*;
+//!# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
+//!use epd_waveshare::{epd4in2::*, luts::{self, LutKind, Panel}, prelude::*};
+//!#
+//!# let expectations = [];
+//!# let mut spi = spi::Mock::new(&expectations);
+//!# let expectations = [];
+//!# let cs_pin = pin::Mock::new(&expectations);
+//!# let busy_in = pin::Mock::new(&expectations);
+//!# let dc = pin::Mock::new(&expectations);
+//!# let rst = pin::Mock::new(&expectations);
+//!# let mut delay = delay::MockNoop::new();
+//!
+//!let mut epd = Epd4in2::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
+//!
+//!// pick the fast waveform for the current temperature
+//!if let Some(preset) = luts::select(Panel::Epd4in2, LutKind::Fast, 22) {
+//!    epd.set_lut_preset(&mut spi, preset)?;
+//!}
+//!
+//!// or by name, e.g. from a configuration file
+//!let preset = luts::by_name("4in2/full").unwrap();
+//!epd.set_lut_preset(&mut spi, preset)?;
+//!# Ok(())
+//!# }
+//!```
+
+use embedded_hal::{
+    blocking::{delay::*, spi::Write},
+    digital::v2::{InputPin, OutputPin},
+};
+
+use crate::interface::DisplayInterface;
+use crate::traits::{self, RefreshLut};
+use crate::Error;
+
+/// The panel a preset is made for, the same as the one chosen at runtime in
+/// [`factory`](crate::factory)
+pub use crate::factory::Panel;
+
+pub mod ssd1680;
+pub mod uc81xx;
+
+/// The kind of refresh a preset is made for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LutKind {
+    /// Full refresh without ghosting
+    Full,
+    /// Refresh of the whole frame with a shorter sequence
+    Fast,
+    /// Refresh of the changed pixels only
+    Partial,
+    /// Refresh with 4 gray levels
+    Gray4,
+}
+
+/// Where the waveform of the full refreshes comes from
+///
+/// The OTP waveform is tuned by the panel vendor for the measured temperature and gives the
+/// best image, the registers take the fast or custom waveforms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LutSource {
+    /// The OTP memory of the panel
+    Otp,
+    /// The LUT registers of the controller
+    Register,
+}
+
+/// Temperature range in °C a preset is made for, both ends included
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TemperatureBand {
+    /// Lowest temperature
+    pub min_c: i8,
+    /// Highest temperature
+    pub max_c: i8,
+}
+
+impl TemperatureBand {
+    /// The operating range of the panels, which the vendor waveforms are made for
+    pub const OPERATING: TemperatureBand = TemperatureBand::new(0, 50);
+
+    /// Creates the band from `min_c` to `max_c`
+    pub const fn new(min_c: i8, max_c: i8) -> Self {
+        TemperatureBand { min_c, max_c }
+    }
+
+    /// Whether `temperature_c` lies within the band
+    pub fn contains(&self, temperature_c: i8) -> bool {
+        (self.min_c..=self.max_c).contains(&temperature_c)
+    }
+}
+
+/// One register write of a preset
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LutRegister {
+    /// Address of the command
+    pub command: u8,
+    /// Data sent with the command
+    pub data: &'static [u8],
+}
+
+/// A waveform for one panel and kind of refresh
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LutPreset {
+    /// Unique name, `<panel>/<kind>` like `"2in9_v2/gray4"`
+    pub name: &'static str,
+    /// Panel the preset is made for
+    pub panel: Panel,
+    /// Kind of refresh
+    pub kind: LutKind,
+    /// Temperatures the preset is made for
+    pub band: TemperatureBand,
+    /// Registers to write, in order
+    pub registers: &'static [LutRegister],
+}
+
+const fn preset(
+    name: &'static str,
+    panel: Panel,
+    kind: LutKind,
+    registers: &'static [LutRegister],
+) -> LutPreset {
+    LutPreset {
+        name,
+        panel,
+        kind,
+        band: TemperatureBand::OPERATING,
+        registers,
+    }
+}
+
+const fn register(command: u8, data: &'static [u8]) -> LutRegister {
+    LutRegister { command, data }
+}
+
+// Register addresses, see the command modules of the drivers
+const SSD_WRITE_LUT: u8 = 0x32;
+const SSD_END_OPTION: u8 = 0x3F;
+const SSD_GATE_VOLTAGE: u8 = 0x03;
+const SSD_SOURCE_VOLTAGE: u8 = 0x04;
+const SSD_VCOM: u8 = 0x2C;
+const UC_LUT_VCOM: u8 = 0x20;
+const UC_LUT_WW: u8 = 0x21;
+const UC_LUT_BW: u8 = 0x22;
+const UC_LUT_WB: u8 = 0x23;
+const UC_LUT_BB: u8 = 0x24;
+
+mod tables {
+    pub(super) use crate::epd2in13_v2::constants::{
+        LUT_FULL_UPDATE as EPD2IN13_V2_FULL, LUT_PARTIAL_UPDATE as EPD2IN13_V2_PARTIAL,
+    };
+    pub(super) use crate::epd2in9_v2::{LUT_GRAY4_2IN9, LUT_PARTIAL_2IN9};
+    pub(super) use crate::epd4in2::constants::*;
+    pub(super) use crate::epd7in5_v2::constants::{
+        LUT_BB_QUICK as EPD7IN5_V2_BB, LUT_BW_QUICK as EPD7IN5_V2_BW,
+        LUT_VCOM_QUICK as EPD7IN5_V2_VCOM, LUT_WB_QUICK as EPD7IN5_V2_WB,
+        LUT_WW_QUICK as EPD7IN5_V2_WW,
+    };
+    pub(super) use crate::type_a::constants::{
+        LUT_FULL_UPDATE as TYPE_A_FULL, LUT_PARTIAL_UPDATE as TYPE_A_PARTIAL,
+    };
+}
+use self::tables::*;
+
+/// All presets
+pub static PRESETS: &[LutPreset] = &[
+    preset(
+        "2in13_v2/full",
+        Panel::Epd2in13V2,
+        LutKind::Full,
+        &[register(SSD_WRITE_LUT, &EPD2IN13_V2_FULL)],
+    ),
+    preset(
+        "2in13_v2/partial",
+        Panel::Epd2in13V2,
+        LutKind::Partial,
+        &[register(SSD_WRITE_LUT, &EPD2IN13_V2_PARTIAL)],
+    ),
+    preset(
+        "2in9/full",
+        Panel::Epd2in9,
+        LutKind::Full,
+        &[register(SSD_WRITE_LUT, &TYPE_A_FULL)],
+    ),
+    preset(
+        "2in9/partial",
+        Panel::Epd2in9,
+        LutKind::Partial,
+        &[register(SSD_WRITE_LUT, &TYPE_A_PARTIAL)],
+    ),
+    preset(
+        "2in9_v2/partial",
+        Panel::Epd2in9V2,
+        LutKind::Partial,
+        &[register(SSD_WRITE_LUT, &LUT_PARTIAL_2IN9)],
+    ),
+    preset(
+        "2in9_v2/gray4",
+        Panel::Epd2in9V2,
+        LutKind::Gray4,
+        &[
+            register(SSD_WRITE_LUT, LUT_GRAY4_2IN9.split_at(153).0),
+            register(SSD_END_OPTION, &[LUT_GRAY4_2IN9[153]]),
+            register(SSD_GATE_VOLTAGE, &[LUT_GRAY4_2IN9[154]]),
+            register(
+                SSD_SOURCE_VOLTAGE,
+                &[
+                    LUT_GRAY4_2IN9[155],
+                    LUT_GRAY4_2IN9[156],
+                    LUT_GRAY4_2IN9[157],
+                ],
+            ),
+            register(SSD_VCOM, &[LUT_GRAY4_2IN9[158]]),
+        ],
+    ),
+    preset(
+        "4in2/full",
+        Panel::Epd4in2,
+        LutKind::Full,
+        &[
+            register(UC_LUT_VCOM, &LUT_VCOM0),
+            register(UC_LUT_WW, &LUT_WW),
+            register(UC_LUT_BW, &LUT_BW),
+            register(UC_LUT_WB, &LUT_WB),
+            register(UC_LUT_BB, &LUT_BB),
+        ],
+    ),
+    preset(
+        "4in2/fast",
+        Panel::Epd4in2,
+        LutKind::Fast,
+        &[
+            register(UC_LUT_VCOM, &LUT_VCOM0_QUICK),
+            register(UC_LUT_WW, &LUT_WW_QUICK),
+            register(UC_LUT_BW, &LUT_BW_QUICK),
+            register(UC_LUT_WB, &LUT_WB_QUICK),
+            register(UC_LUT_BB, &LUT_BB_QUICK),
+        ],
+    ),
+    preset(
+        "4in2/gray4",
+        Panel::Epd4in2,
+        LutKind::Gray4,
+        &[
+            register(UC_LUT_VCOM, &LUT_VCOM_GRAY4),
+            register(UC_LUT_WW, &LUT_WW_GRAY4),
+            register(UC_LUT_BW, &LUT_BW_GRAY4),
+            register(UC_LUT_WB, &LUT_WB_GRAY4),
+            register(UC_LUT_BB, &LUT_BB_GRAY4),
+        ],
+    ),
+    preset(
+        "7in5_v2/fast",
+        Panel::Epd7in5V2,
+        LutKind::Fast,
+        &[
+            register(UC_LUT_VCOM, &EPD7IN5_V2_VCOM),
+            register(UC_LUT_WW, &EPD7IN5_V2_WW),
+            register(UC_LUT_BW, &EPD7IN5_V2_BW),
+            register(UC_LUT_WB, &EPD7IN5_V2_WB),
+            register(UC_LUT_BB, &EPD7IN5_V2_BB),
+        ],
+    ),
+];
+
+/// Finds the preset called `name`
+pub fn by_name(name: &str) -> Option<&'static LutPreset> {
+    PRESETS.iter().find(|preset| preset.name == name)
+}
+
+/// Finds the preset of `panel` for `kind` made for `temperature_c`
+pub fn select(panel: Panel, kind: LutKind, temperature_c: i8) -> Option<&'static LutPreset> {
+    PRESETS.iter().find(|preset| {
+        preset.panel == panel && preset.kind == kind && preset.band.contains(temperature_c)
+    })
+}
+
+/// Waveforms of one panel for several temperature bands
+///
+/// Like the firmware of electronic shelf labels, the waveform is picked for the measured
+/// temperature. Outside of all bands the panel refreshes with its full waveform:
+///
+///```rust, no_run
+///# use embedded_hal_mock::*;
+///# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
+///use epd_waveshare::{
+///    epd2in9_v2::*,
+///    luts::{LutKind, LutPreset, LutRegister, LutTable, Panel, TemperatureBand},
+///    prelude::*,
+///};
+///#
+///# let expectations = [];
+///# let mut spi = spi::Mock::new(&expectations);
+///# let expectations = [];
+///# let cs_pin = pin::Mock::new(&expectations);
+///# let busy_in = pin::Mock::new(&expectations);
+///# let dc = pin::Mock::new(&expectations);
+///# let rst = pin::Mock::new(&expectations);
+///# let mut delay = delay::MockNoop::new();
+///# const COLD: [u8; 153] = [0; 153];
+///# const WARM: [u8; 153] = [0; 153];
+///
+///// tuned waveforms for the cold storage and the sales floor
+///static TABLE: LutTable = LutTable::new(&[
+///    LutPreset {
+///        name: "cold",
+///        panel: Panel::Epd2in9V2,
+///        kind: LutKind::Full,
+///        band: TemperatureBand::new(-10, 9),
+///        registers: &[LutRegister { command: 0x32, data: &COLD }],
+///    },
+///    LutPreset {
+///        name: "warm",
+///        panel: Panel::Epd2in9V2,
+///        kind: LutKind::Full,
+///        band: TemperatureBand::new(10, 40),
+///        registers: &[LutRegister { command: 0x32, data: &WARM }],
+///    },
+///]);
+///
+///let mut epd = Epd2in9::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
+///let temperature_c = epd.read_temperature(&mut spi)?;
+///epd.set_lut(&mut spi, Some(TABLE.refresh_lut(temperature_c)))?;
+///# Ok(())
+///# }
+///```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LutTable<'a> {
+    presets: &'a [LutPreset],
+}
+
+impl<'a> LutTable<'a> {
+    /// Creates the table, the first preset whose band contains the temperature is chosen
+    pub const fn new(presets: &'a [LutPreset]) -> Self {
+        LutTable { presets }
+    }
+
+    /// Finds the preset made for `temperature_c`
+    pub fn select(&self, temperature_c: i8) -> Option<&'a LutPreset> {
+        self.presets
+            .iter()
+            .find(|preset| preset.band.contains(temperature_c))
+    }
+}
+
+impl LutTable<'static> {
+    /// The waveform to pass to [`set_lut`](traits::WaveshareDisplay::set_lut) at
+    /// `temperature_c`, `RefreshLut::Full` outside of all bands
+    pub fn refresh_lut(&self, temperature_c: i8) -> RefreshLut {
+        self.select(temperature_c)
+            .map_or(RefreshLut::Full, RefreshLut::Custom)
+    }
+}
+
+/// A command given by its address only
+#[derive(Clone, Copy)]
+struct Register(u8);
+
+impl traits::Command for Register {
+    fn address(self) -> u8 {
+        self.0
+    }
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> DisplayInterface<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    /// Writes all registers of `preset`, which has to be made for `panel`
+    pub(crate) fn write_lut_preset(
+        &mut self,
+        spi: &mut SPI,
+        panel: Panel,
+        preset: &LutPreset,
+    ) -> Result<(), Error<SPI::Error>> {
+        ensure!(preset.panel == panel);
+        for register in preset.registers {
+            self.cmd_with_data(spi, Register(register.command), register.data)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_are_unique() {
+        for (i, preset) in PRESETS.iter().enumerate() {
+            assert!(PRESETS[i + 1..].iter().all(|p| p.name != preset.name));
+            assert_eq!(by_name(preset.name), Some(preset));
+        }
+        assert_eq!(by_name("7in5/fast"), None);
+        assert_eq!(by_name("7in5_v2/fast").unwrap().panel, Panel::Epd7in5V2);
+    }
+
+    #[test]
+    fn select_by_temperature() {
+        let preset = select(Panel::Epd4in2, LutKind::Fast, 25).unwrap();
+        assert_eq!(preset.name, "4in2/fast");
+        assert_eq!(preset.registers.len(), 5);
+        assert_eq!(select(Panel::Epd4in2, LutKind::Fast, -10), None);
+        assert_eq!(select(Panel::Epd4in2, LutKind::Partial, 25), None);
+        assert_eq!(
+            select(Panel::Epd4in2, LutKind::Gray4, 25).map(|p| p.name),
+            Some("4in2/gray4")
+        );
+    }
+
+    #[test]
+    fn gray_preset_splits_voltages() {
+        let preset = by_name("2in9_v2/gray4").unwrap();
+        let lengths: [usize; 5] = [153, 1, 1, 3, 1];
+        for (register, len) in preset.registers.iter().zip(lengths) {
+            assert_eq!(register.data.len(), len);
+        }
+    }
+
+    #[test]
+    fn table_by_temperature() {
+        static TABLE: LutTable = LutTable::new(&[
+            LutPreset {
+                band: TemperatureBand::new(-20, 4),
+                ..preset("cold", Panel::Epd4in2, LutKind::Full, &[])
+            },
+            LutPreset {
+                band: TemperatureBand::new(0, 30),
+                ..preset("warm", Panel::Epd4in2, LutKind::Full, &[])
+            },
+        ]);
+        assert_eq!(TABLE.select(-20).map(|p| p.name), Some("cold"));
+        // the first band wins where they overlap
+        assert_eq!(TABLE.select(2).map(|p| p.name), Some("cold"));
+        assert_eq!(TABLE.select(5).map(|p| p.name), Some("warm"));
+        assert_eq!(TABLE.select(31), None);
+
+        assert_eq!(TABLE.refresh_lut(20), RefreshLut::Custom(&TABLE.presets[1]));
+        assert_eq!(LutTable::new(&[]).refresh_lut(20), RefreshLut::Full);
+    }
+
+    #[test]
+    fn band_contains_ends() {
+        let band = TemperatureBand::new(-5, 5);
+        assert!(band.contains(-5));
+        assert!(band.contains(5));
+        assert!(!band.contains(6));
+    }
+}
@@ -0,0 +1,160 @@
+//! Waveforms of the SSD1680 and SSD1681 controllers
+//!
+//! A waveform has 12 groups of 4 phases A to D. For every group, the five LUTs choose
+//! the voltage of each phase: LUT0 to LUT3 for the pixels, selected by their bits in
+//! both RAMs, and LUT4 for VCOM. The timing of the phases is shared by all LUTs. The
+//! voltages themselves are set behind the waveform, so one [`Lut`] holds all 159 bytes
+//! the vendor drivers write.
+//!
+//!```rust
+//!use epd_waveshare::luts::ssd1680::{Lut, Voltage};
+//!
+//!// drive the pixels of LUT1 to black with VSH1 for 10 frames
+//!let mut lut = Lut::default();
+//!lut.voltages[1][0] = Voltage::levels([Voltage::Vsh1, Voltage::Vss, Voltage::Vss, Voltage::Vss]);
+//!lut.groups[0].phases = [10, 0, 0, 0];
+//!lut.groups[0].repeat = 1;
+//!assert_eq!(Lut::from_bytes(&lut.to_bytes()), lut);
+//!```
+
+/// Number of groups of a waveform
+pub const GROUPS: usize = 12;
+
+/// Number of LUTs, 4 for the pixels and 1 for VCOM
+pub const LUTS: usize = 5;
+
+/// Length of a waveform with its voltages, as written by the vendor drivers
+pub const LEN: usize = 159;
+
+/// Length of the waveform alone, written with the `WriteLutRegister` command
+const WAVEFORM_LEN: usize = 153;
+
+/// Voltage of a phase, 2 bits of a [`Lut::voltages`] byte
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Voltage {
+    /// Ground
+    Vss = 0b00,
+    /// First positive source voltage
+    Vsh1 = 0b01,
+    /// Negative source voltage
+    Vsl = 0b10,
+    /// Second positive source voltage
+    Vsh2 = 0b11,
+}
+
+impl Voltage {
+    /// Packs the voltages of the phases A to D into one byte, A in the high bits
+    pub const fn levels(phases: [Voltage; 4]) -> u8 {
+        (phases[0] as u8) << 6 | (phases[1] as u8) << 4 | (phases[2] as u8) << 2 | phases[3] as u8
+    }
+}
+
+/// Timing of one group
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Group {
+    /// Frames of the phases A to D
+    pub phases: [u8; 4],
+    /// Repeats of the phases A and B
+    pub repeat_ab: u8,
+    /// Repeats of the phases C and D
+    pub repeat_cd: u8,
+    /// Repeats of the whole group
+    pub repeat: u8,
+}
+
+/// A complete waveform
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Lut {
+    /// Voltages of every group in LUT0 to LUT4, see [`Voltage::levels`]
+    pub voltages: [[u8; GROUPS]; LUTS],
+    /// Timing of every group
+    pub groups: [Group; GROUPS],
+    /// Frame rates, two groups per byte
+    pub frame_rates: [u8; GROUPS / 2],
+    /// Groups with all gates on, one bit per group
+    pub gates_on: [u8; 3],
+    /// Option after the refresh, like keeping the source output
+    pub end_option: u8,
+    /// Gate voltage
+    pub gate_voltage: u8,
+    /// Source voltages VSH1, VSH2 and VSL
+    pub source_voltages: [u8; 3],
+    /// VCOM
+    pub vcom: u8,
+}
+
+impl Lut {
+    /// Reads a waveform in the layout of the vendor drivers
+    pub fn from_bytes(bytes: &[u8; LEN]) -> Self {
+        let mut lut = Lut::default();
+        for (voltages, chunk) in lut.voltages.iter_mut().zip(bytes.chunks(GROUPS)) {
+            voltages.copy_from_slice(chunk);
+        }
+        let timing = &bytes[GROUPS * LUTS..];
+        for (group, chunk) in lut.groups.iter_mut().zip(timing.chunks(7)) {
+            *group = Group {
+                phases: [chunk[0], chunk[1], chunk[3], chunk[4]],
+                repeat_ab: chunk[2],
+                repeat_cd: chunk[5],
+                repeat: chunk[6],
+            };
+        }
+        lut.frame_rates.copy_from_slice(&bytes[144..150]);
+        lut.gates_on.copy_from_slice(&bytes[150..WAVEFORM_LEN]);
+        lut.end_option = bytes[153];
+        lut.gate_voltage = bytes[154];
+        lut.source_voltages.copy_from_slice(&bytes[155..158]);
+        lut.vcom = bytes[158];
+        lut
+    }
+
+    /// Writes the waveform in the layout of the vendor drivers
+    pub fn to_bytes(&self) -> [u8; LEN] {
+        let mut bytes = [0; LEN];
+        for (chunk, voltages) in bytes.chunks_mut(GROUPS).zip(&self.voltages) {
+            chunk.copy_from_slice(voltages);
+        }
+        let timing = &mut bytes[GROUPS * LUTS..];
+        for (chunk, group) in timing.chunks_mut(7).zip(&self.groups) {
+            chunk.copy_from_slice(&[
+                group.phases[0],
+                group.phases[1],
+                group.repeat_ab,
+                group.phases[2],
+                group.phases[3],
+                group.repeat_cd,
+                group.repeat,
+            ]);
+        }
+        bytes[144..150].copy_from_slice(&self.frame_rates);
+        bytes[150..WAVEFORM_LEN].copy_from_slice(&self.gates_on);
+        bytes[153] = self.end_option;
+        bytes[154] = self.gate_voltage;
+        bytes[155..158].copy_from_slice(&self.source_voltages);
+        bytes[158] = self.vcom;
+        bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::epd2in9_v2::LUT_GRAY4_2IN9;
+
+    #[test]
+    fn vendor_layout_round_trip() {
+        let lut = Lut::from_bytes(&LUT_GRAY4_2IN9);
+        assert_eq!(lut.to_bytes(), LUT_GRAY4_2IN9);
+        assert_eq!(lut.voltages[0][0], LUT_GRAY4_2IN9[0]);
+        assert_eq!(lut.groups[0].phases[0], LUT_GRAY4_2IN9[60]);
+        assert_eq!(lut.groups[1].repeat, LUT_GRAY4_2IN9[73]);
+        assert_eq!(lut.vcom, LUT_GRAY4_2IN9[158]);
+    }
+
+    #[test]
+    fn voltage_levels() {
+        use Voltage::*;
+        assert_eq!(Voltage::levels([Vsh1, Vsl, Vsh2, Vss]), 0b0110_1100);
+        assert_eq!(Voltage::levels([Vss; 4]), 0);
+    }
+}
@@ -0,0 +1,129 @@
+//! Waveforms of the UC81xx controllers
+//!
+//! The UC8151, UC8176 and UC8179 write a table for VCOM and one for every transition of a
+//! pixel: white to white, black to white, white to black and black to black. Each table
+//! has 7 groups of 4 phases, which choose a level and last a number of frames.
+//!
+//!```rust
+//!use epd_waveshare::luts::uc81xx::{Group, Level, Lut};
+//!
+//!// drive the white to black pixels low for 14 frames
+//!let mut lut = Lut::default();
+//!lut.wb.groups[0] = Group {
+//!    levels: Level::levels([Level::Vdl, Level::Gnd, Level::Gnd, Level::Gnd]),
+//!    frames: [14, 0, 0, 0],
+//!    repeat: 1,
+//!};
+//!assert_eq!(lut.wb.to_bytes()[..6], [0x80, 14, 0, 0, 0, 1]);
+//!```
+
+/// Number of groups of a table
+pub const GROUPS: usize = 7;
+
+/// Length of a table
+pub const TABLE_LEN: usize = GROUPS * 6;
+
+/// Level of a phase, 2 bits of a [`Group::levels`] byte
+///
+/// In the VCOM table the levels are relative to VCOM_DC, and `Vdhr` leaves VCOM floating.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    /// Ground
+    Gnd = 0b00,
+    /// Positive voltage
+    Vdh = 0b01,
+    /// Negative voltage
+    Vdl = 0b10,
+    /// Positive voltage of the red pixels
+    Vdhr = 0b11,
+}
+
+impl Level {
+    /// Packs the levels of the phases 1 to 4 into one byte, phase 1 in the high bits
+    pub const fn levels(phases: [Level; 4]) -> u8 {
+        (phases[0] as u8) << 6 | (phases[1] as u8) << 4 | (phases[2] as u8) << 2 | phases[3] as u8
+    }
+}
+
+/// One group of a table
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Group {
+    /// Levels of the phases, see [`Level::levels`]
+    pub levels: u8,
+    /// Frames of the phases
+    pub frames: [u8; 4],
+    /// Repeats of the group
+    pub repeat: u8,
+}
+
+/// The table of VCOM or one transition
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Table {
+    /// Groups in the order they run
+    pub groups: [Group; GROUPS],
+}
+
+impl Table {
+    /// Reads a table in the layout of the vendor drivers
+    pub fn from_bytes(bytes: &[u8; TABLE_LEN]) -> Self {
+        let mut table = Table::default();
+        for (group, chunk) in table.groups.iter_mut().zip(bytes.chunks(6)) {
+            *group = Group {
+                levels: chunk[0],
+                frames: [chunk[1], chunk[2], chunk[3], chunk[4]],
+                repeat: chunk[5],
+            };
+        }
+        table
+    }
+
+    /// Writes the table in the layout of the vendor drivers
+    pub fn to_bytes(&self) -> [u8; TABLE_LEN] {
+        let mut bytes = [0; TABLE_LEN];
+        for (chunk, group) in bytes.chunks_mut(6).zip(&self.groups) {
+            chunk[0] = group.levels;
+            chunk[1..5].copy_from_slice(&group.frames);
+            chunk[5] = group.repeat;
+        }
+        bytes
+    }
+}
+
+/// A complete waveform
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Lut {
+    /// VCOM
+    pub vcom: Table,
+    /// White to white
+    pub ww: Table,
+    /// Black to white
+    pub bw: Table,
+    /// White to black
+    pub wb: Table,
+    /// Black to black
+    pub bb: Table,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::epd4in2::constants::{LUT_VCOM0, LUT_WB};
+    use core::convert::TryInto;
+
+    #[test]
+    fn vendor_layout_round_trip() {
+        let table = Table::from_bytes(&LUT_WB);
+        assert_eq!(table.to_bytes(), LUT_WB);
+        assert_eq!(table.groups[0].levels, LUT_WB[0]);
+        assert_eq!(table.groups[1].frames, [0x17, 0x17, 0x00, 0x00]);
+
+        let vcom: &[u8; TABLE_LEN] = LUT_VCOM0[..TABLE_LEN].try_into().unwrap();
+        assert_eq!(Table::from_bytes(vcom).groups[2].repeat, 0x01);
+    }
+
+    #[test]
+    fn phase_levels() {
+        use Level::*;
+        assert_eq!(Level::levels([Vdh, Vdl, Gnd, Vdhr]), 0b0110_0011);
+    }
+}
@@ -0,0 +1,188 @@
+//! Macros for the checks which panic unless the `panic_free` feature is enabled
+//!
+//! Without the feature, unsupported calls and violated preconditions panic, which
+//! points at the mistake during development. With the feature the same calls return
+//! an [`Error`](crate::Error) without sending anything instead, for deployments where a
+//! panic (and the watchdog reset following it) is worse than a frame which isn't shown.
+
+/// Rejects a call whose precondition doesn't hold
+///
+/// Panics without the `panic_free` feature, otherwise returns
+/// [`Error::InvalidArgument`](crate::Error::InvalidArgument), nothing or the given value
+/// from the surrounding function.
+macro_rules! ensure {
+    ($cond:expr) => {
+        ensure!($cond, Err($crate::Error::InvalidArgument))
+    };
+    ($cond:expr, ()) => {
+        if !$cond {
+            #[cfg(not(feature = "panic_free"))]
+            panic!("precondition failed: {}", stringify!($cond));
+            #[cfg(feature = "panic_free")]
+            return;
+        }
+    };
+    ($cond:expr, $ret:expr) => {
+        if !$cond {
+            #[cfg(not(feature = "panic_free"))]
+            panic!("precondition failed: {}", stringify!($cond));
+            #[cfg(feature = "panic_free")]
+            return $ret;
+        }
+    };
+}
+
+/// Marks a trait method which the display doesn't support
+///
+/// Panics without the `panic_free` feature, otherwise returns
+/// [`Error::Unsupported`](crate::Error::Unsupported).
+macro_rules! unsupported {
+    () => {{
+        #[cfg(not(feature = "panic_free"))]
+        unimplemented!();
+        #[cfg(feature = "panic_free")]
+        return Err($crate::Error::Unsupported);
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use crate::bus_log::{Log, Pin, Spi};
+    use crate::traits::WaveshareDisplay;
+    use crate::*;
+    use embedded_hal_mock::delay::MockNoop;
+    use std::{panic, vec};
+
+    fn checked(value: u8) -> Result<(), Error<()>> {
+        ensure!(value < 8);
+        Err(Error::Spi(()))
+    }
+
+    #[cfg(not(feature = "panic_free"))]
+    #[test]
+    #[should_panic]
+    fn ensure_panics() {
+        let _ = checked(8);
+    }
+
+    #[cfg(feature = "panic_free")]
+    #[test]
+    fn ensure_returns() {
+        assert_eq!(checked(8), Err(Error::InvalidArgument));
+        assert_eq!(checked(0), Err(Error::Spi(())));
+    }
+
+    /// Sends partial windows which don't fit on the panel and checks that the driver
+    /// rejects them, with a panic or an error depending on the `panic_free` feature
+    fn rejects_windows<'a, EPD>(log: &'a Log)
+    where
+        EPD: WaveshareDisplay<Spi<'a>, Pin<'a>, Pin<'a>, Pin<'a>, Pin<'a>, MockNoop>,
+    {
+        let mut spi = Spi(log);
+        let mut delay = MockNoop::new();
+        let mut epd = EPD::new(
+            &mut spi,
+            Pin(None),
+            Pin(None),
+            Pin(Some(log)),
+            Pin(None),
+            &mut delay,
+        )
+        .unwrap();
+        log.take();
+        let (width, height) = (epd.width(), epd.height());
+        let windows = [
+            // wraps around the end of u32
+            (u32::MAX - 7, 0, 8, 8),
+            (0, u32::MAX, 8, 1),
+            // reaches past the right or bottom edge
+            (0, 0, width + 8, 8),
+            (0, height, 8, 1),
+        ];
+        for &(x, y, w, h) in windows.iter() {
+            let buffer = vec![0xFF; buffer_len(w as usize, h as usize)];
+            let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+                epd.update_partial_frame(&mut spi, &buffer, x, y, w, h)
+            }));
+            let driver = std::any::type_name::<EPD>().split('<').next();
+            let window = (driver, x, y, w, h);
+            // a gated panic, not an overflow or an index out of bounds
+            #[cfg(not(feature = "panic_free"))]
+            {
+                use std::string::String;
+                let payload = result.expect_err("window accepted");
+                let message = payload
+                    .downcast_ref::<String>()
+                    .map(String::as_str)
+                    .or_else(|| payload.downcast_ref::<&str>().copied());
+                assert!(
+                    matches!(message, Some(m) if m.starts_with("precondition failed")
+                        || m.starts_with("not implemented")),
+                    "{:?}: {:?}",
+                    window,
+                    message
+                );
+            }
+            #[cfg(feature = "panic_free")]
+            assert!(
+                matches!(
+                    result,
+                    Ok(Err(Error::InvalidArgument)) | Ok(Err(Error::Unsupported))
+                ),
+                "{:?}",
+                window
+            );
+            assert!(log.take().is_empty(), "{:?} sent", window);
+        }
+    }
+
+    #[test]
+    fn drivers_reject_windows_off_the_panel() {
+        macro_rules! check {
+            ($($driver:ident)::+) => {{
+                let log = Log::default();
+                rejects_windows::<$($driver)::+<_, _, _, _, _, _>>(&log);
+            }};
+        }
+        check!(epd1in02::Epd1in02);
+        check!(epd1in54::Epd1in54);
+        check!(epd1in54_v2::Epd1in54);
+        check!(epd1in54b::Epd1in54b);
+        check!(epd1in54c::Epd1in54c);
+        check!(epd1in64g::Epd1in64g);
+        check!(epd2in13_v2::Epd2in13);
+        check!(epd2in13_v4::Epd2in13);
+        check!(epd2in13bc::Epd2in13bc);
+        check!(epd2in13g::Epd2in13g);
+        check!(epd2in36g::Epd2in36g);
+        check!(epd2in66b::Epd2in66b);
+        check!(epd2in7_v2::Epd2in7);
+        check!(epd2in7b::Epd2in7b);
+        check!(epd2in9::Epd2in9);
+        check!(epd2in9_v2::Epd2in9);
+        check!(epd2in9b_v4::Epd2in9bV4);
+        check!(epd2in9bc::Epd2in9bc);
+        check!(epd3in0g::Epd3in0g);
+        check!(epd3in52::Epd3in52);
+        check!(epd3in7::Epd3in7);
+        check!(epd4in01f::Epd4in01f);
+        check!(epd4in2::Epd4in2);
+        check!(epd4in26::Epd4in26);
+        check!(epd4in2_v2::Epd4in2);
+        check!(epd4in2bc_v2::Epd4in2bcV2);
+        check!(epd4in37g::Epd4in37g);
+        check!(epd5in65f::Epd5in65f);
+        check!(epd5in79::Epd5in79);
+        check!(epd5in83_v2::Epd5in83);
+        check!(epd5in83bc_v2::Epd5in83bcV2);
+        check!(epd7in3f::Epd7in3f);
+        check!(epd7in3g::Epd7in3g);
+        check!(epd7in5::Epd7in5);
+        check!(epd7in5_hd::Epd7in5);
+        check!(epd7in5_v2::Epd7in5);
+        check!(epd7in5b_v2::Epd7in5bV2);
+        check!(epd10in2::Epd10in2);
+        check!(epd13in3k::Epd13in3k);
+    }
+}
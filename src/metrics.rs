@@ -0,0 +1,204 @@
+//! Measuring the SPI transfers of an update
+//!
+//! Slow updates can have several causes: a low SPI clock, lots of small transfers
+//! (e.g. from `data_x_times`) or simply the panel being busy with its refresh.
+//! Wrap the SPI peripheral in a [`MeteredSpi`] to find out which one it is. It counts
+//! the bytes and transfers and measures the time spent in them with a clock you
+//! provide, e.g. a free running timer in µs.
+//!
+//! Everything between [`MeteredSpi::begin`] and [`MeteredSpi::end`] is accumulated
+//! into one [`TransferStats`], which can be read with [`MeteredSpi::last_transfer_stats`].
+//! The time which isn't spent in SPI transfers is mostly waiting for the busy line
+//! and the delays of the driver.
+//!
+//!```rust, no_run
+//!# use embedded_hal_mock::*;
+//!# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
+//!use epd_waveshare::{epd4in2::*, metrics::MeteredSpi, prelude::*};
+//!#
+//!# let expectations = [];
+//!# let spi = spi::Mock::new(&expectations);
+//!# let expectations = [];
+//!# let cs_pin = pin::Mock::new(&expectations);
+//!# let busy_in = pin::Mock::new(&expectations);
+//!# let dc = pin::Mock::new(&expectations);
+//!# let rst = pin::Mock::new(&expectations);
+//!# let mut delay = delay::MockNoop::new();
+//!# let now_us = || 0u32;
+//!
+//!// `now_us` returns the current time of a free running timer
+//!let mut spi = MeteredSpi::new(spi, now_us);
+//!let mut epd = Epd4in2::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
+//!let display = Display4in2::default();
+//!
+//!spi.begin();
+//!epd.update_and_display_frame(&mut spi, display.buffer(), &mut delay)?;
+//!spi.end();
+//!
+//!let stats = spi.last_transfer_stats();
+//!let _spi_rate = stats.bytes_per_second(1_000_000);
+//!let _waited_us = stats.idle_ticks();
+//!# Ok(())
+//!# }
+//!```
+
+use embedded_hal::blocking::spi::Write;
+
+/// Measurements of all transfers between [`MeteredSpi::begin`] and [`MeteredSpi::end`]
+///
+/// All times are in ticks of the clock passed to [`MeteredSpi::new`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TransferStats {
+    /// Number of bytes written
+    pub bytes: u32,
+    /// Number of calls to `write`, every command and data block is a separate transfer
+    pub transfers: u32,
+    /// Time spent inside the SPI transfers
+    pub spi_ticks: u32,
+    /// Time from `begin` to `end`
+    pub total_ticks: u32,
+}
+
+impl TransferStats {
+    /// Time which wasn't spent in SPI transfers, e.g. waiting for the busy line
+    pub fn idle_ticks(&self) -> u32 {
+        self.total_ticks.saturating_sub(self.spi_ticks)
+    }
+
+    /// Average bytes per second while transferring, `None` if no time was measured
+    ///
+    /// `ticks_per_second` is the frequency of the clock, e.g. `1_000_000` for µs.
+    pub fn bytes_per_second(&self, ticks_per_second: u32) -> Option<u32> {
+        if self.spi_ticks == 0 {
+            return None;
+        }
+        Some(
+            (u64::from(self.bytes) * u64::from(ticks_per_second) / u64::from(self.spi_ticks))
+                as u32,
+        )
+    }
+
+    /// Average number of bytes per transfer
+    pub fn bytes_per_transfer(&self) -> u32 {
+        self.bytes.checked_div(self.transfers).unwrap_or(0)
+    }
+}
+
+/// An SPI peripheral which measures all transfers going through it
+///
+/// `CLK` returns the current time in ticks of any unit. It may wrap around,
+/// only differences between two readings are used.
+pub struct MeteredSpi<SPI, CLK> {
+    spi: SPI,
+    clock: CLK,
+    current: TransferStats,
+    started: u32,
+    last: TransferStats,
+}
+
+impl<SPI, CLK> MeteredSpi<SPI, CLK>
+where
+    CLK: FnMut() -> u32,
+{
+    /// Wraps `spi`, `clock` returns the current time
+    pub fn new(spi: SPI, clock: CLK) -> Self {
+        MeteredSpi {
+            spi,
+            clock,
+            current: TransferStats::default(),
+            started: 0,
+            last: TransferStats::default(),
+        }
+    }
+
+    /// Starts a new measurement
+    pub fn begin(&mut self) {
+        self.current = TransferStats::default();
+        self.started = (self.clock)();
+    }
+
+    /// Finishes the measurement started with `begin` and returns it
+    pub fn end(&mut self) -> TransferStats {
+        self.current.total_ticks = (self.clock)().wrapping_sub(self.started);
+        self.last = self.current;
+        self.last
+    }
+
+    /// The measurement finished by the last call to `end`
+    pub fn last_transfer_stats(&self) -> TransferStats {
+        self.last
+    }
+
+    /// Releases the wrapped SPI peripheral
+    pub fn release(self) -> SPI {
+        self.spi
+    }
+}
+
+impl<SPI, CLK> Write<u8> for MeteredSpi<SPI, CLK>
+where
+    SPI: Write<u8>,
+    CLK: FnMut() -> u32,
+{
+    type Error = SPI::Error;
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+        let start = (self.clock)();
+        let result = self.spi.write(words);
+        let elapsed = (self.clock)().wrapping_sub(start);
+
+        self.current.bytes = self.current.bytes.saturating_add(words.len() as u32);
+        self.current.transfers = self.current.transfers.saturating_add(1);
+        self.current.spi_ticks = self.current.spi_ticks.saturating_add(elapsed);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::Cell;
+    use embedded_hal_mock::spi::{Mock, Transaction};
+
+    #[test]
+    fn counts_transfers() {
+        let expectations = [
+            Transaction::write([0x10].to_vec()),
+            Transaction::write([0xAA; 100].to_vec()),
+        ];
+        // every reading of the clock advances it by 5 ticks
+        let now = Cell::new(0u32);
+        let clock = || {
+            now.set(now.get() + 5);
+            now.get()
+        };
+        let mut spi = MeteredSpi::new(Mock::new(&expectations), clock);
+
+        spi.begin();
+        spi.write(&[0x10]).unwrap();
+        spi.write(&[0xAA; 100]).unwrap();
+        let stats = spi.end();
+
+        assert_eq!(stats.bytes, 101);
+        assert_eq!(stats.transfers, 2);
+        assert_eq!(stats.spi_ticks, 10);
+        assert_eq!(stats.total_ticks, 25);
+        assert_eq!(stats.idle_ticks(), 15);
+        assert_eq!(stats.bytes_per_transfer(), 50);
+        assert_eq!(spi.last_transfer_stats(), stats);
+        spi.release().done();
+    }
+
+    #[test]
+    fn throughput() {
+        let stats = TransferStats {
+            bytes: 15_000,
+            transfers: 3,
+            spi_ticks: 30_000,
+            total_ticks: 2_000_000,
+        };
+        assert_eq!(stats.bytes_per_second(1_000_000), Some(500_000));
+        assert_eq!(TransferStats::default().bytes_per_second(1_000_000), None);
+        assert_eq!(TransferStats::default().bytes_per_transfer(), 0);
+    }
+}
@@ -0,0 +1,374 @@
+//! Switching the supply rail of the display
+//!
+//! Some designs gate the 3.3V rail of the display with a load switch or MOSFET to get
+//! the standby current down to (almost) zero. [`PowerGated`] wraps any driver together
+//! with the `OutputPin` controlling that switch: the rail is enabled before the
+//! display gets initialised and cut after it was sent to sleep.
+//!
+//! The pin is driven high to enable the rail. Since the display loses all its register
+//! contents without power, waking it up always runs the full initialisation again.
+//!
+//! Keep in mind that CS, DC and RST might still backfeed the panel through their
+//! protection diodes while the rail is off, so drive them low as well if possible.
+//!
+//!```rust, no_run
+//!# use embedded_hal_mock::*;
+//!# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
+//!use epd_waveshare::{epd2in9_v2::*, power::PowerGated, prelude::*};
+//!#
+//!# let expectations = [];
+//!# let mut spi = spi::Mock::new(&expectations);
+//!# let expectations = [];
+//!# let cs_pin = pin::Mock::new(&expectations);
+//!# let busy_in = pin::Mock::new(&expectations);
+//!# let dc = pin::Mock::new(&expectations);
+//!# let rst = pin::Mock::new(&expectations);
+//!# let power_en = pin::Mock::new(&expectations);
+//!# let mut delay = delay::MockNoop::new();
+//!
+//!// Enables the rail, waits for it to settle and initialises the display
+//!let mut epd: PowerGated<Epd2in9<_, _, _, _, _, _>, _> =
+//!    PowerGated::new(&mut spi, cs_pin, busy_in, dc, rst, power_en, &mut delay)?;
+//!
+//!epd.epd_mut().clear_frame(&mut spi, &mut delay)?;
+//!epd.epd_mut().display_frame(&mut spi, &mut delay)?;
+//!
+//!// Sends the display to sleep and cuts the rail afterwards
+//!epd.sleep(&mut spi, &mut delay)?;
+//!
+//!// Enables the rail again and reinitialises the display
+//!epd.wake_up(&mut spi, &mut delay)?;
+//!# Ok(())
+//!# }
+//!```
+
+use embedded_hal::{
+    blocking::{delay::*, spi::Write},
+    digital::v2::*,
+};
+
+use crate::traits::WaveshareDisplay;
+use crate::Error;
+
+/// Default time in ms the rail gets to settle after switching it on
+pub const DEFAULT_POWER_ON_SETTLE_MS: u8 = 10;
+/// Default time in ms between the deep sleep command and switching the rail off
+pub const DEFAULT_POWER_OFF_SETTLE_MS: u8 = 10;
+
+/// A driver together with the pin controlling the supply rail of the display
+pub struct PowerGated<EPD, PWR> {
+    epd: EPD,
+    power: PWR,
+    powered: bool,
+    on_settle_ms: u8,
+    off_settle_ms: u8,
+}
+
+impl<EPD, PWR> PowerGated<EPD, PWR>
+where
+    PWR: OutputPin,
+{
+    /// Enables the supply rail and creates the driver afterwards
+    ///
+    /// This already initialises the device.
+    pub fn new<SPI, CS, BUSY, DC, RST, DELAY>(
+        spi: &mut SPI,
+        cs: CS,
+        busy: BUSY,
+        dc: DC,
+        rst: RST,
+        mut power: PWR,
+        delay: &mut DELAY,
+    ) -> Result<Self, Error<SPI::Error>>
+    where
+        EPD: WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>,
+        SPI: Write<u8>,
+        CS: OutputPin,
+        BUSY: InputPin,
+        DC: OutputPin,
+        RST: OutputPin,
+        DELAY: DelayMs<u8>,
+    {
+        let _ = power.set_high();
+        delay.delay_ms(DEFAULT_POWER_ON_SETTLE_MS);
+
+        let epd = EPD::new(spi, cs, busy, dc, rst, delay)?;
+
+        Ok(PowerGated {
+            epd,
+            power,
+            powered: true,
+            on_settle_ms: DEFAULT_POWER_ON_SETTLE_MS,
+            off_settle_ms: DEFAULT_POWER_OFF_SETTLE_MS,
+        })
+    }
+
+    /// Changes the settle times (in ms) used for switching the rail on and off
+    pub fn set_settle_times(&mut self, on_settle_ms: u8, off_settle_ms: u8) {
+        self.on_settle_ms = on_settle_ms;
+        self.off_settle_ms = off_settle_ms;
+    }
+
+    /// Lets the display enter deep sleep and cuts its supply rail afterwards
+    pub fn sleep<SPI, CS, BUSY, DC, RST, DELAY>(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>>
+    where
+        EPD: WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>,
+        SPI: Write<u8>,
+        CS: OutputPin,
+        BUSY: InputPin,
+        DC: OutputPin,
+        RST: OutputPin,
+        DELAY: DelayMs<u8>,
+    {
+        self.epd.sleep(spi, delay)?;
+        // the SSD controllers keep BUSY high in deep sleep, so only the settle time is waited
+        delay.delay_ms(self.off_settle_ms);
+
+        let _ = self.power.set_low();
+        self.powered = false;
+        Ok(())
+    }
+
+    /// Enables the supply rail and reinitialises the display
+    pub fn wake_up<SPI, CS, BUSY, DC, RST, DELAY>(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>>
+    where
+        EPD: WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>,
+        SPI: Write<u8>,
+        CS: OutputPin,
+        BUSY: InputPin,
+        DC: OutputPin,
+        RST: OutputPin,
+        DELAY: DelayMs<u8>,
+    {
+        if !self.powered {
+            let _ = self.power.set_high();
+            self.powered = true;
+            delay.delay_ms(self.on_settle_ms);
+        }
+        self.epd.wake_up(spi, delay)
+    }
+
+    /// Returns true as long as the supply rail is enabled
+    pub fn is_powered(&self) -> bool {
+        self.powered
+    }
+
+    /// Returns the wrapped driver
+    pub fn epd(&self) -> &EPD {
+        &self.epd
+    }
+
+    /// Returns the wrapped driver mutably, e.g. for updating and displaying frames
+    pub fn epd_mut(&mut self) -> &mut EPD {
+        &mut self.epd
+    }
+
+    /// Releases the driver and the power pin, the rail is left as it is
+    pub fn release(self) -> (EPD, PWR) {
+        (self.epd, self.power)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::RefreshLut;
+    use core::cell::RefCell;
+    extern crate std;
+    use std::vec::Vec;
+
+    /// Something which happened to the driver, the rail or the delay
+    #[derive(Debug, PartialEq)]
+    enum Event {
+        Rail(bool),
+        New,
+        Sleep,
+        WakeUp,
+        Delay(u8),
+    }
+
+    type Log = RefCell<Vec<Event>>;
+
+    struct Spi;
+
+    impl Write<u8> for Spi {
+        type Error = ();
+
+        fn write(&mut self, _words: &[u8]) -> Result<(), ()> {
+            Ok(())
+        }
+    }
+
+    /// Power pin if it has a log, other pins only accept every level
+    struct Pin<'a>(Option<&'a Log>);
+
+    impl OutputPin for Pin<'_> {
+        type Error = ();
+
+        fn set_low(&mut self) -> Result<(), ()> {
+            if let Some(log) = self.0 {
+                log.borrow_mut().push(Event::Rail(false));
+            }
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), ()> {
+            if let Some(log) = self.0 {
+                log.borrow_mut().push(Event::Rail(true));
+            }
+            Ok(())
+        }
+    }
+
+    impl InputPin for Pin<'_> {
+        type Error = ();
+
+        fn is_high(&self) -> Result<bool, ()> {
+            Ok(true)
+        }
+
+        fn is_low(&self) -> Result<bool, ()> {
+            Ok(false)
+        }
+    }
+
+    struct Delay<'a>(&'a Log);
+
+    impl DelayMs<u8> for Delay<'_> {
+        fn delay_ms(&mut self, ms: u8) {
+            self.0.borrow_mut().push(Event::Delay(ms));
+        }
+    }
+
+    /// Driver which logs its calls and, like the SSD controllers in deep sleep, is
+    /// always busy
+    struct FakeEpd<'a>(&'a Log);
+
+    impl<'a> WaveshareDisplay<Spi, Pin<'a>, Pin<'a>, Pin<'a>, Pin<'a>, Delay<'a>> for FakeEpd<'a> {
+        type DisplayColor = ();
+
+        fn new(
+            _spi: &mut Spi,
+            _cs: Pin<'a>,
+            _busy: Pin<'a>,
+            _dc: Pin<'a>,
+            _rst: Pin<'a>,
+            delay: &mut Delay<'a>,
+        ) -> Result<Self, Error<()>> {
+            delay.0.borrow_mut().push(Event::New);
+            Ok(FakeEpd(delay.0))
+        }
+        fn sleep(&mut self, _spi: &mut Spi, _delay: &mut Delay<'a>) -> Result<(), Error<()>> {
+            self.0.borrow_mut().push(Event::Sleep);
+            Ok(())
+        }
+        fn wake_up(&mut self, _spi: &mut Spi, _delay: &mut Delay<'a>) -> Result<(), Error<()>> {
+            self.0.borrow_mut().push(Event::WakeUp);
+            Ok(())
+        }
+        fn set_background_color(&mut self, _color: ()) {}
+        fn background_color(&self) -> &() {
+            &()
+        }
+        fn width(&self) -> u32 {
+            8
+        }
+        fn height(&self) -> u32 {
+            1
+        }
+        fn update_frame(
+            &mut self,
+            _spi: &mut Spi,
+            _buffer: &[u8],
+            _delay: &mut Delay<'a>,
+        ) -> Result<(), Error<()>> {
+            Ok(())
+        }
+        fn update_partial_frame(
+            &mut self,
+            _spi: &mut Spi,
+            _buffer: &[u8],
+            _x: u32,
+            _y: u32,
+            _width: u32,
+            _height: u32,
+        ) -> Result<(), Error<()>> {
+            Ok(())
+        }
+        fn display_frame(
+            &mut self,
+            _spi: &mut Spi,
+            _delay: &mut Delay<'a>,
+        ) -> Result<(), Error<()>> {
+            Ok(())
+        }
+        fn update_and_display_frame(
+            &mut self,
+            _spi: &mut Spi,
+            _buffer: &[u8],
+            _delay: &mut Delay<'a>,
+        ) -> Result<(), Error<()>> {
+            Ok(())
+        }
+        fn clear_frame(&mut self, _spi: &mut Spi, _delay: &mut Delay<'a>) -> Result<(), Error<()>> {
+            Ok(())
+        }
+        fn set_lut(
+            &mut self,
+            _spi: &mut Spi,
+            _refresh_rate: Option<RefreshLut>,
+        ) -> Result<(), Error<()>> {
+            Ok(())
+        }
+        fn is_busy(&self) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn rail_around_sleep_and_wake_up() {
+        let log = Log::default();
+        let mut delay = Delay(&log);
+        let mut epd: PowerGated<FakeEpd, _> = PowerGated::new(
+            &mut Spi,
+            Pin(None),
+            Pin(None),
+            Pin(None),
+            Pin(None),
+            Pin(Some(&log)),
+            &mut delay,
+        )
+        .unwrap();
+        assert!(epd.is_powered());
+
+        epd.set_settle_times(20, 5);
+        // returns although the driver stays busy
+        epd.sleep(&mut Spi, &mut delay).unwrap();
+        assert!(!epd.is_powered());
+        epd.wake_up(&mut Spi, &mut delay).unwrap();
+        assert!(epd.is_powered());
+
+        assert_eq!(
+            log.into_inner(),
+            [
+                Event::Rail(true),
+                Event::Delay(DEFAULT_POWER_ON_SETTLE_MS),
+                Event::New,
+                Event::Sleep,
+                Event::Delay(5),
+                Event::Rail(false),
+                Event::Rail(true),
+                Event::Delay(20),
+                Event::WakeUp,
+            ]
+        );
+    }
+}
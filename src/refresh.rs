@@ -0,0 +1,338 @@
+//! Choosing the refresh mode for the next update
+//!
+//! Full refreshes remove ghosting but flash the display and draw the most power,
+//! fast and partial refreshes are cheaper but leave traces behind which add up over
+//! time. Firmware for e-readers and similar devices usually decides per update which
+//! one to use, depending on how much of the screen changed and how much battery is
+//! left. A [`RefreshPolicy`] makes this decision, [`EnergyAwarePolicy`] implements the
+//! common heuristics:
+//!
+//! - a change covering a large part of the screen gets a full refresh
+//! - a medium sized change gets a fast refresh, a small one a partial refresh
+//! - after a number of fast or partial refreshes a full refresh clears the ghosting
+//! - on a low battery large changes only get a fast refresh and the full refresh
+//!   clearing the ghosting happens less often
+//!
+//!```rust
+//!use epd_waveshare::{prelude::*, refresh::*};
+//!
+//!let mut policy = EnergyAwarePolicy::new();
+//!
+//!// the first page of a 400x300 frame
+//!let mode = policy.select(400 * 300, 400 * 300, PowerSource::Battery(80));
+//!assert_eq!(mode, RefreshMode::Full);
+//!assert_eq!(mode.lut(), RefreshLut::Full);
+//!
+//!// a few characters changed
+//!let mode = policy.select(120, 400 * 300, PowerSource::Battery(80));
+//!assert_eq!(mode, RefreshMode::Partial);
+//!```
+
+use crate::traits::RefreshLut;
+
+/// The kinds of refreshes a policy can choose from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RefreshMode {
+    /// Full refresh with the [`RefreshLut::Full`] waveform
+    Full,
+    /// Refresh of the whole frame with the [`RefreshLut::Quick`] waveform
+    Fast,
+    /// Refresh of the changed area only, e.g. with
+    /// [`QuickRefresh::display_partial_frame`](crate::prelude::QuickRefresh::display_partial_frame)
+    Partial,
+}
+
+impl RefreshMode {
+    /// The lookup table to set with `set_lut` before refreshing in this mode
+    pub fn lut(self) -> RefreshLut {
+        match self {
+            RefreshMode::Full => RefreshLut::Full,
+            RefreshMode::Fast | RefreshMode::Partial => RefreshLut::Quick,
+        }
+    }
+}
+
+/// Where the device gets its power from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerSource {
+    /// Mains or USB power, energy doesn't matter
+    External,
+    /// Running on battery with the remaining charge in percent
+    Battery(u8),
+}
+
+/// Decides which kind of refresh is used for an update
+pub trait RefreshPolicy {
+    /// Returns the mode for an update which changed `changed_pixels` out of
+    /// `total_pixels`, and counts it as done in this mode
+    fn select(&mut self, changed_pixels: u32, total_pixels: u32, power: PowerSource)
+        -> RefreshMode;
+}
+
+/// [`RefreshPolicy`] trading ghosting against battery life
+///
+/// The defaults give a full refresh for changes covering at least half of the
+/// screen and after 10 fast or partial refreshes. Below 20% battery this interval
+/// is tripled and large changes get a fast refresh instead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnergyAwarePolicy {
+    full_percent: u8,
+    fast_percent: u8,
+    full_every: u16,
+    low_battery_percent: u8,
+    low_battery_factor: u16,
+    since_full: u16,
+    force_full: bool,
+}
+
+impl Default for EnergyAwarePolicy {
+    fn default() -> Self {
+        EnergyAwarePolicy {
+            full_percent: 50,
+            fast_percent: 15,
+            full_every: 10,
+            low_battery_percent: 20,
+            low_battery_factor: 3,
+            since_full: 0,
+            force_full: true,
+        }
+    }
+}
+
+impl EnergyAwarePolicy {
+    /// Creates a policy with the default thresholds
+    ///
+    /// The first update always gets a full refresh, as the content of the
+    /// display is unknown after power up.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Changes covering at least `percent` of the screen get a full refresh
+    pub fn with_full_threshold(mut self, percent: u8) -> Self {
+        self.full_percent = percent;
+        self
+    }
+
+    /// Changes covering at least `percent` of the screen get a fast refresh
+    /// instead of a partial one
+    pub fn with_fast_threshold(mut self, percent: u8) -> Self {
+        self.fast_percent = percent;
+        self
+    }
+
+    /// A full refresh clears the ghosting after `updates` fast or partial refreshes
+    ///
+    /// `0` disables these full refreshes.
+    pub fn with_full_every(mut self, updates: u16) -> Self {
+        self.full_every = updates;
+        self
+    }
+
+    /// Below `percent` battery the full refreshes clearing the ghosting happen
+    /// `factor` times less often and large changes only get a fast refresh
+    pub fn with_low_battery(mut self, percent: u8, factor: u16) -> Self {
+        self.low_battery_percent = percent;
+        self.low_battery_factor = factor.max(1);
+        self
+    }
+
+    /// Number of fast or partial refreshes since the last full one
+    pub fn since_full(&self) -> u16 {
+        self.since_full
+    }
+
+    /// Makes the next update a full refresh regardless of the battery,
+    /// e.g. after the display was cleared or woke up from deep sleep
+    pub fn force_full(&mut self) {
+        self.force_full = true;
+    }
+
+    fn is_low(&self, power: PowerSource) -> bool {
+        match power {
+            PowerSource::External => false,
+            PowerSource::Battery(percent) => percent < self.low_battery_percent,
+        }
+    }
+
+    fn mode(&self, changed_pixels: u32, total_pixels: u32, power: PowerSource) -> RefreshMode {
+        if self.force_full {
+            return RefreshMode::Full;
+        }
+        let low = self.is_low(power);
+        let full_every = if low {
+            self.full_every.saturating_mul(self.low_battery_factor)
+        } else {
+            self.full_every
+        };
+        if full_every != 0 && self.since_full >= full_every {
+            return RefreshMode::Full;
+        }
+
+        // compared in u64 so large displays can't overflow
+        let changed = u64::from(changed_pixels) * 100;
+        let covers = |percent: u8| changed >= u64::from(total_pixels) * u64::from(percent);
+        if covers(self.full_percent) {
+            if low {
+                RefreshMode::Fast
+            } else {
+                RefreshMode::Full
+            }
+        } else if covers(self.fast_percent) {
+            RefreshMode::Fast
+        } else {
+            RefreshMode::Partial
+        }
+    }
+}
+
+impl RefreshPolicy for EnergyAwarePolicy {
+    fn select(
+        &mut self,
+        changed_pixels: u32,
+        total_pixels: u32,
+        power: PowerSource,
+    ) -> RefreshMode {
+        let mode = self.mode(changed_pixels, total_pixels, power);
+        if mode == RefreshMode::Full {
+            self.since_full = 0;
+            self.force_full = false;
+        } else {
+            self.since_full = self.since_full.saturating_add(1);
+        }
+        mode
+    }
+}
+
+/// Counts the quick refreshes of a driver to force a full one every now and then,
+/// see [`FullRefreshInterval`](crate::traits::FullRefreshInterval)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) struct QuickRefreshCounter {
+    full_every: u16,
+    since_full: u16,
+}
+
+impl QuickRefreshCounter {
+    /// A full refresh follows `updates` quick ones, `0` disables it
+    pub(crate) fn set_full_every(&mut self, updates: u16) {
+        self.full_every = updates;
+    }
+
+    pub(crate) fn since_full(&self) -> u16 {
+        self.since_full
+    }
+
+    /// Counts a full refresh
+    pub(crate) fn full(&mut self) {
+        self.since_full = 0;
+    }
+
+    /// Counts the next quick refresh, or returns `true` if it has to be a full one
+    pub(crate) fn next_is_full(&mut self) -> bool {
+        if self.full_every != 0 && self.since_full >= self.full_every {
+            self.since_full = 0;
+            true
+        } else {
+            self.since_full = self.since_full.saturating_add(1);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOTAL: u32 = 100 * 100;
+
+    fn started() -> EnergyAwarePolicy {
+        let mut policy = EnergyAwarePolicy::new();
+        assert_eq!(
+            policy.select(0, TOTAL, PowerSource::External),
+            RefreshMode::Full
+        );
+        policy
+    }
+
+    #[test]
+    fn counter_forces_full_refreshes() {
+        let mut counter = QuickRefreshCounter::default();
+        assert!(!(0..100).any(|_| counter.next_is_full()));
+
+        counter.set_full_every(2);
+        counter.full();
+        let refreshes: [bool; 6] = core::array::from_fn(|_| counter.next_is_full());
+        assert_eq!(refreshes, [false, false, true, false, false, true]);
+
+        counter.next_is_full();
+        counter.full();
+        assert_eq!(counter.since_full(), 0);
+    }
+
+    #[test]
+    fn first_update_is_full() {
+        let mut policy = EnergyAwarePolicy::new();
+        assert_eq!(
+            policy.select(1, TOTAL, PowerSource::Battery(5)),
+            RefreshMode::Full
+        );
+        assert_eq!(policy.since_full(), 0);
+    }
+
+    #[test]
+    fn mode_by_changed_area() {
+        let mut policy = started();
+        let power = PowerSource::Battery(90);
+        assert_eq!(policy.select(100, TOTAL, power), RefreshMode::Partial);
+        assert_eq!(policy.select(1500, TOTAL, power), RefreshMode::Fast);
+        assert_eq!(policy.select(5000, TOTAL, power), RefreshMode::Full);
+        assert_eq!(policy.since_full(), 0);
+    }
+
+    #[test]
+    fn periodic_full_refresh() {
+        let mut policy = started().with_full_every(3);
+        for _ in 0..3 {
+            assert_eq!(
+                policy.select(10, TOTAL, PowerSource::External),
+                RefreshMode::Partial
+            );
+        }
+        assert_eq!(
+            policy.select(10, TOTAL, PowerSource::External),
+            RefreshMode::Full
+        );
+    }
+
+    #[test]
+    fn low_battery_saves_full_refreshes() {
+        let mut policy = started().with_full_every(2).with_low_battery(30, 2);
+        let low = PowerSource::Battery(10);
+        assert_eq!(policy.select(TOTAL, TOTAL, low), RefreshMode::Fast);
+        for _ in 0..3 {
+            assert_eq!(policy.select(10, TOTAL, low), RefreshMode::Partial);
+        }
+        assert_eq!(policy.select(10, TOTAL, low), RefreshMode::Full);
+    }
+
+    #[test]
+    fn forced_full_refresh() {
+        let mut policy = started();
+        policy.force_full();
+        assert_eq!(
+            policy.select(1, TOTAL, PowerSource::Battery(1)),
+            RefreshMode::Full
+        );
+        assert_eq!(
+            policy.select(1, TOTAL, PowerSource::Battery(1)),
+            RefreshMode::Partial
+        );
+    }
+
+    #[test]
+    fn lut_of_mode() {
+        assert_eq!(RefreshMode::Full.lut(), RefreshLut::Full);
+        assert_eq!(RefreshMode::Fast.lut(), RefreshLut::Quick);
+        assert_eq!(RefreshMode::Partial.lut(), RefreshLut::Quick);
+    }
+}
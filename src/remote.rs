@@ -0,0 +1,652 @@
+//! Validation of frames received over the air, e.g. via MQTT or BLE
+//!
+//! A frame pushed to a device by a server can arrive truncated, be meant for a
+//! different display or be a leftover of an older firmware with another layout.
+//! Handing such a buffer to `update_frame` shows garbage or leaves the controller
+//! with half a frame. Frames are therefore sent with a small header in front, which
+//! describes the payload and carries a checksum:
+//!
+//! | Bytes  | Content                                        |
+//! |--------|------------------------------------------------|
+//! | 0..2   | [`MAGIC`], `"EP"`                              |
+//! | 2      | [`VERSION`] of the header                      |
+//! | 3      | [`FrameFormat`] of the payload                 |
+//! | 4..6   | Panel id, little endian                        |
+//! | 6..8   | Width in pixels, little endian                 |
+//! | 8..10  | Height in pixels, little endian                |
+//! | 10..12 | CRC-16/CCITT-FALSE of bytes 0..10 and payload  |
+//!
+//! The panel id is chosen by the application, e.g. one per kind of device in the
+//! field. [`validate_frame_header`] checks a packet against the [`FrameHeader`] the
+//! device expects and returns the payload, [`apply_remote_frame`] sends it right
+//! on to the display.
+//!
+//! Frames compressed with a [`FrameCodec`] are checked with
+//! [`validate_compressed_frame`], the checksum covers the compressed payload.
+//! [`apply_compressed_frame`] decodes them in bands of rows straight into
+//! `update_partial_frame`, so the whole frame never has to fit into RAM.
+//!
+//!```rust, no_run
+//!# use embedded_hal_mock::*;
+//!# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
+//!use epd_waveshare::{epd2in9::*, prelude::*};
+//!use epd_waveshare::remote::{apply_remote_frame, FrameFormat, FrameHeader, RemoteFrameError};
+//!#
+//!# let expectations = [];
+//!# let mut spi = spi::Mock::new(&expectations);
+//!# let expectations = [];
+//!# let cs_pin = pin::Mock::new(&expectations);
+//!# let busy_in = pin::Mock::new(&expectations);
+//!# let dc = pin::Mock::new(&expectations);
+//!# let rst = pin::Mock::new(&expectations);
+//!# let mut delay = delay::MockNoop::new();
+//!# let packet = [0u8; 12 + WIDTH as usize / 8 * HEIGHT as usize];
+//!
+//!let mut epd = Epd2in9::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
+//!let expected = FrameHeader::new(0x0029, FrameFormat::Mono, WIDTH as u16, HEIGHT as u16);
+//!
+//!// `packet` was received over the air, nothing is sent if it doesn't check out
+//!match apply_remote_frame(&mut epd, &mut spi, &packet, &expected, &mut delay) {
+//!    Ok(()) => epd.display_frame(&mut spi, &mut delay)?,
+//!    Err(RemoteFrameError::Spi(e)) => return Err(e),
+//!    // the display keeps showing the last good frame
+//!    Err(_) => {}
+//!}
+//!# Ok(())
+//!# }
+//!```
+
+use core::convert::Infallible;
+
+use embedded_hal::{
+    blocking::{delay::*, spi::Write},
+    digital::v2::{InputPin, OutputPin},
+};
+
+use crate::codec::{CodecError, FrameCodec};
+use crate::traits::WaveshareDisplay;
+use crate::{buffer_len, Error};
+
+/// First two bytes of every header
+pub const MAGIC: [u8; 2] = *b"EP";
+/// Version of the header layout
+pub const VERSION: u8 = 1;
+/// Length of the header in bytes
+pub const HEADER_LEN: usize = 12;
+
+/// Layout of the pixels in the payload
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameFormat {
+    /// 1 bit per pixel, like [Color](crate::color::Color)
+    Mono = 0,
+    /// 2 bits per pixel, like [QuadColor](crate::color::QuadColor) or 4 gray levels
+    TwoBit = 1,
+    /// Black/white plane followed by the chromatic plane, like
+    /// [TriColor](crate::color::TriColor)
+    TriColor = 2,
+    /// 4 bits per pixel, like [OctColor](crate::color::OctColor)
+    Oct = 3,
+}
+
+impl FrameFormat {
+    fn from_u8(value: u8) -> Option<FrameFormat> {
+        match value {
+            0 => Some(FrameFormat::Mono),
+            1 => Some(FrameFormat::TwoBit),
+            2 => Some(FrameFormat::TriColor),
+            3 => Some(FrameFormat::Oct),
+            _ => None,
+        }
+    }
+
+    /// Length of a payload with `width` x `height` pixels
+    pub const fn frame_len(self, width: usize, height: usize) -> usize {
+        match self {
+            FrameFormat::Mono => buffer_len(width, height),
+            FrameFormat::TwoBit => width.div_ceil(4) * height,
+            FrameFormat::TriColor => 2 * buffer_len(width, height),
+            FrameFormat::Oct => width.div_ceil(2) * height,
+        }
+    }
+}
+
+/// Description of a frame, as sent in the header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameHeader {
+    /// Id of the panel the frame is made for, chosen by the application
+    pub panel_id: u16,
+    /// Layout of the pixels
+    pub format: FrameFormat,
+    /// Width in pixels
+    pub width: u16,
+    /// Height in pixels
+    pub height: u16,
+}
+
+impl FrameHeader {
+    /// Describes a frame of `width` x `height` pixels in `format` for `panel_id`
+    pub const fn new(panel_id: u16, format: FrameFormat, width: u16, height: u16) -> Self {
+        FrameHeader {
+            panel_id,
+            format,
+            width,
+            height,
+        }
+    }
+
+    /// Length of the payload following the header
+    pub const fn payload_len(&self) -> usize {
+        self.format
+            .frame_len(self.width as usize, self.height as usize)
+    }
+
+    /// Builds the header to send in front of `payload`, for the sending side
+    pub fn encode(&self, payload: &[u8]) -> [u8; HEADER_LEN] {
+        let mut header = [0u8; HEADER_LEN];
+        header[..2].copy_from_slice(&MAGIC);
+        header[2] = VERSION;
+        header[3] = self.format as u8;
+        header[4..6].copy_from_slice(&self.panel_id.to_le_bytes());
+        header[6..8].copy_from_slice(&self.width.to_le_bytes());
+        header[8..10].copy_from_slice(&self.height.to_le_bytes());
+        let crc = crc16(crc16(CRC_INIT, &header[..10]), payload);
+        header[10..].copy_from_slice(&crc.to_le_bytes());
+        header
+    }
+
+    fn decode(header: &[u8; HEADER_LEN]) -> Result<Self, RemoteFrameError<Infallible>> {
+        if header[..2] != MAGIC {
+            return Err(RemoteFrameError::BadMagic);
+        }
+        if header[2] != VERSION {
+            return Err(RemoteFrameError::UnsupportedVersion(header[2]));
+        }
+        let format =
+            FrameFormat::from_u8(header[3]).ok_or(RemoteFrameError::UnknownFormat(header[3]))?;
+        let field = |i: usize| u16::from_le_bytes([header[i], header[i + 1]]);
+        Ok(FrameHeader::new(field(4), format, field(6), field(8)))
+    }
+}
+
+/// Reasons for rejecting a received frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemoteFrameError<E> {
+    /// The packet is shorter than the header
+    Truncated,
+    /// The packet doesn't start with [`MAGIC`]
+    BadMagic,
+    /// The header has a different [`VERSION`]
+    UnsupportedVersion(u8),
+    /// The format byte names no [`FrameFormat`]
+    UnknownFormat(u8),
+    /// The frame is made for another panel, format or size
+    Mismatch(FrameHeader),
+    /// The payload length doesn't match the header
+    WrongLength,
+    /// The checksum doesn't match, the packet is corrupt
+    Checksum,
+    /// The compressed payload doesn't decode to exactly one frame
+    Corrupt,
+    /// The format can't be decoded in bands of rows
+    Unsupported(FrameFormat),
+    /// The display returned an error
+    Spi(E),
+}
+
+/// Checks `packet` against the `expected` frame and returns its payload
+///
+/// The payload is only returned if the header is intact, describes exactly the
+/// expected frame, and the checksum over header and payload matches.
+pub fn validate_frame_header<'a>(
+    packet: &'a [u8],
+    expected: &FrameHeader,
+) -> Result<&'a [u8], RemoteFrameError<Infallible>> {
+    validate(packet, expected, Some(expected.payload_len()))
+}
+
+/// Checks `packet` like [`validate_frame_header`], for a compressed payload
+///
+/// The length of the payload isn't known before it is decoded and therefore
+/// not checked.
+pub fn validate_compressed_frame<'a>(
+    packet: &'a [u8],
+    expected: &FrameHeader,
+) -> Result<&'a [u8], RemoteFrameError<Infallible>> {
+    validate(packet, expected, None)
+}
+
+fn validate<'a>(
+    packet: &'a [u8],
+    expected: &FrameHeader,
+    payload_len: Option<usize>,
+) -> Result<&'a [u8], RemoteFrameError<Infallible>> {
+    if packet.len() < HEADER_LEN {
+        return Err(RemoteFrameError::Truncated);
+    }
+    let (header, payload) = packet.split_at(HEADER_LEN);
+    let mut bytes = [0u8; HEADER_LEN];
+    bytes.copy_from_slice(header);
+
+    let received = FrameHeader::decode(&bytes)?;
+    if received != *expected {
+        return Err(RemoteFrameError::Mismatch(received));
+    }
+    if payload_len.is_some_and(|len| payload.len() != len) {
+        return Err(RemoteFrameError::WrongLength);
+    }
+    let crc = crc16(crc16(CRC_INIT, &header[..10]), payload);
+    if crc.to_le_bytes() != header[10..] {
+        return Err(RemoteFrameError::Checksum);
+    }
+    Ok(payload)
+}
+
+/// Validates `packet` like [`validate_frame_header`] and passes the payload to
+/// `update_frame` of `epd`
+///
+/// Nothing is sent to the display if the validation fails. The frame isn't shown
+/// until `display_frame` is called.
+pub fn apply_remote_frame<SPI, CS, BUSY, DC, RST, DELAY, EPD>(
+    epd: &mut EPD,
+    spi: &mut SPI,
+    packet: &[u8],
+    expected: &FrameHeader,
+    delay: &mut DELAY,
+) -> Result<(), RemoteFrameError<Error<SPI::Error>>>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+    EPD: WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>,
+{
+    let payload = validate_frame_header(packet, expected).map_err(widen)?;
+    epd.update_frame(spi, payload, delay)
+        .map_err(RemoteFrameError::Spi)
+}
+
+/// Validates `packet` like [`validate_compressed_frame`] and decodes the payload
+/// with `codec` into `update_partial_frame` of `epd`
+///
+/// The frame is decoded into `band`, which holds at least one row, and every full
+/// band is sent right away. Nothing is sent if the validation fails. A payload
+/// which passes the checksum but decodes to the wrong length was already sent up
+/// to that point and returns [`RemoteFrameError::Corrupt`].
+///
+/// [`FrameFormat::TriColor`] is made of two planes and returns
+/// [`RemoteFrameError::Unsupported`], decode it with
+/// [`decode_to_slice`](FrameCodec::decode_to_slice) instead.
+pub fn apply_compressed_frame<SPI, CS, BUSY, DC, RST, DELAY, EPD, C>(
+    epd: &mut EPD,
+    spi: &mut SPI,
+    packet: &[u8],
+    expected: &FrameHeader,
+    codec: &C,
+    band: &mut [u8],
+    _delay: &mut DELAY,
+) -> Result<(), RemoteFrameError<Error<SPI::Error>>>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+    EPD: WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>,
+    C: FrameCodec,
+{
+    let payload = validate_compressed_frame(packet, expected).map_err(widen)?;
+    if expected.format == FrameFormat::TriColor {
+        return Err(RemoteFrameError::Unsupported(expected.format));
+    }
+    let width = expected.width as u32;
+    let row_bytes = expected.format.frame_len(expected.width as usize, 1);
+    let band_rows = band.len() / row_bytes.max(1);
+    ensure!(
+        band_rows > 0,
+        Err(RemoteFrameError::Spi(Error::InvalidArgument))
+    );
+    let band = &mut band[..band_rows * row_bytes];
+    let frame_len = expected.payload_len();
+
+    // bytes decoded so far, the current band starts at the last full band
+    let mut decoded = 0;
+    codec
+        .decode(payload, |mut chunk| {
+            while !chunk.is_empty() {
+                if decoded == frame_len {
+                    return Err(RemoteFrameError::Corrupt);
+                }
+                let filled = decoded % band.len();
+                let n = chunk
+                    .len()
+                    .min(band.len() - filled)
+                    .min(frame_len - decoded);
+                band[filled..filled + n].copy_from_slice(&chunk[..n]);
+                decoded += n;
+                chunk = &chunk[n..];
+                if decoded % band.len() == 0 || decoded == frame_len {
+                    let len = filled + n;
+                    let first = (decoded - len) / row_bytes;
+                    let rows = len / row_bytes;
+                    epd.update_partial_frame(
+                        spi,
+                        &band[..len],
+                        0,
+                        first as u32,
+                        width,
+                        rows as u32,
+                    )
+                    .map_err(RemoteFrameError::Spi)?;
+                }
+            }
+            Ok(())
+        })
+        .map_err(|err| match err {
+            CodecError::Sink(err) => err,
+            CodecError::Corrupt | CodecError::BufferTooSmall => RemoteFrameError::Corrupt,
+        })?;
+    if decoded != frame_len {
+        return Err(RemoteFrameError::Corrupt);
+    }
+    Ok(())
+}
+
+fn widen<E>(err: RemoteFrameError<Infallible>) -> RemoteFrameError<E> {
+    match err {
+        RemoteFrameError::Truncated => RemoteFrameError::Truncated,
+        RemoteFrameError::BadMagic => RemoteFrameError::BadMagic,
+        RemoteFrameError::UnsupportedVersion(v) => RemoteFrameError::UnsupportedVersion(v),
+        RemoteFrameError::UnknownFormat(f) => RemoteFrameError::UnknownFormat(f),
+        RemoteFrameError::Mismatch(header) => RemoteFrameError::Mismatch(header),
+        RemoteFrameError::WrongLength => RemoteFrameError::WrongLength,
+        RemoteFrameError::Checksum => RemoteFrameError::Checksum,
+        RemoteFrameError::Corrupt => RemoteFrameError::Corrupt,
+        RemoteFrameError::Unsupported(format) => RemoteFrameError::Unsupported(format),
+        RemoteFrameError::Spi(e) => match e {},
+    }
+}
+
+const CRC_INIT: u16 = 0xFFFF;
+
+/// CRC-16/CCITT-FALSE, continued from `crc`
+fn crc16(mut crc: u16, data: &[u8]) -> u16 {
+    for &byte in data {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::Rle;
+    use embedded_hal_mock::{
+        delay::MockNoop, pin::Mock as PinMock, spi::Mock as SpiMock, MockError,
+    };
+
+    const EXPECTED: FrameHeader = FrameHeader::new(7, FrameFormat::Mono, 16, 2);
+
+    fn packet(header: &FrameHeader, payload: &[u8]) -> [u8; HEADER_LEN + 4] {
+        let mut packet = [0u8; HEADER_LEN + 4];
+        packet[..HEADER_LEN].copy_from_slice(&header.encode(payload));
+        packet[HEADER_LEN..].copy_from_slice(payload);
+        packet
+    }
+
+    #[test]
+    fn crc_check_value() {
+        assert_eq!(crc16(CRC_INIT, b"123456789"), 0x29B1);
+    }
+
+    #[test]
+    fn accepts_valid_frame() {
+        let payload = [0x00, 0xFF, 0x0F, 0xF0];
+        let packet = packet(&EXPECTED, &payload);
+        assert_eq!(&packet[..4], &[b'E', b'P', VERSION, 0]);
+        assert_eq!(validate_frame_header(&packet, &EXPECTED), Ok(&payload[..]));
+    }
+
+    #[test]
+    fn rejects_broken_packets() {
+        let payload = [0x00, 0xFF, 0x0F, 0xF0];
+        let good = packet(&EXPECTED, &payload);
+
+        assert_eq!(
+            validate_frame_header(&good[..5], &EXPECTED),
+            Err(RemoteFrameError::Truncated)
+        );
+        assert_eq!(
+            validate_frame_header(&good[..15], &EXPECTED),
+            Err(RemoteFrameError::WrongLength)
+        );
+
+        let mut bad = good;
+        bad[0] = b'X';
+        assert_eq!(
+            validate_frame_header(&bad, &EXPECTED),
+            Err(RemoteFrameError::BadMagic)
+        );
+
+        let mut bad = good;
+        bad[2] = 2;
+        assert_eq!(
+            validate_frame_header(&bad, &EXPECTED),
+            Err(RemoteFrameError::UnsupportedVersion(2))
+        );
+
+        let mut bad = good;
+        bad[3] = 9;
+        assert_eq!(
+            validate_frame_header(&bad, &EXPECTED),
+            Err(RemoteFrameError::UnknownFormat(9))
+        );
+
+        let mut bad = good;
+        bad[HEADER_LEN + 1] ^= 0x01;
+        assert_eq!(
+            validate_frame_header(&bad, &EXPECTED),
+            Err(RemoteFrameError::Checksum)
+        );
+    }
+
+    #[test]
+    fn rejects_other_panels() {
+        let other = FrameHeader::new(8, FrameFormat::Mono, 16, 2);
+        let packet = packet(&other, &[0; 4]);
+        assert_eq!(
+            validate_frame_header(&packet, &EXPECTED),
+            Err(RemoteFrameError::Mismatch(other))
+        );
+    }
+
+    #[test]
+    fn payload_lengths() {
+        assert_eq!(FrameFormat::Mono.frame_len(10, 3), 6);
+        assert_eq!(FrameFormat::TwoBit.frame_len(10, 3), 9);
+        assert_eq!(FrameFormat::TriColor.frame_len(10, 3), 12);
+        assert_eq!(FrameFormat::Oct.frame_len(10, 3), 15);
+    }
+
+    /// 16x4 driver which records the bands sent with `update_partial_frame`
+    #[derive(Default)]
+    struct FakeEpd {
+        frame: [u8; 8],
+        bands: u32,
+    }
+
+    impl WaveshareDisplay<SpiMock, PinMock, PinMock, PinMock, PinMock, MockNoop> for FakeEpd {
+        type DisplayColor = ();
+
+        fn new(
+            _spi: &mut SpiMock,
+            _cs: PinMock,
+            _busy: PinMock,
+            _dc: PinMock,
+            _rst: PinMock,
+            _delay: &mut MockNoop,
+        ) -> Result<Self, Error<MockError>> {
+            Ok(FakeEpd::default())
+        }
+        fn sleep(
+            &mut self,
+            _spi: &mut SpiMock,
+            _delay: &mut MockNoop,
+        ) -> Result<(), Error<MockError>> {
+            Ok(())
+        }
+        fn wake_up(
+            &mut self,
+            _spi: &mut SpiMock,
+            _delay: &mut MockNoop,
+        ) -> Result<(), Error<MockError>> {
+            Ok(())
+        }
+        fn set_background_color(&mut self, _color: ()) {}
+        fn background_color(&self) -> &() {
+            &()
+        }
+        fn width(&self) -> u32 {
+            16
+        }
+        fn height(&self) -> u32 {
+            4
+        }
+        fn update_frame(
+            &mut self,
+            _spi: &mut SpiMock,
+            buffer: &[u8],
+            _delay: &mut MockNoop,
+        ) -> Result<(), Error<MockError>> {
+            self.frame.copy_from_slice(buffer);
+            Ok(())
+        }
+        fn update_partial_frame(
+            &mut self,
+            _spi: &mut SpiMock,
+            buffer: &[u8],
+            x: u32,
+            y: u32,
+            width: u32,
+            height: u32,
+        ) -> Result<(), Error<MockError>> {
+            assert_eq!((x, width), (0, 16));
+            assert_eq!(buffer.len(), 2 * height as usize);
+            self.frame[2 * y as usize..][..buffer.len()].copy_from_slice(buffer);
+            self.bands += 1;
+            Ok(())
+        }
+        fn display_frame(
+            &mut self,
+            _spi: &mut SpiMock,
+            _delay: &mut MockNoop,
+        ) -> Result<(), Error<MockError>> {
+            Ok(())
+        }
+        fn update_and_display_frame(
+            &mut self,
+            spi: &mut SpiMock,
+            buffer: &[u8],
+            delay: &mut MockNoop,
+        ) -> Result<(), Error<MockError>> {
+            self.update_frame(spi, buffer, delay)?;
+            self.display_frame(spi, delay)
+        }
+        fn clear_frame(
+            &mut self,
+            _spi: &mut SpiMock,
+            _delay: &mut MockNoop,
+        ) -> Result<(), Error<MockError>> {
+            Ok(())
+        }
+        fn set_lut(
+            &mut self,
+            _spi: &mut SpiMock,
+            _refresh_rate: Option<crate::traits::RefreshLut>,
+        ) -> Result<(), Error<MockError>> {
+            Ok(())
+        }
+        fn is_busy(&self) -> bool {
+            false
+        }
+    }
+
+    const SMALL: FrameHeader = FrameHeader::new(7, FrameFormat::Mono, 16, 4);
+
+    fn compressed(header: &FrameHeader, frame: &[u8]) -> ([u8; 64], usize) {
+        let mut packet = [0u8; 64];
+        let len = Rle
+            .encode_to_slice(frame, &mut packet[HEADER_LEN..])
+            .unwrap();
+        let header = header.encode(&packet[HEADER_LEN..HEADER_LEN + len]);
+        packet[..HEADER_LEN].copy_from_slice(&header);
+        (packet, HEADER_LEN + len)
+    }
+
+    #[test]
+    fn compressed_frame_sent_in_bands() {
+        let frame = [0xFF, 0xFF, 0x00, 0x00, 0x0F, 0x0F, 0xAA, 0x55];
+        let (packet, len) = compressed(&SMALL, &frame);
+        let mut spi = SpiMock::new(&[]);
+        let mut epd = FakeEpd::default();
+        // 3 rows: a full band and one with the last row
+        let mut band = [0u8; 7];
+        apply_compressed_frame(
+            &mut epd,
+            &mut spi,
+            &packet[..len],
+            &SMALL,
+            &Rle,
+            &mut band,
+            &mut MockNoop::new(),
+        )
+        .unwrap();
+        assert_eq!(epd.frame, frame);
+        assert_eq!(epd.bands, 2);
+    }
+
+    #[test]
+    fn compressed_frame_of_wrong_length() {
+        let mut spi = SpiMock::new(&[]);
+        let mut band = [0u8; 8];
+        for frame in [&[0u8; 6][..], &[0u8; 10][..]] {
+            let (packet, len) = compressed(&SMALL, frame);
+            assert_eq!(
+                apply_compressed_frame(
+                    &mut FakeEpd::default(),
+                    &mut spi,
+                    &packet[..len],
+                    &SMALL,
+                    &Rle,
+                    &mut band,
+                    &mut MockNoop::new(),
+                ),
+                Err(RemoteFrameError::Corrupt)
+            );
+        }
+
+        let tricolor = FrameHeader::new(7, FrameFormat::TriColor, 16, 4);
+        let (packet, len) = compressed(&tricolor, &[0u8; 16]);
+        assert_eq!(
+            apply_compressed_frame(
+                &mut FakeEpd::default(),
+                &mut spi,
+                &packet[..len],
+                &tricolor,
+                &Rle,
+                &mut band,
+                &mut MockNoop::new(),
+            ),
+            Err(RemoteFrameError::Unsupported(FrameFormat::TriColor))
+        );
+    }
+}
@@ -0,0 +1,253 @@
+//! Command sequences for bringing up panels without a driver
+//!
+//! The vendor drivers mostly consist of tables of commands, their data, delays and waits
+//! for the busy line. To try out a panel which isn't supported yet, such a table can be
+//! copied line by line into a slice of [`SeqOp`] and run with
+//! [`SequenceRunner::run_sequence`], without writing a driver first. Once the panel
+//! shows something, the sequence is a good start for a real driver.
+//!
+//! [`SeqOp::WaitBusy`] waits for the busy line without a timeout, like the drivers do.
+//! Check the level of the busy line in the datasheet if the sequence gets stuck there.
+//!
+//!```rust, no_run
+//!# use embedded_hal_mock::*;
+//!# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
+//!use epd_waveshare::sequence::{SeqOp, SequenceRunner};
+//!#
+//!# let expectations = [];
+//!# let mut spi = spi::Mock::new(&expectations);
+//!# let expectations = [];
+//!# let cs_pin = pin::Mock::new(&expectations);
+//!# let busy_in = pin::Mock::new(&expectations);
+//!# let dc = pin::Mock::new(&expectations);
+//!# let rst = pin::Mock::new(&expectations);
+//!# let mut delay = delay::MockNoop::new();
+//!
+//!// the panel signals busy with a low level
+//!let mut runner = SequenceRunner::new(cs_pin, busy_in, dc, rst, true);
+//!runner.run_sequence(
+//!    &mut spi,
+//!    &mut delay,
+//!    &[
+//!        SeqOp::Reset(2),
+//!        SeqOp::Cmd(0x01),
+//!        SeqOp::Data(&[0x07, 0x07, 0x3F, 0x3F]),
+//!        SeqOp::Cmd(0x04),
+//!        SeqOp::Delay(100),
+//!        SeqOp::WaitBusy,
+//!    ],
+//!)?;
+//!# Ok(())
+//!# }
+//!```
+
+use embedded_hal::{
+    blocking::{delay::*, spi::Write},
+    digital::v2::{InputPin, OutputPin},
+};
+
+use crate::interface::DisplayInterface;
+use crate::traits::Command;
+use crate::Error;
+
+/// One step of a command sequence
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeqOp<'a> {
+    /// Pulls the reset pin low for the given milliseconds
+    Reset(u8),
+    /// Sends a command byte
+    Cmd(u8),
+    /// Sends data bytes, usually the parameters of the command before
+    Data(&'a [u8]),
+    /// Waits for the given milliseconds
+    Delay(u16),
+    /// Waits until the busy line is released
+    WaitBusy,
+}
+
+/// Command byte of a [`SeqOp::Cmd`]
+#[derive(Clone, Copy)]
+struct RawCommand(u8);
+
+impl Command for RawCommand {
+    fn address(self) -> u8 {
+        self.0
+    }
+}
+
+/// Runs command sequences on a display connected like the supported ones
+pub struct SequenceRunner<SPI, CS, BUSY, DC, RST, DELAY> {
+    /// Connection Interface
+    interface: DisplayInterface<SPI, CS, BUSY, DC, RST, DELAY>,
+    /// Whether a low busy line means busy
+    is_busy_low: bool,
+}
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> SequenceRunner<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    /// Creates a runner, `is_busy_low` is the level of the busy line of the panel
+    ///
+    /// Nothing is sent to the display until a sequence is run.
+    pub fn new(cs: CS, busy: BUSY, dc: DC, rst: RST, is_busy_low: bool) -> Self {
+        SequenceRunner {
+            interface: DisplayInterface::new(cs, busy, dc, rst),
+            is_busy_low,
+        }
+    }
+
+    /// Runs the steps of `ops` one after the other, stopping at the first SPI error
+    pub fn run_sequence(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        ops: &[SeqOp],
+    ) -> Result<(), Error<SPI::Error>> {
+        for op in ops {
+            match *op {
+                SeqOp::Reset(duration) => self.interface.reset(delay, duration),
+                SeqOp::Cmd(command) => self.interface.cmd(spi, RawCommand(command))?,
+                SeqOp::Data(data) => self.interface.data(spi, data)?,
+                SeqOp::Delay(ms) => delay_ms(delay, ms),
+                SeqOp::WaitBusy => self.interface.wait_until_idle(self.is_busy_low),
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks if the display is busy
+    pub fn is_busy(&self) -> bool {
+        self.interface.is_busy(self.is_busy_low)
+    }
+}
+
+/// Waits for `ms` milliseconds in steps `DelayMs<u8>` can take
+fn delay_ms<DELAY: DelayMs<u8>>(delay: &mut DELAY, mut ms: u16) {
+    while ms > 0 {
+        let step = ms.min(u16::from(u8::MAX));
+        delay.delay_ms(step as u8);
+        ms -= step;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::RefCell;
+    extern crate std;
+    use std::vec::Vec;
+
+    /// Something which happened on the bus
+    #[derive(Debug, PartialEq)]
+    enum Event {
+        Command(u8),
+        Data(u8),
+        Delay(u8),
+    }
+
+    /// Log shared by the SPI, the DC pin and the delay
+    struct Log {
+        events: RefCell<Vec<Event>>,
+        data: RefCell<bool>,
+    }
+
+    struct Spi<'a>(&'a Log);
+
+    impl Write<u8> for Spi<'_> {
+        type Error = ();
+
+        fn write(&mut self, words: &[u8]) -> Result<(), ()> {
+            let data = *self.0.data.borrow();
+            self.0.events.borrow_mut().extend(words.iter().map(|&b| {
+                if data {
+                    Event::Data(b)
+                } else {
+                    Event::Command(b)
+                }
+            }));
+            Ok(())
+        }
+    }
+
+    /// DC pin, other pins only accept every level
+    struct Pin<'a>(Option<&'a Log>);
+
+    impl OutputPin for Pin<'_> {
+        type Error = ();
+
+        fn set_low(&mut self) -> Result<(), ()> {
+            if let Some(log) = self.0 {
+                *log.data.borrow_mut() = false;
+            }
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), ()> {
+            if let Some(log) = self.0 {
+                *log.data.borrow_mut() = true;
+            }
+            Ok(())
+        }
+    }
+
+    impl InputPin for Pin<'_> {
+        type Error = ();
+
+        fn is_high(&self) -> Result<bool, ()> {
+            Ok(true)
+        }
+
+        fn is_low(&self) -> Result<bool, ()> {
+            Ok(false)
+        }
+    }
+
+    struct Delay<'a>(&'a Log);
+
+    impl DelayMs<u8> for Delay<'_> {
+        fn delay_ms(&mut self, ms: u8) {
+            self.0.events.borrow_mut().push(Event::Delay(ms));
+        }
+    }
+
+    #[test]
+    fn runs_ops_in_order() {
+        let log = Log {
+            events: RefCell::new(Vec::new()),
+            data: RefCell::new(false),
+        };
+        let mut runner =
+            SequenceRunner::new(Pin(None), Pin(None), Pin(Some(&log)), Pin(None), true);
+        runner
+            .run_sequence(
+                &mut Spi(&log),
+                &mut Delay(&log),
+                &[
+                    SeqOp::Cmd(0x01),
+                    SeqOp::Data(&[0x07, 0x3F]),
+                    SeqOp::WaitBusy,
+                    SeqOp::Delay(300),
+                    SeqOp::Cmd(0x12),
+                ],
+            )
+            .unwrap();
+        assert!(!runner.is_busy());
+        assert_eq!(
+            log.events.into_inner(),
+            [
+                Event::Command(0x01),
+                Event::Data(0x07),
+                Event::Data(0x3F),
+                Event::Delay(255),
+                Event::Delay(45),
+                Event::Command(0x12),
+            ]
+        );
+    }
+}
@@ -0,0 +1,429 @@
+//! Driving the display from a dedicated core or task
+//!
+//! On dual core controllers like the RP2040 or the ESP32 it's often easier to give one
+//! core the SPI bus and the display for itself than to share the driver between both.
+//! The other core describes what it wants as a [`Request`], encodes it into a message
+//! with [`Request::encode`] and hands it over through whatever channel the platform
+//! offers, e.g. the inter core FIFO or a channel of an async executor. The owning core
+//! wraps this channel in a [`RequestQueue`] and runs [`run_epd_service`], which decodes
+//! the messages and executes them until it receives [`Request::Stop`].
+//!
+//! A message is a tag byte followed by the parameters, frame data always comes last and
+//! takes up the rest of the message:
+//!
+//! | Request | Tag | Parameters |
+//! | --- | --- | --- |
+//! | `UpdateFrame` | `0x00` | frame |
+//! | `UpdatePartialFrame` | `0x01` | x, y, width, height as little endian `u16`, frame |
+//! | `DisplayFrame` | `0x02` | |
+//! | `UpdateAndDisplayFrame` | `0x03` | frame |
+//! | `ClearFrame` | `0x04` | |
+//! | `SetLut` | `0x05` | `0x00` for `Full`, `0x01` for `Quick`, `0x02` and the index in [`PRESETS`] for `Custom` |
+//! | `Sleep` | `0x06` | |
+//! | `WakeUp` | `0x07` | |
+//! | `Stop` | `0x08` | |
+//!
+//!```rust, no_run
+//!# use embedded_hal_mock::*;
+//!# fn main() -> Result<(), epd_waveshare::service::ServiceError<epd_waveshare::Error<MockError>>> {
+//!use epd_waveshare::{epd4in2::*, graphics::Display, prelude::*, service::*};
+//!#
+//!# let expectations = [];
+//!# let mut spi = spi::Mock::new(&expectations);
+//!# let expectations = [];
+//!# let cs_pin = pin::Mock::new(&expectations);
+//!# let busy_in = pin::Mock::new(&expectations);
+//!# let dc = pin::Mock::new(&expectations);
+//!# let rst = pin::Mock::new(&expectations);
+//!# let mut delay = delay::MockNoop::new();
+//!
+//!/// Stands in for the channel between both cores
+//!struct Mailbox {
+//!    messages: [[u8; 15001]; 2],
+//!    lens: [usize; 2],
+//!    next: usize,
+//!}
+//!
+//!impl RequestQueue for Mailbox {
+//!    fn receive(&mut self) -> Option<&[u8]> {
+//!        let i = self.next;
+//!        self.next += 1;
+//!        self.messages.get(i).map(|m| &m[..self.lens[i]])
+//!    }
+//!}
+//!
+//!// on the core drawing the content
+//!let display = Display4in2::default();
+//!let mut mailbox = Mailbox { messages: [[0; 15001]; 2], lens: [0; 2], next: 0 };
+//!mailbox.lens[0] = Request::UpdateAndDisplayFrame(display.buffer())
+//!    .encode(&mut mailbox.messages[0])
+//!    .unwrap();
+//!mailbox.lens[1] = Request::Stop.encode(&mut mailbox.messages[1]).unwrap();
+//!
+//!// on the core owning SPI and the display
+//!let mut epd = Epd4in2::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
+//!run_epd_service(&mut epd, &mut spi, &mut delay, &mut mailbox)?;
+//!# Ok(())
+//!# }
+//!```
+
+use core::convert::Infallible;
+use embedded_hal::{
+    blocking::{delay::*, spi::Write},
+    digital::v2::*,
+};
+
+use crate::luts::PRESETS;
+use crate::traits::{RefreshLut, WaveshareDisplay};
+use crate::Error;
+
+const UPDATE_FRAME: u8 = 0x00;
+const UPDATE_PARTIAL_FRAME: u8 = 0x01;
+const DISPLAY_FRAME: u8 = 0x02;
+const UPDATE_AND_DISPLAY_FRAME: u8 = 0x03;
+const CLEAR_FRAME: u8 = 0x04;
+const SET_LUT: u8 = 0x05;
+const SLEEP: u8 = 0x06;
+const WAKE_UP: u8 = 0x07;
+const STOP: u8 = 0x08;
+
+/// Errors of the display service and the message format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServiceError<E> {
+    /// The message is malformed
+    Corrupt,
+    /// The output slice can't hold the encoded message
+    BufferTooSmall,
+    /// The display returned an error
+    Spi(E),
+}
+
+impl<E> From<E> for ServiceError<E> {
+    fn from(err: E) -> Self {
+        ServiceError::Spi(err)
+    }
+}
+
+/// Something another core or task wants the display to do
+///
+/// The variants map to the methods of [`WaveshareDisplay`] with the same name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Request<'a> {
+    /// Transmit a full frame
+    UpdateFrame(&'a [u8]),
+    /// Transmit a part of the frame
+    UpdatePartialFrame {
+        /// Left edge of the window
+        x: u16,
+        /// Top edge of the window
+        y: u16,
+        /// Width of the window
+        width: u16,
+        /// Height of the window
+        height: u16,
+        /// Content of the window
+        buffer: &'a [u8],
+    },
+    /// Show the transmitted frame
+    DisplayFrame,
+    /// Transmit and show a full frame
+    UpdateAndDisplayFrame(&'a [u8]),
+    /// Clear the display with its background color
+    ClearFrame,
+    /// Select the waveform
+    SetLut(RefreshLut),
+    /// Put the display to sleep
+    Sleep,
+    /// Wake the display up again
+    WakeUp,
+    /// Ends [`run_epd_service`]
+    Stop,
+}
+
+impl<'a> Request<'a> {
+    /// Encodes the request into `output` and returns the length of the message
+    ///
+    /// A [`RefreshLut::Custom`] preset can only be sent if it's one of the [`PRESETS`],
+    /// otherwise the request is `Corrupt`.
+    pub fn encode(&self, output: &mut [u8]) -> Result<usize, ServiceError<Infallible>> {
+        let mut header = [0u8; 9];
+        let (header_len, frame): (usize, &[u8]) = match *self {
+            Request::UpdateFrame(buffer) => {
+                header[0] = UPDATE_FRAME;
+                (1, buffer)
+            }
+            Request::UpdatePartialFrame {
+                x,
+                y,
+                width,
+                height,
+                buffer,
+            } => {
+                header[0] = UPDATE_PARTIAL_FRAME;
+                for (i, value) in [x, y, width, height].iter().enumerate() {
+                    header[1 + 2 * i..3 + 2 * i].copy_from_slice(&value.to_le_bytes());
+                }
+                (9, buffer)
+            }
+            Request::DisplayFrame => {
+                header[0] = DISPLAY_FRAME;
+                (1, &[])
+            }
+            Request::UpdateAndDisplayFrame(buffer) => {
+                header[0] = UPDATE_AND_DISPLAY_FRAME;
+                (1, buffer)
+            }
+            Request::ClearFrame => {
+                header[0] = CLEAR_FRAME;
+                (1, &[])
+            }
+            Request::SetLut(lut) => {
+                header[0] = SET_LUT;
+                let (code, index) = match lut {
+                    RefreshLut::Full => (0x00, None),
+                    RefreshLut::Quick => (0x01, None),
+                    RefreshLut::Custom(preset) => {
+                        let index = PRESETS.iter().position(|p| p == preset);
+                        (0x02, Some(index.ok_or(ServiceError::Corrupt)?))
+                    }
+                };
+                header[1] = code;
+                match index {
+                    Some(index) => {
+                        header[2] = index as u8;
+                        (3, &[])
+                    }
+                    None => (2, &[]),
+                }
+            }
+            Request::Sleep => {
+                header[0] = SLEEP;
+                (1, &[])
+            }
+            Request::WakeUp => {
+                header[0] = WAKE_UP;
+                (1, &[])
+            }
+            Request::Stop => {
+                header[0] = STOP;
+                (1, &[])
+            }
+        };
+
+        let len = header_len + frame.len();
+        if len > output.len() {
+            return Err(ServiceError::BufferTooSmall);
+        }
+        output[..header_len].copy_from_slice(&header[..header_len]);
+        output[header_len..len].copy_from_slice(frame);
+        Ok(len)
+    }
+
+    /// Decodes a message, frames are borrowed from it
+    pub fn decode(message: &'a [u8]) -> Result<Self, ServiceError<Infallible>> {
+        let (&tag, rest) = message.split_first().ok_or(ServiceError::Corrupt)?;
+        let empty = |request| {
+            if rest.is_empty() {
+                Ok(request)
+            } else {
+                Err(ServiceError::Corrupt)
+            }
+        };
+        match tag {
+            UPDATE_FRAME => Ok(Request::UpdateFrame(rest)),
+            UPDATE_PARTIAL_FRAME => {
+                if rest.len() < 8 {
+                    return Err(ServiceError::Corrupt);
+                }
+                let value = |i: usize| u16::from_le_bytes([rest[2 * i], rest[2 * i + 1]]);
+                Ok(Request::UpdatePartialFrame {
+                    x: value(0),
+                    y: value(1),
+                    width: value(2),
+                    height: value(3),
+                    buffer: &rest[8..],
+                })
+            }
+            DISPLAY_FRAME => empty(Request::DisplayFrame),
+            UPDATE_AND_DISPLAY_FRAME => Ok(Request::UpdateAndDisplayFrame(rest)),
+            CLEAR_FRAME => empty(Request::ClearFrame),
+            SET_LUT => match rest {
+                [0x00] => Ok(Request::SetLut(RefreshLut::Full)),
+                [0x01] => Ok(Request::SetLut(RefreshLut::Quick)),
+                [0x02, index] => PRESETS
+                    .get(*index as usize)
+                    .map(|preset| Request::SetLut(RefreshLut::Custom(preset)))
+                    .ok_or(ServiceError::Corrupt),
+                _ => Err(ServiceError::Corrupt),
+            },
+            SLEEP => empty(Request::Sleep),
+            WAKE_UP => empty(Request::WakeUp),
+            STOP => empty(Request::Stop),
+            _ => Err(ServiceError::Corrupt),
+        }
+    }
+
+    /// Executes the request on `epd`, [`Request::Stop`] does nothing
+    pub fn execute<EPD, SPI, CS, BUSY, DC, RST, DELAY>(
+        &self,
+        epd: &mut EPD,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>>
+    where
+        EPD: WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>,
+        SPI: Write<u8>,
+        CS: OutputPin,
+        BUSY: InputPin,
+        DC: OutputPin,
+        RST: OutputPin,
+        DELAY: DelayMs<u8>,
+    {
+        match *self {
+            Request::UpdateFrame(buffer) => epd.update_frame(spi, buffer, delay),
+            Request::UpdatePartialFrame {
+                x,
+                y,
+                width,
+                height,
+                buffer,
+            } => epd.update_partial_frame(
+                spi,
+                buffer,
+                x.into(),
+                y.into(),
+                width.into(),
+                height.into(),
+            ),
+            Request::DisplayFrame => epd.display_frame(spi, delay),
+            Request::UpdateAndDisplayFrame(buffer) => {
+                epd.update_and_display_frame(spi, buffer, delay)
+            }
+            Request::ClearFrame => epd.clear_frame(spi, delay),
+            Request::SetLut(lut) => epd.set_lut(spi, Some(lut)),
+            Request::Sleep => epd.sleep(spi, delay),
+            Request::WakeUp => epd.wake_up(spi, delay),
+            Request::Stop => Ok(()),
+        }
+    }
+}
+
+/// Source of encoded requests for [`run_epd_service`]
+pub trait RequestQueue {
+    /// Returns the next message, or `None` if there is none yet
+    ///
+    /// The message only has to stay valid until the next call, so its slot can be
+    /// handed back to the sender then.
+    fn receive(&mut self) -> Option<&[u8]>;
+}
+
+/// Executes the requests from `queue` on `epd` until [`Request::Stop`] is received
+///
+/// The queue is polled while it's empty. Malformed messages and errors of the display
+/// end the service, it can be started again to carry on with the next message.
+pub fn run_epd_service<EPD, SPI, CS, BUSY, DC, RST, DELAY, Q>(
+    epd: &mut EPD,
+    spi: &mut SPI,
+    delay: &mut DELAY,
+    queue: &mut Q,
+) -> Result<(), ServiceError<Error<SPI::Error>>>
+where
+    EPD: WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>,
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+    Q: RequestQueue,
+{
+    loop {
+        let message = match queue.receive() {
+            Some(message) => message,
+            None => continue,
+        };
+        let request = Request::decode(message).map_err(|_| ServiceError::Corrupt)?;
+        if request == Request::Stop {
+            return Ok(());
+        }
+        request.execute(epd, spi, delay)?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(request: Request) {
+        let mut message = [0u8; 16];
+        let len = request.encode(&mut message).unwrap();
+        assert_eq!(Request::decode(&message[..len]), Ok(request));
+    }
+
+    #[test]
+    fn encode_decode() {
+        round_trip(Request::UpdateFrame(&[0x12, 0x34]));
+        round_trip(Request::UpdatePartialFrame {
+            x: 8,
+            y: 300,
+            width: 16,
+            height: 1,
+            buffer: &[0xAA, 0x55],
+        });
+        round_trip(Request::DisplayFrame);
+        round_trip(Request::UpdateAndDisplayFrame(&[0xFF]));
+        round_trip(Request::ClearFrame);
+        round_trip(Request::SetLut(RefreshLut::Quick));
+        round_trip(Request::SetLut(RefreshLut::Custom(
+            crate::luts::by_name("4in2/fast").unwrap(),
+        )));
+        round_trip(Request::Sleep);
+        round_trip(Request::WakeUp);
+        round_trip(Request::Stop);
+    }
+
+    #[test]
+    fn message_layout() {
+        let mut message = [0u8; 16];
+        let request = Request::UpdatePartialFrame {
+            x: 0x0102,
+            y: 3,
+            width: 8,
+            height: 2,
+            buffer: &[0xF0, 0x0F],
+        };
+        let len = request.encode(&mut message).unwrap();
+        assert_eq!(
+            &message[..len],
+            &[0x01, 0x02, 0x01, 3, 0, 8, 0, 2, 0, 0xF0, 0x0F]
+        );
+    }
+
+    #[test]
+    fn buffer_too_small() {
+        let mut message = [0u8; 2];
+        assert_eq!(
+            Request::UpdateFrame(&[1, 2]).encode(&mut message),
+            Err(ServiceError::BufferTooSmall)
+        );
+    }
+
+    #[test]
+    fn corrupt_messages() {
+        assert_eq!(Request::decode(&[]), Err(ServiceError::Corrupt));
+        assert_eq!(Request::decode(&[0x42]), Err(ServiceError::Corrupt));
+        assert_eq!(
+            Request::decode(&[SET_LUT, 0x02]),
+            Err(ServiceError::Corrupt)
+        );
+        assert_eq!(
+            Request::decode(&[SET_LUT, 0x02, 0xFF]),
+            Err(ServiceError::Corrupt)
+        );
+        assert_eq!(Request::decode(&[SLEEP, 0x00]), Err(ServiceError::Corrupt));
+        assert_eq!(
+            Request::decode(&[UPDATE_PARTIAL_FRAME, 0, 0]),
+            Err(ServiceError::Corrupt)
+        );
+    }
+}
@@ -0,0 +1,268 @@
+//! Drawing straight to the display in bands of rows
+//!
+//! The [`Display`](crate::graphics::Display) buffers hold the whole frame, which doesn't
+//! fit into the RAM of small MCUs for the bigger panels. A [`StreamingDisplay`] only keeps
+//! a band of rows and hands every finished band to a sink, e.g. a closure calling
+//! `update_partial_frame`. The sink can fail on the bus, and these errors come back as
+//! [`StreamError::Sink`] from the `draw` call which moved on to the next band, so there is
+//! no need to stash them somewhere on the side.
+//!
+//! Pixels have to be drawn from the top to the bottom, like images or lines of text
+//! are. A pixel above the current band can't be drawn anymore, since its band was
+//! already sent, and returns [`StreamError::OutOfOrder`]. The buffer has 1 bit per pixel
+//! and no rotation.
+//!
+//!```rust, no_run
+//!# use embedded_hal_mock::*;
+//!# fn main() -> Result<(), epd_waveshare::stream::StreamError<epd_waveshare::Error<MockError>>> {
+//!use embedded_graphics::{
+//!    pixelcolor::BinaryColor::On as Black, prelude::*, primitives::{Line, PrimitiveStyle},
+//!};
+//!use epd_waveshare::{color::Color, epd4in2::*, prelude::*, stream::StreamingDisplay};
+//!#
+//!# let expectations = [];
+//!# let mut spi = spi::Mock::new(&expectations);
+//!# let expectations = [];
+//!# let cs_pin = pin::Mock::new(&expectations);
+//!# let busy_in = pin::Mock::new(&expectations);
+//!# let dc = pin::Mock::new(&expectations);
+//!# let rst = pin::Mock::new(&expectations);
+//!# let mut delay = delay::MockNoop::new();
+//!
+//!let mut epd = Epd4in2::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
+//!
+//!// 16 rows instead of the full 300
+//!let mut band = [0u8; WIDTH as usize / 8 * 16];
+//!let mut display = StreamingDisplay::new(WIDTH, HEIGHT, &mut band, Color::White, |row, rows, bytes| {
+//!    epd.update_partial_frame(&mut spi, bytes, 0, row, WIDTH, rows)
+//!});
+//!
+//!Line::new(Point::new(0, 10), Point::new(0, 290))
+//!    .into_styled(PrimitiveStyle::with_stroke(Black, 1))
+//!    .draw(&mut display)?;
+//!
+//!// sends the last band and the untouched rows below
+//!display.finish()?;
+//!epd.display_frame(&mut spi, &mut delay)?;
+//!# Ok(())
+//!# }
+//!```
+
+use crate::color::Color;
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics_core::prelude::*;
+
+/// Errors returned while drawing to a [`StreamingDisplay`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamError<E> {
+    /// The pixel lies above the current band, which was already sent
+    OutOfOrder,
+    /// The sink returned an error
+    Sink(E),
+}
+
+impl<E> From<E> for StreamError<E> {
+    fn from(error: E) -> Self {
+        StreamError::Sink(error)
+    }
+}
+
+/// A draw target which sends the frame in bands of rows while drawing
+///
+/// `sink` is called with the first row, the number of rows and the bytes of every
+/// band, which is always made of full rows.
+pub struct StreamingDisplay<'a, F> {
+    width: u32,
+    height: u32,
+    band: &'a mut [u8],
+    /// Rows which fit into the band
+    band_rows: u32,
+    /// First row of the current band
+    row: u32,
+    background: Color,
+    sink: F,
+}
+
+impl<'a, F, E> StreamingDisplay<'a, F>
+where
+    F: FnMut(u32, u32, &[u8]) -> Result<(), E>,
+{
+    /// Creates a display of `width` x `height` pixels drawing into `band`
+    ///
+    /// `band` has to hold at least one row, `(width + 7) / 8` bytes. Pixels which
+    /// aren't drawn keep the `background` color.
+    pub fn new(width: u32, height: u32, band: &'a mut [u8], background: Color, sink: F) -> Self {
+        let band_rows = (band.len() / row_bytes(width)) as u32;
+        #[cfg(not(feature = "panic_free"))]
+        assert!(band_rows > 0);
+        let mut display = StreamingDisplay {
+            width,
+            height,
+            band,
+            band_rows,
+            row: 0,
+            background,
+            sink,
+        };
+        display.clear_band();
+        display
+    }
+
+    /// First row of the band which is drawn into
+    pub fn row(&self) -> u32 {
+        self.row
+    }
+
+    /// Sends the current band and all rows below it
+    pub fn finish(mut self) -> Result<(), StreamError<E>> {
+        while self.row < self.height && self.band_rows > 0 {
+            self.send_band()?;
+        }
+        Ok(())
+    }
+
+    /// Sends the bands up to the one holding `row`
+    fn advance_to(&mut self, row: u32) -> Result<(), StreamError<E>> {
+        // a band without rows would never reach `row`
+        while row >= self.row + self.band_rows && self.band_rows > 0 {
+            self.send_band()?;
+        }
+        Ok(())
+    }
+
+    /// Sends the current band and starts the next one below it
+    fn send_band(&mut self) -> Result<(), StreamError<E>> {
+        let rows = self.band_rows.min(self.height - self.row);
+        let len = rows as usize * row_bytes(self.width);
+        (self.sink)(self.row, rows, &self.band[..len])?;
+        self.row += rows;
+        self.clear_band();
+        Ok(())
+    }
+
+    fn clear_band(&mut self) {
+        let byte = self.background.get_byte_value();
+        self.band.iter_mut().for_each(|b| *b = byte);
+    }
+}
+
+fn row_bytes(width: u32) -> usize {
+    (width as usize).div_ceil(8)
+}
+
+impl<'a, F, E> DrawTarget for StreamingDisplay<'a, F>
+where
+    F: FnMut(u32, u32, &[u8]) -> Result<(), E>,
+{
+    type Color = BinaryColor;
+    type Error = StreamError<E>;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(point, color) in pixels {
+            if point.x < 0 || point.y < 0 {
+                continue;
+            }
+            let (x, y) = (point.x as u32, point.y as u32);
+            if x >= self.width || y >= self.height {
+                continue;
+            }
+            if y < self.row {
+                return Err(StreamError::OutOfOrder);
+            }
+            self.advance_to(y)?;
+
+            let index = (y - self.row) as usize * row_bytes(self.width) + x as usize / 8;
+            let bit = 0x80 >> (x % 8);
+            if let Some(byte) = self.band.get_mut(index) {
+                match color {
+                    // Black
+                    BinaryColor::On => *byte &= !bit,
+                    // White
+                    BinaryColor::Off => *byte |= bit,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a, F> OriginDimensions for StreamingDisplay<'a, F> {
+    fn size(&self) -> Size {
+        Size::new(self.width, self.height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Black;
+    extern crate std;
+    use std::vec::Vec;
+
+    #[test]
+    fn sends_bands_in_order() {
+        let mut sent = Vec::new();
+        let mut band = [0u8; 2 * 2];
+        let mut display =
+            StreamingDisplay::new(16, 5, &mut band, Color::White, |row, rows, bytes: &[u8]| {
+                sent.push((row, rows, bytes.to_vec()));
+                Ok::<(), ()>(())
+            });
+
+        Pixel(Point::new(0, 0), Black).draw(&mut display).unwrap();
+        assert_eq!(display.row(), 0);
+        Pixel(Point::new(9, 3), Black).draw(&mut display).unwrap();
+        assert_eq!(display.row(), 2);
+        display.finish().unwrap();
+
+        assert_eq!(
+            sent,
+            [
+                (0, 2, std::vec![0x7F, 0xFF, 0xFF, 0xFF]),
+                (2, 2, std::vec![0xFF, 0xFF, 0xFF, 0xBF]),
+                (4, 1, std::vec![0xFF, 0xFF]),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_pixels_above_band() {
+        let mut band = [0u8; 2];
+        let mut display =
+            StreamingDisplay::new(16, 4, &mut band, Color::White, |_, _, _: &[u8]| {
+                Ok::<(), ()>(())
+            });
+
+        Pixel(Point::new(0, 2), Black).draw(&mut display).unwrap();
+        assert_eq!(
+            Pixel(Point::new(0, 1), Black).draw(&mut display),
+            Err(StreamError::OutOfOrder)
+        );
+        // outside of the display is skipped, like for the other displays
+        Pixel(Point::new(-1, 0), Black).draw(&mut display).unwrap();
+    }
+
+    #[test]
+    fn propagates_sink_errors() {
+        let mut band = [0u8; 2];
+        let mut display =
+            StreamingDisplay::new(16, 4, &mut band, Color::White, |row, _, _: &[u8]| {
+                if row == 1 {
+                    Err("bus error")
+                } else {
+                    Ok(())
+                }
+            });
+
+        Pixel(Point::new(0, 1), Black).draw(&mut display).unwrap();
+        assert_eq!(
+            Pixel(Point::new(0, 2), Black).draw(&mut display),
+            Err(StreamError::Sink("bus error"))
+        );
+        // the failed band is kept and sent again
+        assert_eq!(display.row(), 1);
+    }
+}
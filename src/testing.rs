@@ -0,0 +1,633 @@
+//! Scripted self test for a connected display
+//!
+//! Drives a real panel through a fixed sequence of steps, so new drivers (or new
+//! wiring) can be checked against the hardware in the same way every time:
+//!
+//! 1. [`Step::Clear`]: clears the frame and displays it
+//! 2. [`Step::Pattern`]: displays a checkerboard over the full frame
+//! 3. [`Step::PartialGrid`]: clears the frame and sends a grid of tiles with `update_partial_frame`
+//! 4. [`Step::SleepWake`]: sends the display to sleep and wakes it up again
+//!
+//! Every step is reported to a callback with a [`Marker`] when it starts and when it
+//! passed or failed, e.g. for printing it over a serial port. A step fails if the SPI
+//! transfer fails or if the busy line doesn't get released in time. The sequence stops
+//! at the first failure, which is also returned as [`SelfTestError`].
+//!
+//! The pattern and the tiles are written into a scratch buffer with 1 bit per pixel, so
+//! this is only usable with drivers using such buffers.
+//!
+//! Whether the pattern actually looks right can't be checked by the display itself.
+//! For incoming inspection of panels bought in bulk, the displayed pattern can be
+//! captured (e.g. photographed and thresholded into a buffer with 1 bit per pixel) and
+//! compared against the expected [`checkerboard`] with [`PatternAnalysis`]. It reports
+//! gate lines (rows) and source lines (columns) stuck at one color as well as single
+//! dead pixels. With the `std` feature [`PatternAnalysis::defects`] collects them into
+//! a `Vec` for use in host tools.
+//!
+//!```rust, no_run
+//!# use embedded_hal_mock::*;
+//!# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
+//!use epd_waveshare::{epd2in9::*, prelude::*, testing::*};
+//!#
+//!# let expectations = [];
+//!# let mut spi = spi::Mock::new(&expectations);
+//!# let expectations = [];
+//!# let cs_pin = pin::Mock::new(&expectations);
+//!# let busy_in = pin::Mock::new(&expectations);
+//!# let dc = pin::Mock::new(&expectations);
+//!# let rst = pin::Mock::new(&expectations);
+//!# let mut delay = delay::MockNoop::new();
+//!
+//!let mut epd = Epd2in9::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
+//!let mut scratch = [0u8; epd_waveshare::buffer_len(WIDTH as usize, HEIGHT as usize)];
+//!
+//!let result = SelfTest::new().run(&mut epd, &mut spi, &mut delay, &mut scratch, |marker| {
+//!    // e.g. print "PASS pattern" over the serial port
+//!    let _ = (marker.label(), marker.step().name());
+//!});
+//!assert!(result.is_ok());
+//!# Ok(())
+//!# }
+//!```
+
+use crate::calibration::RefreshTiming;
+use crate::color::Color;
+use crate::refresh::RefreshMode;
+use crate::traits::WaveshareDisplay;
+use crate::{buffer_len, Error};
+use embedded_hal::{
+    blocking::{delay::*, spi::Write},
+    digital::v2::*,
+};
+
+/// Default time in ms a step waits for the busy line to be released
+pub const DEFAULT_BUSY_TIMEOUT_MS: u32 = 10_000;
+/// Default number of tiles per row and column of [`Step::PartialGrid`]
+pub const DEFAULT_GRID_SIZE: u32 = 4;
+
+/// Interval in ms in which the busy line is polled
+const BUSY_POLL_MS: u8 = 10;
+
+/// The steps of the self test, in the order they are run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    /// Clears the frame and displays it
+    Clear,
+    /// Displays a checkerboard over the full frame
+    Pattern,
+    /// Displays a grid of tiles sent with partial updates
+    PartialGrid,
+    /// Sends the display to sleep and wakes it up again
+    SleepWake,
+}
+
+impl Step {
+    /// All steps in the order they are run
+    pub const ALL: [Step; 4] = [
+        Step::Clear,
+        Step::Pattern,
+        Step::PartialGrid,
+        Step::SleepWake,
+    ];
+
+    /// Short lowercase name of the step
+    pub fn name(self) -> &'static str {
+        match self {
+            Step::Clear => "clear",
+            Step::Pattern => "pattern",
+            Step::PartialGrid => "partial-grid",
+            Step::SleepWake => "sleep-wake",
+        }
+    }
+}
+
+/// Status of a step reported to the callback of [`SelfTest::run`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Marker {
+    /// The step is about to run
+    Start(Step),
+    /// The step finished successfully
+    Pass(Step),
+    /// The step failed, no further steps will be run
+    Fail(Step),
+}
+
+impl Marker {
+    /// The step this marker belongs to
+    pub fn step(self) -> Step {
+        match self {
+            Marker::Start(step) | Marker::Pass(step) | Marker::Fail(step) => step,
+        }
+    }
+
+    /// `"START"`, `"PASS"` or `"FAIL"`
+    pub fn label(self) -> &'static str {
+        match self {
+            Marker::Start(_) => "START",
+            Marker::Pass(_) => "PASS",
+            Marker::Fail(_) => "FAIL",
+        }
+    }
+}
+
+/// Reason for a failed step
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureKind<E> {
+    /// The SPI transfer failed
+    Spi(E),
+    /// The busy line wasn't released within the timeout
+    BusyTimeout,
+    /// The scratch buffer is smaller than a full frame
+    BufferTooSmall,
+}
+
+/// The first failed step of a self test
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SelfTestError<E> {
+    /// The step which failed
+    pub step: Step,
+    /// Why it failed
+    pub kind: FailureKind<E>,
+}
+
+/// Configuration of the self test
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SelfTest {
+    busy_timeout_ms: u32,
+    grid_size: u32,
+}
+
+impl Default for SelfTest {
+    fn default() -> Self {
+        SelfTest {
+            busy_timeout_ms: DEFAULT_BUSY_TIMEOUT_MS,
+            grid_size: DEFAULT_GRID_SIZE,
+        }
+    }
+}
+
+impl SelfTest {
+    /// Self test with the default timeout and grid size
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Changes the time in ms a step waits for the busy line
+    pub fn with_busy_timeout(mut self, busy_timeout_ms: u32) -> Self {
+        self.busy_timeout_ms = busy_timeout_ms;
+        self
+    }
+
+    /// Waits for the busy line as long as a full refresh of `timing` may take
+    pub fn with_refresh_timing(self, timing: &RefreshTiming) -> Self {
+        self.with_busy_timeout(timing.timeout_ms(RefreshMode::Full))
+    }
+
+    /// Changes the number of tiles per row and column of [`Step::PartialGrid`]
+    pub fn with_grid_size(mut self, grid_size: u32) -> Self {
+        self.grid_size = grid_size.max(1);
+        self
+    }
+
+    /// Runs all steps and reports their status to `report`
+    ///
+    /// `scratch` needs to hold a full frame with 1 bit per pixel. The display is left
+    /// awake with a cleared frame if all steps passed.
+    pub fn run<EPD, SPI, CS, BUSY, DC, RST, DELAY, F>(
+        &self,
+        epd: &mut EPD,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        scratch: &mut [u8],
+        mut report: F,
+    ) -> Result<(), SelfTestError<Error<SPI::Error>>>
+    where
+        EPD: WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>,
+        SPI: Write<u8>,
+        CS: OutputPin,
+        BUSY: InputPin,
+        DC: OutputPin,
+        RST: OutputPin,
+        DELAY: DelayMs<u8>,
+        F: FnMut(Marker),
+    {
+        for &step in Step::ALL.iter() {
+            report(Marker::Start(step));
+            match self.run_step(step, epd, spi, delay, scratch) {
+                Ok(()) => report(Marker::Pass(step)),
+                Err(kind) => {
+                    report(Marker::Fail(step));
+                    return Err(SelfTestError { step, kind });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn run_step<EPD, SPI, CS, BUSY, DC, RST, DELAY>(
+        &self,
+        step: Step,
+        epd: &mut EPD,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        scratch: &mut [u8],
+    ) -> Result<(), FailureKind<Error<SPI::Error>>>
+    where
+        EPD: WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>,
+        SPI: Write<u8>,
+        CS: OutputPin,
+        BUSY: InputPin,
+        DC: OutputPin,
+        RST: OutputPin,
+        DELAY: DelayMs<u8>,
+    {
+        let (width, height) = (epd.width(), epd.height());
+        let len = buffer_len(width as usize, height as usize);
+        if step != Step::Clear && step != Step::SleepWake && scratch.len() < len {
+            return Err(FailureKind::BufferTooSmall);
+        }
+
+        match step {
+            Step::Clear => {
+                epd.clear_frame(spi, delay).map_err(FailureKind::Spi)?;
+                epd.display_frame(spi, delay).map_err(FailureKind::Spi)?;
+            }
+            Step::Pattern => {
+                let frame = &mut scratch[..len];
+                checkerboard(frame, width);
+                epd.update_frame(spi, frame, delay)
+                    .map_err(FailureKind::Spi)?;
+                epd.display_frame(spi, delay).map_err(FailureKind::Spi)?;
+            }
+            Step::PartialGrid => {
+                epd.clear_frame(spi, delay).map_err(FailureKind::Spi)?;
+                self.wait_idle(epd, delay)?;
+                for (x, y, tile_width, tile_height) in grid_tiles(width, height, self.grid_size) {
+                    // only every other tile gets filled, like a coarse checkerboard
+                    let filled = ((x / tile_width) + (y / tile_height)) % 2 == 0;
+                    let tile_len = buffer_len(tile_width as usize, tile_height as usize);
+                    let tile = &mut scratch[..tile_len];
+                    tile.fill(if filled { 0x00 } else { 0xFF });
+                    epd.update_partial_frame(spi, tile, x, y, tile_width, tile_height)
+                        .map_err(FailureKind::Spi)?;
+                }
+                epd.display_frame(spi, delay).map_err(FailureKind::Spi)?;
+            }
+            Step::SleepWake => {
+                epd.sleep(spi, delay).map_err(FailureKind::Spi)?;
+                epd.wake_up(spi, delay).map_err(FailureKind::Spi)?;
+                epd.clear_frame(spi, delay).map_err(FailureKind::Spi)?;
+                epd.display_frame(spi, delay).map_err(FailureKind::Spi)?;
+            }
+        }
+        self.wait_idle(epd, delay)
+    }
+
+    /// Polls the busy line until it is released or the timeout is reached
+    fn wait_idle<EPD, SPI, CS, BUSY, DC, RST, DELAY>(
+        &self,
+        epd: &EPD,
+        delay: &mut DELAY,
+    ) -> Result<(), FailureKind<Error<SPI::Error>>>
+    where
+        EPD: WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>,
+        SPI: Write<u8>,
+        CS: OutputPin,
+        BUSY: InputPin,
+        DC: OutputPin,
+        RST: OutputPin,
+        DELAY: DelayMs<u8>,
+    {
+        let mut waited = 0;
+        while epd.is_busy() {
+            if waited >= self.busy_timeout_ms {
+                return Err(FailureKind::BusyTimeout);
+            }
+            delay.delay_ms(BUSY_POLL_MS);
+            waited += u32::from(BUSY_POLL_MS);
+        }
+        Ok(())
+    }
+}
+
+/// Fills `buffer` with the checkerboard of 8x8 pixel squares shown by [`Step::Pattern`]
+///
+/// This is the frame [`PatternAnalysis`] compares the captured one against.
+pub fn checkerboard(buffer: &mut [u8], width: u32) {
+    let row_bytes = width.div_ceil(8) as usize;
+    for (i, byte) in buffer.iter_mut().enumerate() {
+        let (row, col) = (i / row_bytes, i % row_bytes);
+        *byte = if (row / 8 + col) % 2 == 0 { 0x00 } else { 0xFF };
+    }
+}
+
+/// Splits the display into `grid_size` x `grid_size` tiles as `(x, y, width, height)`
+///
+/// The tiles start and end on byte boundaries, the last row and column take the
+/// remaining pixels.
+fn grid_tiles(
+    width: u32,
+    height: u32,
+    grid_size: u32,
+) -> impl Iterator<Item = (u32, u32, u32, u32)> {
+    let tile_width = (width / grid_size / 8 * 8).max(8);
+    let tile_height = (height / grid_size).max(1);
+    let cols = width.div_ceil(tile_width).min(grid_size);
+    let rows = height.div_ceil(tile_height).min(grid_size);
+    (0..rows).flat_map(move |row| {
+        (0..cols).map(move |col| {
+            let (x, y) = (col * tile_width, row * tile_height);
+            let w = if col + 1 == cols {
+                width - x
+            } else {
+                tile_width
+            };
+            let h = if row + 1 == rows {
+                height - y
+            } else {
+                tile_height
+            };
+            (x, y, w, h)
+        })
+    })
+}
+
+/// A defect found by [`PatternAnalysis`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Defect {
+    /// A row showing one color regardless of the pattern
+    StuckGate {
+        /// Index of the row
+        row: u32,
+        /// The color it is stuck at
+        color: Color,
+    },
+    /// A column showing one color regardless of the pattern
+    StuckSource {
+        /// Index of the column
+        column: u32,
+        /// The color it is stuck at
+        color: Color,
+    },
+    /// A single pixel showing the wrong color, outside of stuck lines
+    Pixel {
+        /// Column of the pixel
+        x: u32,
+        /// Row of the pixel
+        y: u32,
+        /// The color shown instead of the expected one
+        color: Color,
+    },
+}
+
+/// Compares a captured frame against the expected pattern
+///
+/// Both frames use 1 bit per pixel with the layout of the display buffers, a set bit
+/// is white. A line counts as stuck if at least the threshold of its pixels show the
+/// same color although the pattern has less of it in that line, the default of 90%
+/// leaves some room for noise in photographed frames.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PatternAnalysis {
+    threshold_percent: u32,
+}
+
+impl Default for PatternAnalysis {
+    fn default() -> Self {
+        PatternAnalysis {
+            threshold_percent: 90,
+        }
+    }
+}
+
+impl PatternAnalysis {
+    /// Analysis with the default threshold
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Changes the share of pixels in percent which makes a line count as stuck
+    pub fn with_threshold(mut self, percent: u8) -> Self {
+        self.threshold_percent = u32::from(percent.clamp(1, 100));
+        self
+    }
+
+    /// Passes all defects of `captured` to `report`: first the stuck rows, then the
+    /// stuck columns and then the dead pixels row by row
+    ///
+    /// Panics if one of the frames is smaller than `width` x `height` pixels, with the
+    /// `panic_free` feature nothing is reported then.
+    pub fn for_each_defect<F>(
+        &self,
+        expected: &[u8],
+        captured: &[u8],
+        width: u32,
+        height: u32,
+        mut report: F,
+    ) where
+        F: FnMut(Defect),
+    {
+        let len = buffer_len(width as usize, height as usize);
+        ensure!(expected.len() >= len && captured.len() >= len, ());
+        let expected = Frame::new(expected, width);
+        let captured = Frame::new(captured, width);
+
+        for row in 0..height {
+            let line = (0..width).map(|x| (x, row));
+            if let Some(color) = self.stuck(&expected, &captured, line) {
+                report(Defect::StuckGate { row, color });
+            }
+        }
+        for column in 0..width {
+            let line = (0..height).map(|y| (column, y));
+            if let Some(color) = self.stuck(&expected, &captured, line) {
+                report(Defect::StuckSource { column, color });
+            }
+        }
+
+        for y in 0..height {
+            let row = (0..width).map(|x| (x, y));
+            if self.stuck(&expected, &captured, row).is_some() {
+                continue;
+            }
+            for x in 0..width {
+                let color = captured.color(x, y);
+                if color == expected.color(x, y) {
+                    continue;
+                }
+                let column = (0..height).map(|y| (x, y));
+                if self.stuck(&expected, &captured, column).is_none() {
+                    report(Defect::Pixel { x, y, color });
+                }
+            }
+        }
+    }
+
+    /// Collects all defects of `captured`, see [`PatternAnalysis::for_each_defect`]
+    #[cfg(feature = "std")]
+    pub fn defects(
+        &self,
+        expected: &[u8],
+        captured: &[u8],
+        width: u32,
+        height: u32,
+    ) -> std::vec::Vec<Defect> {
+        let mut defects = std::vec::Vec::new();
+        self.for_each_defect(expected, captured, width, height, |defect| {
+            defects.push(defect)
+        });
+        defects
+    }
+
+    /// The color a line is stuck at, if any
+    fn stuck<I>(&self, expected: &Frame, captured: &Frame, line: I) -> Option<Color>
+    where
+        I: Iterator<Item = (u32, u32)>,
+    {
+        let (mut len, mut expected_white, mut captured_white) = (0, 0, 0);
+        for (x, y) in line {
+            len += 1;
+            expected_white += u32::from(expected.color(x, y) == Color::White);
+            captured_white += u32::from(captured.color(x, y) == Color::White);
+        }
+        let reaches = |count: u32| count * 100 >= len * self.threshold_percent;
+        if reaches(captured_white) && !reaches(expected_white) {
+            Some(Color::White)
+        } else if reaches(len - captured_white) && !reaches(len - expected_white) {
+            Some(Color::Black)
+        } else {
+            None
+        }
+    }
+}
+
+/// Pixel access to a buffer with 1 bit per pixel
+struct Frame<'a> {
+    buffer: &'a [u8],
+    row_bytes: usize,
+}
+
+impl<'a> Frame<'a> {
+    fn new(buffer: &'a [u8], width: u32) -> Self {
+        Frame {
+            buffer,
+            row_bytes: width.div_ceil(8) as usize,
+        }
+    }
+
+    fn color(&self, x: u32, y: u32) -> Color {
+        let byte = self.buffer[y as usize * self.row_bytes + x as usize / 8];
+        if byte & (0x80 >> (x % 8)) != 0 {
+            Color::White
+        } else {
+            Color::Black
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_covers_display() {
+        for &(width, height, grid) in &[(128, 296, 4), (400, 300, 4), (122, 250, 3), (80, 128, 1)] {
+            let mut area = 0;
+            for (x, y, w, h) in grid_tiles(width, height, grid) {
+                assert_eq!(x % 8, 0);
+                assert!(x + w <= width && y + h <= height);
+                area += w * h;
+            }
+            assert_eq!(area, width * height);
+        }
+    }
+
+    #[test]
+    fn grid_tile_count() {
+        assert_eq!(grid_tiles(128, 296, 4).count(), 16);
+        // only 2 byte-aligned columns fit into 16 pixels
+        assert_eq!(grid_tiles(16, 16, 4).count(), 8);
+    }
+
+    #[test]
+    fn checkerboard_rows() {
+        let mut buffer = [0xAA; 2 * 16];
+        checkerboard(&mut buffer, 16);
+        assert_eq!(&buffer[..2], &[0x00, 0xFF]);
+        assert_eq!(&buffer[14..16], &[0x00, 0xFF]);
+        assert_eq!(&buffer[16..18], &[0xFF, 0x00]);
+    }
+
+    fn analyze(captured: &[u8]) -> ([Option<Defect>; 4], usize) {
+        let mut expected = [0u8; 2 * 16];
+        checkerboard(&mut expected, 16);
+        let (mut defects, mut count) = ([None; 4], 0);
+        PatternAnalysis::new().for_each_defect(&expected, captured, 16, 16, |defect| {
+            if count < defects.len() {
+                defects[count] = Some(defect);
+            }
+            count += 1;
+        });
+        (defects, count)
+    }
+
+    #[test]
+    fn analysis_intact_panel() {
+        let mut captured = [0u8; 2 * 16];
+        checkerboard(&mut captured, 16);
+        assert_eq!(analyze(&captured).1, 0);
+    }
+
+    #[test]
+    fn analysis_stuck_lines() {
+        let mut captured = [0u8; 2 * 16];
+        checkerboard(&mut captured, 16);
+        // row 3 stays white
+        captured[6..8].copy_from_slice(&[0xFF, 0xFF]);
+        // column 10 stays black
+        for row in captured.chunks_mut(2) {
+            row[1] &= !0x20;
+        }
+
+        let (defects, count) = analyze(&captured);
+        assert_eq!(count, 2);
+        assert_eq!(
+            defects[0],
+            Some(Defect::StuckGate {
+                row: 3,
+                color: Color::White
+            })
+        );
+        assert_eq!(
+            defects[1],
+            Some(Defect::StuckSource {
+                column: 10,
+                color: Color::Black
+            })
+        );
+    }
+
+    #[test]
+    fn analysis_dead_pixel() {
+        let mut captured = [0u8; 2 * 16];
+        checkerboard(&mut captured, 16);
+        // pixel (1, 2) is in a black square but shows white
+        captured[4] |= 0x40;
+
+        let (defects, count) = analyze(&captured);
+        assert_eq!(count, 1);
+        assert_eq!(
+            defects[0],
+            Some(Defect::Pixel {
+                x: 1,
+                y: 2,
+                color: Color::White
+            })
+        );
+    }
+
+    #[test]
+    fn marker() {
+        assert_eq!(Marker::Fail(Step::PartialGrid).label(), "FAIL");
+        assert_eq!(Marker::Start(Step::SleepWake).step().name(), "sleep-wake");
+    }
+}
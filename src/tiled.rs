@@ -0,0 +1,406 @@
+//! One image across several panels
+//!
+//! A video wall of identical panels shows one large frame. [`TiledDisplay`] takes the
+//! drivers together with the position of every panel in that frame, cuts the frame into
+//! the parts of the panels and refreshes all of them at once: every refresh is started
+//! before the first one is waited for, so the whole wall changes in the time of a single
+//! panel.
+//!
+//! The frame holds 1 bit per pixel, packed in rows like the buffers of the black/white
+//! displays, with the width of the whole wall. The drivers take the frame of a panel in
+//! one piece, so its rows are gathered in a scratch buffer of the size of one panel frame.
+//! The panels have to start at a multiple of 8 horizontally.
+//!
+//!```rust, no_run
+//!# use embedded_hal_mock::*;
+//!# fn main() -> Result<(), epd_waveshare::tiled::TiledError<epd_waveshare::Error<MockError>>> {
+//!use epd_waveshare::{epd7in5_v2::*, prelude::*, tiled::TiledDisplay};
+//!#
+//!# let expectations = [];
+//!# let mut spi = spi::Mock::new(&expectations);
+//!# let mut delay = delay::MockNoop::new();
+//!# let mut epd = |spi: &mut spi::Mock, delay: &mut delay::MockNoop| {
+//!#     let pin = || pin::Mock::new(&[]);
+//!#     Epd7in5::new(spi, pin(), pin(), pin(), pin(), delay)
+//!# };
+//!
+//!// four panels in a square
+//!let epds = [
+//!    epd(&mut spi, &mut delay)?,
+//!    epd(&mut spi, &mut delay)?,
+//!    epd(&mut spi, &mut delay)?,
+//!    epd(&mut spi, &mut delay)?,
+//!];
+//!let origins = [(0, 0), (WIDTH, 0), (0, HEIGHT), (WIDTH, HEIGHT)];
+//!let scratch = [0u8; WIDTH as usize / 8 * HEIGHT as usize];
+//!let mut wall = TiledDisplay::new(epds, origins, 2 * WIDTH, 2 * HEIGHT, scratch);
+//!
+//!let frame = [0xFF; 4 * WIDTH as usize / 8 * HEIGHT as usize];
+//!wall.update_and_display_frame(&mut spi, &frame, &mut delay)?;
+//!# Ok(())
+//!# }
+//!```
+
+use embedded_hal::{
+    blocking::{delay::*, spi::Write},
+    digital::v2::{InputPin, OutputPin},
+};
+
+use crate::traits::WaveshareDisplay;
+use crate::Error;
+
+/// Errors returned by a [`TiledDisplay`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TiledError<E> {
+    /// A panel doesn't start at a multiple of 8 horizontally
+    Unaligned,
+    /// A panel lies partly outside of the wall
+    OutOfBounds,
+    /// The frame is smaller than the wall
+    FrameTooSmall,
+    /// The scratch buffer can't hold the frame of a panel
+    ScratchTooSmall,
+    /// A driver returned an error
+    Spi(E),
+}
+
+impl<E> From<E> for TiledError<E> {
+    fn from(error: E) -> Self {
+        TiledError::Spi(error)
+    }
+}
+
+/// Drivers of the panels of a wall, which show one frame together
+///
+/// `origins` holds the position of the top left pixel of every panel in the frame of the
+/// wall, in the order of `epds`.
+pub struct TiledDisplay<EPD, S, const N: usize> {
+    epds: [EPD; N],
+    origins: [(u32, u32); N],
+    width: u32,
+    height: u32,
+    scratch: S,
+}
+
+impl<EPD, S, const N: usize> TiledDisplay<EPD, S, N>
+where
+    S: AsMut<[u8]>,
+{
+    /// Combines the drivers of a `width` x `height` wall
+    ///
+    /// The layout is checked when the first frame is sent.
+    pub fn new(
+        epds: [EPD; N],
+        origins: [(u32, u32); N],
+        width: u32,
+        height: u32,
+        scratch: S,
+    ) -> Self {
+        TiledDisplay {
+            epds,
+            origins,
+            width,
+            height,
+            scratch,
+        }
+    }
+
+    /// Width of the wall
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Height of the wall
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns the drivers, e.g. to put them to sleep
+    pub fn epds_mut(&mut self) -> &mut [EPD; N] {
+        &mut self.epds
+    }
+
+    /// Releases the drivers and the scratch buffer
+    pub fn release(self) -> ([EPD; N], S) {
+        (self.epds, self.scratch)
+    }
+
+    /// Sends the part of `frame` of every panel without showing it yet
+    ///
+    /// Nothing is sent if the layout doesn't fit the frame or the scratch buffer.
+    pub fn update_frame<SPI, CS, BUSY, DC, RST, DELAY>(
+        &mut self,
+        spi: &mut SPI,
+        frame: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), TiledError<Error<SPI::Error>>>
+    where
+        EPD: WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>,
+        SPI: Write<u8>,
+        CS: OutputPin,
+        BUSY: InputPin,
+        DC: OutputPin,
+        RST: OutputPin,
+        DELAY: DelayMs<u8>,
+    {
+        let stride = row_bytes(self.width);
+        if frame.len() < stride * self.height as usize {
+            return Err(TiledError::FrameTooSmall);
+        }
+        for (epd, &origin) in self.epds.iter().zip(&self.origins) {
+            let (width, height) = (epd.width(), epd.height());
+            check_tile(origin, width, height, self.width, self.height)?;
+            if self.scratch.as_mut().len() < row_bytes(width) * height as usize {
+                return Err(TiledError::ScratchTooSmall);
+            }
+        }
+
+        for (epd, &(x, y)) in self.epds.iter_mut().zip(&self.origins) {
+            let (width, height) = (epd.width(), epd.height());
+            let tile_stride = row_bytes(width);
+            let tile = &mut self.scratch.as_mut()[..tile_stride * height as usize];
+            for (row, line) in tile.chunks_mut(tile_stride).enumerate() {
+                let start = (y as usize + row) * stride + x as usize / 8;
+                line.copy_from_slice(&frame[start..start + tile_stride]);
+            }
+            epd.update_frame(spi, tile, delay)?;
+        }
+        Ok(())
+    }
+
+    /// Refreshes all panels at once and waits until the last one is done
+    pub fn display_frame<SPI, CS, BUSY, DC, RST, DELAY>(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), TiledError<Error<SPI::Error>>>
+    where
+        EPD: WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>,
+        SPI: Write<u8>,
+        CS: OutputPin,
+        BUSY: InputPin,
+        DC: OutputPin,
+        RST: OutputPin,
+        DELAY: DelayMs<u8>,
+    {
+        for epd in self.epds.iter_mut() {
+            epd.start_display_frame(spi, delay)?;
+        }
+        while self.epds.iter().any(|epd| !epd.is_refresh_complete()) {
+            delay.delay_ms(10);
+        }
+        for epd in self.epds.iter_mut() {
+            epd.finish_refresh(spi, delay)?;
+        }
+        Ok(())
+    }
+
+    /// Sends the parts of `frame` and refreshes all panels at once
+    pub fn update_and_display_frame<SPI, CS, BUSY, DC, RST, DELAY>(
+        &mut self,
+        spi: &mut SPI,
+        frame: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), TiledError<Error<SPI::Error>>>
+    where
+        EPD: WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>,
+        SPI: Write<u8>,
+        CS: OutputPin,
+        BUSY: InputPin,
+        DC: OutputPin,
+        RST: OutputPin,
+        DELAY: DelayMs<u8>,
+    {
+        self.update_frame(spi, frame, delay)?;
+        self.display_frame(spi, delay)
+    }
+}
+
+/// Bytes of a row of `width` pixels
+fn row_bytes(width: u32) -> usize {
+    (width as usize).div_ceil(8)
+}
+
+/// Checks that a `width` x `height` panel at `origin` lies on the wall
+fn check_tile<E>(
+    (x, y): (u32, u32),
+    width: u32,
+    height: u32,
+    wall_width: u32,
+    wall_height: u32,
+) -> Result<(), TiledError<E>> {
+    if !x.is_multiple_of(8) {
+        return Err(TiledError::Unaligned);
+    }
+    // the padding bits of the last byte may overlap the next panel, but not the row end
+    if x as usize / 8 + row_bytes(width) > row_bytes(wall_width) || y + height > wall_height {
+        return Err(TiledError::OutOfBounds);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_hal_mock::{
+        delay::MockNoop, pin::Mock as PinMock, spi::Mock as SpiMock, MockError,
+    };
+
+    /// 8x2 driver which records the last frame
+    #[derive(Default)]
+    struct FakeEpd {
+        frame: [u8; 2],
+        displayed: u32,
+    }
+
+    impl WaveshareDisplay<SpiMock, PinMock, PinMock, PinMock, PinMock, MockNoop> for FakeEpd {
+        type DisplayColor = ();
+
+        fn new(
+            _spi: &mut SpiMock,
+            _cs: PinMock,
+            _busy: PinMock,
+            _dc: PinMock,
+            _rst: PinMock,
+            _delay: &mut MockNoop,
+        ) -> Result<Self, Error<MockError>> {
+            Ok(FakeEpd::default())
+        }
+        fn sleep(
+            &mut self,
+            _spi: &mut SpiMock,
+            _delay: &mut MockNoop,
+        ) -> Result<(), Error<MockError>> {
+            Ok(())
+        }
+        fn wake_up(
+            &mut self,
+            _spi: &mut SpiMock,
+            _delay: &mut MockNoop,
+        ) -> Result<(), Error<MockError>> {
+            Ok(())
+        }
+        fn set_background_color(&mut self, _color: ()) {}
+        fn background_color(&self) -> &() {
+            &()
+        }
+        fn width(&self) -> u32 {
+            8
+        }
+        fn height(&self) -> u32 {
+            2
+        }
+        fn update_frame(
+            &mut self,
+            _spi: &mut SpiMock,
+            buffer: &[u8],
+            _delay: &mut MockNoop,
+        ) -> Result<(), Error<MockError>> {
+            self.frame.copy_from_slice(buffer);
+            Ok(())
+        }
+        fn update_partial_frame(
+            &mut self,
+            _spi: &mut SpiMock,
+            _buffer: &[u8],
+            _x: u32,
+            _y: u32,
+            _width: u32,
+            _height: u32,
+        ) -> Result<(), Error<MockError>> {
+            Ok(())
+        }
+        fn display_frame(
+            &mut self,
+            _spi: &mut SpiMock,
+            _delay: &mut MockNoop,
+        ) -> Result<(), Error<MockError>> {
+            self.displayed += 1;
+            Ok(())
+        }
+        fn update_and_display_frame(
+            &mut self,
+            spi: &mut SpiMock,
+            buffer: &[u8],
+            delay: &mut MockNoop,
+        ) -> Result<(), Error<MockError>> {
+            self.update_frame(spi, buffer, delay)?;
+            self.display_frame(spi, delay)
+        }
+        fn clear_frame(
+            &mut self,
+            _spi: &mut SpiMock,
+            _delay: &mut MockNoop,
+        ) -> Result<(), Error<MockError>> {
+            Ok(())
+        }
+        fn set_lut(
+            &mut self,
+            _spi: &mut SpiMock,
+            _refresh_rate: Option<crate::traits::RefreshLut>,
+        ) -> Result<(), Error<MockError>> {
+            Ok(())
+        }
+        fn is_busy(&self) -> bool {
+            false
+        }
+    }
+
+    fn fakes() -> [FakeEpd; 4] {
+        Default::default()
+    }
+
+    #[test]
+    fn frame_split_into_panels() {
+        let mut spi = SpiMock::new(&[]);
+        let mut delay = MockNoop::new();
+        let origins = [(0, 0), (8, 0), (0, 2), (8, 2)];
+        let mut wall = TiledDisplay::new(fakes(), origins, 16, 4, [0u8; 2]);
+
+        // one byte per panel row, two per wall row
+        let frame = [0x11, 0x21, 0x12, 0x22, 0x31, 0x41, 0x32, 0x42];
+        wall.update_and_display_frame(&mut spi, &frame, &mut delay)
+            .unwrap();
+
+        let (epds, _) = wall.release();
+        assert_eq!(epds[0].frame, [0x11, 0x12]);
+        assert_eq!(epds[1].frame, [0x21, 0x22]);
+        assert_eq!(epds[2].frame, [0x31, 0x32]);
+        assert_eq!(epds[3].frame, [0x41, 0x42]);
+        assert!(epds.iter().all(|epd| epd.displayed == 1));
+    }
+
+    #[test]
+    fn layout_checked_before_sending() {
+        let mut spi = SpiMock::new(&[]);
+        let mut delay = MockNoop::new();
+        let frame = [0xFF; 8];
+
+        let origins = [(0, 0), (4, 0), (0, 2), (8, 2)];
+        let mut wall = TiledDisplay::new(fakes(), origins, 16, 4, [0u8; 2]);
+        assert_eq!(
+            wall.update_frame(&mut spi, &frame, &mut delay),
+            Err(TiledError::Unaligned)
+        );
+
+        let origins = [(0, 0), (8, 0), (0, 2), (8, 3)];
+        let mut wall = TiledDisplay::new(fakes(), origins, 16, 4, [0u8; 2]);
+        assert_eq!(
+            wall.update_frame(&mut spi, &frame, &mut delay),
+            Err(TiledError::OutOfBounds)
+        );
+        assert_eq!(
+            wall.update_frame(&mut spi, &frame[..6], &mut delay),
+            Err(TiledError::FrameTooSmall)
+        );
+
+        let origins = [(0, 0), (8, 0), (0, 2), (8, 2)];
+        let mut wall = TiledDisplay::new(fakes(), origins, 16, 4, [0u8; 1]);
+        assert_eq!(
+            wall.update_frame(&mut spi, &frame, &mut delay),
+            Err(TiledError::ScratchTooSmall)
+        );
+        let (epds, _) = wall.release();
+        assert!(epds.iter().all(|epd| epd.frame == [0, 0]));
+    }
+}
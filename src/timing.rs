@@ -0,0 +1,186 @@
+//! Setup and hold guard delays for the CS and DC lines
+//!
+//! The drivers switch DC, assert CS and start clocking without any pause in between.
+//! That's fine for a display connected with short traces, but slow level shifters or long
+//! cables can delay the edge of DC or CS long enough for the first bit to be sampled
+//! with the wrong level.
+//!
+//! Wrap the CS and DC pins of a driver in a [`GuardedPin`] to wait a little after every
+//! level change (setup) and before it (hold). The delay is done by a closure you provide,
+//! which gets the time to wait in nanoseconds, e.g. a cycle counting busy loop.
+//!
+//!```rust, no_run
+//!# use embedded_hal_mock::*;
+//!# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
+//!use epd_waveshare::{epd4in2::*, prelude::*, timing::{GuardTiming, GuardedPin}};
+//!#
+//!# let expectations = [];
+//!# let mut spi = spi::Mock::new(&expectations);
+//!# let expectations = [];
+//!# let cs_pin = pin::Mock::new(&expectations);
+//!# let busy_in = pin::Mock::new(&expectations);
+//!# let dc = pin::Mock::new(&expectations);
+//!# let rst = pin::Mock::new(&expectations);
+//!# let mut delay = delay::MockNoop::new();
+//!# let delay_ns = |_ns: u32| {};
+//!
+//!// 200ns between CS/DC and the clock, 100ns between the last clock and CS
+//!let timing = GuardTiming::new(200, 100);
+//!let cs_pin = GuardedPin::new(cs_pin, delay_ns, timing);
+//!let dc = GuardedPin::new(dc, delay_ns, timing);
+//!
+//!let mut epd = Epd4in2::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
+//!# Ok(())
+//!# }
+//!```
+
+use embedded_hal::digital::v2::OutputPin;
+
+/// Guard times in nanoseconds around a level change of a [`GuardedPin`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GuardTiming {
+    /// Time to wait after the level changed, before the first clock
+    pub setup_ns: u32,
+    /// Time to wait before the level changes, after the last clock
+    pub hold_ns: u32,
+}
+
+impl GuardTiming {
+    /// Creates the guard times, `0` disables the respective delay
+    pub const fn new(setup_ns: u32, hold_ns: u32) -> Self {
+        GuardTiming { setup_ns, hold_ns }
+    }
+
+    /// Same guard time for setup and hold, given in µs
+    pub const fn from_us(us: u32) -> Self {
+        GuardTiming::new(us * 1000, us * 1000)
+    }
+}
+
+/// An [`OutputPin`] which waits for the configured [`GuardTiming`] around
+/// every level change.
+///
+/// Setting the pin to the level it already has doesn't wait at all, so sending
+/// several data blocks in a row only pays for the DC guard once.
+pub struct GuardedPin<P, D> {
+    pin: P,
+    delay_ns: D,
+    timing: GuardTiming,
+    level: Option<bool>,
+}
+
+impl<P, D> GuardedPin<P, D>
+where
+    P: OutputPin,
+    D: FnMut(u32),
+{
+    /// Wraps `pin`, `delay_ns` waits for the given number of nanoseconds
+    pub fn new(pin: P, delay_ns: D, timing: GuardTiming) -> Self {
+        GuardedPin {
+            pin,
+            delay_ns,
+            timing,
+            level: None,
+        }
+    }
+
+    /// The current guard times
+    pub fn timing(&self) -> GuardTiming {
+        self.timing
+    }
+
+    /// Changes the guard times
+    pub fn set_timing(&mut self, timing: GuardTiming) {
+        self.timing = timing;
+    }
+
+    /// Releases the wrapped pin and the delay
+    pub fn release(self) -> (P, D) {
+        (self.pin, self.delay_ns)
+    }
+
+    fn set_level(&mut self, high: bool) -> Result<(), P::Error> {
+        if self.level == Some(high) {
+            return Ok(());
+        }
+        if self.level.is_some() && self.timing.hold_ns > 0 {
+            (self.delay_ns)(self.timing.hold_ns);
+        }
+        if high {
+            self.pin.set_high()?;
+        } else {
+            self.pin.set_low()?;
+        }
+        self.level = Some(high);
+        if self.timing.setup_ns > 0 {
+            (self.delay_ns)(self.timing.setup_ns);
+        }
+        Ok(())
+    }
+}
+
+impl<P, D> OutputPin for GuardedPin<P, D>
+where
+    P: OutputPin,
+    D: FnMut(u32),
+{
+    type Error = P::Error;
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.set_level(false)
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.set_level(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::Cell;
+    use embedded_hal_mock::pin::{Mock, State, Transaction};
+
+    #[test]
+    fn waits_around_level_changes() {
+        let expectations = [Transaction::set(State::Low), Transaction::set(State::High)];
+        let waited = Cell::new(0u32);
+        let delay_ns = |ns: u32| waited.set(waited.get() + ns);
+        let mut pin = GuardedPin::new(
+            Mock::new(&expectations),
+            delay_ns,
+            GuardTiming::new(200, 50),
+        );
+
+        // first change: nothing to hold yet
+        pin.set_low().unwrap();
+        assert_eq!(waited.get(), 200);
+
+        // same level: no change, no delay
+        pin.set_low().unwrap();
+        assert_eq!(waited.get(), 200);
+
+        pin.set_high().unwrap();
+        assert_eq!(waited.get(), 450);
+
+        pin.release().0.done();
+    }
+
+    #[test]
+    fn disabled() {
+        let expectations = [Transaction::set(State::High)];
+        let mut pin = GuardedPin::new(
+            Mock::new(&expectations),
+            |_| panic!("no delay expected"),
+            GuardTiming::default(),
+        );
+
+        pin.set_high().unwrap();
+        pin.release().0.done();
+    }
+
+    #[test]
+    fn from_us() {
+        assert_eq!(GuardTiming::from_us(2), GuardTiming::new(2000, 2000));
+    }
+}
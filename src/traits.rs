@@ -1,6 +1,12 @@
+use crate::color::BorderColor;
+use crate::luts::{LutPreset, LutSource};
+use crate::Error;
 use core::marker::Sized;
 use embedded_hal::{
-    blocking::{delay::*, spi::Write},
+    blocking::{
+        delay::*,
+        spi::{Transfer, Write},
+    },
     digital::v2::*,
 };
 
@@ -11,21 +17,80 @@ pub(crate) trait Command {
 }
 
 /// Seperates the different LUT for the Display Refresh process
-#[derive(Debug, Clone, PartialEq, Eq, Copy)]
+#[derive(Debug, Clone, PartialEq, Eq, Copy, Default)]
 pub enum RefreshLut {
     /// The "normal" full Lookuptable for the Refresh-Sequence
+    #[default]
     Full,
     /// The quick LUT where not the full refresh sequence is followed.
     /// This might lead to some
     Quick,
+    /// A waveform given by the user, like one of the [`luts`](crate::luts) or a
+    /// `static` [`LutPreset`] of its own
+    ///
+    /// Only Epd2in9, Epd2in9 V2, Epd2in13 V2 and Epd4in2 load it, and only presets of
+    /// their own panel. The other drivers return
+    /// [`Error::Unsupported`](crate::Error::Unsupported) and keep their waveform.
+    Custom(&'static LutPreset),
+}
+
+/// How deep the SSD controllers sleep in [`sleep`](WaveshareDisplay::sleep)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DeepSleepMode {
+    /// Deep sleep mode 1, the RAM keeps the frame, e.g. as the old one of the next
+    /// quick refresh
+    #[default]
+    RetainRam = 0x01,
+    /// Deep sleep mode 2 with the lowest current, the frame has to be uploaded again
+    /// after waking up
+    DiscardRam = 0x03,
+}
+
+/// Which axes the controller scans in reverse, see [`HardwareMirror`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Mirror {
+    /// The frame as it is in the buffer
+    #[default]
+    None,
+    /// Left and right swapped, the sources are shifted the other way
+    Horizontal,
+    /// Top and bottom swapped, the gates are scanned the other way
+    Vertical,
+    /// Both axes swapped, which turns the frame by 180°
+    Both,
 }
 
-impl Default for RefreshLut {
-    fn default() -> Self {
-        RefreshLut::Full
+impl Mirror {
+    /// Whether the sources are shifted in reverse
+    pub fn horizontal(self) -> bool {
+        matches!(self, Mirror::Horizontal | Mirror::Both)
+    }
+
+    /// Whether the gates are scanned in reverse
+    pub fn vertical(self) -> bool {
+        matches!(self, Mirror::Vertical | Mirror::Both)
     }
 }
 
+/// Frame rate of the refresh, written to the PLL control register, see [`FrameRateControl`]
+///
+/// A slower frame rate draws less current and gives the ink more time to move, which
+/// clears the artifacts of refreshes in the cold. The waveforms count frames, so the
+/// refresh takes longer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameRate {
+    /// 50 Hz
+    Hz50 = 0x3C,
+    /// 100 Hz
+    Hz100 = 0x3A,
+    /// 150 Hz
+    Hz150 = 0x29,
+    /// 171 Hz
+    Hz171 = 0x31,
+    /// 200 Hz
+    Hz200 = 0x39,
+}
+
 pub(crate) trait InternalWiAdditions<SPI, CS, BUSY, DC, RST, DELAY>
 where
     SPI: Write<u8>,
@@ -45,7 +110,7 @@ where
     /// This function calls [reset](WaveshareDisplay::reset),
     /// so you don't need to call reset your self when trying to wake your device up
     /// after setting it to sleep.
-    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error>;
+    fn init(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>>;
 }
 
 /// Functions to interact with three color panels
@@ -67,19 +132,37 @@ where
         spi: &mut SPI,
         black: &[u8],
         chromatic: &[u8],
-    ) -> Result<(), SPI::Error>;
+    ) -> Result<(), Error<SPI::Error>>;
 
     /// Update only the black/white data of the display.
     ///
     /// This must be finished by calling `update_chromatic_frame`.
-    fn update_achromatic_frame(&mut self, spi: &mut SPI, black: &[u8]) -> Result<(), SPI::Error>;
+    fn update_achromatic_frame(
+        &mut self,
+        spi: &mut SPI,
+        black: &[u8],
+    ) -> Result<(), Error<SPI::Error>>;
 
     /// Update only the chromatic data of the display.
     ///
     /// This should be preceded by a call to `update_achromatic_frame`.
     /// This data takes precedence over the black/white data.
-    fn update_chromatic_frame(&mut self, spi: &mut SPI, chromatic: &[u8])
-        -> Result<(), SPI::Error>;
+    fn update_chromatic_frame(
+        &mut self,
+        spi: &mut SPI,
+        chromatic: &[u8],
+    ) -> Result<(), Error<SPI::Error>>;
+
+    /// How much longer than a frame without chromatic pixels, in percent, the refresh of
+    /// a frame of only chromatic pixels takes at most
+    ///
+    /// The chromatic particles are slower, so red-heavy frames keep the busy line low for
+    /// longer. [`RefreshTiming::chromatic_timeout_ms`](crate::calibration::RefreshTiming::chromatic_timeout_ms)
+    /// scales its timeout with it. Drivers of panels which are known to be slower or
+    /// faster override it, the default doubles the time.
+    fn chromatic_extra_percent(&self) -> u32 {
+        100
+    }
 }
 
 /// All the functions to interact with the EPDs
@@ -90,7 +173,7 @@ where
 ///
 ///```rust, no_run
 ///# use embedded_hal_mock::*;
-///# fn main() -> Result<(), MockError> {
+///# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
 ///use embedded_graphics::{
 ///    pixelcolor::BinaryColor::On as Black, prelude::*, primitives::{Line, PrimitiveStyle},
 ///};
@@ -147,19 +230,19 @@ where
         dc: DC,
         rst: RST,
         delay: &mut DELAY,
-    ) -> Result<Self, SPI::Error>
+    ) -> Result<Self, Error<SPI::Error>>
     where
         Self: Sized;
 
     /// Let the device enter deep-sleep mode to save power.
     ///
     /// The deep sleep mode returns to standby with a hardware reset.
-    fn sleep(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error>;
+    fn sleep(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>>;
 
     /// Wakes the device up from sleep
     ///
     /// Also reintialises the device if necessary.
-    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error>;
+    fn wake_up(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>>;
 
     /// Sets the backgroundcolor for various commands like [clear_frame](WaveshareDisplay::clear_frame)
     fn set_background_color(&mut self, color: Self::DisplayColor);
@@ -174,12 +257,17 @@ where
     fn height(&self) -> u32;
 
     /// Transmit a full frame to the SRAM of the EPD
+    ///
+    /// `buffer` is handed to the SPI without being copied, at most split into chunks by
+    /// the [transport](crate::transport), so it can be read straight from memory-mapped
+    /// flash. Drivers whose controller expects inverted or repacked data send it through a
+    /// small buffer on the stack instead.
     fn update_frame(
         &mut self,
         spi: &mut SPI,
         buffer: &[u8],
         delay: &mut DELAY,
-    ) -> Result<(), SPI::Error>;
+    ) -> Result<(), Error<SPI::Error>>;
 
     /// Transmits partial data to the SRAM of the EPD
     ///
@@ -194,12 +282,77 @@ where
         y: u32,
         width: u32,
         height: u32,
-    ) -> Result<(), SPI::Error>;
+    ) -> Result<(), Error<SPI::Error>>;
 
     /// Displays the frame data from SRAM
     ///
     /// This function waits until the device isn`t busy anymore
-    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error>;
+    fn display_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>>;
+
+    /// Starts displaying the frame data from SRAM and returns while the panel refreshes
+    ///
+    /// Check [`is_refresh_complete`](WaveshareDisplay::is_refresh_complete) from the main
+    /// loop and call [`finish_refresh`](WaveshareDisplay::finish_refresh) once it returns
+    /// true:
+    ///
+    ///```rust, no_run
+    ///# use embedded_hal_mock::*;
+    ///# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
+    ///use epd_waveshare::{epd2in9_v2::*, prelude::*};
+    ///#
+    ///# let expectations = [];
+    ///# let mut spi = spi::Mock::new(&expectations);
+    ///# let expectations = [];
+    ///# let cs_pin = pin::Mock::new(&expectations);
+    ///# let busy_in = pin::Mock::new(&expectations);
+    ///# let dc = pin::Mock::new(&expectations);
+    ///# let rst = pin::Mock::new(&expectations);
+    ///# let mut delay = delay::MockNoop::new();
+    ///# let frame = [0xFF; epd_waveshare::buffer_len(WIDTH as usize, HEIGHT as usize)];
+    ///# fn poll_buttons() {}
+    ///
+    ///let mut epd = Epd2in9::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
+    ///epd.update_frame(&mut spi, &frame, &mut delay)?;
+    ///epd.start_display_frame(&mut spi, &mut delay)?;
+    ///while !epd.is_refresh_complete() {
+    ///    poll_buttons();
+    ///}
+    ///epd.finish_refresh(&mut spi, &mut delay)?;
+    ///# Ok(())
+    ///# }
+    ///```
+    ///
+    /// The default refreshes like [`display_frame`](WaveshareDisplay::display_frame), for
+    /// drivers which can't split their refresh sequence. All drivers of this crate split it.
+    fn start_display_frame(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.display_frame(spi, delay)
+    }
+
+    /// Whether the refresh started with
+    /// [`start_display_frame`](WaveshareDisplay::start_display_frame) is over
+    fn is_refresh_complete(&self) -> bool {
+        !self.is_busy()
+    }
+
+    /// Ends the refresh started with
+    /// [`start_display_frame`](WaveshareDisplay::start_display_frame), e.g. by powering
+    /// the panel off or leaving partial mode
+    ///
+    /// This function waits until the device isn't busy anymore.
+    fn finish_refresh(
+        &mut self,
+        _spi: &mut SPI,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>> {
+        while self.is_busy() {
+            delay.delay_ms(10);
+        }
+        Ok(())
+    }
 
     /// Provide a combined update&display and save some time (skipping a busy check in between)
     fn update_and_display_frame(
@@ -207,12 +360,12 @@ where
         spi: &mut SPI,
         buffer: &[u8],
         delay: &mut DELAY,
-    ) -> Result<(), SPI::Error>;
+    ) -> Result<(), Error<SPI::Error>>;
 
     /// Clears the frame buffer on the EPD with the declared background color
     ///
     /// The background color can be changed with [`WaveshareDisplay::set_background_color`]
-    fn clear_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), SPI::Error>;
+    fn clear_frame(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>>;
 
     /// Trait for using various Waveforms from different LUTs
     /// E.g. for partial refreshes
@@ -222,17 +375,23 @@ where
     /// WARNING: Quick Refresh might lead to ghosting-effects/problems with your display. Especially for the 4.2in Display!
     ///
     /// If None is used the old value will be loaded on the LUTs once more
+    ///
+    /// A [`RefreshLut::Custom`] waveform returns [`Error::Unsupported`](crate::Error::Unsupported)
+    /// on the drivers without presets.
     fn set_lut(
         &mut self,
         spi: &mut SPI,
         refresh_rate: Option<RefreshLut>,
-    ) -> Result<(), SPI::Error>;
+    ) -> Result<(), Error<SPI::Error>>;
 
     /// Checks if the display is busy transmitting data
     ///
     /// This is normally handled by the more complicated commands themselves,
     /// but in the case you send data and commands directly you might need to check
     /// if the device is still busy
+    ///
+    /// To handle updates sent during a refresh the same way with every driver,
+    /// wrap it in a [BusyGuard](crate::busy::BusyGuard).
     fn is_busy(&self) -> bool;
 }
 
@@ -243,11 +402,20 @@ where
 /// buffer data marked as old, and new. This is used to determine which pixels need to change,
 /// and how they will change. This isn't required when using full refreshes.
 ///
+/// On the SSD168x controllers the old frame is written to RAM bank 0x26 and the new one to
+/// 0x24. Write the old frame again after `wake_up` if the controller was reset, otherwise
+/// the first quick refresh compares against an undefined frame.
+///
+/// On the UC81xx controllers of the 4.2" and the 7.5" V2 the old frame goes to the first
+/// data transmission and the new one to the second. The partial frames put the controller
+/// into partial mode, so the next `display_new_frame` only refreshes their window, which
+/// has to start at a multiple of 8 and be a multiple of 8 wide.
+///
 /// (todo: Example ommitted due to CI failures.)
 /// Example:
 ///```rust, no_run
 ///# use embedded_hal_mock::*;
-///# fn main() -> Result<(), MockError> {
+///# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
 ///# use embedded_graphics::{
 ///#   pixelcolor::BinaryColor::On as Black, prelude::*, primitives::{Line, PrimitiveStyle},
 ///# };
@@ -265,7 +433,7 @@ where
 ///#
 ///# // Setup EPD
 ///# let mut epd = Epd4in2::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
-///let (x, y, frame_width, frame_height) = (20, 40, 80,80);
+///let (x, y, frame_width, frame_height) = (16, 40, 80, 80);
 ///
 ///let mut buffer = [DEFAULT_BACKGROUND_COLOR.get_byte_value(); 80 / 8 * 80];
 ///let mut display = VarDisplay::new(frame_width, frame_height, &mut buffer);
@@ -296,7 +464,7 @@ where
         spi: &mut SPI,
         buffer: &[u8],
         delay: &mut DELAY,
-    ) -> Result<(), SPI::Error>;
+    ) -> Result<(), Error<SPI::Error>>;
 
     /// Updates the new frame.
     fn update_new_frame(
@@ -304,10 +472,14 @@ where
         spi: &mut SPI,
         buffer: &[u8],
         delay: &mut DELAY,
-    ) -> Result<(), SPI::Error>;
+    ) -> Result<(), Error<SPI::Error>>;
 
     /// Displays the new frame
-    fn display_new_frame(&mut self, spi: &mut SPI, _delay: &mut DELAY) -> Result<(), SPI::Error>;
+    fn display_new_frame(
+        &mut self,
+        spi: &mut SPI,
+        _delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>>;
 
     /// Updates and displays the new frame.
     fn update_and_display_new_frame(
@@ -315,7 +487,7 @@ where
         spi: &mut SPI,
         buffer: &[u8],
         delay: &mut DELAY,
-    ) -> Result<(), SPI::Error>;
+    ) -> Result<(), Error<SPI::Error>>;
 
     /// Updates the old frame for a portion of the display.
     fn update_partial_old_frame(
@@ -326,7 +498,7 @@ where
         y: u32,
         width: u32,
         height: u32,
-    ) -> Result<(), SPI::Error>;
+    ) -> Result<(), Error<SPI::Error>>;
 
     /// Updates the new frame for a portion of the display.
     fn update_partial_new_frame(
@@ -337,7 +509,7 @@ where
         y: u32,
         width: u32,
         height: u32,
-    ) -> Result<(), SPI::Error>;
+    ) -> Result<(), Error<SPI::Error>>;
 
     /// Clears the partial frame buffer on the EPD with the declared background color
     /// The background color can be changed with [`WaveshareDisplay::set_background_color`]
@@ -348,5 +520,815 @@ where
         y: u32,
         width: u32,
         height: u32,
-    ) -> Result<(), SPI::Error>;
+    ) -> Result<(), Error<SPI::Error>>;
+}
+
+/// Functions for controllers with two frame RAM banks
+///
+/// The SSD168x controllers hold the current and the previous frame in two RAM banks
+/// for their partial refreshes. With the RAM ping-pong option these banks swap their
+/// roles after every refresh: one bank is shown, while the other one (the back bank)
+/// receives the next frame. This allows tear free updates, and switching between two
+/// preloaded frames without transmitting them again.
+///
+/// A full `update_frame` with `RefreshLut::Full` writes the frame into both banks.
+///
+/// Example:
+///```rust, no_run
+///# use embedded_hal_mock::*;
+///# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
+///# use epd_waveshare::{epd1in54_v2::*, prelude::*};
+///#
+///# let expectations = [];
+///# let mut spi = spi::Mock::new(&expectations);
+///# let expectations = [];
+///# let cs_pin = pin::Mock::new(&expectations);
+///# let busy_in = pin::Mock::new(&expectations);
+///# let dc = pin::Mock::new(&expectations);
+///# let rst = pin::Mock::new(&expectations);
+///# let mut delay = delay::MockNoop::new();
+///#
+///# let mut epd = Epd1in54::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
+///# let first_screen = [0x00; epd_waveshare::buffer_len(WIDTH as usize, HEIGHT as usize)];
+///# let second_screen = [0xFF; epd_waveshare::buffer_len(WIDTH as usize, HEIGHT as usize)];
+///epd.update_and_display_frame(&mut spi, &first_screen, &mut delay)?;
+///epd.update_back_bank(&mut spi, &second_screen, &mut delay)?;
+///
+///// toggles between both screens
+///for _ in 0..10 {
+///    epd.swap_banks(&mut spi, &mut delay)?;
+///}
+///# Ok(())
+///# }
+///```
+pub trait RamBanks<SPI, CS, BUSY, DC, RST, DELAY>:
+    WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    /// Transmits a full frame into the bank which isn't shown right now
+    fn update_back_bank(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        delay: &mut DELAY,
+    ) -> Result<(), Error<SPI::Error>>;
+
+    /// Transmits partial data into the bank which isn't shown right now
+    ///
+    /// (x,y) is the top left corner
+    fn update_partial_back_bank(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>>;
+
+    /// Displays the back bank, the bank shown before becomes the new back bank
+    ///
+    /// This function waits until the device isn`t busy anymore
+    fn swap_banks(&mut self, spi: &mut SPI, delay: &mut DELAY) -> Result<(), Error<SPI::Error>>;
+}
+
+/// Position of the visible area of the glass in the RAM of the controller
+///
+/// The controllers drive more source and gate lines than some of the small panels
+/// have, and the glass isn't always connected to the first of them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PanelOffset {
+    /// First source line (RAM column) of the visible area, a multiple of 8
+    pub source: u32,
+    /// First gate line (RAM row) of the visible area
+    pub gate: u32,
+}
+
+impl PanelOffset {
+    /// Creates the offset, `source` has to be a multiple of 8
+    ///
+    /// With the `panic_free` feature other values are rounded down instead.
+    pub const fn new(source: u32, gate: u32) -> Self {
+        #[cfg(not(feature = "panic_free"))]
+        assert!(
+            source.is_multiple_of(8),
+            "source offset must be a multiple of 8"
+        );
+        PanelOffset {
+            source: source & !7,
+            gate,
+        }
+    }
+}
+
+/// Displays whose frames can be moved within the RAM of the controller
+///
+/// Used for panels with the glass offset against the controller RAM, so the
+/// graphics buffers stay the size of the visible area:
+///
+///```rust, no_run
+///# use embedded_hal_mock::*;
+///# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
+///# use epd_waveshare::{epd1in54::*, prelude::*};
+///#
+///# let expectations = [];
+///# let mut spi = spi::Mock::new(&expectations);
+///# let expectations = [];
+///# let cs_pin = pin::Mock::new(&expectations);
+///# let busy_in = pin::Mock::new(&expectations);
+///# let dc = pin::Mock::new(&expectations);
+///# let rst = pin::Mock::new(&expectations);
+///# let mut delay = delay::MockNoop::new();
+///#
+///let mut epd = Epd1in54::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
+///// this glass starts at the 9th source and the 3rd gate line
+///epd.set_panel_offset(PanelOffset::new(8, 2));
+///# let frame = [0xFF; epd_waveshare::buffer_len(WIDTH as usize, HEIGHT as usize)];
+///epd.update_and_display_frame(&mut spi, &frame, &mut delay)?;
+///# Ok(())
+///# }
+///```
+pub trait GlassOffset<SPI, CS, BUSY, DC, RST, DELAY>:
+    WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    /// Sets where the visible area starts in the RAM, used from the next frame on
+    fn set_panel_offset(&mut self, offset: PanelOffset);
+
+    /// The current offset, none by default
+    fn panel_offset(&self) -> PanelOffset;
+}
+
+/// Displays whose controller can report the temperature of its sensor
+///
+/// The waveforms are made for a temperature band, a quick or gray waveform written into
+/// the registers only looks right close to the temperature it was made for. The reading
+/// can pick a preset from [`luts`](crate::luts) or decide whether a quick refresh is
+/// worth it.
+///
+/// Reading needs the data line of the controller on MISO, which the Waveshare HATs don't
+/// connect, and a SPI implementing [`Transfer`] besides [`Write`]:
+///
+///```rust, no_run
+///# use embedded_hal_mock::*;
+///# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
+///use epd_waveshare::{epd2in9_v2::*, luts, prelude::*};
+///#
+///# let expectations = [];
+///# let mut spi = spi::Mock::new(&expectations);
+///# let expectations = [];
+///# let cs_pin = pin::Mock::new(&expectations);
+///# let busy_in = pin::Mock::new(&expectations);
+///# let dc = pin::Mock::new(&expectations);
+///# let rst = pin::Mock::new(&expectations);
+///# let mut delay = delay::MockNoop::new();
+///
+///let mut epd = Epd2in9::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
+///let temperature_c = epd.read_temperature(&mut spi)?;
+///if luts::select(luts::Panel::Epd2in9V2, luts::LutKind::Partial, temperature_c).is_none() {
+///    // too hot or too cold for the partial waveform
+///    epd.set_lut(&mut spi, Some(RefreshLut::Full))?;
+///}
+///# Ok(())
+///# }
+///```
+pub trait ReadTemperature<SPI, CS, BUSY, DC, RST, DELAY>:
+    WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8> + Transfer<u8, Error = <SPI as Write<u8>>::Error>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    /// Measures the temperature, in degrees Celsius rounded down
+    ///
+    /// The display has to be awake. This function waits until the device isn't busy
+    /// anymore.
+    fn read_temperature(&mut self, spi: &mut SPI) -> Result<i8, Error<<SPI as Write<u8>>::Error>>;
+}
+
+/// Silicon and OTP revision reported by the controller, see [`ReadRevision`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Revision {
+    /// ID of the chip
+    pub chip_id: u8,
+    /// Version of the waveform programmed into the OTP
+    pub lut_version: [u8; 4],
+}
+
+/// Displays whose controller can report its revision
+///
+/// Applications can log which silicon they drive and pick workarounds for it. Like
+/// [`ReadTemperature`], this needs the data line of the controller on MISO and a SPI
+/// implementing [`Transfer`].
+pub trait ReadRevision<SPI, CS, BUSY, DC, RST, DELAY>:
+    WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8> + Transfer<u8, Error = <SPI as Write<u8>>::Error>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    /// Reads the chip ID and the waveform version
+    ///
+    /// The display has to be awake. This function waits until the device isn't busy
+    /// anymore.
+    fn read_revision(
+        &mut self,
+        spi: &mut SPI,
+    ) -> Result<Revision, Error<<SPI as Write<u8>>::Error>>;
+}
+
+/// Displays whose waveform can be chosen for a temperature measured elsewhere
+///
+/// The controllers pick the waveform of a full refresh for the temperature of their
+/// sensor. If it reads wrong, e.g. because it sits next to something warm, the temperature
+/// can be measured elsewhere and written into the controller before the refresh:
+///
+///```rust, no_run
+///# use embedded_hal_mock::*;
+///# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
+///use epd_waveshare::{epd7in5_v2::*, prelude::*};
+///#
+///# let expectations = [];
+///# let mut spi = spi::Mock::new(&expectations);
+///# let expectations = [];
+///# let cs_pin = pin::Mock::new(&expectations);
+///# let busy_in = pin::Mock::new(&expectations);
+///# let dc = pin::Mock::new(&expectations);
+///# let rst = pin::Mock::new(&expectations);
+///# let mut delay = delay::MockNoop::new();
+///# let frame = [0xFF; epd_waveshare::buffer_len(WIDTH as usize, HEIGHT as usize)];
+///
+///let mut epd = Epd7in5::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
+///// measured by a sensor away from the board
+///epd.set_temperature(&mut spi, Some(4))?;
+///epd.update_and_display_frame(&mut spi, &frame, &mut delay)?;
+///// back to the on-glass sensor
+///epd.set_temperature(&mut spi, None)?;
+///# Ok(())
+///# }
+///```
+pub trait TemperatureOverride<SPI, CS, BUSY, DC, RST, DELAY>:
+    WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    /// Uses the waveform for `celsius` from now on, `None` goes back to the sensor
+    ///
+    /// The override is kept across [`wake_up`](WaveshareDisplay::wake_up).
+    fn set_temperature(
+        &mut self,
+        spi: &mut SPI,
+        celsius: Option<i8>,
+    ) -> Result<(), Error<SPI::Error>>;
+
+    /// The temperature set with [`set_temperature`](TemperatureOverride::set_temperature)
+    fn temperature_override(&self) -> Option<i8>;
+}
+
+/// Displays whose VCOM voltage can be adjusted
+///
+/// VCOM is the voltage of the common electrode, and every batch of panels has its own
+/// best value for the contrast. If it's printed on the flex cable, e.g. `-1.50V`, pass
+/// it in mV without the sign:
+///
+///```rust, no_run
+///# use embedded_hal_mock::*;
+///# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
+///use epd_waveshare::{epd4in2::*, prelude::*};
+///#
+///# let expectations = [];
+///# let mut spi = spi::Mock::new(&expectations);
+///# let expectations = [];
+///# let cs_pin = pin::Mock::new(&expectations);
+///# let busy_in = pin::Mock::new(&expectations);
+///# let dc = pin::Mock::new(&expectations);
+///# let rst = pin::Mock::new(&expectations);
+///# let mut delay = delay::MockNoop::new();
+///
+///let mut epd = Epd4in2::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
+///epd.set_vcom(&mut spi, 1500)?;
+///# Ok(())
+///# }
+///```
+pub trait VcomSetting<SPI, CS, BUSY, DC, RST, DELAY>:
+    WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    /// Sets VCOM to minus `millivolts`, kept across [`wake_up`](WaveshareDisplay::wake_up)
+    ///
+    /// The controllers of the small panels take 100mV to 3000mV in steps of 50mV, values in
+    /// between are rounded to the nearest step.
+    fn set_vcom(&mut self, spi: &mut SPI, millivolts: u16) -> Result<(), Error<SPI::Error>>;
+
+    /// The VCOM in mV, without the sign
+    fn vcom(&self) -> u16;
+}
+
+/// Displays whose border can be set independent of the frame
+///
+/// The border around the active area is driven like a pixel by most controllers. A
+/// floating border keeps its color, e.g. to blend into a case, while white or black
+/// borders are refreshed with every frame:
+///
+///```rust, no_run
+///# use embedded_hal_mock::*;
+///# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
+///use epd_waveshare::{color::BorderColor, epd4in2::*, prelude::*};
+///#
+///# let expectations = [];
+///# let mut spi = spi::Mock::new(&expectations);
+///# let expectations = [];
+///# let cs_pin = pin::Mock::new(&expectations);
+///# let busy_in = pin::Mock::new(&expectations);
+///# let dc = pin::Mock::new(&expectations);
+///# let rst = pin::Mock::new(&expectations);
+///# let mut delay = delay::MockNoop::new();
+///
+///let mut epd = Epd4in2::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
+///epd.set_border(&mut spi, BorderColor::Black)?;
+///# Ok(())
+///# }
+///```
+pub trait BorderControl<SPI, CS, BUSY, DC, RST, DELAY>:
+    WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    /// Sets the border for the next refreshes, kept across
+    /// [`wake_up`](WaveshareDisplay::wake_up)
+    fn set_border(&mut self, spi: &mut SPI, border: BorderColor) -> Result<(), Error<SPI::Error>>;
+}
+
+/// Displays which can refresh with the waveform of the OTP or with the LUT registers
+///
+/// Fast refreshes need a waveform in the registers, but the one in the OTP memory gives the
+/// best image quality, e.g. for a daily full refresh against ghosting:
+///
+///```rust, no_run
+///# use embedded_hal_mock::*;
+///# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
+///use epd_waveshare::{epd4in2::*, luts, prelude::*};
+///#
+///# let expectations = [];
+///# let mut spi = spi::Mock::new(&expectations);
+///# let expectations = [];
+///# let cs_pin = pin::Mock::new(&expectations);
+///# let busy_in = pin::Mock::new(&expectations);
+///# let dc = pin::Mock::new(&expectations);
+///# let rst = pin::Mock::new(&expectations);
+///# let mut delay = delay::MockNoop::new();
+///# let frame = [0xFF; epd_waveshare::buffer_len(WIDTH as usize, HEIGHT as usize)];
+///
+///let mut epd = Epd4in2::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
+///epd.use_register_lut(&mut spi, luts::by_name("4in2/fast").unwrap())?;
+///epd.update_and_display_frame(&mut spi, &frame, &mut delay)?;
+///
+///epd.use_otp_lut(&mut spi)?;
+///epd.update_and_display_frame(&mut spi, &frame, &mut delay)?;
+///# Ok(())
+///# }
+///```
+pub trait LutSelection<SPI, CS, BUSY, DC, RST, DELAY>:
+    WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    /// Where the waveform of the next full refreshes comes from
+    fn lut_source(&self) -> LutSource;
+
+    /// Refreshes with the waveform of the OTP, kept across
+    /// [`wake_up`](WaveshareDisplay::wake_up)
+    fn use_otp_lut(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>>;
+
+    /// Refreshes with the waveform of `preset` in the registers, kept across
+    /// [`wake_up`](WaveshareDisplay::wake_up)
+    ///
+    /// Panics if the preset is made for another panel.
+    fn use_register_lut(
+        &mut self,
+        spi: &mut SPI,
+        preset: &'static LutPreset,
+    ) -> Result<(), Error<SPI::Error>>;
+}
+
+/// Displays which can refresh with a custom waveform
+///
+/// The waveform is given in the format of the controller family, like
+/// [`luts::ssd1680::Lut`](crate::luts::ssd1680::Lut), with named phases and timings:
+///
+///```rust, no_run
+///# use embedded_hal_mock::*;
+///# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
+///use epd_waveshare::{epd4in2::*, luts::uc81xx::Lut, prelude::*};
+///#
+///# let expectations = [];
+///# let mut spi = spi::Mock::new(&expectations);
+///# let expectations = [];
+///# let cs_pin = pin::Mock::new(&expectations);
+///# let busy_in = pin::Mock::new(&expectations);
+///# let dc = pin::Mock::new(&expectations);
+///# let rst = pin::Mock::new(&expectations);
+///# let mut delay = delay::MockNoop::new();
+///# let frame = [0xFF; epd_waveshare::buffer_len(WIDTH as usize, HEIGHT as usize)];
+///
+///let mut epd = Epd4in2::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
+///
+///let mut lut = Lut::default();
+///// shorten the first group of the black to white transition
+///lut.bw.groups[0].frames[0] = 10;
+///lut.bw.groups[0].repeat = 1;
+///epd.load_lut(&mut spi, &lut)?;
+///epd.update_and_display_frame(&mut spi, &frame, &mut delay)?;
+///# Ok(())
+///# }
+///```
+pub trait CustomLut<SPI, CS, BUSY, DC, RST, DELAY>:
+    WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    /// Waveform format of the controller
+    type Lut;
+
+    /// Writes `lut` into the registers and refreshes with it, until the driver loads
+    /// another waveform, e.g. in [`set_lut`](WaveshareDisplay::set_lut) or
+    /// [`wake_up`](WaveshareDisplay::wake_up)
+    fn load_lut(&mut self, spi: &mut SPI, lut: &Self::Lut) -> Result<(), Error<SPI::Error>>;
+}
+
+/// Displays which can keep or discard their RAM in deep sleep
+pub trait SleepMode<SPI, CS, BUSY, DC, RST, DELAY>:
+    WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    /// Chooses the mode of the next [`sleep`](WaveshareDisplay::sleep)
+    fn set_sleep_mode(&mut self, mode: DeepSleepMode);
+
+    /// The mode of [`sleep`](WaveshareDisplay::sleep)
+    fn sleep_mode(&self) -> DeepSleepMode;
+}
+
+/// Quick refresh displays which clear the ghosting with a full refresh now and then
+///
+/// Every quick refresh leaves some traces of the old frame behind. With an interval set,
+/// [`update_and_display_new_frame`](QuickRefresh::update_and_display_new_frame) refreshes
+/// with the full waveform after that many quick refreshes, and the counting starts again.
+/// A full [`display_frame`](WaveshareDisplay::display_frame) restarts it as well:
+///
+///```rust, no_run
+///# use embedded_hal_mock::*;
+///# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
+///use epd_waveshare::{epd2in9_v2::*, prelude::*};
+///#
+///# let expectations = [];
+///# let mut spi = spi::Mock::new(&expectations);
+///# let expectations = [];
+///# let cs_pin = pin::Mock::new(&expectations);
+///# let busy_in = pin::Mock::new(&expectations);
+///# let dc = pin::Mock::new(&expectations);
+///# let rst = pin::Mock::new(&expectations);
+///# let mut delay = delay::MockNoop::new();
+///# let frame = [0xFF; epd_waveshare::buffer_len(WIDTH as usize, HEIGHT as usize)];
+///
+///let mut epd = Epd2in9::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
+///epd.set_full_refresh_every(5);
+///
+///// the 6th and 12th refresh are full ones
+///for _ in 0..12 {
+///    epd.update_and_display_new_frame(&mut spi, &frame, &mut delay)?;
+///}
+///# Ok(())
+///# }
+///```
+pub trait FullRefreshInterval<SPI, CS, BUSY, DC, RST, DELAY>:
+    QuickRefresh<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    /// Refreshes with the full waveform after `updates` quick refreshes, `0` (the
+    /// default) never does
+    fn set_full_refresh_every(&mut self, updates: u16);
+
+    /// Number of quick refreshes since the last full one
+    fn quick_refreshes(&self) -> u16;
+}
+
+/// Displays which can mirror the frame in the controller
+///
+/// The controller scans the gates or shifts the sources the other way, so a panel
+/// mounted upside down shows the buffer as it is, without rotating it in software:
+///
+///```rust, no_run
+///# use embedded_hal_mock::*;
+///# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
+///use epd_waveshare::{epd4in2::*, prelude::*};
+///#
+///# let expectations = [];
+///# let mut spi = spi::Mock::new(&expectations);
+///# let expectations = [];
+///# let cs_pin = pin::Mock::new(&expectations);
+///# let busy_in = pin::Mock::new(&expectations);
+///# let dc = pin::Mock::new(&expectations);
+///# let rst = pin::Mock::new(&expectations);
+///# let mut delay = delay::MockNoop::new();
+///# let frame = [0xFF; epd_waveshare::buffer_len(WIDTH as usize, HEIGHT as usize)];
+///
+///let mut epd = Epd4in2::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
+///epd.set_mirror(&mut spi, Mirror::Both)?;
+///epd.update_and_display_frame(&mut spi, &frame, &mut delay)?;
+///# Ok(())
+///# }
+///```
+///
+/// The SSD controllers only reverse the gates and support [`Mirror::Vertical`] alone.
+pub trait HardwareMirror<SPI, CS, BUSY, DC, RST, DELAY>:
+    WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    /// Mirrors the next refreshes, and keeps doing so after [`wake_up`](WaveshareDisplay::wake_up)
+    fn set_mirror(&mut self, spi: &mut SPI, mirror: Mirror) -> Result<(), Error<SPI::Error>>;
+
+    /// The current mirroring, none by default
+    fn mirror(&self) -> Mirror;
+}
+
+/// UC81xx displays which can change the frame rate of the refresh
+///
+///```rust, no_run
+///# use embedded_hal_mock::*;
+///# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
+///use epd_waveshare::{epd4in2::*, prelude::*};
+///#
+///# let expectations = [];
+///# let mut spi = spi::Mock::new(&expectations);
+///# let expectations = [];
+///# let cs_pin = pin::Mock::new(&expectations);
+///# let busy_in = pin::Mock::new(&expectations);
+///# let dc = pin::Mock::new(&expectations);
+///# let rst = pin::Mock::new(&expectations);
+///# let mut delay = delay::MockNoop::new();
+///# let frame = [0xFF; epd_waveshare::buffer_len(WIDTH as usize, HEIGHT as usize)];
+///
+///let mut epd = Epd4in2::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
+///// slower and cleaner below 10 °C
+///epd.set_frame_rate(&mut spi, FrameRate::Hz50)?;
+///epd.update_and_display_frame(&mut spi, &frame, &mut delay)?;
+///# Ok(())
+///# }
+///```
+pub trait FrameRateControl<SPI, CS, BUSY, DC, RST, DELAY>:
+    WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    /// Sets the frame rate of the next refreshes, and keeps it after
+    /// [`wake_up`](WaveshareDisplay::wake_up)
+    fn set_frame_rate(&mut self, spi: &mut SPI, rate: FrameRate) -> Result<(), Error<SPI::Error>>;
+
+    /// The current frame rate, the one of the vendor driver by default
+    fn frame_rate(&self) -> FrameRate;
+}
+
+/// Displays which can switch their drivers off between refreshes
+///
+/// After a refresh the controller keeps the source and gate drivers and the booster
+/// running, which draws current until the next one. [`power_down`](PowerDown::power_down)
+/// floats the border and switches them off without the reset and init of
+/// [`sleep`](WaveshareDisplay::sleep), the next refresh powers them on again:
+///
+///```rust, no_run
+///# use embedded_hal_mock::*;
+///# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
+///use epd_waveshare::{epd4in2::*, prelude::*};
+///#
+///# let expectations = [];
+///# let mut spi = spi::Mock::new(&expectations);
+///# let expectations = [];
+///# let cs_pin = pin::Mock::new(&expectations);
+///# let busy_in = pin::Mock::new(&expectations);
+///# let dc = pin::Mock::new(&expectations);
+///# let rst = pin::Mock::new(&expectations);
+///# let mut delay = delay::MockNoop::new();
+///# let frame = [0xFF; epd_waveshare::buffer_len(WIDTH as usize, HEIGHT as usize)];
+///
+///let mut epd = Epd4in2::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
+///epd.update_and_display_frame(&mut spi, &frame, &mut delay)?;
+///epd.power_down(&mut spi)?;
+///// the frame stays in RAM, a quick refresh can follow right away
+///# Ok(())
+///# }
+///```
+///
+/// Only the 4.2", the 5.83" V2 and the 7.5" V2 displays implement it so far, the other
+/// UC81xx displays keep their drivers on until [`sleep`](WaveshareDisplay::sleep). The SSD
+/// controllers switch the analog part off at the end of every update sequence and don't
+/// need it.
+pub trait PowerDown<SPI, CS, BUSY, DC, RST, DELAY>:
+    WaveshareDisplay<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    /// Waits for the refresh, floats the border and switches the drivers off
+    fn power_down(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>>;
+
+    /// Whether the drivers are off until the next refresh
+    fn is_powered_down(&self) -> bool;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::Cell;
+    use embedded_hal_mock::{
+        delay::MockNoop, pin::Mock as PinMock, spi::Mock as SpiMock, MockError,
+    };
+
+    /// Driver without a split refresh sequence, which counts the refreshes
+    struct FakeEpd<'a> {
+        busy: &'a Cell<bool>,
+        displayed: u32,
+    }
+
+    impl WaveshareDisplay<SpiMock, PinMock, PinMock, PinMock, PinMock, MockNoop> for FakeEpd<'_> {
+        type DisplayColor = ();
+
+        fn new(
+            _spi: &mut SpiMock,
+            _cs: PinMock,
+            _busy: PinMock,
+            _dc: PinMock,
+            _rst: PinMock,
+            _delay: &mut MockNoop,
+        ) -> Result<Self, Error<MockError>> {
+            unreachable!()
+        }
+        fn sleep(
+            &mut self,
+            _spi: &mut SpiMock,
+            _delay: &mut MockNoop,
+        ) -> Result<(), Error<MockError>> {
+            Ok(())
+        }
+        fn wake_up(
+            &mut self,
+            _spi: &mut SpiMock,
+            _delay: &mut MockNoop,
+        ) -> Result<(), Error<MockError>> {
+            Ok(())
+        }
+        fn set_background_color(&mut self, _color: ()) {}
+        fn background_color(&self) -> &() {
+            &()
+        }
+        fn width(&self) -> u32 {
+            8
+        }
+        fn height(&self) -> u32 {
+            1
+        }
+        fn update_frame(
+            &mut self,
+            _spi: &mut SpiMock,
+            _buffer: &[u8],
+            _delay: &mut MockNoop,
+        ) -> Result<(), Error<MockError>> {
+            Ok(())
+        }
+        fn update_partial_frame(
+            &mut self,
+            _spi: &mut SpiMock,
+            _buffer: &[u8],
+            _x: u32,
+            _y: u32,
+            _width: u32,
+            _height: u32,
+        ) -> Result<(), Error<MockError>> {
+            Ok(())
+        }
+        fn display_frame(
+            &mut self,
+            _spi: &mut SpiMock,
+            _delay: &mut MockNoop,
+        ) -> Result<(), Error<MockError>> {
+            self.displayed += 1;
+            Ok(())
+        }
+        fn update_and_display_frame(
+            &mut self,
+            spi: &mut SpiMock,
+            buffer: &[u8],
+            delay: &mut MockNoop,
+        ) -> Result<(), Error<MockError>> {
+            self.update_frame(spi, buffer, delay)?;
+            self.display_frame(spi, delay)
+        }
+        fn clear_frame(
+            &mut self,
+            _spi: &mut SpiMock,
+            _delay: &mut MockNoop,
+        ) -> Result<(), Error<MockError>> {
+            Ok(())
+        }
+        fn set_lut(
+            &mut self,
+            _spi: &mut SpiMock,
+            _refresh_rate: Option<RefreshLut>,
+        ) -> Result<(), Error<MockError>> {
+            Ok(())
+        }
+        fn is_busy(&self) -> bool {
+            self.busy.get()
+        }
+    }
+
+    #[test]
+    fn staged_refresh_defaults() {
+        let mut spi = SpiMock::new(&[]);
+        let mut delay = MockNoop::new();
+        let busy = Cell::new(false);
+        let mut epd = FakeEpd {
+            busy: &busy,
+            displayed: 0,
+        };
+
+        // without a split sequence the driver refreshes like display_frame
+        epd.start_display_frame(&mut spi, &mut delay).unwrap();
+        assert_eq!(epd.displayed, 1);
+        busy.set(true);
+        assert!(!epd.is_refresh_complete());
+        busy.set(false);
+        assert!(epd.is_refresh_complete());
+        epd.finish_refresh(&mut spi, &mut delay).unwrap();
+    }
 }
@@ -0,0 +1,489 @@
+//! Platform specific constraints of SPI transfers
+//!
+//! Not every SPI peripheral can send a whole frame in one transfer: Linux' spidev
+//! limits a single transfer to 4096 bytes by default, some DMA engines have a maximum
+//! length or want to get the buffer handed over instead of being fed byte by byte.
+//!
+//! A [`TransportStrategy`] describes how a block of bytes is put on the wire. Wrap the
+//! SPI peripheral in a [`Transport`] with the strategy fitting your platform and pass it
+//! to the driver like the bare SPI:
+//!
+//!```rust, no_run
+//!# use embedded_hal_mock::*;
+//!# fn main() -> Result<(), epd_waveshare::Error<MockError>> {
+//!use epd_waveshare::{epd4in2::*, prelude::*, transport::{Chunked, Transport}};
+//!#
+//!# let expectations = [];
+//!# let spi = spi::Mock::new(&expectations);
+//!# let expectations = [];
+//!# let cs_pin = pin::Mock::new(&expectations);
+//!# let busy_in = pin::Mock::new(&expectations);
+//!# let dc = pin::Mock::new(&expectations);
+//!# let rst = pin::Mock::new(&expectations);
+//!# let mut delay = delay::MockNoop::new();
+//!
+//!// The DMA of this MCU can't transfer more than 1024 bytes at once
+//!let mut spi = Transport::new(spi, Chunked::new(1024));
+//!let mut epd = Epd4in2::new(&mut spi, cs_pin, busy_in, dc, rst, &mut delay)?;
+//!# Ok(())
+//!# }
+//!```
+//!
+//! With [`Yielding`] a hook runs between the chunks, so a large frame doesn't keep
+//! other tasks waiting until its last byte is out.
+//!
+//! Without a [`Transport`] the drivers send every block with the [`DefaultStrategy`]:
+//! in chunks of [`SPIDEV_MAX_TRANSFER`] bytes on Linux, in a single transfer everywhere
+//! else.
+//!
+//! # Frames in memory-mapped flash
+//!
+//! Splash screens and other static frames don't need to be copied into RAM:
+//! [`update_frame`](crate::traits::WaveshareDisplay::update_frame) hands the buffer to
+//! the strategy as it is, and all strategies pass on slices of it. A frame in
+//! memory-mapped (XIP) external flash is therefore read straight from the flash while
+//! it is sent. Every read may stall the bus until the flash answers, so on MCUs where
+//! such a stall blocks DMA or interrupts for too long, pick a [`Chunked`] or
+//! [`Yielding`] size the flash can deliver in one go.
+//!
+//! The drivers whose controller expects the bits inverted or repacked go through a
+//! 64 byte buffer on the stack instead. [`ZeroCopyCheck`] counts how many bytes were
+//! sent straight from the frame, to make sure a driver (or a refactor of it) doesn't
+//! copy the frame.
+
+use core::ops::Range;
+
+use embedded_hal::blocking::spi::Write;
+
+/// Maximum size of a single transfer with Linux' spidev in its default configuration
+///
+/// See <https://raspberrypi.stackexchange.com/questions/65595/spi-transfer-fails-with-buffer-size-greater-than-4096>
+pub const SPIDEV_MAX_TRANSFER: usize = 4096;
+
+/// The strategy of the drivers when the SPI peripheral isn't wrapped in a [`Transport`]
+///
+/// On Linux this is [`Chunked`] with [`SPIDEV_MAX_TRANSFER`], so the frames fit through
+/// spidev in its default configuration, everywhere else [`SingleShot`].
+#[cfg(target_os = "linux")]
+pub type DefaultStrategy = Chunked;
+/// The strategy of the drivers when the SPI peripheral isn't wrapped in a [`Transport`]
+///
+/// On Linux this is [`Chunked`] with [`SPIDEV_MAX_TRANSFER`], so the frames fit through
+/// spidev in its default configuration, everywhere else [`SingleShot`].
+#[cfg(not(target_os = "linux"))]
+pub type DefaultStrategy = SingleShot;
+
+/// How a block of bytes is transferred over the SPI peripheral
+pub trait TransportStrategy<SPI: Write<u8>> {
+    /// Transfers all of `data`, CS is already asserted by the driver
+    fn transfer(&mut self, spi: &mut SPI, data: &[u8]) -> Result<(), SPI::Error>;
+}
+
+/// Sends every block in a single transfer
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SingleShot;
+
+impl<SPI: Write<u8>> TransportStrategy<SPI> for SingleShot {
+    fn transfer(&mut self, spi: &mut SPI, data: &[u8]) -> Result<(), SPI::Error> {
+        spi.write(data)
+    }
+}
+
+/// Splits every block into transfers of at most `size` bytes
+#[derive(Debug, Clone, Copy)]
+pub struct Chunked {
+    size: usize,
+}
+
+impl Chunked {
+    /// Creates the strategy, `size` must not be 0
+    ///
+    /// With the `panic_free` feature a size of 0 sends every byte on its own.
+    pub const fn new(size: usize) -> Self {
+        #[cfg(not(feature = "panic_free"))]
+        assert!(size > 0, "chunk size must not be 0");
+        let size = if size == 0 { 1 } else { size };
+        Chunked { size }
+    }
+
+    /// Maximum number of bytes per transfer
+    pub fn size(&self) -> usize {
+        self.size
+    }
+}
+
+impl Default for Chunked {
+    /// Chunks of [`SPIDEV_MAX_TRANSFER`] bytes
+    fn default() -> Self {
+        Chunked::new(SPIDEV_MAX_TRANSFER)
+    }
+}
+
+impl<SPI: Write<u8>> TransportStrategy<SPI> for Chunked {
+    fn transfer(&mut self, spi: &mut SPI, data: &[u8]) -> Result<(), SPI::Error> {
+        if data.len() <= self.size {
+            return spi.write(data);
+        }
+        for chunk in data.chunks(self.size) {
+            spi.write(chunk)?;
+        }
+        Ok(())
+    }
+}
+
+/// Hands every block over to a closure, e.g. to start a DMA transfer and wait for it
+///
+/// The closure has to block until the transfer is done, since the driver deasserts
+/// CS and may reuse the buffer as soon as it returns.
+pub struct DmaHandover<F> {
+    handover: F,
+}
+
+impl<F> DmaHandover<F> {
+    /// Creates the strategy with the closure doing the transfer
+    pub fn new(handover: F) -> Self {
+        DmaHandover { handover }
+    }
+}
+
+impl<SPI, F> TransportStrategy<SPI> for DmaHandover<F>
+where
+    SPI: Write<u8>,
+    F: FnMut(&mut SPI, &[u8]) -> Result<(), SPI::Error>,
+{
+    fn transfer(&mut self, spi: &mut SPI, data: &[u8]) -> Result<(), SPI::Error> {
+        (self.handover)(spi, data)
+    }
+}
+
+/// Splits every block into transfers of at most `size` bytes and calls a hook
+/// between them
+///
+/// The drivers are blocking, so a large frame keeps the CPU busy until the last byte
+/// is out. The hook is the place to give other work a turn in between, e.g. the
+/// yield of an RTOS or polling a cooperative scheduler. It isn't called before the
+/// first or after the last chunk.
+pub struct Yielding<F> {
+    chunks: Chunked,
+    hook: F,
+}
+
+impl<F: FnMut()> Yielding<F> {
+    /// Creates the strategy, `size` must not be 0
+    pub fn new(size: usize, hook: F) -> Self {
+        Yielding {
+            chunks: Chunked::new(size),
+            hook,
+        }
+    }
+
+    /// Maximum number of bytes per transfer
+    pub fn size(&self) -> usize {
+        self.chunks.size()
+    }
+}
+
+impl<SPI, F> TransportStrategy<SPI> for Yielding<F>
+where
+    SPI: Write<u8>,
+    F: FnMut(),
+{
+    fn transfer(&mut self, spi: &mut SPI, data: &[u8]) -> Result<(), SPI::Error> {
+        for (i, chunk) in data.chunks(self.chunks.size()).enumerate() {
+            if i > 0 {
+                (self.hook)();
+            }
+            spi.write(chunk)?;
+        }
+        Ok(())
+    }
+}
+
+/// Counts the bytes sent straight from a frame buffer and passes every block on to the
+/// wrapped strategy
+///
+/// Only the address of the blocks is compared, so a copy of the frame with the same
+/// content isn't counted. Commands and their parameters are never part of the frame.
+pub struct ZeroCopyCheck<S> {
+    frame: Range<usize>,
+    from_frame: usize,
+    strategy: S,
+}
+
+impl<S> ZeroCopyCheck<S> {
+    /// Creates the check for `frame`, the blocks are sent with `strategy`
+    pub fn new(frame: &[u8], strategy: S) -> Self {
+        let frame = frame.as_ptr_range();
+        ZeroCopyCheck {
+            frame: frame.start as usize..frame.end as usize,
+            from_frame: 0,
+            strategy,
+        }
+    }
+
+    /// Number of bytes sent straight from the frame
+    pub fn from_frame(&self) -> usize {
+        self.from_frame
+    }
+
+    /// Whether the whole frame was sent straight from its buffer, once or several times
+    pub fn is_zero_copy(&self) -> bool {
+        let len = self.frame.len();
+        len > 0 && self.from_frame > 0 && self.from_frame.is_multiple_of(len)
+    }
+
+    /// The wrapped strategy
+    pub fn strategy(&self) -> &S {
+        &self.strategy
+    }
+}
+
+impl<SPI, S> TransportStrategy<SPI> for ZeroCopyCheck<S>
+where
+    SPI: Write<u8>,
+    S: TransportStrategy<SPI>,
+{
+    fn transfer(&mut self, spi: &mut SPI, data: &[u8]) -> Result<(), SPI::Error> {
+        let block = data.as_ptr() as usize..data.as_ptr() as usize + data.len();
+        let start = block.start.max(self.frame.start);
+        let end = block.end.min(self.frame.end);
+        self.from_frame += end.saturating_sub(start);
+        self.strategy.transfer(spi, data)
+    }
+}
+
+/// An SPI peripheral which sends all writes with the chosen [`TransportStrategy`]
+pub struct Transport<SPI, S> {
+    spi: SPI,
+    strategy: S,
+}
+
+impl<SPI, S> Transport<SPI, S>
+where
+    SPI: Write<u8>,
+    S: TransportStrategy<SPI>,
+{
+    /// Wraps `spi`, all writes go through `strategy`
+    pub fn new(spi: SPI, strategy: S) -> Self {
+        Transport { spi, strategy }
+    }
+
+    /// The strategy used for the writes
+    pub fn strategy(&self) -> &S {
+        &self.strategy
+    }
+
+    /// Releases the wrapped SPI peripheral and the strategy
+    pub fn release(self) -> (SPI, S) {
+        (self.spi, self.strategy)
+    }
+}
+
+impl<SPI, S> Write<u8> for Transport<SPI, S>
+where
+    SPI: Write<u8>,
+    S: TransportStrategy<SPI>,
+{
+    type Error = SPI::Error;
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+        self.strategy.transfer(&mut self.spi, words)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::epd4in2::Epd4in2;
+    use crate::epd4in2_v2::Epd4in2 as Epd4in2V2;
+    use crate::epd5in83_v2::Epd5in83;
+    use crate::epd7in5_v2::Epd7in5;
+    use crate::traits::WaveshareDisplay;
+    use embedded_hal::digital::v2::{InputPin, OutputPin};
+    use embedded_hal_mock::delay::MockNoop;
+    use embedded_hal_mock::spi::{Mock, Transaction};
+
+    #[test]
+    fn chunked() {
+        let expectations = [
+            Transaction::write([0xAA; 4].to_vec()),
+            Transaction::write([0xAA; 4].to_vec()),
+            Transaction::write([0xAA; 2].to_vec()),
+            Transaction::write([0x10].to_vec()),
+        ];
+        let mut spi = Transport::new(Mock::new(&expectations), Chunked::new(4));
+
+        spi.write(&[0xAA; 10]).unwrap();
+        spi.write(&[0x10]).unwrap();
+        spi.release().0.done();
+    }
+
+    #[test]
+    fn single_shot() {
+        let expectations = [Transaction::write([0xAA; 5000].to_vec())];
+        let mut spi = Transport::new(Mock::new(&expectations), SingleShot);
+
+        spi.write(&[0xAA; 5000]).unwrap();
+        spi.release().0.done();
+    }
+
+    #[test]
+    fn yielding_between_chunks() {
+        let expectations = [
+            Transaction::write([0xAA; 4].to_vec()),
+            Transaction::write([0xAA; 4].to_vec()),
+            Transaction::write([0xAA; 2].to_vec()),
+            Transaction::write([0x10].to_vec()),
+        ];
+        let mut yields = 0;
+        let strategy = Yielding::new(4, || yields += 1);
+        let mut spi = Transport::new(Mock::new(&expectations), strategy);
+
+        spi.write(&[0xAA; 10]).unwrap();
+        spi.write(&[0x10]).unwrap();
+        spi.release().0.done();
+        assert_eq!(yields, 2);
+    }
+
+    #[test]
+    fn dma_handover() {
+        let expectations = [Transaction::write([0x01, 0x02].to_vec())];
+        let mut handed_over = 0;
+        let strategy = DmaHandover::new(|spi: &mut Mock, data: &[u8]| {
+            handed_over += data.len();
+            spi.write(data)
+        });
+        let mut spi = Transport::new(Mock::new(&expectations), strategy);
+
+        spi.write(&[0x01, 0x02]).unwrap();
+        let (mut mock, _) = spi.release();
+        mock.done();
+        assert_eq!(handed_over, 2);
+    }
+
+    /// SPI which accepts every write
+    struct Sink;
+
+    impl Write<u8> for Sink {
+        type Error = ();
+
+        fn write(&mut self, _words: &[u8]) -> Result<(), ()> {
+            Ok(())
+        }
+    }
+
+    /// Pin whose reads fail, which the drivers take as never busy
+    struct Pin;
+
+    impl OutputPin for Pin {
+        type Error = ();
+
+        fn set_low(&mut self) -> Result<(), ()> {
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), ()> {
+            Ok(())
+        }
+    }
+
+    impl InputPin for Pin {
+        type Error = ();
+
+        fn is_high(&self) -> Result<bool, ()> {
+            Err(())
+        }
+
+        fn is_low(&self) -> Result<bool, ()> {
+            Err(())
+        }
+    }
+
+    /// Bytes of `frame` sent straight from it by `update_frame` of `EPD`
+    fn update_frame_from_frame<EPD>(frame: &[u8]) -> ZeroCopyCheck<SingleShot>
+    where
+        EPD: WaveshareDisplay<
+            Transport<Sink, ZeroCopyCheck<SingleShot>>,
+            Pin,
+            Pin,
+            Pin,
+            Pin,
+            MockNoop,
+        >,
+    {
+        let mut delay = MockNoop::new();
+        let mut spi = Transport::new(Sink, ZeroCopyCheck::new(frame, SingleShot));
+        let mut epd = EPD::new(&mut spi, Pin, Pin, Pin, Pin, &mut delay).unwrap();
+        epd.update_frame(&mut spi, frame, &mut delay).unwrap();
+        spi.release().1
+    }
+
+    #[test]
+    fn zero_copy_check() {
+        let frame = [0xAA; 10];
+        let mut spi = Transport::new(Sink, ZeroCopyCheck::new(&frame, Chunked::new(4)));
+
+        spi.write(&[0x10]).unwrap();
+        spi.write(&frame[2..]).unwrap();
+        assert_eq!(spi.strategy().from_frame(), 8);
+        assert!(!spi.strategy().is_zero_copy());
+
+        // the same content at another address doesn't count
+        spi.write(&[0xAA; 2]).unwrap();
+        spi.write(&frame[..2]).unwrap();
+        assert_eq!(spi.strategy().from_frame(), 10);
+        assert!(spi.strategy().is_zero_copy());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn chunked_for_spidev_by_default() {
+        extern crate std;
+        use std::vec;
+
+        /// SPI which remembers its longest write
+        struct Longest(usize);
+
+        impl Write<u8> for Longest {
+            type Error = ();
+
+            fn write(&mut self, words: &[u8]) -> Result<(), ()> {
+                self.0 = self.0.max(words.len());
+                Ok(())
+            }
+        }
+
+        let mut delay = MockNoop::new();
+        let mut spi = Longest(0);
+        let mut epd = Epd7in5::new(&mut spi, Pin, Pin, Pin, Pin, &mut delay).unwrap();
+        let frame = vec![0xAA; 800 * 480 / 8];
+        epd.update_frame(&mut spi, &frame, &mut delay).unwrap();
+        assert_eq!(spi.0, SPIDEV_MAX_TRANSFER);
+    }
+
+    #[test]
+    fn update_frame_is_zero_copy() {
+        extern crate std;
+        use std::vec;
+
+        let frame = vec![0xAA; 400 * 300 / 8];
+        let check = update_frame_from_frame::<Epd4in2<_, _, _, _, _, _>>(&frame);
+        assert!(check.is_zero_copy());
+
+        // larger than a single transfer of spidev
+        let frame = vec![0xAA; 800 * 480 / 8];
+        let check = update_frame_from_frame::<Epd7in5<_, _, _, _, _, _>>(&frame);
+        assert!(check.is_zero_copy());
+
+        // written into both RAMs
+        let frame = vec![0xAA; 400 * 300 / 8];
+        let check = update_frame_from_frame::<Epd4in2V2<_, _, _, _, _, _>>(&frame);
+        assert_eq!(check.from_frame(), 2 * frame.len());
+        assert!(check.is_zero_copy());
+
+        // inverted through the stack
+        let frame = vec![0xAA; 648 * 480 / 8];
+        let check = update_frame_from_frame::<Epd5in83<_, _, _, _, _, _>>(&frame);
+        assert_eq!(check.from_frame(), 0);
+    }
+}
@@ -17,6 +17,10 @@ pub(crate) enum Command {
     ///     0.. B[2:0]
     ///     Default: Set A[8:0] = 0x127 and B[2:0] = 0x0
     DriverOutputControl = 0x01,
+    /// Gate driving voltage (VGH/VGL), written by host LUTs of the V2 displays
+    GateDrivingVoltageControl = 0x03,
+    /// Source driving voltages (VSH1, VSH2, VSL), written by host LUTs of the V2 displays
+    SourceDrivingVoltageControl = 0x04,
     /// Booster Soft start control
     ///     3 Databytes:
     ///     1.. A[6:0]
@@ -39,6 +43,9 @@ pub(crate) enum Command {
 
     SwReset = 0x12,
 
+    /// Selects the internal (`0x80`) or an external temperature sensor
+    TemperatureSensorSelection = 0x18,
+
     TemperatureSensorControl = 0x1A,
 
     MasterActivation = 0x20,
@@ -63,6 +70,8 @@ pub(crate) enum Command {
 
     BorderWaveformControl = 0x3C,
 
+    EndOption = 0x3F,
+
     SetRamXAddressStartEndPosition = 0x44,
 
     SetRamYAddressStartEndPosition = 0x45,
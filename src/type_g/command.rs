@@ -0,0 +1,105 @@
+//! SPI Commands for the Waveshare 4 color (G) E-Ink Displays
+
+use crate::traits;
+
+/// Epd1in64g, Epd2in13g, Epd2in36g, Epd3in0g and Epd4in37g commands
+///
+/// Should rarely (never?) be needed directly.
+///
+/// For more infos about the addresses and what they are doing look into the PDFs.
+#[allow(dead_code)]
+#[derive(Copy, Clone)]
+pub(crate) enum Command {
+    /// Set Resolution, LUT selection, gate scan direction, source shift
+    /// direction, booster switch, soft reset.
+    PanelSetting = 0x00,
+
+    /// Selecting internal and external power
+    PowerSetting = 0x01,
+
+    /// After the Power Off command, the driver will power off following the Power Off
+    /// Sequence; BUSY signal will become "0".
+    PowerOff = 0x02,
+
+    /// Turning On the Power
+    ///
+    /// After the Power ON command, the driver will power on following the Power ON
+    /// sequence. Once complete, the BUSY signal will become "1".
+    PowerOn = 0x04,
+
+    /// Timings of the power off sequence
+    PowerOffSequenceSetting = 0x03,
+
+    /// Starting data transmission
+    BoosterSoftStart = 0x06,
+
+    /// This command makes the chip enter the deep-sleep mode to save power.
+    ///
+    /// The deep sleep mode would return to stand-by by hardware reset.
+    ///
+    /// The only one parameter is a check code, the command would be excuted if check code = 0xA5.
+    DeepSleep = 0x07,
+
+    /// This command starts transmitting the 2 bit per pixel data and writes it into SRAM.
+    DataStartTransmission = 0x10,
+
+    /// After this command is issued, driver will refresh display (data/VCOM) according to
+    /// SRAM data and LUT.
+    ///
+    /// After Display Refresh command, BUSY signal will become "0" until the display
+    /// update is finished.
+    DisplayRefresh = 0x12,
+
+    /// Frame rate of the refresh
+    PllControl = 0x30,
+
+    /// Undocumented, the first command of the init sequence of the 2.13" vendor driver
+    Unknown4D = 0x4D,
+
+    /// This command indicates the interval of Vcom and data output.
+    VcomAndDataIntervalSetting = 0x50,
+
+    /// This command defines non-overlap period of Gate and Source.
+    TconSetting = 0x60,
+    /// This command defines the resolution
+    TconResolution = 0x61,
+
+    /// Undocumented, the first command of the init sequence of the vendor driver
+    SourceSetting = 0x66,
+    /// Undocumented, wraps the data transmission in the vendor driver
+    DataTransmissionEnable = 0x68,
+    /// Undocumented, sent by the vendor driver as part of the init sequence
+    Unknown84 = 0x84,
+    /// Undocumented, selects the booster strength in the vendor driver
+    BoosterSelection = 0xB0,
+    /// Undocumented, sent by the 2.13" vendor driver as part of the init sequence
+    UnknownB4 = 0xB4,
+    /// Undocumented, sent by the 2.13" vendor driver as part of the init sequence
+    UnknownB5 = 0xB5,
+    /// Undocumented, sent by the vendor driver as part of the init sequence
+    PowerSaving = 0xE3,
+    /// Undocumented, sent by the 2.13" vendor driver as part of the init sequence
+    UnknownE7 = 0xE7,
+    /// Undocumented, sent by the 2.13" vendor driver as part of the init sequence
+    UnknownE9 = 0xE9,
+}
+
+impl traits::Command for Command {
+    /// Returns the address of the command
+    fn address(self) -> u8 {
+        self as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::Command as CommandTrait;
+
+    #[test]
+    fn command_addr() {
+        assert_eq!(Command::PanelSetting.address(), 0x00);
+        assert_eq!(Command::DisplayRefresh.address(), 0x12);
+        assert_eq!(Command::DataTransmissionEnable.address(), 0x68);
+    }
+}
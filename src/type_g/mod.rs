@@ -0,0 +1,123 @@
+//! Shared plumbing of the 4 color (G) displays
+//!
+//! All G-series panels use the same controller with 2 bits per pixel. They share the
+//! transmission and refresh sequence, only the size and the panel setting differ. The
+//! 2.13" (G) is the exception: it has its own init sequence and keeps the panel powered
+//! between refreshes, so it only shares the commands and `sleep_quad`.
+
+use embedded_hal::{
+    blocking::{delay::*, spi::Write},
+    digital::v2::{InputPin, OutputPin},
+};
+
+use crate::color::QuadColor;
+use crate::interface::DisplayInterface;
+use crate::Error;
+
+pub(crate) mod command;
+pub(crate) use self::command::Command;
+
+pub(crate) const IS_BUSY_LOW: bool = true;
+
+impl<SPI, CS, BUSY, DC, RST, DELAY> DisplayInterface<SPI, CS, BUSY, DC, RST, DELAY>
+where
+    SPI: Write<u8>,
+    CS: OutputPin,
+    BUSY: InputPin,
+    DC: OutputPin,
+    RST: OutputPin,
+    DELAY: DelayMs<u8>,
+{
+    /// Resets the panel and sends the init sequence of the vendor drivers
+    pub(crate) fn init_quad(
+        &mut self,
+        spi: &mut SPI,
+        delay: &mut DELAY,
+        panel_setting: [u8; 2],
+        width: u32,
+        height: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.reset(delay, 2);
+
+        self.cmd_with_data(
+            spi,
+            Command::SourceSetting,
+            &[0x49, 0x55, 0x13, 0x5D, 0x05, 0x10],
+        )?;
+        self.cmd_with_data(spi, Command::BoosterSelection, &[0x00])?;
+        self.cmd_with_data(spi, Command::PowerSetting, &[0x0F, 0x00])?;
+        self.cmd_with_data(spi, Command::PanelSetting, &panel_setting)?;
+        self.cmd_with_data(spi, Command::BoosterSoftStart, &[0xD7, 0xDE, 0x12])?;
+        self.cmd_with_data(
+            spi,
+            Command::TconResolution,
+            &[
+                (width >> 8) as u8,
+                width as u8,
+                (height >> 8) as u8,
+                height as u8,
+            ],
+        )?;
+        self.cmd_with_data(spi, Command::VcomAndDataIntervalSetting, &[0x37])?;
+        self.cmd_with_data(spi, Command::TconSetting, &[0x0C, 0x05])?;
+        self.cmd_with_data(spi, Command::PowerSaving, &[0xFF])?;
+        self.cmd_with_data(spi, Command::Unknown84, &[0x00])
+    }
+
+    /// Transmits a full frame with 2 bits per pixel
+    pub(crate) fn update_quad_frame(
+        &mut self,
+        spi: &mut SPI,
+        buffer: &[u8],
+    ) -> Result<(), Error<SPI::Error>> {
+        self.start_quad_transmission(spi)?;
+        self.data(spi, buffer)?;
+        self.cmd_with_data(spi, Command::DataTransmissionEnable, &[0x00])
+    }
+
+    /// Transmits a full frame of `color`
+    pub(crate) fn clear_quad_frame(
+        &mut self,
+        spi: &mut SPI,
+        color: QuadColor,
+        num_bytes: u32,
+    ) -> Result<(), Error<SPI::Error>> {
+        self.start_quad_transmission(spi)?;
+        self.data_x_times(spi, color.get_byte_value(), num_bytes)?;
+        self.cmd_with_data(spi, Command::DataTransmissionEnable, &[0x00])
+    }
+
+    /// Refreshes the panel and powers it off afterwards
+    pub(crate) fn display_quad_frame(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        self.start_quad_refresh(spi)?;
+        self.finish_quad_refresh(spi)
+    }
+
+    /// Starts refreshing the panel without waiting for it
+    pub(crate) fn start_quad_refresh(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        self.cmd_with_data(spi, Command::DisplayRefresh, &[0x01])
+    }
+
+    /// Waits for the refresh and powers the panel off
+    pub(crate) fn finish_quad_refresh(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        self.wait_until_idle(IS_BUSY_LOW);
+        self.cmd_with_data(spi, Command::PowerOff, &[0x00])?;
+        self.wait_until_idle(IS_BUSY_LOW);
+        Ok(())
+    }
+
+    /// Powers the panel off and enters deep sleep
+    pub(crate) fn sleep_quad(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        self.cmd_with_data(spi, Command::PowerOff, &[0x00])?;
+        self.wait_until_idle(IS_BUSY_LOW);
+        self.cmd_with_data(spi, Command::DeepSleep, &[0xA5])
+    }
+
+    /// Powers on the panel and opens the frame memory for the 2 bit per pixel data
+    fn start_quad_transmission(&mut self, spi: &mut SPI) -> Result<(), Error<SPI::Error>> {
+        self.cmd_with_data(spi, Command::DataTransmissionEnable, &[0x01])?;
+        self.cmd(spi, Command::PowerOn)?;
+        self.wait_until_idle(IS_BUSY_LOW);
+        self.cmd(spi, Command::DataStartTransmission)
+    }
+}